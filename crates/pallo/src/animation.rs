@@ -1,30 +1,190 @@
-use std::rc::Rc;
+use std::{any::Any, marker::PhantomData, rc::Rc};
 
 use rustc_hash::FxHashMap;
 
+use crate::{App, Color, Computed, Cx, Point, Rect, Signal};
+
+/// A value that can be tweened by `Animations`.
+pub trait Animatable: Copy + PartialEq + 'static {
+    fn lerp(&self, target: &Self, t: f32) -> Self;
+}
+
+impl Animatable for f32 {
+    fn lerp(&self, target: &Self, t: f32) -> Self {
+        lerp(*self, *target, t)
+    }
+}
+
+impl Animatable for Point {
+    fn lerp(&self, target: &Self, t: f32) -> Self {
+        Point::lerp(self, *target, t)
+    }
+}
+
+impl Animatable for Rect {
+    fn lerp(&self, target: &Self, t: f32) -> Self {
+        self.with_lerp(target, t)
+    }
+}
+
+impl Animatable for Color {
+    fn lerp(&self, target: &Self, t: f32) -> Self {
+        self.with_mix(*target, t)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseInQuad,
+    EaseOutQuad,
+    EaseInOutQuad,
+    EaseInCubic,
+    EaseOutCubic,
+    EaseInOutCubic,
+}
+
+impl Easing {
+    /// Maps the linear progress `t` in 0..=1 to the eased one.
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInQuad => t * t,
+            Easing::EaseOutQuad => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOutQuad => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Easing::EaseInCubic => t * t * t,
+            Easing::EaseOutCubic => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOutCubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// A damped spring pulling a value towards its target, for motion that follows the user's input such as snapping
+/// back after a drag. It's critically damped (as fast as possible without overshooting) when
+/// `damping == 2.0 * stiffness.sqrt()`, and bounces when the damping is lower.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spring {
+    pub stiffness: f32,
+    pub damping: f32,
+}
+
+impl Spring {
+    pub fn new(stiffness: f32, damping: f32) -> Self {
+        Self { stiffness, damping }
+    }
+
+    pub fn critically_damped(stiffness: f32) -> Self {
+        Self { stiffness, damping: 2.0 * stiffness.sqrt() }
+    }
+}
+
 #[derive(Default)]
 pub struct Animations {
     list: FxHashMap<AnimationId, Animation>,
+    tweens: FxHashMap<AnimationId, Box<dyn AnyTween>>,
+    springs: FxHashMap<AnimationId, SpringState>,
+    color_transitions: FxHashMap<AnimationId, ColorTransitionState>,
     id_cursor: usize,
 }
 
 pub type AnimationId = Rc<usize>;
 
+/// Identifies a tween of a `T` in `Animations`. Like with `AnimationId`, the tween is removed once all of its ids are
+/// dropped.
+pub struct TweenId<T> {
+    id: AnimationId,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for TweenId<T> {
+    fn clone(&self) -> Self {
+        Self { id: self.id.clone(), marker: PhantomData }
+    }
+}
+
+/// Identifies a spring in `Animations`, which is removed once all of its ids are dropped.
+#[derive(Clone)]
+pub struct SpringId {
+    id: AnimationId,
+    value: Signal<f32>,
+}
+
+impl SpringId {
+    /// The spring's value, updated as it moves.
+    pub fn as_computed(&self) -> Computed<f32> {
+        self.value.as_computed()
+    }
+}
+
+/// Identifies a color in `Animations` that eases to each new target instead of jumping to it, e.g. for hover colors.
+/// Like with springs, the transition is removed once all of its ids are dropped.
+#[derive(Clone)]
+pub struct ColorTransition {
+    id: AnimationId,
+    value: Signal<Color>,
+}
+
+impl ColorTransition {
+    /// The transition's color, updated as it eases.
+    pub fn as_computed(&self) -> Computed<Color> {
+        self.value.as_computed()
+    }
+}
+
+impl Computed<Color> {
+    /// A computed that follows this one, easing to each of its new colors over `duration_ms`.
+    pub fn lerp_to<A: App>(&self, cx: &mut Cx<A>, duration_ms: f32) -> Computed<Color> {
+        let value = cx.signal(self.get());
+        let transition =
+            cx.animations.add_color_transition(value.clone(), Some(self.clone()), duration_ms, Easing::EaseOutQuad);
+        // the transition lives for as long as the computed reading it
+        value.map(move |color| {
+            let _ = &transition;
+            color
+        })
+    }
+}
+
 impl Animations {
-    pub fn add_linear(&mut self, duration_ms: f32) -> AnimationId {
+    fn next_id(&mut self) -> AnimationId {
         let id = Rc::new(self.id_cursor);
         self.id_cursor += 1;
+        id
+    }
+
+    pub fn add_linear(&mut self, duration_ms: f32) -> AnimationId {
+        let id = self.next_id();
         self.list.insert(id.clone(), Animation::new_linear(duration_ms));
         id
     }
 
     pub fn add_decaying(&mut self, decay_coeff: f32) -> AnimationId {
-        let id = Rc::new(self.id_cursor);
-        self.id_cursor += 1;
+        let id = self.next_id();
         self.list.insert(id.clone(), Animation::new_decaying(decay_coeff));
         id
     }
 
+    /// Adds an animation whose value is the number of milliseconds that have elapsed since it was added, or since
+    /// the last time it was `set`.
+    pub fn add_clock(&mut self) -> AnimationId {
+        let id = self.next_id();
+        self.list.insert(id.clone(), Animation::Clock { elapsed_ms: 0.0 });
+        id
+    }
+
     pub fn set(&mut self, id: &AnimationId, value: f32) {
         self.list.get_mut(id).unwrap().set(value);
     }
@@ -33,15 +193,124 @@ impl Animations {
         self.list[id].get()
     }
 
-    pub fn tick(&mut self, frame_delta_ms: f32) {
+    /// Adds a tween that starts at `value` and eases to the targets given to `set_tween` over `duration_ms`.
+    pub fn add_tween<T: Animatable>(&mut self, value: T, duration_ms: f32, easing: Easing) -> TweenId<T> {
+        let id = self.next_id();
+        self.tweens.insert(id.clone(), Box::new(Tween::new(value, duration_ms, easing)));
+        TweenId { id, marker: PhantomData }
+    }
+
+    /// Starts tweening towards `target` from the current value, unless it's already the target.
+    pub fn set_tween<T: Animatable>(&mut self, id: &TweenId<T>, target: T) {
+        self.tween_mut(id).set(target);
+    }
+
+    /// Jumps to `value` without tweening.
+    pub fn reset_tween<T: Animatable>(&mut self, id: &TweenId<T>, value: T) {
+        let tween = self.tween_mut(id);
+        *tween = Tween::new(value, tween.duration_ms, tween.easing);
+    }
+
+    pub fn get_tween<T: Animatable>(&self, id: &TweenId<T>) -> T {
+        self.tween(id).current
+    }
+
+    pub fn is_tween_done<T: Animatable>(&self, id: &TweenId<T>) -> bool {
+        self.tween(id).is_done()
+    }
+
+    fn tween<T: Animatable>(&self, id: &TweenId<T>) -> &Tween<T> {
+        self.tweens[&id.id].as_any().downcast_ref().unwrap()
+    }
+
+    fn tween_mut<T: Animatable>(&mut self, id: &TweenId<T>) -> &mut Tween<T> {
+        self.tweens.get_mut(&id.id).unwrap().as_any_mut().downcast_mut().unwrap()
+    }
+
+    /// Adds a spring at rest at the current value of `value`, which it keeps updated from then on.
+    pub fn add_spring(&mut self, spring: Spring, value: Signal<f32>) -> SpringId {
+        let id = self.next_id();
+        let current = value.get_fast();
+        let state = SpringState { spring, current, target: current, velocity: 0.0, value: value.clone() };
+        self.springs.insert(id.clone(), state);
+        SpringId { id, value }
+    }
+
+    pub fn set_spring_target(&mut self, id: &SpringId, target: f32) {
+        self.springs.get_mut(&id.id).unwrap().target = target;
+    }
+
+    /// Sets the velocity in units per second, e.g. to carry on the motion of a released drag.
+    pub fn set_spring_velocity(&mut self, id: &SpringId, velocity: f32) {
+        self.springs.get_mut(&id.id).unwrap().velocity = velocity;
+    }
+
+    /// Jumps to `value` and stops there.
+    pub fn reset_spring(&mut self, id: &SpringId, value: f32) {
+        let state = self.springs.get_mut(&id.id).unwrap();
+        state.current = value;
+        state.target = value;
+        state.velocity = 0.0;
+        state.value.set_if_changed(value);
+    }
+
+    pub fn get_spring(&self, id: &SpringId) -> f32 {
+        self.springs[&id.id].current
+    }
+
+    pub fn is_spring_at_rest(&self, id: &SpringId) -> bool {
+        self.springs[&id.id].is_at_rest()
+    }
+
+    /// Adds a color transition that keeps `value` updated. It jumps to the first target it's given and eases to the
+    /// following ones, so it can be added before the color is known. With a `source`, the targets are the colors of
+    /// the source, otherwise the ones given to `set_color_transition_target`.
+    pub fn add_color_transition(
+        &mut self,
+        value: Signal<Color>,
+        source: Option<Computed<Color>>,
+        duration_ms: f32,
+        easing: Easing,
+    ) -> ColorTransition {
+        let id = self.next_id();
+        let tween = Tween::new(value.get_fast(), duration_ms, easing);
+        let state = ColorTransitionState { tween, has_target: false, source, value: value.clone() };
+        self.color_transitions.insert(id.clone(), state);
+        ColorTransition { id, value }
+    }
+
+    pub fn set_color_transition_target(&mut self, id: &ColorTransition, target: Color) {
+        self.color_transitions.get_mut(&id.id).unwrap().set_target(target);
+    }
+
+    pub fn get_color_transition(&self, id: &ColorTransition) -> Color {
+        self.color_transitions[&id.id].tween.current
+    }
+
+    /// Advances everything by `frame_delta_ms`. Returns whether any value changed, i.e. whether something is moving.
+    pub fn tick(&mut self, frame_delta_ms: f32) -> bool {
         self.garbage_collect();
+        let mut moved = false;
         for animation in self.list.values_mut() {
-            animation.tick(frame_delta_ms);
+            moved |= animation.tick(frame_delta_ms);
+        }
+        for tween in self.tweens.values_mut() {
+            moved |= tween.tick(frame_delta_ms);
+        }
+        for spring in self.springs.values_mut() {
+            moved |= spring.tick(frame_delta_ms);
+        }
+        for transition in self.color_transitions.values_mut() {
+            moved |= transition.tick(frame_delta_ms);
         }
+        moved
     }
 
     pub fn garbage_collect(&mut self) {
         self.list.retain(|id, _| Rc::strong_count(id) > 1);
+        self.tweens.retain(|id, _| Rc::strong_count(id) > 1);
+        self.springs.retain(|id, _| Rc::strong_count(id) > 1);
+        self.color_transitions.retain(|id, _| Rc::strong_count(id) > 1);
     }
 }
 
@@ -49,9 +318,138 @@ fn lerp(start: f32, end: f32, t: f32) -> f32 {
     (1.0 - t) * start + t * end
 }
 
+struct Tween<T> {
+    start: T,
+    current: T,
+    target: T,
+    duration_ms: f32,
+    elapsed: f32,
+    easing: Easing,
+}
+
+impl<T: Animatable> Tween<T> {
+    fn new(value: T, duration_ms: f32, easing: Easing) -> Self {
+        Self { start: value, current: value, target: value, duration_ms, elapsed: duration_ms, easing }
+    }
+
+    fn set(&mut self, target: T) {
+        if target == self.target {
+            return;
+        }
+        self.start = self.current;
+        self.target = target;
+        self.elapsed = 0.0;
+    }
+
+    fn is_done(&self) -> bool {
+        self.elapsed >= self.duration_ms
+    }
+
+    fn tick(&mut self, delta_ms: f32) -> bool {
+        let previous = self.current;
+        if self.elapsed < self.duration_ms {
+            self.elapsed += delta_ms;
+            let t = (self.elapsed / self.duration_ms).clamp(0.0, 1.0);
+            self.current = self.start.lerp(&self.target, self.easing.apply(t));
+        } else {
+            self.current = self.target;
+        }
+        self.current != previous
+    }
+}
+
+struct SpringState {
+    spring: Spring,
+    current: f32,
+    target: f32,
+    velocity: f32,
+    value: Signal<f32>,
+}
+
+impl SpringState {
+    /// How close to the target, and how slow, the spring has to be to stop.
+    const REST_THRESHOLD: f32 = 1e-3;
+    /// Longer frames are integrated in steps of at most this length to keep stiff springs stable.
+    const MAX_STEP_S: f32 = 1.0 / 240.0;
+
+    fn is_at_rest(&self) -> bool {
+        self.current == self.target && self.velocity == 0.0
+    }
+
+    fn tick(&mut self, delta_ms: f32) -> bool {
+        if self.is_at_rest() {
+            return false;
+        }
+        let mut remaining = delta_ms / 1000.0;
+        while remaining > 0.0 {
+            let dt = remaining.min(Self::MAX_STEP_S);
+            remaining -= dt;
+            let Spring { stiffness, damping } = self.spring;
+            let acceleration = stiffness * (self.target - self.current) - damping * self.velocity;
+            self.velocity += acceleration * dt;
+            self.current += self.velocity * dt;
+        }
+        if (self.current - self.target).abs() < Self::REST_THRESHOLD && self.velocity.abs() < Self::REST_THRESHOLD {
+            self.current = self.target;
+            self.velocity = 0.0;
+        }
+        self.value.set_if_changed(self.current);
+        true
+    }
+}
+
+struct ColorTransitionState {
+    tween: Tween<Color>,
+    has_target: bool,
+    source: Option<Computed<Color>>,
+    value: Signal<Color>,
+}
+
+impl ColorTransitionState {
+    fn set_target(&mut self, target: Color) {
+        if self.has_target {
+            self.tween.set(target);
+        } else {
+            self.has_target = true;
+            self.tween = Tween::new(target, self.tween.duration_ms, self.tween.easing);
+            self.value.set_if_changed(target);
+        }
+    }
+
+    fn tick(&mut self, delta_ms: f32) -> bool {
+        if let Some(target) = self.source.as_ref().and_then(Computed::next) {
+            self.set_target(target);
+        }
+        self.tween.tick(delta_ms);
+        self.value.set_if_changed(self.tween.current)
+    }
+}
+
+/// Lets `Animations` tick tweens of any type.
+trait AnyTween {
+    fn tick(&mut self, delta_ms: f32) -> bool;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Animatable> AnyTween for Tween<T> {
+    fn tick(&mut self, delta_ms: f32) -> bool {
+        Tween::tick(self, delta_ms)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
 enum Animation {
     Decaying { current: f32, decay_coeff: f32 },
-    Linear { start: f32, current: f32, target: f32, duration_ms: f32, elapsed: f32 },
+    Linear(Tween<f32>),
+    Clock { elapsed_ms: f32 },
 }
 
 impl Animation {
@@ -60,7 +458,7 @@ impl Animation {
     }
 
     fn new_linear(duration_ms: f32) -> Self {
-        Self::Linear { current: 0.0, start: 0.0, target: 0.0, duration_ms, elapsed: 0.0 }
+        Self::Linear(Tween::new(0.0, duration_ms, Easing::Linear))
     }
 
     fn set(&mut self, v: f32) {
@@ -68,34 +466,30 @@ impl Animation {
             Animation::Decaying { current, .. } => {
                 *current = v;
             }
-            Animation::Linear { start, current, target, elapsed, .. } => {
-                *start = *current;
-                *target = v;
-                *elapsed = 0.0;
-            }
+            Animation::Linear(tween) => tween.set(v),
+            Animation::Clock { elapsed_ms } => *elapsed_ms = v,
         }
     }
 
     fn get(&self) -> f32 {
         match self {
             Animation::Decaying { current, .. } => *current,
-            Animation::Linear { current, .. } => *current,
+            Animation::Linear(tween) => tween.current,
+            Animation::Clock { elapsed_ms } => *elapsed_ms,
         }
     }
 
-    fn tick(&mut self, delta_ms: f32) {
+    fn tick(&mut self, delta_ms: f32) -> bool {
         match self {
             Animation::Decaying { current, decay_coeff } => {
+                let previous = *current;
                 *current *= decay_coeff.powf(delta_ms);
+                *current != previous
             }
-            Animation::Linear { start, current, target, duration_ms, elapsed } => {
-                if elapsed < duration_ms {
-                    *elapsed += delta_ms;
-                    let t = (*elapsed / *duration_ms).clamp(0.0, 1.0);
-                    *current = lerp(*start, *target, t);
-                } else {
-                    *current = *target;
-                }
+            Animation::Linear(tween) => tween.tick(delta_ms),
+            Animation::Clock { elapsed_ms } => {
+                *elapsed_ms += delta_ms;
+                true
             }
         }
     }
@@ -103,7 +497,11 @@ impl Animation {
 
 #[cfg(test)]
 mod tests {
-    use crate::animation::Animation;
+    use crate::{
+        Rect, SignalCx,
+        animation::{Animation, Animations, Easing, Spring},
+        rgb,
+    };
 
     #[test]
     fn works() {
@@ -127,4 +525,99 @@ mod tests {
         a.tick(60.0 * 60.0 * 1000.0);
         assert_eq!(a.get(), 0.0);
     }
+
+    #[test]
+    fn clocks_count_elapsed_time() {
+        let mut animations = Animations::default();
+        let clock = animations.add_clock();
+        animations.tick(16.0);
+        animations.tick(16.0);
+        assert_eq!(animations.get(&clock), 32.0);
+        animations.set(&clock, 100.0);
+        animations.tick(10.0);
+        assert_eq!(animations.get(&clock), 110.0);
+    }
+
+    #[test]
+    fn tweens_ease_to_the_target() {
+        let mut animations = Animations::default();
+        let id = animations.add_tween(Rect::from_size(10.0, 10.0), 100.0, Easing::EaseOutCubic);
+        animations.set_tween(&id, Rect::from_size(20.0, 20.0));
+        animations.tick(50.0);
+        assert_eq!(animations.get_tween(&id).width(), 18.75);
+        animations.set_tween(&id, Rect::from_size(20.0, 20.0));
+        animations.tick(60.0);
+        assert_eq!(animations.get_tween(&id), Rect::from_size(20.0, 20.0));
+        assert!(animations.is_tween_done(&id));
+    }
+
+    #[test]
+    fn springs_settle_at_the_target() {
+        let cx = SignalCx::new();
+        let mut animations = Animations::default();
+        let critical = animations.add_spring(Spring::critically_damped(200.0), cx.signal(0.0));
+        let bouncy = animations.add_spring(Spring::new(200.0, 5.0), cx.signal(0.0));
+        animations.set_spring_target(&critical, 1.0);
+        animations.set_spring_target(&bouncy, 1.0);
+        let (mut critical_max, mut bouncy_max) = (0.0f32, 0.0f32);
+        for _ in 0..300 {
+            animations.tick(16.0);
+            critical_max = critical_max.max(animations.get_spring(&critical));
+            bouncy_max = bouncy_max.max(animations.get_spring(&bouncy));
+        }
+        assert!(critical_max <= 1.0);
+        assert!(bouncy_max > 1.0);
+        assert!(animations.is_spring_at_rest(&critical));
+        assert!(animations.is_spring_at_rest(&bouncy));
+        assert_eq!(critical.as_computed().get(), 1.0);
+    }
+
+    #[test]
+    fn color_transitions_jump_to_the_first_target_and_follow_their_source() {
+        let cx = SignalCx::new();
+        let mut animations = Animations::default();
+        let transition = animations.add_color_transition(cx.signal_default(), None, 100.0, Easing::Linear);
+        animations.set_color_transition_target(&transition, rgb(0x000000));
+        assert_eq!(transition.as_computed().get(), rgb(0x000000));
+        animations.set_color_transition_target(&transition, rgb(0xffffff));
+        animations.tick(50.0);
+        assert_eq!(animations.get_color_transition(&transition), rgb(0x000000).with_mix(rgb(0xffffff), 0.5));
+
+        let source = cx.signal(rgb(0xff0000));
+        let follower =
+            animations.add_color_transition(cx.signal_default(), Some(source.as_computed()), 100.0, Easing::Linear);
+        animations.tick(16.0);
+        assert_eq!(follower.as_computed().get(), rgb(0xff0000));
+        source.set(rgb(0x0000ff));
+        animations.tick(100.0);
+        animations.tick(16.0);
+        assert_eq!(follower.as_computed().get(), rgb(0x0000ff));
+    }
+
+    #[test]
+    fn ticks_report_whether_anything_moved() {
+        let cx = SignalCx::new();
+        let mut animations = Animations::default();
+        assert!(!animations.tick(16.0));
+        let tween = animations.add_tween(0.0, 100.0, Easing::Linear);
+        let spring = animations.add_spring(Spring::critically_damped(200.0), cx.signal(0.0));
+        assert!(!animations.tick(16.0));
+
+        animations.set_tween(&tween, 1.0);
+        assert!(animations.tick(60.0));
+        assert!(animations.tick(60.0));
+        assert!(!animations.tick(60.0));
+
+        animations.set_spring_target(&spring, 1.0);
+        let changes = cx.changes();
+        assert!(animations.tick(16.0));
+        assert!(cx.changes() > changes);
+        while !animations.is_spring_at_rest(&spring) {
+            animations.tick(16.0);
+        }
+        assert!(!animations.tick(16.0));
+
+        let _clock = animations.add_clock();
+        assert!(animations.tick(16.0));
+    }
 }
@@ -1,24 +1,23 @@
 use std::sync::Arc;
 
-use baseview::WindowHandle;
 use baseview::{
-    DropData, Event, EventStatus, MouseEvent, ScrollDelta, WindowEvent, WindowHandler, WindowOpenOptions,
+    DropData, Event, EventStatus, MouseEvent, ScrollDelta, WindowEvent, WindowHandle, WindowHandler, WindowOpenOptions,
     WindowScalePolicy,
 };
 use keyboard_types::KeyState;
-use nih_plug::editor::Editor;
 use parking_lot::Mutex;
-use ui::Modifiers;
-use ui::UILike;
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
 
-use crate::platform::{Platform, PlatformCommon};
-use crate::{App, Component, ComponentId, Cx, IntPoint, Point, PointerId, UI, point, ui};
+use crate::platform::Platform;
+use crate::{App, Component, ComponentId, Cx, Error, IntPoint, Modifiers, Point, PointerId, UI, point, ui};
 
 struct PalloWindowHandler<A: App> {
     ui: UI<A>,
-    nih_ui_context: Arc<dyn nih_plug::prelude::GuiContext>,
     size: Arc<Mutex<IntPoint>>,
+    request_resize: Box<dyn Fn() -> bool + Send>,
+    scale_factor: f32,
     mouse_pos: Point,
+    modifiers: keyboard_types::Modifiers,
 }
 
 impl<A: App> PalloWindowHandler<A> {
@@ -26,6 +25,38 @@ impl<A: App> PalloWindowHandler<A> {
         let size = *self.size.lock();
         self.ui.on_event(ui::WindowEvent::Resized(size));
     }
+
+    fn update_modifiers(&mut self, modifiers: keyboard_types::Modifiers) {
+        if modifiers != self.modifiers {
+            self.modifiers = modifiers;
+            self.ui.on_event(ui::WindowEvent::ModifiersChanged(convert_modifiers(modifiers)));
+        }
+    }
+}
+
+fn convert_modifiers(modifiers: keyboard_types::Modifiers) -> Modifiers {
+    Modifiers {
+        #[cfg(target_os = "windows")]
+        meta: modifiers.contains(keyboard_types::Modifiers::CONTROL),
+        #[cfg(target_os = "macos")]
+        meta: modifiers.contains(keyboard_types::Modifiers::META),
+        shift: modifiers.contains(keyboard_types::Modifiers::SHIFT),
+        alt: modifiers.contains(keyboard_types::Modifiers::ALT),
+        #[cfg(target_os = "macos")]
+        ctrl: modifiers.contains(keyboard_types::Modifiers::CONTROL),
+        #[cfg(target_os = "windows")]
+        ctrl: false,
+    }
+}
+
+fn platform_for_window(window: &baseview::Window) -> Result<Platform, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        RawWindowHandle::AppKit(handle) => Platform::new_from_window_handle(handle.ns_view),
+        #[cfg(target_os = "windows")]
+        RawWindowHandle::Win32(handle) => Platform::new_from_window_handle(handle.hwnd),
+        _ => Err(Error::Window("unsupported window handle".into())),
+    }
 }
 
 impl From<crate::event::EventStatus> for baseview::EventStatus {
@@ -43,7 +74,7 @@ impl<A: App> WindowHandler for PalloWindowHandler<A> {
 
         if let Some(new_size) = self.ui.should_resize_to() {
             *self.size.lock() = new_size;
-            if self.nih_ui_context.request_resize() {
+            if (self.request_resize)() {
                 window.resize(baseview::Size::new(new_size.x as f64, new_size.y as f64));
                 self.resize();
             }
@@ -53,14 +84,16 @@ impl<A: App> WindowHandler for PalloWindowHandler<A> {
     fn on_event(&mut self, window: &mut baseview::Window, event: Event) -> baseview::EventStatus {
         match event {
             Event::Mouse(event) => match event {
-                MouseEvent::CursorMoved { position, modifiers: _ } => {
+                MouseEvent::CursorMoved { position, modifiers } => {
+                    self.update_modifiers(modifiers);
                     self.mouse_pos = point(position.x as f32, position.y as f32);
                     return self
                         .ui
                         .on_event(ui::WindowEvent::PointerMove { position: self.mouse_pos, id: PointerId::Mouse })
                         .into();
                 }
-                MouseEvent::ButtonPressed { button, modifiers: _ } => {
+                MouseEvent::ButtonPressed { button, modifiers } => {
+                    self.update_modifiers(modifiers);
                     window.focus();
                     return self
                         .ui
@@ -76,11 +109,20 @@ impl<A: App> WindowHandler for PalloWindowHandler<A> {
                         })
                         .into();
                 }
-                MouseEvent::ButtonReleased { button: _, modifiers: _ } => {
+                MouseEvent::ButtonReleased { button: _, modifiers } => {
+                    self.update_modifiers(modifiers);
                     return self.ui.on_event(ui::WindowEvent::PointerUp { id: PointerId::Mouse }).into();
                 }
-                MouseEvent::WheelScrolled { delta: ScrollDelta::Pixels { x, y }, modifiers: _ } => {
-                    return self.ui.on_event(ui::WindowEvent::MouseWheel(point(x, y))).into();
+                MouseEvent::WheelScrolled { delta, modifiers } => {
+                    self.update_modifiers(modifiers);
+                    let delta = match delta {
+                        ScrollDelta::Pixels { x, y } => crate::ScrollDelta::Pixels(point(x, y)),
+                        ScrollDelta::Lines { x, y } => crate::ScrollDelta::Lines(point(x, y)),
+                    };
+                    return self
+                        .ui
+                        .on_event(ui::WindowEvent::MouseWheel { delta, phase: crate::ScrollPhase::Changed })
+                        .into();
                 }
                 MouseEvent::DragEntered { position: _, modifiers: _, data: DropData::Files(files) } => {
                     if let crate::event::EventStatus::Captured = self.ui.on_event(ui::WindowEvent::FileHovered(
@@ -120,29 +162,36 @@ impl<A: App> WindowHandler for PalloWindowHandler<A> {
                 _ => {}
             },
             Event::Keyboard(event) => {
-                self.ui.on_event(ui::WindowEvent::ModifiersChanged(Modifiers {
-                    #[cfg(target_os = "windows")]
-                    meta: event.modifiers.contains(keyboard_types::Modifiers::CONTROL),
-                    #[cfg(target_os = "macos")]
-                    meta: event.modifiers.contains(keyboard_types::Modifiers::META),
-                    shift: event.modifiers.contains(keyboard_types::Modifiers::SHIFT),
-                    alt: event.modifiers.contains(keyboard_types::Modifiers::ALT),
-                    #[cfg(target_os = "macos")]
-                    ctrl: event.modifiers.contains(keyboard_types::Modifiers::CONTROL),
-                    #[cfg(target_os = "windows")]
-                    ctrl: false,
-                }));
+                self.update_modifiers(event.modifiers);
 
                 match event.state {
                     KeyState::Down => {
-                        return self.ui.on_event(ui::WindowEvent::Keydown(event.key)).into();
+                        let keydown = ui::WindowEvent::Keydown {
+                            key: event.key,
+                            code: event.code,
+                            repeat: event.repeat,
+                            location: event.location,
+                        };
+                        return self.ui.on_event(keydown).into();
                     }
                     KeyState::Up => {
-                        return self.ui.on_event(ui::WindowEvent::Keyup(event.key)).into();
+                        let keyup =
+                            ui::WindowEvent::Keyup { key: event.key, code: event.code, location: event.location };
+                        return self.ui.on_event(keyup).into();
                     }
                 }
             }
             Event::Window(event) => match event {
+                WindowEvent::Resized(info) => {
+                    let scale_factor = info.scale() as f32;
+                    if scale_factor != self.scale_factor {
+                        self.scale_factor = scale_factor;
+                        self.ui.on_event(ui::WindowEvent::ScaleFactorChanged(scale_factor));
+                    }
+                    let size = info.logical_size();
+                    *self.size.lock() = (size.width.round() as u32, size.height.round() as u32).into();
+                    self.resize();
+                }
                 WindowEvent::Focused => {
                     self.ui.on_event(ui::WindowEvent::FocusChanged(true));
                 }
@@ -156,79 +205,135 @@ impl<A: App> WindowHandler for PalloWindowHandler<A> {
     }
 }
 
-struct Instance {
+/// A UI running in a baseview window. The window is closed when this is dropped.
+pub struct BaseviewWindow {
     handle: WindowHandle,
 }
 
-impl Drop for Instance {
+impl Drop for BaseviewWindow {
     fn drop(&mut self) {
         self.handle.close();
     }
 }
 
-unsafe impl Send for Instance {}
+unsafe impl Send for BaseviewWindow {}
 
-pub struct PalloEditor<A: App, R: Component<A>> {
-    size: Arc<Mutex<IntPoint>>,
-    create_root: Box<dyn Fn(&mut Cx<A>, ComponentId, Arc<dyn nih_plug::prelude::GuiContext>) -> R + Send>,
+fn open_window<A: App, R: Component<A> + 'static>(
+    parent: &impl HasRawWindowHandle,
     init: A::AppInit,
+    size: Arc<Mutex<IntPoint>>,
+    scale: WindowScalePolicy,
+    request_resize: Box<dyn Fn() -> bool + Send>,
+    create_root: impl Fn(&mut Cx<A>, ComponentId) -> R + Send + 'static,
+) -> BaseviewWindow {
+    let initial_size = *size.lock();
+    let options = WindowOpenOptions {
+        scale,
+        size: baseview::Size { width: initial_size.x as f64, height: initial_size.y as f64 },
+        title: "Plug-in".to_owned(),
+    };
+
+    let handle = baseview::Window::open_parented(parent, options, move |window| {
+        // the host gives us no way to fail here
+        let platform = platform_for_window(window).unwrap_or_else(|error| panic!("{error}"));
+        let ui = UI::new(init, platform, create_root);
+        let mut handler = PalloWindowHandler {
+            scale_factor: ui.ui_context.scale_factor.get_fast(),
+            ui,
+            size,
+            request_resize,
+            mouse_pos: point(-1.0, -1.0),
+            modifiers: keyboard_types::Modifiers::empty(),
+        };
+        handler.resize();
+        handler
+    });
+    BaseviewWindow { handle }
 }
 
-impl<A: App, R: Component<A>> PalloEditor<A, R> {
-    pub fn new(
+impl<A: App> UI<A> {
+    /// Opens a UI in a baseview window embedded in `parent`, e.g. the window handle given by a plug-in host.
+    pub fn new_baseview_window<R: Component<A> + 'static>(
+        parent: &impl HasRawWindowHandle,
         init: A::AppInit,
-        create_root: impl Fn(&mut Cx<A>, ComponentId, Arc<dyn nih_plug::prelude::GuiContext>) -> R + Send + 'static,
-    ) -> Self {
-        let initial_size = A::get_initial_size(&init);
-        Self { init, create_root: Box::new(create_root), size: Arc::new(Mutex::new(initial_size)) }
+        create_root: impl Fn(&mut Cx<A>, ComponentId) -> R + Send + 'static,
+    ) -> BaseviewWindow {
+        let size = Arc::new(Mutex::new(A::get_initial_size(&init)));
+        open_window(parent, init, size, WindowScalePolicy::SystemScaleFactor, Box::new(|| true), create_root)
     }
 }
 
-impl<A: App, R: Component<A> + 'static> Editor for PalloEditor<A, R> {
-    fn spawn(
-        &self,
-        parent: nih_plug::prelude::ParentWindowHandle,
-        nih_ui_context: Arc<dyn nih_plug::prelude::GuiContext>,
-    ) -> Box<dyn std::any::Any + Send> {
-        if let Some(platform) = Platform::new(parent) {
-            let scale_factor = platform.get_scale_factor();
-
-            let nih_ctx = nih_ui_context.clone();
-
-            let ui = UI::new(self.init.clone(), platform, move |cx, root_id| {
-                (self.create_root)(cx, root_id, nih_ctx.clone())
-            });
-
-            let draw_size = point(self.size().0 as f32, self.size().1 as f32);
-            let options = WindowOpenOptions {
-                scale: WindowScalePolicy::ScaleFactor(scale_factor as f64),
-                size: baseview::Size { width: draw_size.x as f64, height: draw_size.y as f64 },
-                title: "Plug-in".to_owned(),
-            };
+#[cfg(feature = "nih")]
+mod editor {
+    use std::{marker::PhantomData, sync::Arc};
 
-            let size = self.size.clone();
-            let window_handle = baseview::Window::open_parented(&parent, options, move |_| {
-                let mut handler = PalloWindowHandler { ui, nih_ui_context, size, mouse_pos: point(-1.0, -1.0) };
-                handler.resize();
-                handler
-            });
-            return Box::new(Instance { handle: window_handle });
-        }
-        panic!("invalid window handle");
-    }
+    use baseview::WindowScalePolicy;
+    use nih_plug::prelude::{Editor, GuiContext, ParentWindowHandle};
+    use parking_lot::Mutex;
+
+    use crate::{App, Component, ComponentId, Cx, IntPoint};
 
-    fn size(&self) -> (u32, u32) {
-        (*self.size.lock()).into()
+    type CreateRoot<A, R> = dyn Fn(&mut Cx<A>, ComponentId, Arc<dyn GuiContext>) -> R + Send + Sync;
+
+    pub struct PalloEditor<A: App, R: Component<A>> {
+        size: Arc<Mutex<IntPoint>>,
+        scale_factor: Mutex<Option<f32>>,
+        create_root: Arc<CreateRoot<A, R>>,
+        init: A::AppInit,
+        _p: PhantomData<fn() -> R>,
     }
 
-    fn set_scale_factor(&self, _factor: f32) -> bool {
-        // self.ui.on_event(ui::WindowEvent::ScaleFactorChanged(factor));
-        true
+    impl<A: App, R: Component<A>> PalloEditor<A, R> {
+        pub fn new(
+            init: A::AppInit,
+            create_root: impl Fn(&mut Cx<A>, ComponentId, Arc<dyn GuiContext>) -> R + Send + Sync + 'static,
+        ) -> Self {
+            let initial_size = A::get_initial_size(&init);
+            Self {
+                init,
+                create_root: Arc::new(create_root),
+                size: Arc::new(Mutex::new(initial_size)),
+                scale_factor: Mutex::new(None),
+                _p: PhantomData,
+            }
+        }
     }
 
-    fn param_value_changed(&self, _id: &str, _normalized_value: f32) {}
+    impl<A: App, R: Component<A> + 'static> Editor for PalloEditor<A, R> {
+        fn spawn(&self, parent: ParentWindowHandle, context: Arc<dyn GuiContext>) -> Box<dyn std::any::Any + Send> {
+            let scale = match *self.scale_factor.lock() {
+                Some(factor) => WindowScalePolicy::ScaleFactor(factor as f64),
+                None => WindowScalePolicy::SystemScaleFactor,
+            };
+            let create_root = self.create_root.clone();
+            let root_context = context.clone();
+            Box::new(super::open_window(
+                &parent,
+                self.init.clone(),
+                self.size.clone(),
+                scale,
+                Box::new(move || context.request_resize()),
+                move |cx, root_id| (create_root)(cx, root_id, root_context.clone()),
+            ))
+        }
 
-    fn param_modulation_changed(&self, _id: &str, _modulation_offset: f32) {}
+        fn size(&self) -> (u32, u32) {
+            (*self.size.lock()).into()
+        }
+
+        fn set_scale_factor(&self, factor: f32) -> bool {
+            // only used when opening the window, after that the scale comes from baseview's resize events
+            *self.scale_factor.lock() = Some(factor);
+            true
+        }
 
-    fn param_values_changed(&self) {}
+        fn param_value_changed(&self, _id: &str, _normalized_value: f32) {}
+
+        fn param_modulation_changed(&self, _id: &str, _modulation_offset: f32) {}
+
+        fn param_values_changed(&self) {}
+    }
 }
+
+#[cfg(feature = "nih")]
+pub use editor::PalloEditor;
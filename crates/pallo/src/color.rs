@@ -2,7 +2,7 @@ use palette::{
     FromColor, Hsl, Hsla, IntoColor, Lighten, Mix, Okhsla, OklabHue, Oklaba, Saturate, Srgb, Srgba, WithAlpha, WithHue,
 };
 
-use crate::{Fill, Point};
+use crate::{Error, Fill, Point, ShaderEffect};
 
 #[derive(Clone, Debug, Default, Copy, PartialEq)]
 pub struct Color {
@@ -149,22 +149,93 @@ impl Color {
     }
 }
 
-#[derive(Default, Clone)]
+/// A linear gradient between two points through any number of color stops. Build one with `Gradient::builder`, or
+/// with one of the constructors for common cases.
+#[derive(Default, Clone, Debug, PartialEq)]
 pub struct Gradient {
     pub(crate) points: (Point, Point),
-    pub(crate) colors: [Color; 4],
-    pub(crate) positions: [f32; 4],
-    pub(crate) num_positions: u8,
+    pub(crate) colors: Vec<Color>,
+    pub(crate) positions: Vec<f32>,
 }
 
 impl Gradient {
+    /// Starts a gradient that runs from `start` to `end`, with its stops added by `GradientBuilder::stop`.
+    pub fn builder(start: impl Into<Point>, end: impl Into<Point>) -> GradientBuilder {
+        GradientBuilder { points: (start.into(), end.into()), colors: vec![], positions: vec![] }
+    }
+
     pub fn two_points(points: (impl Into<Point>, impl Into<Point>), colors: (Color, Color)) -> Self {
-        Self {
-            points: (points.0.into(), points.1.into()),
-            colors: [colors.0, colors.1, Default::default(), Default::default()],
-            positions: [0.0, 1.0, 0.0, 0.0],
-            num_positions: 2,
+        Self { points: (points.0.into(), points.1.into()), colors: vec![colors.0, colors.1], positions: vec![0.0, 1.0] }
+    }
+
+    /// Goes from `color` to fully transparent, e.g. for fading out the edge of a scrolled list.
+    pub fn fade_out(points: (impl Into<Point>, impl Into<Point>), color: Color) -> Self {
+        Self::two_points(points, (color, color.with_alpha(0.0)))
+    }
+
+    /// Spaces `colors` evenly from the start to the end. A single color is drawn as a solid fill, and no colors fail.
+    pub fn evenly_spaced(points: (impl Into<Point>, impl Into<Point>), colors: &[Color]) -> Result<Self, Error> {
+        let builder = Self::builder(points.0, points.1);
+        match colors {
+            [] => builder.build(),
+            [color] => builder.stop(0.0, *color).stop(1.0, *color).build(),
+            _ => {
+                let last = (colors.len() - 1) as f32;
+                colors
+                    .iter()
+                    .enumerate()
+                    .fold(builder, |builder, (index, color)| builder.stop(index as f32 / last, *color))
+                    .build()
+            }
+        }
+    }
+
+    pub fn get_points(&self) -> (Point, Point) {
+        self.points
+    }
+
+    /// The positions and colors of the stops, in order.
+    pub fn get_stops(&self) -> impl Iterator<Item = (f32, Color)> + '_ {
+        self.positions.iter().copied().zip(self.colors.iter().copied())
+    }
+}
+
+/// Collects the stops of a `Gradient`, see `Gradient::builder`.
+#[derive(Clone, Debug)]
+pub struct GradientBuilder {
+    points: (Point, Point),
+    colors: Vec<Color>,
+    positions: Vec<f32>,
+}
+
+impl GradientBuilder {
+    /// Adds a stop of `color` at `position`, from 0 at the start point to 1 at the end point. Stops are added in order
+    /// and may share a position for a hard edge.
+    pub fn stop(mut self, position: f32, color: Color) -> Self {
+        self.positions.push(position);
+        self.colors.push(color);
+        self
+    }
+
+    /// Fails if there are fewer than two stops, or if their positions are outside of 0 to 1 or out of order.
+    pub fn build(self) -> Result<Gradient, Error> {
+        if self.positions.len() < 2 {
+            return Err(Error::Graphics(format!("a gradient needs at least 2 stops, got {}", self.positions.len())));
+        }
+        let mut previous = 0.0;
+        for (index, &position) in self.positions.iter().enumerate() {
+            if !(0.0..=1.0).contains(&position) {
+                return Err(Error::Graphics(format!("gradient stop {index} is at {position}, outside of 0 to 1")));
+            }
+            if position < previous {
+                return Err(Error::Graphics(format!(
+                    "gradient stop {index} at {position} comes before stop {}",
+                    index - 1
+                )));
+            }
+            previous = position;
         }
+        Ok(Gradient { points: self.points, colors: self.colors, positions: self.positions })
     }
 }
 
@@ -185,3 +256,45 @@ impl From<Gradient> for Fill {
         Fill::Gradient(val)
     }
 }
+
+impl From<ShaderEffect> for Fill {
+    fn from(val: ShaderEffect) -> Self {
+        Fill::Shader(val)
+    }
+}
+
+impl From<&ShaderEffect> for Fill {
+    fn from(val: &ShaderEffect) -> Self {
+        Fill::Shader(val.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Gradient, rgb};
+
+    #[test]
+    fn gradients_keep_any_number_of_stops() {
+        let colors = [
+            rgb(0xff0000),
+            rgb(0x00ff00),
+            rgb(0x0000ff),
+            rgb(0xffffff),
+            rgb(0x000000),
+        ];
+        let gradient = Gradient::evenly_spaced(((0.0, 0.0), (100.0, 0.0)), &colors).unwrap();
+        let positions: Vec<f32> = gradient.get_stops().map(|(position, _)| position).collect();
+        assert_eq!(positions, [0.0, 0.25, 0.5, 0.75, 1.0]);
+        assert_eq!(gradient.get_stops().nth(4).unwrap().1, rgb(0x000000));
+    }
+
+    #[test]
+    fn invalid_stops_are_rejected() {
+        let builder = Gradient::builder((0.0, 0.0), (0.0, 100.0));
+        assert!(builder.clone().stop(0.0, rgb(0)).build().is_err());
+        assert!(builder.clone().stop(0.0, rgb(0)).stop(1.5, rgb(0)).build().is_err());
+        assert!(builder.clone().stop(0.6, rgb(0)).stop(0.4, rgb(0)).build().is_err());
+        assert!(builder.clone().stop(f32::NAN, rgb(0)).stop(1.0, rgb(0)).build().is_err());
+        assert!(builder.stop(0.0, rgb(0)).stop(0.5, rgb(0)).stop(0.5, rgb(0xffffff)).build().is_ok());
+    }
+}
@@ -1,29 +1,132 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::RefCell,
+    panic::AssertUnwindSafe,
+    rc::{Rc, Weak},
+};
+
+use crate::{
+    App, Canvas, CanvasType, Cx, Easing, Event, EventKinds, Grid, Matrix, Point, PointerState, Property, PropertyId,
+    PropertyStore, Rect, tree::NodeId, wheel::ScrollContainer,
+};
+
+/// The exit of a component that is being unmounted with `Cx::unmount`.
+#[derive(Clone, Copy)]
+pub(crate) struct Unmount {
+    pub(crate) elapsed_ms: f32,
+    pub(crate) duration_ms: f32,
+}
 
-use crate::{App, Canvas, Cx, Event, Grid, PointerState, Property, PropertyId, PropertyStore, Rect, tree::NodeId};
+impl Unmount {
+    /// How far the exit is, from 0 to 1.
+    pub(crate) fn progress(&self) -> f32 {
+        if self.duration_ms > 0.0 {
+            (self.elapsed_ms / self.duration_ms).min(1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Flags that a component sets for itself and that also have to hold for its ancestors, because e.g. an ancestor that
+/// doesn't get an event can't pass it on.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) struct SubtreeFlags {
+    pub(crate) subscriptions: EventKinds,
+    pub(crate) updates_when_hidden: bool,
+    pub(crate) draws_outside_bounds: bool,
+}
+
+impl SubtreeFlags {
+    pub(crate) fn combine(self, other: Self) -> Self {
+        Self {
+            subscriptions: self.subscriptions | other.subscriptions,
+            updates_when_hidden: self.updates_when_hidden || other.updates_when_hidden,
+            draws_outside_bounds: self.draws_outside_bounds || other.draws_outside_bounds,
+        }
+    }
+}
+
+impl Default for SubtreeFlags {
+    fn default() -> Self {
+        Self { subscriptions: EventKinds::ALL, updates_when_hidden: false, draws_outside_bounds: false }
+    }
+}
 
 pub struct ComponentState<A: App> {
     pub(crate) visible: bool,
     pub(crate) focusable: bool,
     pub(crate) hoverable: bool,
     pub(crate) disabled: bool,
+    /// Set when the component panicked while `Cx::catch_panics` was on.
+    pub(crate) panicked: bool,
     pub(crate) bounds: Rect,
+    /// The transform from the component's coordinates to the root's, as of when it was last drawn.
+    pub(crate) transform: Matrix,
     pub(crate) clips_children: bool,
     pub(crate) needs_relayout: bool,
+    pub(crate) flags: SubtreeFlags,
+    /// `flags` of the component and all of its descendants combined.
+    pub(crate) subtree_flags: SubtreeFlags,
+    pub(crate) unmount: Option<Unmount>,
+    /// Drawn and hit-tested above everything else by a `Portal` instead of in place.
+    pub(crate) portal: bool,
+    /// Drawn and hit-tested by `UI` in the order of its `OverlayLayer` instead of with the root's other children.
+    pub(crate) overlay: bool,
+    /// Set for scroll containers, see `Cx::set_can_scroll`.
+    pub(crate) scroll: Option<ScrollContainer>,
     pub(crate) app_state: A::ComponentState,
     pub(crate) properties: PropertyStore,
 }
 
+impl<A: App> ComponentState<A> {
+    pub(crate) fn to_local(&self, point: Point) -> Point {
+        if self.transform.is_identity() {
+            return point;
+        }
+        self.transform.invert().map_or(point, |inverse| inverse.map_point(point))
+    }
+
+    /// Whether the component is entirely outside of `clip`, see `Cx::is_culled`.
+    pub(crate) fn is_culled(&self, clip: Option<Rect>) -> bool {
+        let bounds = self.bounds;
+        if self.subtree_flags.draws_outside_bounds || bounds.width() <= 0.0 || bounds.height() <= 0.0 {
+            return false;
+        }
+        clip.and_then(|clip| clip.intersection(bounds)).is_none()
+    }
+
+    /// Whether `event` should be dispatched to the component or its descendants, see `Cx::wants_event`.
+    pub(crate) fn wants_event(&self, event: &Event<A>, skip_hidden_updates: bool) -> bool {
+        if self.unmount.is_some()
+            && event.kind().intersects(EventKinds::POINTER | EventKinds::KEYBOARD | EventKinds::FOCUS)
+        {
+            return false;
+        }
+        if skip_hidden_updates && event.update() && !self.visible && !self.subtree_flags.updates_when_hidden {
+            return false;
+        }
+        !self.panicked && self.subtree_flags.subscriptions.intersects(event.kind())
+    }
+}
+
 impl<A: App> Default for ComponentState<A> {
     fn default() -> Self {
         Self {
             visible: true,
             focusable: false,
             disabled: false,
+            panicked: false,
             hoverable: false,
             clips_children: true,
             bounds: Rect::default(),
+            transform: Matrix::IDENTITY,
             needs_relayout: false,
+            flags: SubtreeFlags::default(),
+            subtree_flags: SubtreeFlags::default(),
+            unmount: None,
+            portal: false,
+            overlay: false,
+            scroll: None,
             properties: PropertyStore::default(),
             app_state: A::ComponentState::default(),
         }
@@ -137,6 +240,47 @@ macro_rules! component_methods {
             cx.get_changed_property(self.$get_id(), id)
         }
 
+        /// Limits the events the component receives through `dispatch_event` to `kinds`. Components get every event
+        /// until they subscribe, and a parent still gets the events its children subscribed to.
+        #[inline]
+        fn subscribe(&self, cx: &mut Cx<A>, kinds: EventKinds) {
+            cx.set_subscriptions(self.$get_id(), kinds);
+        }
+
+        /// Keeps `Event::Update` coming while the component is hidden and `Cx::skip_hidden_updates` is on, e.g. for
+        /// meters that have to keep their state up to date offscreen.
+        #[inline]
+        fn set_updates_when_hidden(&self, cx: &mut Cx<A>, value: bool) {
+            cx.set_updates_when_hidden(self.$get_id(), value);
+        }
+
+        /// Keeps the component from being culled when its bounds are outside of the clip, for components that draw
+        /// outside of their bounds, e.g. shadows or popups.
+        #[inline]
+        fn set_draws_outside_bounds(&self, cx: &mut Cx<A>, value: bool) {
+            cx.set_draws_outside_bounds(self.$get_id(), value);
+        }
+
+        #[inline]
+        fn animate_bounds(&self, cx: &mut Cx<A>, target: Rect, duration_ms: f32, easing: Easing) {
+            cx.animate_bounds(self.$get_id(), target, duration_ms, easing);
+        }
+
+        #[inline]
+        fn unmount(&self, cx: &mut Cx<A>, exit_ms: f32) {
+            cx.unmount(self.$get_id(), exit_ms);
+        }
+
+        #[inline]
+        fn is_unmounted(&self, cx: &Cx<A>) -> bool {
+            cx.is_unmounted(self.$get_id())
+        }
+
+        #[inline]
+        fn unmount_progress(&self, cx: &Cx<A>) -> Option<f32> {
+            cx.unmount_progress(self.$get_id())
+        }
+
         #[inline]
         fn move_to_front(&self, cx: &mut Cx<A>) {
             cx.move_to_front(self.$get_id());
@@ -182,6 +326,33 @@ pub trait Component<A: App> {
         self.draw_children(cx, canvas);
     }
 
+    /// Calls `draw` inside a span named after the component's type, so that profilers can show the cost of each
+    /// component when the `tracing` feature is enabled. With `Cx::catch_panics` on, a component that panics is drawn
+    /// as a placeholder instead.
+    #[inline]
+    fn draw_instrumented(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        cx.set_transform(self.id(), canvas.current_matrix());
+        if cx.has_requested_screenshots() {
+            cx.take_requested_screenshots(self);
+        }
+        if cx.is_culled(self.id(), canvas) {
+            return;
+        }
+        crate::utils::span!("draw_component", component = std::any::type_name::<Self>());
+        if !cx.catch_panics {
+            self.draw(cx, canvas);
+            return;
+        }
+        if !cx.has_panicked(self.id())
+            && let Err(panic) = canvas.catch_unwind(|canvas| self.draw(cx, canvas))
+        {
+            cx.report_panic(self.id(), std::any::type_name::<Self>(), "draw", panic);
+        }
+        if cx.has_panicked(self.id()) {
+            cx.draw_panic_placeholder(self.id(), canvas);
+        }
+    }
+
     fn relayout(&mut self, cx: &mut Cx<A>) {
         self.layout(cx, self.get_bounds(cx));
     }
@@ -189,11 +360,30 @@ pub trait Component<A: App> {
     #[allow(unused_variables)]
     fn event_children(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {}
 
+    #[allow(unused_variables)]
+    fn layout_children(&mut self, cx: &mut Cx<A>, bounds: Rect) {}
+
     #[allow(unused_variables)]
     fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
         self.event_children(cx, event);
     }
 
+    /// Calls `event` unless neither the component nor any of its descendants subscribed to this kind of event, or
+    /// the component panicked with `Cx::catch_panics` on.
+    #[inline]
+    fn dispatch_event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        if !cx.wants_event(self.id(), event) {
+            return;
+        }
+        if !cx.catch_panics {
+            self.event(cx, event);
+            return;
+        }
+        if let Err(panic) = std::panic::catch_unwind(AssertUnwindSafe(|| self.event(cx, event))) {
+            cx.report_panic(self.id(), std::any::type_name::<Self>(), "event", panic);
+        }
+    }
+
     #[allow(unused_variables)]
     fn get_preferred_size(&mut self, cx: &mut Cx<A>, parent_bounds: Rect) -> (Option<f32>, Option<f32>) {
         (None, None)
@@ -205,6 +395,7 @@ pub trait Component<A: App> {
 
     fn relayout_if_necessary_with_parent(&mut self, cx: &mut Cx<A>, id: WeakComponentId) {
         if cx.needs_relayout(id) {
+            crate::utils::span!("relayout", component = std::any::type_name::<Self>());
             self.relayout(cx);
             cx.set_needs_relayout(id, false);
         }
@@ -214,6 +405,7 @@ pub trait Component<A: App> {
 }
 
 pub type Overlay<T> = Rc<RefCell<T>>;
+pub(crate) type WeakOverlay<T> = Weak<RefCell<T>>;
 
 pub trait NodeIdLike<A: App> {
     fn node_id(&self) -> NodeId;
@@ -222,6 +414,16 @@ pub trait NodeIdLike<A: App> {
         cx.add_child(self.node_id(), add_child)
     }
 
+    fn insert_child_at<T>(
+        &self,
+        cx: &mut Cx<A>,
+        index: usize,
+        add_child: impl FnOnce(&mut Cx<A>, ComponentId) -> T,
+    ) -> T {
+        let id = cx.insert_child_id_at(self.node_id(), index);
+        (add_child)(cx, id)
+    }
+
     component_methods!(node_id);
 }
 
@@ -256,7 +458,9 @@ impl From<&ComponentId> for NodeId {
     }
 }
 
-#[derive(Clone, Eq, Hash, PartialEq, Copy)]
+/// Refers to a component without keeping it alive. One that may outlive its component should be checked with
+/// `Cx::is_alive` before use, accessing a removed component panics in debug builds.
+#[derive(Clone, Eq, Hash, PartialEq, Copy, Debug)]
 pub struct WeakComponentId(pub(crate) NodeId);
 
 impl<A: App> NodeIdLike<A> for WeakComponentId {
@@ -0,0 +1,135 @@
+use crate::*;
+
+/// A count in a bubble on a corner of another component, e.g. of the unread messages in an inbox. It's drawn over the
+/// corner given by `with_corner` of the anchor's bounds, so it should be added after the anchor to be drawn on top.
+pub struct Badge {
+    pub id: ComponentId,
+    anchor: WeakComponentId,
+    count: Computed<usize>,
+    /// Counts above this are shown as e.g. "99+".
+    max_count: usize,
+    show_zero: bool,
+    label: Option<Text>,
+    corner: (Align, Align),
+    text: TextBuilder,
+    height: f32,
+    color: Computed<Color>,
+    text_color: Computed<Color>,
+}
+
+impl Badge {
+    pub fn new<A: App>(
+        cx: &mut Cx<A>,
+        id: ComponentId,
+        anchor: &ComponentId,
+        count: impl Into<Computed<usize>>,
+        font_size: f32,
+        typeface: impl Into<usize>,
+    ) -> Self {
+        id.set_draws_outside_bounds(cx, true);
+        Self {
+            id,
+            anchor: anchor.weak(),
+            count: count.into(),
+            max_count: 99,
+            show_zero: false,
+            label: None,
+            corner: (Align::End, Align::Start),
+            text: TextBuilder::default().font_size(font_size).typeface(typeface),
+            height: (font_size * 1.5).round(),
+            color: rgb(0xff453a).into(),
+            text_color: rgb(0xffffff).into(),
+        }
+    }
+
+    /// The corner of the anchor that the badge is centered on, top right by default.
+    pub fn with_corner(mut self, x: Align, y: Align) -> Self {
+        self.corner = (x, y);
+        self
+    }
+
+    pub fn with_max_count(mut self, max_count: usize) -> Self {
+        self.max_count = max_count;
+        self
+    }
+
+    /// Shows the badge when the count is 0 instead of hiding it.
+    pub fn with_zero_shown(mut self) -> Self {
+        self.show_zero = true;
+        self
+    }
+
+    pub fn with_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    pub fn with_text_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.text_color = color.into();
+        self
+    }
+
+    fn format_count(&self, count: usize) -> String {
+        if count > self.max_count {
+            format!("{}+", self.max_count)
+        } else {
+            count.to_string()
+        }
+    }
+
+    fn get_bubble_bounds<A: App>(&self, cx: &Cx<A>, label: &Text) -> Option<Rect> {
+        if !cx.is_alive(self.anchor) {
+            return None;
+        }
+        let anchor = self.anchor.get_bounds(cx);
+        let corner = point(
+            match self.corner.0 {
+                Align::Start => anchor.left(),
+                Align::Center => anchor.center().x,
+                Align::End => anchor.right(),
+            },
+            match self.corner.1 {
+                Align::Start => anchor.top(),
+                Align::Center => anchor.center().y,
+                Align::End => anchor.bottom(),
+            },
+        );
+        // a single digit fits in a circle, longer counts stretch it into a pill
+        let width = (label.get_width() + self.height * 0.6).max(self.height);
+        Some(Rect::from_xywh(corner.x - width * 0.5, corner.y - self.height * 0.5, width, self.height))
+    }
+}
+
+impl<A: App> Component<A> for Badge {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let Some(label) = &self.label else {
+            return;
+        };
+        let Some(bounds) = self.get_bubble_bounds(cx, label) else {
+            return;
+        };
+        canvas.fill(self.color.get()).draw_round_rect(bounds, self.height * 0.5);
+        let cap_height = label.get_cap_height();
+        let text_bounds = Rect::from_size(label.get_width(), cap_height).centered_within(bounds);
+        label.draw(canvas, text_bounds);
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        if let Event::Update = event
+            && let Some(count) = self.count.next()
+        {
+            self.label = (count > 0 || self.show_zero)
+                .then(|| self.text.clone().text(self.format_count(count)).color(self.text_color.get()).build(cx));
+            self.relayout(cx);
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        let bubble_bounds = self.label.as_ref().and_then(|label| self.get_bubble_bounds(cx, label));
+        self.set_bounds(cx, bubble_bounds.unwrap_or(bounds));
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
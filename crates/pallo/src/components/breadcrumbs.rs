@@ -0,0 +1,302 @@
+use std::ops::Range;
+
+use crate::*;
+
+const PADDING: f32 = 6.0;
+
+pub enum BreadcrumbsEvent<T> {
+    /// A segment was clicked, either in the bar or in the menu of the segments that didn't fit.
+    Clicked(T),
+    /// Files were dropped on a segment, e.g. to move them into the folder it stands for.
+    Dropped { segment: T, files: Vec<File> },
+}
+
+struct Segment<T> {
+    id: ComponentId,
+    label: Text,
+    value: T,
+}
+
+/// What's shown in the bar from left to right.
+#[derive(Clone, Copy, PartialEq)]
+enum Slot {
+    Segment(usize),
+    /// The button that opens a menu of the collapsed segments.
+    Overflow,
+}
+
+/// Shows a path as a row of segments, e.g. the folders leading to the one open in a file browser. When the path is
+/// too long to fit, the segments after the first are collapsed into a menu, starting from the ones nearest to it. Each
+/// segment is a drop target for files.
+pub struct Breadcrumbs<T> {
+    id: ComponentId,
+    segments: Vec<Segment<T>>,
+    slots: Vec<Slot>,
+    /// The segments that are only shown in the overflow menu.
+    collapsed: Range<usize>,
+    overflow_id: ComponentId,
+    overflow_menu: Option<Overlay<PopupMenu<usize>>>,
+    /// The segment that files are being dragged over.
+    drop_target: Option<usize>,
+    text: TextBuilder,
+    height: f32,
+    text_color: Computed<Color>,
+    hover_color: Computed<Color>,
+    drop_color: Computed<Color>,
+    outputs: Output<BreadcrumbsEvent<T>>,
+}
+
+impl<T: Clone + 'static> Breadcrumbs<T> {
+    pub fn new<A: App>(cx: &mut Cx<A>, id: ComponentId, font_size: f32, typeface: impl Into<usize>) -> Self {
+        Self {
+            overflow_id: cx.add_child_id(&id).hoverable(cx),
+            id,
+            segments: vec![],
+            slots: vec![],
+            collapsed: 0..0,
+            overflow_menu: None,
+            drop_target: None,
+            text: TextBuilder::default().font_size(font_size).typeface(typeface),
+            height: (font_size * 2.0).round(),
+            text_color: rgb(0xffffff).into(),
+            hover_color: rgb(0xffffff).with_alpha(0.1).into(),
+            drop_color: rgb(0x0a84ff).with_alpha(0.4).into(),
+            outputs: Default::default(),
+        }
+    }
+
+    /// The color of the labels. Only applies to the segments set after this.
+    pub fn with_text_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.text_color = color.into();
+        self
+    }
+
+    pub fn with_hover_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.hover_color = color.into();
+        self
+    }
+
+    /// The color of the segment that files are being dragged over.
+    pub fn with_drop_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.drop_color = color.into();
+        self
+    }
+
+    pub fn next_output(&mut self) -> Option<BreadcrumbsEvent<T>> {
+        self.outputs.next_output()
+    }
+
+    /// Shows `segments` as labels with the value sent when they're clicked, from the root of the path to its end.
+    pub fn set_segments<A: App>(&mut self, cx: &mut Cx<A>, segments: impl IntoIterator<Item = (String, T)>) {
+        self.close_overflow_menu(cx);
+        self.drop_target = None;
+        let color = self.text_color.get();
+        self.segments = segments
+            .into_iter()
+            .map(|(label, value)| Segment {
+                id: cx.add_child_id(&self.id).hoverable(cx),
+                label: self.text.clone().text(label).color(color).build(cx),
+                value,
+            })
+            .collect();
+        self.relayout(cx);
+    }
+
+    fn get_segment_width(&self, index: usize) -> f32 {
+        self.segments[index].label.get_width() + PADDING * 2.0
+    }
+
+    fn get_separator_width(&self) -> f32 {
+        self.height * 0.5
+    }
+
+    fn get_slots(&self, collapsed: &Range<usize>) -> Vec<Slot> {
+        let mut slots: Vec<_> = (0..collapsed.start).map(Slot::Segment).collect();
+        if !collapsed.is_empty() {
+            slots.push(Slot::Overflow);
+        }
+        slots.extend((collapsed.end..self.segments.len()).map(Slot::Segment));
+        slots
+    }
+
+    fn get_slots_width(&self, slots: &[Slot]) -> f32 {
+        let widths: f32 = slots
+            .iter()
+            .map(|slot| match slot {
+                Slot::Segment(index) => self.get_segment_width(*index),
+                Slot::Overflow => self.height,
+            })
+            .sum();
+        widths + slots.len().saturating_sub(1) as f32 * self.get_separator_width()
+    }
+
+    /// Collapses as few segments as it takes for the rest to fit in `width`, keeping the first and the last one shown
+    /// for as long as possible.
+    fn collapse_to_fit(&mut self, width: f32) {
+        let count = self.segments.len();
+        let candidates = (1..count).map(|end| 1..end).chain(std::iter::once(0..count.saturating_sub(1)));
+        let mut collapsed = 0..0;
+        for candidate in candidates {
+            collapsed = candidate;
+            if self.get_slots_width(&self.get_slots(&collapsed)) <= width {
+                break;
+            }
+        }
+        self.slots = self.get_slots(&collapsed);
+        self.collapsed = collapsed;
+    }
+
+    fn get_slot_id(&self, slot: Slot) -> &ComponentId {
+        match slot {
+            Slot::Segment(index) => &self.segments[index].id,
+            Slot::Overflow => &self.overflow_id,
+        }
+    }
+
+    fn open_overflow_menu<A: App>(&mut self, cx: &mut Cx<A>) {
+        let bounds = self.overflow_id.get_bounds(cx);
+        let menu = PopupMenu::open(cx, point(bounds.left(), bounds.bottom()), |cx, id| {
+            self.collapsed.clone().fold(PopupMenu::new(cx, id), |menu, index| {
+                menu.with_item(self.segments[index].label.clone(), index, true)
+            })
+        });
+        self.overflow_menu = Some(menu);
+    }
+
+    fn close_overflow_menu<A: App>(&mut self, cx: &mut Cx<A>) {
+        if let Some(menu) = self.overflow_menu.take() {
+            cx.close_overlay(&menu);
+        }
+    }
+
+    fn segment_at<A: App>(&self, cx: &Cx<A>, position: Point) -> Option<usize> {
+        self.slots.iter().find_map(|&slot| match slot {
+            Slot::Segment(index) if self.segments[index].id.get_bounds(cx).contains(&position) => Some(index),
+            _ => None,
+        })
+    }
+
+    fn draw_separator(&self, canvas: &mut Canvas, center: Point, color: Color) {
+        let size = self.height * 0.12;
+        let mut path = PathBuilder::default();
+        path.move_to(point(center.x - size * 0.5, center.y - size))
+            .line_to(point(center.x + size * 0.5, center.y))
+            .line_to(point(center.x - size * 0.5, center.y + size));
+        canvas.stroke(color, 1.5).stroke_cap(Cap::Round).draw_path(&path.build());
+    }
+}
+
+impl<A: App, T: Clone + 'static> Component<A> for Breadcrumbs<T> {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let bounds = self.get_bounds(cx);
+        let text_color = self.text_color.get();
+        canvas.with_clip_rect(bounds, |canvas| {
+            let mut previous_right = None;
+            for &slot in &self.slots {
+                let id = self.get_slot_id(slot);
+                let slot_bounds = id.get_bounds(cx);
+                if let Some(right) = previous_right {
+                    let center = point((right + slot_bounds.left()) * 0.5, bounds.center().y);
+                    self.draw_separator(canvas, center, text_color.with_alpha(0.4));
+                }
+                previous_right = Some(slot_bounds.right());
+
+                let highlight = match slot {
+                    Slot::Segment(index) if self.drop_target == Some(index) => Some(self.drop_color.get()),
+                    _ if id.is_hovered_any(cx) || (slot == Slot::Overflow && self.overflow_menu.is_some()) => {
+                        Some(self.hover_color.get())
+                    }
+                    _ => None,
+                };
+                if let Some(color) = highlight {
+                    canvas.fill(color).draw_round_rect(slot_bounds, 4.0);
+                }
+
+                match slot {
+                    Slot::Segment(index) => {
+                        let label = &self.segments[index].label;
+                        let cap_height = label.get_cap_height();
+                        let text_bounds = Rect::from_xywh(
+                            slot_bounds.left() + PADDING,
+                            slot_bounds.center().y - cap_height * 0.5,
+                            label.get_width(),
+                            cap_height,
+                        );
+                        label.draw(canvas, text_bounds);
+                    }
+                    Slot::Overflow => {
+                        let center = slot_bounds.center();
+                        for offset in [-1.0, 0.0, 1.0] {
+                            canvas.fill(text_color).draw_circle(point(center.x + offset * 4.0, center.y), 1.25);
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        match event {
+            Event::Update => {
+                let output = self.overflow_menu.as_ref().and_then(|menu| menu.borrow_mut().next_output());
+                match output {
+                    Some(PopupMenuEvent::Selected(index)) => {
+                        self.close_overflow_menu(cx);
+                        self.outputs.add_output(BreadcrumbsEvent::Clicked(self.segments[index].value.clone()));
+                    }
+                    Some(PopupMenuEvent::Dismissed) => self.close_overflow_menu(cx),
+                    None => {}
+                }
+            }
+            Event::Click { pointer, .. } => {
+                if self.overflow_id.is_pressed(pointer) && self.overflow_menu.is_none() {
+                    self.open_overflow_menu(cx);
+                } else if let Some(segment) = self.segments.iter().find(|segment| segment.id.is_pressed(pointer)) {
+                    self.outputs.add_output(BreadcrumbsEvent::Clicked(segment.value.clone()));
+                }
+            }
+            Event::FileHovered(_) => {
+                self.drop_target = self.segment_at(cx, cx.main_pointer().position);
+            }
+            Event::FileDropped(files) => {
+                if let Some(index) = self.drop_target.take() {
+                    let segment = self.segments[index].value.clone();
+                    self.outputs.add_output(BreadcrumbsEvent::Dropped { segment, files: files.clone() });
+                }
+            }
+            Event::FileDropCancelled => {
+                self.drop_target = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        self.set_bounds(cx, bounds);
+        self.collapse_to_fit(bounds.width());
+        for index in self.collapsed.clone() {
+            self.segments[index].id.set_visible(cx, false);
+        }
+        self.overflow_id.set_visible(cx, !self.collapsed.is_empty());
+
+        let mut left = bounds.left();
+        for slot in self.slots.clone() {
+            let width = match slot {
+                Slot::Segment(index) => self.get_segment_width(index),
+                Slot::Overflow => self.height,
+            };
+            let id = self.get_slot_id(slot).clone();
+            id.set_visible(cx, true);
+            id.set_bounds(cx, bounds.with_left(left).with_width(width).with_height_align(self.height, Align::Center));
+            left += width + self.get_separator_width();
+        }
+    }
+
+    fn get_preferred_size(&mut self, _cx: &mut Cx<A>, _parent_bounds: Rect) -> (Option<f32>, Option<f32>) {
+        (None, Some(self.height))
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
@@ -0,0 +1,382 @@
+use crate::*;
+
+const HOVER_FADE_MS: f32 = 100.0;
+
+pub enum ChipEvent {
+    Clicked,
+    /// The close button was clicked.
+    Removed,
+}
+
+/// A small rounded tag with a label, e.g. for a filter or a recipient, which can have a button for removing it.
+pub struct Chip {
+    pub id: ComponentId,
+    close_id: Option<ComponentId>,
+    label: Text,
+    height: f32,
+    color: Computed<Color>,
+    hover_color: Computed<Color>,
+    background: ColorTransition,
+    outputs: Output<ChipEvent>,
+}
+
+impl Chip {
+    pub fn new<A: App>(
+        cx: &mut Cx<A>,
+        id: ComponentId,
+        label: impl Into<String>,
+        font_size: f32,
+        typeface: impl Into<usize>,
+    ) -> Self {
+        id.set_hoverable(cx, true);
+        Self {
+            close_id: None,
+            label: TextBuilder::default().font_size(font_size).typeface(typeface).text(label).build(cx),
+            height: (font_size * 2.0).round(),
+            id,
+            color: rgb(0xffffff).with_alpha(0.12).into(),
+            hover_color: rgb(0xffffff).with_alpha(0.2).into(),
+            background: cx.add_color_transition(HOVER_FADE_MS, Easing::EaseOutQuad),
+            outputs: Default::default(),
+        }
+    }
+
+    /// Adds a button for removing the chip, which sends `ChipEvent::Removed`.
+    pub fn removable<A: App>(mut self, cx: &mut Cx<A>) -> Self {
+        self.close_id = Some(cx.add_child_id(&self.id).hoverable(cx));
+        self
+    }
+
+    pub fn with_colors(mut self, color: impl Into<Computed<Color>>, hover_color: impl Into<Computed<Color>>) -> Self {
+        self.color = color.into();
+        self.hover_color = hover_color.into();
+        self
+    }
+
+    pub fn next_output(&mut self) -> Option<ChipEvent> {
+        self.outputs.next_output()
+    }
+
+    pub fn get_width(&self) -> f32 {
+        let padding = self.get_padding();
+        let close_width = if self.close_id.is_some() {
+            self.height - padding
+        } else {
+            0.0
+        };
+        padding * 2.0 + self.label.get_width() + close_width
+    }
+
+    pub fn get_height(&self) -> f32 {
+        self.height
+    }
+
+    fn get_padding(&self) -> f32 {
+        self.height * 0.4
+    }
+
+    fn draw_close_button<A: App>(&self, cx: &Cx<A>, canvas: &mut Canvas, close_id: &ComponentId) {
+        let bounds = close_id.get_bounds(cx);
+        let center = bounds.center();
+        let color = self.label.get_color();
+        if close_id.is_hovered_any(cx) {
+            canvas.fill(color.with_alpha_mul(0.2)).draw_circle(center, bounds.height() * 0.35);
+        }
+        let size = bounds.height() * 0.12;
+        let mut path = PathBuilder::default();
+        path.move_to(point(center.x - size, center.y - size))
+            .line_to(point(center.x + size, center.y + size))
+            .move_to(point(center.x + size, center.y - size))
+            .line_to(point(center.x - size, center.y + size));
+        canvas.stroke(color, 1.5).stroke_cap(Cap::Round).draw_path(&path.build());
+    }
+}
+
+impl<A: App> Component<A> for Chip {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let bounds = self.get_bounds(cx);
+        let target = if self.is_hovered_any(cx) {
+            self.hover_color.get()
+        } else {
+            self.color.get()
+        };
+        cx.animations.set_color_transition_target(&self.background, target);
+        let color = cx.animations.get_color_transition(&self.background);
+        canvas.fill(color).draw_round_rect(bounds, self.height * 0.5);
+
+        let cap_height = self.label.get_cap_height();
+        let text_bounds = Rect::from_xywh(
+            bounds.left() + self.get_padding(),
+            bounds.center().y - cap_height * 0.5,
+            self.label.get_width(),
+            cap_height,
+        );
+        self.label.draw(canvas, text_bounds);
+
+        if let Some(close_id) = &self.close_id {
+            self.draw_close_button(cx, canvas, close_id);
+        }
+    }
+
+    fn event(&mut self, _cx: &mut Cx<A>, event: &mut Event<A>) {
+        if let Event::Click { pointer, .. } = event {
+            if self.close_id.as_ref().is_some_and(|close_id| close_id.is_pressed(pointer)) {
+                self.outputs.add_output(ChipEvent::Removed);
+            } else if self.is_pressed(pointer) {
+                self.outputs.add_output(ChipEvent::Clicked);
+            }
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        self.set_bounds(cx, bounds);
+        if let Some(close_id) = &self.close_id {
+            let size = bounds.height();
+            close_id.set_bounds(cx, bounds.with_left(bounds.right() - size).with_width(size));
+        }
+    }
+
+    fn get_preferred_size(&mut self, _cx: &mut Cx<A>, _parent_bounds: Rect) -> (Option<f32>, Option<f32>) {
+        (Some(self.get_width()), Some(self.height))
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
+
+pub enum ChipGroupEvent<T> {
+    Clicked(T),
+    /// A chip was removed with its close button.
+    Removed(T),
+}
+
+/// Lays out chips in rows that wrap, e.g. for the tags of a file. With `with_max_rows` the chips that don't fit are
+/// collapsed into a "+N" chip, which shows all of them when clicked.
+pub struct ChipGroup<T> {
+    pub id: ComponentId,
+    chips: Vec<(Chip, T)>,
+    /// The "+N" chip for the collapsed chips, rebuilt when their number changes.
+    more: Option<(Chip, usize)>,
+    /// The width of the "+N" chip for all of the chips, see `get_more_width`.
+    more_width: Option<(usize, f32)>,
+    max_rows: Option<usize>,
+    expanded: bool,
+    removable: bool,
+    spacing: f32,
+    font_size: f32,
+    typeface: usize,
+    outputs: Output<ChipGroupEvent<T>>,
+}
+
+impl<T: Clone + 'static> ChipGroup<T> {
+    pub fn new<A: App>(cx: &mut Cx<A>, id: ComponentId, font_size: f32, typeface: impl Into<usize>) -> Self {
+        cx.set_clips_children(&id, true);
+        Self {
+            id,
+            chips: vec![],
+            more: None,
+            more_width: None,
+            max_rows: None,
+            expanded: false,
+            removable: false,
+            spacing: 6.0,
+            font_size,
+            typeface: typeface.into(),
+            outputs: Default::default(),
+        }
+    }
+
+    /// Collapses the chips that don't fit in `max_rows` rows into a "+N" chip.
+    pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = Some(max_rows.max(1));
+        self
+    }
+
+    /// Gives the chips close buttons. Only applies to the chips set after this.
+    pub fn with_removable_chips(mut self) -> Self {
+        self.removable = true;
+        self
+    }
+
+    pub fn with_spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    pub fn next_output(&mut self) -> Option<ChipGroupEvent<T>> {
+        self.outputs.next_output()
+    }
+
+    /// Shows a chip for each of `chips`, with its label and the value sent when it's clicked or removed.
+    pub fn set_chips<A: App>(&mut self, cx: &mut Cx<A>, chips: impl IntoIterator<Item = (String, T)>) {
+        self.chips = chips
+            .into_iter()
+            .map(|(label, value)| {
+                let chip = self.id.add_child(cx, |cx, id| {
+                    let chip = Chip::new(cx, id, label, self.font_size, self.typeface);
+                    if self.removable { chip.removable(cx) } else { chip }
+                });
+                (chip, value)
+            })
+            .collect();
+        self.expanded = false;
+        self.relayout(cx);
+    }
+
+    /// Whether the collapsed chips are shown after the "+N" chip was clicked.
+    pub fn is_expanded(&self) -> bool {
+        self.expanded
+    }
+
+    pub fn set_expanded<A: App>(&mut self, cx: &mut Cx<A>, expanded: bool) {
+        self.expanded = expanded;
+        self.relayout(cx);
+    }
+
+    fn get_more_chip<A: App>(&mut self, cx: &mut Cx<A>, hidden: usize) -> &mut Chip {
+        if self.more.as_ref().is_none_or(|(_, count)| *count != hidden) {
+            let chip =
+                self.id.add_child(cx, |cx, id| Chip::new(cx, id, format!("+{hidden}"), self.font_size, self.typeface));
+            self.more = Some((chip, hidden));
+        }
+        &mut self.more.as_mut().unwrap().0
+    }
+
+    /// Places the chips in rows within `width` from the top left corner, returning their positions and how many of
+    /// them fit. If some don't, the position of the "+N" chip is added after those of the ones that do.
+    fn flow(&self, width: f32, more_width: f32) -> (Vec<Point>, usize) {
+        let row_height = self.chips.first().map(|(chip, _)| chip.get_height()).unwrap_or(0.0) + self.spacing;
+        let max_rows = if self.expanded { None } else { self.max_rows };
+        let mut positions = vec![];
+        let mut position = Point::default();
+        let mut row = 0;
+        for (chip, _) in &self.chips {
+            let chip_width = chip.get_width();
+            if position.x > 0.0 && position.x + chip_width > width {
+                position = point(0.0, position.y + row_height);
+                row += 1;
+            }
+            if max_rows.is_some_and(|max_rows| row >= max_rows) {
+                break;
+            }
+            positions.push(position);
+            position.x += chip_width + self.spacing;
+        }
+        if positions.len() == self.chips.len() {
+            return (positions, self.chips.len());
+        }
+        // make room for the "+N" chip at the end of the last row
+        while !positions.is_empty() && self.get_row_end(&positions).x + more_width > width {
+            positions.pop();
+        }
+        let visible = positions.len();
+        positions.push(self.get_row_end(&positions));
+        (positions, visible)
+    }
+
+    /// Where the chip after those at `positions` would go in the same row.
+    fn get_row_end(&self, positions: &[Point]) -> Point {
+        match positions.last() {
+            Some(last) => point(last.x + self.chips[positions.len() - 1].0.get_width() + self.spacing, last.y),
+            None => Point::default(),
+        }
+    }
+
+    /// The width of the "+N" chip for the widest N, so that the flow doesn't change as N does.
+    fn get_more_width<A: App>(&mut self, cx: &mut Cx<A>) -> f32 {
+        if self.max_rows.is_none() || self.expanded || self.chips.is_empty() {
+            return 0.0;
+        }
+        let count = self.chips.len();
+        if let Some((cached_count, width)) = self.more_width
+            && cached_count == count
+        {
+            return width;
+        }
+        let label = TextBuilder::default().font_size(self.font_size).typeface(self.typeface).text(format!("+{count}"));
+        let padding = (self.font_size * 2.0).round() * 0.4;
+        let width = label.build(cx).get_width() + padding * 2.0;
+        self.more_width = Some((count, width));
+        width
+    }
+}
+
+impl<A: App, T: Clone + 'static> Component<A> for ChipGroup<T> {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        for (chip, _) in &self.chips {
+            chip.draw_instrumented(cx, canvas);
+        }
+        if let Some((more, _)) = &self.more {
+            more.draw_instrumented(cx, canvas);
+        }
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        let mut removed = None;
+        for (index, (chip, value)) in self.chips.iter_mut().enumerate() {
+            chip.dispatch_event(cx, event);
+            while let Some(output) = chip.next_output() {
+                match output {
+                    ChipEvent::Clicked => self.outputs.add_output(ChipGroupEvent::Clicked(value.clone())),
+                    ChipEvent::Removed => removed = Some(index),
+                }
+            }
+        }
+        if let Some(index) = removed {
+            let (_, value) = self.chips.remove(index);
+            self.outputs.add_output(ChipGroupEvent::Removed(value));
+            self.relayout(cx);
+        }
+
+        let mut expand = false;
+        if let Some((more, _)) = &mut self.more {
+            more.dispatch_event(cx, event);
+            while let Some(output) = more.next_output() {
+                expand |= matches!(output, ChipEvent::Clicked);
+            }
+        }
+        if expand {
+            self.set_expanded(cx, true);
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        self.set_bounds(cx, bounds);
+        let more_width = self.get_more_width(cx);
+        let (positions, visible) = self.flow(bounds.width(), more_width);
+        let origin = bounds.relative_point((0.0, 0.0));
+        for (index, (chip, _)) in self.chips.iter_mut().enumerate() {
+            chip.id.set_visible(cx, index < visible);
+            if index < visible {
+                let size = point(chip.get_width(), chip.get_height());
+                chip.layout(
+                    cx,
+                    Rect::from_xywh(origin.x + positions[index].x, origin.y + positions[index].y, size.x, size.y),
+                );
+            }
+        }
+
+        let hidden = self.chips.len() - visible;
+        if hidden == 0 {
+            self.more = None;
+            return;
+        }
+        let position = origin + positions[visible];
+        let more = self.get_more_chip(cx, hidden);
+        let rect = Rect::from_xywh(position.x, position.y, more.get_width(), more.get_height());
+        more.layout(cx, rect);
+    }
+
+    fn get_preferred_size(&mut self, cx: &mut Cx<A>, parent_bounds: Rect) -> (Option<f32>, Option<f32>) {
+        let more_width = self.get_more_width(cx);
+        let (positions, _) = self.flow(parent_bounds.width(), more_width);
+        let row_height = self.chips.first().map(|(chip, _)| chip.get_height()).unwrap_or(0.0);
+        let height = positions.iter().map(|position| position.y + row_height).fold(0.0, f32::max);
+        (None, Some(height))
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
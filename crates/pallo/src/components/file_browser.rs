@@ -0,0 +1,445 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use crate::*;
+
+const INDENT: f32 = 14.0;
+const DRAG_DISTANCE: f32 = 4.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DirEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+impl DirEntry {
+    pub fn extension(&self) -> Option<String> {
+        self.path.extension().map(|ext| ext.to_string_lossy().to_lowercase())
+    }
+}
+
+/// Lists the contents of a directory. Implement this to browse something other than the local
+/// file system, e.g. a bundled sample library on wasm.
+pub trait FileProvider {
+    fn list(&self, path: &Path) -> Vec<DirEntry>;
+}
+
+/// Lists directories with `std::fs`, folders first and hidden files left out.
+#[cfg(not(target_family = "wasm"))]
+#[derive(Default)]
+pub struct FsProvider;
+
+#[cfg(not(target_family = "wasm"))]
+impl FileProvider for FsProvider {
+    fn list(&self, path: &Path) -> Vec<DirEntry> {
+        let Ok(read_dir) = std::fs::read_dir(path) else {
+            return vec![];
+        };
+        let mut entries: Vec<_> = read_dir
+            .flatten()
+            .map(|entry| DirEntry {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                path: entry.path(),
+                is_dir: entry.path().is_dir(),
+            })
+            .filter(|entry| !entry.name.starts_with('.'))
+            .collect();
+        entries.sort_by_cached_key(|entry| (!entry.is_dir, entry.name.to_lowercase()));
+        entries
+    }
+}
+
+pub enum FileBrowserEvent {
+    Selected(PathBuf),
+    /// A file was double clicked or had enter pressed on it.
+    Opened(PathBuf),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FileBrowserView {
+    /// Folders expand in place.
+    Tree,
+    /// Opening a folder replaces the listing with its contents.
+    List,
+}
+
+pub type IconDrawer = fn(&mut Canvas, Rect, &DirEntry);
+
+struct Row {
+    entry: DirEntry,
+    depth: usize,
+    text: Text,
+}
+
+/// Browses a directory hierarchy as a tree or a flat list, with keyboard navigation and dragging files out.
+pub struct FileBrowser {
+    pub id: ComponentId,
+    provider: Box<dyn FileProvider>,
+    path: PathBuf,
+    view: FileBrowserView,
+    expanded: HashSet<PathBuf>,
+    rows: Vec<Row>,
+    selected: Option<usize>,
+    scroll_top: f32,
+    font_size: f32,
+    typeface: usize,
+    row_height: f32,
+    pressed_row: Option<usize>,
+    dragging: bool,
+    text_color: Computed<Color>,
+    selection_color: Computed<Color>,
+    draw_icon: IconDrawer,
+    outputs: Output<FileBrowserEvent>,
+}
+
+impl FileBrowser {
+    pub fn new<A: App>(
+        cx: &mut Cx<A>,
+        id: ComponentId,
+        provider: impl FileProvider + 'static,
+        path: impl Into<PathBuf>,
+        font_size: f32,
+        typeface: impl Into<usize>,
+    ) -> Self {
+        cx.set_interactive(&id, true);
+        let mut browser = Self {
+            id,
+            provider: Box::new(provider),
+            path: path.into(),
+            view: FileBrowserView::Tree,
+            expanded: Default::default(),
+            rows: vec![],
+            selected: None,
+            scroll_top: 0.0,
+            font_size,
+            typeface: typeface.into(),
+            row_height: (font_size * 1.6).round(),
+            pressed_row: None,
+            dragging: false,
+            text_color: rgb(0xffffff).into(),
+            selection_color: rgb(0xffffff).with_alpha(0.15).into(),
+            draw_icon: draw_default_icon,
+            outputs: Default::default(),
+        };
+        browser.refresh(cx);
+        browser
+    }
+
+    pub fn with_view<A: App>(mut self, cx: &mut Cx<A>, view: FileBrowserView) -> Self {
+        self.view = view;
+        self.refresh(cx);
+        self
+    }
+
+    pub fn with_text_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.text_color = color.into();
+        // the rows were already built by `new`
+        let color = self.text_color.get();
+        for row in &mut self.rows {
+            row.text.set_color(color);
+        }
+        self
+    }
+
+    pub fn with_selection_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.selection_color = color.into();
+        self
+    }
+
+    pub fn with_icon_drawer(mut self, draw_icon: IconDrawer) -> Self {
+        self.draw_icon = draw_icon;
+        self
+    }
+
+    pub fn with_row_height(mut self, row_height: f32) -> Self {
+        self.row_height = row_height;
+        self
+    }
+
+    pub fn next_output(&mut self) -> Option<FileBrowserEvent> {
+        self.outputs.next_output()
+    }
+
+    /// The directory being listed. In the tree view this is the root.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn set_path<A: App>(&mut self, cx: &mut Cx<A>, path: impl Into<PathBuf>) {
+        self.path = path.into();
+        self.selected = None;
+        self.scroll_top = 0.0;
+        self.refresh(cx);
+    }
+
+    pub fn selected(&self) -> Option<&Path> {
+        self.selected.and_then(|index| self.rows.get(index)).map(|row| row.entry.path.as_path())
+    }
+
+    /// Lists the directories again, keeping the expanded folders and the selection where they still exist.
+    pub fn refresh<A: App>(&mut self, cx: &mut Cx<A>) {
+        let selected = self.selected().map(Path::to_path_buf);
+        let mut entries = vec![];
+        self.collect_entries(&self.path, 0, &mut entries);
+        self.expanded.retain(|path| entries.iter().any(|(entry, _)| &entry.path == path));
+        self.rows = entries
+            .into_iter()
+            .map(|(entry, depth)| {
+                let text = TextBuilder::default()
+                    .font_size(self.font_size)
+                    .typeface(self.typeface)
+                    .text(entry.name.clone())
+                    .color(self.text_color.get())
+                    .build(cx);
+                Row { entry, depth, text }
+            })
+            .collect();
+        self.selected = selected.and_then(|selected| self.rows.iter().position(|row| row.entry.path == selected));
+        self.scroll_to(cx, self.scroll_top);
+    }
+
+    fn collect_entries(&self, path: &Path, depth: usize, entries: &mut Vec<(DirEntry, usize)>) {
+        for entry in self.provider.list(path) {
+            let expand = self.view == FileBrowserView::Tree && entry.is_dir && self.expanded.contains(&entry.path);
+            let path = entry.path.clone();
+            entries.push((entry, depth));
+            if expand {
+                self.collect_entries(&path, depth + 1, entries);
+            }
+        }
+    }
+
+    fn is_listed(&self, dir: &Path) -> bool {
+        dir == self.path || self.expanded.contains(dir)
+    }
+
+    fn select<A: App>(&mut self, cx: &mut Cx<A>, index: usize) {
+        let Some(row) = self.rows.get(index) else {
+            return;
+        };
+        if self.selected != Some(index) {
+            self.selected = Some(index);
+            self.outputs.add_output(FileBrowserEvent::Selected(row.entry.path.clone()));
+        }
+        self.scroll_into_view(cx, index);
+    }
+
+    fn open<A: App>(&mut self, cx: &mut Cx<A>, index: usize) {
+        let Some(entry) = self.rows.get(index).map(|row| row.entry.clone()) else {
+            return;
+        };
+        if !entry.is_dir {
+            self.outputs.add_output(FileBrowserEvent::Opened(entry.path));
+            return;
+        }
+        match self.view {
+            FileBrowserView::Tree => self.set_expanded(cx, index, !self.expanded.contains(&entry.path)),
+            FileBrowserView::List => self.set_path(cx, entry.path),
+        }
+    }
+
+    fn set_expanded<A: App>(&mut self, cx: &mut Cx<A>, index: usize, expanded: bool) {
+        let Some(path) = self.rows.get(index).map(|row| row.entry.path.clone()) else {
+            return;
+        };
+        if expanded {
+            self.expanded.insert(path);
+        } else {
+            self.expanded.remove(&path);
+        }
+        self.refresh(cx);
+    }
+
+    fn go_to_parent<A: App>(&mut self, cx: &mut Cx<A>) {
+        let Some(selected) = self.selected else {
+            return;
+        };
+        let row = &self.rows[selected];
+        if self.view == FileBrowserView::Tree {
+            if row.entry.is_dir && self.expanded.contains(&row.entry.path) {
+                self.set_expanded(cx, selected, false);
+            } else if row.depth > 0
+                && let Some(parent) = (0..selected).rev().find(|i| self.rows[*i].depth < row.depth)
+            {
+                self.select(cx, parent);
+            }
+        } else if let Some(parent) = self.path.parent().map(Path::to_path_buf) {
+            let previous = std::mem::replace(&mut self.path, parent);
+            self.selected = None;
+            self.refresh(cx);
+            if let Some(index) = self.rows.iter().position(|row| row.entry.path == previous) {
+                self.select(cx, index);
+            }
+        }
+    }
+
+    fn row_at<A: App>(&self, cx: &Cx<A>, position: Point) -> Option<usize> {
+        let bounds = self.get_bounds(cx);
+        if !bounds.contains(&position) {
+            return None;
+        }
+        let index = ((position.y - bounds.top() + self.scroll_top) / self.row_height) as usize;
+        (index < self.rows.len()).then_some(index)
+    }
+
+    fn max_scroll<A: App>(&self, cx: &Cx<A>) -> f32 {
+        (self.rows.len() as f32 * self.row_height - self.get_bounds(cx).height()).max(0.0)
+    }
+
+    fn scroll_into_view<A: App>(&mut self, cx: &mut Cx<A>, index: usize) {
+        let top = index as f32 * self.row_height;
+        let height = self.get_bounds(cx).height();
+        self.scroll_to(cx, self.scroll_top.clamp((top + self.row_height - height).max(0.0), top));
+    }
+
+    fn scroll_to<A: App>(&mut self, cx: &mut Cx<A>, top: f32) {
+        let max_scroll = self.max_scroll(cx);
+        self.scroll_top = top.clamp(0.0, max_scroll);
+        cx.set_can_scroll(&self.id, CanScroll::vertical(self.scroll_top, 0.0, max_scroll));
+    }
+}
+
+/// Draws a folder, or a page tinted by what kind of file it is.
+pub fn draw_default_icon(canvas: &mut Canvas, bounds: Rect, entry: &DirEntry) {
+    let size = bounds.height().min(bounds.width());
+    let icon = Rect::from_size(size, size * 0.8).centered_within(bounds);
+    if entry.is_dir {
+        let color = rgb(0x8fb3d9);
+        canvas.fill(color).draw_round_rect(icon.with_width(icon.width() * 0.45).with_height(icon.height() * 0.4), 1.0);
+        let mut body = icon;
+        body.remove_from(icon.height() * 0.2, Side::Top);
+        canvas.fill(color).draw_round_rect(body, 1.5);
+    } else {
+        let color = match entry.extension().as_deref() {
+            Some("wav" | "aif" | "aiff" | "flac" | "mp3" | "ogg" | "m4a") => rgb(0x7fd49a),
+            Some("png" | "jpg" | "jpeg" | "gif" | "webp" | "svg") => rgb(0xd9a46b),
+            Some("txt" | "md" | "json" | "toml" | "xml" | "csv") => rgb(0xcccccc),
+            _ => rgb(0x999999),
+        };
+        let page = Rect::from_size(size * 0.7, size).centered_within(bounds);
+        canvas.fill(color).draw_round_rect(page, 1.5);
+    }
+}
+
+impl<A: App> Component<A> for FileBrowser {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let bounds = self.get_bounds(cx);
+        let text_color = self.text_color.get();
+        canvas.with_clip_rect(bounds, |canvas| {
+            let first = (self.scroll_top / self.row_height) as usize;
+            let visible = (bounds.height() / self.row_height).ceil() as usize + 1;
+            for (index, row) in self.rows.iter().enumerate().skip(first).take(visible) {
+                let row_bounds = Rect::from_xywh(
+                    bounds.left(),
+                    bounds.top() + index as f32 * self.row_height - self.scroll_top,
+                    bounds.width(),
+                    self.row_height,
+                );
+                if self.selected == Some(index) {
+                    canvas.fill(self.selection_color.get()).draw_rect(row_bounds);
+                }
+
+                let mut content = row_bounds.with_margin(Margin::left_right(4.0));
+                content.remove_from(row.depth as f32 * INDENT, Side::Left);
+                if self.view == FileBrowserView::Tree && row.entry.is_dir {
+                    let arrow = content.remove_from(INDENT, Side::Left);
+                    let expanded = self.expanded.contains(&row.entry.path);
+                    let marker = if expanded {
+                        Rect::from_size(7.0, 2.0)
+                    } else {
+                        Rect::from_size(2.0, 7.0)
+                    };
+                    canvas.fill(text_color.with_alpha(0.5)).draw_rect(marker.centered_within(arrow));
+                } else if self.view == FileBrowserView::Tree {
+                    content.remove_from(INDENT, Side::Left);
+                }
+                let icon = content.remove_from(self.row_height, Side::Left);
+                (self.draw_icon)(canvas, icon.with_margin(Margin::even(self.row_height * 0.2)), &row.entry);
+                content.remove_from(4.0, Side::Left);
+
+                let cap_height = row.text.get_cap_height();
+                let text_bounds = Rect::from_xywh(
+                    content.left(),
+                    row_bounds.center().y - cap_height * 0.5,
+                    row.text.get_width(),
+                    cap_height,
+                );
+                canvas.with_clip_rect(content, |canvas| row.text.draw(canvas, text_bounds));
+            }
+        });
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        match event {
+            Event::MouseWheel { delta, .. } if cx.is_wheel_target(&self.id) => {
+                self.scroll_to(cx, self.scroll_top - delta.to_pixels().y);
+            }
+            Event::PointerDown(pointer) if self.id.is_hovered(pointer) => {
+                self.dragging = false;
+                self.pressed_row = self.row_at(cx, pointer.position);
+                if let Some(index) = self.pressed_row {
+                    self.select(cx, index);
+                    if cx.num_clicks == 2 {
+                        self.open(cx, index);
+                    }
+                }
+            }
+            Event::PointerMove(pointer) if self.id.is_pressed(pointer) => {
+                if !self.dragging
+                    && (pointer.position - pointer.down_position).len() > DRAG_DISTANCE
+                    && let Some(row) = self.pressed_row.and_then(|index| self.rows.get(index))
+                    && !row.entry.is_dir
+                {
+                    self.dragging = true;
+                    cx.platform.start_drag(row.entry.path.clone());
+                }
+            }
+            Event::PointerUp(pointer) if self.id.is_pressed(pointer) => {
+                self.pressed_row = None;
+                self.dragging = false;
+            }
+            Event::Keydown { key, captured, .. } if self.is_focused(cx) && !self.rows.is_empty() => {
+                let last = self.rows.len() - 1;
+                match key {
+                    Key::ArrowUp => self.select(cx, self.selected.map_or(last, |i| i.saturating_sub(1))),
+                    Key::ArrowDown => self.select(cx, self.selected.map_or(0, |i| (i + 1).min(last))),
+                    Key::Home => self.select(cx, 0),
+                    Key::End => self.select(cx, last),
+                    Key::ArrowRight if self.view == FileBrowserView::Tree => {
+                        if let Some(index) = self.selected
+                            && self.rows[index].entry.is_dir
+                        {
+                            self.set_expanded(cx, index, true);
+                        }
+                    }
+                    Key::ArrowLeft | Key::Backspace => self.go_to_parent(cx),
+                    Key::Enter => {
+                        if let Some(index) = self.selected {
+                            self.open(cx, index);
+                        }
+                    }
+                    _ => return,
+                }
+                *captured = true;
+            }
+            Event::FileChanged(path)
+                if self.is_listed(path) || path.parent().is_some_and(|parent| self.is_listed(parent)) =>
+            {
+                self.refresh(cx);
+            }
+            _ => {}
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        self.set_bounds(cx, bounds);
+        self.scroll_to(cx, self.scroll_top);
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
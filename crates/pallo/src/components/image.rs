@@ -0,0 +1,312 @@
+use std::rc::Rc;
+
+use crate::*;
+
+/// How an image is sized to the bounds of an `ImageView`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ImageFit {
+    /// Scaled to fit entirely inside the bounds, keeping its aspect ratio.
+    Contain,
+    /// Scaled to cover the bounds, keeping its aspect ratio and cropping what doesn't fit.
+    Cover,
+    /// Stretched to the bounds.
+    Fill,
+    /// Like `Contain`, but never scaled up.
+    ScaleDown,
+}
+
+impl ImageFit {
+    /// Where an image of `size` is drawn within `bounds`.
+    pub fn apply(self, size: Point, bounds: Rect, align_x: Align, align_y: Align) -> Rect {
+        if size.x <= 0.0 || size.y <= 0.0 {
+            return bounds;
+        }
+        let contain = (bounds.width() / size.x).min(bounds.height() / size.y);
+        let scale = match self {
+            ImageFit::Contain => contain,
+            ImageFit::Cover => (bounds.width() / size.x).max(bounds.height() / size.y),
+            ImageFit::Fill => return bounds,
+            ImageFit::ScaleDown => contain.min(1.0),
+        };
+        Rect::from_size(size.x * scale, size.y * scale)
+            .x_aligned_within(bounds, align_x)
+            .y_aligned_within(bounds, align_y)
+    }
+}
+
+pub type ImagePlaceholderDrawer = fn(&mut Canvas, Rect);
+
+/// Shows an image from a `File` or encoded bytes, decoded through `Cx::decode_image`. Animated images are decoded
+/// through `Cx::decode_animated_image` and start playing once loaded.
+pub struct ImageView {
+    pub id: ComponentId,
+    image: Option<Rc<Image>>,
+    animated_image: Option<Rc<AnimatedImage>>,
+    /// Counts the playback time while playing.
+    clock: Option<AnimationId>,
+    /// The playback time while paused.
+    paused_at_ms: f32,
+    looping: bool,
+    load: Option<FileLoad>,
+    failed: bool,
+    fit: ImageFit,
+    align_x: Align,
+    align_y: Align,
+    radius: BorderRadius,
+    draw_placeholder: Option<ImagePlaceholderDrawer>,
+}
+
+impl ImageView {
+    pub fn new(id: ComponentId) -> Self {
+        Self {
+            id,
+            image: None,
+            animated_image: None,
+            clock: None,
+            paused_at_ms: 0.0,
+            looping: true,
+            load: None,
+            failed: false,
+            fit: ImageFit::Contain,
+            align_x: Align::Center,
+            align_y: Align::Center,
+            radius: BorderRadius::default(),
+            draw_placeholder: None,
+        }
+    }
+
+    pub fn with_fit(mut self, fit: ImageFit) -> Self {
+        self.fit = fit;
+        self
+    }
+
+    pub fn with_alignment(mut self, align_x: Align, align_y: Align) -> Self {
+        self.align_x = align_x;
+        self.align_y = align_y;
+        self
+    }
+
+    /// Rounds the corners of the visible part of the image.
+    pub fn with_corner_radius(mut self, radius: impl Into<BorderRadius>) -> Self {
+        self.radius = radius.into();
+        self
+    }
+
+    /// Whether animated images start over after the last frame. If not, playback stops on the last frame.
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Drawn while the image is loading or if it couldn't be loaded, see `is_failed`.
+    pub fn with_placeholder(mut self, draw_placeholder: ImagePlaceholderDrawer) -> Self {
+        self.draw_placeholder = Some(draw_placeholder);
+        self
+    }
+
+    /// Loads the image in `file` in the background.
+    pub fn set_file<A: App>(&mut self, cx: &mut Cx<A>, file: &File) {
+        self.clear();
+        self.failed = false;
+        self.load = Some(cx.load_file(file));
+        self.poll_load(cx);
+    }
+
+    pub fn set_data<A: App>(&mut self, cx: &mut Cx<A>, data: &[u8]) {
+        cx.invalidate(&self.id);
+        if let Some(animated_image) = cx.decode_animated_image(data) {
+            self.set_animated_image(cx, animated_image);
+            return;
+        }
+        self.clear();
+        self.image = cx.decode_image(data);
+        self.failed = self.image.is_none();
+    }
+
+    pub fn set_image(&mut self, image: Rc<Image>) {
+        self.clear();
+        self.image = Some(image);
+    }
+
+    /// Shows `animated_image` and starts playing it from the first frame.
+    pub fn set_animated_image<A: App>(&mut self, cx: &mut Cx<A>, animated_image: Rc<AnimatedImage>) {
+        self.clear();
+        self.animated_image = Some(animated_image);
+        self.play(cx);
+    }
+
+    /// The image being shown, if it's a still image.
+    pub fn image(&self) -> Option<&Rc<Image>> {
+        self.image.as_ref()
+    }
+
+    pub fn animated_image(&self) -> Option<&Rc<AnimatedImage>> {
+        self.animated_image.as_ref()
+    }
+
+    /// Resumes playing an animated image. If it doesn't loop and has ended, it's played again from the start.
+    pub fn play<A: App>(&mut self, cx: &mut Cx<A>) {
+        let Some(animated_image) = &self.animated_image else {
+            return;
+        };
+        if self.clock.is_some() {
+            return;
+        }
+        if !self.looping && self.paused_at_ms >= animated_image.duration_ms() {
+            self.paused_at_ms = 0.0;
+        }
+        let clock = cx.animations.add_clock();
+        cx.animations.set(&clock, self.paused_at_ms);
+        self.clock = Some(clock);
+    }
+
+    /// Stops an animated image on its current frame.
+    pub fn pause<A: App>(&mut self, cx: &mut Cx<A>) {
+        self.paused_at_ms = self.playback_time_ms(cx);
+        self.clock = None;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.clock.is_some()
+    }
+
+    fn clear(&mut self) {
+        self.load = None;
+        self.failed = false;
+        self.image = None;
+        self.animated_image = None;
+        self.clock = None;
+        self.paused_at_ms = 0.0;
+    }
+
+    fn playback_time_ms<A: App>(&self, cx: &mut Cx<A>) -> f32 {
+        match &self.clock {
+            Some(clock) => cx.animations.get(clock),
+            None => self.paused_at_ms,
+        }
+    }
+
+    fn current_image<A: App>(&self, cx: &mut Cx<A>) -> Option<&Image> {
+        let Some(animated_image) = &self.animated_image else {
+            return self.image.as_deref();
+        };
+        let time_ms = self.playback_time_ms(cx);
+        if !self.looping && time_ms >= animated_image.duration_ms() {
+            return animated_image.frames().last().map(|frame| &frame.image);
+        }
+        Some(animated_image.frame_at(time_ms))
+    }
+
+    /// Whether the file couldn't be read or decoded.
+    pub fn is_failed(&self) -> bool {
+        self.failed
+    }
+
+    fn poll_load<A: App>(&mut self, cx: &mut Cx<A>) {
+        let Some(load) = &self.load else {
+            return;
+        };
+        if let Some(data) = load.result.value() {
+            self.load = None;
+            self.set_data(cx, &data);
+        } else if load.failed.get_fast() {
+            self.load = None;
+            self.failed = true;
+            cx.invalidate(&self.id);
+        }
+    }
+}
+
+impl<A: App> Component<A> for ImageView {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let bounds = self.get_bounds(cx);
+        let Some(image) = self.current_image(cx) else {
+            if let Some(draw_placeholder) = self.draw_placeholder {
+                draw_placeholder(canvas, bounds);
+            }
+            return;
+        };
+
+        let dest = self.fit.apply(image.get_bounds().size(), bounds, self.align_x, self.align_y);
+        let visible = dest.intersection(bounds).unwrap_or(dest);
+        if self.radius == BorderRadius::default() {
+            canvas.with_clip_rect(visible, |canvas| {
+                canvas.draw_image(image, dest);
+            });
+        } else {
+            canvas.with_clip_rrect(visible, self.radius, ClipOp::Intersect, |canvas| {
+                canvas.draw_image(image, dest);
+            });
+        }
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        if let Event::Update = event {
+            self.poll_load(cx);
+            if let Some(animated_image) = &self.animated_image
+                && !self.looping
+                && self.playback_time_ms(cx) >= animated_image.duration_ms()
+            {
+                self.pause(cx);
+            }
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        self.set_bounds(cx, bounds);
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: Rect = Rect { a: Point { x: 0.0, y: 0.0 }, b: Point { x: 200.0, y: 100.0 } };
+
+    #[test]
+    fn contain_and_cover_keep_the_aspect_ratio() {
+        let size = point(50.0, 50.0);
+        assert_eq!(
+            ImageFit::Contain.apply(size, BOUNDS, Align::Center, Align::Center),
+            Rect::from_xywh(50.0, 0.0, 100.0, 100.0)
+        );
+        assert_eq!(
+            ImageFit::Contain.apply(size, BOUNDS, Align::Start, Align::Start),
+            Rect::from_xywh(0.0, 0.0, 100.0, 100.0)
+        );
+        assert_eq!(
+            ImageFit::Cover.apply(size, BOUNDS, Align::Center, Align::Center),
+            Rect::from_xywh(0.0, -50.0, 200.0, 200.0)
+        );
+        assert_eq!(
+            ImageFit::Cover.apply(size, BOUNDS, Align::End, Align::End),
+            Rect::from_xywh(0.0, -100.0, 200.0, 200.0)
+        );
+    }
+
+    #[test]
+    fn fill_stretches_and_scale_down_never_enlarges() {
+        let small = point(20.0, 10.0);
+        assert_eq!(ImageFit::Fill.apply(small, BOUNDS, Align::Center, Align::Center), BOUNDS);
+        assert_eq!(
+            ImageFit::ScaleDown.apply(small, BOUNDS, Align::Start, Align::End),
+            Rect::from_xywh(0.0, 90.0, 20.0, 10.0)
+        );
+        let large = point(400.0, 100.0);
+        assert_eq!(
+            ImageFit::ScaleDown.apply(large, BOUNDS, Align::Start, Align::Start),
+            Rect::from_xywh(0.0, 0.0, 200.0, 50.0)
+        );
+    }
+
+    #[test]
+    fn empty_images_fill_the_bounds() {
+        for fit in [ImageFit::Contain, ImageFit::Cover, ImageFit::ScaleDown] {
+            assert_eq!(fit.apply(point(0.0, 10.0), BOUNDS, Align::Center, Align::Center), BOUNDS);
+        }
+    }
+}
@@ -1,7 +1,10 @@
+use std::{cell::RefCell, rc::Rc};
+
 use web_time::Instant;
 
 use crate::{
-    platform::{Clipboard, InputType, PlatformCommon},
+    font_cache::TextBlobCache,
+    platform::{Clipboard, EditAction, EditMenuOptions, InputType, PlatformCommon, TextFieldEvent, TextFieldOptions},
     *,
 };
 
@@ -22,13 +25,15 @@ impl Default for TextBuilder {
 
 impl TextBuilder {
     pub fn build<A: App>(mut self, cx: &mut Cx<A>) -> Text {
-        if !self.variables.iter().any(|v| v.get_axis() == "wght") {
-            self.variables.push(FontVariable::new("wght", A::default_font_weight()));
-        }
+        add_default_weight::<A>(&mut self.variables);
+        let cached_font = cx.cached_font(self.typeface, self.font_size, self.variables);
         let mut text = Text {
             blob: None,
-            font: cx.backend.create_font(self.typeface, self.font_size, self.variables),
+            font: cached_font.font.clone(),
+            blobs: cached_font.blobs.clone(),
+            glyph_widths: Default::default(),
             text: self.text,
+            font_size: self.font_size,
             color: self.color,
         };
         text.set_text(text.text.clone());
@@ -61,17 +66,65 @@ impl TextBuilder {
     }
 }
 
+fn add_default_weight<A: App>(variables: &mut Vec<FontVariable>) {
+    if !variables.iter().any(|v| v.get_axis() == "wght") {
+        variables.push(FontVariable::new("wght", A::default_font_weight()));
+    }
+}
+
+/// The size of a string in a given font, see `Cx::measure_text`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextMetrics {
+    pub width: f32,
+    pub cap_height: f32,
+    /// The distance from the baseline to the top of the tallest glyphs.
+    pub ascent: f32,
+    /// The distance from the baseline to the bottom of the lowest glyphs.
+    pub descent: f32,
+    /// The recommended distance between the baselines of consecutive lines.
+    pub line_height: f32,
+}
+
+impl<A: App> Cx<A> {
+    /// Measures `text` as a `Text` built with the same typeface, size and variations would measure it, without
+    /// creating one. Like `TextBuilder::build`, the app's default weight is used unless a "wght" variation is given.
+    pub fn measure_text(
+        &mut self,
+        typeface: impl Into<usize>,
+        font_size: f32,
+        variables: &[FontVariable],
+        text: &str,
+    ) -> TextMetrics {
+        let mut variables = variables.to_vec();
+        add_default_weight::<A>(&mut variables);
+        let font = &self.cached_font(typeface.into(), font_size, variables).font;
+        TextMetrics {
+            width: font.get_string_width(text),
+            cap_height: font.get_cap_height(),
+            ascent: font.get_ascent(),
+            descent: font.get_descent(),
+            line_height: font.get_line_height(),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Text {
     font: Font,
     blob: Option<TextBlob>,
+    /// Shared by all `Text`s with the same style, so that labels showing the same strings share their blobs.
+    blobs: Rc<RefCell<TextBlobCache>>,
+    /// The widths last returned by `get_glyph_widths` and the text they were measured for.
+    glyph_widths: RefCell<Option<(String, Vec<f32>)>>,
     text: String,
+    font_size: f32,
     color: Color,
 }
 
 impl Text {
     pub fn set_text(&mut self, text: String) -> &mut Self {
         self.text = text;
-        self.blob = TextBlob::new(self.text.clone(), &self.font);
+        self.blob = self.blobs.borrow_mut().get(&self.font, &self.text);
         self
     }
 
@@ -80,7 +133,15 @@ impl Text {
     }
 
     fn get_glyph_widths(&self, text: &String) -> Vec<f32> {
-        self.font.get_glyph_widths(text)
+        let mut glyph_widths = self.glyph_widths.borrow_mut();
+        match &*glyph_widths {
+            Some((measured_text, widths)) if measured_text == text => widths.clone(),
+            _ => {
+                let widths = self.font.get_glyph_widths(text);
+                *glyph_widths = Some((text.clone(), widths.clone()));
+                widths
+            }
+        }
     }
 
     pub fn get_width(&self) -> f32 {
@@ -91,6 +152,14 @@ impl Text {
         self.color = color;
     }
 
+    pub fn get_color(&self) -> Color {
+        self.color
+    }
+
+    pub fn get_font_size(&self) -> f32 {
+        self.font_size
+    }
+
     pub fn get_cap_height(&self) -> f32 {
         self.font.get_cap_height()
     }
@@ -126,8 +195,11 @@ impl Text {
 
         rows.into_iter()
             .map(|text| Text {
-                blob: TextBlob::new(text.clone(), &self.font),
+                blob: self.blobs.borrow_mut().get(&self.font, &text),
                 font: self.font.clone(),
+                blobs: self.blobs.clone(),
+                glyph_widths: Default::default(),
+                font_size: self.font_size,
                 color: self.color,
                 text,
             })
@@ -276,6 +348,27 @@ pub const NAME_INPUT_CHAR_MAPPER: CharMapper = |_text, char, _caret| {
     None
 };
 
+/// Checks an edited value before it's committed. The error message is shown to the user.
+pub type Validator = fn(&str) -> Result<(), String>;
+
+/// Draws the error state of a `TextInput` with the input's bounds and the validation message.
+pub type ValidationErrorDrawer = fn(&mut Canvas, Rect, &Text);
+
+/// Outlines the input in red and shows the message in a box below it.
+pub fn draw_default_validation_error(canvas: &mut Canvas, bounds: Rect, message: &Text) {
+    let color = rgb(0xff3b30);
+    canvas.stroke(color, 1.0).draw_rect(bounds.rounded());
+    let padding = 4.0;
+    let tooltip = Rect::from_xywh(
+        bounds.left(),
+        bounds.bottom() + 2.0,
+        message.get_width() + padding * 2.0,
+        message.get_cap_height() + padding * 2.0,
+    );
+    canvas.fill(color.with_alpha(0.9)).draw_rect(tooltip);
+    message.draw(canvas, tooltip.with_margin(Margin::even(padding)));
+}
+
 pub struct TextInput {
     pub label: Label,
     caret_index: i32,
@@ -289,9 +382,22 @@ pub struct TextInput {
     map_char: CharMapper,
     x_scroll_offset: f32,
     prompt_value: Later<String>,
+    paste_value: Later<String>,
     is_editable: bool,
     readonly: bool,
     input_type: InputType,
+    native_text_field: bool,
+    /// The bounds of the open native text field and where its events go.
+    text_field: Option<(Rect, Later<TextFieldEvent>)>,
+    edit_menu: Option<Overlay<PopupMenu<EditAction>>>,
+    /// The action chosen from the platform's own edit menu.
+    edit_menu_action: Later<EditAction>,
+    masked: Signal<bool>,
+    source_text: Option<Computed<String>>,
+    validator: Option<Validator>,
+    is_invalid: Signal<bool>,
+    error_message: Text,
+    draw_error: ValidationErrorDrawer,
 }
 
 impl TextInput {
@@ -301,6 +407,7 @@ impl TextInput {
 
     pub fn new_with_builder<A: App>(cx: &mut Cx<A>, id: ComponentId, builder: TextBuilder) -> Self {
         cx.set_interactive(&id, true);
+        let error_message = builder.clone().text("").color(rgb(0xffffff)).build(cx);
         Self {
             label: Label::new_with_builder(cx, id, builder),
             caret_index: 0,
@@ -314,25 +421,52 @@ impl TextInput {
             map_char: NUMBER_INPUT_CHAR_MAPPER,
             x_scroll_offset: 0.0,
             prompt_value: Default::default(),
+            paste_value: Default::default(),
             is_editable: true,
             readonly: false,
             input_type: InputType::Text,
+            native_text_field: false,
+            text_field: None,
+            edit_menu: None,
+            edit_menu_action: Default::default(),
+            masked: cx.signal_default(),
+            source_text: None,
+            validator: None,
+            is_invalid: cx.signal_default(),
+            error_message,
+            draw_error: draw_default_validation_error,
         }
     }
 
     pub fn set_text(&mut self, text: impl Into<Computed<String>>) -> &mut Self {
         let editing = self.is_editing.clone();
         let edited_text = self.edited_text.clone();
+        let masked = self.masked.clone();
         let text: Computed<String> = text.into();
         self.edited_text.set(text.get());
-        self.label.set_text(editing.cx().computed(move || if editing.get() { edited_text.get() } else { text.get() }));
+        self.source_text = Some(text.clone());
+        self.label.set_text(editing.cx().computed(move || {
+            let text = if editing.get() { edited_text.get() } else { text.get() };
+            if masked.get() { mask_text(&text) } else { text }
+        }));
         self
     }
 
+    /// The displayed text, which is masked when `with_masked` is on. The value itself is in `edited_text`.
     pub fn get_text(&self) -> &String {
         self.label.text.get_text()
     }
 
+    /// Shows a bullet for every character, e.g. for passwords. A masked value can't be copied to the clipboard.
+    pub fn set_masked(&mut self, masked: bool) {
+        self.masked.set(masked);
+    }
+
+    pub fn with_masked(mut self, masked: bool) -> Self {
+        self.set_masked(masked);
+        self
+    }
+
     pub fn with_text(mut self, text: impl Into<Computed<String>>) -> Self {
         self.set_text(text);
         self
@@ -357,32 +491,76 @@ impl TextInput {
         self
     }
 
+    /// Edits in a native text field placed over the input where the platform has one, i.e. on iOS and the web,
+    /// for native autocorrect, selection handles and emoji. The field draws the text while it's open.
+    pub fn with_native_text_field(mut self, native_text_field: bool) -> Self {
+        self.native_text_field = native_text_field;
+        self
+    }
+
     pub fn with_char_mapper(mut self, mapper: CharMapper) -> Self {
         self.map_char = mapper;
         self
     }
 
-    fn get_cursor_x(&self, index: i32) -> f32 {
-        self.label.text.get_glyph_widths(&self.edited_text.get_fast())[0..index as usize].iter().sum()
+    /// Runs `validator` when an edit is completed. Invalid values aren't committed: pressing enter keeps the input in
+    /// edit mode with the error shown, and losing focus throws the edit away.
+    pub fn with_validator(mut self, validator: Validator) -> Self {
+        self.validator = Some(validator);
+        self
     }
 
-    fn get_cursor_index(&self, position: f32) -> i32 {
-        let text = self.edited_text.get_fast();
-        let mut min_distance = f32::MAX;
-        let mut min_distance_index = 0;
-        let mut p = 0.0;
-        for (i, width) in self.label.text.get_glyph_widths(&text).iter().enumerate() {
-            let dist = (p - position).abs();
-            if dist < min_distance {
-                min_distance = dist;
-                min_distance_index = i;
-            }
-            p += width;
+    pub fn with_error_drawer(mut self, draw_error: ValidationErrorDrawer) -> Self {
+        self.draw_error = draw_error;
+        self
+    }
+
+    /// True while the last completed edit failed validation.
+    pub fn is_invalid(&self) -> Signal<bool> {
+        self.is_invalid.clone()
+    }
+
+    pub fn get_error_message(&self) -> Option<&String> {
+        self.is_invalid.get_fast().then(|| self.error_message.get_text())
+    }
+
+    /// Ends editing and returns `value` if it passes validation.
+    fn commit(&mut self, value: String) -> Option<String> {
+        if let Some(validator) = self.validator
+            && let Err(message) = validator(&value)
+        {
+            self.error_message.set_text(message);
+            self.is_invalid.set(true);
+            return None;
         }
-        if (p - position).abs() < min_distance {
-            min_distance_index = text.len();
+        self.is_invalid.set(false);
+        self.is_editing.set(false);
+        Some(value)
+    }
+
+    fn get_grapheme_positions(&self) -> Vec<(usize, f32)> {
+        let text = self.edited_text.get_fast();
+        if self.masked.get_fast() {
+            let mask_width = self.label.text.get_glyph_widths(&MASK_CHAR.to_string()).first().copied().unwrap_or(0.0);
+            grapheme_positions(&text, &masked_glyph_widths(&text, mask_width))
+        } else {
+            grapheme_positions(&text, &self.label.text.get_glyph_widths(&text))
         }
-        min_distance_index as i32
+    }
+
+    fn get_cursor_x(&self, index: i32) -> f32 {
+        self.get_grapheme_positions()
+            .iter()
+            .take_while(|(offset, _)| *offset <= index as usize)
+            .last()
+            .map_or(0.0, |(_, x)| *x)
+    }
+
+    fn get_cursor_index(&self, position: f32) -> i32 {
+        self.get_grapheme_positions()
+            .into_iter()
+            .min_by(|(_, a), (_, b)| (a - position).abs().total_cmp(&(b - position).abs()))
+            .map_or(0, |(offset, _)| offset as i32)
     }
 
     pub fn get_text_width(&self) -> f32 {
@@ -410,15 +588,47 @@ impl TextInput {
         self.anchor_position = self.get_cursor_x(self.anchor_index);
     }
 
+    /// Clamps a byte offset to the edited text and snaps it to a grapheme boundary.
+    fn clamp_index(&self, index: i32) -> i32 {
+        floor_grapheme_boundary(&self.edited_text.get_fast(), index.max(0) as usize) as i32
+    }
+
     fn move_caret(&mut self, position: i32, move_anchor: bool) {
         self.caret_animation_counter = 0.0;
-        self.caret_index = position.clamp(0, self.edited_text.get_fast().len() as i32);
+        self.caret_index = self.clamp_index(position);
         if move_anchor {
             self.anchor_index = self.caret_index;
         }
         self.update_caret_positions();
     }
 
+    fn selection_range(&self) -> (i32, i32) {
+        (self.anchor_index.min(self.caret_index), self.anchor_index.max(self.caret_index))
+    }
+
+    /// Deletes from the caret to `index`, or the selection if there is one.
+    fn delete_to(&mut self, index: i32) {
+        if self.readonly {
+            return;
+        }
+        if self.anchor_index == self.caret_index {
+            self.anchor_index = self.clamp_index(index);
+        }
+        self.remove_selected_text();
+    }
+
+    /// Replaces the selection with `text`, leaving the caret after it. Line breaks and other control characters are
+    /// dropped since the input is a single line.
+    fn insert_text(&mut self, text: &str) {
+        if self.anchor_index != self.caret_index {
+            self.remove_selected_text();
+        }
+        let text: String = text.chars().filter(|c| !c.is_control()).collect();
+        let index = self.caret_index;
+        self.edited_text.mutate(|mut edited| edited.insert_str(index as usize, &text));
+        self.move_caret(index + text.len() as i32, true);
+    }
+
     fn remove_selected_text(&mut self) {
         let start = self.anchor_index.min(self.caret_index);
         let end = self.anchor_index.max(self.caret_index);
@@ -439,16 +649,24 @@ impl TextInput {
         if self.is_editable {
             self.start_edit_time = Instant::now();
             self.is_editing.set(true);
-            self.edited_text.set(self.label.text.get_text().clone());
+            self.is_invalid.set(false);
+            let text = match &self.source_text {
+                Some(text) => text.get(),
+                None => self.label.text.get_text().clone(),
+            };
+            self.edited_text.set(text);
+            if self.native_text_field {
+                self.open_text_field(cx);
+            }
             #[allow(unused)]
             let val = self.prompt_value.clone();
             #[cfg(target_os = "ios")]
-            {
+            if self.text_field.is_none() {
                 cx.platform.open_prompt(
                     "Edit value".into(),
                     "Enter".into(),
                     self.edited_text.get(),
-                    self.input_type,
+                    self.get_effective_input_type(),
                     &self.prompt_value,
                 );
             }
@@ -460,6 +678,175 @@ impl TextInput {
         self.is_editing.set(false);
     }
 
+    fn get_effective_input_type(&self) -> InputType {
+        if self.masked.get_fast() {
+            InputType::Password
+        } else {
+            self.input_type
+        }
+    }
+
+    /// The input's bounds in the window, which is what the platform places text fields in.
+    fn get_text_field_bounds<A: App>(&self, cx: &Cx<A>) -> Rect {
+        let bounds = self.label.get_bounds(cx);
+        let id = &self.label.id;
+        Rect::from_ab(cx.to_global(id, bounds.a), cx.to_global(id, bounds.b)).with_scale(cx.ui_scale)
+    }
+
+    fn open_text_field<A: App>(&mut self, cx: &mut Cx<A>) {
+        let bounds = self.get_text_field_bounds(cx);
+        let events = Later::default();
+        let options = TextFieldOptions {
+            bounds,
+            value: self.edited_text.get_fast(),
+            input_type: self.get_effective_input_type(),
+            font_size: self.label.text.get_font_size() * cx.ui_scale,
+            color: self.label.text.get_color(),
+            events: events.clone(),
+        };
+        if cx.platform.open_text_field(options) {
+            self.text_field = Some((bounds, events));
+        }
+    }
+
+    fn close_text_field<A: App>(&mut self, cx: &mut Cx<A>) {
+        if self.text_field.take().is_some() {
+            cx.platform.close_text_field();
+        }
+    }
+
+    /// Follows the native text field's edits and keeps it over the input.
+    fn sync_text_field<A: App>(&mut self, cx: &mut Cx<A>) -> Option<String> {
+        if !self.is_editing.get_fast() {
+            self.close_text_field(cx);
+            return None;
+        }
+        let bounds = self.get_text_field_bounds(cx);
+        let (last_bounds, events) = self.text_field.as_mut()?;
+        let event = events.value();
+        if *last_bounds != bounds {
+            *last_bounds = bounds;
+            cx.platform.move_text_field(bounds);
+        }
+        match event? {
+            TextFieldEvent::Changed(text) => {
+                let len = text.len() as i32;
+                self.edited_text.set(text);
+                self.move_caret(len, true);
+                None
+            }
+            TextFieldEvent::Submitted(text) => {
+                self.edited_text.set(text.clone());
+                // an invalid value stays in the field with the error shown
+                let value = self.commit(text);
+                if value.is_some() {
+                    self.close_text_field(cx);
+                }
+                value
+            }
+            TextFieldEvent::Closed(text) => {
+                let value = self.commit(text);
+                // like losing focus, an invalid edit is dropped
+                self.is_editing.set(false);
+                self.is_invalid.set(false);
+                self.close_text_field(cx);
+                value
+            }
+        }
+    }
+
+    /// The edit actions that can be performed right now.
+    fn get_edit_actions(&self) -> Vec<EditAction> {
+        let can_copy = self.anchor_index != self.caret_index && !self.masked.get_fast();
+        EditAction::ALL
+            .into_iter()
+            .filter(|action| match action {
+                EditAction::Cut => can_copy && !self.readonly,
+                EditAction::Copy => can_copy,
+                EditAction::Paste => !self.readonly,
+                EditAction::SelectAll => !self.edited_text.get_fast().is_empty(),
+            })
+            .collect()
+    }
+
+    /// Shows the platform's edit menu, or a `PopupMenu` at `position` where there is none.
+    fn open_edit_menu<A: App>(&mut self, cx: &mut Cx<A>, position: Point) {
+        self.close_edit_menu(cx);
+        let actions = self.get_edit_actions();
+        let options = EditMenuOptions {
+            bounds: self.get_text_field_bounds(cx),
+            actions: actions.clone(),
+            events: self.edit_menu_action.clone(),
+        };
+        if cx.platform.show_edit_menu(options) {
+            return;
+        }
+        let text = &self.label.text;
+        self.edit_menu = Some(PopupMenu::open(cx, position, |cx, id| {
+            EditAction::ALL.into_iter().fold(PopupMenu::new(cx, id), |menu, action| {
+                let mut label = text.clone();
+                label.set_text(action.label().into());
+                menu.with_item(label, action, actions.contains(&action))
+            })
+        }));
+    }
+
+    fn close_edit_menu<A: App>(&mut self, cx: &mut Cx<A>) {
+        if let Some(menu) = self.edit_menu.take() {
+            cx.close_overlay(&menu);
+        }
+    }
+
+    fn apply_edit_action<A: App>(&mut self, cx: &mut Cx<A>, action: EditAction) {
+        match action {
+            EditAction::Cut => {
+                if !self.readonly && self.copy_selection(cx) {
+                    self.remove_selected_text();
+                }
+            }
+            EditAction::Copy => {
+                self.copy_selection(cx);
+            }
+            EditAction::Paste => {
+                self.paste(cx);
+            }
+            EditAction::SelectAll => self.select_all(),
+        }
+    }
+
+    /// Copies the selection to the clipboard unless the value is masked. Returns whether it was copied.
+    fn copy_selection<A: App>(&self, cx: &mut Cx<A>) -> bool {
+        if self.masked.get_fast() {
+            return false;
+        }
+        let (start, end) = self.selection_range();
+        let text = (&self.edited_text.get_fast())[start as usize..end as usize].to_owned();
+        if let Err(error) = cx.platform.clipboard().write_string(text) {
+            cx.report_error(error);
+            return false;
+        }
+        true
+    }
+
+    /// Replaces the selection with the clipboard's text. Returns false if there was nothing to paste.
+    fn paste<A: App>(&mut self, cx: &mut Cx<A>) -> bool {
+        if self.readonly {
+            return false;
+        }
+        // the clipboard can only be read asynchronously on the web, the text is inserted once it arrives
+        if cfg!(target_family = "wasm") {
+            cx.platform.clipboard().read_string_async(&self.paste_value);
+            return true;
+        }
+        match cx.platform.clipboard().read_string() {
+            Some(text) => {
+                self.insert_text(&text);
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn start_edit_with_text<A: App>(&mut self, cx: &mut Cx<A>, text: impl Into<String>) {
         self.start_edit(cx);
         let text: String = text.into();
@@ -487,8 +874,9 @@ impl TextInput {
         self.update_caret_positions();
     }
 
+    /// Places the caret at byte offset `pos`, snapped back to the nearest grapheme boundary.
     pub fn set_cursor_position(&mut self, pos: i32) {
-        self.anchor_index = pos.clamp(0, self.edited_text.get_fast().len() as i32);
+        self.anchor_index = self.clamp_index(pos);
         self.caret_index = self.anchor_index;
         self.update_caret_positions();
     }
@@ -498,6 +886,10 @@ impl TextInput {
         match event {
             Event::Update => {
                 self.caret_animation_counter += cx.frame_delta_ms * 0.01;
+                if self.is_editing() {
+                    // the caret blinks
+                    cx.invalidate(&self.label.id);
+                }
 
                 let safe_margin = 2.0;
 
@@ -516,35 +908,89 @@ impl TextInput {
                     self.x_scroll_offset = min_scroll_offset.max(self.x_scroll_offset);
                 }
 
+                if self.text_field.is_some()
+                    && let Some(value) = self.sync_text_field(cx)
+                {
+                    return Some(value);
+                }
+
                 if let Some(val) = self.prompt_value.value() {
+                    let value = self.commit(val.to_string());
                     self.is_editing.set(false);
-                    return Some(val.to_string());
+                    return value;
+                }
+
+                if let Some(txt) = self.paste_value.value() {
+                    self.insert_text(&txt);
+                }
+
+                if let Some(action) = self.edit_menu_action.value() {
+                    self.apply_edit_action(cx, action);
+                }
+                if let Some(event) = self.edit_menu.as_ref().and_then(|menu| menu.borrow_mut().next_output()) {
+                    self.close_edit_menu(cx);
+                    match event {
+                        PopupMenuEvent::Selected(action) => {
+                            self.focus(cx);
+                            self.apply_edit_action(cx, action);
+                        }
+                        PopupMenuEvent::Dismissed => {
+                            if self.is_editing.get_fast() && !self.is_focused(cx) {
+                                self.is_editing.set(false);
+                                self.is_invalid.set(false);
+                            }
+                        }
+                    }
                 }
             }
             Event::FocusChanged(_) => {
                 if !self.is_editing.get_fast() && self.is_focused(cx) {
                     self.start_edit(cx);
-                } else if self.is_editing.get_fast() && !self.is_focused(cx) {
+                } else if self.is_editing.get_fast() && !self.is_focused(cx) && self.edit_menu.is_none() {
+                    // pressing the edit menu takes the focus, which is given back once an action is chosen
                     self.is_editing.set(false);
+                    self.is_invalid.set(false);
                 }
             }
+            Event::PointerDown(pointer) if pointer.button == MouseButton::Right && self.is_hovered(pointer) => {
+                if !self.is_editing.get_fast() {
+                    self.start_edit(cx);
+                }
+                if self.is_editing.get_fast() {
+                    self.open_edit_menu(cx, pointer.position);
+                }
+            }
+            Event::LongPress(pointer) if self.is_pressed(pointer) && self.is_editing.get_fast() => {
+                self.open_edit_menu(cx, pointer.position);
+            }
             Event::PointerDown(pointer) => {
                 if self.is_hovered(pointer) {
                     let time_since_edit_start = (Instant::now() - self.start_edit_time).as_millis();
                     if time_since_edit_start > 50 && self.is_editing.get_fast() {
-                        if cx.num_clicks.is_multiple_of(2) {
-                            self.select_all();
-                        } else {
+                        // the later presses of a multi-click keep the caret for the `Click` that selects
+                        if cx.num_clicks <= 1 {
                             let text_bounds = self.get_aligned_text_bounds(cx);
                             let x = pointer.position.x - text_bounds.left() - self.x_scroll_offset;
                             let cursor_index = self.get_cursor_index(x);
-                            self.move_caret(cursor_index, true);
+                            // shift+click extends the selection
+                            self.move_caret(cursor_index, !cx.mods.shift);
                         }
                     } else if !self.is_editing.get_fast() {
                         self.start_edit(cx);
                     }
                 }
             }
+            Event::Click { pointer, count } if self.is_pressed(pointer) && self.is_editing.get_fast() => {
+                // word boundaries would reveal the structure of a masked value
+                if *count >= 3 || (*count == 2 && self.masked.get_fast()) {
+                    self.select_all();
+                } else if *count == 2 {
+                    let (start, end) = word_range_at(&self.edited_text.get_fast(), self.caret_index as usize);
+                    self.anchor_index = start as i32;
+                    self.caret_index = end as i32;
+                    self.update_caret_positions();
+                }
+            }
             Event::PointerMove(pointer) => {
                 if self.is_pressed(pointer) {
                     let time_since_edit_start = (Instant::now() - self.start_edit_time).as_millis();
@@ -558,39 +1004,73 @@ impl TextInput {
             }
             Event::WindowFocusChanged(is_focused) => {
                 if !*is_focused && self.is_editing.get_fast() {
+                    let value = self.commit(self.edited_text.get_fast());
+                    // an invalid edit is dropped, there's no one to show the error to
                     self.is_editing.set(false);
-                    return Some(self.edited_text.get_fast());
+                    self.is_invalid.set(false);
+                    return value;
                 }
             }
-            Event::Keydown { key, captured } => {
-                if self.is_focused(cx) {
+            Event::Keydown { key, repeat, captured, .. } => {
+                // the menu takes the keys while it's open
+                if self.is_focused(cx) && self.edit_menu.is_none() {
+                    // word boundaries would reveal the structure of a masked value, so word jumps go to the ends
+                    let masked = self.masked.get_fast();
+                    let by_line = is_line_modifier(&cx.mods) || (masked && is_word_modifier(&cx.mods));
+                    let by_word = !by_line && is_word_modifier(&cx.mods);
                     match key {
                         Key::Enter => {
                             *captured = true;
+                            // holding enter would otherwise commit and start editing again on every repeat
+                            if *repeat {
+                                return None;
+                            }
                             if !self.is_editing.get_fast() {
                                 self.start_edit(cx);
                             } else {
-                                self.is_editing.set(false);
-                                return Some(self.edited_text.get_fast());
+                                return self.commit(self.edited_text.get_fast());
                             }
                         }
                         Key::Escape => {
                             self.is_editing.set(false);
+                            self.is_invalid.set(false);
                             *captured = true;
                         }
                         Key::ArrowLeft => {
-                            self.move_caret(if cx.mods.meta { 0 } else { self.caret_index - 1 }, !cx.mods.shift);
+                            let text = self.edited_text.get_fast();
+                            let index = if by_line {
+                                0
+                            } else if by_word {
+                                previous_word_boundary(&text, self.caret_index as usize) as i32
+                            } else if self.anchor_index != self.caret_index && !cx.mods.shift {
+                                // collapse the selection to its start
+                                self.selection_range().0
+                            } else {
+                                previous_grapheme_boundary(&text, self.caret_index as usize) as i32
+                            };
+                            self.move_caret(index, !cx.mods.shift);
                             *captured = true;
                         }
                         Key::ArrowRight => {
-                            self.move_caret(
-                                if cx.mods.meta {
-                                    self.get_text().len() as i32
-                                } else {
-                                    self.caret_index + 1
-                                },
-                                !cx.mods.shift,
-                            );
+                            let text = self.edited_text.get_fast();
+                            let index = if by_line {
+                                text.len() as i32
+                            } else if by_word {
+                                next_word_boundary(&text, self.caret_index as usize) as i32
+                            } else if self.anchor_index != self.caret_index && !cx.mods.shift {
+                                self.selection_range().1
+                            } else {
+                                next_grapheme_boundary(&text, self.caret_index as usize) as i32
+                            };
+                            self.move_caret(index, !cx.mods.shift);
+                            *captured = true;
+                        }
+                        Key::Home => {
+                            self.move_caret(0, !cx.mods.shift);
+                            *captured = true;
+                        }
+                        Key::End => {
+                            self.move_caret(self.edited_text.get_fast().len() as i32, !cx.mods.shift);
                             *captured = true;
                         }
                         Key::ArrowUp => {
@@ -602,40 +1082,49 @@ impl TextInput {
                             *captured = true;
                         }
                         Key::Backspace => {
-                            if self.anchor_index != self.caret_index {
-                                self.remove_selected_text();
-                            } else if self.caret_index > 0 && !self.readonly {
-                                let mut text = self.edited_text.get_fast();
-                                text.remove(self.caret_index as usize - 1);
-                                self.edited_text.set(text);
-                                self.move_caret(self.caret_index - 1, true);
-                            }
+                            let text = self.edited_text.get_fast();
+                            let index = if by_line {
+                                0
+                            } else if by_word {
+                                previous_word_boundary(&text, self.caret_index as usize) as i32
+                            } else {
+                                previous_grapheme_boundary(&text, self.caret_index as usize) as i32
+                            };
+                            self.delete_to(index);
+                            *captured = true;
+                        }
+                        Key::Delete => {
+                            let text = self.edited_text.get_fast();
+                            let index = if by_line {
+                                text.len() as i32
+                            } else if by_word {
+                                next_word_boundary(&text, self.caret_index as usize) as i32
+                            } else {
+                                next_grapheme_boundary(&text, self.caret_index as usize) as i32
+                            };
+                            self.delete_to(index);
                             *captured = true;
                         }
                         Key::Character(ch) => {
                             if ch == "v" && cx.mods.meta && !self.readonly {
-                                if let Some(txt) = cx.platform.clipboard().read_string() {
-                                    self.edited_text.set(txt);
+                                if self.paste(cx) {
                                     *captured = true;
                                 }
-                            } else if ch == "c" && cx.mods.meta {
-                                let start = self.caret_index.min(self.anchor_index) as usize;
-                                let end = self.caret_index.max(self.anchor_index) as usize;
-                                let text = (&self.edited_text.get_fast())[start..end].to_owned();
-                                cx.platform.clipboard().write_string(text);
+                            } else if ch == "c" && cx.mods.meta && !masked {
+                                self.copy_selection(cx);
+                            } else if ch == "x" && cx.mods.meta && !masked && !self.readonly {
+                                self.apply_edit_action(cx, EditAction::Cut);
+                                *captured = true;
+                            } else if ch == "a" && cx.mods.meta {
+                                self.select_all();
+                                *captured = true;
                             } else if !self.readonly {
                                 let text = self.edited_text.get_fast();
                                 if let Some(ch) = (self.map_char)(&text, ch, self.caret_index) {
                                     if !self.is_editing.get_fast() {
                                         self.start_edit(cx);
                                     }
-                                    if self.anchor_index != self.caret_index {
-                                        self.remove_selected_text();
-                                    }
-                                    let mut text = self.edited_text.get_fast();
-                                    text.insert_str(self.caret_index as usize, &ch);
-                                    self.edited_text.set(text);
-                                    self.move_caret(self.caret_index + 1, true);
+                                    self.insert_text(&ch);
                                     *captured = true;
                                 }
                             }
@@ -643,6 +1132,7 @@ impl TextInput {
                         _ => {}
                     }
                 } else if let Key::Enter = key
+                    && !*repeat
                     && self.is_focused(cx)
                     && !self.is_editing.get_fast()
                 {
@@ -661,6 +1151,10 @@ impl<A: App> Component<A> for TextInput {
             return;
         }
         canvas.with_clip_rect(self.label.get_bounds(cx), |canvas| {
+            // the native text field draws the text and the caret while it's open
+            if self.text_field.is_some() {
+                return;
+            }
             let text_bounds = self.get_aligned_text_bounds(cx);
             self.label.text.draw(canvas, text_bounds.with_x_offset(self.x_scroll_offset));
 
@@ -680,6 +1174,9 @@ impl<A: App> Component<A> for TextInput {
                     .draw_rect(caret_bounds.with_x_offset(caret_pos).with_width(0.0).rounded());
             }
         });
+        if self.is_invalid.get_fast() {
+            (self.draw_error)(canvas, self.label.get_bounds(cx), &self.error_message);
+        }
     }
 
     fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
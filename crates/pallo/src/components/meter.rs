@@ -0,0 +1,183 @@
+use crate::*;
+
+fn gain_to_db(gain: f32) -> f32 {
+    20.0 * gain.max(1e-6).log10()
+}
+
+/// A vertical peak/RMS level meter fed with samples from a `SampleSender`.
+pub struct Meter {
+    pub id: ComponentId,
+    receiver: SampleReceiver,
+    peak: f32,
+    rms: f32,
+    peak_hold: f32,
+    peak_hold_elapsed_ms: f32,
+    clipped: bool,
+    min_db: f32,
+    release_ms: f32,
+    rms_ms: f32,
+    peak_hold_ms: f32,
+    color: Computed<Color>,
+    background_color: Computed<Color>,
+    clip_color: Computed<Color>,
+}
+
+impl Meter {
+    pub fn new<A: App>(cx: &mut Cx<A>, id: ComponentId, receiver: SampleReceiver) -> Self {
+        id.set_hoverable(cx, true);
+        Self {
+            id,
+            receiver,
+            peak: 0.0,
+            rms: 0.0,
+            peak_hold: 0.0,
+            peak_hold_elapsed_ms: 0.0,
+            clipped: false,
+            min_db: -60.0,
+            release_ms: 300.0,
+            rms_ms: 300.0,
+            peak_hold_ms: 1500.0,
+            color: rgb(0x4cd964).into(),
+            background_color: rgb(0x000000).with_alpha(0.3).into(),
+            clip_color: rgb(0xff3b30).into(),
+        }
+    }
+
+    pub fn with_min_db(mut self, min_db: f32) -> Self {
+        self.min_db = min_db;
+        self
+    }
+
+    /// Sets how long the peak takes to fall back and how long the RMS is averaged over.
+    pub fn with_ballistics(mut self, release_ms: f32, rms_ms: f32) -> Self {
+        self.release_ms = release_ms;
+        self.rms_ms = rms_ms;
+        self
+    }
+
+    pub fn with_peak_hold(mut self, peak_hold_ms: f32) -> Self {
+        self.peak_hold_ms = peak_hold_ms;
+        self
+    }
+
+    pub fn set_color(&mut self, color: impl Into<Computed<Color>>) {
+        self.color = color.into();
+    }
+
+    pub fn with_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.set_color(color);
+        self
+    }
+
+    pub fn with_background_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.background_color = color.into();
+        self
+    }
+
+    pub fn with_clip_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.clip_color = color.into();
+        self
+    }
+
+    pub fn is_clipped(&self) -> bool {
+        self.clipped
+    }
+
+    pub fn reset_clip(&mut self) {
+        self.clipped = false;
+    }
+
+    pub fn peak_db(&self) -> f32 {
+        gain_to_db(self.peak)
+    }
+
+    pub fn rms_db(&self) -> f32 {
+        gain_to_db(self.rms)
+    }
+
+    fn db_to_ratio(&self, gain: f32) -> f32 {
+        (1.0 - gain_to_db(gain) / self.min_db).clamp(0.0, 1.0)
+    }
+
+    fn update<A: App>(&mut self, cx: &Cx<A>) {
+        let mut frame_peak = 0.0f32;
+        let mut sum_squares = 0.0;
+        let mut count = 0;
+        self.receiver.drain(|sample| {
+            frame_peak = frame_peak.max(sample.abs());
+            sum_squares += sample * sample;
+            count += 1;
+        });
+
+        if frame_peak >= 1.0 {
+            self.clipped = true;
+        }
+
+        // instant attack, exponential release
+        if frame_peak >= self.peak {
+            self.peak = frame_peak;
+        } else {
+            exp_decay(cx, &mut self.peak, self.release_ms, frame_peak);
+        }
+
+        if count > 0 {
+            let mut mean_square = self.rms * self.rms;
+            exp_decay(cx, &mut mean_square, self.rms_ms, sum_squares / count as f32);
+            self.rms = mean_square.sqrt();
+        } else {
+            exp_decay(cx, &mut self.rms, self.rms_ms, 0.0);
+        }
+
+        self.peak_hold_elapsed_ms += cx.frame_delta_ms;
+        if self.peak >= self.peak_hold || self.peak_hold_elapsed_ms > self.peak_hold_ms {
+            self.peak_hold = self.peak;
+            self.peak_hold_elapsed_ms = 0.0;
+        }
+    }
+}
+
+impl<A: App> Component<A> for Meter {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let mut bounds = self.get_bounds(cx);
+        let clip_bounds = bounds.remove_from(bounds.width().min(4.0), Side::Top);
+        canvas.fill(self.background_color.get()).draw_rect(bounds);
+
+        let color = self.color.get();
+        let height = bounds.height();
+        canvas
+            .fill(color.with_alpha(0.5))
+            .draw_rect(bounds.with_top(bounds.bottom() - height * self.db_to_ratio(self.peak)));
+        canvas.fill(color).draw_rect(bounds.with_top(bounds.bottom() - height * self.db_to_ratio(self.rms)));
+
+        if self.peak_hold > 0.0 {
+            let y = bounds.bottom() - height * self.db_to_ratio(self.peak_hold);
+            canvas.fill(color).draw_rect(bounds.with_top(y).with_height(1.0));
+        }
+
+        if self.clipped {
+            canvas.fill(self.clip_color.get()).draw_rect(clip_bounds);
+        }
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        match event {
+            Event::Update => {
+                let previous = (self.peak, self.rms, self.peak_hold, self.clipped);
+                self.update(cx);
+                if (self.peak, self.rms, self.peak_hold, self.clipped) != previous {
+                    cx.invalidate(&self.id);
+                }
+            }
+            Event::PointerDown(pointer) if self.id.is_hovered(pointer) => self.reset_clip(),
+            _ => {}
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        self.set_bounds(cx, bounds);
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
@@ -1,3 +1,21 @@
+pub mod badge;
+pub mod breadcrumbs;
+pub mod chip;
+pub mod file_browser;
+pub mod image;
 pub mod label;
+pub mod meter;
+pub mod node_graph;
+pub mod number_input;
 pub mod paragraph;
+pub mod popup_menu;
+pub mod portal;
+pub mod progress_ring;
+pub mod scope;
 pub mod scroll;
+pub mod step_grid;
+pub mod table;
+pub mod tree_view;
+pub mod viewport;
+pub mod waveform;
+pub mod xy_pad;
@@ -0,0 +1,529 @@
+use crate::*;
+
+const NODE_WIDTH: f32 = 140.0;
+const HEADER_HEIGHT: f32 = 24.0;
+const PORT_SPACING: f32 = 20.0;
+const PORT_RADIUS: f32 = 4.5;
+/// How close to a port the pointer has to be to hover it, in content coordinates.
+const PORT_HIT_RADIUS: f32 = 9.0;
+/// How far from the origin nodes can be placed and still be hit-tested.
+const CANVAS_EXTENT: f32 = 100_000.0;
+
+/// A node as given to `NodeGraph::add_node`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphNode<NodeId> {
+    pub id: NodeId,
+    pub title: String,
+    /// The top left corner of the node in the graph's coordinates.
+    pub position: Point,
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+}
+
+impl<NodeId> GraphNode<NodeId> {
+    pub fn new(id: NodeId, title: impl Into<String>, position: Point) -> Self {
+        Self { id, title: title.into(), position, inputs: vec![], outputs: vec![] }
+    }
+
+    pub fn with_input(mut self, label: impl Into<String>) -> Self {
+        self.inputs.push(label.into());
+        self
+    }
+
+    pub fn with_output(mut self, label: impl Into<String>) -> Self {
+        self.outputs.push(label.into());
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortRef<NodeId> {
+    pub node: NodeId,
+    pub port: usize,
+}
+
+/// A wire from an output port to an input port. An input has at most one connection, an output can have many.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Connection<NodeId> {
+    pub from: PortRef<NodeId>,
+    pub to: PortRef<NodeId>,
+}
+
+pub enum NodeGraphEvent<NodeId> {
+    /// A wire was dragged from an output to an input.
+    Connected(Connection<NodeId>),
+    /// A wire was dragged off of its input, or replaced by a new one to the same input.
+    Disconnected(Connection<NodeId>),
+    /// The nodes were dragged to new positions, sent once the drag ends.
+    NodesMoved(Vec<NodeId>),
+    SelectionChanged(Vec<NodeId>),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum PortKind {
+    Input,
+    Output,
+}
+
+/// A port by the index of its node in `GraphCanvas::nodes`.
+#[derive(Clone, Copy, PartialEq)]
+struct Port {
+    node: usize,
+    kind: PortKind,
+    index: usize,
+}
+
+struct NodeState<NodeId> {
+    node: GraphNode<NodeId>,
+    title: Text,
+    inputs: Vec<Text>,
+    outputs: Vec<Text>,
+}
+
+impl<NodeId> NodeState<NodeId> {
+    fn get_bounds(&self) -> Rect {
+        let rows = self.inputs.len().max(self.outputs.len()) as f32;
+        let height = HEADER_HEIGHT + rows * PORT_SPACING + PORT_SPACING * 0.5;
+        Rect::from_xywh(self.node.position.x, self.node.position.y, NODE_WIDTH, height)
+    }
+
+    fn get_port_position(&self, kind: PortKind, index: usize) -> Point {
+        let bounds = self.get_bounds();
+        let y = bounds.top() + HEADER_HEIGHT + (index as f32 + 0.5) * PORT_SPACING;
+        match kind {
+            PortKind::Input => point(bounds.left(), y),
+            PortKind::Output => point(bounds.right(), y),
+        }
+    }
+}
+
+enum Drag<NodeId> {
+    /// The selected nodes, with where they were when the drag started.
+    Nodes {
+        start_positions: Vec<(NodeId, Point)>,
+        moved: bool,
+    },
+    /// A new wire from an output, or one that was picked up from an input.
+    Wire {
+        from: PortRef<NodeId>,
+        to: Point,
+    },
+    Marquee {
+        start: Point,
+        end: Point,
+    },
+}
+
+#[derive(Clone)]
+struct NodeGraphStyle {
+    node_color: Computed<Color>,
+    text_color: Computed<Color>,
+    wire_color: Computed<Color>,
+    selection_color: Computed<Color>,
+}
+
+/// The graph in its own coordinates, shown zoomed and panned by the `Viewport` of a `NodeGraph`.
+struct GraphCanvas<NodeId> {
+    id: ComponentId,
+    /// From bottom to top, so the last one is drawn above the others and hit first.
+    nodes: Vec<NodeState<NodeId>>,
+    connections: Vec<Connection<NodeId>>,
+    selection: Vec<NodeId>,
+    hovered_port: Option<Port>,
+    drag: Option<Drag<NodeId>>,
+    text: TextBuilder,
+    style: NodeGraphStyle,
+    outputs: Output<NodeGraphEvent<NodeId>>,
+}
+
+impl<NodeId: Clone + PartialEq + 'static> GraphCanvas<NodeId> {
+    fn find(&self, id: &NodeId) -> Option<usize> {
+        self.nodes.iter().position(|node| &node.node.id == id)
+    }
+
+    fn node_at(&self, position: Point) -> Option<usize> {
+        self.nodes.iter().rposition(|node| node.get_bounds().contains(&position))
+    }
+
+    fn port_at(&self, position: Point) -> Option<Port> {
+        self.nodes.iter().enumerate().rev().find_map(|(node_index, node)| {
+            let ports = [
+                (PortKind::Input, node.inputs.len()),
+                (PortKind::Output, node.outputs.len()),
+            ];
+            ports.into_iter().find_map(|(kind, count)| {
+                (0..count)
+                    .find(|&index| node.get_port_position(kind, index).distance_to(position) <= PORT_HIT_RADIUS)
+                    .map(|index| Port { node: node_index, kind, index })
+            })
+        })
+    }
+
+    fn get_port_ref(&self, port: Port) -> PortRef<NodeId> {
+        PortRef { node: self.nodes[port.node].node.id.clone(), port: port.index }
+    }
+
+    fn get_port_position(&self, port: &PortRef<NodeId>, kind: PortKind) -> Option<Point> {
+        self.find(&port.node).map(|index| self.nodes[index].get_port_position(kind, port.port))
+    }
+
+    fn set_selection(&mut self, selection: Vec<NodeId>) {
+        if selection != self.selection {
+            self.selection = selection;
+            self.outputs.add_output(NodeGraphEvent::SelectionChanged(self.selection.clone()));
+        }
+    }
+
+    fn add_connection(&mut self, connection: Connection<NodeId>) {
+        if self.connections.contains(&connection) {
+            return;
+        }
+        // an input only takes one wire
+        if let Some(index) = self.connections.iter().position(|existing| existing.to == connection.to) {
+            let replaced = self.connections.remove(index);
+            self.outputs.add_output(NodeGraphEvent::Disconnected(replaced));
+        }
+        self.connections.push(connection.clone());
+        self.outputs.add_output(NodeGraphEvent::Connected(connection));
+    }
+
+    fn press(&mut self, position: Point, shift: bool) {
+        if let Some(port) = self.port_at(position) {
+            let port_ref = self.get_port_ref(port);
+            let from = match port.kind {
+                PortKind::Output => Some(port_ref),
+                PortKind::Input => self.connections.iter().position(|c| c.to == port_ref).map(|index| {
+                    let connection = self.connections.remove(index);
+                    self.outputs.add_output(NodeGraphEvent::Disconnected(connection.clone()));
+                    connection.from
+                }),
+            };
+            if let Some(from) = from {
+                self.drag = Some(Drag::Wire { from, to: position });
+                return;
+            }
+        }
+
+        if let Some(index) = self.node_at(position) {
+            let node = self.nodes.remove(index);
+            let id = node.node.id.clone();
+            self.nodes.push(node);
+            let selected = self.selection.contains(&id);
+            if shift {
+                let mut selection = self.selection.clone();
+                if selected {
+                    selection.retain(|selected| selected != &id);
+                } else {
+                    selection.push(id);
+                }
+                self.set_selection(selection);
+            } else if !selected {
+                self.set_selection(vec![id]);
+            }
+            let start_positions = self
+                .nodes
+                .iter()
+                .filter(|node| self.selection.contains(&node.node.id))
+                .map(|node| (node.node.id.clone(), node.node.position))
+                .collect();
+            self.drag = Some(Drag::Nodes { start_positions, moved: false });
+        } else {
+            if !shift {
+                self.set_selection(vec![]);
+            }
+            self.drag = Some(Drag::Marquee { start: position, end: position });
+        }
+    }
+
+    fn drag_to(&mut self, position: Point, delta_sum: Point) {
+        match &mut self.drag {
+            Some(Drag::Nodes { start_positions, moved }) => {
+                *moved = true;
+                for (id, start) in start_positions.iter() {
+                    if let Some(node) = self.nodes.iter_mut().find(|node| &node.node.id == id) {
+                        node.node.position = *start + delta_sum;
+                    }
+                }
+            }
+            Some(Drag::Wire { to, .. }) => *to = position,
+            Some(Drag::Marquee { end, .. }) => *end = position,
+            None => {}
+        }
+    }
+
+    fn release(&mut self, position: Point, shift: bool) {
+        match self.drag.take() {
+            Some(Drag::Nodes { start_positions, moved: true }) => {
+                let moved = start_positions.into_iter().map(|(id, _)| id).collect();
+                self.outputs.add_output(NodeGraphEvent::NodesMoved(moved));
+            }
+            Some(Drag::Wire { from, .. }) => {
+                if let Some(port) = self.port_at(position).filter(|port| port.kind == PortKind::Input)
+                    && self.nodes[port.node].node.id != from.node
+                {
+                    let to = self.get_port_ref(port);
+                    self.add_connection(Connection { from, to });
+                }
+            }
+            Some(Drag::Marquee { start, end }) => {
+                let marquee = Rect::from_ab(start, end);
+                let mut selection = if shift { self.selection.clone() } else { vec![] };
+                for node in &self.nodes {
+                    if node.get_bounds().overlaps(marquee) && !selection.contains(&node.node.id) {
+                        selection.push(node.node.id.clone());
+                    }
+                }
+                self.set_selection(selection);
+            }
+            _ => {}
+        }
+    }
+
+    fn draw_wire(&self, canvas: &mut Canvas, from: Point, to: Point, color: Color) {
+        let handle = ((to.x - from.x).abs() * 0.5).max(40.0);
+        let mut path = PathBuilder::default();
+        path.move_to(from).cubic_to(from + point(handle, 0.0), to - point(handle, 0.0), to);
+        canvas.stroke(color, 2.0).draw_path(&path.build());
+    }
+
+    fn draw_node(&self, canvas: &mut Canvas, node: &NodeState<NodeId>) {
+        let bounds = node.get_bounds();
+        let text_color = self.style.text_color.get();
+        canvas.fill(self.style.node_color.get()).draw_round_rect(bounds, 6.0);
+        if self.selection.contains(&node.node.id) {
+            canvas.stroke(self.style.selection_color.get(), 2.0).draw_round_rect(bounds, 6.0);
+        }
+        let header = bounds.with_height(HEADER_HEIGHT);
+        canvas.fill(text_color.with_alpha(0.08)).draw_round_rect(header, 6.0);
+
+        let draw_text = |canvas: &mut Canvas, text: &Text, left: f32, center_y: f32| {
+            let cap_height = text.get_cap_height();
+            let text_bounds = Rect::from_xywh(left, center_y - cap_height * 0.5, text.get_width(), cap_height);
+            text.draw(canvas, text_bounds);
+        };
+        draw_text(canvas, &node.title, header.left() + 8.0, header.center().y);
+
+        let ports = [(PortKind::Input, &node.inputs), (PortKind::Output, &node.outputs)];
+        for (kind, labels) in ports {
+            for (index, label) in labels.iter().enumerate() {
+                let position = node.get_port_position(kind, index);
+                let hovered = self.hovered_port.is_some_and(|port| {
+                    port.kind == kind && port.index == index && self.nodes[port.node].node.id == node.node.id
+                });
+                let radius = if hovered { PORT_RADIUS * 1.4 } else { PORT_RADIUS };
+                canvas.fill(self.style.wire_color.get()).draw_circle(position, radius);
+                let left = match kind {
+                    PortKind::Input => position.x + PORT_RADIUS + 6.0,
+                    PortKind::Output => position.x - PORT_RADIUS - 6.0 - label.get_width(),
+                };
+                draw_text(canvas, label, left, position.y);
+            }
+        }
+    }
+}
+
+impl<A: App, NodeId: Clone + PartialEq + 'static> Component<A> for GraphCanvas<NodeId> {
+    fn draw(&self, _cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let wire_color = self.style.wire_color.get();
+        for connection in &self.connections {
+            if let (Some(from), Some(to)) = (
+                self.get_port_position(&connection.from, PortKind::Output),
+                self.get_port_position(&connection.to, PortKind::Input),
+            ) {
+                self.draw_wire(canvas, from, to, wire_color);
+            }
+        }
+
+        let visible = canvas.local_clip_bounds();
+        for node in &self.nodes {
+            if visible.is_none_or(|visible| visible.overlaps(node.get_bounds().with_expansion(PORT_HIT_RADIUS))) {
+                self.draw_node(canvas, node);
+            }
+        }
+
+        match &self.drag {
+            Some(Drag::Wire { from, to }) => {
+                if let Some(from) = self.get_port_position(from, PortKind::Output) {
+                    self.draw_wire(canvas, from, *to, wire_color.with_alpha_mul(0.6));
+                }
+            }
+            Some(Drag::Marquee { start, end }) => {
+                let marquee = Rect::from_ab(*start, *end);
+                let color = self.style.selection_color.get();
+                canvas.fill(color.with_alpha_mul(0.15)).draw_rect(marquee);
+                canvas.stroke(color, 1.0).draw_rect(marquee);
+            }
+            _ => {}
+        }
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        match event {
+            Event::PointerDown(pointer) if self.is_hovered(pointer) && pointer.button != MouseButton::Middle => {
+                self.press(pointer.position, cx.mods.shift);
+            }
+            Event::PointerMove(pointer) => {
+                self.hovered_port = self.port_at(pointer.position);
+                if self.is_pressed(pointer) {
+                    self.drag_to(pointer.position, pointer.position - pointer.down_position);
+                }
+            }
+            Event::PointerUp(pointer) if self.drag.is_some() => {
+                self.release(pointer.position, cx.mods.shift);
+            }
+            _ => {}
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        self.set_bounds(cx, bounds);
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
+
+/// An editor for a graph of nodes connected by wires from their outputs to their inputs, e.g. for modular synths.
+/// Nodes are moved by dragging them, wires by dragging from an output to an input or off of an input, and nodes are
+/// selected by clicking them or dragging a marquee around them. The graph is shown in a `Viewport`, so it can be
+/// zoomed and panned.
+pub struct NodeGraph<NodeId> {
+    pub id: ComponentId,
+    viewport: Viewport<GraphCanvas<NodeId>>,
+}
+
+impl<NodeId: Clone + PartialEq + 'static> NodeGraph<NodeId> {
+    pub fn new<A: App>(cx: &mut Cx<A>, id: ComponentId, font_size: f32, typeface: impl Into<usize>) -> Self {
+        let text = TextBuilder::default().font_size(font_size).typeface(typeface);
+        let viewport = cx.add_child(&id, |cx, viewport_id| {
+            Viewport::new(cx, viewport_id, |cx, id| GraphCanvas {
+                id: id.hoverable(cx),
+                nodes: vec![],
+                connections: vec![],
+                selection: vec![],
+                hovered_port: None,
+                drag: None,
+                text,
+                style: NodeGraphStyle {
+                    node_color: rgb(0x2c2c2e).into(),
+                    text_color: rgb(0xffffff).into(),
+                    wire_color: rgb(0x8e8e93).into(),
+                    selection_color: rgb(0x0a84ff).into(),
+                },
+                outputs: Default::default(),
+            })
+            .with_zoom_range(0.2, 4.0)
+        });
+        let mut graph = Self { id, viewport };
+        let canvas = Rect::from_xywh(-CANVAS_EXTENT, -CANVAS_EXTENT, CANVAS_EXTENT * 2.0, CANVAS_EXTENT * 2.0);
+        graph.viewport.set_content_bounds(cx, canvas);
+        graph
+    }
+
+    pub fn with_node_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.viewport.content_mut().style.node_color = color.into();
+        self
+    }
+
+    /// The color of the titles and port labels. Only applies to the nodes added after this.
+    pub fn with_text_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.viewport.content_mut().style.text_color = color.into();
+        self
+    }
+
+    /// The color of the wires and ports.
+    pub fn with_wire_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.viewport.content_mut().style.wire_color = color.into();
+        self
+    }
+
+    /// The color of the outlines of the selected nodes and of the marquee.
+    pub fn with_selection_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.viewport.content_mut().style.selection_color = color.into();
+        self
+    }
+
+    pub fn next_output(&mut self) -> Option<NodeGraphEvent<NodeId>> {
+        self.viewport.content_mut().outputs.next_output()
+    }
+
+    pub fn add_node<A: App>(&mut self, cx: &mut Cx<A>, node: GraphNode<NodeId>) {
+        let graph = self.viewport.content_mut();
+        let color = graph.style.text_color.get();
+        let mut build = |label: &String| graph.text.clone().text(label.clone()).color(color).build(cx);
+        let state = NodeState {
+            title: build(&node.title),
+            inputs: node.inputs.iter().map(&mut build).collect(),
+            outputs: node.outputs.iter().map(&mut build).collect(),
+            node,
+        };
+        graph.nodes.push(state);
+    }
+
+    /// Removes the node and its connections without sending events for them.
+    pub fn remove_node(&mut self, id: &NodeId) {
+        let graph = self.viewport.content_mut();
+        graph.nodes.retain(|node| &node.node.id != id);
+        graph.connections.retain(|connection| &connection.from.node != id && &connection.to.node != id);
+        graph.selection.retain(|selected| selected != id);
+        graph.hovered_port = None;
+        graph.drag = None;
+    }
+
+    pub fn get_node_position(&self, id: &NodeId) -> Option<Point> {
+        let graph = self.viewport.content();
+        graph.find(id).map(|index| graph.nodes[index].node.position)
+    }
+
+    pub fn set_node_position(&mut self, id: &NodeId, position: Point) {
+        let graph = self.viewport.content_mut();
+        if let Some(index) = graph.find(id) {
+            graph.nodes[index].node.position = position;
+        }
+    }
+
+    pub fn connections(&self) -> &[Connection<NodeId>] {
+        &self.viewport.content().connections
+    }
+
+    /// Replaces the connections without sending events for them.
+    pub fn set_connections(&mut self, connections: Vec<Connection<NodeId>>) {
+        self.viewport.content_mut().connections = connections;
+    }
+
+    pub fn selection(&self) -> &[NodeId] {
+        &self.viewport.content().selection
+    }
+
+    /// Animates the view to show all of the nodes.
+    pub fn zoom_to_fit<A: App>(&mut self, cx: &mut Cx<A>, duration_ms: f32) {
+        let bounds = self.viewport.content().nodes.iter().map(NodeState::get_bounds).reduce(|a, b| a.union(b));
+        if let Some(bounds) = bounds {
+            self.viewport.zoom_to_fit(cx, bounds, duration_ms);
+        }
+    }
+}
+
+impl<A: App, NodeId: Clone + PartialEq + 'static> Component<A> for NodeGraph<NodeId> {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        self.viewport.draw(cx, canvas);
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        self.viewport.dispatch_event(cx, event);
+        // a press that starts a pan isn't also a marquee
+        if self.viewport.is_panning() {
+            self.viewport.content_mut().drag = None;
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        self.set_bounds(cx, bounds);
+        self.viewport.layout(cx, bounds);
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
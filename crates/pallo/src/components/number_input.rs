@@ -0,0 +1,332 @@
+use std::rc::Rc;
+
+use crate::*;
+
+/// How far the pointer has to move before a press on the value turns into a drag instead of a click.
+const DRAG_DISTANCE: f32 = 3.0;
+
+pub type NumberFormatter = Rc<dyn Fn(f64) -> String>;
+pub type NumberParser = Rc<dyn Fn(&str) -> Option<f64>>;
+
+/// A numeric field that can be typed into, stepped with its arrow buttons or the arrow keys, or dragged vertically
+/// like a knob. Holding shift makes steps and drags finer.
+pub struct NumberInput {
+    pub id: ComponentId,
+    input: TextInput,
+    increment_id: ComponentId,
+    decrement_id: ComponentId,
+    value: Signal<f64>,
+    min: f64,
+    max: f64,
+    step: f64,
+    fine_step: f64,
+    unit: String,
+    /// Pixels of vertical movement per step when dragging.
+    drag_sensitivity: f32,
+    drag: Option<Drag>,
+    format: Option<NumberFormatter>,
+    parse: Option<NumberParser>,
+    /// Writes values with the active locale's decimal separator.
+    i18n: Localization,
+    arrow_color: Computed<Color>,
+    button_width: f32,
+    outputs: Output<f64>,
+}
+
+struct Drag {
+    start_value: f64,
+    start_y: f32,
+    fine: bool,
+    moved: bool,
+}
+
+impl NumberInput {
+    pub fn new<A: App>(
+        cx: &mut Cx<A>,
+        id: ComponentId,
+        value: f64,
+        min: f64,
+        max: f64,
+        font_size: f32,
+        typeface: impl Into<usize>,
+    ) -> Self {
+        let typeface = typeface.into();
+        let input = id.add_child(cx, |cx, id| TextInput::new(cx, id, font_size, typeface).with_type(InputType::Number));
+        let increment_id = cx.add_child_id(&id);
+        increment_id.set_hoverable(cx, true);
+        let decrement_id = cx.add_child_id(&id);
+        decrement_id.set_hoverable(cx, true);
+        let mut number_input = Self {
+            id,
+            input,
+            increment_id,
+            decrement_id,
+            value: cx.signal(value.clamp(min, max)),
+            min,
+            max,
+            step: 1.0,
+            fine_step: 0.1,
+            unit: String::new(),
+            drag_sensitivity: 4.0,
+            drag: None,
+            format: None,
+            parse: None,
+            i18n: cx.i18n.clone(),
+            arrow_color: rgb(0xffffff).with_alpha(0.6).into(),
+            button_width: 12.0,
+            outputs: Default::default(),
+        };
+        number_input.bind_text();
+        number_input
+    }
+
+    /// Values snap to multiples of `step` from `min`. `fine_step` is used instead while shift is held.
+    pub fn with_step(mut self, step: f64, fine_step: f64) -> Self {
+        self.step = step;
+        self.fine_step = fine_step;
+        self.bind_text();
+        self
+    }
+
+    /// Appended to the value by the default formatter, e.g. "dB" or "ms".
+    pub fn with_unit(mut self, unit: impl Into<String>) -> Self {
+        self.unit = unit.into();
+        self.bind_text();
+        self
+    }
+
+    pub fn with_formatter(mut self, format: impl Fn(f64) -> String + 'static) -> Self {
+        self.format = Some(Rc::new(format));
+        self.bind_text();
+        self
+    }
+
+    /// Turns typed text into a value. Returning `None` rejects the edit and keeps the previous value.
+    pub fn with_parser(mut self, parse: impl Fn(&str) -> Option<f64> + 'static) -> Self {
+        self.parse = Some(Rc::new(parse));
+        self
+    }
+
+    pub fn with_drag_sensitivity(mut self, pixels_per_step: f32) -> Self {
+        self.drag_sensitivity = pixels_per_step;
+        self
+    }
+
+    pub fn with_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.input.set_color(color);
+        self
+    }
+
+    pub fn with_arrow_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.arrow_color = color.into();
+        self
+    }
+
+    pub fn with_button_width(mut self, width: f32) -> Self {
+        self.button_width = width;
+        self
+    }
+
+    pub fn value(&self) -> f64 {
+        self.value.get_fast()
+    }
+
+    /// Sets the value without emitting an output, e.g. when it changed elsewhere.
+    pub fn set_value(&mut self, value: f64) {
+        self.value.set(self.constrain(value, self.step));
+    }
+
+    /// The values the user entered, already clamped and snapped.
+    pub fn next_output(&mut self) -> Option<f64> {
+        self.outputs.next_output()
+    }
+
+    fn bind_text(&mut self) {
+        let value = self.value.clone();
+        let format = self.formatter();
+        self.input.set_text(value.cx().computed(move || format(value.get())));
+    }
+
+    fn formatter(&self) -> NumberFormatter {
+        if let Some(format) = &self.format {
+            return format.clone();
+        }
+        // enough decimals for fine adjustments to show
+        let decimals = decimals(self.fine_step.min(self.step));
+        let options = FormatOptions::fixed(decimals).with_grouping(false);
+        let unit = self.unit.clone();
+        let i18n = self.i18n.clone();
+        Rc::new(move |value| {
+            let value = i18n.format_number(value, &options);
+            if unit.is_empty() {
+                value
+            } else {
+                format!("{value} {unit}")
+            }
+        })
+    }
+
+    fn parse(&self, text: &str) -> Option<f64> {
+        match &self.parse {
+            Some(parse) => parse(text),
+            // ignore the unit and accept a decimal comma
+            None => text
+                .trim()
+                .trim_end_matches(|c: char| c.is_alphabetic() || c.is_whitespace() || c == '%')
+                .replace(',', ".")
+                .parse()
+                .ok(),
+        }
+    }
+
+    fn constrain(&self, value: f64, step: f64) -> f64 {
+        let snapped = if step > 0.0 {
+            self.min + ((value - self.min) / step).round() * step
+        } else {
+            value
+        };
+        snapped.clamp(self.min, self.max)
+    }
+
+    fn current_step<A: App>(&self, cx: &Cx<A>) -> f64 {
+        if cx.mods.shift { self.fine_step } else { self.step }
+    }
+
+    fn commit(&mut self, value: f64, step: f64) {
+        let value = self.constrain(value, step);
+        if value != self.value.get_fast() {
+            self.value.set(value);
+            self.outputs.add_output(value);
+        }
+    }
+
+    fn step_by<A: App>(&mut self, cx: &mut Cx<A>, steps: f64) {
+        let step = self.current_step(cx);
+        self.commit(self.value.get_fast() + steps * step, step);
+        if self.input.is_editing() {
+            // keep the field in sync while it's being typed into
+            self.input.edited_text.set((self.formatter())(self.value.get_fast()));
+            self.input.select_all();
+        }
+    }
+
+    fn draw_arrow(&self, canvas: &mut Canvas, bounds: Rect, up: bool, hovered: bool) {
+        let color = self.arrow_color.get();
+        let color = if hovered { color } else { color.with_alpha_mul(0.6) };
+        let center = bounds.center();
+        let size = (bounds.width().min(bounds.height()) * 0.3).max(2.0);
+        let tip = if up { -size * 0.5 } else { size * 0.5 };
+        let mut path = PathBuilder::default();
+        path.move_to(point(center.x - size, center.y - tip))
+            .line_to(point(center.x + size, center.y - tip))
+            .line_to(point(center.x, center.y + tip))
+            .close();
+        canvas.fill(color).draw_path(&path.build());
+    }
+}
+
+/// The number of decimals needed to show multiples of `step`.
+fn decimals(step: f64) -> usize {
+    let mut decimals = 0;
+    while decimals < 6 && (step * 10f64.powi(decimals as i32)).fract().abs() > 1e-9 {
+        decimals += 1;
+    }
+    decimals
+}
+
+impl<A: App> Component<A> for NumberInput {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        if !cx.is_visible(&self.id) {
+            return;
+        }
+        self.input.draw(cx, canvas);
+        let increment = cx.get_bounds(&self.increment_id);
+        let decrement = cx.get_bounds(&self.decrement_id);
+        self.draw_arrow(canvas, increment, true, self.increment_id.is_hovered_any(cx));
+        self.draw_arrow(canvas, decrement, false, self.decrement_id.is_hovered_any(cx));
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        match event {
+            Event::PointerDown(pointer) if self.increment_id.is_hovered(pointer) => {
+                self.step_by(cx, 1.0);
+                return;
+            }
+            Event::PointerDown(pointer) if self.decrement_id.is_hovered(pointer) => {
+                self.step_by(cx, -1.0);
+                return;
+            }
+            Event::PointerDown(pointer) if self.input.is_hovered(pointer) && !self.input.is_editing() => {
+                // a click starts editing on release, unless the press turns into a drag
+                self.drag = Some(Drag {
+                    start_value: self.value.get_fast(),
+                    start_y: pointer.position.y,
+                    fine: cx.mods.shift,
+                    moved: false,
+                });
+                return;
+            }
+            Event::PointerMove(pointer) if self.drag.is_some() && self.input.is_pressed(pointer) => {
+                let fine = cx.mods.shift;
+                let step = self.current_step(cx);
+                let value = self.value.get_fast();
+                let Some(drag) = &mut self.drag else { return };
+                if drag.fine != fine {
+                    // continue from the current value so that toggling shift doesn't jump
+                    *drag = Drag { start_value: value, start_y: pointer.position.y, fine, moved: drag.moved };
+                }
+                drag.moved |= (pointer.position - pointer.down_position).len() > DRAG_DISTANCE;
+                if drag.moved {
+                    let steps = ((drag.start_y - pointer.position.y) / self.drag_sensitivity) as f64;
+                    let start_value = drag.start_value;
+                    self.commit(start_value + steps * step, step);
+                }
+                return;
+            }
+            Event::PointerUp(_) if self.drag.is_some() => {
+                if let Some(drag) = self.drag.take()
+                    && !drag.moved
+                {
+                    self.input.start_edit(cx);
+                    self.input.focus(cx);
+                }
+                return;
+            }
+            Event::Keydown { key: key @ (Key::ArrowUp | Key::ArrowDown), captured, .. }
+                if self.input.is_focused(cx) =>
+            {
+                let steps = if *key == Key::ArrowUp { 1.0 } else { -1.0 };
+                self.step_by(cx, steps);
+                *captured = true;
+                return;
+            }
+            _ => {}
+        }
+
+        // text that doesn't parse is dropped, and the field shows the previous value again
+        if let Some(text) = self.input.event(cx, event)
+            && let Some(value) = self.parse(&text)
+        {
+            self.commit(value, self.fine_step);
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        cx.set_bounds(&self.id, bounds);
+        let mut text_bounds = bounds;
+        let mut buttons = text_bounds.remove_from(self.button_width.min(bounds.width()), Side::Right);
+        let increment = buttons.remove_from(buttons.height() / 2.0, Side::Top);
+        cx.set_bounds(&self.increment_id, increment);
+        cx.set_bounds(&self.decrement_id, buttons);
+        self.input.layout(cx, text_bounds);
+    }
+
+    fn get_preferred_size(&mut self, cx: &mut Cx<A>, parent_bounds: Rect) -> (Option<f32>, Option<f32>) {
+        let (width, height) = self.input.get_preferred_size(cx, parent_bounds);
+        (width.map(|width| width + self.button_width), height)
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
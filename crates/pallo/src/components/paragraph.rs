@@ -59,6 +59,7 @@ impl<A: App> Component<A> for Paragraph {
         match event {
             Event::Update if self.lines.is_none() => {
                 self.relayout(cx);
+                cx.invalidate(&self.id);
             }
             _ => {}
         }
@@ -0,0 +1,181 @@
+use crate::*;
+
+const MIN_WIDTH: f32 = 120.0;
+const PADDING: f32 = 4.0;
+const FADE_IN_MS: f32 = 120.0;
+
+pub enum PopupMenuEvent<T> {
+    Selected(T),
+    /// The menu was closed without choosing an item, by pressing outside of it or with Escape.
+    Dismissed,
+}
+
+struct PopupMenuItem<T> {
+    label: Text,
+    value: T,
+    enabled: bool,
+}
+
+/// A list of choices shown above the rest of the UI, e.g. a context menu. Open one with `PopupMenu::open`, and close
+/// it with `Cx::close_overlay` once it sends a `PopupMenuEvent`.
+pub struct PopupMenu<T> {
+    id: ComponentId,
+    items: Vec<PopupMenuItem<T>>,
+    /// The item under the pointer or picked with the arrow keys.
+    highlighted: Option<usize>,
+    /// Items are chosen with a press and a release on them, so that the release of the press that opened the menu
+    /// doesn't choose one.
+    pressed: bool,
+    outputs: Output<PopupMenuEvent<T>>,
+    fade_in: TweenId<f32>,
+}
+
+impl<T: Clone + 'static> PopupMenu<T> {
+    pub fn new<A: App>(cx: &mut Cx<A>, id: ComponentId) -> Self {
+        cx.set_hoverable(&id, true);
+        let fade_in = cx.animations.add_tween(0.0, FADE_IN_MS, Easing::EaseOutCubic);
+        cx.animations.set_tween(&fade_in, 1.0);
+        Self { id, items: vec![], highlighted: None, pressed: false, outputs: Default::default(), fade_in }
+    }
+
+    /// Adds an item showing `label`. Disabled items are dimmed and can't be chosen.
+    pub fn with_item(mut self, mut label: Text, value: T, enabled: bool) -> Self {
+        if !enabled {
+            label.set_color(label.get_color().with_alpha(0.4));
+        }
+        self.items.push(PopupMenuItem { label, value, enabled });
+        self
+    }
+
+    /// Adds the menu returned by `create` on the `Popup` layer with its top left corner at `position`, or moved so
+    /// that it fits within `Cx::safe_bounds`.
+    pub fn open<A: App>(
+        cx: &mut Cx<A>,
+        position: Point,
+        create: impl FnOnce(&mut Cx<A>, ComponentId) -> Self,
+    ) -> Overlay<Self> {
+        let menu = cx.add_overlay(create);
+        let (width, height) = menu.borrow().get_size();
+        let area = cx.safe_bounds();
+        let top = if position.y + height > area.bottom() {
+            position.y - height
+        } else {
+            position.y
+        };
+        let left = position.x.min(area.right() - width).max(area.left());
+        let bounds = Rect::from_xywh(left, top.min(area.bottom() - height).max(area.top()), width, height);
+        menu.borrow_mut().layout(cx, bounds);
+        menu
+    }
+
+    pub fn next_output(&mut self) -> Option<PopupMenuEvent<T>> {
+        self.outputs.next_output()
+    }
+
+    fn get_row_height(&self) -> f32 {
+        self.items.first().map_or(0.0, |item| (item.label.get_font_size() * 1.8).round())
+    }
+
+    fn get_size(&self) -> (f32, f32) {
+        let widest = self.items.iter().map(|item| item.label.get_width()).fold(0.0, f32::max);
+        let width = (widest + self.get_row_height()).max(MIN_WIDTH);
+        (width, self.items.len() as f32 * self.get_row_height() + PADDING * 2.0)
+    }
+
+    fn get_item_bounds<A: App>(&self, cx: &Cx<A>, index: usize) -> Rect {
+        let bounds = self.get_bounds(cx).with_margin(Margin::even(PADDING));
+        let row_height = self.get_row_height();
+        bounds.with_y_offset(index as f32 * row_height).with_height(row_height)
+    }
+
+    fn item_at<A: App>(&self, cx: &Cx<A>, position: Point) -> Option<usize> {
+        (0..self.items.len()).find(|&index| self.get_item_bounds(cx, index).contains(&position))
+    }
+
+    /// Moves the highlight by `step` items, skipping the disabled ones.
+    fn move_highlight(&mut self, step: isize) {
+        let count = self.items.len() as isize;
+        let mut index = self.highlighted.map_or(if step > 0 { -1 } else { count }, |index| index as isize);
+        for _ in 0..count {
+            index = (index + step).rem_euclid(count);
+            if self.items[index as usize].enabled {
+                self.highlighted = Some(index as usize);
+                return;
+            }
+        }
+    }
+
+    fn choose(&mut self, index: usize) {
+        if let Some(item) = self.items.get(index).filter(|item| item.enabled) {
+            self.outputs.add_output(PopupMenuEvent::Selected(item.value.clone()));
+        }
+    }
+}
+
+impl<A: App, T: Clone + 'static> Component<A> for PopupMenu<T> {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let bounds = self.get_bounds(cx);
+        // faded as a group so that the highlight and the labels don't show the background through them
+        canvas.with_group_alpha(cx.animations.get_tween(&self.fade_in), |canvas| {
+            canvas.fill(rgb(0x1e1e1e).with_alpha(0.97)).draw_round_rect(bounds, 6.0);
+            canvas.stroke(rgb(0xffffff).with_alpha(0.15), 1.0).draw_round_rect(bounds, 6.0);
+            for (index, item) in self.items.iter().enumerate() {
+                let row = self.get_item_bounds(cx, index);
+                if self.highlighted == Some(index) && item.enabled {
+                    canvas.fill(rgb(0x0a84ff)).draw_round_rect(row, 4.0);
+                }
+                let cap_height = item.label.get_cap_height();
+                let text_bounds = Rect::from_xywh(
+                    row.left() + row.height() * 0.5,
+                    row.center().y - cap_height * 0.5,
+                    item.label.get_width(),
+                    cap_height,
+                );
+                item.label.draw(canvas, text_bounds);
+            }
+        });
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        match event {
+            Event::PointerMove(pointer) => {
+                self.highlighted = self.item_at(cx, pointer.position);
+            }
+            Event::PointerDown(pointer) => {
+                self.pressed = self.get_bounds(cx).contains(&pointer.position);
+                if !self.pressed {
+                    self.outputs.add_output(PopupMenuEvent::Dismissed);
+                }
+            }
+            Event::PointerUp(pointer) if self.pressed => {
+                self.pressed = false;
+                if let Some(index) = self.item_at(cx, pointer.position) {
+                    self.choose(index);
+                }
+            }
+            Event::Keydown { key, captured, .. } => {
+                match key {
+                    Key::Escape => self.outputs.add_output(PopupMenuEvent::Dismissed),
+                    Key::ArrowUp => self.move_highlight(-1),
+                    Key::ArrowDown => self.move_highlight(1),
+                    Key::Enter => {
+                        if let Some(index) = self.highlighted {
+                            self.choose(index);
+                        }
+                    }
+                    _ => return,
+                }
+                *captured = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        self.set_bounds(cx, bounds);
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
@@ -0,0 +1,55 @@
+use std::{
+    cell::{Ref, RefCell, RefMut},
+    rc::Rc,
+};
+
+use crate::*;
+
+/// Draws its content above the rest of the UI, unclipped by the components it's inside of, e.g. for a dropdown in a
+/// scroll area. The content stays in place otherwise: it's laid out by the portal's parent and gets events through
+/// the portal like any other child.
+pub struct Portal<C> {
+    pub id: ComponentId,
+    content: Rc<RefCell<C>>,
+}
+
+impl<C> Portal<C> {
+    pub fn new<A: App>(cx: &mut Cx<A>, id: ComponentId, add_content: impl FnOnce(&mut Cx<A>, ComponentId) -> C) -> Self
+    where
+        C: Component<A> + 'static,
+    {
+        let content = Rc::new(RefCell::new(id.add_child(cx, add_content)));
+        cx.add_portal(&content);
+        Self { id, content }
+    }
+
+    pub fn content(&self) -> Ref<'_, C> {
+        self.content.borrow()
+    }
+
+    pub fn content_mut(&self) -> RefMut<'_, C> {
+        self.content.borrow_mut()
+    }
+}
+
+impl<A: App, C: Component<A>> Component<A> for Portal<C> {
+    // the content is drawn by the UI after everything else
+    fn draw(&self, _cx: &mut Cx<A>, _canvas: &mut Canvas) {}
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        self.content.borrow_mut().dispatch_event(cx, event);
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        cx.set_bounds(&self.id, bounds);
+        self.content.borrow_mut().layout(cx, bounds);
+    }
+
+    fn get_preferred_size(&mut self, cx: &mut Cx<A>, parent_bounds: Rect) -> (Option<f32>, Option<f32>) {
+        self.content.borrow_mut().get_preferred_size(cx, parent_bounds)
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
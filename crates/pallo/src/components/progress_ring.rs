@@ -0,0 +1,241 @@
+use crate::*;
+
+/// How long an indeterminate `ProgressRing` takes to grow and shrink its arc once.
+const CYCLE_MS: f32 = 1333.0;
+/// How long a `ProgressRing` takes to ease to a new progress.
+const PROGRESS_TWEEN_MS: f32 = 250.0;
+/// The shortest arc drawn by an indeterminate `ProgressRing`, in degrees.
+const MIN_SWEEP: f32 = 20.0;
+/// How much the arc of an indeterminate `ProgressRing` grows and shrinks, in degrees.
+const SWEEP_RANGE: f32 = 270.0;
+
+/// The stroke shared by `ProgressRing` and `Spinner`.
+struct RingStyle {
+    stroke_width: f32,
+    cap: Cap,
+    color: Computed<Color>,
+    track_color: Computed<Color>,
+}
+
+impl RingStyle {
+    fn new() -> Self {
+        Self {
+            stroke_width: 3.0,
+            cap: Cap::Round,
+            color: rgb(0x0a84ff).into(),
+            track_color: rgb(0xffffff).with_alpha(0.1).into(),
+        }
+    }
+
+    /// The square that the ring is drawn in, centered in `bounds`.
+    fn get_ring_bounds(bounds: Rect) -> Rect {
+        let size = bounds.width().min(bounds.height());
+        Rect::from_size(size, size).centered_within(bounds)
+    }
+
+    fn draw_track(&self, canvas: &mut Canvas, bounds: Rect) {
+        canvas.stroke(self.track_color.get(), self.stroke_width).draw_arc(bounds, 0.0, 360.0);
+    }
+
+    /// Draws an arc from `start_angle` clockwise over `sweep_angle`, in degrees from the top.
+    fn draw_arc(&self, canvas: &mut Canvas, bounds: Rect, start_angle: f32, sweep_angle: f32) {
+        canvas.stroke(self.color.get(), self.stroke_width).stroke_cap(self.cap).draw_arc(
+            bounds,
+            start_angle - 90.0,
+            sweep_angle,
+        );
+    }
+}
+
+/// Shows progress as an arc around a ring, e.g. of a download. It's indeterminate until given a progress, in which
+/// case an arc that grows and shrinks goes around it.
+pub struct ProgressRing {
+    pub id: ComponentId,
+    style: RingStyle,
+    /// The progress from 0 to 1, or `None` for an indeterminate ring.
+    progress: Option<Computed<f32>>,
+    /// Eases the drawn progress to the latest one.
+    progress_tween: Option<TweenId<f32>>,
+    /// Counts the time since the ring became indeterminate.
+    clock: Option<AnimationId>,
+}
+
+impl ProgressRing {
+    pub fn new<A: App>(cx: &mut Cx<A>, id: ComponentId) -> Self {
+        Self {
+            id,
+            style: RingStyle::new(),
+            progress: None,
+            progress_tween: None,
+            clock: Some(cx.animations.add_clock()),
+        }
+    }
+
+    /// Shows `progress` from 0 to 1 instead of going around indeterminately.
+    pub fn with_progress<A: App>(mut self, cx: &mut Cx<A>, progress: impl Into<Computed<f32>>) -> Self {
+        self.set_progress(cx, Some(progress.into()));
+        self
+    }
+
+    pub fn with_stroke_width(mut self, width: f32) -> Self {
+        self.style.stroke_width = width;
+        self
+    }
+
+    pub fn with_cap(mut self, cap: Cap) -> Self {
+        self.style.cap = cap;
+        self
+    }
+
+    pub fn with_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.style.color = color.into();
+        self
+    }
+
+    /// The color of the ring behind the arc.
+    pub fn with_track_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.style.track_color = color.into();
+        self
+    }
+
+    /// Switches between showing `progress` and going around indeterminately with `None`, e.g. once the size of a
+    /// download becomes known.
+    pub fn set_progress<A: App>(&mut self, cx: &mut Cx<A>, progress: Option<Computed<f32>>) {
+        match progress {
+            Some(progress) => {
+                let value = progress.next().unwrap_or_else(|| progress.get()).clamp(0.0, 1.0);
+                self.progress_tween = Some(cx.animations.add_tween(value, PROGRESS_TWEEN_MS, Easing::EaseOutCubic));
+                self.progress = Some(progress);
+                self.clock = None;
+            }
+            None => {
+                self.progress = None;
+                self.progress_tween = None;
+                self.clock = Some(cx.animations.add_clock());
+            }
+        }
+    }
+
+    pub fn is_indeterminate(&self) -> bool {
+        self.progress.is_none()
+    }
+}
+
+impl<A: App> Component<A> for ProgressRing {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let bounds = RingStyle::get_ring_bounds(self.get_bounds(cx));
+        self.style.draw_track(canvas, bounds);
+        if let Some(tween) = &self.progress_tween {
+            let progress = cx.animations.get_tween(tween);
+            if progress > 0.0 {
+                self.style.draw_arc(canvas, bounds, 0.0, progress * 360.0);
+            }
+        } else if let Some(clock) = &self.clock {
+            // the head of the arc leads in the first half of a cycle and the tail catches up in the second, and the
+            // whole arc is moved forward by what the tail gained so that the next cycle starts where this one ended
+            let elapsed = cx.animations.get(clock) / CYCLE_MS;
+            let (cycle, phase) = (elapsed.floor(), elapsed.fract());
+            let head = Easing::EaseInOutCubic.apply((phase * 2.0).min(1.0));
+            let tail = Easing::EaseInOutCubic.apply((phase * 2.0 - 1.0).max(0.0));
+            let rotation = (elapsed * 0.5 * 360.0 + (cycle + tail) * SWEEP_RANGE) % 360.0;
+            self.style.draw_arc(canvas, bounds, rotation, MIN_SWEEP + (head - tail) * SWEEP_RANGE);
+        }
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        if let Event::Update = event
+            && let Some(progress) = self.progress.as_ref().and_then(|progress| progress.next())
+            && let Some(tween) = &self.progress_tween
+        {
+            cx.animations.set_tween(tween, progress.clamp(0.0, 1.0));
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        self.set_bounds(cx, bounds);
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
+
+/// A constantly spinning arc for showing that something is in progress, e.g. while loading a list.
+pub struct Spinner {
+    pub id: ComponentId,
+    style: RingStyle,
+    /// How long a revolution takes.
+    period_ms: f32,
+    /// How much of the ring the arc covers, in degrees.
+    sweep_angle: f32,
+    show_track: bool,
+    clock: AnimationId,
+}
+
+impl Spinner {
+    pub fn new<A: App>(cx: &mut Cx<A>, id: ComponentId) -> Self {
+        Self {
+            id,
+            style: RingStyle::new(),
+            period_ms: 800.0,
+            sweep_angle: 90.0,
+            show_track: true,
+            clock: cx.animations.add_clock(),
+        }
+    }
+
+    pub fn with_period_ms(mut self, period_ms: f32) -> Self {
+        self.period_ms = period_ms;
+        self
+    }
+
+    pub fn with_sweep_angle(mut self, degrees: f32) -> Self {
+        self.sweep_angle = degrees;
+        self
+    }
+
+    pub fn with_stroke_width(mut self, width: f32) -> Self {
+        self.style.stroke_width = width;
+        self
+    }
+
+    pub fn with_cap(mut self, cap: Cap) -> Self {
+        self.style.cap = cap;
+        self
+    }
+
+    pub fn with_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.style.color = color.into();
+        self
+    }
+
+    /// The color of the ring behind the arc, which isn't drawn after `without_track`.
+    pub fn with_track_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.style.track_color = color.into();
+        self
+    }
+
+    pub fn without_track(mut self) -> Self {
+        self.show_track = false;
+        self
+    }
+}
+
+impl<A: App> Component<A> for Spinner {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let bounds = RingStyle::get_ring_bounds(self.get_bounds(cx));
+        if self.show_track {
+            self.style.draw_track(canvas, bounds);
+        }
+        let turns = cx.animations.get(&self.clock) / self.period_ms.max(1.0);
+        self.style.draw_arc(canvas, bounds, turns.fract() * 360.0, self.sweep_angle);
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        self.set_bounds(cx, bounds);
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
@@ -0,0 +1,129 @@
+use crate::*;
+
+/// An oscilloscope that scrolls through the most recent samples received from a `SampleSender`.
+pub struct Scope {
+    pub id: ComponentId,
+    receiver: SampleReceiver,
+    samples: Vec<f32>,
+    write_position: usize,
+    gain: f32,
+    color: Computed<Color>,
+    line_width: f32,
+}
+
+impl Scope {
+    /// `length` is the number of samples shown across the width of the scope.
+    pub fn new(id: ComponentId, receiver: SampleReceiver, length: usize) -> Self {
+        Self {
+            id,
+            receiver,
+            samples: vec![0.0; length.max(2)],
+            write_position: 0,
+            gain: 1.0,
+            color: rgb(0xffffff).into(),
+            line_width: 1.0,
+        }
+    }
+
+    pub fn with_gain(mut self, gain: f32) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    pub fn set_color(&mut self, color: impl Into<Computed<Color>>) {
+        self.color = color.into();
+    }
+
+    pub fn with_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.set_color(color);
+        self
+    }
+
+    pub fn with_line_width(mut self, line_width: f32) -> Self {
+        self.line_width = line_width;
+        self
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.fill(0.0);
+    }
+
+    /// Iterates the buffered samples from oldest to newest.
+    fn ordered_samples(&self) -> impl Iterator<Item = f32> + '_ {
+        let (newest, oldest) = self.samples.split_at(self.write_position);
+        oldest.iter().chain(newest).copied()
+    }
+
+    fn build_path(&self, bounds: Rect) -> Path {
+        let mut path = PathBuilder::default();
+        let columns = bounds.width().max(1.0) as usize;
+        let to_y = |sample: f32| bounds.relative_point((0.0, 0.5 - 0.5 * (sample * self.gain).clamp(-1.0, 1.0))).y;
+
+        if self.samples.len() <= columns {
+            let step = bounds.width() / (self.samples.len() - 1) as f32;
+            for (i, sample) in self.ordered_samples().enumerate() {
+                let p = point(bounds.left() + i as f32 * step, to_y(sample));
+                if i == 0 {
+                    path.move_to(p);
+                } else {
+                    path.line_to(p);
+                }
+            }
+        } else {
+            // more samples than pixels, so draw each column's min/max span as one segment of a single path
+            let samples_per_column = self.samples.len() as f32 / columns as f32;
+            let mut samples = self.ordered_samples();
+            let mut taken = 0;
+            for column in 0..columns {
+                let end = ((column + 1) as f32 * samples_per_column) as usize;
+                let (mut min, mut max) = (f32::MAX, f32::MIN);
+                for sample in samples.by_ref().take(end - taken) {
+                    min = min.min(sample);
+                    max = max.max(sample);
+                }
+                taken = end;
+                let x = bounds.left() + column as f32;
+                if column == 0 {
+                    path.move_to(point(x, to_y(max)));
+                } else {
+                    path.line_to(point(x, to_y(max)));
+                }
+                path.line_to(point(x, to_y(min)));
+            }
+        }
+        path.build()
+    }
+}
+
+impl<A: App> Component<A> for Scope {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let bounds = self.get_bounds(cx);
+        let path = self.build_path(bounds);
+        canvas.with_clip_rect(bounds, |canvas| {
+            canvas.stroke(self.color.get(), self.line_width).draw_path(&path);
+        });
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        if let Event::Update = event {
+            let samples = &mut self.samples;
+            let write_position = &mut self.write_position;
+            let previous_position = *write_position;
+            self.receiver.drain(|sample| {
+                samples[*write_position] = sample;
+                *write_position = (*write_position + 1) % samples.len();
+            });
+            if self.write_position != previous_position {
+                cx.invalidate(&self.id);
+            }
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        self.set_bounds(cx, bounds);
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
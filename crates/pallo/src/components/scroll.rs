@@ -21,15 +21,158 @@ impl<T: Clone> Memo<T> {
     }
 }
 
+const RUBBER_BAND_COEFFICIENT: f32 = 0.55;
+const REORDER_DRAG_DISTANCE: f32 = 4.0;
+/// How close to the edges the lifted item has to be for the list to scroll.
+const REORDER_SCROLL_EDGE: f32 = 32.0;
+/// How far the list has to be pulled past its top for a refresh.
+const REFRESH_THRESHOLD: f32 = 64.0;
+/// The space kept above the items for the refresh indicator while refreshing.
+const REFRESH_INDICATOR_HEIGHT: f32 = 48.0;
+/// How many item ids are fetched at a time from an item provider.
+const PROVIDER_PAGE_SIZE: usize = 50;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SelectionMode {
+    None,
+    Single,
+    /// Shift extends the selection from the last selected item and Cmd toggles single items.
+    Multi,
+}
+
+#[derive(Clone, Copy)]
+struct MeasuredHeight {
+    height: f32,
+    version: u64,
+}
+
+pub enum ScrollListEvent {
+    /// An item was dragged to a new position. The list has already moved it, so that the item that was at `from`
+    /// in the items is now at `to`.
+    Reordered { from: usize, to: usize },
+    /// The list was pulled down past its top. Call `ScrollList::end_refresh` once done refreshing.
+    RefreshRequested,
+}
+
+/// An item being dragged to a new position. The positions are in the filtered items.
+struct Reorder {
+    from: usize,
+    to: usize,
+    grab_offset: Point,
+    pointer: Point,
+}
+
+impl Reorder {
+    /// How far the item at `i` moves to make room for the lifted one, which is `height` tall.
+    fn offset(&self, i: usize, height: f32) -> f32 {
+        if self.from < i && i <= self.to {
+            -height
+        } else if self.to <= i && i < self.from {
+            height
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Draws a snapshot of the lifted item above everything else while it's being reordered.
+struct ReorderGhost {
+    id: ComponentId,
+    surface: Option<Surface>,
+}
+
+impl<A: App> Component<A> for ReorderGhost {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        if let Some(surface) = &self.surface {
+            let position = self.get_bounds(cx).relative_point((0.0, 0.0));
+            canvas.with_alpha(0.9, |canvas| {
+                canvas.draw_surface(surface, position);
+            });
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        self.set_bounds(cx, bounds);
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
+
+type FetchRange<ItemID> = Box<dyn Fn(Range<usize>, Later<Vec<ItemID>>)>;
+
+/// Fetches the ids of items by their index, see `ScrollList::set_item_provider`.
+struct ItemProvider<ItemID> {
+    fetch_range: FetchRange<ItemID>,
+    pending: Vec<(Range<usize>, Later<Vec<ItemID>>)>,
+    requested: Vec<bool>,
+}
+
+impl<ItemID> ItemProvider<ItemID> {
+    fn new(count: usize, fetch_range: FetchRange<ItemID>) -> Self {
+        Self { fetch_range, pending: vec![], requested: vec![false; count] }
+    }
+
+    /// Fetches the page of ids that `index` is on, unless it's been fetched already.
+    fn request(&mut self, index: usize) {
+        if self.requested[index] {
+            return;
+        }
+        let start = index / PROVIDER_PAGE_SIZE * PROVIDER_PAGE_SIZE;
+        let range = start..(start + PROVIDER_PAGE_SIZE).min(self.requested.len());
+        self.requested[range.clone()].fill(true);
+        let later = Later::default();
+        (self.fetch_range)(range.clone(), later.clone());
+        self.pending.push((range, later));
+    }
+
+    /// Moves the ids that have arrived into `ids`, returning whether any did. Indexes that the provider didn't return
+    /// an id for are requested again the next time they're visible.
+    fn receive(&mut self, ids: &mut [Option<ItemID>]) -> bool {
+        let mut received = false;
+        let requested = &mut self.requested;
+        self.pending.retain(|(range, later)| {
+            let Some(fetched) = later.value() else {
+                return true;
+            };
+            let mut fetched = fetched.into_iter();
+            for i in range.clone() {
+                match fetched.next() {
+                    Some(id) => ids[i] = Some(id),
+                    None => requested[i] = false,
+                }
+            }
+            received = true;
+            false
+        });
+        received
+    }
+}
+
 pub type ScrollbarDrawer<A> = fn(&mut Cx<A>, canvas: &mut Canvas, Rect, bool);
+pub type PlaceholderDrawer<A> = fn(&mut Cx<A>, canvas: &mut Canvas, Rect);
+pub type RefreshIndicatorDrawer<A> = fn(&mut Cx<A>, canvas: &mut Canvas, Rect, Option<f32>);
 
 pub struct ScrollList<A: App, ItemID, C> {
     id: ComponentId,
     filtered_item_indexes: Vec<usize>,
+    /// The items that have been created, in the list's order unless they come from an item provider, whose items are
+    /// created once they're visible.
     items: Vec<C>,
-    item_ids: Vec<ItemID>,
+    /// The index in `items` of each item in the list, if it's been created.
+    item_slots: Vec<Option<usize>>,
+    /// Ids from an item provider are `None` until they've been fetched.
+    item_ids: Vec<Option<ItemID>>,
+    provider: Option<ItemProvider<ItemID>>,
+    placeholder_height: f32,
+    draw_placeholder: PlaceholderDrawer<A>,
     create_item: Box<dyn Fn(&mut Cx<A>, ComponentId, ItemID) -> C>,
+    /// The bounds of the filtered items before scrolling, one after the other so they can be binary searched.
     item_bounds: Vec<Rect>,
+    /// The last measured height of each item, in the same order as `items`.
+    measured_heights: Vec<Option<MeasuredHeight>>,
+    measured_width: f32,
     content_height: f32,
     scroll_top: f32,
     scroll_top_on_mouse_down: f32,
@@ -44,21 +187,61 @@ pub struct ScrollList<A: App, ItemID, C> {
     scroll_by_dragging: bool,
     draw_scrollbar: ScrollbarDrawer<A>,
     scrollbar_width: f32,
+    bounce: bool,
+    scroll_chaining: bool,
+    is_touch_drag: bool,
+    spring_back: Option<SpringId>,
+    is_springing_back: bool,
+    selection_mode: SelectionMode,
+    selection: Signal<Vec<ItemID>>,
+    focused_item: Option<ItemID>,
+    selection_anchor: Option<ItemID>,
+    reorderable: bool,
+    reorder: Option<Reorder>,
+    /// How far each filtered item is currently moved out of the way of the lifted one.
+    reorder_offsets: Vec<f32>,
+    reorder_ghost: Option<Overlay<ReorderGhost>>,
+    outputs: Output<ScrollListEvent>,
+    pull_to_refresh: bool,
+    is_refreshing: bool,
+    draw_refresh_indicator: RefreshIndicatorDrawer<A>,
 }
 
 pub trait ScrollListItem<A: App> {
     #[allow(unused)]
     fn set_is_scrolling(&mut self, cx: &mut Cx<A>, is_scrolling_with_cursor: bool) {}
+    /// Lets the list keep the item's measured height instead of measuring it on every layout, for items that are
+    /// costly to measure. Return a version that changes whenever the height may have changed, and the list measures
+    /// the item again only then, or when its width changes. `None`, the default, measures the item every time.
+    fn height_version(&self) -> Option<u64> {
+        None
+    }
     fn get_shown(&self) -> bool {
         true
     }
 }
 
-impl<A: App, ItemId: PartialEq + Clone, C: Component<A> + ScrollListItem<A>> Component<A> for ScrollList<A, ItemId, C> {
+impl<A: App, ItemId: PartialEq + Clone + 'static, C: Component<A> + ScrollListItem<A>> Component<A>
+    for ScrollList<A, ItemId, C>
+{
     fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
         canvas.with_clip_rect(self.get_bounds(cx), |canvas| {
-            for &i in &self.filtered_item_indexes[self.get_visible_items_range(cx)] {
-                self.items[i].draw(cx, canvas);
+            let lifted = self.reorder.as_ref().map(|reorder| reorder.from);
+            for position in self.get_visible_items_range(cx) {
+                match self.get_item(self.filtered_item_indexes[position]) {
+                    _ if Some(position) == lifted => {}
+                    Some(item) => item.draw(cx, canvas),
+                    None => {
+                        let bounds = self.item_bounds[position].with_y_offset(-self.scroll_top);
+                        (self.draw_placeholder)(cx, canvas, bounds);
+                    }
+                }
+            }
+
+            if self.pull_to_refresh && self.scroll_top < 0.0 {
+                let bounds = self.get_bounds(cx).with_height(-self.scroll_top);
+                let progress = (!self.is_refreshing).then(|| (-self.scroll_top / REFRESH_THRESHOLD).min(1.0));
+                (self.draw_refresh_indicator)(cx, canvas, bounds, progress);
             }
 
             if self.scrollbar_id.is_visible(cx) {
@@ -72,16 +255,41 @@ impl<A: App, ItemId: PartialEq + Clone, C: Component<A> + ScrollListItem<A>> Com
         let mut pass_to_items = true;
         match event {
             Event::Update => {
-                if self.scroll_velocity.abs() > 0.001 {
-                    self.scroll_to(cx, self.scroll_top - self.scroll_velocity);
-                    self.scroll_velocity *= 0.995f32.powf(cx.frame_delta_ms);
+                if self.reorder.is_some() {
+                    self.tick_reorder(cx);
+                } else if self.is_springing_back
+                    && let Some(spring) = &self.spring_back
+                {
+                    let top = cx.animations.get_spring(spring);
+                    self.is_springing_back = !cx.animations.is_spring_at_rest(spring);
+                    self.set_scroll_top(cx, top);
+                } else if self.scroll_velocity.abs() > 0.001 {
+                    let top = self.scroll_top - self.scroll_velocity;
+                    if self.bounces() && top != self.clamp_scroll_top(cx, top) {
+                        // let the fling carry on past the edge and have the spring bring it back
+                        let velocity = -self.scroll_velocity * 1000.0 / cx.frame_delta_ms.max(1.0);
+                        self.scroll_velocity = 0.0;
+                        self.start_spring_back(cx, velocity);
+                    } else {
+                        self.scroll_to(cx, top);
+                        self.scroll_velocity *= 0.995f32.powf(cx.frame_delta_ms);
+                    }
                 }
 
+                self.load_visible_items(cx);
                 self.relayout_if_necessary(cx);
             }
-            Event::MouseWheel(delta) => {
-                if self.get_bounds(cx).contains(&cx.main_pointer().position) {
-                    self.scroll_to(cx, self.scroll_top - delta.y);
+            Event::MouseWheel { delta, phase } => {
+                if cx.is_wheel_target(&self.id) {
+                    match phase {
+                        // a new gesture or the end of the platform's own momentum stops any fling we're running
+                        ScrollPhase::Started | ScrollPhase::MomentumEnded => self.scroll_velocity = 0.0,
+                        ScrollPhase::Changed => {}
+                    }
+                    if *phase != ScrollPhase::MomentumEnded {
+                        self.is_springing_back = false;
+                        self.scroll_to(cx, self.scroll_top - delta.to_pixels().y);
+                    }
                     pass_to_items = false;
                 }
             }
@@ -93,6 +301,8 @@ impl<A: App, ItemId: PartialEq + Clone, C: Component<A> + ScrollListItem<A>> Com
                         && self.get_bounds(cx).contains(&pointer.position))
                 {
                     self.scroll_velocity = 0.0;
+                    self.is_springing_back = false;
+                    self.is_touch_drag = !self.scrollbar_hovered && matches!(pointer.id, PointerId::Touch(_));
                     let scrollbar_bounds = self.get_scrollbar_bounds(cx);
                     self.dragging_scroll_handle = scrollbar_bounds.contains(&pointer.position);
 
@@ -105,20 +315,47 @@ impl<A: App, ItemId: PartialEq + Clone, C: Component<A> + ScrollListItem<A>> Com
                         self.dragging_scroll_handle = true;
                     }
 
-                    self.scroll_top_on_mouse_down = self.scroll_top;
+                    // continue from where an overscrolled list was caught without a jump
+                    self.scroll_top_on_mouse_down = self.unrubber_band(cx, self.scroll_top);
 
                     self.is_scrolling_with_cursor = !self.scrollbar_hovered;
                 }
+
+                if self.selection_mode != SelectionMode::None
+                    && !self.scrollbar_hovered
+                    && self.is_visible(cx)
+                    && self.get_bounds(cx).contains(&pointer.position)
+                    && let Some(index) = self.item_index_at(pointer.position.y)
+                {
+                    self.focus(cx);
+                    if cx.mods.meta {
+                        self.toggle_selected(cx, index);
+                    } else {
+                        self.focus_item(cx, index, cx.mods.shift, false);
+                    }
+                }
             }
             Event::PointerMove(pointer) => {
                 if self.is_visible(cx) {
                     if self.is_scrolling_with_cursor && pointer.delta.len() > 7.0 {
-                        for item in &mut self.items {
+                        for item in self.items.iter_mut() {
                             item.set_is_scrolling(cx, true);
                         }
                     }
 
-                    if self.scrollbar_id.is_pressed(pointer) && self.dragging_scroll_handle {
+                    if let Some(reorder) = &mut self.reorder {
+                        reorder.pointer = pointer.position;
+                        self.update_reorder_target(cx);
+                    } else if self.reorderable
+                        && !self.scroll_by_dragging
+                        && !self.dragging_scroll_handle
+                        && pointer.pressed_component.is_some()
+                        && self.get_bounds(cx).contains(&pointer.down_position)
+                        && (pointer.position - pointer.down_position).len() > REORDER_DRAG_DISTANCE
+                        && let Some(index) = self.item_index_at(pointer.down_position.y)
+                    {
+                        self.lift(cx, index, pointer.position);
+                    } else if self.scrollbar_id.is_pressed(pointer) && self.dragging_scroll_handle {
                         let delta_ratio = pointer.delta.y / self.scrollbar_id.get_bounds(cx).height();
                         let delta = delta_ratio * self.content_height;
                         self.scroll_to(cx, self.scroll_top_on_mouse_down + delta);
@@ -126,11 +363,30 @@ impl<A: App, ItemId: PartialEq + Clone, C: Component<A> + ScrollListItem<A>> Com
                         && pointer.pressed_component.is_some()
                         && self.get_bounds(cx).contains(&pointer.down_position)
                     {
-                        self.scroll_to(cx, self.scroll_top_on_mouse_down - pointer.delta.y);
+                        let top = self.scroll_top_on_mouse_down - pointer.delta.y;
+                        if self.bounces() {
+                            let top = self.rubber_band(cx, top);
+                            self.set_scroll_top(cx, top);
+                        } else {
+                            self.scroll_to(cx, top);
+                        }
                     }
                 }
             }
+            Event::LongPress(pointer)
+                if self.reorderable
+                    && self.is_visible(cx)
+                    && !self.scrollbar_hovered
+                    && self.get_bounds(cx).contains(&pointer.down_position) =>
+            {
+                if let Some(index) = self.item_index_at(pointer.down_position.y) {
+                    self.lift(cx, index, pointer.position);
+                }
+            }
             Event::PointerUp(pointer) => {
+                if self.reorder.is_some() {
+                    self.end_reorder(cx, true);
+                }
                 if pointer.is_pressed(&self.scrollbar_id) {
                     self.scrollbar_hovered = false;
                 }
@@ -143,23 +399,87 @@ impl<A: App, ItemId: PartialEq + Clone, C: Component<A> + ScrollListItem<A>> Com
                     }
                     self.is_scrolling_with_cursor = false;
 
-                    for item in &mut self.items {
+                    if self.pull_to_refresh
+                        && self.bounces()
+                        && !self.is_refreshing
+                        && self.scroll_top <= -REFRESH_THRESHOLD
+                    {
+                        self.is_refreshing = true;
+                        self.outputs.add_output(ScrollListEvent::RefreshRequested);
+                    }
+                    if self.bounces() && self.scroll_top != self.clamp_scroll_top(cx, self.scroll_top) {
+                        self.scroll_velocity = 0.0;
+                        self.start_spring_back(cx, 0.0);
+                    }
+
+                    for item in self.items.iter_mut() {
                         item.set_is_scrolling(cx, false);
                     }
                 }
             }
+            Event::Keydown { key, captured, .. }
+                if self.selection_mode != SelectionMode::None
+                    && self.is_focused(cx)
+                    && !self.filtered_item_indexes.is_empty() =>
+            {
+                let last = self.filtered_item_indexes.len() - 1;
+                let focused = self.focused_item.as_ref().and_then(|id| self.filtered_position(id));
+                let page = |list: &Self, cx: &mut Cx<A>, direction: f32| {
+                    let viewport = list.get_bounds(cx).with_y_offset(list.scroll_top);
+                    let bounds = focused.map_or(viewport, |i| list.item_bounds[i]);
+                    let y = bounds.center().y + direction * viewport.height();
+                    list.item_index_at_content_y(y).unwrap_or(if direction < 0.0 { 0 } else { last })
+                };
+                let multi = self.selection_mode == SelectionMode::Multi;
+                let (extend, keep_selection) = (cx.mods.shift, multi && cx.mods.meta);
+                let mut handled = true;
+                let target = match key {
+                    Key::ArrowUp => Some(arrow_target(focused, last, false)),
+                    Key::ArrowDown => Some(arrow_target(focused, last, true)),
+                    Key::PageUp => Some(page(self, cx, -1.0)),
+                    Key::PageDown => Some(page(self, cx, 1.0)),
+                    Key::Home => Some(0),
+                    Key::End => Some(last),
+                    Key::Character(ch) if multi && *ch == " " => {
+                        if let Some(index) = focused {
+                            self.toggle_selected(cx, index);
+                        }
+                        None
+                    }
+                    Key::Character(ch) if multi && cx.mods.meta && ch.eq_ignore_ascii_case("a") => {
+                        let all = self.filtered_item_indexes.iter().filter_map(|&i| self.item_ids[i].clone()).collect();
+                        self.selection.set_if_changed(all);
+                        None
+                    }
+                    _ => {
+                        handled = false;
+                        None
+                    }
+                };
+                if let Some(index) = target {
+                    self.focus_item(cx, index, extend, keep_selection);
+                }
+                if handled {
+                    *captured = true;
+                    pass_to_items = false;
+                }
+            }
             _ => {}
         }
 
         if pass_to_items {
             for &i in &self.filtered_item_indexes[self.get_visible_items_range(cx)] {
-                self.items[i].event(cx, event);
+                if let Some(slot) = self.item_slots[i] {
+                    self.items[slot].dispatch_event(cx, event);
+                }
             }
         }
     }
 
     fn layout(&mut self, cx: &mut Cx<A>, mut bounds: Rect) {
         self.set_bounds(cx, bounds);
+        cx.set_focusable(&self.id, self.selection_mode != SelectionMode::None);
+        cx.set_scroll_chaining(&self.id, self.scroll_chaining);
         self.scrollbar_id.set_bounds(cx, bounds.remove_from(self.scrollbar_width, Side::Right));
         self.update_item_bounds(cx);
     }
@@ -169,7 +489,7 @@ impl<A: App, ItemId: PartialEq + Clone, C: Component<A> + ScrollListItem<A>> Com
     }
 }
 
-impl<A: App, ItemId: Clone + PartialEq, C: Component<A> + ScrollListItem<A>> ScrollList<A, ItemId, C> {
+impl<A: App, ItemId: Clone + PartialEq + 'static, C: Component<A> + ScrollListItem<A>> ScrollList<A, ItemId, C> {
     pub fn new(
         cx: &mut Cx<A>,
         id: ComponentId,
@@ -179,7 +499,13 @@ impl<A: App, ItemId: Clone + PartialEq, C: Component<A> + ScrollListItem<A>> Scr
             filtered_item_indexes: vec![],
             items: vec![],
             item_bounds: vec![],
+            measured_heights: vec![],
+            measured_width: 0.0,
+            item_slots: vec![],
             item_ids: vec![],
+            provider: None,
+            placeholder_height: 32.0,
+            draw_placeholder: A::draw_list_placeholder,
             create_item: Box::new(create_item),
             content_height: 0.0,
             scroll_top: 0.0,
@@ -199,6 +525,23 @@ impl<A: App, ItemId: Clone + PartialEq, C: Component<A> + ScrollListItem<A>> Scr
             scroll_by_dragging: false,
             draw_scrollbar: A::draw_scrollbar,
             scrollbar_width: 8.0,
+            bounce: false,
+            scroll_chaining: true,
+            is_touch_drag: false,
+            spring_back: None,
+            is_springing_back: false,
+            selection_mode: SelectionMode::None,
+            selection: cx.signal(vec![]),
+            focused_item: None,
+            selection_anchor: None,
+            reorderable: false,
+            reorder: None,
+            reorder_offsets: vec![],
+            reorder_ghost: None,
+            outputs: Default::default(),
+            pull_to_refresh: false,
+            is_refreshing: false,
+            draw_refresh_indicator: A::draw_refresh_indicator,
             _p: PhantomData,
         }
     }
@@ -218,6 +561,76 @@ impl<A: App, ItemId: Clone + PartialEq, C: Component<A> + ScrollListItem<A>> Scr
         self
     }
 
+    /// Lets touch drags and flings pull the content past its edges with a rubber-band resistance and springs it
+    /// back on release. Mouse wheel and scrollbar scrolling stay clamped to the content.
+    pub fn with_bounce(mut self, bounce: bool) -> Self {
+        self.bounce = bounce;
+        self
+    }
+
+    /// Whether wheel events go on to the scroll container around the list once it's scrolled to its end, see
+    /// `Cx::set_scroll_chaining`. On by default.
+    pub fn with_scroll_chaining(mut self, chaining: bool) -> Self {
+        self.scroll_chaining = chaining;
+        self
+    }
+
+    /// Lets items be selected by clicking them and with the arrow, Page Up/Down, Home and End keys while the list
+    /// is focused.
+    pub fn with_selection_mode(mut self, mode: SelectionMode) -> Self {
+        self.selection_mode = mode;
+        self
+    }
+
+    /// The height of the items of an item provider that haven't been created yet.
+    pub fn with_placeholder_height(mut self, height: f32) -> Self {
+        self.placeholder_height = height;
+        self
+    }
+
+    pub fn with_placeholder_drawer(mut self, draw_placeholder: PlaceholderDrawer<A>) -> Self {
+        self.draw_placeholder = draw_placeholder;
+        self
+    }
+
+    /// Lets items be dragged to a new position after a long press, or right away when the list doesn't scroll by
+    /// dragging. See `ScrollListEvent::Reordered`. Lists with an item provider can't be reordered.
+    pub fn with_reordering(mut self) -> Self {
+        self.reorderable = true;
+        self
+    }
+
+    /// Lets the list be pulled down past its top with touch to request a refresh, see
+    /// `ScrollListEvent::RefreshRequested`. Touch drags overscroll like with `with_bounce`.
+    pub fn with_pull_to_refresh(mut self) -> Self {
+        self.pull_to_refresh = true;
+        self
+    }
+
+    pub fn with_refresh_indicator_drawer(mut self, draw_refresh_indicator: RefreshIndicatorDrawer<A>) -> Self {
+        self.draw_refresh_indicator = draw_refresh_indicator;
+        self
+    }
+
+    /// Hides the refresh indicator after a `ScrollListEvent::RefreshRequested`.
+    pub fn end_refresh(&mut self, cx: &mut Cx<A>) {
+        if !self.is_refreshing {
+            return;
+        }
+        self.is_refreshing = false;
+        if self.scroll_top < 0.0 {
+            self.start_spring_back(cx, 0.0);
+        }
+    }
+
+    pub fn is_refreshing(&self) -> bool {
+        self.is_refreshing
+    }
+
+    pub fn next_output(&mut self) -> Option<ScrollListEvent> {
+        self.outputs.next_output()
+    }
+
     pub fn set_scroll_by_dragging(&mut self, value: bool) {
         self.scroll_by_dragging = value;
     }
@@ -230,6 +643,26 @@ impl<A: App, ItemId: Clone + PartialEq, C: Component<A> + ScrollListItem<A>> Scr
         &self.scrollbar_id
     }
 
+    /// The ids of the selected items, in the order they were selected.
+    pub fn selection(&self) -> Signal<Vec<ItemId>> {
+        self.selection.clone()
+    }
+
+    pub fn set_selection(&mut self, cx: &mut Cx<A>, ids: impl IntoIterator<Item = ItemId>) {
+        let ids: Vec<_> = ids.into_iter().filter(|id| self.contains_id(id)).collect();
+        if let Some(index) = ids.last().and_then(|id| self.filtered_position(id)) {
+            self.focused_item = ids.last().cloned();
+            self.selection_anchor = self.focused_item.clone();
+            self.scroll_into_view(cx, index);
+        }
+        self.selection.set_if_changed(ids);
+    }
+
+    /// The item that keyboard navigation moves from.
+    pub fn focused_item(&self) -> Option<&ItemId> {
+        self.focused_item.as_ref()
+    }
+
     pub fn set_items_with_create_item(
         &mut self,
         cx: &mut Cx<A>,
@@ -241,34 +674,110 @@ impl<A: App, ItemId: Clone + PartialEq, C: Component<A> + ScrollListItem<A>> Scr
     }
 
     pub fn set_items(&mut self, cx: &mut Cx<A>, ids: impl IntoIterator<Item = ItemId>) {
+        self.end_reorder(cx, false);
+        self.provider = None;
         let mut new_items = vec![];
         let mut new_item_ids = vec![];
+        let mut new_measured_heights = vec![];
+        self.measured_heights.resize(self.item_ids.len(), None);
+        let mut old_items: Vec<_> = std::mem::take(&mut self.items).into_iter().map(Some).collect();
         for item_id in ids {
-            new_item_ids.push(item_id.clone());
-            if let Some(idx) = self.item_ids.iter().position(|id| *id == item_id) {
-                new_items.push(self.items.remove(idx));
+            let existing = self.item_ids.iter().position(|id| id.as_ref() == Some(&item_id)).map(|idx| {
                 self.item_ids.remove(idx);
+                let slot = self.item_slots.remove(idx);
+                (slot.and_then(|slot| old_items[slot].take()), self.measured_heights.remove(idx))
+            });
+            if let Some((Some(item), measured)) = existing {
+                new_items.push(item);
+                new_measured_heights.push(measured);
             } else {
+                let item_id = item_id.clone();
                 new_items.push(cx.add_child(&self.id, |cx, id| (self.create_item)(cx, id, item_id)));
+                new_measured_heights.push(None);
             }
+            new_item_ids.push(Some(item_id));
         }
+        self.item_slots = (0..new_items.len()).map(Some).collect();
         self.items = new_items;
         self.item_ids = new_item_ids;
+        self.measured_heights = new_measured_heights;
+        self.items_changed(cx);
+    }
+
+    /// Shows `count` items whose ids are fetched a page at a time as they scroll into view, instead of all being
+    /// given upfront, e.g. for lists backed by a database. `fetch_range` should set the ids of the items in the
+    /// range it's given on the `Later` once they're loaded, which can be done from another thread. Items are only
+    /// created once they're visible, with a placeholder drawn in their place until then.
+    pub fn set_item_provider(
+        &mut self,
+        cx: &mut Cx<A>,
+        count: usize,
+        fetch_range: impl Fn(Range<usize>, Later<Vec<ItemId>>) + 'static,
+    ) {
+        self.end_reorder(cx, false);
+        self.items = vec![];
+        self.item_slots = vec![None; count];
+        self.item_ids = vec![None; count];
+        self.measured_heights = vec![None; count];
+        self.provider = Some(ItemProvider::new(count, Box::new(fetch_range)));
+        self.items_changed(cx);
+        self.load_visible_items(cx);
+    }
+
+    fn items_changed(&mut self, cx: &mut Cx<A>) {
+        let selection = self.selection.get_fast();
+        if selection.iter().any(|id| !self.contains_id(id)) {
+            self.selection.set(selection.into_iter().filter(|id| self.contains_id(id)).collect());
+        }
+        self.focused_item = self.focused_item.take().filter(|id| self.contains_id(id));
+        self.selection_anchor = self.selection_anchor.take().filter(|id| self.contains_id(id));
         self.scrollbar_id = cx.add_child_id(self.id()).interactive(cx);
         self.update_item_bounds(cx);
     }
 
+    /// Takes in the ids fetched by the item provider, fetches the ones that have become visible and creates their
+    /// items.
+    fn load_visible_items(&mut self, cx: &mut Cx<A>) {
+        let visible = self.get_visible_items_range(cx);
+        let Some(provider) = &mut self.provider else {
+            return;
+        };
+        let mut changed = provider.receive(&mut self.item_ids);
+
+        for &i in &self.filtered_item_indexes[visible] {
+            if self.item_ids[i].is_none() {
+                provider.request(i);
+            }
+            if self.item_slots[i].is_none()
+                && let Some(item_id) = self.item_ids[i].clone()
+            {
+                self.item_slots[i] = Some(self.items.len());
+                self.items.push(cx.add_child(&self.id, |cx, id| (self.create_item)(cx, id, item_id)));
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.update_item_bounds(cx);
+        }
+    }
+
     pub fn get_items(&mut self) -> impl Iterator<Item = &mut C> {
         self.items.iter_mut()
     }
 
     pub fn get_filtered_items(&mut self) -> impl Iterator<Item = &mut C> {
-        self.items
-            .iter_mut()
-            .enumerate()
-            .filter_map(|(i, item)| self.filtered_item_indexes.contains(&i).then_some(item))
+        let mut shown = vec![false; self.items.len()];
+        for &i in &self.filtered_item_indexes {
+            if let Some(slot) = self.item_slots[i] {
+                shown[slot] = true;
+            }
+        }
+        self.items.iter_mut().zip(shown).filter_map(|(item, shown)| shown.then_some(item))
     }
 
+    /// The items that have been created, which are all of them in the list's order unless there's an item provider.
+    /// Use `get_item_mut` to get an item by its index in the list.
     pub fn items_mut(&mut self) -> &mut Vec<C> {
         &mut self.items
     }
@@ -277,31 +786,73 @@ impl<A: App, ItemId: Clone + PartialEq, C: Component<A> + ScrollListItem<A>> Scr
         &self.items
     }
 
+    /// The item at `index` in the list, or `None` if an item provider hasn't created it yet.
+    pub fn get_item(&self, index: usize) -> Option<&C> {
+        self.item_slots.get(index).copied().flatten().map(|slot| &self.items[slot])
+    }
+
+    pub fn get_item_mut(&mut self, index: usize) -> Option<&mut C> {
+        self.item_slots.get(index).copied().flatten().map(|slot| &mut self.items[slot])
+    }
+
+    /// How many items the list has, including the ones an item provider hasn't created yet.
+    pub fn len(&self) -> usize {
+        self.item_ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.item_ids.is_empty()
+    }
+
     fn update_item_bounds(&mut self, cx: &mut Cx<A>) {
         self.filtered_item_indexes =
-            self.items.iter().enumerate().filter_map(|(i, item)| item.get_shown().then_some(i)).collect();
+            (0..self.item_ids.len()).filter(|&i| self.get_item(i).is_none_or(|item| item.get_shown())).collect();
 
         let mut bounds = self.get_bounds(cx);
         if self.content_height > bounds.height() {
             bounds.remove_from(self.scrollbar_width + 2.0, Side::Right);
         }
+        if bounds.width() != self.measured_width {
+            self.measured_width = bounds.width();
+            self.measured_heights.clear();
+        }
+        self.measured_heights.resize(self.item_ids.len(), None);
+
         let mut y = bounds.top();
         self.item_bounds = self
             .filtered_item_indexes
             .iter()
-            .map(|i| {
-                let height = self.items[*i]
-                    .get_preferred_size(cx, bounds)
-                    .1
-                    .unwrap_or_else(|| panic!("Each scroll list item must declare its own height!"));
+            .map(|&i| {
+                let Some(slot) = self.item_slots[i] else {
+                    let bounds = Rect::from_xywh(bounds.left(), y, bounds.width(), self.placeholder_height);
+                    y += self.placeholder_height;
+                    return bounds;
+                };
+                let item = &mut self.items[slot];
+                let version = item.height_version();
+                let height = match (self.measured_heights[i], version) {
+                    (Some(measured), Some(version)) if measured.version == version => measured.height,
+                    _ => {
+                        let height = item
+                            .get_preferred_size(cx, bounds)
+                            .1
+                            .unwrap_or_else(|| panic!("Each scroll list item must declare its own height!"));
+                        self.measured_heights[i] = version.map(|version| MeasuredHeight { height, version });
+                        height
+                    }
+                };
                 let bounds = Rect::from_xywh(bounds.left(), y, bounds.width(), height);
                 y += height;
                 bounds
             })
             .collect();
-        self.content_height = self.item_bounds.iter().map(|b| b.height()).sum::<f32>().max(bounds.height());
+        self.content_height = (y - bounds.top()).max(bounds.height());
         self.scrollbar_id.set_visible(cx, self.content_height > bounds.height());
-        self.scroll_to(cx, self.scroll_top);
+        if self.is_springing_back {
+            self.set_scroll_top(cx, self.scroll_top);
+        } else {
+            self.scroll_to(cx, self.scroll_top);
+        }
     }
 
     fn get_scrollbar_bounds(&self, cx: &mut Cx<A>) -> Rect {
@@ -317,44 +868,429 @@ impl<A: App, ItemId: Clone + PartialEq, C: Component<A> + ScrollListItem<A>> Scr
 
     fn get_visible_items_range(&self, cx: &mut Cx<A>) -> Range<usize> {
         self.visible_items.get(|| {
-            let content_bounds = self.get_bounds(cx);
-            let viewport_top = content_bounds.top();
-            let viewport_height = content_bounds.height();
-            let mut first = 0;
-            let mut last = 0;
-            for (i, bounds) in self.item_bounds.iter().enumerate() {
-                if bounds.bottom() >= viewport_top + self.scroll_top {
-                    first = i;
-                    break;
-                }
+            let viewport = self.get_bounds(cx).with_y_offset(self.scroll_top);
+            let first = self.item_bounds.partition_point(|bounds| bounds.bottom() < viewport.top());
+            let end = self.item_bounds.partition_point(|bounds| bounds.top() <= viewport.bottom());
+            if first < end { first..end } else { 0..0 }
+        })
+    }
+
+    fn contains_id(&self, id: &ItemId) -> bool {
+        self.item_ids.iter().any(|item_id| item_id.as_ref() == Some(id))
+    }
+
+    fn filtered_position(&self, id: &ItemId) -> Option<usize> {
+        self.filtered_item_indexes.iter().position(|&i| self.item_ids[i].as_ref() == Some(id))
+    }
+
+    fn item_index_at_content_y(&self, y: f32) -> Option<usize> {
+        let index = self.item_bounds.partition_point(|bounds| bounds.bottom() <= y);
+        self.item_bounds.get(index).is_some_and(|bounds| bounds.top() <= y).then_some(index)
+    }
+
+    /// The position in the filtered items of the one under `y` in window coordinates.
+    fn item_index_at(&self, y: f32) -> Option<usize> {
+        self.item_index_at_content_y(y + self.scroll_top)
+    }
+
+    /// Moves the focus to the filtered item at `index`. With `extend` the selection becomes the range from the
+    /// anchor, with `keep_selection` only the focus moves.
+    fn focus_item(&mut self, cx: &mut Cx<A>, index: usize, extend: bool, keep_selection: bool) {
+        let Some(id) = self.item_ids[self.filtered_item_indexes[index]].clone() else {
+            // not loaded yet, so it can't be selected
+            self.scroll_into_view(cx, index);
+            return;
+        };
+        match self.selection_mode {
+            SelectionMode::None => return,
+            SelectionMode::Single => {
+                self.selection.set_if_changed(vec![id.clone()]);
+                self.selection_anchor = Some(id.clone());
             }
-            for (i, bounds) in self.item_bounds.iter().enumerate().rev() {
-                if bounds.top() <= viewport_top + self.scroll_top + viewport_height {
-                    last = i + 1;
-                    break;
+            SelectionMode::Multi if keep_selection => {}
+            SelectionMode::Multi => {
+                let anchor = self.selection_anchor.as_ref().and_then(|id| self.filtered_position(id));
+                match anchor {
+                    Some(anchor) if extend => {
+                        let ids =
+                            range_selection(anchor, index, |i| self.item_ids[self.filtered_item_indexes[i]].clone());
+                        self.selection.set_if_changed(ids);
+                    }
+                    _ => {
+                        self.selection.set_if_changed(vec![id.clone()]);
+                        self.selection_anchor = Some(id.clone());
+                    }
                 }
             }
-            first..last
-        })
+        }
+        self.focused_item = Some(id);
+        self.scroll_into_view(cx, index);
+    }
+
+    fn toggle_selected(&mut self, cx: &mut Cx<A>, index: usize) {
+        if self.selection_mode != SelectionMode::Multi {
+            self.focus_item(cx, index, false, false);
+            return;
+        }
+        let Some(id) = self.item_ids[self.filtered_item_indexes[index]].clone() else {
+            return;
+        };
+        self.selection.mutate(|mut selection| toggle_selection(&mut selection, id.clone()));
+        self.selection_anchor = Some(id.clone());
+        self.focused_item = Some(id);
+        self.scroll_into_view(cx, index);
+    }
+
+    fn scroll_into_view(&mut self, cx: &mut Cx<A>, index: usize) {
+        let viewport = self.get_bounds(cx);
+        let item = self.item_bounds[index];
+        let top = item.top() - viewport.top();
+        self.scroll_to(cx, self.scroll_top.max(top + item.height() - viewport.height()).min(top));
+    }
+
+    fn lift(&mut self, cx: &mut Cx<A>, index: usize, pointer: Point) {
+        if self.reorder.is_some() || self.provider.is_some() {
+            return;
+        }
+        self.scroll_velocity = 0.0;
+        self.is_springing_back = false;
+        if self.is_scrolling_with_cursor {
+            self.is_scrolling_with_cursor = false;
+            for item in self.items.iter_mut() {
+                item.set_is_scrolling(cx, false);
+            }
+        }
+
+        let Some(item) = self.get_item(self.filtered_item_indexes[index]) else {
+            return;
+        };
+        let bounds = item.get_bounds(cx);
+        let surface = cx.create_surface(bounds.size().to_int(), cx.scale_factor.get_fast());
+        {
+            let mut canvas = surface.get_canvas();
+            canvas.set_scale_factor(cx.scale_factor.get_fast());
+            canvas.translate((-bounds.left(), -bounds.top()));
+            item.draw(cx, &mut canvas);
+        }
+        let ghost =
+            self.reorder_ghost.get_or_insert_with(|| cx.add_overlay(|_, id| ReorderGhost { id, surface: None }));
+        ghost.borrow_mut().surface = Some(surface);
+
+        let grab_offset = pointer - bounds.relative_point((0.0, 0.0));
+        self.reorder = Some(Reorder { from: index, to: index, grab_offset, pointer });
+        self.reorder_offsets = vec![0.0; self.filtered_item_indexes.len()];
+        self.update_reorder_target(cx);
+    }
+
+    /// Moves the ghost to follow the pointer and works out where the lifted item would be dropped.
+    fn update_reorder_target(&mut self, cx: &mut Cx<A>) {
+        let Some(reorder) = &mut self.reorder else {
+            return;
+        };
+        let lifted = self.item_bounds[reorder.from];
+        let top = reorder.pointer.y - reorder.grab_offset.y;
+        let center = top + self.scroll_top + lifted.height() * 0.5;
+        reorder.to = drop_index(&self.item_bounds, reorder.from, center);
+        if let Some(ghost) = &self.reorder_ghost {
+            ghost.borrow_mut().layout(cx, lifted.with_top(top).with_height(lifted.height()));
+        }
+    }
+
+    fn tick_reorder(&mut self, cx: &mut Cx<A>) {
+        let Some(reorder) = &self.reorder else {
+            return;
+        };
+
+        // scroll when the item is held near the edges
+        let bounds = self.get_bounds(cx);
+        let y = reorder.pointer.y;
+        let speed = if y < bounds.top() + REORDER_SCROLL_EDGE {
+            y - bounds.top() - REORDER_SCROLL_EDGE
+        } else if y > bounds.bottom() - REORDER_SCROLL_EDGE {
+            y - bounds.bottom() + REORDER_SCROLL_EDGE
+        } else {
+            0.0
+        };
+        if speed != 0.0 {
+            let speed = speed.clamp(-REORDER_SCROLL_EDGE, REORDER_SCROLL_EDGE) * 0.02;
+            self.scroll_to(cx, self.scroll_top + speed * cx.frame_delta_ms);
+            self.update_reorder_target(cx);
+        }
+
+        let Some(reorder) = &self.reorder else {
+            return;
+        };
+        let height = self.item_bounds[reorder.from].height();
+        let t = 1.0 - 0.5f32.powf(cx.frame_delta_ms / 40.0);
+        for (i, offset) in self.reorder_offsets.iter_mut().enumerate() {
+            *offset += (reorder.offset(i, height) - *offset) * t;
+        }
+        self.set_scroll_top(cx, self.scroll_top);
+    }
+
+    fn end_reorder(&mut self, cx: &mut Cx<A>, commit: bool) {
+        let Some(reorder) = self.reorder.take() else {
+            return;
+        };
+        if let Some(ghost) = &self.reorder_ghost {
+            ghost.borrow_mut().surface = None;
+        }
+        self.reorder_offsets.clear();
+        if commit && reorder.from != reorder.to {
+            let from = self.filtered_item_indexes[reorder.from];
+            let to = self.filtered_item_indexes[reorder.to];
+            move_item(&mut self.items, from, to);
+            move_item(&mut self.measured_heights, from, to);
+            move_item(&mut self.item_ids, from, to);
+            self.outputs.add_output(ScrollListEvent::Reordered { from, to });
+        }
+        self.update_item_bounds(cx);
+    }
+
+    fn bounces(&self) -> bool {
+        (self.bounce || self.pull_to_refresh) && self.is_touch_drag
+    }
+
+    /// Negative while refreshing, to keep the refresh indicator in view.
+    fn min_scroll_top(&self) -> f32 {
+        if self.is_refreshing {
+            -REFRESH_INDICATOR_HEIGHT
+        } else {
+            0.0
+        }
+    }
+
+    fn clamp_scroll_top(&self, cx: &mut Cx<A>, top: f32) -> f32 {
+        top.clamp(self.min_scroll_top(), self.max_scroll_top(cx))
+    }
+
+    fn max_scroll_top(&self, cx: &mut Cx<A>) -> f32 {
+        (self.content_height - self.get_bounds(cx).height()).max(0.0)
+    }
+
+    /// Maps a scroll position past the edges onto a resisted one that approaches, but never reaches, a full
+    /// viewport of overscroll.
+    fn rubber_band(&self, cx: &mut Cx<A>, top: f32) -> f32 {
+        let (min, max) = (self.min_scroll_top(), self.max_scroll_top(cx));
+        let extent = self.get_bounds(cx).height().max(1.0);
+        let band = |excess: f32| (1.0 - 1.0 / (excess * RUBBER_BAND_COEFFICIENT / extent + 1.0)) * extent;
+        if top < min {
+            min - band(min - top)
+        } else if top > max {
+            max + band(top - max)
+        } else {
+            top
+        }
+    }
+
+    /// The inverse of [`Self::rubber_band`].
+    fn unrubber_band(&self, cx: &mut Cx<A>, top: f32) -> f32 {
+        let (min, max) = (self.min_scroll_top(), self.max_scroll_top(cx));
+        let extent = self.get_bounds(cx).height().max(1.0);
+        let unband = |banded: f32| (1.0 / (1.0 - (banded / extent).min(0.99)) - 1.0) * extent / RUBBER_BAND_COEFFICIENT;
+        if top < min {
+            min - unband(min - top)
+        } else if top > max {
+            max + unband(top - max)
+        } else {
+            top
+        }
+    }
+
+    fn start_spring_back(&mut self, cx: &mut Cx<A>, velocity: f32) {
+        let target = self.clamp_scroll_top(cx, self.scroll_top);
+        let spring = self.spring_back.get_or_insert_with(|| cx.add_spring(Spring::critically_damped(170.0), 0.0));
+        cx.animations.reset_spring(spring, self.scroll_top);
+        cx.animations.set_spring_target(spring, target);
+        cx.animations.set_spring_velocity(spring, velocity);
+        self.is_springing_back = true;
     }
 
     fn scroll_to(&mut self, cx: &mut Cx<A>, top: f32) {
-        self.scroll_top = top.clamp(0.0, self.content_height - self.get_bounds(cx).height());
+        let top = self.clamp_scroll_top(cx, top);
+        self.set_scroll_top(cx, top);
+    }
+
+    fn set_scroll_top(&mut self, cx: &mut Cx<A>, top: f32) {
+        self.scroll_top = top;
+        cx.invalidate(&self.id);
         self.visible_items.invalidate();
         self.scrollbar_bounds.invalidate();
+        let can_scroll = CanScroll::vertical(top, self.min_scroll_top(), self.max_scroll_top(cx));
+        cx.set_can_scroll(&self.id, can_scroll);
 
         let range = self.get_visible_items_range(cx);
 
-        for (i, item) in self.items.iter_mut().enumerate() {
-            if let Some(filtered_idx) = self.filtered_item_indexes.iter().position(|j| *j == i) {
-                if range.contains(&filtered_idx) {
-                    let bounds = self.item_bounds[filtered_idx];
-                    item.layout(cx, bounds.with_top(bounds.top() - self.scroll_top).with_height(bounds.height()));
-                    item.set_visible(cx, true);
-                } else {
-                    item.set_visible(cx, false);
-                }
+        for (filtered_idx, &i) in self.filtered_item_indexes.iter().enumerate() {
+            let Some(slot) = self.item_slots[i] else {
+                continue;
+            };
+            let item = &mut self.items[slot];
+            if range.contains(&filtered_idx) {
+                let bounds = self.item_bounds[filtered_idx];
+                let offset = self.reorder_offsets.get(filtered_idx).copied().unwrap_or(0.0);
+                let top = bounds.top() - self.scroll_top + offset;
+                item.layout(cx, bounds.with_top(top).with_height(bounds.height()));
+                item.set_visible(cx, true);
+            } else {
+                item.set_visible(cx, false);
             }
         }
     }
 }
+
+/// The item that the arrow keys move the focus to, starting from either end when nothing is focused yet.
+fn arrow_target(focused: Option<usize>, last: usize, down: bool) -> usize {
+    match focused {
+        Some(i) if down => (i + 1).min(last),
+        Some(i) => i.saturating_sub(1),
+        None if down => 0,
+        None => last,
+    }
+}
+
+/// The ids from `anchor` to `index` that extending the selection covers. The newly focused item is kept last like with
+/// any other selection.
+fn range_selection<T>(anchor: usize, index: usize, id_at: impl Fn(usize) -> Option<T>) -> Vec<T> {
+    let ids = (anchor.min(index)..=anchor.max(index)).filter_map(id_at);
+    if index < anchor {
+        ids.rev().collect()
+    } else {
+        ids.collect()
+    }
+}
+
+/// Adds `id` to the selection, or removes it if it's already selected.
+fn toggle_selection<T: PartialEq>(selection: &mut Vec<T>, id: T) {
+    if let Some(position) = selection.iter().position(|selected| *selected == id) {
+        selection.remove(position);
+    } else {
+        selection.push(id);
+    }
+}
+
+/// Where the item lifted from `from` would be dropped with its center at `center`: after every other item whose center
+/// is above it.
+fn drop_index(item_bounds: &[Rect], from: usize, center: f32) -> usize {
+    item_bounds.iter().enumerate().filter(|(i, bounds)| *i != from && bounds.center().y < center).count()
+}
+
+fn move_item<T>(items: &mut Vec<T>, from: usize, to: usize) {
+    let item = items.remove(from);
+    items.insert(to, item);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    type Fetches = Rc<RefCell<Vec<(Range<usize>, Later<Vec<usize>>)>>>;
+
+    /// A provider that records what it was asked for, with the `Later`s to answer with.
+    fn recording_provider(count: usize) -> (ItemProvider<usize>, Fetches) {
+        let fetches = Rc::new(RefCell::new(vec![]));
+        let recorded = fetches.clone();
+        let provider =
+            ItemProvider::new(count, Box::new(move |range, later| recorded.borrow_mut().push((range, later))));
+        (provider, fetches)
+    }
+
+    #[test]
+    fn provider_fetches_each_page_once() {
+        let (mut provider, fetches) = recording_provider(120);
+        provider.request(3);
+        provider.request(7);
+        provider.request(110);
+        let ranges: Vec<_> = fetches.borrow().iter().map(|(range, _)| range.clone()).collect();
+        assert_eq!(ranges, [0..PROVIDER_PAGE_SIZE, 100..120]);
+    }
+
+    #[test]
+    fn provider_fills_in_ids_once_they_arrive() {
+        let (mut provider, fetches) = recording_provider(60);
+        let mut ids = vec![None; 60];
+        provider.request(55);
+        assert!(!provider.receive(&mut ids));
+
+        let (range, later) = fetches.borrow()[0].clone();
+        later.set(range.clone().map(|i| i * 10).collect());
+        assert!(provider.receive(&mut ids));
+        assert_eq!(ids[50], Some(500));
+        assert_eq!(ids[59], Some(590));
+        assert_eq!(ids[0], None);
+        assert!(provider.pending.is_empty());
+    }
+
+    #[test]
+    fn provider_requests_missing_ids_again() {
+        let (mut provider, fetches) = recording_provider(10);
+        let mut ids = vec![None; 10];
+        provider.request(0);
+        fetches.borrow()[0].1.set(vec![1, 2, 3]);
+        assert!(provider.receive(&mut ids));
+        assert_eq!(&ids[..4], [Some(1), Some(2), Some(3), None]);
+
+        provider.request(2);
+        assert_eq!(fetches.borrow().len(), 1);
+        provider.request(5);
+        assert_eq!(fetches.borrow().len(), 2);
+        assert_eq!(fetches.borrow()[1].0, 0..10);
+    }
+
+    #[test]
+    fn arrow_keys_stop_at_the_ends() {
+        assert_eq!(arrow_target(None, 4, true), 0);
+        assert_eq!(arrow_target(None, 4, false), 4);
+        assert_eq!(arrow_target(Some(2), 4, true), 3);
+        assert_eq!(arrow_target(Some(4), 4, true), 4);
+        assert_eq!(arrow_target(Some(0), 4, false), 0);
+    }
+
+    #[test]
+    fn range_selection_ends_with_the_focused_item() {
+        let ids = ["a", "b", "c", "d", "e"];
+        let id_at = |i: usize| ids.get(i).copied();
+        assert_eq!(range_selection(1, 3, id_at), ["b", "c", "d"]);
+        assert_eq!(range_selection(3, 1, id_at), ["d", "c", "b"]);
+        // items that haven't loaded yet are left out
+        assert_eq!(range_selection(0, 2, |i| (i != 1).then_some(i)), [0, 2]);
+    }
+
+    #[test]
+    fn toggling_adds_and_removes_from_the_selection() {
+        let mut selection = vec!["a", "b"];
+        toggle_selection(&mut selection, "c");
+        assert_eq!(selection, ["a", "b", "c"]);
+        toggle_selection(&mut selection, "a");
+        assert_eq!(selection, ["b", "c"]);
+    }
+
+    #[test]
+    fn dragging_an_item_down_moves_the_ones_it_passes_up() {
+        let item_bounds: Vec<_> = (0..4).map(|i| Rect::from_xywh(0.0, i as f32 * 10.0, 100.0, 10.0)).collect();
+        // the first item held with its center just past the third one's
+        let to = drop_index(&item_bounds, 0, 26.0);
+        assert_eq!(to, 2);
+        assert_eq!(drop_index(&item_bounds, 0, 24.0), 1);
+
+        let reorder = Reorder { from: 0, to, grab_offset: point(0.0, 0.0), pointer: point(0.0, 0.0) };
+        let offsets: Vec<_> = (0..4).map(|i| reorder.offset(i, 10.0)).collect();
+        assert_eq!(offsets, [0.0, -10.0, -10.0, 0.0]);
+
+        let mut items = vec!["a", "b", "c", "d"];
+        move_item(&mut items, 0, to);
+        assert_eq!(items, ["b", "c", "a", "d"]);
+    }
+
+    #[test]
+    fn dragging_an_item_up_moves_the_ones_it_passes_down() {
+        let reorder = Reorder { from: 3, to: 1, grab_offset: point(0.0, 0.0), pointer: point(0.0, 0.0) };
+        let offsets: Vec<_> = (0..4).map(|i| reorder.offset(i, 10.0)).collect();
+        assert_eq!(offsets, [0.0, 10.0, 10.0, 0.0]);
+
+        let mut items = vec!["a", "b", "c", "d"];
+        move_item(&mut items, 3, 1);
+        assert_eq!(items, ["a", "d", "b", "c"]);
+    }
+}
@@ -0,0 +1,343 @@
+use std::cell::{Cell, RefCell};
+
+use crate::*;
+
+/// The space between cells.
+const GAP: f32 = 2.0;
+/// How far a cell has to be dragged vertically to go from silent to full velocity.
+const VELOCITY_DRAG_RANGE: f32 = 100.0;
+/// How far a press on a cell has to move before it's known whether it sets the velocity or paints.
+const DRAG_THRESHOLD: f32 = 4.0;
+
+pub enum StepGridEvent {
+    /// A cell was turned on or off, or had its velocity changed. `velocity` is `None` for cells that are off.
+    CellChanged { row: usize, column: usize, velocity: Option<f32> },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Drag {
+    /// Pressed on a cell that was on, which is turned off unless the drag turns out to set its velocity.
+    Pending {
+        cell: usize,
+        velocity_on_down: f32,
+    },
+    Velocity {
+        cell: usize,
+        velocity_on_down: f32,
+    },
+    /// Cells dragged over are turned on, or off if the drag started on a cell that was on.
+    Paint {
+        on: bool,
+    },
+}
+
+/// A grid of steps for sequencers and piano rolls, with a row per note or sound and a column per step. Cells are
+/// turned on and off by clicking them or painting over them, and the velocity of a cell that's on is set by dragging
+/// it up or down. The cells are kept drawn in a surface that only the ones that changed are redrawn into, so large
+/// grids stay cheap to draw while a playhead moves over them.
+pub struct StepGrid {
+    pub id: ComponentId,
+    rows: usize,
+    columns: usize,
+    /// The velocity of each cell, row by row.
+    cells: Vec<Option<f32>>,
+    default_velocity: f32,
+    /// Columns are shaded in groups of this many, e.g. 4 for the steps of a beat.
+    group_size: usize,
+    drag: Option<Drag>,
+    playhead: Option<Signal<f32>>,
+    surface: RefCell<Option<Surface>>,
+    /// The scale factor that `surface` was created for.
+    surface_scale_factor: f32,
+    dirty_cells: RefCell<Vec<usize>>,
+    redraw_all: Cell<bool>,
+    cell_color: Computed<Color>,
+    alternate_cell_color: Computed<Color>,
+    active_color: Computed<Color>,
+    background_color: Computed<Color>,
+    playhead_color: Computed<Color>,
+    outputs: Output<StepGridEvent>,
+}
+
+impl StepGrid {
+    pub fn new<A: App>(cx: &mut Cx<A>, id: ComponentId, rows: usize, columns: usize) -> Self {
+        cx.set_interactive(&id, true);
+        Self {
+            id,
+            rows,
+            columns,
+            cells: vec![None; rows * columns],
+            default_velocity: 0.8,
+            group_size: 4,
+            drag: None,
+            playhead: None,
+            surface: RefCell::new(None),
+            surface_scale_factor: 0.0,
+            dirty_cells: RefCell::new(vec![]),
+            redraw_all: Cell::new(true),
+            cell_color: rgb(0x2c2c2e).into(),
+            alternate_cell_color: rgb(0x3a3a3c).into(),
+            active_color: rgb(0x0a84ff).into(),
+            background_color: rgb(0x1c1c1e).into(),
+            playhead_color: rgb(0xffffff).with_alpha(0.6).into(),
+            outputs: Default::default(),
+        }
+    }
+
+    /// Draws a playhead at `position`, in steps from the start of the first column.
+    pub fn with_playhead(mut self, position: Signal<f32>) -> Self {
+        self.playhead = Some(position);
+        self
+    }
+
+    /// The velocity of the cells that are turned on by clicking them.
+    pub fn with_default_velocity(mut self, velocity: f32) -> Self {
+        self.default_velocity = velocity.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Shades every other group of `group_size` columns, or none with 0.
+    pub fn with_group_size(mut self, group_size: usize) -> Self {
+        self.group_size = group_size;
+        self
+    }
+
+    pub fn with_colors(
+        mut self,
+        cell: impl Into<Computed<Color>>,
+        alternate_cell: impl Into<Computed<Color>>,
+        active: impl Into<Computed<Color>>,
+    ) -> Self {
+        self.cell_color = cell.into();
+        self.alternate_cell_color = alternate_cell.into();
+        self.active_color = active.into();
+        self
+    }
+
+    /// The color between the cells, which has to be opaque since changed cells are drawn over the old ones.
+    pub fn with_background_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.background_color = color.into();
+        self
+    }
+
+    pub fn with_playhead_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.playhead_color = color.into();
+        self
+    }
+
+    pub fn next_output(&mut self) -> Option<StepGridEvent> {
+        self.outputs.next_output()
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Changes the number of rows and columns, keeping the cells that are still within them.
+    pub fn set_size(&mut self, rows: usize, columns: usize) {
+        let mut cells = vec![None; rows * columns];
+        for row in 0..rows.min(self.rows) {
+            for column in 0..columns.min(self.columns) {
+                cells[row * columns + column] = self.cells[row * self.columns + column];
+            }
+        }
+        self.rows = rows;
+        self.columns = columns;
+        self.cells = cells;
+        self.drag = None;
+        self.redraw_all.set(true);
+    }
+
+    pub fn get_cell(&self, row: usize, column: usize) -> Option<f32> {
+        if row >= self.rows || column >= self.columns {
+            return None;
+        }
+        self.cells[row * self.columns + column]
+    }
+
+    /// Sets a cell without sending a `StepGridEvent`.
+    pub fn set_cell(&mut self, row: usize, column: usize, velocity: Option<f32>) {
+        if row < self.rows && column < self.columns {
+            self.set_cell_at(row * self.columns + column, velocity);
+        }
+    }
+
+    fn set_cell_at(&mut self, cell: usize, velocity: Option<f32>) -> bool {
+        if self.cells[cell] == velocity {
+            return false;
+        }
+        self.cells[cell] = velocity;
+        self.dirty_cells.borrow_mut().push(cell);
+        true
+    }
+
+    fn change_cell(&mut self, cell: usize, velocity: Option<f32>) {
+        if self.set_cell_at(cell, velocity) {
+            let (row, column) = (cell / self.columns, cell % self.columns);
+            self.outputs.add_output(StepGridEvent::CellChanged { row, column, velocity });
+        }
+    }
+
+    fn get_cell_size<A: App>(&self, cx: &Cx<A>) -> Point {
+        let size = self.get_bounds(cx).size();
+        point(size.x / self.columns.max(1) as f32, size.y / self.rows.max(1) as f32)
+    }
+
+    /// The bounds of `cell` relative to the grid's top left corner, including the gap around it.
+    fn get_cell_rect(&self, cell_size: Point, cell: usize) -> Rect {
+        let (row, column) = (cell / self.columns, cell % self.columns);
+        Rect::from_xywh(column as f32 * cell_size.x, row as f32 * cell_size.y, cell_size.x, cell_size.y)
+    }
+
+    fn cell_at<A: App>(&self, cx: &Cx<A>, position: Point) -> Option<usize> {
+        let bounds = self.get_bounds(cx);
+        if !bounds.contains(&position) || self.columns == 0 || self.rows == 0 {
+            return None;
+        }
+        let cell_size = self.get_cell_size(cx);
+        let column = (((position.x - bounds.left()) / cell_size.x) as usize).min(self.columns - 1);
+        let row = (((position.y - bounds.top()) / cell_size.y) as usize).min(self.rows - 1);
+        Some(row * self.columns + column)
+    }
+
+    fn draw_cell(&self, canvas: &mut Canvas, cell_size: Point, cell: usize) {
+        let rect = self.get_cell_rect(cell_size, cell);
+        canvas.fill(self.background_color.get()).draw_rect(rect);
+        let inner = rect.with_margin(Margin::even(GAP * 0.5));
+        let column = cell % self.columns;
+        let alternate = self.group_size > 0 && (column / self.group_size) % 2 == 1;
+        let color = if alternate {
+            self.alternate_cell_color.get()
+        } else {
+            self.cell_color.get()
+        };
+        canvas.fill(color).draw_round_rect(inner, 2.0);
+        if let Some(velocity) = self.cells[cell] {
+            let active = self.active_color.get();
+            canvas.fill(active.with_alpha_mul(0.35)).draw_round_rect(inner, 2.0);
+            let level = inner.with_top(inner.bottom() - inner.height() * velocity);
+            canvas.fill(active).draw_round_rect(level, 2.0);
+        }
+    }
+
+    /// Redraws the cells that changed since the last frame into the surface, or all of them if it was recreated.
+    fn update_surface<A: App>(&self, cx: &Cx<A>) {
+        let surface = self.surface.borrow();
+        let Some(surface) = surface.as_ref() else {
+            return;
+        };
+        // every cell is drawn with these, so all of them are redrawn when any changes
+        let colors_changed = [
+            &self.cell_color,
+            &self.alternate_cell_color,
+            &self.active_color,
+            &self.background_color,
+        ]
+        .map(|color| color.next().is_some());
+        if colors_changed.contains(&true) {
+            self.redraw_all.set(true);
+        }
+        let dirty: Vec<_> = if self.redraw_all.replace(false) {
+            self.dirty_cells.borrow_mut().clear();
+            (0..self.cells.len()).collect()
+        } else {
+            std::mem::take(&mut *self.dirty_cells.borrow_mut())
+        };
+        if dirty.is_empty() || self.columns == 0 {
+            return;
+        }
+        let cell_size = self.get_cell_size(cx);
+        let mut canvas = surface.get_canvas();
+        canvas.set_scale_factor(cx.scale_factor.get_fast());
+        for cell in dirty {
+            self.draw_cell(&mut canvas, cell_size, cell);
+        }
+    }
+}
+
+impl<A: App> Component<A> for StepGrid {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let bounds = self.get_bounds(cx);
+        self.update_surface(cx);
+        if let Some(surface) = &*self.surface.borrow() {
+            canvas.draw_surface(surface, bounds.relative_point((0.0, 0.0)));
+        }
+
+        if let Some(playhead) = &self.playhead
+            && self.columns > 0
+        {
+            let position = playhead.get_fast();
+            if (0.0..self.columns as f32).contains(&position) {
+                let x = bounds.left() + position * bounds.width() / self.columns as f32;
+                canvas.fill(self.playhead_color.get()).draw_rect(bounds.with_left(x - 1.0).with_width(2.0));
+            }
+        }
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        match event {
+            Event::PointerDown(pointer) if self.is_hovered(pointer) => {
+                if let Some(cell) = self.cell_at(cx, pointer.position) {
+                    self.drag = Some(match self.cells[cell] {
+                        Some(velocity_on_down) => Drag::Pending { cell, velocity_on_down },
+                        None => {
+                            self.change_cell(cell, Some(self.default_velocity));
+                            Drag::Paint { on: true }
+                        }
+                    });
+                }
+            }
+            Event::PointerMove(pointer) if self.is_pressed(pointer) => {
+                let moved = pointer.position - pointer.down_position;
+                match self.drag {
+                    Some(Drag::Pending { cell, velocity_on_down }) if moved.len() > DRAG_THRESHOLD => {
+                        if moved.y.abs() > moved.x.abs() {
+                            self.drag = Some(Drag::Velocity { cell, velocity_on_down });
+                        } else {
+                            self.change_cell(cell, None);
+                            self.drag = Some(Drag::Paint { on: false });
+                        }
+                    }
+                    Some(Drag::Velocity { cell, velocity_on_down }) => {
+                        let velocity = (velocity_on_down - moved.y / VELOCITY_DRAG_RANGE).clamp(0.0, 1.0);
+                        self.change_cell(cell, Some(velocity));
+                    }
+                    Some(Drag::Paint { on }) => {
+                        if let Some(cell) = self.cell_at(cx, pointer.position) {
+                            let velocity = on.then_some(self.cells[cell].unwrap_or(self.default_velocity));
+                            self.change_cell(cell, velocity);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::PointerUp(_) => {
+                if let Some(Drag::Pending { cell, .. }) = self.drag.take() {
+                    self.change_cell(cell, None);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        let scale_factor = cx.scale_factor.get_fast();
+        if bounds.size() != self.get_bounds(cx).size()
+            || self.surface.borrow().is_none()
+            || scale_factor != self.surface_scale_factor
+        {
+            *self.surface.borrow_mut() = Some(cx.create_surface(bounds.size().to_int(), scale_factor));
+            self.surface_scale_factor = scale_factor;
+            self.redraw_all.set(true);
+        }
+        self.set_bounds(cx, bounds);
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
@@ -0,0 +1,459 @@
+use std::{
+    cell::{Cell, RefCell},
+    ops::Range,
+    rc::Rc,
+};
+
+use crate::*;
+
+const CELL_PADDING: f32 = 8.0;
+/// How close to the edge between two columns in the header a press has to be to resize the column on its left.
+const RESIZE_HANDLE_WIDTH: f32 = 8.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColumnWidth {
+    Fixed(f32),
+    /// Takes a share of the width left over from the fixed columns, in proportion to the other flexible ones.
+    Flex(f32),
+}
+
+pub struct Column {
+    title: String,
+    width: ColumnWidth,
+    min_width: f32,
+    sortable: bool,
+}
+
+impl Column {
+    pub fn new(title: impl Into<String>, width: ColumnWidth) -> Self {
+        Self { title: title.into(), width, min_width: 24.0, sortable: false }
+    }
+
+    /// Lets the column be resized no narrower than `min_width`, which flexible columns also never shrink below.
+    pub fn with_min_width(mut self, min_width: f32) -> Self {
+        self.min_width = min_width;
+        self
+    }
+
+    /// Makes clicking the column's header send a `TableEvent::SortChanged`.
+    pub fn sortable(mut self) -> Self {
+        self.sortable = true;
+        self
+    }
+}
+
+pub enum TableEvent<RowId> {
+    /// A sortable column's header was clicked. The table doesn't reorder its rows itself, so sort them and pass them
+    /// to `Table::set_rows` again.
+    SortChanged {
+        column: usize,
+        ascending: bool,
+    },
+    /// A column was dragged to `width`, after which it keeps that width instead of flexing.
+    ColumnResized {
+        column: usize,
+        width: f32,
+    },
+    SelectionChanged(Vec<RowId>),
+}
+
+/// What the rows share with their table, so that a resized column moves the cells of every row.
+struct RowLayout {
+    widths: RefCell<Vec<f32>>,
+    height: Cell<f32>,
+}
+
+/// A row of the cells that `Table::new` was given for a single row id.
+struct TableRow<A: App, RowId> {
+    id: ComponentId,
+    row_id: RowId,
+    cells: Vec<Box<dyn Component<A>>>,
+    layout: Rc<RowLayout>,
+}
+
+impl<A: App, RowId> Component<A> for TableRow<A, RowId> {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        for cell in &self.cells {
+            cell.draw(cx, canvas);
+        }
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        for cell in &mut self.cells {
+            cell.dispatch_event(cx, event);
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        self.set_bounds(cx, bounds);
+        let widths = self.layout.widths.borrow();
+        let mut left = bounds.left();
+        for (cell, &width) in self.cells.iter_mut().zip(widths.iter()) {
+            cell.layout(cx, bounds.with_left(left).with_width(width));
+            left += width;
+        }
+    }
+
+    fn get_preferred_size(&mut self, _cx: &mut Cx<A>, _parent_bounds: Rect) -> (Option<f32>, Option<f32>) {
+        (None, Some(self.layout.height.get()))
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
+
+impl<A: App, RowId> ScrollListItem<A> for TableRow<A, RowId> {}
+
+/// The column being resized by dragging the edge on its right in the header.
+struct ColumnResize {
+    column: usize,
+    width_on_down: f32,
+    /// The pointer dragging the edge, so that other pointers don't resize the column.
+    pointer: PointerId,
+}
+
+/// Rows of cells under a header of column titles, kept in a `ScrollList` that also handles the selection. Rows given
+/// to `set_rows` are all created upfront, while with `set_row_provider` only the rows that scroll into view are, for
+/// tables with too many rows for that. The header sorts by a column when it's clicked and resizes the columns by
+/// dragging the edges between them.
+pub struct Table<A: App, RowId> {
+    id: ComponentId,
+    header_id: ComponentId,
+    list: ScrollList<A, RowId, TableRow<A, RowId>>,
+    columns: Vec<Column>,
+    titles: Vec<Text>,
+    text: TextBuilder,
+    row_layout: Rc<RowLayout>,
+    header_height: f32,
+    /// The sorted column and whether it's ascending.
+    sort: Option<(usize, bool)>,
+    resize: Option<ColumnResize>,
+    selection_changes: Computed<Vec<RowId>>,
+    text_color: Computed<Color>,
+    header_color: Computed<Color>,
+    selection_color: Computed<Color>,
+    divider_color: Computed<Color>,
+    outputs: Output<TableEvent<RowId>>,
+}
+
+impl<A: App, RowId: Clone + PartialEq + 'static> Table<A, RowId> {
+    /// Creates a table with `columns` whose cells are created by `create_cell` from the id of their row and the
+    /// index of their column.
+    pub fn new(
+        cx: &mut Cx<A>,
+        id: ComponentId,
+        font_size: f32,
+        typeface: impl Into<usize>,
+        columns: Vec<Column>,
+        create_cell: impl Fn(&mut Cx<A>, ComponentId, &RowId, usize) -> Box<dyn Component<A>> + 'static,
+    ) -> Self {
+        let row_layout =
+            Rc::new(RowLayout { widths: RefCell::default(), height: Cell::new((font_size * 1.8).round()) });
+        let column_count = columns.len();
+        let list = {
+            let row_layout = row_layout.clone();
+            cx.add_child(&id, |cx, id| {
+                ScrollList::new(cx, id, move |cx, id, row_id: RowId| {
+                    let cells = (0..column_count)
+                        .map(|column| cx.add_child(&id, |cx, cell_id| create_cell(cx, cell_id, &row_id, column)))
+                        .collect();
+                    TableRow { id, row_id, cells, layout: row_layout.clone() }
+                })
+                .with_selection_mode(SelectionMode::Single)
+            })
+        };
+        let selection_changes = list.selection().as_computed();
+        // only changes after this are sent as events
+        selection_changes.next();
+        Self {
+            header_id: cx.add_child_id(&id).hoverable(cx),
+            id,
+            list,
+            columns,
+            titles: vec![],
+            text: TextBuilder::default().font_size(font_size).typeface(typeface),
+            row_layout,
+            header_height: (font_size * 2.0).round(),
+            sort: None,
+            resize: None,
+            selection_changes,
+            text_color: rgb(0xffffff).into(),
+            header_color: rgb(0xffffff).with_alpha(0.06).into(),
+            selection_color: rgb(0xffffff).with_alpha(0.15).into(),
+            divider_color: rgb(0xffffff).with_alpha(0.12).into(),
+            outputs: Default::default(),
+        }
+    }
+
+    /// Single selection by default.
+    pub fn with_selection_mode(mut self, mode: SelectionMode) -> Self {
+        self.list = self.list.with_selection_mode(mode);
+        self
+    }
+
+    pub fn with_row_height(self, row_height: f32) -> Self {
+        self.row_layout.height.set(row_height);
+        self
+    }
+
+    pub fn with_header_height(mut self, header_height: f32) -> Self {
+        self.header_height = header_height;
+        self
+    }
+
+    /// Shows the rows as sorted by `column`, for rows that are passed in sorted.
+    pub fn with_sort(mut self, column: usize, ascending: bool) -> Self {
+        self.sort = Some((column, ascending));
+        self
+    }
+
+    /// The color of the column titles.
+    pub fn with_text_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.text_color = color.into();
+        self
+    }
+
+    pub fn with_header_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.header_color = color.into();
+        self
+    }
+
+    pub fn with_selection_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.selection_color = color.into();
+        self
+    }
+
+    /// The color of the lines between the column titles.
+    pub fn with_divider_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.divider_color = color.into();
+        self
+    }
+
+    pub fn next_output(&mut self) -> Option<TableEvent<RowId>> {
+        self.outputs.next_output()
+    }
+
+    /// Shows a row for each of `ids`, creating the cells of all of them and keeping those of the rows that were
+    /// already shown. Use `set_row_provider` for tables with too many rows to create them all.
+    pub fn set_rows(&mut self, cx: &mut Cx<A>, ids: impl IntoIterator<Item = RowId>) {
+        self.list.set_items(cx, ids);
+    }
+
+    /// Shows `count` rows whose ids are fetched as they scroll into view, see `ScrollList::set_item_provider`.
+    pub fn set_row_provider(
+        &mut self,
+        cx: &mut Cx<A>,
+        count: usize,
+        fetch_range: impl Fn(Range<usize>, Later<Vec<RowId>>) + 'static,
+    ) {
+        self.list.set_item_provider(cx, count, fetch_range);
+    }
+
+    pub fn selection(&self) -> Signal<Vec<RowId>> {
+        self.list.selection()
+    }
+
+    pub fn set_selection(&mut self, cx: &mut Cx<A>, ids: impl IntoIterator<Item = RowId>) {
+        self.list.set_selection(cx, ids);
+    }
+
+    /// The sorted column and whether it's sorted in ascending order.
+    pub fn sort(&self) -> Option<(usize, bool)> {
+        self.sort
+    }
+
+    /// The current width of each column.
+    pub fn column_widths(&self) -> Vec<f32> {
+        self.row_layout.widths.borrow().clone()
+    }
+
+    fn update_widths(&mut self, available: f32) {
+        let fixed: f32 = self
+            .columns
+            .iter()
+            .map(|column| match column.width {
+                ColumnWidth::Fixed(width) => width.max(column.min_width),
+                ColumnWidth::Flex(_) => column.min_width,
+            })
+            .sum();
+        let total_flex: f32 = self
+            .columns
+            .iter()
+            .map(|column| match column.width {
+                ColumnWidth::Fixed(_) => 0.0,
+                ColumnWidth::Flex(flex) => flex,
+            })
+            .sum();
+        let remaining = (available - fixed).max(0.0);
+        *self.row_layout.widths.borrow_mut() = self
+            .columns
+            .iter()
+            .map(|column| match column.width {
+                ColumnWidth::Fixed(width) => width.max(column.min_width),
+                ColumnWidth::Flex(flex) if total_flex > 0.0 => column.min_width + remaining * flex / total_flex,
+                ColumnWidth::Flex(_) => column.min_width,
+            })
+            .collect();
+    }
+
+    /// The bounds of each column in the header.
+    fn get_header_cells(&self, cx: &Cx<A>) -> Vec<Rect> {
+        let header = self.header_id.get_bounds(cx);
+        let mut left = header.left();
+        self.row_layout
+            .widths
+            .borrow()
+            .iter()
+            .map(|&width| {
+                let cell = header.with_left(left).with_width(width);
+                left += width;
+                cell
+            })
+            .collect()
+    }
+
+    fn column_at(&self, cx: &Cx<A>, x: f32) -> Option<usize> {
+        self.get_header_cells(cx).iter().position(|cell| cell.left() <= x && x < cell.right())
+    }
+
+    /// The column whose right edge is at `x`.
+    fn resize_handle_at(&self, cx: &Cx<A>, x: f32) -> Option<usize> {
+        self.get_header_cells(cx).iter().position(|cell| (cell.right() - x).abs() <= RESIZE_HANDLE_WIDTH * 0.5)
+    }
+
+    fn toggle_sort(&mut self, column: usize) {
+        if !self.columns[column].sortable {
+            return;
+        }
+        let ascending = match self.sort {
+            Some((sorted, ascending)) if sorted == column => !ascending,
+            _ => true,
+        };
+        self.sort = Some((column, ascending));
+        self.outputs.add_output(TableEvent::SortChanged { column, ascending });
+    }
+
+    fn build_titles(&mut self, cx: &mut Cx<A>) {
+        if self.titles.len() == self.columns.len() {
+            return;
+        }
+        let color = self.text_color.get();
+        self.titles = self
+            .columns
+            .iter()
+            .map(|column| self.text.clone().text(column.title.clone()).color(color).build(cx))
+            .collect();
+    }
+
+    fn draw_sort_arrow(&self, canvas: &mut Canvas, cell: Rect, ascending: bool) {
+        let center = point(cell.right() - CELL_PADDING - 4.0, cell.center().y);
+        let tip = if ascending { -2.0 } else { 2.0 };
+        let mut path = PathBuilder::default();
+        path.move_to(point(center.x - 4.0, center.y - tip))
+            .line_to(point(center.x + 4.0, center.y - tip))
+            .line_to(point(center.x, center.y + tip))
+            .close();
+        canvas.fill(self.text_color.get().with_alpha_mul(0.6)).draw_path(&path.build());
+    }
+}
+
+impl<A: App, RowId: Clone + PartialEq + 'static> Component<A> for Table<A, RowId> {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let header = self.header_id.get_bounds(cx);
+        let divider_color = self.divider_color.get();
+        canvas.fill(self.header_color.get()).draw_rect(header);
+        canvas.with_clip_rect(header, |canvas| {
+            for (column, (cell, title)) in self.get_header_cells(cx).into_iter().zip(&self.titles).enumerate() {
+                let cap_height = title.get_cap_height();
+                let text_bounds = Rect::from_xywh(
+                    cell.left() + CELL_PADDING,
+                    cell.center().y - cap_height * 0.5,
+                    title.get_width(),
+                    cap_height,
+                );
+                canvas.with_clip_rect(cell, |canvas| {
+                    title.draw(canvas, text_bounds);
+                });
+                if let Some((sorted, ascending)) = self.sort
+                    && sorted == column
+                {
+                    self.draw_sort_arrow(canvas, cell, ascending);
+                }
+                canvas.fill(divider_color).draw_rect(cell.with_left(cell.right() - 1.0).with_width(1.0));
+            }
+        });
+
+        let selection = self.list.selection().get_fast();
+        if !selection.is_empty() {
+            canvas.with_clip_rect(self.list.get_bounds(cx), |canvas| {
+                for row in self.list.items() {
+                    if row.is_visible(cx) && selection.contains(&row.row_id) {
+                        canvas.fill(self.selection_color.get()).draw_rect(row.get_bounds(cx));
+                    }
+                }
+            });
+        }
+        self.list.draw(cx, canvas);
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        match event {
+            Event::Update => {
+                self.build_titles(cx);
+            }
+            Event::PointerDown(pointer) if self.header_id.is_hovered(pointer) => {
+                if let Some(column) = self.resize_handle_at(cx, pointer.position.x) {
+                    let width_on_down = self.row_layout.widths.borrow()[column];
+                    self.resize = Some(ColumnResize { column, width_on_down, pointer: pointer.id });
+                }
+            }
+            Event::PointerMove(pointer) => {
+                if let Some(resize) = &self.resize
+                    && resize.pointer == pointer.id
+                {
+                    let column = &mut self.columns[resize.column];
+                    let width =
+                        (resize.width_on_down + pointer.position.x - pointer.down_position.x).max(column.min_width);
+                    column.width = ColumnWidth::Fixed(width);
+                    self.update_widths(self.header_id.get_bounds(cx).width());
+                    self.list.relayout(cx);
+                }
+            }
+            Event::PointerUp(pointer) => {
+                if let Some(resize) = self.resize.take_if(|resize| resize.pointer == pointer.id) {
+                    let width = self.row_layout.widths.borrow()[resize.column];
+                    self.outputs.add_output(TableEvent::ColumnResized { column: resize.column, width });
+                }
+            }
+            Event::Click { pointer, .. }
+                if self.header_id.is_pressed(pointer) && self.resize_handle_at(cx, pointer.position.x).is_none() =>
+            {
+                if let Some(column) = self.column_at(cx, pointer.position.x) {
+                    self.toggle_sort(column);
+                }
+            }
+            _ => {}
+        }
+
+        self.list.dispatch_event(cx, event);
+
+        if matches!(event, Event::Update)
+            && let Some(selection) = self.selection_changes.next()
+        {
+            self.outputs.add_output(TableEvent::SelectionChanged(selection));
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, mut bounds: Rect) {
+        self.set_bounds(cx, bounds);
+        self.build_titles(cx);
+        self.header_id.set_bounds(cx, bounds.remove_from(self.header_height, Side::Top));
+        self.update_widths(bounds.width());
+        self.list.layout(cx, bounds);
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
@@ -0,0 +1,457 @@
+use std::ops::Range;
+
+use crate::*;
+
+const INDENT: f32 = 14.0;
+
+/// A node as given to a `TreeView` by its child loader.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreeNode<Id> {
+    pub id: Id,
+    pub label: String,
+    /// Makes the node expandable. Its children are loaded the first time it's expanded.
+    pub has_children: bool,
+}
+
+impl<Id> TreeNode<Id> {
+    pub fn new(id: Id, label: impl Into<String>, has_children: bool) -> Self {
+        Self { id, label: label.into(), has_children }
+    }
+}
+
+pub enum TreeViewEvent<Id> {
+    Selected(Id),
+    /// A node without children was double clicked or had enter pressed on it.
+    Activated(Id),
+    Expanded(Id),
+    Collapsed(Id),
+}
+
+/// Loads the children of a node, or the roots for `None`, into the `Later`. It can be set right away or once they've
+/// been loaded in the background.
+type LoadChildren<Id> = Box<dyn Fn(Option<&Id>, LoadedChildren<Id>)>;
+type LoadedChildren<Id> = Later<Vec<TreeNode<Id>>>;
+
+enum Children {
+    Unloaded,
+    Loading,
+    /// The indexes of the children in `TreeView::nodes`.
+    Loaded(Vec<usize>),
+}
+
+struct Node<Id> {
+    node: TreeNode<Id>,
+    parent: Option<usize>,
+    depth: usize,
+    expanded: bool,
+    children: Children,
+    /// Built once the node is first scrolled into view, so that large trees don't shape text for every node.
+    text: Option<Text>,
+}
+
+/// Shows a hierarchy whose children are loaded on demand, e.g. a library of presets or samples. Only the rows in view
+/// are drawn, so it stays fast with many nodes expanded.
+pub struct TreeView<Id> {
+    pub id: ComponentId,
+    load_children: LoadChildren<Id>,
+    nodes: Vec<Node<Id>>,
+    roots: Children,
+    /// Loads that haven't finished yet, by the node whose children they are.
+    pending: Vec<(Option<usize>, LoadedChildren<Id>)>,
+    /// The nodes whose ancestors are all expanded, from top to bottom.
+    rows: Vec<usize>,
+    selected: Option<usize>,
+    scroll_top: f32,
+    text: TextBuilder,
+    row_height: f32,
+    text_color: Computed<Color>,
+    selection_color: Computed<Color>,
+    guide_color: Computed<Color>,
+    outputs: Output<TreeViewEvent<Id>>,
+}
+
+impl<Id: Clone + PartialEq + 'static> TreeView<Id> {
+    pub fn new<A: App>(
+        cx: &mut Cx<A>,
+        id: ComponentId,
+        font_size: f32,
+        typeface: impl Into<usize>,
+        load_children: impl Fn(Option<&Id>, Later<Vec<TreeNode<Id>>>) + 'static,
+    ) -> Self {
+        cx.set_interactive(&id, true);
+        let mut tree = Self {
+            id,
+            load_children: Box::new(load_children),
+            nodes: vec![],
+            roots: Children::Unloaded,
+            pending: vec![],
+            rows: vec![],
+            selected: None,
+            scroll_top: 0.0,
+            text: TextBuilder::default().font_size(font_size).typeface(typeface),
+            row_height: (font_size * 1.6).round(),
+            text_color: rgb(0xffffff).into(),
+            selection_color: rgb(0xffffff).with_alpha(0.15).into(),
+            guide_color: rgb(0xffffff).with_alpha(0.12).into(),
+            outputs: Default::default(),
+        };
+        tree.load(None);
+        tree
+    }
+
+    /// The color of the labels and expand markers. Labels that were already shown keep their color.
+    pub fn with_text_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.text_color = color.into();
+        self
+    }
+
+    pub fn with_selection_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.selection_color = color.into();
+        self
+    }
+
+    /// The color of the lines that connect children to their parent.
+    pub fn with_guide_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.guide_color = color.into();
+        self
+    }
+
+    pub fn with_row_height(mut self, row_height: f32) -> Self {
+        self.row_height = row_height;
+        self
+    }
+
+    pub fn next_output(&mut self) -> Option<TreeViewEvent<Id>> {
+        self.outputs.next_output()
+    }
+
+    pub fn selected(&self) -> Option<&Id> {
+        self.selected.map(|index| &self.nodes[index].node.id)
+    }
+
+    pub fn is_expanded(&self, id: &Id) -> bool {
+        self.find(id).is_some_and(|index| self.nodes[index].expanded)
+    }
+
+    /// Expands or collapses a loaded node, loading its children if this is the first time it's expanded.
+    pub fn set_expanded<A: App>(&mut self, cx: &mut Cx<A>, id: &Id, expanded: bool) {
+        if let Some(index) = self.find(id) {
+            self.set_node_expanded(cx, index, expanded);
+        }
+    }
+
+    fn find(&self, id: &Id) -> Option<usize> {
+        self.nodes.iter().position(|node| &node.node.id == id)
+    }
+
+    fn load(&mut self, parent: Option<usize>) {
+        let children = Later::default();
+        (self.load_children)(parent.map(|index| &self.nodes[index].node.id), children.clone());
+        match children.value() {
+            Some(children) => self.add_children(parent, children),
+            None => self.pending.push((parent, children)),
+        }
+    }
+
+    /// Adds the children that finished loading. Returns whether there were any.
+    fn poll_loads(&mut self) -> bool {
+        let mut loaded_any = false;
+        for (parent, children) in std::mem::take(&mut self.pending) {
+            match children.value() {
+                Some(loaded) => {
+                    self.add_children(parent, loaded);
+                    loaded_any = true;
+                }
+                None => self.pending.push((parent, children)),
+            }
+        }
+        loaded_any
+    }
+
+    fn add_children(&mut self, parent: Option<usize>, children: Vec<TreeNode<Id>>) {
+        let depth = parent.map_or(0, |index| self.nodes[index].depth + 1);
+        let first = self.nodes.len();
+        self.nodes.extend(children.into_iter().map(|node| Node {
+            node,
+            parent,
+            depth,
+            expanded: false,
+            children: Children::Unloaded,
+            text: None,
+        }));
+        let children = Children::Loaded((first..self.nodes.len()).collect());
+        match parent {
+            Some(index) => self.nodes[index].children = children,
+            None => self.roots = children,
+        }
+        self.update_rows();
+    }
+
+    fn update_rows(&mut self) {
+        self.rows.clear();
+        let Children::Loaded(roots) = &self.roots else {
+            return;
+        };
+        let mut stack: Vec<usize> = roots.iter().rev().copied().collect();
+        while let Some(index) = stack.pop() {
+            self.rows.push(index);
+            let node = &self.nodes[index];
+            if node.expanded
+                && let Children::Loaded(children) = &node.children
+            {
+                stack.extend(children.iter().rev());
+            }
+        }
+    }
+
+    fn set_node_expanded<A: App>(&mut self, cx: &mut Cx<A>, index: usize, expanded: bool) {
+        let node = &mut self.nodes[index];
+        if !node.node.has_children || node.expanded == expanded {
+            return;
+        }
+        node.expanded = expanded;
+        let id = node.node.id.clone();
+        if expanded {
+            if let Children::Unloaded = node.children {
+                node.children = Children::Loading;
+                self.load(Some(index));
+            }
+            self.outputs.add_output(TreeViewEvent::Expanded(id));
+        } else {
+            self.outputs.add_output(TreeViewEvent::Collapsed(id.clone()));
+            // a selection that gets hidden moves up to the collapsed node
+            if self.selected.is_some_and(|selected| self.is_descendant(selected, index)) {
+                self.selected = Some(index);
+                self.outputs.add_output(TreeViewEvent::Selected(id));
+            }
+        }
+        self.update_rows();
+        self.scroll_to(cx, self.scroll_top);
+    }
+
+    fn is_descendant(&self, index: usize, ancestor: usize) -> bool {
+        let mut parent = self.nodes[index].parent;
+        while let Some(index) = parent {
+            if index == ancestor {
+                return true;
+            }
+            parent = self.nodes[index].parent;
+        }
+        false
+    }
+
+    fn select<A: App>(&mut self, cx: &mut Cx<A>, row: usize) {
+        let Some(&index) = self.rows.get(row) else {
+            return;
+        };
+        if self.selected != Some(index) {
+            self.selected = Some(index);
+            self.outputs.add_output(TreeViewEvent::Selected(self.nodes[index].node.id.clone()));
+        }
+        self.scroll_into_view(cx, row);
+    }
+
+    fn selected_row(&self) -> Option<usize> {
+        self.rows.iter().position(|&index| Some(index) == self.selected)
+    }
+
+    fn activate<A: App>(&mut self, cx: &mut Cx<A>, index: usize) {
+        let node = &self.nodes[index];
+        if node.node.has_children {
+            self.set_node_expanded(cx, index, !node.expanded);
+        } else {
+            self.outputs.add_output(TreeViewEvent::Activated(node.node.id.clone()));
+        }
+    }
+
+    fn get_row_bounds<A: App>(&self, cx: &Cx<A>, row: usize) -> Rect {
+        let bounds = self.get_bounds(cx);
+        let top = bounds.top() + row as f32 * self.row_height - self.scroll_top;
+        Rect::from_xywh(bounds.left(), top, bounds.width(), self.row_height)
+    }
+
+    /// Where the expand marker of `index` is drawn and can be clicked.
+    fn get_marker_bounds(&self, row_bounds: Rect, index: usize) -> Rect {
+        let left = row_bounds.left() + 4.0 + self.nodes[index].depth as f32 * INDENT;
+        row_bounds.with_left(left).with_width(INDENT)
+    }
+
+    fn row_at<A: App>(&self, cx: &Cx<A>, position: Point) -> Option<usize> {
+        let bounds = self.get_bounds(cx);
+        if !bounds.contains(&position) {
+            return None;
+        }
+        let row = ((position.y - bounds.top() + self.scroll_top) / self.row_height) as usize;
+        (row < self.rows.len()).then_some(row)
+    }
+
+    fn is_on_marker<A: App>(&self, cx: &Cx<A>, row: usize, position: Point) -> bool {
+        let index = self.rows[row];
+        self.nodes[index].node.has_children
+            && self.get_marker_bounds(self.get_row_bounds(cx, row), index).contains(&position)
+    }
+
+    fn get_visible_rows<A: App>(&self, cx: &Cx<A>) -> Range<usize> {
+        let first = ((self.scroll_top / self.row_height) as usize).min(self.rows.len());
+        let count = (self.get_bounds(cx).height() / self.row_height).ceil() as usize + 1;
+        first..(first + count).min(self.rows.len())
+    }
+
+    fn build_visible_texts<A: App>(&mut self, cx: &mut Cx<A>) {
+        for row in self.get_visible_rows(cx) {
+            let node = &mut self.nodes[self.rows[row]];
+            if node.text.is_none() {
+                let text = self.text.clone().text(node.node.label.clone()).color(self.text_color.get());
+                node.text = Some(text.build(cx));
+            }
+        }
+    }
+
+    fn max_scroll<A: App>(&self, cx: &Cx<A>) -> f32 {
+        (self.rows.len() as f32 * self.row_height - self.get_bounds(cx).height()).max(0.0)
+    }
+
+    fn scroll_into_view<A: App>(&mut self, cx: &mut Cx<A>, row: usize) {
+        let top = row as f32 * self.row_height;
+        let height = self.get_bounds(cx).height();
+        self.scroll_to(cx, self.scroll_top.clamp((top + self.row_height - height).max(0.0), top));
+    }
+
+    fn scroll_to<A: App>(&mut self, cx: &mut Cx<A>, top: f32) {
+        let max_scroll = self.max_scroll(cx);
+        self.scroll_top = top.clamp(0.0, max_scroll);
+        cx.set_can_scroll(&self.id, CanScroll::vertical(self.scroll_top, 0.0, max_scroll));
+        self.build_visible_texts(cx);
+    }
+}
+
+impl<A: App, Id: Clone + PartialEq + 'static> Component<A> for TreeView<Id> {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let bounds = self.get_bounds(cx);
+        let text_color = self.text_color.get();
+        let guide_color = self.guide_color.get();
+        canvas.with_clip_rect(bounds, |canvas| {
+            for row in self.get_visible_rows(cx) {
+                let index = self.rows[row];
+                let node = &self.nodes[index];
+                let row_bounds = self.get_row_bounds(cx, row);
+                if self.selected == Some(index) {
+                    canvas.fill(self.selection_color.get()).draw_rect(row_bounds);
+                }
+
+                for depth in 0..node.depth {
+                    let x = row_bounds.left() + 4.0 + depth as f32 * INDENT + INDENT * 0.5;
+                    canvas.fill(guide_color).draw_rect(row_bounds.with_left(x.round()).with_width(1.0));
+                }
+
+                let marker_bounds = self.get_marker_bounds(row_bounds, index);
+                if node.node.has_children {
+                    let marker = if node.expanded {
+                        Rect::from_size(7.0, 2.0)
+                    } else {
+                        Rect::from_size(2.0, 7.0)
+                    };
+                    let alpha = if matches!(node.children, Children::Loading) {
+                        0.25
+                    } else {
+                        0.5
+                    };
+                    canvas.fill(text_color.with_alpha(alpha)).draw_rect(marker.centered_within(marker_bounds));
+                }
+
+                if let Some(text) = &node.text {
+                    let cap_height = text.get_cap_height();
+                    let text_bounds = Rect::from_xywh(
+                        marker_bounds.right() + 2.0,
+                        row_bounds.center().y - cap_height * 0.5,
+                        text.get_width(),
+                        cap_height,
+                    );
+                    text.draw(canvas, text_bounds);
+                }
+            }
+        });
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        match event {
+            Event::Update => {
+                if self.poll_loads() {
+                    cx.invalidate(&self.id);
+                }
+                self.build_visible_texts(cx);
+            }
+            Event::MouseWheel { delta, .. } if cx.is_wheel_target(&self.id) => {
+                self.scroll_to(cx, self.scroll_top - delta.to_pixels().y);
+            }
+            Event::PointerDown(pointer) if self.id.is_hovered(pointer) => {
+                if let Some(row) = self.row_at(cx, pointer.position) {
+                    if self.is_on_marker(cx, row, pointer.position) {
+                        let index = self.rows[row];
+                        self.set_node_expanded(cx, index, !self.nodes[index].expanded);
+                    } else {
+                        self.select(cx, row);
+                    }
+                }
+            }
+            Event::DoubleClick(pointer) if self.id.is_pressed(pointer) => {
+                if let Some(row) = self.row_at(cx, pointer.position)
+                    && !self.is_on_marker(cx, row, pointer.position)
+                {
+                    self.activate(cx, self.rows[row]);
+                }
+            }
+            Event::Keydown { key, captured, .. } if self.is_focused(cx) && !self.rows.is_empty() => {
+                let last = self.rows.len() - 1;
+                let selected_row = self.selected_row();
+                match key {
+                    Key::ArrowUp => self.select(cx, selected_row.map_or(last, |row| row.saturating_sub(1))),
+                    Key::ArrowDown => self.select(cx, selected_row.map_or(0, |row| (row + 1).min(last))),
+                    Key::Home => self.select(cx, 0),
+                    Key::End => self.select(cx, last),
+                    Key::ArrowRight => {
+                        if let (Some(row), Some(index)) = (selected_row, self.selected) {
+                            let node = &self.nodes[index];
+                            if !node.expanded {
+                                self.set_node_expanded(cx, index, true);
+                            } else if let Children::Loaded(children) = &node.children
+                                && !children.is_empty()
+                            {
+                                self.select(cx, row + 1);
+                            }
+                        }
+                    }
+                    Key::ArrowLeft => {
+                        if let Some(index) = self.selected {
+                            let node = &self.nodes[index];
+                            if node.expanded {
+                                self.set_node_expanded(cx, index, false);
+                            } else if let Some(parent) = node.parent
+                                && let Some(row) = self.rows.iter().position(|&index| index == parent)
+                            {
+                                self.select(cx, row);
+                            }
+                        }
+                    }
+                    Key::Enter => {
+                        if let Some(index) = self.selected {
+                            self.activate(cx, index);
+                        }
+                    }
+                    _ => return,
+                }
+                *captured = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        self.set_bounds(cx, bounds);
+        self.scroll_to(cx, self.scroll_top);
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
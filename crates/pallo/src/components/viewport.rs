@@ -0,0 +1,291 @@
+use crate::*;
+
+/// How much a pixel of wheel movement zooms in or out.
+const WHEEL_ZOOM_SPEED: f32 = 0.002;
+/// The space kept around the rect given to `Viewport::zoom_to_fit`, in pixels.
+const FIT_PADDING: f32 = 16.0;
+
+/// A pan started by dragging with space held, with the middle mouse button or with a touch on the background.
+struct Pan {
+    pointer: PointerId,
+    offset_on_down: Point,
+}
+
+/// Shows its content zoomed and panned, e.g. for node editors and arrangement views. The content is laid out in its
+/// own coordinates, which the pointer events it gets are converted into, so it doesn't need to know about the zoom.
+///
+/// The wheel zooms around the pointer, except for the pixel deltas of trackpads which pan unless ctrl is held, as it
+/// is for pinches on them. On touch screens the view is panned by dragging its background and zoomed by pinching.
+pub struct Viewport<C> {
+    pub id: ComponentId,
+    content: C,
+    /// The content's bounds, or the viewport's size at the origin if not set.
+    content_bounds: Option<Rect>,
+    zoom: f32,
+    min_zoom: f32,
+    max_zoom: f32,
+    /// The point of the content that's shown at the top left corner.
+    offset: Point,
+    pan: Option<Pan>,
+    space_down: bool,
+    /// The touches on the viewport in its own coordinates, two of which make a pinch.
+    touches: Vec<(PointerId, Point)>,
+    /// The content rect that's being animated to fill the viewport, see `zoom_to_fit`.
+    view_tween: Option<TweenId<Rect>>,
+}
+
+impl<C> Viewport<C> {
+    pub fn new<A: App>(cx: &mut Cx<A>, id: ComponentId, add_content: impl FnOnce(&mut Cx<A>, ComponentId) -> C) -> Self
+    where
+        C: Component<A>,
+    {
+        cx.set_hoverable(&id, true);
+        cx.set_clips_children(&id, true);
+        cx.set_can_scroll(&id, CanScroll { up: true, down: true, left: true, right: true });
+        Self {
+            content: id.add_child(cx, add_content),
+            id,
+            content_bounds: None,
+            zoom: 1.0,
+            min_zoom: 0.1,
+            max_zoom: 8.0,
+            offset: Point::default(),
+            pan: None,
+            space_down: false,
+            touches: vec![],
+            view_tween: None,
+        }
+    }
+
+    pub fn with_zoom_range(mut self, min_zoom: f32, max_zoom: f32) -> Self {
+        self.min_zoom = min_zoom;
+        self.max_zoom = max_zoom;
+        self
+    }
+
+    pub fn content(&self) -> &C {
+        &self.content
+    }
+
+    pub fn content_mut(&mut self) -> &mut C {
+        &mut self.content
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// The point of the content that's shown at the top left corner.
+    pub fn offset(&self) -> Point {
+        self.offset
+    }
+
+    /// Whether a pointer is panning the view, in which case the content should ignore what it's doing.
+    pub fn is_panning(&self) -> bool {
+        self.pan.is_some() || self.touches.len() == 2
+    }
+
+    /// Shows the content from `offset` at `zoom`, stopping any `zoom_to_fit` animation. With damage tracking on, call
+    /// `Cx::invalidate` afterwards when this isn't in response to input.
+    pub fn set_view(&mut self, offset: Point, zoom: f32) {
+        self.view_tween = None;
+        self.offset = offset;
+        self.zoom = zoom.clamp(self.min_zoom, self.max_zoom);
+    }
+
+    /// Lays out the content in `bounds` of its own coordinates.
+    pub fn set_content_bounds<A: App>(&mut self, cx: &mut Cx<A>, bounds: Rect)
+    where
+        C: Component<A>,
+    {
+        self.content_bounds = Some(bounds);
+        self.content.layout(cx, bounds);
+    }
+
+    /// The part of the content that's in view.
+    pub fn get_visible_rect<A: App>(&self, cx: &Cx<A>) -> Rect {
+        let size = self.get_size(cx);
+        Rect::from_xywh(self.offset.x, self.offset.y, size.x / self.zoom, size.y / self.zoom)
+    }
+
+    /// Animates the view over `duration_ms` so that `rect` of the content fits in it and is centered, e.g. to show all
+    /// of the nodes of a graph.
+    pub fn zoom_to_fit<A: App>(&mut self, cx: &mut Cx<A>, rect: Rect, duration_ms: f32) {
+        let size = self.get_size(cx);
+        if rect.width() <= 0.0 || rect.height() <= 0.0 || size.x <= 0.0 || size.y <= 0.0 {
+            return;
+        }
+        let padding = FIT_PADDING * 2.0;
+        let zoom = ((size.x - padding) / rect.width())
+            .min((size.y - padding) / rect.height())
+            .clamp(self.min_zoom, self.max_zoom);
+        let target = Rect::from_xywh(0.0, 0.0, size.x / zoom, size.y / zoom).centered_within(rect);
+        if duration_ms <= 0.0 {
+            self.set_view(target.relative_point((0.0, 0.0)), zoom);
+            cx.invalidate(&self.id);
+            return;
+        }
+        let tween = cx.animations.add_tween(self.get_visible_rect(cx), duration_ms, Easing::EaseInOutCubic);
+        cx.animations.set_tween(&tween, target);
+        self.view_tween = Some(tween);
+    }
+
+    /// Converts `point` from the root's coordinates, like those of pointer events, into the content's.
+    pub fn to_content<A: App>(&self, cx: &Cx<A>, point: Point) -> Point {
+        self.offset + self.to_viewport(cx, point) / self.zoom
+    }
+
+    /// Converts `point` from the root's coordinates into ones relative to the viewport's top left corner.
+    fn to_viewport<A: App>(&self, cx: &Cx<A>, point: Point) -> Point {
+        cx.to_local(&self.id, point) - cx.get_bounds(&self.id).relative_point((0.0, 0.0))
+    }
+
+    fn get_size<A: App>(&self, cx: &Cx<A>) -> Point {
+        cx.get_bounds(&self.id).size()
+    }
+
+    /// Zooms by `factor` keeping the content under `anchor`, relative to the viewport, in place.
+    fn zoom_around(&mut self, anchor: Point, factor: f32) {
+        let content_anchor = self.offset + anchor / self.zoom;
+        let zoom = (self.zoom * factor).clamp(self.min_zoom, self.max_zoom);
+        self.set_view(content_anchor - anchor / zoom, zoom);
+    }
+
+    fn pan_by(&mut self, pixels: Point) {
+        self.set_view(self.offset - pixels / self.zoom, self.zoom);
+    }
+
+    fn map_pointer<A: App>(&self, cx: &Cx<A>, pointer: &PointerState<A>) -> PointerState<A> {
+        let mut pointer = pointer.clone();
+        pointer.position = self.to_content(cx, pointer.position);
+        pointer.down_position = self.to_content(cx, pointer.down_position);
+        pointer.delta = pointer.delta / self.zoom;
+        pointer.delta_sum = pointer.delta_sum / self.zoom;
+        pointer.velocity = pointer.velocity / self.zoom;
+        pointer
+    }
+
+    /// Moves a tracked touch to `position`, zooming and panning with the pinch if there are two of them.
+    fn move_touch(&mut self, id: PointerId, position: Point) {
+        let before: Vec<_> = self.touches.iter().map(|(_, position)| *position).collect();
+        if let Some(touch) = self.touches.iter_mut().find(|(touch, _)| *touch == id) {
+            touch.1 = position;
+        }
+        if let ([a, b], [(_, new_a), (_, new_b)]) = (before.as_slice(), self.touches.as_slice()) {
+            let (center, new_center) = ((*a + *b) * 0.5, (*new_a + *new_b) * 0.5);
+            let distance = a.distance_to(*b);
+            if distance > 0.0 {
+                self.zoom_around(center, new_a.distance_to(*new_b) / distance);
+            }
+            self.pan_by(new_center - center);
+        }
+    }
+}
+
+impl<A: App, C: Component<A>> Component<A> for Viewport<C> {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let bounds = self.get_bounds(cx);
+        canvas.with_clip_rect(bounds, |canvas| {
+            canvas.save();
+            canvas.translate(bounds.relative_point((0.0, 0.0)));
+            canvas.scale_rel(point(self.zoom, self.zoom));
+            canvas.translate(-self.offset);
+            self.content.draw_instrumented(cx, canvas);
+            canvas.restore();
+        });
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        match event {
+            Event::Update => {
+                if let Some(tween) = &self.view_tween {
+                    let rect = cx.animations.get_tween(tween);
+                    let done = cx.animations.is_tween_done(tween);
+                    let size = self.get_size(cx);
+                    if rect.width() > 0.0 {
+                        self.offset = rect.relative_point((0.0, 0.0));
+                        self.zoom = size.x / rect.width();
+                    }
+                    if done {
+                        self.view_tween = None;
+                    }
+                }
+            }
+            Event::MouseWheel { delta, .. } if cx.is_wheel_target(&self.id) => {
+                let pixels = delta.to_pixels();
+                match delta {
+                    ScrollDelta::Pixels(_) if !cx.mods.ctrl => self.pan_by(pixels),
+                    _ => {
+                        let anchor = self.to_viewport(cx, cx.main_pointer().position);
+                        self.zoom_around(anchor, (pixels.y * WHEEL_ZOOM_SPEED).exp());
+                    }
+                }
+            }
+            Event::PointerDown(pointer) if self.is_hovered_any(cx) => {
+                let is_touch = matches!(pointer.id, PointerId::Touch(_));
+                if is_touch && self.touches.len() < 2 {
+                    self.touches.push((pointer.id, self.to_viewport(cx, pointer.position)));
+                }
+                if self.touches.len() == 2 {
+                    // the second touch turns the pan into a pinch
+                    self.pan = None;
+                } else if (self.space_down && !is_touch)
+                    || pointer.button == MouseButton::Middle
+                    || (is_touch && self.is_hovered(pointer))
+                {
+                    self.view_tween = None;
+                    self.pan = Some(Pan { pointer: pointer.id, offset_on_down: self.offset });
+                }
+            }
+            Event::PointerMove(pointer) => {
+                if self.touches.iter().any(|(id, _)| *id == pointer.id) {
+                    let position = self.to_viewport(cx, pointer.position);
+                    self.move_touch(pointer.id, position);
+                }
+                if let Some(pan) = &self.pan
+                    && pan.pointer == pointer.id
+                {
+                    let moved = self.to_viewport(cx, pointer.position) - self.to_viewport(cx, pointer.down_position);
+                    self.set_view(pan.offset_on_down - moved / self.zoom, self.zoom);
+                }
+            }
+            Event::PointerUp(pointer) => {
+                self.touches.retain(|(id, _)| *id != pointer.id);
+                if self.pan.as_ref().is_some_and(|pan| pan.pointer == pointer.id) {
+                    self.pan = None;
+                }
+            }
+            Event::Keydown { key: Key::Character(ch), repeat: false, .. } if *ch == " " && self.is_hovered_any(cx) => {
+                self.space_down = true;
+            }
+            Event::Keyup { key: Key::Character(ch), .. } if *ch == " " => {
+                self.space_down = false;
+            }
+            _ => {}
+        }
+
+        let mut mapped = match event {
+            Event::PointerDown(pointer) => Event::PointerDown(self.map_pointer(cx, pointer)),
+            Event::PointerUp(pointer) => Event::PointerUp(self.map_pointer(cx, pointer)),
+            Event::PointerMove(pointer) => Event::PointerMove(self.map_pointer(cx, pointer)),
+            Event::LongPress(pointer) => Event::LongPress(self.map_pointer(cx, pointer)),
+            Event::Click { pointer, count } => Event::Click { pointer: self.map_pointer(cx, pointer), count: *count },
+            Event::DoubleClick(pointer) => Event::DoubleClick(self.map_pointer(cx, pointer)),
+            _ => {
+                self.content.dispatch_event(cx, event);
+                return;
+            }
+        };
+        self.content.dispatch_event(cx, &mut mapped);
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        self.set_bounds(cx, bounds);
+        let content_bounds = self.content_bounds.unwrap_or(Rect::from_size(bounds.width(), bounds.height()));
+        self.content.layout(cx, content_bounds);
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
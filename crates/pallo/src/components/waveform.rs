@@ -0,0 +1,308 @@
+use std::ops::Range;
+
+use crate::*;
+
+const EDGE_GRAB_DISTANCE: f32 = 4.0;
+const DEFAULT_SAMPLES_PER_PEAK: usize = 256;
+/// How far the view can be zoomed in, past one sample per pixel.
+const MIN_SAMPLES_PER_PIXEL: f64 = 1.0 / 16.0;
+
+pub enum WaveformEvent {
+    /// The waveform was clicked without dragging.
+    Seek(usize),
+    SelectionChanged(Option<Range<usize>>),
+}
+
+enum SelectionDrag {
+    New { anchor: usize },
+    Start,
+    End,
+    Move { grab_offset: isize },
+}
+
+/// Displays min/max peak data with zooming, panning, a playhead and a draggable selection.
+/// Positions are in samples.
+pub struct Waveform {
+    pub id: ComponentId,
+    peaks: Vec<(f32, f32)>,
+    samples_per_peak: usize,
+    view_start: f64,
+    samples_per_pixel: f64,
+    playhead: Option<usize>,
+    selection: Option<Range<usize>>,
+    drag: Option<SelectionDrag>,
+    color: Computed<Color>,
+    selection_color: Computed<Color>,
+    playhead_color: Computed<Color>,
+    outputs: Output<WaveformEvent>,
+}
+
+impl Waveform {
+    pub fn new<A: App>(cx: &mut Cx<A>, id: ComponentId) -> Self {
+        id.set_hoverable(cx, true);
+        Self {
+            id,
+            peaks: vec![],
+            samples_per_peak: DEFAULT_SAMPLES_PER_PEAK,
+            view_start: 0.0,
+            samples_per_pixel: 0.0,
+            playhead: None,
+            selection: None,
+            drag: None,
+            color: rgb(0xffffff).into(),
+            selection_color: rgb(0xffffff).with_alpha(0.2).into(),
+            playhead_color: rgb(0xffcc00).into(),
+            outputs: Default::default(),
+        }
+    }
+
+    /// Sets precomputed `(min, max)` pairs, each covering `samples_per_peak` samples.
+    pub fn set_peaks<A: App>(&mut self, cx: &Cx<A>, peaks: Vec<(f32, f32)>, samples_per_peak: usize) {
+        self.peaks = peaks;
+        self.samples_per_peak = samples_per_peak.max(1);
+        self.zoom_to_fit(cx);
+    }
+
+    /// Decimates raw samples into peaks.
+    pub fn set_samples<A: App>(&mut self, cx: &Cx<A>, samples: &[f32]) {
+        let peaks = samples
+            .chunks(DEFAULT_SAMPLES_PER_PEAK)
+            .map(|chunk| chunk.iter().fold((f32::MAX, f32::MIN), |(min, max), s| (min.min(*s), max.max(*s))))
+            .collect();
+        self.set_peaks(cx, peaks, DEFAULT_SAMPLES_PER_PEAK);
+    }
+
+    pub fn num_samples(&self) -> usize {
+        self.peaks.len() * self.samples_per_peak
+    }
+
+    pub fn set_playhead(&mut self, position: Option<usize>) {
+        self.playhead = position;
+    }
+
+    pub fn selection(&self) -> Option<Range<usize>> {
+        self.selection.clone()
+    }
+
+    pub fn set_selection(&mut self, selection: Option<Range<usize>>) {
+        self.selection = selection;
+    }
+
+    pub fn with_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    pub fn with_selection_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.selection_color = color.into();
+        self
+    }
+
+    pub fn with_playhead_color(mut self, color: impl Into<Computed<Color>>) -> Self {
+        self.playhead_color = color.into();
+        self
+    }
+
+    pub fn next_output(&mut self) -> Option<WaveformEvent> {
+        self.outputs.next_output()
+    }
+
+    /// The range of samples currently in view.
+    pub fn view<A: App>(&self, cx: &Cx<A>) -> Range<usize> {
+        let width = self.get_bounds(cx).width() as f64;
+        self.view_start as usize..(self.view_start + width * self.samples_per_pixel) as usize
+    }
+
+    pub fn set_view<A: App>(&mut self, cx: &Cx<A>, view: Range<usize>) {
+        let width = self.get_bounds(cx).width().max(1.0) as f64;
+        self.samples_per_pixel = view.len() as f64 / width;
+        self.view_start = view.start as f64;
+        self.clamp_view(cx);
+    }
+
+    pub fn zoom_to_fit<A: App>(&mut self, cx: &Cx<A>) {
+        self.set_view(cx, 0..self.num_samples());
+    }
+
+    /// Zooms by `factor` while keeping the sample under `x` in place.
+    pub fn zoom_around<A: App>(&mut self, cx: &Cx<A>, x: f32, factor: f64) {
+        let anchor = self.x_to_sample_f64(cx, x);
+        self.samples_per_pixel /= factor;
+        self.view_start = anchor - (x - self.get_bounds(cx).left()) as f64 * self.samples_per_pixel;
+        self.clamp_view(cx);
+    }
+
+    fn max_samples_per_pixel<A: App>(&self, cx: &Cx<A>) -> f64 {
+        (self.num_samples() as f64 / self.get_bounds(cx).width().max(1.0) as f64).max(1.0)
+    }
+
+    fn max_view_start<A: App>(&self, cx: &Cx<A>) -> f64 {
+        let width = self.get_bounds(cx).width().max(1.0) as f64;
+        (self.num_samples() as f64 - width * self.samples_per_pixel).max(0.0)
+    }
+
+    fn clamp_view<A: App>(&mut self, cx: &Cx<A>) {
+        self.samples_per_pixel = self.samples_per_pixel.clamp(MIN_SAMPLES_PER_PIXEL, self.max_samples_per_pixel(cx));
+        self.view_start = self.view_start.clamp(0.0, self.max_view_start(cx));
+    }
+
+    /// Tells the wheel routing which ways the view can still pan, or zoom with the modifiers that zoom held, so that
+    /// the wheel scrolls whatever is around the waveform once it can't.
+    fn update_can_scroll<A: App>(&self, cx: &mut Cx<A>) {
+        let can_scroll = if cx.mods.meta || cx.mods.ctrl {
+            CanScroll {
+                up: self.samples_per_pixel > MIN_SAMPLES_PER_PIXEL,
+                down: self.samples_per_pixel < self.max_samples_per_pixel(cx),
+                ..Default::default()
+            }
+        } else {
+            let pan = CanScroll::horizontal(self.view_start as f32, 0.0, self.max_view_start(cx) as f32);
+            if cx.mods.shift {
+                CanScroll { up: pan.left, down: pan.right, ..pan }
+            } else {
+                pan
+            }
+        };
+        cx.set_can_scroll(&self.id, can_scroll);
+    }
+
+    fn x_to_sample_f64<A: App>(&self, cx: &Cx<A>, x: f32) -> f64 {
+        self.view_start + (x - self.get_bounds(cx).left()) as f64 * self.samples_per_pixel
+    }
+
+    fn x_to_sample<A: App>(&self, cx: &Cx<A>, x: f32) -> usize {
+        (self.x_to_sample_f64(cx, x).max(0.0) as usize).min(self.num_samples())
+    }
+
+    fn sample_to_x<A: App>(&self, cx: &Cx<A>, sample: usize) -> f32 {
+        self.get_bounds(cx).left() + ((sample as f64 - self.view_start) / self.samples_per_pixel) as f32
+    }
+
+    fn build_path(&self, bounds: Rect) -> Path {
+        let mut path = PathBuilder::default();
+        if self.peaks.is_empty() {
+            return path.build();
+        }
+
+        let to_y = |value: f32| bounds.relative_point((0.0, 0.5 - 0.5 * value.clamp(-1.0, 1.0))).y;
+        for column in 0..bounds.width().max(0.0) as usize {
+            let start = self.view_start + column as f64 * self.samples_per_pixel;
+            let first = (start / self.samples_per_peak as f64) as usize;
+            let last = ((start + self.samples_per_pixel) / self.samples_per_peak as f64).ceil() as usize;
+            let Some(peaks) = self.peaks.get(first..last.clamp(first + 1, self.peaks.len())) else {
+                break;
+            };
+            let (min, max) =
+                peaks.iter().fold((f32::MAX, f32::MIN), |(min, max), peak| (min.min(peak.0), max.max(peak.1)));
+
+            let x = bounds.left() + column as f32 + 0.5;
+            if column == 0 {
+                path.move_to(point(x, to_y(max)));
+            } else {
+                path.line_to(point(x, to_y(max)));
+            }
+            path.line_to(point(x, to_y(min)));
+        }
+        path.build()
+    }
+}
+
+impl<A: App> Component<A> for Waveform {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let bounds = self.get_bounds(cx);
+        canvas.with_clip_rect(bounds, |canvas| {
+            if let Some(selection) = &self.selection {
+                let left = self.sample_to_x(cx, selection.start);
+                let right = self.sample_to_x(cx, selection.end);
+                canvas.fill(self.selection_color.get()).draw_rect(bounds.with_left(left).with_right(right));
+            }
+
+            canvas.stroke(self.color.get(), 1.0).draw_path(&self.build_path(bounds));
+
+            if let Some(playhead) = self.playhead {
+                let x = self.sample_to_x(cx, playhead);
+                canvas.fill(self.playhead_color.get()).draw_rect(bounds.with_left(x - 0.5).with_width(1.0));
+            }
+        });
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        match event {
+            Event::MouseWheel { delta, .. } if cx.is_wheel_target(&self.id) => {
+                let delta = delta.to_pixels();
+                if cx.mods.meta || cx.mods.ctrl {
+                    let factor = 1.01f64.powf(delta.y as f64);
+                    self.zoom_around(cx, cx.main_pointer().position.x, factor);
+                } else {
+                    let pan = if cx.mods.shift { delta.y } else { delta.x };
+                    self.view_start -= pan as f64 * self.samples_per_pixel;
+                    self.clamp_view(cx);
+                }
+            }
+            Event::PointerDown(pointer) if self.id.is_hovered(pointer) => {
+                let x = pointer.position.x;
+                let sample = self.x_to_sample(cx, x);
+                self.drag = Some(match &self.selection {
+                    Some(selection) if (x - self.sample_to_x(cx, selection.start)).abs() < EDGE_GRAB_DISTANCE => {
+                        SelectionDrag::Start
+                    }
+                    Some(selection) if (x - self.sample_to_x(cx, selection.end)).abs() < EDGE_GRAB_DISTANCE => {
+                        SelectionDrag::End
+                    }
+                    Some(selection) if selection.contains(&sample) => {
+                        SelectionDrag::Move { grab_offset: sample as isize - selection.start as isize }
+                    }
+                    _ => SelectionDrag::New { anchor: sample },
+                });
+            }
+            Event::PointerMove(pointer) if self.id.is_pressed(pointer) => {
+                let sample = self.x_to_sample(cx, pointer.position.x);
+                let num_samples = self.num_samples();
+                match (&self.drag, &mut self.selection) {
+                    (Some(SelectionDrag::New { anchor }), selection) if pointer.delta.x.abs() > 2.0 => {
+                        *selection = Some(sample.min(*anchor)..sample.max(*anchor));
+                    }
+                    (Some(SelectionDrag::Start), Some(selection)) => {
+                        selection.start = sample.min(selection.end);
+                    }
+                    (Some(SelectionDrag::End), Some(selection)) => {
+                        selection.end = sample.max(selection.start);
+                    }
+                    (Some(SelectionDrag::Move { grab_offset }), Some(selection)) => {
+                        let len = selection.len();
+                        let start = (sample as isize - grab_offset).clamp(0, num_samples.saturating_sub(len) as isize);
+                        *selection = start as usize..start as usize + len;
+                    }
+                    _ => {}
+                }
+            }
+            Event::PointerUp(pointer) if self.id.is_pressed(pointer) => match self.drag.take() {
+                Some(SelectionDrag::New { anchor }) if pointer.delta.x.abs() <= 2.0 => {
+                    if self.selection.take().is_some() {
+                        self.outputs.add_output(WaveformEvent::SelectionChanged(None));
+                    }
+                    self.outputs.add_output(WaveformEvent::Seek(anchor));
+                }
+                Some(_) => self.outputs.add_output(WaveformEvent::SelectionChanged(self.selection.clone())),
+                None => {}
+            },
+            _ => {}
+        }
+        self.update_can_scroll(cx);
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        let fit = self.samples_per_pixel == 0.0;
+        self.set_bounds(cx, bounds);
+        if fit {
+            self.zoom_to_fit(cx);
+        } else {
+            self.clamp_view(cx);
+        }
+        self.update_can_scroll(cx);
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
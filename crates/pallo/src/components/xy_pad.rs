@@ -0,0 +1,212 @@
+use crate::*;
+
+/// How much slower the pucks move while shift is held.
+const FINE_FACTOR: f32 = 0.1;
+
+struct Puck {
+    x: Signal<f32>,
+    y: Signal<f32>,
+    /// The springs pulling the puck back to its rest position, added when it's first released.
+    springs: Option<(SpringId, SpringId)>,
+}
+
+/// A pointer dragging a puck. The puck moves relative to where it was at `anchor_value` when the pointer was at
+/// `anchor_position`, which are moved whenever fine adjustment is turned on or off so the puck doesn't jump.
+struct Grab {
+    pointer: PointerId,
+    puck: usize,
+    anchor_value: Point,
+    anchor_position: Point,
+    fine: bool,
+}
+
+/// A two dimensional control with pucks whose positions are bound to pairs of signals from 0 to 1, with `x` growing to
+/// the right and `y` upwards. Pressing the pad grabs the nearest puck that isn't already held, moving it under the
+/// pointer unless the press was on it, so on touch screens each finger can move a puck of its own. Holding shift moves
+/// the pucks slower for fine adjustment.
+pub struct XYPad {
+    pub id: ComponentId,
+    pucks: Vec<Puck>,
+    grabs: Vec<Grab>,
+    /// The position that released pucks spring back to, if any.
+    spring_return: Option<(Point, Spring)>,
+    puck_radius: f32,
+    background_color: Computed<Color>,
+    crosshair_color: Computed<Color>,
+    puck_color: Computed<Color>,
+}
+
+impl XYPad {
+    pub fn new<A: App>(cx: &mut Cx<A>, id: ComponentId, x: Signal<f32>, y: Signal<f32>) -> Self {
+        cx.set_interactive(&id, true);
+        Self {
+            id,
+            pucks: vec![Puck { x, y, springs: None }],
+            grabs: vec![],
+            spring_return: None,
+            puck_radius: 8.0,
+            background_color: rgb(0xffffff).with_alpha(0.06).into(),
+            crosshair_color: rgb(0xffffff).with_alpha(0.25).into(),
+            puck_color: rgb(0x0a84ff).into(),
+        }
+    }
+
+    /// Adds another puck, e.g. for controlling two sounds at once with two fingers.
+    pub fn with_puck(mut self, x: Signal<f32>, y: Signal<f32>) -> Self {
+        self.pucks.push(Puck { x, y, springs: None });
+        self
+    }
+
+    /// Makes released pucks spring back to `rest`, like the stick of a joystick or a pitch bend wheel.
+    pub fn with_spring_return(mut self, rest: impl Into<Point>, spring: Spring) -> Self {
+        self.spring_return = Some((rest.into(), spring));
+        self
+    }
+
+    pub fn with_puck_radius(mut self, radius: f32) -> Self {
+        self.puck_radius = radius;
+        self
+    }
+
+    pub fn with_colors(
+        mut self,
+        background: impl Into<Computed<Color>>,
+        crosshair: impl Into<Computed<Color>>,
+        puck: impl Into<Computed<Color>>,
+    ) -> Self {
+        self.background_color = background.into();
+        self.crosshair_color = crosshair.into();
+        self.puck_color = puck.into();
+        self
+    }
+
+    fn get_value(&self, puck: usize) -> Point {
+        let puck = &self.pucks[puck];
+        point(puck.x.get_fast(), puck.y.get_fast())
+    }
+
+    fn set_value(&self, puck: usize, value: Point) {
+        let puck = &self.pucks[puck];
+        puck.x.set_if_changed(value.x.clamp(0.0, 1.0));
+        puck.y.set_if_changed(value.y.clamp(0.0, 1.0));
+    }
+
+    fn value_to_position(bounds: Rect, value: Point) -> Point {
+        point(bounds.left() + value.x * bounds.width(), bounds.bottom() - value.y * bounds.height())
+    }
+
+    fn position_to_value(bounds: Rect, position: Point) -> Point {
+        point((position.x - bounds.left()) / bounds.width(), (bounds.bottom() - position.y) / bounds.height())
+    }
+
+    /// The puck nearest to `position` that no other pointer is holding.
+    fn nearest_free_puck(&self, bounds: Rect, position: Point) -> Option<usize> {
+        (0..self.pucks.len())
+            .filter(|puck| !self.grabs.iter().any(|grab| grab.puck == *puck))
+            .map(|puck| (puck, Self::value_to_position(bounds, self.get_value(puck)).distance_to(position)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(puck, _)| puck)
+    }
+
+    fn grab<A: App>(&mut self, cx: &mut Cx<A>, pointer: &PointerState<A>) {
+        let bounds = self.get_bounds(cx);
+        let Some(puck) = self.nearest_free_puck(bounds, pointer.position) else {
+            return;
+        };
+        if let Some((x, y)) = &self.pucks[puck].springs {
+            let value = self.get_value(puck);
+            cx.animations.reset_spring(x, value.x);
+            cx.animations.reset_spring(y, value.y);
+        }
+        let position = Self::value_to_position(bounds, self.get_value(puck));
+        if position.distance_to(pointer.position) > self.puck_radius {
+            self.set_value(puck, Self::position_to_value(bounds, pointer.position));
+        }
+        self.grabs.push(Grab {
+            pointer: pointer.id,
+            puck,
+            anchor_value: self.get_value(puck),
+            anchor_position: pointer.position,
+            fine: cx.mods.shift,
+        });
+    }
+
+    fn drag<A: App>(&mut self, cx: &mut Cx<A>, pointer: &PointerState<A>) {
+        let bounds = self.get_bounds(cx);
+        let fine = cx.mods.shift;
+        let Some(index) = self.grabs.iter().position(|grab| grab.pointer == pointer.id) else {
+            return;
+        };
+        if self.grabs[index].fine != fine {
+            let value = self.get_value(self.grabs[index].puck);
+            let grab = &mut self.grabs[index];
+            grab.anchor_value = value;
+            grab.anchor_position = pointer.position;
+            grab.fine = fine;
+        }
+        let grab = &self.grabs[index];
+        let moved = pointer.position - grab.anchor_position;
+        let scale = if fine { FINE_FACTOR } else { 1.0 };
+        let delta = point(moved.x / bounds.width(), -moved.y / bounds.height()) * scale;
+        self.set_value(grab.puck, grab.anchor_value + delta);
+    }
+
+    fn release<A: App>(&mut self, cx: &mut Cx<A>, pointer: &PointerState<A>) {
+        let Some(index) = self.grabs.iter().position(|grab| grab.pointer == pointer.id) else {
+            return;
+        };
+        let grab = self.grabs.remove(index);
+        let Some((rest, spring)) = self.spring_return else {
+            return;
+        };
+        let puck = &mut self.pucks[grab.puck];
+        let (x, y) = puck.springs.get_or_insert_with(|| {
+            (cx.animations.add_spring(spring, puck.x.clone()), cx.animations.add_spring(spring, puck.y.clone()))
+        });
+        cx.animations.reset_spring(x, puck.x.get_fast());
+        cx.animations.reset_spring(y, puck.y.get_fast());
+        cx.animations.set_spring_target(x, rest.x);
+        cx.animations.set_spring_target(y, rest.y);
+    }
+}
+
+impl<A: App> Component<A> for XYPad {
+    fn draw(&self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let bounds = self.get_bounds(cx);
+        canvas.fill(self.background_color.get()).draw_round_rect(bounds, 4.0);
+        canvas.with_clip_rect(bounds, |canvas| {
+            let crosshair_color = self.crosshair_color.get();
+            for puck in 0..self.pucks.len() {
+                let position = Self::value_to_position(bounds, self.get_value(puck));
+                canvas.fill(crosshair_color).draw_rect(bounds.with_left(position.x - 0.5).with_width(1.0));
+                canvas.fill(crosshair_color).draw_rect(bounds.with_top(position.y - 0.5).with_height(1.0));
+            }
+            let puck_color = self.puck_color.get();
+            for puck in 0..self.pucks.len() {
+                let position = Self::value_to_position(bounds, self.get_value(puck));
+                let held = self.grabs.iter().any(|grab| grab.puck == puck);
+                if held {
+                    canvas.fill(puck_color.with_alpha_mul(0.3)).draw_circle(position, self.puck_radius * 1.75);
+                }
+                canvas.fill(puck_color).draw_circle(position, self.puck_radius);
+            }
+        });
+    }
+
+    fn event(&mut self, cx: &mut Cx<A>, event: &mut Event<A>) {
+        match event {
+            Event::PointerDown(pointer) if self.is_hovered(pointer) => self.grab(cx, pointer),
+            Event::PointerMove(pointer) => self.drag(cx, pointer),
+            Event::PointerUp(pointer) => self.release(cx, pointer),
+            _ => {}
+        }
+    }
+
+    fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+        self.set_bounds(cx, bounds);
+    }
+
+    fn id(&self) -> &ComponentId {
+        &self.id
+    }
+}
@@ -1,14 +1,26 @@
 use rustc_hash::FxHashMap;
-use std::{cell::RefCell, collections::VecDeque, ops::Deref, rc::Rc};
+use std::{any::Any, cell::RefCell, collections::VecDeque, ops::Deref, rc::Rc};
 use web_time::Instant;
 
 use crate::{
-    Animations, AnyEvent, App, Component, Event, IntPoint, Modifiers, Overlay, Point, PointerId, PointerState,
-    Property, PropertyId, Rect, Signal, SignalCx,
-    component::{ComponentId, ComponentState, WeakComponentId},
+    Animations, AnyEvent, App, Canvas, CanvasType, ChannelReceiver, ChannelSender, ColorTransition, Component,
+    ControllerMap, Easing, Error, Event, EventKinds, FileLoad, FrameStats, IntPoint, Localization, Margin, Matrix,
+    Modifiers, Point, PointerId, PointerState, Property, PropertyId, Rect, Signal, SignalCx, Spring, SpringId, Surface,
+    SurfacePoolStats, TweenId,
+    component::{ComponentId, ComponentState, Unmount, WeakComponentId, WeakOverlay},
+    damage::Damage,
+    fetch::Fetcher,
+    file_loader::FileLoader,
+    font_cache::FontCache,
+    image_cache::ImageCache,
+    live_channel::LiveChannels,
+    overlay::Overlays,
     platform::Platform,
     renderers::{RendererType, renderer::Renderer},
+    rgb,
+    screenshot::Screenshots,
     tree::{NodeId, Tree},
+    utils::{LaterCallback, panic_message},
 };
 
 pub struct Cx<A: App> {
@@ -22,14 +34,34 @@ pub struct Cx<A: App> {
     pub frame_time_micros: u128,
     pub draw_time_micros: u128,
     pub update_time_micros: u128,
+    pub frame_stats: FrameStats,
     pub(crate) backend: Renderer,
     pub mods: Modifiers,
     pub frame_delta_ms: f32,
+    /// How many times per second the display refreshes, as reported by the `DisplayLink` drawing the UI. Assumed to
+    /// be 60 otherwise.
+    pub refresh_rate: f32,
     pub scale_factor: Signal<f32>,
     pub ui_scale: f32,
+    /// Skips `Event::Update` for components hidden with `set_visible(false)` and their descendants, unless they opted
+    /// out with `set_updates_when_hidden`. Off by default because components may rely on updates to catch up.
+    pub skip_hidden_updates: bool,
+    /// Catches panics in components' `draw` and `event`, so that instead of taking down the host (e.g. the DAW that
+    /// a plugin runs in) the component that panicked is logged, disabled and drawn as a placeholder from then on.
+    /// Off by default because a panic may leave the component or the app in a state that isn't valid.
+    pub catch_panics: bool,
+    pub(crate) damage: Damage,
     pub(crate) resize: Option<IntPoint>,
     pub ui_bounds: Rect,
-    pub(crate) overlays: Vec<Overlay<dyn Component<A>>>,
+    /// The edges of `ui_bounds` covered by notches, system bars or the on-screen keyboard, see `safe_bounds`.
+    pub safe_area: Signal<Margin>,
+    /// Lays the root out within `safe_bounds` instead of all of `ui_bounds`. Off by default so that backgrounds can
+    /// extend under the insets, with components keeping their content out of them with `safe_bounds`.
+    pub layout_in_safe_area: bool,
+    pub(crate) overlays: Overlays<A>,
+    pub(crate) portals: Vec<(NodeId, WeakOverlay<dyn Component<A>>)>,
+    /// The scroll container that the wheel event being sent is for.
+    pub(crate) wheel_target: Option<NodeId>,
     signal_cx: SignalCx,
     pub num_clicks: usize,
     pub(crate) num_clicks_component: Option<NodeId>,
@@ -37,12 +69,29 @@ pub struct Cx<A: App> {
     pub(crate) previous_pointer_down_time: Instant,
     pub num_frames: u64,
     pub platform: Platform,
+    pub(crate) file_loader: FileLoader,
+    pub(crate) fetcher: Fetcher,
+    pub(crate) live_channels: LiveChannels<A>,
+    pub(crate) image_cache: ImageCache,
+    pub(crate) font_cache: FontCache,
+    /// The translations used by `tr`.
+    pub i18n: Localization,
+    pub(crate) catalog_loads: Vec<(String, FileLoad)>,
+    pub(crate) screenshots: Screenshots,
+    pub(crate) unmounting: Vec<NodeId>,
+    bounds_animations: Vec<(NodeId, TweenId<Rect>)>,
+    pub(crate) later_callbacks: Vec<LaterCallback<A>>,
+    /// Hardware controls bound to signals.
+    pub controllers: ControllerMap,
+    #[cfg(feature = "nih")]
+    pub(crate) param_bindings: crate::nih::ParamBindings,
 }
 
 impl<A: App> Cx<A> {
     pub fn new(init: A::AppInit, platform: Platform) -> Self {
         let signal_cx = SignalCx::new();
         let app = A::new(&signal_cx, init);
+        let i18n = Localization::new(&signal_cx, "en");
         Self {
             tree: Default::default(),
             component_ids: vec![],
@@ -54,38 +103,93 @@ impl<A: App> Cx<A> {
             frame_time_micros: 0,
             draw_time_micros: 0,
             update_time_micros: 0,
+            frame_stats: Default::default(),
             backend: Default::default(),
             mods: Default::default(),
             frame_delta_ms: 0.0,
+            refresh_rate: 60.0,
             scale_factor: signal_cx.signal(1.0),
             resize: None,
             ui_bounds: Default::default(),
-            overlays: vec![],
+            safe_area: signal_cx.signal_default(),
+            layout_in_safe_area: false,
+            overlays: Default::default(),
+            portals: vec![],
+            wheel_target: None,
             signal_cx,
             num_frames: 0,
             ui_scale: 1.0,
+            skip_hidden_updates: false,
+            catch_panics: false,
+            damage: Default::default(),
             num_clicks: 0,
             num_clicks_component: None,
             previous_pointer_down_time: Instant::now(),
             previous_pointer_down_position: Point::new(0.0, 0.0),
             platform,
+            file_loader: Default::default(),
+            fetcher: Default::default(),
+            live_channels: Default::default(),
+            image_cache: Default::default(),
+            font_cache: Default::default(),
+            i18n,
+            catalog_loads: vec![],
+            screenshots: Default::default(),
+            unmounting: vec![],
+            bounds_animations: vec![],
+            later_callbacks: vec![],
+            controllers: Default::default(),
+            #[cfg(feature = "nih")]
+            param_bindings: Default::default(),
         }
     }
 
+    /// Adds a typeface for `id`, replacing any that was added for it before.
     pub fn add_font(&mut self, id: A::FontId, data: &[u8]) {
-        self.backend.add_typeface(id, data)
+        let id = id.into();
+        self.backend.add_typeface(id, data);
+        self.font_cache.remove_typeface(id);
+    }
+
+    /// A surface of `size` for caching drawings, which reuses one that was dropped if one of about the same size is
+    /// free, so that components whose size keeps changing don't allocate a new one each time.
+    pub fn create_surface(&self, size: IntPoint, scale_factor: f32) -> Surface {
+        self.backend.create_surface(size, scale_factor)
+    }
+
+    /// Frees the dropped surfaces that are kept for reuse until they take at most `max_bytes`, e.g. when the app goes
+    /// to the background.
+    pub fn trim_surface_pool(&mut self, max_bytes: usize) {
+        self.backend.trim_surface_pool(max_bytes);
+    }
+
+    pub fn surface_pool_stats(&self) -> SurfacePoolStats {
+        self.backend.surface_pool_stats()
     }
 
     pub fn send_event(&mut self, event: Event<A>) {
         self.input.push_back(event);
     }
 
+    /// Logs `error` and broadcasts it as `Event::PlatformError` so the app can degrade gracefully.
+    pub fn report_error(&mut self, error: Error) {
+        crate::error!("{error}");
+        self.input.push_back(Event::PlatformError(error));
+    }
+
     pub fn send_app_event(&mut self, event: A::Input) {
         self.input.push_back(Event::App(event));
     }
 
     pub fn send_any_event<T: 'static>(&mut self, data: T) {
-        self.input.push_back(Event::Any(AnyEvent(Box::new(data))));
+        self.input.push_back(Event::Any(AnyEvent::new(data)));
+    }
+
+    /// Creates a channel for messages of type `T`, e.g. for a library component to receive its own messages without
+    /// going through `A::Input`. Unlike app events, which are broadcast to every component, the messages only reach
+    /// the component polling the receiver.
+    pub fn channel<T>(&self) -> (ChannelSender<T>, ChannelReceiver<T>) {
+        crate::utils::channel()
     }
 
     pub(crate) fn add_child<T>(
@@ -98,18 +202,192 @@ impl<A: App> Cx<A> {
     }
 
     pub fn add_child_id(&mut self, parent_id: impl Into<NodeId>) -> ComponentId {
-        let id = ComponentId(Rc::new(self.tree.add(parent_id.into())));
+        let parent_id = parent_id.into();
+        let id = ComponentId(Rc::new(self.tree.add(parent_id)));
+        self.component_ids.push(id.clone());
+        Self::propagate_new_child(&mut self.tree, parent_id);
+        id
+    }
+
+    /// Like `add_child_id`, but the new component is placed at `index` among its siblings instead of last, which puts
+    /// it behind the siblings after it.
+    pub fn insert_child_id_at(&mut self, parent_id: impl Into<NodeId>, index: usize) -> ComponentId {
+        let parent_id = parent_id.into();
+        let id = ComponentId(Rc::new(self.tree.insert(parent_id, index)));
         self.component_ids.push(id.clone());
+        Self::propagate_new_child(&mut self.tree, parent_id);
         id
     }
 
+    fn propagate_new_child(tree: &mut Tree<ComponentState<A>>, parent_id: NodeId) {
+        // new components get every event, so their ancestors have to as well
+        let mut node_id = Some(parent_id);
+        while let Some(id) = node_id {
+            let state = tree.get_mut(id);
+            if state.subtree_flags.subscriptions == EventKinds::ALL {
+                break;
+            }
+            state.subtree_flags.subscriptions = EventKinds::ALL;
+            node_id = tree.get_parent(id);
+        }
+    }
+
+    /// Moves the component `id` and its descendants under `new_parent`, after its existing children.
+    pub fn reparent(&mut self, id: impl Into<NodeId>, new_parent: impl Into<NodeId>) {
+        self.reparent_at(id, new_parent, usize::MAX);
+    }
+
+    /// Moves the component `id` and its descendants to `index` among the children of `new_parent`. The component
+    /// keeps its state and focus, both parents are laid out again, and pointers stop hovering it until they move.
+    ///
+    /// Panics if `new_parent` is `id` or one of its descendants.
+    pub fn reparent_at(&mut self, id: impl Into<NodeId>, new_parent: impl Into<NodeId>, index: usize) {
+        let id = id.into();
+        let new_parent = new_parent.into();
+        assert!(!self.contains_child(id, new_parent), "can't move a component inside itself");
+        let Some(old_parent) = self.tree.get_parent(id) else {
+            return;
+        };
+        self.tree.move_node(id, new_parent, index);
+        Self::update_subtree_flags(&mut self.tree, old_parent);
+        Self::update_subtree_flags(&mut self.tree, new_parent);
+        for pointer in self.pointer_state.values_mut() {
+            if pointer.hovered_component.is_some_and(|hovered| Self::is_inside(&self.tree, id, hovered)) {
+                pointer.hovered_component = None;
+            }
+        }
+        self.notify_size_changed(old_parent);
+        self.notify_size_changed(id);
+    }
+
+    pub fn get_parent(&self, id: impl Into<NodeId>) -> Option<WeakComponentId> {
+        self.tree.get_parent(id.into()).map(WeakComponentId)
+    }
+
+    /// The children of `id` from back to front, i.e. in the order they're drawn in.
+    pub fn get_children(&self, id: impl Into<NodeId>) -> impl Iterator<Item = WeakComponentId> + '_ {
+        self.tree.get_children(id.into()).iter().copied().map(WeakComponentId)
+    }
+
+    /// The position of `id` among its siblings.
+    pub fn sibling_index(&self, id: impl Into<NodeId>) -> Option<usize> {
+        let id = id.into();
+        let parent = self.tree.get_parent(id)?;
+        self.tree.get_children(parent).iter().position(|child| *child == id)
+    }
+
+    pub fn next_sibling(&self, id: impl Into<NodeId>) -> Option<WeakComponentId> {
+        let id = id.into();
+        let index = self.sibling_index(id)?;
+        let parent = self.tree.get_parent(id)?;
+        self.tree.get_children(parent).get(index + 1).copied().map(WeakComponentId)
+    }
+
+    pub fn previous_sibling(&self, id: impl Into<NodeId>) -> Option<WeakComponentId> {
+        let id = id.into();
+        let index = self.sibling_index(id)?.checked_sub(1)?;
+        let parent = self.tree.get_parent(id)?;
+        self.tree.get_children(parent).get(index).copied().map(WeakComponentId)
+    }
+
+    pub(crate) fn set_subscriptions(&mut self, id: impl Into<NodeId>, kinds: EventKinds) {
+        let id = id.into();
+        self.tree.get_mut(id).flags.subscriptions = kinds;
+        Self::update_subtree_flags(&mut self.tree, id);
+    }
+
+    pub(crate) fn set_updates_when_hidden(&mut self, id: impl Into<NodeId>, value: bool) {
+        let id = id.into();
+        self.tree.get_mut(id).flags.updates_when_hidden = value;
+        Self::update_subtree_flags(&mut self.tree, id);
+    }
+
+    pub(crate) fn set_draws_outside_bounds(&mut self, id: impl Into<NodeId>, value: bool) {
+        let id = id.into();
+        self.tree.get_mut(id).flags.draws_outside_bounds = value;
+        Self::update_subtree_flags(&mut self.tree, id);
+    }
+
+    /// Recomputes the flags that combine a component's own with its descendants' after either changed, from `id` up
+    /// to the first ancestor that isn't affected.
+    fn update_subtree_flags(tree: &mut Tree<ComponentState<A>>, id: NodeId) {
+        let mut node_id = Some(id);
+        while let Some(id) = node_id {
+            let flags = tree
+                .get_children(id)
+                .iter()
+                .fold(tree.get(id).flags, |flags, child| flags.combine(tree.get(*child).subtree_flags));
+            let state = tree.get_mut(id);
+            if state.subtree_flags == flags {
+                break;
+            }
+            state.subtree_flags = flags;
+            node_id = tree.get_parent(id);
+        }
+    }
+
+    /// Whether `event` should be dispatched to the component `id` or its descendants.
+    pub fn wants_event(&self, id: impl Into<NodeId>, event: &Event<A>) -> bool {
+        self.tree.get(id.into()).wants_event(event, self.skip_hidden_updates)
+    }
+
+    /// Whether the component `id` is entirely outside of the canvas' clip, so that drawing it can be skipped. Components
+    /// without a size aren't culled, since they may only be grouping children that are laid out elsewhere.
+    pub(crate) fn is_culled(&self, id: impl Into<NodeId>, canvas: &Canvas) -> bool {
+        self.tree.get(id.into()).is_culled(canvas.local_clip_bounds())
+    }
+
+    /// Whether the component `id` panicked while `catch_panics` was on. It's neither drawn nor sent events after that.
+    pub fn has_panicked(&self, id: impl Into<NodeId>) -> bool {
+        self.tree.get(id.into()).panicked
+    }
+
+    /// Logs the panic that the component `id` of type `component` had in `method`, and disables the component.
+    pub(crate) fn report_panic(
+        &mut self,
+        id: impl Into<NodeId>,
+        component: &str,
+        method: &str,
+        panic: Box<dyn Any + Send>,
+    ) {
+        let id = id.into();
+        let state = self.tree.get_mut(id);
+        state.panicked = true;
+        state.disabled = true;
+        if self.focused_component == Some(id) {
+            self.focused_component = None;
+        }
+        crate::error!("{component} panicked in `{method}` and was disabled: {}", panic_message(&*panic));
+    }
+
+    /// Draws what's shown in place of a component that panicked.
+    pub(crate) fn draw_panic_placeholder(&self, id: impl Into<NodeId>, canvas: &mut Canvas) {
+        const COLOR: u32 = 0xe5484d;
+        let bounds = self.tree.get(id.into()).bounds;
+        canvas
+            .fill(rgb(COLOR).with_alpha(0.2))
+            .draw_rect(bounds)
+            .stroke(rgb(COLOR), 1.0)
+            .draw_rect(bounds.with_expansion(-0.5));
+    }
+
+    /// Whether `id` still refers to a component, i.e. it hasn't been dropped and garbage collected. Useful for
+    /// `WeakComponentId`s that outlive their component.
+    pub fn is_alive(&self, id: impl Into<NodeId>) -> bool {
+        self.tree.contains(id.into())
+    }
+
     pub(crate) fn is_visible(&self, id: impl Into<NodeId>) -> bool {
-        let mut node_id = Some(id.into());
+        Self::is_visible_in(&self.tree, id.into())
+    }
+
+    pub(crate) fn is_visible_in(tree: &Tree<ComponentState<A>>, id: NodeId) -> bool {
+        let mut node_id = Some(id);
         while let Some(id) = node_id {
-            if !self.tree.get(id).visible {
+            if !tree.get(id).visible {
                 return false;
             }
-            node_id = self.tree.get_parent(id);
+            node_id = tree.get_parent(id);
         }
         true
     }
@@ -142,17 +420,110 @@ impl<A: App> Cx<A> {
     }
 
     pub fn contains_child(&self, parent: impl Into<NodeId>, child: impl Into<NodeId>) -> bool {
-        let parent: NodeId = parent.into();
-        let mut child: Option<NodeId> = Some(child.into());
+        Self::is_inside(&self.tree, parent.into(), child.into())
+    }
+
+    fn is_inside(tree: &Tree<ComponentState<A>>, parent: NodeId, child: NodeId) -> bool {
+        let mut child = Some(child);
         while let Some(id) = child {
             if id == parent {
                 return true;
             }
-            child = self.tree.get_parent(id);
+            child = tree.get_parent(id);
         }
         false
     }
 
+    /// Starts removing the component `id`. It gets `Event::WillUnmount`, loses hover and focus and stops receiving
+    /// input, but keeps drawing for `exit_ms` so that it can animate out. Its owner drops it once `is_unmounted`.
+    pub fn unmount(&mut self, id: impl Into<NodeId>, exit_ms: f32) {
+        let id = id.into();
+        let state = self.tree.get_mut(id);
+        if state.unmount.is_some() {
+            return;
+        }
+        state.unmount = Some(Unmount { elapsed_ms: 0.0, duration_ms: exit_ms });
+        self.unmounting.push(id);
+        for pointer in self.pointer_state.values_mut() {
+            if pointer.hovered_component.is_some_and(|hovered| Self::is_inside(&self.tree, id, hovered)) {
+                pointer.hovered_component = None;
+            }
+            if pointer.pressed_component.is_some_and(|pressed| Self::is_inside(&self.tree, id, pressed)) {
+                pointer.pressed_component = None;
+            }
+        }
+        if self.focused_component.is_some_and(|focused| Self::is_inside(&self.tree, id, focused)) {
+            self.set_focus(None::<NodeId>);
+        }
+        self.send_event(Event::WillUnmount(WeakComponentId(id)));
+    }
+
+    pub fn is_unmounting(&self, id: impl Into<NodeId>) -> bool {
+        self.tree.get(id.into()).unmount.is_some()
+    }
+
+    /// Whether the exit animation of an unmounting component is done and it can be dropped.
+    pub fn is_unmounted(&self, id: impl Into<NodeId>) -> bool {
+        self.unmount_progress(id).is_some_and(|progress| progress >= 1.0)
+    }
+
+    /// How far the exit animation of an unmounting component is, from 0 to 1.
+    pub fn unmount_progress(&self, id: impl Into<NodeId>) -> Option<f32> {
+        self.tree.get(id.into()).unmount.map(|unmount| unmount.progress())
+    }
+
+    /// Tweens the bounds of `id` from their current value to `target`. Until it's done, the animated bounds override
+    /// the ones set by layout, and the component is marked as needing a relayout every frame.
+    pub fn animate_bounds(&mut self, id: impl Into<NodeId>, target: Rect, duration_ms: f32, easing: Easing) {
+        let id = id.into();
+        if let Some((_, tween)) = self.bounds_animations.iter().find(|(i, _)| *i == id) {
+            self.animations.set_tween(tween, target);
+            return;
+        }
+        let tween = self.animations.add_tween(self.tree.get(id).bounds, duration_ms, easing);
+        self.animations.set_tween(&tween, target);
+        self.bounds_animations.push((id, tween));
+    }
+
+    /// Adds a spring at rest at `value`, whose value can also be read with `SpringId::as_computed`.
+    pub fn add_spring(&mut self, spring: Spring, value: f32) -> SpringId {
+        let value = self.signal_cx.signal(value);
+        self.animations.add_spring(spring, value)
+    }
+
+    /// Adds a color transition for the targets given to `Animations::set_color_transition_target`, whose color can
+    /// also be read with `ColorTransition::as_computed`.
+    pub fn add_color_transition(&mut self, duration_ms: f32, easing: Easing) -> ColorTransition {
+        let value = self.signal_cx.signal_default();
+        self.animations.add_color_transition(value, None, duration_ms, easing)
+    }
+
+    pub(crate) fn tick_bounds_animations(&mut self) {
+        let mut animations = std::mem::take(&mut self.bounds_animations);
+        animations.retain(|(id, tween)| {
+            if !self.tree.contains(*id) {
+                return false;
+            }
+            self.tree.get_mut(*id).bounds = self.animations.get_tween(tween);
+            self.set_needs_relayout(*id, true);
+            !self.animations.is_tween_done(tween)
+        });
+        self.bounds_animations = animations;
+    }
+
+    pub(crate) fn tick_unmounts(&mut self, delta_ms: f32) {
+        let tree = &mut self.tree;
+        self.unmounting.retain(|id| {
+            if !tree.contains(*id) {
+                return false;
+            }
+            if let Some(unmount) = &mut tree.get_mut(*id).unmount {
+                unmount.elapsed_ms += delta_ms;
+            }
+            true
+        });
+    }
+
     pub(crate) fn is_disabled(tree: &Tree<ComponentState<A>>, id: impl Into<NodeId>) -> bool {
         let mut node_id = Some(id.into());
         while let Some(id) = node_id {
@@ -201,6 +572,22 @@ impl<A: App> Cx<A> {
         self.tree.get_mut(id.into()).bounds = bounds;
     }
 
+    /// Records the canvas' matrix that `id` is being drawn with, relative to the UI scale.
+    pub(crate) fn set_transform(&mut self, id: impl Into<NodeId>, canvas_matrix: Matrix) {
+        self.tree.get_mut(id.into()).transform = Matrix::scale(1.0 / self.ui_scale) * canvas_matrix;
+    }
+
+    /// Converts `point` from the root's coordinates (e.g. a pointer position) into those of `id`, taking into account
+    /// any rotation, scaling or translation it was last drawn with.
+    pub fn to_local(&self, id: impl Into<NodeId>, point: Point) -> Point {
+        self.tree.get(id.into()).to_local(point)
+    }
+
+    /// Converts `point` from the coordinates of `id` into the root's.
+    pub fn to_global(&self, id: impl Into<NodeId>, point: Point) -> Point {
+        self.tree.get(id.into()).transform.map_point(point)
+    }
+
     pub(crate) fn set_visible(&mut self, c: impl Into<NodeId>, visible: bool) {
         self.tree.get_mut(c.into()).visible = visible;
     }
@@ -285,15 +672,25 @@ impl<A: App> Cx<A> {
         self.resize = Some(size.into());
     }
 
-    pub fn add_overlay<C: Component<A> + 'static>(
-        &mut self,
-        add_component: impl FnOnce(&mut Cx<A>, ComponentId) -> C,
-    ) -> Overlay<C> {
-        let id = ComponentId(Rc::new(self.tree.add(self.tree.get_root_id())));
-        let component = Rc::new(RefCell::new((add_component)(self, id.clone())));
-        self.component_ids.push(id);
-        self.overlays.push(component.clone());
-        component
+    /// Draws and hit-tests `component` above the rest of the UI from now on, see `Portal`.
+    pub(crate) fn add_portal<C: Component<A> + 'static>(&mut self, component: &Rc<RefCell<C>>) {
+        let id = NodeId::from(component.borrow().id());
+        self.tree.get_mut(id).portal = true;
+        let component: Rc<RefCell<dyn Component<A>>> = component.clone();
+        self.portals.push((id, Rc::downgrade(&component)));
+    }
+
+    /// The part of `ui_bounds` that isn't covered by the insets in `safe_area`.
+    pub fn safe_bounds(&self) -> Rect {
+        self.ui_bounds.with_margin(self.safe_area.get())
+    }
+
+    pub(crate) fn root_bounds(&self) -> Rect {
+        if self.layout_in_safe_area {
+            self.ui_bounds.with_margin(self.safe_area.get_fast())
+        } else {
+            self.ui_bounds
+        }
     }
 
     pub fn move_to_front(&mut self, id: impl Into<NodeId>) {
@@ -381,3 +778,146 @@ impl<A: App> Deref for Cx<A> {
         &self.signal_cx
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Code, Key, Location, ScrollDelta, ScrollPhase, point, ui::tests::TestApp};
+
+    type TestCx = Cx<TestApp>;
+
+    #[test]
+    fn ancestors_get_the_events_their_descendants_subscribed_to() {
+        let mut tree = Tree::<ComponentState<TestApp>>::default();
+        let root = tree.get_root_id();
+        let panel = tree.add(root);
+        let button = tree.add(panel);
+        for (id, kinds) in [
+            (root, EventKinds::NONE),
+            (panel, EventKinds::KEYBOARD),
+            (button, EventKinds::POINTER),
+        ] {
+            tree.get_mut(id).flags.subscriptions = kinds;
+            TestCx::update_subtree_flags(&mut tree, id);
+        }
+        let subscriptions = |tree: &Tree<ComponentState<TestApp>>, id| tree.get(id).subtree_flags.subscriptions;
+        assert_eq!(subscriptions(&tree, button), EventKinds::POINTER);
+        assert_eq!(subscriptions(&tree, panel), EventKinds::POINTER | EventKinds::KEYBOARD);
+        assert_eq!(subscriptions(&tree, root), EventKinds::POINTER | EventKinds::KEYBOARD);
+
+        let wheel = Event::MouseWheel { delta: ScrollDelta::Pixels(point(0.0, 1.0)), phase: ScrollPhase::Changed };
+        assert!(tree.get(root).wants_event(&wheel, false));
+        assert!(!tree.get(root).wants_event(&Event::Update, false));
+        assert!(!tree.get(button).wants_event(&Event::WindowFocusChanged(true), false));
+
+        // a new component gets everything until it subscribes, so its ancestors do too
+        let label = tree.add(button);
+        TestCx::propagate_new_child(&mut tree, button);
+        assert_eq!(subscriptions(&tree, root), EventKinds::ALL);
+        tree.get_mut(label).flags.subscriptions = EventKinds::NONE;
+        TestCx::update_subtree_flags(&mut tree, label);
+        assert_eq!(subscriptions(&tree, root), EventKinds::POINTER | EventKinds::KEYBOARD);
+    }
+
+    #[test]
+    fn hidden_subtrees_skip_updates_unless_asked_not_to() {
+        let mut tree = Tree::<ComponentState<TestApp>>::default();
+        let root = tree.get_root_id();
+        let panel = tree.add(root);
+        let meter = tree.add(panel);
+        tree.get_mut(panel).visible = false;
+
+        assert!(!tree.get(panel).wants_event(&Event::Update, true));
+        assert!(tree.get(panel).wants_event(&Event::Update, false));
+        assert!(tree.get(panel).wants_event(&Event::WindowFocusChanged(true), true));
+        // only the hidden component itself is checked, its children aren't reached anyway
+        assert!(tree.get(meter).wants_event(&Event::Update, true));
+
+        tree.get_mut(meter).flags.updates_when_hidden = true;
+        TestCx::update_subtree_flags(&mut tree, meter);
+        assert!(tree.get(panel).wants_event(&Event::Update, true));
+    }
+
+    /// Times sending `Event::Update` through a tree where most components are hidden. Run with
+    /// `cargo test --release -- --ignored --nocapture hidden_updates_benchmark`.
+    #[test]
+    #[ignore = "benchmark"]
+    fn hidden_updates_benchmark() {
+        const PANELS: usize = 100;
+        const CHILDREN: usize = 100;
+        const RUNS: u32 = 200;
+        let mut tree = Tree::<ComponentState<TestApp>>::default();
+        let root = tree.get_root_id();
+        for panel in 0..PANELS {
+            let id = tree.add(root);
+            // like the inactive pages of tabs, all but one are hidden
+            tree.get_mut(id).visible = panel == 0;
+            for _ in 0..CHILDREN {
+                tree.add(id);
+            }
+        }
+
+        let dispatch = |skip_hidden_updates: bool| {
+            let mut visited = 0;
+            tree.traverse_depth(root, |_, state| {
+                let wants = state.wants_event(&Event::Update, skip_hidden_updates);
+                visited += wants as usize;
+                wants
+            });
+            visited
+        };
+        for skip_hidden_updates in [false, true] {
+            let start = Instant::now();
+            let mut visited = 0;
+            for _ in 0..RUNS {
+                visited = std::hint::black_box(dispatch(skip_hidden_updates));
+            }
+            let micros = start.elapsed().as_secs_f64() * 1e6 / RUNS as f64;
+            println!("skip_hidden_updates: {skip_hidden_updates}, {visited} components updated in {micros:.1} µs");
+        }
+        assert_eq!(dispatch(false), 1 + PANELS * (CHILDREN + 1));
+        // the root, the visible panel and its children
+        assert_eq!(dispatch(true), 2 + CHILDREN);
+    }
+
+    #[test]
+    fn unmounting_components_only_get_events_that_are_not_input() {
+        let state = ComponentState::<TestApp> {
+            unmount: Some(Unmount { elapsed_ms: 0.0, duration_ms: 200.0 }),
+            ..Default::default()
+        };
+        let key = Event::Keyup { key: Key::Enter, code: Code::Enter, location: Location::Standard };
+        assert!(!state.wants_event(&key, false));
+        assert!(!state.wants_event(&Event::WindowFocusChanged(true), false));
+        assert!(state.wants_event(&Event::Update, false));
+        assert!(state.wants_event(&Event::App(()), false));
+    }
+
+    #[test]
+    fn unmount_progress_goes_from_zero_to_one() {
+        let mut unmount = Unmount { elapsed_ms: 0.0, duration_ms: 200.0 };
+        assert_eq!(unmount.progress(), 0.0);
+        unmount.elapsed_ms = 50.0;
+        assert_eq!(unmount.progress(), 0.25);
+        unmount.elapsed_ms = 250.0;
+        assert_eq!(unmount.progress(), 1.0);
+        assert_eq!(Unmount { elapsed_ms: 0.0, duration_ms: 0.0 }.progress(), 1.0);
+    }
+
+    #[test]
+    fn only_sized_components_outside_the_clip_are_culled() {
+        let clip = Some(Rect::from_xywh(0.0, 0.0, 100.0, 100.0));
+        let mut state =
+            ComponentState::<TestApp> { bounds: Rect::from_xywh(90.0, 90.0, 20.0, 20.0), ..Default::default() };
+        assert!(!state.is_culled(clip));
+        state.bounds = Rect::from_xywh(200.0, 0.0, 20.0, 20.0);
+        assert!(state.is_culled(clip));
+        // an empty clip culls everything
+        assert!(state.is_culled(None));
+        state.subtree_flags.draws_outside_bounds = true;
+        assert!(!state.is_culled(clip));
+        state.subtree_flags.draws_outside_bounds = false;
+        state.bounds = Rect::from_xywh(200.0, 0.0, 0.0, 20.0);
+        assert!(!state.is_culled(clip));
+    }
+}
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Signal;
+
+/// A physical control on a MIDI device or gamepad.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ControlId {
+    MidiCc {
+        channel: u8,
+        number: u8,
+    },
+    MidiNote {
+        channel: u8,
+        note: u8,
+    },
+    MidiPitchBend {
+        channel: u8,
+    },
+    GamepadButton(u8),
+    GamepadAxis(u8),
+    /// Anything else the host knows how to tell apart.
+    Other(u32),
+}
+
+/// A value from a hardware control, normalized by the host to `0.0..=1.0`. Bipolar controls like pitch bend and
+/// gamepad sticks rest at 0.5.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ControllerInput {
+    /// A name for the device, e.g. the MIDI port name, so that the same control on two devices can be told apart.
+    pub device: String,
+    pub control: ControlId,
+    pub value: f32,
+}
+
+impl ControllerInput {
+    pub fn source(&self) -> ControlSource {
+        ControlSource { device: self.device.clone(), control: self.control.clone() }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ControlSource {
+    pub device: String,
+    pub control: ControlId,
+}
+
+struct Binding {
+    source: ControlSource,
+    target: String,
+    signal: Signal<f32>,
+}
+
+/// Binds hardware controls to normalized signals, typically the value of a knob or slider. Targets are named so that
+/// the mapping can be saved with `sources` and restored with `bind`.
+///
+/// "MIDI learn" is `learn` followed by moving a control: the first input that arrives is bound to the target.
+#[derive(Default)]
+pub struct ControllerMap {
+    bindings: Vec<Binding>,
+    learning: Option<(String, Signal<f32>)>,
+}
+
+impl ControllerMap {
+    /// Binds `source` to `signal`, replacing whatever `source` or `target` were bound to before.
+    pub fn bind(&mut self, source: ControlSource, target: impl Into<String>, signal: Signal<f32>) {
+        let target = target.into();
+        self.bindings.retain(|binding| binding.source != source && binding.target != target);
+        self.bindings.push(Binding { source, target, signal });
+    }
+
+    /// Binds the next control that is moved to `signal`.
+    pub fn learn(&mut self, target: impl Into<String>, signal: Signal<f32>) {
+        self.learning = Some((target.into(), signal));
+    }
+
+    pub fn cancel_learn(&mut self) {
+        self.learning = None;
+    }
+
+    /// The target waiting for a control to be moved, if any.
+    pub fn learning(&self) -> Option<&str> {
+        self.learning.as_ref().map(|(target, _)| target.as_str())
+    }
+
+    pub fn unbind(&mut self, target: &str) {
+        self.bindings.retain(|binding| binding.target != target);
+    }
+
+    pub fn source_for(&self, target: &str) -> Option<&ControlSource> {
+        self.bindings.iter().find(|binding| binding.target == target).map(|binding| &binding.source)
+    }
+
+    /// Every bound target and its control.
+    pub fn sources(&self) -> impl Iterator<Item = (&str, &ControlSource)> {
+        self.bindings.iter().map(|binding| (binding.target.as_str(), &binding.source))
+    }
+
+    pub(crate) fn handle(&mut self, input: &ControllerInput) {
+        if let Some((target, signal)) = self.learning.take() {
+            self.bind(input.source(), target, signal);
+        }
+        for binding in &self.bindings {
+            if binding.source.device == input.device && binding.source.control == input.control {
+                binding.signal.set(input.value.clamp(0.0, 1.0));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SignalCx;
+
+    fn cc(number: u8, value: f32) -> ControllerInput {
+        ControllerInput { device: "keys".into(), control: ControlId::MidiCc { channel: 0, number }, value }
+    }
+
+    #[test]
+    fn learn_binds_the_next_control() {
+        let cx = SignalCx::new();
+        let cutoff = cx.signal(0.0);
+        let mut map = ControllerMap::default();
+
+        map.learn("cutoff", cutoff.clone());
+        assert_eq!(map.learning(), Some("cutoff"));
+        map.handle(&cc(74, 0.25));
+        assert_eq!(map.learning(), None);
+        assert_eq!(cutoff.get_fast(), 0.25);
+
+        map.handle(&cc(74, 0.5));
+        assert_eq!(cutoff.get_fast(), 0.5);
+        // other controls are ignored
+        map.handle(&cc(71, 1.0));
+        assert_eq!(cutoff.get_fast(), 0.5);
+
+        // learning again moves the binding
+        map.learn("cutoff", cutoff.clone());
+        map.handle(&cc(71, 1.0));
+        map.handle(&cc(74, 0.0));
+        assert_eq!(cutoff.get_fast(), 1.0);
+        assert_eq!(map.sources().count(), 1);
+
+        map.unbind("cutoff");
+        map.handle(&cc(71, 0.0));
+        assert_eq!(cutoff.get_fast(), 1.0);
+    }
+}
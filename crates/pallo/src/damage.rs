@@ -0,0 +1,184 @@
+use crate::{App, Cx, Rect, point, tree::NodeId};
+
+/// Past this many separate rects the whole frame is presented, since compositors gain little from long lists.
+const MAX_RECTS: usize = 16;
+
+/// The parts of the UI that changed since the last frame, in the root's coordinates.
+#[derive(Default)]
+pub(crate) struct Damage {
+    pub(crate) enabled: bool,
+    full: bool,
+    rects: Vec<Rect>,
+    /// Components whose new bounds get added once they've been drawn, since they may have moved.
+    components: Vec<NodeId>,
+    /// `SignalCx::changes` when the last frame was drawn.
+    signal_changes: u64,
+}
+
+impl Damage {
+    pub(crate) fn add_rect(&mut self, rect: Rect) {
+        if self.full || rect.width() <= 0.0 || rect.height() <= 0.0 {
+            return;
+        }
+        let mut rect = rect;
+        // merge overlapping rects until none overlap the new one
+        while let Some(index) = self.rects.iter().position(|r| r.overlaps(rect)) {
+            rect = rect.union(self.rects.swap_remove(index));
+        }
+        self.rects.push(rect);
+        if self.rects.len() > MAX_RECTS {
+            self.add_all();
+        }
+    }
+
+    pub(crate) fn add_all(&mut self) {
+        self.full = true;
+        self.rects.clear();
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        !self.full && self.rects.is_empty() && self.components.is_empty()
+    }
+
+    /// Takes the damage collected since the last frame, in pixels given the scale from the root's coordinates.
+    /// Returns an empty list when everything should be presented.
+    fn take(&mut self, scale: f32) -> Vec<Rect> {
+        let full = std::mem::take(&mut self.full);
+        let rects = std::mem::take(&mut self.rects);
+        if full {
+            return vec![];
+        }
+        let pixels = |r: Rect| {
+            let a = r.a * scale;
+            let b = r.b * scale;
+            Rect::from_ab(point(a.x.floor(), a.y.floor()), point(b.x.ceil(), b.y.ceil()))
+        };
+        rects.into_iter().map(pixels).collect()
+    }
+}
+
+impl<A: App> Cx<A> {
+    /// Tracks which parts of the UI change so that only those get presented, and skips frames where nothing did.
+    /// Input, running animations, tweens and springs, signals being set, resizing, overlays, the inspector and the
+    /// frame stats HUD invalidate everything by themselves, and the built-in components invalidate themselves when
+    /// they change on `Event::Update`. Anything else that makes a component draw differently needs a call to
+    /// `invalidate` (or `invalidate_rect`), e.g. data that custom components poll, or setters of built-in ones that
+    /// don't take a `Cx` like `Waveform::set_playhead`, since anything that isn't invalidated stays on screen as it
+    /// was. Off by default. Platforms that can't present partially, e.g. Vulkan on Windows, still present whole
+    /// frames, but skip the ones without damage.
+    pub fn set_damage_tracking(&mut self, enabled: bool) {
+        self.damage.enabled = enabled;
+        self.damage.add_all();
+    }
+
+    pub fn is_damage_tracking(&self) -> bool {
+        self.damage.enabled
+    }
+
+    /// Marks the area that `id` was last drawn in, and the one it gets drawn in next, as changed.
+    pub fn invalidate(&mut self, id: impl Into<NodeId>) {
+        if !self.damage.enabled {
+            return;
+        }
+        let id = id.into();
+        let state = self.tree.get(id);
+        if state.subtree_flags.draws_outside_bounds {
+            self.damage.add_all();
+            return;
+        }
+        self.damage.add_rect(state.transform.map_rect(state.bounds));
+        if !self.damage.components.contains(&id) {
+            self.damage.components.push(id);
+        }
+    }
+
+    /// Marks `rect`, in the root's coordinates, as changed.
+    pub fn invalidate_rect(&mut self, rect: Rect) {
+        if self.damage.enabled {
+            self.damage.add_rect(rect);
+        }
+    }
+
+    /// Marks the whole UI as changed.
+    pub fn invalidate_all(&mut self) {
+        self.damage.add_all();
+    }
+
+    /// Invalidates everything if anything is `animating`, unmounting, or if a signal was set since the last frame.
+    pub(crate) fn invalidate_changes(&mut self, animating: bool) {
+        if animating || !self.unmounting.is_empty() || self.changes() != self.damage.signal_changes {
+            self.damage.add_all();
+        }
+    }
+
+    /// Whether there's anything to draw this frame. Always true without damage tracking.
+    pub(crate) fn has_damage(&self) -> bool {
+        !self.damage.enabled || !self.damage.is_empty()
+    }
+
+    /// Adds where the invalidated components got drawn and takes the rects to present, in pixels. Empty when the
+    /// whole frame should be presented.
+    pub(crate) fn take_dirty_rects(&mut self) -> Vec<Rect> {
+        if !self.damage.enabled {
+            return vec![];
+        }
+        // signals set while drawing, e.g. by memos, don't count
+        self.damage.signal_changes = self.changes();
+        for id in std::mem::take(&mut self.damage.components) {
+            if self.tree.contains(id) {
+                let state = self.tree.get(id);
+                self.damage.add_rect(state.transform.map_rect(state.bounds));
+            }
+        }
+        self.damage.take(self.ui_scale * self.scale_factor.get_fast())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(left: f32, top: f32, right: f32, bottom: f32) -> Rect {
+        Rect::from_ab(point(left, top), point(right, bottom))
+    }
+
+    #[test]
+    fn merges_overlapping_rects() {
+        let mut damage = Damage::default();
+        damage.add_rect(rect(0.0, 0.0, 10.0, 10.0));
+        damage.add_rect(rect(50.0, 50.0, 60.0, 60.0));
+        damage.add_rect(rect(5.0, 5.0, 55.0, 55.0));
+        assert_eq!(damage.take(1.0), vec![rect(0.0, 0.0, 60.0, 60.0)]);
+        assert!(damage.is_empty());
+    }
+
+    #[test]
+    fn scales_to_whole_pixels() {
+        let mut damage = Damage::default();
+        damage.add_rect(rect(0.4, 0.6, 10.2, 10.0));
+        assert_eq!(damage.take(2.0), vec![rect(0.0, 1.0, 21.0, 20.0)]);
+    }
+
+    #[test]
+    fn presents_everything_when_full() {
+        let mut damage = Damage::default();
+        damage.add_rect(rect(0.0, 0.0, 10.0, 10.0));
+        damage.add_all();
+        damage.add_rect(rect(0.0, 0.0, 10.0, 10.0));
+        assert!(!damage.is_empty());
+        assert!(damage.take(1.0).is_empty());
+        assert!(damage.is_empty());
+
+        for i in 0..=MAX_RECTS {
+            damage.add_rect(Rect::from_xywh(i as f32 * 20.0, 0.0, 10.0, 10.0));
+        }
+        assert!(damage.take(1.0).is_empty());
+    }
+
+    #[test]
+    fn ignores_empty_rects() {
+        let mut damage = Damage::default();
+        damage.add_rect(rect(5.0, 5.0, 5.0, 10.0));
+        assert!(damage.is_empty());
+    }
+}
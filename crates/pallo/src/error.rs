@@ -0,0 +1,40 @@
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+
+/// A recoverable failure in the platform layer. Errors that can't be returned from the call that caused them, e.g.
+/// from a dialog that finishes later, are delivered as `Event::PlatformError`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Error {
+    /// The GPU device, context or swap chain couldn't be created or updated.
+    Graphics(String),
+    Clipboard(String),
+    /// A file dialog couldn't be shown or its result couldn't be written.
+    Dialog(String),
+    /// The window or view is unusable, e.g. it was accessed from the wrong thread.
+    Window(String),
+    Io(String),
+    /// A request couldn't be sent or its response couldn't be read or decoded.
+    Network(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Graphics(message) => write!(f, "graphics error: {message}"),
+            Error::Clipboard(message) => write!(f, "clipboard error: {message}"),
+            Error::Dialog(message) => write!(f, "dialog error: {message}"),
+            Error::Window(message) => write!(f, "window error: {message}"),
+            Error::Io(message) => write!(f, "io error: {message}"),
+            Error::Network(message) => write!(f, "network error: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error.to_string())
+    }
+}
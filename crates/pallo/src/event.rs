@@ -1,28 +1,56 @@
-use crate::{Modifiers, Point, component::WeakComponentId, point, tree::NodeId, ui::App};
-use keyboard_types::Key;
+use crate::{
+    ControllerInput, Error, Modifiers, Point, PointerId, component::WeakComponentId, point, tree::NodeId, ui::App,
+};
+use keyboard_types::{Code, Key, Location};
 use pallo_util::File;
-use std::{any::Any, marker::PhantomData};
+use serde::{Deserialize, Serialize};
+use std::{any::Any, marker::PhantomData, ops::BitOr, path::PathBuf};
 use web_time::Instant;
 
-pub struct AnyEvent(pub(crate) Box<dyn Any>);
+/// The data of an `Event::Any`, sent with `Cx::send_any_event`. Like other events it's broadcast to every component,
+/// unless one of them calls `consume` to keep the rest from seeing it.
+pub struct AnyEvent {
+    data: Box<dyn Any>,
+    consumed: bool,
+}
 
 impl AnyEvent {
+    pub(crate) fn new<T: 'static>(data: T) -> Self {
+        Self { data: Box::new(data), consumed: false }
+    }
+
     pub fn map<M, F>(&mut self, f: F)
     where
         M: Any + Send,
         F: FnOnce(&M),
     {
-        if let Some(data) = self.0.as_ref().downcast_ref() {
+        if let Some(data) = self.get() {
             (f)(data);
         }
     }
 
+    /// The data if it's a `T` and hasn't been consumed.
     pub fn get<T: 'static>(&self) -> Option<&T> {
-        if let Some(data) = self.0.as_ref().downcast_ref() {
-            Some(data)
-        } else {
-            None
+        if self.consumed {
+            return None;
+        }
+        self.data.downcast_ref()
+    }
+
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        if self.consumed {
+            return None;
         }
+        self.data.downcast_mut()
+    }
+
+    /// Marks the event as handled, so that `get` returns `None` for the components that receive it after this one.
+    pub fn consume(&mut self) {
+        self.consumed = true;
+    }
+
+    pub fn is_consumed(&self) -> bool {
+        self.consumed
     }
 }
 
@@ -31,7 +59,7 @@ pub enum EventStatus {
     Ignored,
 }
 
-#[derive(PartialEq, Clone, Copy, Default)]
+#[derive(PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
 pub enum MouseButton {
     Left,
     Middle,
@@ -40,7 +68,69 @@ pub enum MouseButton {
     Unknown,
 }
 
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum ScrollDelta {
+    /// Precise deltas, e.g. from a trackpad.
+    Pixels(Point),
+    /// Deltas in lines, e.g. from a notched mouse wheel.
+    Lines(Point),
+}
+
+impl ScrollDelta {
+    pub const PIXELS_PER_LINE: f32 = 40.0;
+
+    pub fn to_pixels(&self) -> Point {
+        match *self {
+            ScrollDelta::Pixels(delta) => delta,
+            ScrollDelta::Lines(delta) => delta * Self::PIXELS_PER_LINE,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub enum ScrollPhase {
+    /// The user started a new scroll gesture.
+    Started,
+    /// The gesture is in progress.
+    #[default]
+    Changed,
+    /// The platform has finished delivering momentum deltas for the gesture.
+    MomentumEnded,
+}
+
+/// Works out the phases of wheel events from the gaps between them, since neither baseview nor browsers report them.
+/// Backends send `ScrollPhase::Changed` and the first event after a pause becomes `Started`.
+#[derive(Default)]
+pub(crate) struct ScrollGesture {
+    last_wheel: Option<Instant>,
+}
+
+impl ScrollGesture {
+    // longer than the gaps between momentum events, which come every frame or so
+    const GAP_MS: u128 = 150;
+
+    /// The phase of a wheel event that the platform reported as `phase`, arriving at `now`.
+    pub(crate) fn on_wheel(&mut self, phase: ScrollPhase, now: Instant) -> ScrollPhase {
+        let is_new = self.last_wheel.is_none_or(|last| now.duration_since(last).as_millis() > Self::GAP_MS);
+        self.last_wheel = (phase != ScrollPhase::MomentumEnded).then_some(now);
+        match phase {
+            ScrollPhase::Changed if is_new => ScrollPhase::Started,
+            phase => phase,
+        }
+    }
+
+    /// Whether the gesture has gone quiet by `now`, meaning any momentum has ended. It's only true once per gesture.
+    pub(crate) fn has_ended(&mut self, now: Instant) -> bool {
+        let ended = self.last_wheel.is_some_and(|last| now.duration_since(last).as_millis() > Self::GAP_MS);
+        if ended {
+            self.last_wheel = None;
+        }
+        ended
+    }
+}
+
 pub struct PointerState<A: App> {
+    pub id: PointerId,
     pub position: Point,
     pub down_position: Point,
     pub down_time: Option<Instant>,
@@ -51,12 +141,18 @@ pub struct PointerState<A: App> {
     pub hovered_component: Option<NodeId>,
     pub pressed_component: Option<NodeId>,
     pub is_long_press: bool,
+    /// Normalized pen pressure in `0.0..=1.0`. Always 1.0 for pointers that don't report pressure.
+    pub pressure: f32,
+    /// Pen tilt in degrees along the x and y axes, zero when perpendicular to the surface.
+    pub tilt: Point,
+    pub barrel_button: bool,
     pub _p: PhantomData<A>,
 }
 
 impl<A: App> Clone for PointerState<A> {
     fn clone(&self) -> Self {
         Self {
+            id: self.id,
             position: self.position,
             down_position: self.down_position,
             down_time: self.down_time,
@@ -67,6 +163,9 @@ impl<A: App> Clone for PointerState<A> {
             hovered_component: self.hovered_component,
             pressed_component: self.pressed_component,
             is_long_press: self.is_long_press,
+            pressure: self.pressure,
+            tilt: self.tilt,
+            barrel_button: self.barrel_button,
             _p: self._p,
         }
     }
@@ -103,6 +202,7 @@ impl<A: App> PointerState<A> {
 impl<A: App> Default for PointerState<A> {
     fn default() -> Self {
         Self {
+            id: PointerId::Mouse,
             position: Default::default(),
             down_position: Default::default(),
             down_time: None,
@@ -113,6 +213,9 @@ impl<A: App> Default for PointerState<A> {
             hovered_component: Default::default(),
             pressed_component: Default::default(),
             is_long_press: false,
+            pressure: 1.0,
+            tilt: Default::default(),
+            barrel_button: false,
             _p: PhantomData,
         }
     }
@@ -124,16 +227,45 @@ pub enum Event<A: App> {
     PointerUp(PointerState<A>),
     PointerMove(PointerState<A>),
     LongPress(PointerState<A>),
+    /// Sent after `PointerUp` when a pointer is pressed and released on the same component without dragging. `count`
+    /// is 2 for the second click of a double click, 3 for a triple click and so on.
+    Click {
+        pointer: PointerState<A>,
+        count: usize,
+    },
+    /// Sent after the `Click` with a count of 2.
+    DoubleClick(PointerState<A>),
     App(A::Input),
     ModifiersChanged(Modifiers),
-    MouseWheel(Point),
+    MouseWheel {
+        delta: ScrollDelta,
+        phase: ScrollPhase,
+    },
     FocusChanged(Option<WeakComponentId>),
     FileDropped(Vec<File>),
     FileHovered(Vec<String>),
     FileDropCancelled,
-    Keydown { key: Key, captured: bool },
-    Keyup(Key),
+    /// A file inside a path watched with `PlatformCommon::watch_path` was created, modified or removed.
+    FileChanged(PathBuf),
+    /// See `WindowEvent::Keydown` for what the fields mean. Set `captured` to keep the key from reaching the host.
+    Keydown {
+        key: Key,
+        code: Code,
+        repeat: bool,
+        location: Location,
+        captured: bool,
+    },
+    Keyup {
+        key: Key,
+        code: Code,
+        location: Location,
+    },
     WindowFocusChanged(bool),
+    /// The component was unmounted with `Cx::unmount` and is about to play its exit animation.
+    WillUnmount(WeakComponentId),
+    /// Input from a MIDI device or gamepad, after it has been applied to the bindings in `Cx::controllers`.
+    Controller(ControllerInput),
+    PlatformError(Error),
     Any(AnyEvent),
 }
 
@@ -141,4 +273,143 @@ impl<A: App> Event<A> {
     pub fn update(&self) -> bool {
         matches!(self, Self::Update)
     }
+
+    /// The data of an `Event::Any` if it's a `T` that no other component has consumed.
+    pub fn any<T: 'static>(&self) -> Option<&T> {
+        match self {
+            Event::Any(event) => event.get(),
+            _ => None,
+        }
+    }
+
+    pub fn any_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        match self {
+            Event::Any(event) => event.get_mut(),
+            _ => None,
+        }
+    }
+
+    /// Consumes an `Event::Any` so that the components after this one don't handle it too. Does nothing for other
+    /// events.
+    pub fn consume(&mut self) {
+        if let Event::Any(event) = self {
+            event.consume();
+        }
+    }
+
+    pub fn kind(&self) -> EventKinds {
+        match self {
+            Event::Update => EventKinds::UPDATE,
+            Event::PointerDown(_)
+            | Event::PointerUp(_)
+            | Event::PointerMove(_)
+            | Event::LongPress(_)
+            | Event::Click { .. }
+            | Event::DoubleClick(_)
+            | Event::MouseWheel { .. } => EventKinds::POINTER,
+            Event::Keydown { .. } | Event::Keyup { .. } | Event::ModifiersChanged(_) => EventKinds::KEYBOARD,
+            Event::FocusChanged(_) | Event::WindowFocusChanged(_) => EventKinds::FOCUS,
+            Event::App(_) => EventKinds::APP,
+            Event::FileDropped(_)
+            | Event::FileHovered(_)
+            | Event::FileDropCancelled
+            | Event::FileChanged(_)
+            | Event::Controller(_)
+            | Event::PlatformError(_)
+            | Event::WillUnmount(_)
+            | Event::Any(_) => EventKinds::OTHER,
+        }
+    }
+}
+
+/// The kinds of events a component subscribes to with `Component::subscribe`. Combine them with `|`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct EventKinds(u8);
+
+impl EventKinds {
+    pub const NONE: Self = Self(0);
+    /// Pointer presses, moves and the mouse wheel.
+    pub const POINTER: Self = Self(1);
+    /// Keys and modifiers.
+    pub const KEYBOARD: Self = Self(1 << 1);
+    /// Component and window focus changes.
+    pub const FOCUS: Self = Self(1 << 2);
+    pub const UPDATE: Self = Self(1 << 3);
+    pub const APP: Self = Self(1 << 4);
+    /// Files, controllers, platform errors, unmounting and `Event::Any`.
+    pub const OTHER: Self = Self(1 << 5);
+    pub const ALL: Self = Self(0b11_1111);
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for EventKinds {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AnyEvent, Event, EventKinds, ScrollGesture, ScrollPhase};
+    use crate::ui::tests::TestApp;
+    use std::time::Duration;
+    use web_time::Instant;
+
+    #[test]
+    fn any_events_downcast_to_their_type() {
+        let mut event = AnyEvent::new(String::from("hello"));
+        assert_eq!(event.get::<String>().map(String::as_str), Some("hello"));
+        assert!(event.get::<u32>().is_none());
+        event.get_mut::<String>().unwrap().push('!');
+        assert_eq!(event.get::<String>().map(String::as_str), Some("hello!"));
+    }
+
+    #[test]
+    fn consumed_any_events_are_hidden() {
+        let mut event = AnyEvent::new(7u32);
+        event.consume();
+        assert!(event.is_consumed());
+        assert!(event.get::<u32>().is_none());
+        assert!(event.get_mut::<u32>().is_none());
+        let mut mapped = false;
+        event.map(|_: &u32| mapped = true);
+        assert!(!mapped);
+    }
+
+    #[test]
+    fn event_kinds_combine() {
+        let kinds = EventKinds::POINTER | EventKinds::KEYBOARD;
+        assert!(kinds.contains(EventKinds::POINTER));
+        assert!(!kinds.contains(EventKinds::POINTER | EventKinds::FOCUS));
+        assert!(kinds.intersects(EventKinds::POINTER | EventKinds::FOCUS));
+        assert!(!kinds.intersects(EventKinds::NONE));
+        assert!(EventKinds::ALL.contains(kinds | EventKinds::UPDATE | EventKinds::APP | EventKinds::OTHER));
+
+        assert_eq!(Event::<TestApp>::Update.kind(), EventKinds::UPDATE);
+        assert_eq!(Event::<TestApp>::App(()).kind(), EventKinds::APP);
+        assert_eq!(Event::<TestApp>::WindowFocusChanged(false).kind(), EventKinds::FOCUS);
+        assert_eq!(Event::<TestApp>::FileDropCancelled.kind(), EventKinds::OTHER);
+    }
+
+    #[test]
+    fn wheel_events_after_a_pause_start_a_new_gesture() {
+        let mut gesture = ScrollGesture::default();
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        assert_eq!(gesture.on_wheel(ScrollPhase::Changed, at(0)), ScrollPhase::Started);
+        assert_eq!(gesture.on_wheel(ScrollPhase::Changed, at(16)), ScrollPhase::Changed);
+        assert!(!gesture.has_ended(at(100)));
+        assert!(gesture.has_ended(at(200)));
+        assert!(!gesture.has_ended(at(300)));
+        assert_eq!(gesture.on_wheel(ScrollPhase::Changed, at(400)), ScrollPhase::Started);
+    }
 }
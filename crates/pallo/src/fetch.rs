@@ -0,0 +1,383 @@
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{App, Cx, Error, Later, Signal, file_loader::LoadState};
+
+/// How many bytes of response bodies the fetch cache keeps by default before dropping the least recently used ones.
+const DEFAULT_FETCH_CACHE_BYTES: usize = 32 << 20;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Method {
+    #[default]
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+impl Method {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Patch => "PATCH",
+            Method::Delete => "DELETE",
+        }
+    }
+}
+
+/// What `Cx::fetch` sends. The default is a GET without headers that isn't cached.
+#[derive(Clone, Debug, Default)]
+pub struct FetchOptions {
+    pub method: Method,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+    /// Answers a GET with the response of an earlier successful GET of the same URL with the same headers if there is
+    /// one, and keeps the response for later otherwise. The least recently used responses are dropped once the cache
+    /// grows past `Cx::set_fetch_cache_size`, and all of them with `Cx::clear_fetch_cache`.
+    pub cache: bool,
+}
+
+impl FetchOptions {
+    pub fn with_method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn with_body(mut self, body: Vec<u8>) -> Self {
+        self.body = Some(body);
+        self
+    }
+
+    /// Sends `value` as JSON, with the matching content type.
+    pub fn with_json<T: Serialize>(self, value: &T) -> Result<Self, Error> {
+        let body = serde_json::to_vec(value).map_err(|error| Error::Network(error.to_string()))?;
+        Ok(self.with_header("Content-Type", "application/json").with_body(body))
+    }
+
+    pub fn cached(mut self) -> Self {
+        self.cache = true;
+        self
+    }
+}
+
+/// A response from `Cx::fetch`, whatever its status.
+#[derive(Clone, Debug)]
+pub struct FetchResponse {
+    pub status: u16,
+    pub body: Arc<Vec<u8>>,
+}
+
+impl FetchResponse {
+    /// Whether the status is in the 2xx range.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn text(&self) -> Result<&str, Error> {
+        std::str::from_utf8(&self.body).map_err(|error| Error::Network(error.to_string()))
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        serde_json::from_slice(&self.body).map_err(|error| Error::Network(error.to_string()))
+    }
+}
+
+/// A request being sent in the background. `progress` goes from 0 to 1 as the body arrives, if the server tells its
+/// length, and reaches 1 once `result` is available. Requests that couldn't be sent at all result in an
+/// `Error::Network`.
+#[derive(Clone)]
+pub struct Fetch {
+    pub progress: Signal<f32>,
+    pub result: Later<Result<FetchResponse, Error>>,
+}
+
+/// What a cached response was fetched with. Headers count since e.g. `Authorization` or `Accept` can change the
+/// response, with their names lowercased and sorted so that the order they're given in doesn't.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    url: String,
+    headers: Vec<(String, String)>,
+}
+
+impl CacheKey {
+    fn new(url: &str, headers: &[(String, String)]) -> Self {
+        let mut headers: Vec<_> =
+            headers.iter().map(|(name, value)| (name.to_ascii_lowercase(), value.clone())).collect();
+        headers.sort();
+        Self { url: url.into(), headers }
+    }
+}
+
+/// The responses kept by requests with `FetchOptions::cache`, with when each was last used.
+pub(crate) struct FetchCache {
+    responses: FxHashMap<CacheKey, (FetchResponse, u64)>,
+    bytes: usize,
+    max_bytes: usize,
+    clock: u64,
+}
+
+impl Default for FetchCache {
+    fn default() -> Self {
+        Self { responses: Default::default(), bytes: 0, max_bytes: DEFAULT_FETCH_CACHE_BYTES, clock: 0 }
+    }
+}
+
+impl FetchCache {
+    fn get(&mut self, key: &CacheKey) -> Option<FetchResponse> {
+        self.clock += 1;
+        let (response, last_used) = self.responses.get_mut(key)?;
+        *last_used = self.clock;
+        Some(response.clone())
+    }
+
+    fn insert(&mut self, key: CacheKey, response: FetchResponse) {
+        if response.body.len() > self.max_bytes {
+            return;
+        }
+        self.clock += 1;
+        self.bytes += response.body.len();
+        if let Some((replaced, _)) = self.responses.insert(key, (response, self.clock)) {
+            self.bytes -= replaced.body.len();
+        }
+        self.trim(self.max_bytes);
+    }
+
+    /// How many responses are kept and how many bytes their bodies take.
+    pub(crate) fn usage(&self) -> (usize, usize) {
+        (self.responses.len(), self.bytes)
+    }
+
+    /// Drops the least recently used responses until the rest take at most `max_bytes`.
+    pub(crate) fn trim(&mut self, max_bytes: usize) {
+        while self.bytes > max_bytes {
+            let Some(oldest) =
+                self.responses.iter().min_by_key(|(_, (_, last_used))| *last_used).map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            if let Some((response, _)) = self.responses.remove(&oldest) {
+                self.bytes -= response.body.len();
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Fetcher {
+    requests: Vec<(Arc<LoadState>, Signal<f32>)>,
+    pub(crate) cache: Arc<Mutex<FetchCache>>,
+}
+
+impl Fetcher {
+    pub(crate) fn sync(&mut self) {
+        self.requests.retain(|(state, progress)| {
+            progress.set_if_changed(state.get_progress());
+            state.get_progress() < 1.0
+        });
+    }
+}
+
+impl<A: App> Cx<A> {
+    /// Sends a request to `url` in the background, with `reqwest` on native platforms and the browser's `fetch` on
+    /// wasm. Native builds need the `fetch` feature.
+    pub fn fetch(&mut self, url: impl Into<String>, options: FetchOptions) -> Fetch {
+        let url = url.into();
+        let fetch = Fetch { progress: self.signal(0.0), result: Default::default() };
+        let cache_key = (options.cache && options.method == Method::Get).then(|| CacheKey::new(&url, &options.headers));
+        if let Some(response) = cache_key.as_ref().and_then(|key| self.fetcher.cache.lock().get(key)) {
+            fetch.result.set(Ok(response));
+            fetch.progress.set(1.0);
+            return fetch;
+        }
+
+        let state = Arc::new(LoadState::default());
+        let cache = self.fetcher.cache.clone();
+        let result = fetch.result.clone();
+        let done_state = state.clone();
+        send(url, options, state.clone(), move |response| {
+            if let (Some(key), Ok(response)) = (cache_key, &response)
+                && response.is_success()
+            {
+                cache.lock().insert(key, response.clone());
+            }
+            result.set(response);
+            // the progress reaches 1 either way so that the request stops being synced
+            done_state.set_progress(1.0);
+        });
+        self.fetcher.requests.push((state, fetch.progress.clone()));
+        fetch
+    }
+
+    /// Forgets the responses kept by requests with `FetchOptions::cache`.
+    pub fn clear_fetch_cache(&mut self) {
+        self.fetcher.cache.lock().trim(0);
+    }
+
+    /// How many bytes of response bodies the fetch cache keeps, 32 MiB by default. Responses larger than that aren't
+    /// cached at all.
+    pub fn set_fetch_cache_size(&mut self, max_bytes: usize) {
+        let mut cache = self.fetcher.cache.lock();
+        cache.max_bytes = max_bytes;
+        cache.trim(max_bytes);
+    }
+}
+
+#[cfg(all(not(target_family = "wasm"), feature = "fetch"))]
+fn send(
+    url: String,
+    options: FetchOptions,
+    state: Arc<LoadState>,
+    done: impl FnOnce(Result<FetchResponse, Error>) + Send + 'static,
+) {
+    use std::io::Read;
+
+    const CHUNK_SIZE: usize = 1 << 16;
+
+    std::thread::spawn(move || {
+        let request = || -> Result<FetchResponse, Error> {
+            let network_error = |error: reqwest::Error| Error::Network(error.to_string());
+            let method = match options.method {
+                Method::Get => reqwest::Method::GET,
+                Method::Post => reqwest::Method::POST,
+                Method::Put => reqwest::Method::PUT,
+                Method::Patch => reqwest::Method::PATCH,
+                Method::Delete => reqwest::Method::DELETE,
+            };
+            let mut request = reqwest::blocking::Client::new().request(method, &url);
+            for (name, value) in &options.headers {
+                request = request.header(name, value);
+            }
+            if let Some(body) = options.body {
+                request = request.body(body);
+            }
+            let mut response = request.send().map_err(network_error)?;
+            let status = response.status().as_u16();
+            let len = response.content_length().unwrap_or(0) as usize;
+            // the length is only a hint, so a bogus one can't make this allocate more than a few chunks upfront
+            let mut body = Vec::with_capacity(len.min(CHUNK_SIZE * 16));
+            let mut chunk = vec![0; CHUNK_SIZE];
+            loop {
+                let read = response.read(&mut chunk).map_err(|error| Error::Network(error.to_string()))?;
+                if read == 0 {
+                    return Ok(FetchResponse { status, body: Arc::new(body) });
+                }
+                body.extend_from_slice(&chunk[..read]);
+                if len > 0 {
+                    // 1 is reserved for when the result has been set
+                    state.set_progress((body.len() as f32 / len as f32).min(0.99));
+                }
+            }
+        };
+        (done)(request());
+    });
+}
+
+#[cfg(all(not(target_family = "wasm"), not(feature = "fetch")))]
+fn send(
+    url: String,
+    _options: FetchOptions,
+    _state: Arc<LoadState>,
+    done: impl FnOnce(Result<FetchResponse, Error>) + Send + 'static,
+) {
+    (done)(Err(Error::Network(format!("can't fetch {url}, pallo was built without the `fetch` feature"))));
+}
+
+#[cfg(target_family = "wasm")]
+fn send(
+    url: String,
+    options: FetchOptions,
+    state: Arc<LoadState>,
+    done: impl FnOnce(Result<FetchResponse, Error>) + Send + 'static,
+) {
+    crate::platform::fetch_request(url, options, state, done);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16, body: &[u8]) -> FetchResponse {
+        FetchResponse { status, body: Arc::new(body.to_vec()) }
+    }
+
+    #[test]
+    fn json_bodies_set_the_content_type() {
+        let options = FetchOptions::default().with_method(Method::Post).with_json(&vec![1, 2, 3]).unwrap();
+        assert_eq!(options.headers, [("Content-Type".to_string(), "application/json".to_string())]);
+        assert_eq!(options.body.as_deref(), Some(&b"[1,2,3]"[..]));
+    }
+
+    #[test]
+    fn responses_parse_their_body() {
+        let ok = response(204, br#"{"a": 1}"#);
+        assert!(ok.is_success());
+        assert_eq!(ok.json::<FxHashMap<String, i32>>().unwrap()["a"], 1);
+        assert_eq!(ok.text().unwrap(), r#"{"a": 1}"#);
+        assert!(!response(301, b"").is_success());
+        assert!(!response(404, b"").is_success());
+        assert!(matches!(response(200, b"not json").json::<i32>(), Err(Error::Network(_))));
+    }
+
+    #[test]
+    fn cached_responses_depend_on_the_headers() {
+        let mut cache = FetchCache::default();
+        let headers = [
+            ("Accept".to_string(), "a".to_string()),
+            ("X-Id".to_string(), "1".to_string()),
+        ];
+        cache.insert(CacheKey::new("https://example.com", &headers), response(200, b"body"));
+
+        let reordered = [
+            ("x-id".to_string(), "1".to_string()),
+            ("accept".to_string(), "a".to_string()),
+        ];
+        let hit = cache.get(&CacheKey::new("https://example.com", &reordered)).unwrap();
+        assert_eq!(&hit.body[..], b"body");
+        assert!(cache.get(&CacheKey::new("https://example.com", &[])).is_none());
+        assert!(cache.get(&CacheKey::new("https://example.com", &headers[..1])).is_none());
+        assert!(cache.get(&CacheKey::new("https://example.org", &headers)).is_none());
+    }
+
+    #[test]
+    fn cache_drops_the_least_recently_used_responses() {
+        let mut cache = FetchCache { max_bytes: 10, ..Default::default() };
+        let key = |url: &str| CacheKey::new(url, &[]);
+        cache.insert(key("a"), response(200, b"aaaa"));
+        cache.insert(key("b"), response(200, b"bbbb"));
+        assert!(cache.get(&key("a")).is_some());
+        cache.insert(key("c"), response(200, b"cccc"));
+        assert!(cache.get(&key("b")).is_none());
+        assert!(cache.get(&key("a")).is_some());
+        assert_eq!(cache.usage(), (2, 8));
+
+        cache.insert(key("d"), response(200, &[0; 11]));
+        assert!(cache.get(&key("d")).is_none());
+        cache.insert(key("a"), response(200, b"a"));
+        assert_eq!(cache.usage(), (2, 5));
+        cache.trim(0);
+        assert_eq!(cache.usage(), (0, 0));
+    }
+
+    #[cfg(all(not(target_family = "wasm"), not(feature = "fetch")))]
+    #[test]
+    fn fetching_without_the_feature_fails() {
+        let result = Arc::new(Mutex::new(None));
+        let done = result.clone();
+        send("https://example.com".into(), FetchOptions::default(), Default::default(), move |response| {
+            *done.lock() = Some(response);
+        });
+        let error = result.lock().take().unwrap().unwrap_err();
+        assert!(matches!(error, Error::Network(message) if message.contains("`fetch` feature")));
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+    },
+};
+
+use pallo_util::File;
+
+use crate::{App, Cx, Later, Signal};
+
+/// Progress shared with the thread or fetch doing the reading.
+#[derive(Default)]
+pub(crate) struct LoadState {
+    progress: AtomicU32,
+    failed: AtomicBool,
+}
+
+impl LoadState {
+    pub(crate) fn set_progress(&self, progress: f32) {
+        self.progress.store(progress.to_bits(), Ordering::Relaxed);
+    }
+
+    pub(crate) fn get_progress(&self) -> f32 {
+        f32::from_bits(self.progress.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn fail(&self) {
+        self.failed.store(true, Ordering::Relaxed);
+    }
+}
+
+/// A file being read in the background. `progress` goes from 0 to 1 and reaches 1 once `result` is available.
+#[derive(Clone)]
+pub struct FileLoad {
+    pub progress: Signal<f32>,
+    pub failed: Signal<bool>,
+    pub result: Later<Arc<Vec<u8>>>,
+}
+
+#[derive(Default)]
+pub(crate) struct FileLoader {
+    loads: Vec<(Arc<LoadState>, FileLoad)>,
+}
+
+impl FileLoader {
+    pub(crate) fn sync(&mut self) {
+        self.loads.retain(|(state, load)| {
+            let progress = state.get_progress();
+            load.progress.set_if_changed(progress);
+            let failed = state.failed.load(Ordering::Relaxed);
+            load.failed.set_if_changed(failed);
+            progress < 1.0 && !failed
+        });
+    }
+}
+
+impl<A: App> Cx<A> {
+    /// Reads `file` on a background thread, or fetches it on wasm where paths are treated as URLs.
+    pub fn load_file(&mut self, file: &File) -> FileLoad {
+        let load = FileLoad { progress: self.signal(0.0), failed: self.signal(false), result: Default::default() };
+        match file {
+            File::Data { data, .. } => {
+                load.result.set(data.clone());
+                load.progress.set(1.0);
+            }
+            File::Path(path) => {
+                let state = Arc::new(LoadState::default());
+                read_in_background(path.clone(), state.clone(), load.result.clone());
+                self.file_loader.loads.push((state, load.clone()));
+            }
+            #[cfg(target_family = "wasm")]
+            File::Blob { blob, .. } => {
+                let state = Arc::new(LoadState::default());
+                crate::platform::read_blob(blob.clone(), state.clone(), load.result.clone());
+                self.file_loader.loads.push((state, load.clone()));
+            }
+        }
+        load
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn read_in_background(path: PathBuf, state: Arc<LoadState>, result: Later<Arc<Vec<u8>>>) {
+    use std::io::Read;
+
+    const CHUNK_SIZE: usize = 1 << 20;
+
+    std::thread::spawn(move || {
+        let read = || -> std::io::Result<Vec<u8>> {
+            let mut file = std::fs::File::open(&path)?;
+            let len = file.metadata()?.len() as usize;
+            let mut data = Vec::with_capacity(len);
+            let mut chunk = vec![0; CHUNK_SIZE];
+            loop {
+                let read = file.read(&mut chunk)?;
+                if read == 0 {
+                    return Ok(data);
+                }
+                data.extend_from_slice(&chunk[..read]);
+                if len > 0 {
+                    // 1 is reserved for when the result has been set
+                    state.set_progress((data.len() as f32 / len as f32).min(0.99));
+                }
+            }
+        };
+        match read() {
+            Ok(data) => {
+                result.set(Arc::new(data));
+                state.set_progress(1.0);
+            }
+            Err(_) => state.fail(),
+        }
+    });
+}
+
+#[cfg(target_family = "wasm")]
+fn read_in_background(path: PathBuf, state: Arc<LoadState>, result: Later<Arc<Vec<u8>>>) {
+    crate::platform::fetch_file(path.to_string_lossy().into_owned(), state, result);
+}
@@ -0,0 +1,93 @@
+use std::{cell::RefCell, rc::Rc};
+
+use rustc_hash::FxHashMap;
+
+use crate::{App, Cx, Font, FontVariable, RendererType, TextBlob, TextBlobType};
+
+/// Typeface, font size and variation axes with the values' bits, since `f32` isn't hashable.
+type FontKey = (usize, u32, Vec<(String, u32)>);
+
+/// How many blobs are kept per font before the least recently used ones are dropped.
+const TEXT_BLOB_CACHE_SIZE: usize = 256;
+
+/// What a blob takes per glyph for its id and position, roughly, since the renderers don't tell the size of a blob.
+const TEXT_BLOB_BYTES_PER_GLYPH: usize = 10;
+
+/// Fonts by their style, so that text with the same style shares a font and the blobs shaped with it.
+#[derive(Default)]
+pub(crate) struct FontCache {
+    fonts: FxHashMap<FontKey, CachedFont>,
+}
+
+pub(crate) struct CachedFont {
+    pub font: Font,
+    pub blobs: Rc<RefCell<TextBlobCache>>,
+}
+
+/// The most recently used blobs shaped with a font, by their text.
+#[derive(Default)]
+pub(crate) struct TextBlobCache {
+    blobs: FxHashMap<String, (Option<TextBlob>, u64)>,
+    uses: u64,
+}
+
+impl TextBlobCache {
+    pub(crate) fn get(&mut self, font: &Font, text: &str) -> Option<TextBlob> {
+        self.uses += 1;
+        if let Some((blob, last_use)) = self.blobs.get_mut(text) {
+            *last_use = self.uses;
+            return blob.clone();
+        }
+        if self.blobs.len() >= TEXT_BLOB_CACHE_SIZE
+            && let Some(oldest) =
+                self.blobs.iter().min_by_key(|(_, (_, last_use))| *last_use).map(|(text, _)| text.clone())
+        {
+            self.blobs.remove(&oldest);
+        }
+        let blob = TextBlob::new(text.to_string(), font);
+        self.blobs.insert(text.to_string(), (blob.clone(), self.uses));
+        blob
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.blobs.len()
+    }
+
+    /// An estimate of the memory taken by the blobs and the texts they're kept by.
+    pub(crate) fn estimated_bytes(&self) -> usize {
+        self.blobs.keys().map(|text| text.len() + text.chars().count() * TEXT_BLOB_BYTES_PER_GLYPH).sum()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.blobs.clear();
+    }
+}
+
+impl FontCache {
+    pub(crate) fn num_fonts(&self) -> usize {
+        self.fonts.len()
+    }
+
+    /// Drops the fonts made from `typeface`, e.g. when it gets replaced, along with the blobs shaped with them.
+    pub(crate) fn remove_typeface(&mut self, typeface: usize) {
+        self.fonts.retain(|(font_typeface, _, _), _| *font_typeface != typeface);
+    }
+
+    pub(crate) fn blob_caches(&self) -> impl Iterator<Item = &Rc<RefCell<TextBlobCache>>> {
+        self.fonts.values().map(|font| &font.blobs)
+    }
+}
+
+impl<A: App> Cx<A> {
+    pub(crate) fn cached_font(&mut self, typeface: usize, font_size: f32, variables: Vec<FontVariable>) -> &CachedFont {
+        let key = (
+            typeface,
+            font_size.to_bits(),
+            variables.iter().map(|variable| (variable.get_axis(), variable.get_value().to_bits())).collect(),
+        );
+        self.font_cache.fonts.entry(key).or_insert_with(|| CachedFont {
+            font: self.backend.create_font(typeface, font_size, variables),
+            blobs: Default::default(),
+        })
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::VecDeque;
+
+use crate::{Align, App, Canvas, CanvasType, Cx, Margin, Rect, Text, TextBuilder, rgb};
+
+/// How many frames the statistics are calculated over.
+pub(crate) const FRAME_STATS_SAMPLES: usize = 120;
+const HUD_WIDTH: f32 = 190.0;
+const HUD_LINE_HEIGHT: f32 = 14.0;
+
+/// The timings of a single frame, in microseconds.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct FrameSample {
+    /// The time since the previous frame started.
+    pub interval_micros: u128,
+    /// The time spent in the whole frame.
+    pub frame_micros: u128,
+    /// Handling window and input events, including the relayouts they cause.
+    pub events_micros: u128,
+    pub update_micros: u128,
+    pub draw_micros: u128,
+    /// Handing the frame to the platform, which may wait for the GPU.
+    pub submit_micros: u128,
+}
+
+/// Rolling statistics over the most recent frames, for measuring how an app performs. Read them from
+/// `cx.frame_stats`, or show them on top of the UI with `set_hud_visible`.
+pub struct FrameStats {
+    samples: VecDeque<FrameSample>,
+    dropped_frames: u64,
+    target_fps: f32,
+    hud_visible: bool,
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self {
+            samples: VecDeque::with_capacity(FRAME_STATS_SAMPLES),
+            dropped_frames: 0,
+            target_fps: 60.0,
+            hud_visible: false,
+        }
+    }
+}
+
+impl FrameStats {
+    pub(crate) fn add_sample(&mut self, sample: FrameSample) {
+        let target_interval_micros = 1_000_000.0 / self.target_fps;
+        // an interval of two and a half frames means that two were dropped
+        let missed_frames = (sample.interval_micros as f32 / target_interval_micros).round() - 1.0;
+        // the first frame has nothing to be late from
+        if !self.samples.is_empty() && missed_frames >= 1.0 {
+            self.dropped_frames += missed_frames as u64;
+        }
+        if self.samples.len() == FRAME_STATS_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// The most recent frames, oldest first.
+    pub fn samples(&self) -> impl ExactSizeIterator<Item = &FrameSample> {
+        self.samples.iter()
+    }
+
+    pub fn last(&self) -> Option<FrameSample> {
+        self.samples.back().copied()
+    }
+
+    /// Frames per second over the recent frames.
+    pub fn fps(&self) -> f32 {
+        // the first interval is from a frame that is no longer in the window
+        let micros: u128 = self.samples.iter().skip(1).map(|sample| sample.interval_micros).sum();
+        if micros == 0 {
+            return 0.0;
+        }
+        (self.samples.len() - 1) as f32 * 1_000_000.0 / micros as f32
+    }
+
+    /// The frame time that `percentile` percent of the recent frames were faster than, in milliseconds.
+    pub fn frame_time_percentile_ms(&self, percentile: f32) -> f32 {
+        let mut times: Vec<_> = self.samples.iter().map(|sample| sample.frame_micros).collect();
+        if times.is_empty() {
+            return 0.0;
+        }
+        times.sort_unstable();
+        let index = ((percentile / 100.0) * (times.len() - 1) as f32).round() as usize;
+        times[index.min(times.len() - 1)] as f32 / 1000.0
+    }
+
+    pub fn p95_frame_time_ms(&self) -> f32 {
+        self.frame_time_percentile_ms(95.0)
+    }
+
+    /// The average timings of the recent frames.
+    pub fn average(&self) -> FrameSample {
+        let count = self.samples.len().max(1) as u128;
+        let sum = self.samples.iter().fold(FrameSample::default(), |sum, sample| FrameSample {
+            interval_micros: sum.interval_micros + sample.interval_micros,
+            frame_micros: sum.frame_micros + sample.frame_micros,
+            events_micros: sum.events_micros + sample.events_micros,
+            update_micros: sum.update_micros + sample.update_micros,
+            draw_micros: sum.draw_micros + sample.draw_micros,
+            submit_micros: sum.submit_micros + sample.submit_micros,
+        });
+        FrameSample {
+            interval_micros: sum.interval_micros / count,
+            frame_micros: sum.frame_micros / count,
+            events_micros: sum.events_micros / count,
+            update_micros: sum.update_micros / count,
+            draw_micros: sum.draw_micros / count,
+            submit_micros: sum.submit_micros / count,
+        }
+    }
+
+    /// How many frames were missed since the statistics were last reset, judging by the gaps between frames.
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames
+    }
+
+    /// The frame rate the display runs at, which dropped frames are counted against. Defaults to 60.
+    pub fn set_target_fps(&mut self, fps: f32) {
+        self.target_fps = fps.max(1.0);
+    }
+
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.dropped_frames = 0;
+    }
+
+    pub fn set_hud_visible(&mut self, visible: bool) {
+        self.hud_visible = visible;
+    }
+
+    pub fn is_hud_visible(&self) -> bool {
+        self.hud_visible
+    }
+}
+
+/// Draws `FrameStats` in the top left corner of the UI.
+#[derive(Default)]
+pub(crate) struct FrameStatsHud {
+    lines: Vec<Text>,
+}
+
+impl FrameStatsHud {
+    pub(crate) fn draw<A: App>(&mut self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let stats = &cx.frame_stats;
+        let average = stats.average();
+        let ms = |micros: u128| micros as f32 / 1000.0;
+        let surfaces = cx.surface_pool_stats();
+        let lines = [
+            format!("{:.0} fps  p95 {:.2} ms", stats.fps(), stats.p95_frame_time_ms()),
+            format!("dropped {}", stats.dropped_frames()),
+            format!("events {:.2}  update {:.2}", ms(average.events_micros), ms(average.update_micros)),
+            format!("draw {:.2}  submit {:.2}", ms(average.draw_micros), ms(average.submit_micros)),
+            format!(
+                "pool {:.1} MB  reused {}/{}",
+                surfaces.free_bytes as f32 / (1024.0 * 1024.0),
+                surfaces.reuses,
+                surfaces.reuses + surfaces.allocations
+            ),
+        ];
+
+        while self.lines.len() < lines.len() {
+            let text = TextBuilder::default().font_size(10.0).color(rgb(0xffffff)).build(cx);
+            self.lines.push(text);
+        }
+
+        let safe_bounds = cx.safe_bounds();
+        let height = lines.len() as f32 * HUD_LINE_HEIGHT + 8.0;
+        let hud = safe_bounds.with_width_align(HUD_WIDTH.min(safe_bounds.width()), Align::Start).with_height(height);
+        canvas.fill(rgb(0x000000).with_alpha(0.7)).draw_rect(hud);
+        let hud = hud.with_margin(Margin::even(4.0));
+        for (i, (line, text)) in lines.into_iter().zip(self.lines.iter_mut()).enumerate() {
+            if text.get_text() != &line {
+                text.set_text(line);
+            }
+            let row = Rect::from_xywh(hud.left(), hud.top() + i as f32 * HUD_LINE_HEIGHT, hud.width(), 10.0);
+            text.draw(canvas, row);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FrameSample, FrameStats};
+
+    fn frame(interval_micros: u128, frame_micros: u128) -> FrameSample {
+        FrameSample { interval_micros, frame_micros, ..Default::default() }
+    }
+
+    #[test]
+    fn fps_is_measured_from_frame_intervals() {
+        let mut stats = FrameStats::default();
+        for _ in 0..10 {
+            stats.add_sample(frame(20_000, 1_000));
+        }
+        assert_eq!(stats.fps(), 50.0);
+    }
+
+    #[test]
+    fn p95_ignores_the_fastest_frames() {
+        let mut stats = FrameStats::default();
+        for i in 1..=100 {
+            stats.add_sample(frame(16_667, i * 1000));
+        }
+        assert_eq!(stats.p95_frame_time_ms(), 95.0);
+    }
+
+    #[test]
+    fn long_intervals_count_as_dropped_frames() {
+        let mut stats = FrameStats::default();
+        stats.add_sample(frame(100_000, 0));
+        stats.add_sample(frame(16_667, 0));
+        stats.add_sample(frame(50_000, 0));
+        assert_eq!(stats.dropped_frames(), 2);
+
+        stats.reset();
+        assert_eq!(stats.dropped_frames(), 0);
+        assert_eq!(stats.last(), None);
+    }
+}
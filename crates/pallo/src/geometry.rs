@@ -1,8 +1,16 @@
 use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub};
 
-use crate::{App, Grid};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Default, Copy, Debug, PartialEq)]
+use crate::{
+    App, BorderRadius, Grid,
+    renderers::{
+        PathBuilderType,
+        renderer::{Path, PathBuilder, Renderer},
+    },
+};
+
+#[derive(Clone, Default, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct IntPoint {
     pub x: i32,
     pub y: i32,
@@ -58,7 +66,7 @@ pub const fn int_point(x: i32, y: i32) -> IntPoint {
     IntPoint { x, y }
 }
 
-#[derive(Default, Copy, PartialEq, Clone, Debug)]
+#[derive(Default, Copy, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct Point {
     pub x: f32,
     pub y: f32,
@@ -121,6 +129,29 @@ impl Point {
     pub fn len(&self) -> f32 {
         (self.x * self.x + self.y * self.y).sqrt()
     }
+
+    #[inline(always)]
+    pub fn distance_to(&self, other: Point) -> f32 {
+        (other - *self).len()
+    }
+
+    /// The point scaled to a length of 1, or zero if it's zero.
+    #[inline(always)]
+    pub fn normalize(&self) -> Self {
+        let len = self.len();
+        if len == 0.0 { *self } else { *self / len }
+    }
+
+    #[inline(always)]
+    pub fn dot(&self, other: Point) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The angle from the positive x axis in radians, clockwise since y points down.
+    #[inline(always)]
+    pub fn angle(&self) -> f32 {
+        self.y.atan2(self.x)
+    }
 }
 
 impl Add<Point> for Point {
@@ -202,7 +233,7 @@ pub fn point(x: f32, y: f32) -> Point {
     Point::new(x, y)
 }
 
-#[derive(Default, Copy, Clone)]
+#[derive(Default, Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Margin {
     pub(crate) left: f32,
     pub(crate) top: f32,
@@ -230,6 +261,30 @@ impl Margin {
     pub fn top(top: f32) -> Self {
         Margin { top, ..Default::default() }
     }
+
+    pub fn new(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Margin { left, top, right, bottom }
+    }
+
+    pub fn with_scale(&self, scale: f32) -> Self {
+        Margin::new(self.left * scale, self.top * scale, self.right * scale, self.bottom * scale)
+    }
+
+    pub fn get_left(&self) -> f32 {
+        self.left
+    }
+
+    pub fn get_top(&self) -> f32 {
+        self.top
+    }
+
+    pub fn get_right(&self) -> f32 {
+        self.right
+    }
+
+    pub fn get_bottom(&self) -> f32 {
+        self.bottom
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -361,9 +416,19 @@ impl Rect {
         p.x >= self.a.x && p.x <= self.b.x && p.y >= self.a.y && p.y <= self.b.y
     }
 
+    /// Whether the rects share some area. Unlike `intersects`, rects that only touch at an edge don't overlap.
     #[inline(always)]
     pub fn overlaps(&self, other: Rect) -> bool {
-        self.contains(&other.a) || self.contains(&other.b)
+        self.left() < other.right()
+            && other.left() < self.right()
+            && self.top() < other.bottom()
+            && other.top() < self.bottom()
+    }
+
+    /// The area shared by both rects, if they overlap.
+    #[inline(always)]
+    pub fn intersection(&self, other: Rect) -> Option<Rect> {
+        self.overlaps(other).then(|| Self { a: self.a.max(other.a), b: self.b.min(other.b) })
     }
 
     #[inline(always)]
@@ -371,6 +436,7 @@ impl Rect {
         Self { a: self.a.min(other.a), b: self.b.max(other.b) }
     }
 
+    /// Whether the rects overlap or touch.
     #[inline(always)]
     pub fn intersects(&self, other: Rect) -> bool {
         self.left() <= other.right()
@@ -379,6 +445,23 @@ impl Rect {
             && other.top() <= self.bottom()
     }
 
+    /// The smallest rect that contains both this one and `p`.
+    #[inline(always)]
+    pub fn inflate_to_include(&self, p: Point) -> Rect {
+        Self { a: self.a.min(p), b: self.b.max(p) }
+    }
+
+    /// The closest point to `p` inside the rect.
+    #[inline(always)]
+    pub fn clamp_point(&self, p: Point) -> Point {
+        p.max(self.a).min(self.b)
+    }
+
+    /// The bounding box of the rect after rotating it clockwise around `center`.
+    pub fn rotated_bounds(&self, degrees: f32, center: impl Into<Point>) -> Rect {
+        Matrix::rotation(degrees, center).map_rect(*self)
+    }
+
     #[inline(always)]
     pub fn center(&self) -> Point {
         point((self.a.x + self.b.x) * 0.5, (self.a.y + self.b.y) * 0.5)
@@ -626,3 +709,356 @@ impl Rect {
             .with_y_offset((within.bottom() - out.bottom()).min(0.0))
     }
 }
+
+/// A 2D affine transform, mapping `(x, y)` to `(a * x + c * y + e, b * x + d * y + f)`.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Matrix {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: f32,
+    pub f: f32,
+}
+
+impl Default for Matrix {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Matrix {
+    pub const IDENTITY: Matrix = Matrix { a: 1.0, b: 0.0, c: 0.0, d: 1.0, e: 0.0, f: 0.0 };
+
+    pub fn translation(offset: impl Into<Point>) -> Self {
+        let offset: Point = offset.into();
+        Self { e: offset.x, f: offset.y, ..Self::IDENTITY }
+    }
+
+    pub fn scale(scale: impl Into<Point>) -> Self {
+        let scale: Point = scale.into();
+        Self { a: scale.x, d: scale.y, ..Self::IDENTITY }
+    }
+
+    /// Rotation clockwise around `center`, matching the canvas' `with_rotation`.
+    pub fn rotation(degrees: f32, center: impl Into<Point>) -> Self {
+        let center: Point = center.into();
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let rotation = Self { a: cos, b: sin, c: -sin, d: cos, ..Self::IDENTITY };
+        Self::translation(center) * rotation * Self::translation(-center)
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::IDENTITY
+    }
+
+    pub fn invert(&self) -> Option<Self> {
+        let det = self.a * self.d - self.b * self.c;
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        let (a, b, c, d) = (self.d / det, -self.b / det, -self.c / det, self.a / det);
+        Some(Self { a, b, c, d, e: -(a * self.e + c * self.f), f: -(b * self.e + d * self.f) })
+    }
+
+    #[inline(always)]
+    pub fn map_point(&self, p: Point) -> Point {
+        point(self.a * p.x + self.c * p.y + self.e, self.b * p.x + self.d * p.y + self.f)
+    }
+
+    /// The bounding box of the transformed rect.
+    pub fn map_rect(&self, rect: Rect) -> Rect {
+        let corners = [
+            self.map_point(rect.a),
+            self.map_point(point(rect.b.x, rect.a.y)),
+            self.map_point(rect.b),
+            self.map_point(point(rect.a.x, rect.b.y)),
+        ];
+        let a = corners.iter().fold(corners[0], |acc, p| acc.min(*p));
+        let b = corners.iter().fold(corners[0], |acc, p| acc.max(*p));
+        Rect::from_ab(a, b)
+    }
+}
+
+/// `lhs * rhs` applies `rhs` first, so `parent * local` maps from local coordinates into the parent's.
+impl Mul<Matrix> for Matrix {
+    type Output = Matrix;
+
+    fn mul(self, rhs: Matrix) -> Self::Output {
+        Self {
+            a: self.a * rhs.a + self.c * rhs.b,
+            b: self.b * rhs.a + self.d * rhs.b,
+            c: self.a * rhs.c + self.c * rhs.d,
+            d: self.b * rhs.c + self.d * rhs.d,
+            e: self.a * rhs.e + self.c * rhs.f + self.e,
+            f: self.b * rhs.e + self.d * rhs.f + self.f,
+        }
+    }
+}
+
+/// A rect with rounded corners. Like in `Canvas::draw_round_rect`, `radius.left` and `radius.right` are the horizontal
+/// radii of the corners on each side, and `radius.top` and `radius.bottom` the vertical ones.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct RRect {
+    pub rect: Rect,
+    pub radius: BorderRadius,
+}
+
+impl RRect {
+    pub fn new(rect: Rect, radius: impl Into<BorderRadius>) -> Self {
+        Self { rect, radius: radius.into() }
+    }
+
+    /// The radii scaled down evenly so that opposite corners don't overlap, which is how they're drawn.
+    pub fn clamped_radius(&self) -> BorderRadius {
+        let BorderRadius { left, top, right, bottom } = self.radius;
+        let scale = 1.0f32.min(self.rect.width() / (left + right)).min(self.rect.height() / (top + bottom)).max(0.0);
+        BorderRadius { left: left * scale, top: top * scale, right: right * scale, bottom: bottom * scale }
+    }
+
+    pub fn contains(&self, p: &Point) -> bool {
+        if !self.rect.contains(p) {
+            return false;
+        }
+        let BorderRadius { left, top, right, bottom } = self.clamped_radius();
+        let (rx, cx) = if p.x < self.rect.left() + left {
+            (left, self.rect.left() + left)
+        } else if p.x > self.rect.right() - right {
+            (right, self.rect.right() - right)
+        } else {
+            return true;
+        };
+        let (ry, cy) = if p.y < self.rect.top() + top {
+            (top, self.rect.top() + top)
+        } else if p.y > self.rect.bottom() - bottom {
+            (bottom, self.rect.bottom() - bottom)
+        } else {
+            return true;
+        };
+        let (dx, dy) = ((p.x - cx) / rx, (p.y - cy) / ry);
+        dx * dx + dy * dy <= 1.0
+    }
+
+    pub fn to_path(&self) -> Path {
+        let mut path = PathBuilder::default();
+        self.add_to_path(&mut path);
+        path.build()
+    }
+
+    /// Adds the outline clockwise from the end of the top left corner, as a closed contour.
+    fn add_to_path(&self, path: &mut impl PathBuilderType<Renderer>) {
+        let BorderRadius { left, top, right, bottom } = self.clamped_radius();
+        let Rect { a, b } = self.rect;
+        // quarter ellipses are conics with this weight
+        let weight = std::f32::consts::FRAC_1_SQRT_2;
+        path.move_to(point(a.x + left, a.y))
+            .line_to(point(b.x - right, a.y))
+            .conic_to(point(b.x, a.y), point(b.x, a.y + top), weight)
+            .line_to(point(b.x, b.y - bottom))
+            .conic_to(b, point(b.x - right, b.y), weight)
+            .line_to(point(a.x + left, b.y))
+            .conic_to(point(a.x, b.y), point(a.x, b.y - bottom), weight)
+            .line_to(point(a.x, a.y + top))
+            .conic_to(a, point(a.x + left, a.y), weight);
+        path.close();
+    }
+}
+
+impl From<Rect> for RRect {
+    fn from(rect: Rect) -> Self {
+        Self { rect, radius: BorderRadius::default() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rects_overlap_without_containing_corners() {
+        let horizontal = Rect::from_xywh(0.0, 10.0, 30.0, 10.0);
+        let vertical = Rect::from_xywh(10.0, 0.0, 10.0, 30.0);
+        assert!(horizontal.overlaps(vertical));
+        assert!(vertical.overlaps(horizontal));
+        assert_eq!(horizontal.intersection(vertical), Some(Rect::from_xywh(10.0, 10.0, 10.0, 10.0)));
+    }
+
+    #[test]
+    fn touching_rects_intersect_but_dont_overlap() {
+        let a = Rect::from_xywh(0.0, 0.0, 10.0, 10.0);
+        let b = Rect::from_xywh(10.0, 0.0, 10.0, 10.0);
+        assert!(a.intersects(b));
+        assert!(!a.overlaps(b));
+        assert_eq!(a.intersection(b), None);
+        assert_eq!(a.intersection(Rect::from_xywh(20.0, 20.0, 5.0, 5.0)), None);
+    }
+
+    fn assert_near(a: Point, b: Point) {
+        assert!((a - b).len() < 1e-4, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn matrices_compose_parent_first() {
+        let parent = Matrix::translation((10.0, 20.0));
+        let local = Matrix::scale(2.0);
+        let matrix = parent * local;
+        assert_near(matrix.map_point(point(1.0, 1.0)), point(12.0, 22.0));
+        assert_near((local * parent).map_point(point(1.0, 1.0)), point(22.0, 42.0));
+        assert_eq!(Matrix::IDENTITY * parent, parent);
+        assert_eq!(parent * Matrix::IDENTITY, parent);
+    }
+
+    #[test]
+    fn rotation_is_clockwise_around_center() {
+        let matrix = Matrix::rotation(90.0, (10.0, 10.0));
+        assert_near(matrix.map_point(point(20.0, 10.0)), point(10.0, 20.0));
+        assert_near(matrix.map_point(point(10.0, 10.0)), point(10.0, 10.0));
+    }
+
+    #[test]
+    fn inverted_matrices_map_points_back() {
+        let matrix = Matrix::translation((5.0, -3.0)) * Matrix::rotation(30.0, (2.0, 1.0)) * Matrix::scale((2.0, 0.5));
+        let inverse = matrix.invert().unwrap();
+        for p in [point(0.0, 0.0), point(1.0, 2.0), point(-7.5, 13.0)] {
+            assert_near(inverse.map_point(matrix.map_point(p)), p);
+        }
+        let product = matrix * inverse;
+        assert_near(point(product.a, product.d), point(1.0, 1.0));
+        assert_near(point(product.e, product.f), point(0.0, 0.0));
+        assert_eq!(Matrix::scale((0.0, 1.0)).invert(), None);
+    }
+
+    #[test]
+    fn mapped_rects_are_bounding_boxes() {
+        let rect = Rect::from_xywh(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(Matrix::translation((5.0, 5.0)).map_rect(rect), Rect::from_xywh(5.0, 5.0, 10.0, 10.0));
+        let rotated = Matrix::rotation(45.0, (5.0, 5.0)).map_rect(rect);
+        let half_diagonal = 50.0f32.sqrt();
+        assert_near(rotated.a, point(5.0 - half_diagonal, 5.0 - half_diagonal));
+        assert_near(rotated.b, point(5.0 + half_diagonal, 5.0 + half_diagonal));
+    }
+
+    #[test]
+    fn rrects_contain_points_within_their_corners() {
+        let rrect = RRect::new(Rect::from_xywh(0.0, 0.0, 100.0, 50.0), 10.0);
+        assert!(rrect.contains(&point(50.0, 25.0)));
+        assert!(rrect.contains(&point(50.0, 0.5)));
+        assert!(rrect.contains(&point(0.5, 25.0)));
+        assert!(rrect.contains(&point(10.0, 10.0)));
+        assert!(rrect.contains(&point(3.0, 3.0)));
+        assert!(!rrect.contains(&point(2.0, 2.0)));
+        assert!(!rrect.contains(&point(99.0, 49.0)));
+        assert!(!rrect.contains(&point(101.0, 25.0)));
+
+        // a rect that isn't rounded contains its corners
+        assert!(RRect::from(rrect.rect).contains(&point(0.5, 0.5)));
+    }
+
+    #[test]
+    fn rrects_round_each_corner_by_its_own_radius() {
+        let radius = BorderRadius { left: 20.0, top: 10.0, right: 0.0, bottom: 0.0 };
+        let rrect = RRect::new(Rect::from_xywh(0.0, 0.0, 100.0, 100.0), radius);
+        // the top left corner is elliptical, 20 wide and 10 high
+        assert!(!rrect.contains(&point(5.0, 2.0)));
+        assert!(rrect.contains(&point(10.0, 2.0)));
+        assert!(!rrect.contains(&point(2.0, 5.0)));
+        // the bottom left corner is rounded horizontally only, which leaves it square
+        assert!(rrect.contains(&point(0.5, 99.5)));
+        assert!(rrect.contains(&point(99.5, 0.5)));
+        assert!(rrect.contains(&point(99.5, 99.5)));
+    }
+
+    #[test]
+    fn rrect_radii_are_scaled_to_fit() {
+        let rrect = RRect::new(Rect::from_xywh(0.0, 0.0, 40.0, 10.0), 10.0);
+        assert_eq!(rrect.clamped_radius(), BorderRadius::from(5.0));
+        // a capsule: the middle of the short sides is the only part of them that's inside
+        assert!(rrect.contains(&point(0.1, 5.0)));
+        assert!(!rrect.contains(&point(0.5, 1.0)));
+        assert_eq!(RRect::new(Rect::from_xywh(0.0, 0.0, 0.0, 10.0), 10.0).clamped_radius(), BorderRadius::default());
+    }
+
+    /// Records what's added to a path, as the name of each command and its points.
+    #[derive(Default)]
+    struct PathRecorder(Vec<(&'static str, Vec<Point>)>);
+
+    impl PathBuilderType<Renderer> for PathRecorder {
+        fn move_to(&mut self, point: Point) -> &mut Self {
+            self.0.push(("move", vec![point]));
+            self
+        }
+
+        fn line_to(&mut self, point: Point) -> &mut Self {
+            self.0.push(("line", vec![point]));
+            self
+        }
+
+        fn conic_to(&mut self, p1: Point, p2: Point, _weight: f32) -> &mut Self {
+            self.0.push(("conic", vec![p1, p2]));
+            self
+        }
+
+        fn quad_to(&mut self, _p1: Point, _p2: Point) -> &mut Self {
+            unimplemented!()
+        }
+
+        fn arc_to_rotated(&mut self, _r: Point, _rotate: f32, _large: bool, _sweep: bool, _end: Point) -> &mut Self {
+            unimplemented!()
+        }
+
+        fn add_circle(&mut self, _point: Point, _radius: f32) -> &mut Self {
+            unimplemented!()
+        }
+
+        fn add_rounded_rectangle(&mut self, _rect: Rect, _rounding: Point) -> &mut Self {
+            unimplemented!()
+        }
+
+        fn close(&mut self) {
+            self.0.push(("close", vec![]));
+        }
+
+        fn cubic_to(&mut self, _cp1: Point, _cp2: Point, _point: Point) -> &mut Self {
+            unimplemented!()
+        }
+
+        fn fill_type_even_odd(&mut self) {
+            unimplemented!()
+        }
+
+        fn reset(&mut self) {
+            self.0.clear();
+        }
+
+        fn build(&self) -> Path {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn rrect_paths_go_around_the_corners() {
+        let radius = BorderRadius { left: 4.0, top: 2.0, right: 8.0, bottom: 6.0 };
+        let rrect = RRect::new(Rect::from_xywh(10.0, 20.0, 100.0, 50.0), radius);
+        let mut path = PathRecorder::default();
+        rrect.add_to_path(&mut path);
+        let expected = [
+            ("move", vec![point(14.0, 20.0)]),
+            ("line", vec![point(102.0, 20.0)]),
+            ("conic", vec![point(110.0, 20.0), point(110.0, 22.0)]),
+            ("line", vec![point(110.0, 64.0)]),
+            ("conic", vec![point(110.0, 70.0), point(102.0, 70.0)]),
+            ("line", vec![point(14.0, 70.0)]),
+            ("conic", vec![point(10.0, 70.0), point(10.0, 64.0)]),
+            ("line", vec![point(10.0, 22.0)]),
+            ("conic", vec![point(10.0, 20.0), point(14.0, 20.0)]),
+            ("close", vec![]),
+        ];
+        assert_eq!(path.0, expected);
+
+        // the radii are clamped the same way as for `contains`
+        let mut path = PathRecorder::default();
+        RRect::new(Rect::from_xywh(0.0, 0.0, 10.0, 10.0), 10.0).add_to_path(&mut path);
+        assert_eq!(path.0[0], ("move", vec![point(5.0, 0.0)]));
+        assert_eq!(path.0[1], ("line", vec![point(5.0, 0.0)]));
+    }
+}
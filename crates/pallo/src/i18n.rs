@@ -0,0 +1,663 @@
+use std::{fmt, rc::Rc};
+
+use pallo_util::File;
+use rustc_hash::FxHashMap;
+use web_time::{SystemTime, UNIX_EPOCH};
+
+use crate::{App, Computed, Cx, Signal, SignalCx};
+
+/// A value given to a translated message, e.g. the count that selects between "1 item" and "2 items".
+#[derive(Clone, Debug, PartialEq)]
+pub enum TrArg {
+    Number(f64),
+    String(String),
+}
+
+macro_rules! impl_number_arg {
+    ($($t:ty),*) => {
+        $(impl From<$t> for TrArg {
+            fn from(value: $t) -> Self {
+                TrArg::Number(value as f64)
+            }
+        })*
+    };
+}
+
+impl_number_arg!(f32, f64, i32, i64, u32, u64, usize);
+
+impl From<&str> for TrArg {
+    fn from(value: &str) -> Self {
+        TrArg::String(value.into())
+    }
+}
+
+impl From<String> for TrArg {
+    fn from(value: String) -> Self {
+        TrArg::String(value)
+    }
+}
+
+impl fmt::Display for TrArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrArg::Number(value) if value.fract() == 0.0 && value.abs() < 1e15 => write!(f, "{}", *value as i64),
+            TrArg::Number(value) => write!(f, "{value}"),
+            TrArg::String(value) => f.write_str(value),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CatalogError {
+    /// 1-based, like in text editors.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for CatalogError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum VariantKey {
+    /// A plural category like "one" or "few", or a string to match a string argument against.
+    Name(String),
+    Number(f64),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Text(String),
+    Variable(String),
+    Select { variable: String, variants: Vec<(VariantKey, Vec<Part>)>, default: usize },
+}
+
+/// The messages of one locale, parsed from a subset of the Fluent syntax:
+///
+/// ```text
+/// # comments start with a hash
+/// greeting = Hello, { $name }!
+/// items = { $count ->
+///     [0] No items
+///     [one] One item
+///    *[other] { $count } items
+/// }
+/// ```
+///
+/// Indented lines after a message continue it on a new line. Numeric arguments select the variant with the same
+/// number, or else the one named after their plural category in the locale's language.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Catalog {
+    messages: FxHashMap<String, Vec<Part>>,
+}
+
+impl Catalog {
+    pub fn parse(source: &str) -> Result<Self, CatalogError> {
+        let mut messages = FxHashMap::default();
+        let mut lines = source.lines().enumerate().map(|(index, line)| (index + 1, line)).peekable();
+        while let Some((line_number, line)) = lines.next() {
+            let error = |message: &str| CatalogError { line: line_number, message: message.into() };
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if line.starts_with(char::is_whitespace) {
+                return Err(error("expected a message, found an indented line"));
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(error("expected `key = value`"));
+            };
+            let key = key.trim();
+            if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                return Err(error("message keys may only contain letters, digits, `-` and `_`"));
+            }
+
+            let value = value.trim();
+            let parts = if let Some(selector) = value.strip_prefix('{').and_then(|v| v.strip_suffix("->")) {
+                let variable = parse_variable(selector).ok_or_else(|| error("expected `{ $variable ->`"))?;
+                let mut variants = vec![];
+                let mut default = None;
+                loop {
+                    let Some((line_number, line)) = lines.next() else {
+                        return Err(error("expected `}` to end the variants"));
+                    };
+                    let error = |message: &str| CatalogError { line: line_number, message: message.into() };
+                    let line = line.trim();
+                    if line == "}" {
+                        break;
+                    }
+                    let (is_default, line) = match line.strip_prefix('*') {
+                        Some(line) => (true, line),
+                        None => (false, line),
+                    };
+                    let Some((variant_key, pattern)) = line.strip_prefix('[').and_then(|line| line.split_once(']'))
+                    else {
+                        return Err(error("expected `[key] value`"));
+                    };
+                    let variant_key = variant_key.trim();
+                    let variant_key = match variant_key.parse() {
+                        Ok(number) => VariantKey::Number(number),
+                        Err(_) => VariantKey::Name(variant_key.into()),
+                    };
+                    if is_default {
+                        default = Some(variants.len());
+                    }
+                    variants.push((variant_key, parse_pattern(pattern.trim()).map_err(error)?));
+                }
+                let default =
+                    default.ok_or_else(|| error("one of the variants must be marked as the default with `*`"))?;
+                vec![Part::Select { variable, variants, default }]
+            } else {
+                let mut text = value.to_string();
+                while let Some((_, line)) =
+                    lines.next_if(|(_, line)| line.starts_with(char::is_whitespace) && !line.trim().is_empty())
+                {
+                    text.push('\n');
+                    text.push_str(line.trim());
+                }
+                parse_pattern(&text).map_err(error)?
+            };
+            messages.insert(key.to_string(), parts);
+        }
+        Ok(Self { messages })
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.messages.contains_key(key)
+    }
+
+    /// Formats the message `key` with `args`, selecting plural variants by the rules of `language`. Arguments that
+    /// weren't given are left as `{$name}`.
+    pub fn format(&self, language: &str, key: &str, args: &[(&str, TrArg)]) -> Option<String> {
+        let mut out = String::new();
+        format_parts(self.messages.get(key)?, language, args, &mut out);
+        Some(out)
+    }
+}
+
+/// Parses `$name`, allowing surrounding whitespace.
+fn parse_variable(text: &str) -> Option<String> {
+    let name = text.trim().strip_prefix('$')?;
+    (!name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')).then(|| name.into())
+}
+
+fn parse_pattern(text: &str) -> Result<Vec<Part>, &'static str> {
+    let mut parts = vec![];
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        if start > 0 {
+            parts.push(Part::Text(rest[..start].into()));
+        }
+        let inner = rest[start + 1..].trim_start();
+        // string literals allow writing braces, like `{ "{" }`
+        let (part, after) = if let Some(literal) = inner.strip_prefix('"') {
+            let (literal, after) = literal.split_once('"').ok_or("expected `\"` to end the string")?;
+            (Part::Text(literal.into()), after)
+        } else {
+            let (variable, _) = inner.split_once('}').ok_or("expected `}` to end the placeable")?;
+            (Part::Variable(parse_variable(variable).ok_or("expected `{ $variable }`")?), &inner[variable.len()..])
+        };
+        rest = after.trim_start().strip_prefix('}').ok_or("expected `}` to end the placeable")?;
+        parts.push(part);
+    }
+    if !rest.is_empty() {
+        parts.push(Part::Text(rest.into()));
+    }
+    Ok(parts)
+}
+
+fn format_parts(parts: &[Part], language: &str, args: &[(&str, TrArg)], out: &mut String) {
+    let get_arg = |name: &str| args.iter().find(|(arg, _)| *arg == name).map(|(_, value)| value);
+    for part in parts {
+        match part {
+            Part::Text(text) => out.push_str(text),
+            Part::Variable(name) => match get_arg(name) {
+                Some(value) => out.push_str(&value.to_string()),
+                None => {
+                    out.push_str("{$");
+                    out.push_str(name);
+                    out.push('}');
+                }
+            },
+            Part::Select { variable, variants, default } => {
+                let matches = |key: &VariantKey| match (get_arg(variable), key) {
+                    (Some(TrArg::Number(n)), VariantKey::Number(key)) => n == key,
+                    (Some(TrArg::String(s)), VariantKey::Name(key)) => s == key,
+                    _ => false,
+                };
+                let plural_category = match get_arg(variable) {
+                    Some(TrArg::Number(n)) => Some(plural_category(language, *n)),
+                    _ => None,
+                };
+                let index = variants
+                    .iter()
+                    .position(|(key, _)| matches(key))
+                    .or_else(|| {
+                        let category = plural_category?;
+                        variants.iter().position(|(key, _)| *key == VariantKey::Name(category.into()))
+                    })
+                    .unwrap_or(*default);
+                format_parts(&variants[index].1, language, args, out);
+            }
+        }
+    }
+}
+
+/// The CLDR plural category of `n` in `language`, e.g. "one" or "few". Covers the common languages and falls back to
+/// the English rules.
+pub fn plural_category(language: &str, n: f64) -> &'static str {
+    let is_integer = n.fract() == 0.0;
+    let i = n.abs().trunc() as u64;
+    match language {
+        "ja" | "zh" | "ko" | "th" | "vi" | "id" | "ms" | "tr" => "other",
+        "fr" | "hi" | "fa" => {
+            if i <= 1 {
+                "one"
+            } else {
+                "other"
+            }
+        }
+        "ru" | "uk" | "be" | "pl" => {
+            if !is_integer {
+                "other"
+            } else if i % 10 == 1 && i % 100 != 11 && (language != "pl" || i == 1) {
+                "one"
+            } else if (2..=4).contains(&(i % 10)) && !(12..=14).contains(&(i % 100)) {
+                "few"
+            } else {
+                "many"
+            }
+        }
+        "cs" | "sk" => {
+            if !is_integer {
+                "many"
+            } else if i == 1 {
+                "one"
+            } else if (2..=4).contains(&i) {
+                "few"
+            } else {
+                "other"
+            }
+        }
+        _ => {
+            if is_integer && i == 1 {
+                "one"
+            } else {
+                "other"
+            }
+        }
+    }
+}
+
+/// The catalogs of the app and the active locale, see `Cx::tr`.
+#[derive(Clone)]
+pub struct Localization {
+    locale: Signal<String>,
+    fallback_locale: Signal<String>,
+    catalogs: Signal<FxHashMap<String, Rc<Catalog>>>,
+}
+
+impl Localization {
+    pub fn new(cx: &SignalCx, locale: impl Into<String>) -> Self {
+        let locale = locale.into();
+        Self { fallback_locale: cx.signal(locale.clone()), locale: cx.signal(locale), catalogs: cx.signal_default() }
+    }
+
+    /// A BCP 47 tag like "en-US" or "fi".
+    pub fn locale(&self) -> String {
+        self.locale.get_ref().clone()
+    }
+
+    /// Switches the language, updating every `Computed` returned by `Cx::tr`.
+    pub fn set_locale(&self, locale: impl Into<String>) {
+        self.locale.set(locale.into());
+    }
+
+    /// The locale whose catalog is used for messages missing from the active one's.
+    pub fn set_fallback_locale(&self, locale: impl Into<String>) {
+        self.fallback_locale.set(locale.into());
+    }
+
+    pub fn add_catalog(&self, locale: impl Into<String>, catalog: Catalog) {
+        self.catalogs.mutate(|mut catalogs| {
+            catalogs.insert(locale.into(), Rc::new(catalog));
+        });
+    }
+
+    /// Formats `value` with the decimal and group separators of the active locale, see `format_number`.
+    pub fn format_number(&self, value: f64, options: &FormatOptions) -> String {
+        format_number(&self.locale.get_ref(), value, options)
+    }
+
+    pub fn format_duration(&self, duration_ms: f64) -> String {
+        format_duration(&self.locale.get_ref(), duration_ms)
+    }
+
+    pub fn format_date(&self, time: SystemTime) -> String {
+        format_date(&self.locale.get_ref(), time)
+    }
+
+    /// Formats the message `key` from the catalog of the active locale, or of its language (e.g. "en" for "en-US"),
+    /// or of the fallback locale. Messages that aren't in any of them are formatted as the key itself.
+    pub fn format(&self, key: &str, args: &[(&str, TrArg)]) -> String {
+        let catalogs = self.catalogs.get_ref();
+        let locale = self.locale.get_ref();
+        let fallback_locale = self.fallback_locale.get_ref();
+        [
+            locale.as_str(),
+            language(&locale),
+            fallback_locale.as_str(),
+            language(&fallback_locale),
+        ]
+        .into_iter()
+        .find_map(|locale| catalogs.get(locale)?.format(language(locale), key, args))
+        .unwrap_or_else(|| key.to_string())
+    }
+}
+
+/// The language subtag of a locale, e.g. "pt" for "pt-BR".
+fn language(locale: &str) -> &str {
+    locale.split(['-', '_']).next().unwrap_or(locale)
+}
+
+/// How `format_number` writes a number.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormatOptions {
+    /// Trailing zeros are kept up to this many fraction digits.
+    pub min_fraction_digits: usize,
+    /// The number is rounded to this many fraction digits.
+    pub max_fraction_digits: usize,
+    /// Whether thousands are separated, e.g. "1,234,567" in English.
+    pub grouping: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self { min_fraction_digits: 0, max_fraction_digits: 2, grouping: true }
+    }
+}
+
+impl FormatOptions {
+    /// Always exactly `digits` fraction digits.
+    pub fn fixed(digits: usize) -> Self {
+        Self { min_fraction_digits: digits, max_fraction_digits: digits, ..Default::default() }
+    }
+
+    pub fn with_grouping(mut self, grouping: bool) -> Self {
+        self.grouping = grouping;
+        self
+    }
+}
+
+/// The decimal and group separators of `locale`, and how many digits the integer part needs before it's grouped.
+fn number_symbols(locale: &str) -> (char, &'static str, usize) {
+    match (language(locale), locale) {
+        (_, "de-CH" | "de_CH" | "it-CH" | "it_CH") => ('.', "\u{2019}", 4),
+        ("fr", _) => (',', "\u{202f}", 4),
+        ("es", _) => (',', ".", 5),
+        ("pl", _) => (',', "\u{a0}", 5),
+        ("ru" | "uk" | "be" | "cs" | "sk" | "fi" | "sv" | "nb" | "no" | "hu" | "bg" | "lt" | "lv" | "et", _) => {
+            (',', "\u{a0}", 4)
+        }
+        ("de" | "nl" | "it" | "pt" | "id" | "tr" | "da" | "el" | "ro" | "hr" | "sl" | "sr" | "vi", _) => (',', ".", 4),
+        _ => ('.', ",", 4),
+    }
+}
+
+/// Formats `value` with the decimal and group separators of `locale`, e.g. "1,234.5" in "en" and "1.234,5" in "de".
+pub fn format_number(locale: &str, value: f64, options: &FormatOptions) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    let (decimal, group, min_grouping_digits) = number_symbols(locale);
+    let max_fraction_digits = options.max_fraction_digits.max(options.min_fraction_digits);
+    let rounded = format!("{:.*}", max_fraction_digits, value.abs());
+    let (integer, fraction) = rounded.split_once('.').unwrap_or((&rounded, ""));
+    let mut fraction = fraction;
+    while fraction.len() > options.min_fraction_digits && fraction.ends_with('0') {
+        fraction = &fraction[..fraction.len() - 1];
+    }
+
+    let mut out = String::new();
+    // negative numbers that round to zero are written as zero
+    if value < 0.0 && rounded.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        out.push('-');
+    }
+    if options.grouping && integer.len() >= min_grouping_digits {
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index) % 3 == 0 {
+                out.push_str(group);
+            }
+            out.push(digit);
+        }
+    } else {
+        out.push_str(integer);
+    }
+    if !fraction.is_empty() {
+        out.push(decimal);
+        out.push_str(fraction);
+    }
+    out
+}
+
+/// Formats a duration with the unit that suits its length, e.g. "250 ms", "1.5 s", "2.5 min" or "1.2 h".
+pub fn format_duration(locale: &str, duration_ms: f64) -> String {
+    let abs_ms = duration_ms.abs();
+    let (value, digits, unit) = if abs_ms < 10.0 {
+        (duration_ms, 1, "ms")
+    } else if abs_ms < 1000.0 {
+        (duration_ms, 0, "ms")
+    } else if abs_ms < 10_000.0 {
+        (duration_ms / 1000.0, 2, "s")
+    } else if abs_ms < 60_000.0 {
+        (duration_ms / 1000.0, 1, "s")
+    } else if abs_ms < 3_600_000.0 {
+        (duration_ms / 60_000.0, 1, "min")
+    } else {
+        (duration_ms / 3_600_000.0, 1, "h")
+    };
+    let options = FormatOptions { max_fraction_digits: digits, ..Default::default() };
+    format!("{}\u{a0}{unit}", format_number(locale, value, &options))
+}
+
+/// Formats the date of `time` in UTC as `locale` writes dates numerically, e.g. "3/14/2025" in "en-US" and
+/// "14.3.2025" in "fi".
+pub fn format_date(locale: &str, time: SystemTime) -> String {
+    let seconds = match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(error) => -(error.duration().as_secs_f64().ceil() as i64),
+    };
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    match (language(locale), locale) {
+        (_, "en-US" | "en_US" | "en") => format!("{month}/{day}/{year}"),
+        ("ja" | "zh", _) => format!("{year}/{month}/{day}"),
+        ("sv" | "lt" | "ko" | "hu", _) | (_, "fr-CA" | "en-CA") => format!("{year}-{month:02}-{day:02}"),
+        ("de" | "ru" | "uk" | "pl" | "tr" | "nb" | "no" | "da" | "ro" | "cs" | "sk", _) => {
+            format!("{day:02}.{month:02}.{year}")
+        }
+        ("fi", _) => format!("{day}.{month}.{year}"),
+        ("nl", _) => format!("{day}-{month}-{year}"),
+        _ => format!("{day:02}/{month:02}/{year}"),
+    }
+}
+
+/// The proleptic Gregorian date of a number of days since 1970-01-01, from Howard Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+impl<A: App> Cx<A> {
+    /// Formats `value` for the active locale. Like the other formatting methods, calling it in a computed makes the
+    /// computed update when the locale changes.
+    pub fn format_number(&self, value: f64, options: FormatOptions) -> String {
+        self.i18n.format_number(value, &options)
+    }
+
+    pub fn format_duration(&self, duration_ms: f64) -> String {
+        self.i18n.format_duration(duration_ms)
+    }
+
+    pub fn format_date(&self, time: SystemTime) -> String {
+        self.i18n.format_date(time)
+    }
+
+    /// A translation of the message `key` that updates when the locale changes or catalogs are added.
+    pub fn tr(&self, key: impl Into<String>, args: &[(&str, TrArg)]) -> Computed<String> {
+        let key = key.into();
+        let args: Vec<(String, TrArg)> = args.iter().map(|(name, value)| (name.to_string(), value.clone())).collect();
+        let i18n = self.i18n.clone();
+        self.computed(move || {
+            let args: Vec<(&str, TrArg)> = args.iter().map(|(name, value)| (name.as_str(), value.clone())).collect();
+            i18n.format(&key, &args)
+        })
+    }
+
+    /// Loads and parses the catalog for `locale` in `file` in the background. Parse errors are logged.
+    pub fn load_catalog(&mut self, locale: impl Into<String>, file: &File) {
+        let load = self.load_file(file);
+        self.catalog_loads.push((locale.into(), load));
+        self.poll_catalog_loads();
+    }
+
+    pub(crate) fn poll_catalog_loads(&mut self) {
+        let i18n = &self.i18n;
+        self.catalog_loads.retain(|(locale, load)| {
+            if let Some(data) = load.result.value() {
+                match std::str::from_utf8(&data)
+                    .map_err(|error| error.to_string())
+                    .and_then(|source| Catalog::parse(source).map_err(|error| error.to_string()))
+                {
+                    Ok(catalog) => i18n.add_catalog(locale.clone(), catalog),
+                    Err(error) => crate::error!("couldn't load the catalog for {locale}: {error}"),
+                }
+                false
+            } else if load.failed.get_fast() {
+                crate::error!("couldn't read the catalog for {locale}");
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "
+# a comment
+greeting = Hello, { $name }!
+items = { $count ->
+    [0] No items
+    [one] One item
+   *[other] { $count } items
+}
+multiline = First
+    Second
+braces = { \"{\" }literal{ \"}\" }
+";
+
+    #[test]
+    fn formats_messages_and_variants() {
+        let catalog = Catalog::parse(SOURCE).unwrap();
+        assert_eq!(catalog.format("en", "greeting", &[("name", "Ada".into())]).unwrap(), "Hello, Ada!");
+        assert_eq!(catalog.format("en", "greeting", &[]).unwrap(), "Hello, {$name}!");
+        assert_eq!(catalog.format("en", "items", &[("count", 0.into())]).unwrap(), "No items");
+        assert_eq!(catalog.format("en", "items", &[("count", 1.into())]).unwrap(), "One item");
+        assert_eq!(catalog.format("en", "items", &[("count", 5.into())]).unwrap(), "5 items");
+        assert_eq!(catalog.format("en", "items", &[("count", 1.5.into())]).unwrap(), "1.5 items");
+        assert_eq!(catalog.format("en", "multiline", &[]).unwrap(), "First\nSecond");
+        assert_eq!(catalog.format("en", "braces", &[]).unwrap(), "{literal}");
+        assert_eq!(catalog.format("en", "missing", &[]), None);
+    }
+
+    #[test]
+    fn reports_the_line_of_errors() {
+        assert_eq!(Catalog::parse("a = ok\nb = { $count ->\n  [one] x\n}").unwrap_err().line, 2);
+        assert_eq!(Catalog::parse("b = { $count ->\n  *[other] x\n  one").unwrap_err().line, 3);
+        assert_eq!(Catalog::parse("\nno equals sign").unwrap_err().line, 2);
+        assert_eq!(Catalog::parse("a = { unclosed").unwrap_err().line, 1);
+    }
+
+    #[test]
+    fn selects_plural_categories_by_language() {
+        assert_eq!(plural_category("en", 1.0), "one");
+        assert_eq!(plural_category("en", 0.0), "other");
+        assert_eq!(plural_category("fr", 0.0), "one");
+        assert_eq!(plural_category("ru", 21.0), "one");
+        assert_eq!(plural_category("ru", 23.0), "few");
+        assert_eq!(plural_category("ru", 12.0), "many");
+        assert_eq!(plural_category("pl", 21.0), "many");
+        assert_eq!(plural_category("pl", 22.0), "few");
+        assert_eq!(plural_category("ja", 1.0), "other");
+    }
+
+    #[test]
+    fn formats_numbers_for_the_locale() {
+        let options = FormatOptions::default();
+        assert_eq!(format_number("en", 1234567.891, &options), "1,234,567.89");
+        assert_eq!(format_number("de", 1234567.891, &options), "1.234.567,89");
+        assert_eq!(format_number("fi", 1234.5, &options), "1\u{a0}234,5");
+        assert_eq!(format_number("es", 1234.0, &options), "1234");
+        assert_eq!(format_number("es", 12345.0, &options), "12.345");
+        assert_eq!(format_number("en", 1234.0, &options.clone().with_grouping(false)), "1234");
+        assert_eq!(format_number("en", -0.001, &options), "0");
+        assert_eq!(format_number("en", -2.5, &FormatOptions::fixed(2)), "-2.50");
+        assert_eq!(format_number("de", 0.5, &FormatOptions::fixed(0)), "0");
+    }
+
+    #[test]
+    fn formats_durations_with_auto_units() {
+        assert_eq!(format_duration("en", 2.5), "2.5\u{a0}ms");
+        assert_eq!(format_duration("en", 250.0), "250\u{a0}ms");
+        assert_eq!(format_duration("de", 1500.0), "1,5\u{a0}s");
+        assert_eq!(format_duration("en", 150_000.0), "2.5\u{a0}min");
+        assert_eq!(format_duration("en", 5_400_000.0), "1.5\u{a0}h");
+    }
+
+    #[test]
+    fn formats_dates_for_the_locale() {
+        let time = UNIX_EPOCH + std::time::Duration::from_secs(1_741_910_400);
+        assert_eq!(format_date("en-US", time), "3/14/2025");
+        assert_eq!(format_date("en-GB", time), "14/03/2025");
+        assert_eq!(format_date("de", time), "14.03.2025");
+        assert_eq!(format_date("sv", time), "2025-03-14");
+        assert_eq!(format_date("en", UNIX_EPOCH - std::time::Duration::from_secs(1)), "12/31/1969");
+    }
+
+    #[test]
+    fn falls_back_to_the_language_and_fallback_locale() {
+        let cx = SignalCx::new();
+        let i18n = Localization::new(&cx, "en");
+        i18n.add_catalog("en", Catalog::parse("title = Settings\nonly-en = English").unwrap());
+        i18n.add_catalog("fi", Catalog::parse("title = Asetukset").unwrap());
+
+        let title = {
+            let i18n = i18n.clone();
+            cx.computed(move || i18n.format("title", &[]))
+        };
+        assert_eq!(title.get(), "Settings");
+        i18n.set_locale("fi-FI");
+        assert_eq!(title.get(), "Asetukset");
+        assert_eq!(i18n.format("only-en", &[]), "English");
+        assert_eq!(i18n.format("unknown", &[]), "unknown");
+    }
+}
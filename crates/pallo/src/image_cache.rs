@@ -0,0 +1,137 @@
+use std::{
+    hash::{Hash, Hasher},
+    rc::{Rc, Weak},
+};
+
+use rustc_hash::{FxHashMap, FxHasher};
+
+use crate::{AnimatedImage, App, Cx, Image, ImageType};
+
+/// Decoded values by their encoded data, kept for as long as something holds on to them. Entries are found by a hash
+/// of the data and then compared byte for byte, so that data that happens to hash the same isn't mistaken for it.
+pub(crate) struct DecodeCache<T> {
+    entries: FxHashMap<u64, Vec<DecodeEntry<T>>>,
+}
+
+/// The encoded data and what it was decoded into.
+type DecodeEntry<T> = (Box<[u8]>, Weak<T>);
+
+impl<T> Default for DecodeCache<T> {
+    fn default() -> Self {
+        Self { entries: Default::default() }
+    }
+}
+
+impl<T> DecodeCache<T> {
+    fn get_or_decode(&mut self, data: &[u8], decode: impl FnOnce(&[u8]) -> Option<T>) -> Option<Rc<T>> {
+        let mut hasher = FxHasher::default();
+        data.hash(&mut hasher);
+        let key = hasher.finish();
+
+        let cached = self.entries.get(&key).and_then(|bucket| {
+            bucket.iter().find(|(encoded, _)| **encoded == *data).and_then(|(_, value)| value.upgrade())
+        });
+        if cached.is_some() {
+            return cached;
+        }
+        self.trim();
+        let value = Rc::new(decode(data)?);
+        self.entries.entry(key).or_default().push((data.into(), Rc::downgrade(&value)));
+        Some(value)
+    }
+
+    /// The values that are still in use.
+    fn values(&self) -> impl Iterator<Item = Rc<T>> + '_ {
+        self.entries.values().flatten().filter_map(|(_, value)| value.upgrade())
+    }
+
+    /// Forgets the values that are no longer in use.
+    fn trim(&mut self) {
+        self.entries.retain(|_, bucket| {
+            bucket.retain(|(_, value)| value.strong_count() > 0);
+            !bucket.is_empty()
+        });
+    }
+}
+
+/// Decoded images by their encoded data, kept for as long as something holds on to them.
+#[derive(Default)]
+pub(crate) struct ImageCache {
+    images: DecodeCache<Image>,
+    animated_images: DecodeCache<AnimatedImage>,
+}
+
+impl ImageCache {
+    /// How many decoded images are still in use, and the memory they take assuming 4 bytes per pixel.
+    pub(crate) fn usage(&self) -> (usize, usize) {
+        let image_bytes = |image: &Image| {
+            let bounds = image.get_bounds();
+            bounds.width() as usize * bounds.height() as usize * 4
+        };
+        let images = self.images.values().map(|image| image_bytes(&image));
+        let animated_images = self
+            .animated_images
+            .values()
+            .map(|image| image.frames().iter().map(|frame| image_bytes(&frame.image)).sum());
+        images.chain(animated_images).fold((0, 0), |(count, bytes), image| (count + 1, bytes + image))
+    }
+
+    /// Forgets the images that are no longer in use.
+    pub(crate) fn trim(&mut self) {
+        self.images.trim();
+        self.animated_images.trim();
+    }
+}
+
+impl<A: App> Cx<A> {
+    /// Decodes an encoded image such as a PNG or JPEG, or returns the already decoded one if the same data was
+    /// decoded before and is still in use.
+    pub fn decode_image(&mut self, data: &[u8]) -> Option<Rc<Image>> {
+        self.image_cache.images.get_or_decode(data, Image::from_encoded)
+    }
+
+    /// Like `decode_image`, but decodes all frames of an animated GIF, APNG or WebP. Returns `None` for still images.
+    pub fn decode_animated_image(&mut self, data: &[u8]) -> Option<Rc<AnimatedImage>> {
+        self.image_cache.animated_images.get_or_decode(data, AnimatedImage::from_encoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(data: &[u8]) -> Option<String> {
+        std::str::from_utf8(data).ok().map(str::to_uppercase)
+    }
+
+    #[test]
+    fn decoded_values_are_shared_while_in_use() {
+        let mut cache = DecodeCache::default();
+        let first = cache.get_or_decode(b"abc", decode).unwrap();
+        let second = cache.get_or_decode(b"abc", |_| panic!("decoded twice")).unwrap();
+        assert!(Rc::ptr_eq(&first, &second));
+        assert_eq!(*cache.get_or_decode(b"abd", decode).unwrap(), "ABD");
+
+        drop((first, second));
+        cache.trim();
+        assert_eq!(cache.values().count(), 0);
+        assert!(cache.entries.is_empty());
+        assert!(cache.get_or_decode(&[0xff], decode).is_none());
+    }
+
+    #[test]
+    fn colliding_hashes_are_told_apart_by_their_data() {
+        let mut cache = DecodeCache::default();
+        let value = cache.get_or_decode(b"one", decode).unwrap();
+        // pretend that "two" hashes the same as "one"
+        let (_, bucket) = cache.entries.drain().next().unwrap();
+        let mut hasher = FxHasher::default();
+        b"two"[..].hash(&mut hasher);
+        cache.entries.insert(hasher.finish(), bucket);
+
+        let other = cache.get_or_decode(b"two", decode).unwrap();
+        assert_eq!(*other, "TWO");
+        assert_eq!(*value, "ONE");
+        assert_eq!(cache.entries.values().next().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,151 @@
+use keyboard_types::Key;
+
+use crate::{
+    Align, App, Canvas, CanvasType, Color, Cx, Margin, PathBuilder, PathBuilderType, Rect, Side, Text, TextBuilder,
+    frame_stats::FRAME_STATS_SAMPLES, point, recent_logs, rgb, tree::NodeId,
+};
+
+const PANEL_WIDTH: f32 = 260.0;
+const LINE_HEIGHT: f32 = 14.0;
+const GRAPH_HEIGHT: f32 = 60.0;
+const LOG_LINES: usize = 8;
+// the top of the graphs, in microseconds
+const GRAPH_MAX_MICROS: f32 = 33_333.0;
+
+/// Draws component bounds, the component tree, recent log messages, frame timings and memory usage on top of the UI.
+#[derive(Default)]
+pub(crate) struct Inspector {
+    pub(crate) enabled: bool,
+    lines: Vec<Text>,
+}
+
+impl Inspector {
+    /// Ctrl+Shift+I, or Cmd+Shift+I on macOS.
+    pub(crate) fn is_toggle_chord<A: App>(cx: &Cx<A>, key: &Key) -> bool {
+        matches!(key, Key::Character(c) if c.eq_ignore_ascii_case("i"))
+            && cx.mods.shift
+            && (cx.mods.ctrl || cx.mods.meta)
+    }
+
+    pub(crate) fn draw<A: App>(&mut self, cx: &mut Cx<A>, canvas: &mut Canvas) {
+        let hovered = cx.main_pointer().hovered_component;
+        let mut rows = vec![];
+        cx.tree.traverse_depth(cx.tree.get_root_id(), |id, state| {
+            if state.visible {
+                let color = if state.needs_relayout {
+                    rgb(0xff4040)
+                } else {
+                    rgb(0x40c0ff).with_alpha(0.5)
+                };
+                canvas.stroke(color, 1.0).draw_rect(state.bounds);
+            }
+            rows.push((id, state.visible, state.needs_relayout, state.bounds));
+            state.visible
+        });
+        if let Some(hovered) = hovered {
+            let bounds = cx.tree.get(hovered).bounds;
+            canvas.fill(rgb(0xffcc00).with_alpha(0.2)).draw_rect(bounds);
+            canvas.stroke(rgb(0xffcc00), 1.0).draw_rect(bounds);
+        }
+
+        let mut panel = cx.ui_bounds.with_width_align(PANEL_WIDTH.min(cx.ui_bounds.width()), Align::End);
+        canvas.fill(rgb(0x000000).with_alpha(0.8)).draw_rect(panel);
+        panel = panel.with_margin(Margin::even(6.0));
+
+        let graphs = panel.remove_from(GRAPH_HEIGHT, Side::Bottom);
+        draw_graphs(cx, canvas, graphs);
+        panel.remove_from(4.0, Side::Bottom);
+
+        let timings = format!(
+            "frame {:.2} ms  draw {:.2} ms  update {:.2} ms",
+            cx.frame_time_micros as f32 / 1000.0,
+            cx.draw_time_micros as f32 / 1000.0,
+            cx.update_time_micros as f32 / 1000.0,
+        );
+        let memory = cx.memory_report();
+        let memory = format!(
+            "memory {:.1} MB  images {:.1} MB  surfaces {:.1} MB  {} components",
+            megabytes(memory.total_bytes()),
+            megabytes(memory.image_bytes),
+            megabytes(memory.surface_bytes + memory.free_surface_bytes),
+            memory.components + memory.overlay_components,
+        );
+        let num_lines = ((panel.height() / LINE_HEIGHT) as usize).max(1);
+        let logs = recent_logs(LOG_LINES.min(num_lines / 2));
+        let num_tree_lines = num_lines.saturating_sub(logs.len() + 2);
+        let tree = rows
+            .iter()
+            .map(|(id, visible, needs_relayout, bounds)| {
+                format!(
+                    "{}#{} {}{}{} {:.0}x{:.0}",
+                    "  ".repeat(depth(cx, *id)),
+                    id.index,
+                    if *visible { "" } else { "hidden " },
+                    if *needs_relayout { "relayout " } else { "" },
+                    if Some(*id) == hovered { "hovered" } else { "" },
+                    bounds.width(),
+                    bounds.height(),
+                )
+            })
+            .chain(std::iter::repeat(String::new()))
+            .take(num_tree_lines);
+        // the log is kept at the bottom of the panel
+        let lines = [timings, memory]
+            .into_iter()
+            .chain(tree)
+            .chain(logs.iter().map(|record| format!("{} {}: {}", record.level, record.module, record.message)))
+            .collect::<Vec<_>>();
+
+        while self.lines.len() < lines.len() {
+            let text = TextBuilder::default().font_size(10.0).color(rgb(0xffffff)).build(cx);
+            self.lines.push(text);
+        }
+        canvas.with_clip_rect(panel, |canvas| {
+            for (i, (line, text)) in lines.into_iter().zip(self.lines.iter_mut()).enumerate() {
+                if text.get_text() != &line {
+                    text.set_text(line);
+                }
+                let row = Rect::from_xywh(panel.left(), panel.top() + i as f32 * LINE_HEIGHT, panel.width(), 10.0);
+                text.draw(canvas, row);
+            }
+        });
+    }
+}
+
+fn draw_graphs<A: App>(cx: &Cx<A>, canvas: &mut Canvas, bounds: Rect) {
+    canvas.fill(rgb(0xffffff).with_alpha(0.05)).draw_rect(bounds);
+    // a line at 60 fps
+    let y = bounds.bottom() - bounds.height() * 16_667.0 / GRAPH_MAX_MICROS;
+    canvas.fill(rgb(0xffffff).with_alpha(0.3)).draw_rect(Rect::from_xywh(bounds.left(), y, bounds.width(), 1.0));
+
+    let samples = || cx.frame_stats.samples();
+    draw_graph(canvas, bounds, samples().map(|sample| sample.frame_micros), rgb(0x40c0ff));
+    draw_graph(canvas, bounds, samples().map(|sample| sample.draw_micros), rgb(0x40ff80));
+}
+
+fn draw_graph(canvas: &mut Canvas, bounds: Rect, micros: impl Iterator<Item = u128>, color: Color) {
+    let mut path = PathBuilder::default();
+    for (i, sample) in micros.enumerate() {
+        let x = bounds.left() + bounds.width() * i as f32 / (FRAME_STATS_SAMPLES - 1) as f32;
+        let y = bounds.bottom() - bounds.height() * (sample as f32 / GRAPH_MAX_MICROS).min(1.0);
+        if i == 0 {
+            path.move_to(point(x, y));
+        } else {
+            path.line_to(point(x, y));
+        }
+    }
+    canvas.stroke(color, 1.0).draw_path(&path.build());
+}
+
+fn megabytes(bytes: usize) -> f32 {
+    bytes as f32 / (1024.0 * 1024.0)
+}
+
+fn depth<A: App>(cx: &Cx<A>, mut id: NodeId) -> usize {
+    let mut depth = 0;
+    while let Some(parent) = cx.tree.get_parent(id) {
+        depth += 1;
+        id = parent;
+    }
+    depth
+}
@@ -1,5 +1,5 @@
 use crate::{
-    App, CanvasType, Cx, IntPoint, Point, RasterSurfaceType, Rect, Signal, Surface, renderers,
+    App, CanvasType, Cx, DisplayList, IntPoint, Point, RasterSurfaceType, Rect, Signal, Surface, renderers, rgba,
 };
 
 pub struct Layer {
@@ -20,13 +20,10 @@ impl Layer {
     pub fn update<A: App>(&mut self, cx: &mut Cx<A>) {
         let surface_size =
             (self.target_size.to_float() * cx.scale_factor.get() * cx.ui_scale).to_int();
-        if self.surface.is_none() {
-            self.surface = Some(Surface::new(surface_size, 1.0));
-            self.surface_size_signal.set(surface_size);
-        } else if let Some(surface) = &mut self.surface
-            && surface.get_size() != surface_size
-        {
-            *surface = Surface::new(surface_size, 1.0);
+        if self.surface.as_ref().is_none_or(|surface| surface.get_size() != surface_size) {
+            // dropped first so that it can be reused if the new size is in the same bucket
+            self.surface = None;
+            self.surface = Some(cx.create_surface(surface_size, 1.0));
             self.surface_size_signal.set(surface_size);
         }
     }
@@ -50,6 +47,16 @@ impl Layer {
         }
     }
 
+    /// Replaces the contents with what was recorded into `list`, in the same units as the layer's size, so that the
+    /// contents can be redrawn after a resize without running the draws that recorded them again.
+    pub fn draw_display_list<A: App>(&self, cx: &Cx<A>, list: &DisplayList) {
+        if let Some(surface) = &self.surface {
+            let mut canvas = surface.get_canvas();
+            canvas.set_scale_factor(cx.scale_factor.get_fast() * cx.ui_scale);
+            canvas.clear(rgba(0x00000000)).draw_display_list(list);
+        }
+    }
+
     pub fn get_size_computed(&self) -> crate::Computed<IntPoint> {
         self.surface_size_signal.as_computed()
     }
@@ -293,3 +293,64 @@ impl<'a, A: App> Grid<'a, A> {
         }
     }
 }
+
+/// A fixed way of arranging a component's children, used by `#[children(layout = "...")]`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChildLayout {
+    /// Left to right, sized by their preferred widths with the rest of the space shared evenly.
+    Row,
+    /// Top to bottom, sized by their preferred heights with the rest of the space shared evenly.
+    Column,
+    /// On top of each other, each filling the bounds.
+    Stack,
+}
+
+impl ChildLayout {
+    pub fn layout<A: App>(self, cx: &mut Cx<A>, bounds: Rect, gap: f32, children: Vec<&mut dyn Component<A>>) {
+        if self == ChildLayout::Stack {
+            for child in children {
+                child.layout(cx, bounds);
+            }
+            return;
+        }
+        let grids: Vec<_> = children.into_iter().map(|c| Grid::component(c).respect_visibility()).collect();
+        let grid = if self == ChildLayout::Row {
+            left_right(grids)
+        } else {
+            top_down(grids)
+        };
+        grid.child_gap(gap).layout(cx, bounds);
+    }
+
+    /// Combines the visible children's preferred sizes: summed (with the gaps) along a row or column, and the largest
+    /// across it. The sum is only known if every child has a preferred size in that direction.
+    pub fn preferred_size<A: App>(
+        self,
+        cx: &mut Cx<A>,
+        parent_bounds: Rect,
+        gap: f32,
+        children: Vec<&mut dyn Component<A>>,
+    ) -> (Option<f32>, Option<f32>) {
+        let mut sizes = vec![];
+        for child in children {
+            if cx.is_visible(child.id()) {
+                sizes.push(child.get_preferred_size(cx, parent_bounds));
+            }
+        }
+        let max = |sizes: Vec<Option<f32>>| sizes.into_iter().flatten().reduce(f32::max);
+        let widths: Vec<_> = sizes.iter().map(|s| s.0).collect();
+        let heights: Vec<_> = sizes.iter().map(|s| s.1).collect();
+        let gaps = gap * sizes.len().saturating_sub(1) as f32;
+        let total = |sizes: Vec<Option<f32>>| {
+            if sizes.is_empty() {
+                return None;
+            }
+            sizes.into_iter().sum::<Option<f32>>().map(|sum| sum + gaps)
+        };
+        match self {
+            ChildLayout::Row => (total(widths), max(heights)),
+            ChildLayout::Column => (max(widths), total(heights)),
+            ChildLayout::Stack => (max(widths), max(heights)),
+        }
+    }
+}
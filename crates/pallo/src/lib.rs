@@ -1,7 +1,7 @@
 pub mod animation;
 
-// #[cfg(any(target_os = "macos", target_os = "windows"))]
-// pub mod baseview;
+#[cfg(all(feature = "baseview", any(target_os = "macos", target_os = "windows")))]
+pub mod baseview;
 
 pub mod platform;
 
@@ -9,44 +9,88 @@ pub mod color;
 pub mod component;
 pub mod components;
 pub mod context;
+pub mod controller;
+mod damage;
+pub mod error;
 pub mod event;
+pub mod fetch;
+pub mod file_loader;
+mod font_cache;
+pub mod frame_stats;
 pub mod geometry;
+pub mod i18n;
+mod image_cache;
+mod inspector;
 pub mod layer;
 pub mod layout;
+pub mod live_channel;
+pub mod memory;
+#[cfg(feature = "nih")]
+pub mod nih;
+pub mod overlay;
 pub mod properties;
 pub mod renderers;
+pub mod screenshot;
 pub mod signal;
 pub mod svg;
+pub mod text_editing;
 mod tree;
 pub mod ui;
 pub mod utils;
+pub mod wheel;
 
 pub use crate::{
     animation::*,
     color::*,
     component::*,
-    components::{label::*, paragraph::*, scroll::*},
+    components::{
+        badge::*, breadcrumbs::*, chip::*, file_browser::*, image::*, label::*, meter::*, node_graph::*,
+        number_input::*, paragraph::*, popup_menu::*, portal::*, progress_ring::*, scope::*, scroll::*, step_grid::*,
+        table::*, tree_view::*, viewport::*, waveform::*, xy_pad::*,
+    },
     context::*,
+    controller::*,
+    error::*,
     event::*,
+    fetch::*,
+    file_loader::*,
+    frame_stats::*,
     geometry::*,
+    i18n::*,
     layer::*,
     layout::*,
+    live_channel::*,
+    memory::*,
+    overlay::*,
     properties::*,
     renderers::*,
+    screenshot::*,
     signal::*,
     svg::*,
+    text_editing::*,
     ui::*,
     utils::*,
+    wheel::*,
 };
-pub use keyboard_types::Key;
+pub use keyboard_types::{Code, Key, Location};
 pub use palette;
 pub use pallo_macro::*;
 pub use pallo_util::*;
-pub use platform::{Clipboard, FileOpenOptions, FileSaveOptions, InputType, Platform, PlatformCommon};
+pub use platform::{
+    Clipboard, EditAction, EditMenuOptions, FileOpenOptions, FileSaveOptions, InputType, Platform, PlatformCommon,
+    TextFieldEvent, TextFieldOptions,
+};
 pub use rustc_hash::FxHashMap;
 
+#[cfg(feature = "nih")]
+pub use nih::ParamSignal;
+#[cfg(feature = "nih")]
+pub use nih_plug;
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub use platform::DisplayLink;
 #[cfg(target_family = "wasm")]
-pub use platform::create_canvas;
+pub use platform::{create_canvas, create_worker_canvas, run_in_worker};
 
 #[cfg(target_family = "wasm")]
 pub use js_sys;
@@ -0,0 +1,265 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use parking_lot::Mutex;
+
+use crate::{App, Cx, Event};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LiveMessage {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+/// What happens on a `LiveChannel`, mapped to app events by the function given to `Cx::open_live_channel`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LiveEvent {
+    Connected,
+    Message(LiveMessage),
+    /// The connection was lost or couldn't be made. It's tried again after `reconnect_in`, or never if it's `None`
+    /// because the `Backoff` ran out of attempts.
+    Disconnected {
+        reconnect_in: Option<Duration>,
+    },
+}
+
+/// How long a `LiveChannel` waits before reconnecting, growing by `factor` with each failed attempt in a row.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+    pub factor: f32,
+    /// How many failed attempts in a row to retry after, or `None` to keep retrying.
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self { initial: Duration::from_millis(500), max: Duration::from_secs(30), factor: 2.0, max_attempts: None }
+    }
+}
+
+impl Backoff {
+    /// The delay before the reconnect after `attempt` failed attempts in a row, starting from 0 for a connection that
+    /// was just lost.
+    pub fn delay(&self, attempt: u32) -> Option<Duration> {
+        if self.max_attempts.is_some_and(|max_attempts| attempt >= max_attempts) {
+            return None;
+        }
+        // a negative or NaN factor would make the delay one too, which `Duration` can't hold
+        let delay = self.initial.as_secs_f32() * self.factor.max(0.0).powi(attempt.min(32) as i32);
+        Some(Duration::from_secs_f32(delay.max(0.0).min(self.max.as_secs_f32())))
+    }
+}
+
+/// Shared between a `LiveChannel` and the connection it owns.
+#[derive(Default)]
+pub(crate) struct LiveState {
+    incoming: Mutex<VecDeque<LiveEvent>>,
+    outgoing: Mutex<VecDeque<LiveMessage>>,
+    connected: AtomicBool,
+    closed: AtomicBool,
+}
+
+impl LiveState {
+    pub(crate) fn receive(&self, event: LiveEvent) {
+        match &event {
+            LiveEvent::Connected => self.connected.store(true, Ordering::Relaxed),
+            LiveEvent::Disconnected { .. } => self.connected.store(false, Ordering::Relaxed),
+            LiveEvent::Message(_) => {}
+        }
+        self.incoming.lock().push_back(event);
+    }
+
+    #[cfg_attr(all(not(target_family = "wasm"), not(feature = "live")), allow(dead_code))]
+    pub(crate) fn take_outgoing(&self) -> Option<LiveMessage> {
+        self.outgoing.lock().pop_front()
+    }
+
+    pub(crate) fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+}
+
+/// A WebSocket connection that reconnects by itself. What it receives is delivered to the app as `Event::App` at the
+/// start of each frame. Dropping the channel closes the connection.
+pub struct LiveChannel {
+    state: Arc<LiveState>,
+    #[cfg(target_family = "wasm")]
+    connection: crate::platform::WebSocketConnection,
+}
+
+impl LiveChannel {
+    /// Sends `message`, or sends it once the channel has reconnected if it's disconnected.
+    pub fn send(&self, message: LiveMessage) {
+        self.state.outgoing.lock().push_back(message);
+        #[cfg(target_family = "wasm")]
+        self.connection.flush(&self.state);
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.state.is_connected()
+    }
+}
+
+impl Drop for LiveChannel {
+    fn drop(&mut self) {
+        self.state.closed.store(true, Ordering::Relaxed);
+        #[cfg(target_family = "wasm")]
+        self.connection.close();
+    }
+}
+
+type LiveEventMapper<A> = Box<dyn Fn(LiveEvent) -> Option<<A as App>::Input>>;
+
+pub(crate) struct LiveChannels<A: App> {
+    channels: Vec<(Arc<LiveState>, LiveEventMapper<A>)>,
+}
+
+impl<A: App> Default for LiveChannels<A> {
+    fn default() -> Self {
+        Self { channels: vec![] }
+    }
+}
+
+impl<A: App> Cx<A> {
+    /// Connects to the WebSocket at `url`, reconnecting after `backoff` when the connection is lost. `map` turns the
+    /// channel's events into app events, or drops them by returning `None`. Native builds need the `live` feature.
+    pub fn open_live_channel(
+        &mut self,
+        url: impl Into<String>,
+        backoff: Backoff,
+        map: impl Fn(LiveEvent) -> Option<A::Input> + 'static,
+    ) -> LiveChannel {
+        let state = Arc::new(LiveState::default());
+        self.live_channels.channels.push((state.clone(), Box::new(map)));
+        #[cfg(target_family = "wasm")]
+        {
+            let connection = crate::platform::open_web_socket(url.into(), state.clone(), backoff);
+            LiveChannel { state, connection }
+        }
+        #[cfg(not(target_family = "wasm"))]
+        {
+            connect_in_background(url.into(), state.clone(), backoff);
+            LiveChannel { state }
+        }
+    }
+
+    /// Sends out what the live channels have received since the previous frame.
+    pub(crate) fn poll_live_channels(&mut self) {
+        let input = &mut self.input;
+        self.live_channels.channels.retain(|(state, map)| {
+            let events: Vec<LiveEvent> = state.incoming.lock().drain(..).collect();
+            input.extend(events.into_iter().filter_map(map).map(Event::App));
+            !state.is_closed()
+        });
+    }
+}
+
+#[cfg(all(not(target_family = "wasm"), feature = "live"))]
+fn connect_in_background(url: String, state: Arc<LiveState>, backoff: Backoff) {
+    use tungstenite::{Message, stream::MaybeTlsStream};
+
+    // how often a connection that's waiting for messages checks for ones to send
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+    std::thread::spawn(move || {
+        let mut attempt = 0;
+        while !state.is_closed() {
+            if let Ok((mut socket, _)) = tungstenite::connect(url.as_str()) {
+                let timeout = match socket.get_mut() {
+                    MaybeTlsStream::Plain(stream) => stream.set_read_timeout(Some(POLL_INTERVAL)),
+                    MaybeTlsStream::Rustls(stream) => stream.sock.set_read_timeout(Some(POLL_INTERVAL)),
+                    _ => Ok(()),
+                };
+                if timeout.is_ok() {
+                    attempt = 0;
+                    state.receive(LiveEvent::Connected);
+                    loop {
+                        if state.is_closed() {
+                            let _ = socket.close(None);
+                            return;
+                        }
+                        let sent = std::iter::from_fn(|| state.take_outgoing()).try_for_each(|message| {
+                            socket.send(match message {
+                                LiveMessage::Text(text) => Message::text(text),
+                                LiveMessage::Binary(data) => Message::binary(data),
+                            })
+                        });
+                        if sent.is_err() {
+                            break;
+                        }
+                        match socket.read() {
+                            Ok(Message::Text(text)) => {
+                                state.receive(LiveEvent::Message(LiveMessage::Text(text.as_str().to_owned())))
+                            }
+                            Ok(Message::Binary(data)) => {
+                                state.receive(LiveEvent::Message(LiveMessage::Binary(data.to_vec())))
+                            }
+                            Ok(Message::Close(_)) => break,
+                            Ok(_) => {}
+                            Err(tungstenite::Error::Io(error))
+                                if matches!(
+                                    error.kind(),
+                                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                                ) => {}
+                            Err(_) => break,
+                        }
+                    }
+                } else {
+                    attempt += 1;
+                }
+            } else {
+                attempt += 1;
+            }
+            let reconnect_in = backoff.delay(attempt);
+            state.receive(LiveEvent::Disconnected { reconnect_in });
+            match reconnect_in {
+                Some(delay) => std::thread::sleep(delay),
+                None => return,
+            }
+        }
+    });
+}
+
+#[cfg(all(not(target_family = "wasm"), not(feature = "live")))]
+fn connect_in_background(url: String, state: Arc<LiveState>, _backoff: Backoff) {
+    crate::error!("can't connect to {url}, pallo was built without the `live` feature");
+    state.receive(LiveEvent::Disconnected { reconnect_in: None });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Backoff;
+
+    #[test]
+    fn backoff_grows_up_to_the_max() {
+        let backoff = Backoff { max_attempts: Some(6), ..Default::default() };
+        assert_eq!(backoff.delay(0), Some(Duration::from_millis(500)));
+        assert_eq!(backoff.delay(2), Some(Duration::from_secs(2)));
+        assert_eq!(backoff.delay(5), Some(Duration::from_secs(16)));
+        assert_eq!(backoff.delay(6), None);
+        assert_eq!(Backoff::default().delay(100), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn backoff_with_a_negative_factor_does_not_panic() {
+        let backoff = Backoff { factor: -2.0, ..Default::default() };
+        assert_eq!(backoff.delay(0), Some(Duration::from_millis(500)));
+        assert_eq!(backoff.delay(1), Some(Duration::ZERO));
+        let backoff = Backoff { factor: f32::NAN, ..Default::default() };
+        assert_eq!(backoff.delay(3), Some(Duration::ZERO));
+    }
+}
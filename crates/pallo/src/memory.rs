@@ -0,0 +1,92 @@
+use crate::{App, Cx, RendererType};
+
+/// What the caches and surfaces of a UI hold on to, from `Cx::memory_report`. Byte counts assume 4 bytes per pixel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// Decoded images that are still in use, counting each frame of animated ones.
+    pub images: usize,
+    pub image_bytes: usize,
+    /// Fonts kept by their style, each with its own cache of text blobs.
+    pub fonts: usize,
+    pub text_blobs: usize,
+    /// An estimate, since the renderers don't tell the size of a blob.
+    pub text_blob_bytes: usize,
+    /// The glyphs that the renderer keeps rasterized, or 0 if it doesn't tell. Skia keeps a single glyph cache for the
+    /// whole process, so with several UIs, e.g. plugin editors, this counts the glyphs of all of them.
+    pub glyph_bytes: usize,
+    /// Responses kept by requests with `FetchOptions::cache`.
+    pub fetch_responses: usize,
+    pub fetch_bytes: usize,
+    /// Surfaces from the surface pool that are in use, e.g. the caches of components.
+    pub surfaces: usize,
+    pub surface_bytes: usize,
+    /// Surfaces that were dropped and are kept for reuse.
+    pub free_surfaces: usize,
+    pub free_surface_bytes: usize,
+    /// Components in the main UI, including the root.
+    pub components: usize,
+    /// Components in open or closing overlays.
+    pub overlay_components: usize,
+}
+
+impl MemoryReport {
+    pub fn total_bytes(&self) -> usize {
+        self.image_bytes
+            + self.text_blob_bytes
+            + self.glyph_bytes
+            + self.fetch_bytes
+            + self.surface_bytes
+            + self.free_surface_bytes
+    }
+}
+
+impl<A: App> Cx<A> {
+    /// Sums up the memory held by the image, text and glyph caches and by surfaces, and counts the components. Walks
+    /// all of the caches, so it's meant for occasional checks rather than every frame.
+    pub fn memory_report(&self) -> MemoryReport {
+        let (images, image_bytes) = self.image_cache.usage();
+        let blob_caches = || self.font_cache.blob_caches().map(|blobs| blobs.borrow());
+        let surface_pool = self.backend.surface_pool_stats();
+        let (fetch_responses, fetch_bytes) = self.fetcher.cache.lock().usage();
+        let root = self.tree.get_root_id();
+        let mut overlay_components = 0;
+        for child in self.tree.get_children(root) {
+            if self.tree.get(*child).overlay {
+                self.tree.traverse_depth(*child, |_, _| {
+                    overlay_components += 1;
+                    true
+                });
+            }
+        }
+        MemoryReport {
+            images,
+            image_bytes,
+            fonts: self.font_cache.num_fonts(),
+            text_blobs: blob_caches().map(|blobs| blobs.len()).sum(),
+            text_blob_bytes: blob_caches().map(|blobs| blobs.estimated_bytes()).sum(),
+            glyph_bytes: self.backend.glyph_cache_bytes(),
+            fetch_responses,
+            fetch_bytes,
+            surfaces: surface_pool.used_surfaces,
+            surface_bytes: surface_pool.used_bytes,
+            free_surfaces: surface_pool.free_surfaces,
+            free_surface_bytes: surface_pool.free_bytes,
+            components: self.tree.len() - overlay_components,
+            overlay_components,
+        }
+    }
+
+    /// Frees what the caches hold that can be recreated when it's needed again: the free surfaces, cached text blobs,
+    /// rasterized glyphs, cached fetch responses and images that are no longer in use. E.g. for when the app goes to
+    /// the background or a plugin's editor is closed. With Skia the rasterized glyphs are shared by the whole process,
+    /// so this drops the ones of any other UIs too, which then rasterize them again.
+    pub fn trim_caches(&mut self) {
+        self.trim_surface_pool(0);
+        for blobs in self.font_cache.blob_caches() {
+            blobs.borrow_mut().clear();
+        }
+        self.backend.purge_glyph_cache();
+        self.image_cache.trim();
+        self.clear_fetch_cache();
+    }
+}
@@ -0,0 +1,151 @@
+use std::{
+    cell::Cell,
+    rc::{Rc, Weak},
+    sync::Arc,
+};
+
+use nih_plug::prelude::{GuiContext, Param, ParamPtr, Params};
+
+use crate::{App, Cx, Signal};
+
+struct ParamState {
+    param: ParamPtr,
+    // keeps the parameter that `param` points to alive
+    _params: Arc<dyn Params>,
+    context: Arc<dyn GuiContext>,
+    value: Signal<f32>,
+    synced_value: Cell<f32>,
+    in_gesture: Cell<bool>,
+}
+
+impl ParamState {
+    fn push_to_host(&self, value: f32) {
+        unsafe {
+            if self.in_gesture.get() {
+                self.context.raw_set_parameter_normalized(self.param, value);
+            } else {
+                self.context.raw_begin_set_parameter(self.param);
+                self.context.raw_set_parameter_normalized(self.param, value);
+                self.context.raw_end_set_parameter(self.param);
+            }
+        }
+        self.synced_value.set(value);
+    }
+
+    fn sync(&self) {
+        let value = self.value.get_fast();
+        if value != self.synced_value.get() {
+            // the signal was set directly since the last sync
+            self.push_to_host(value);
+        } else {
+            let host_value = unsafe { self.param.unmodulated_normalized_value() };
+            if host_value != value {
+                self.value.set(host_value);
+                self.synced_value.set(host_value);
+            }
+        }
+    }
+}
+
+impl Drop for ParamState {
+    fn drop(&mut self) {
+        if self.in_gesture.get() {
+            unsafe { self.context.raw_end_set_parameter(self.param) };
+        }
+    }
+}
+
+/// A signal holding the normalized value of a nih-plug parameter. Changes made by the UI are sent to the host
+/// and host automation is applied to the signal at the start of each frame.
+#[derive(Clone)]
+pub struct ParamSignal(Rc<ParamState>);
+
+impl ParamSignal {
+    pub fn signal(&self) -> &Signal<f32> {
+        &self.0.value
+    }
+
+    pub fn normalized(&self) -> f32 {
+        self.0.value.get()
+    }
+
+    pub fn plain(&self) -> f32 {
+        unsafe { self.0.param.preview_plain(self.0.value.get()) }
+    }
+
+    /// The normalized value including any modulation applied by the host.
+    pub fn modulated_normalized(&self) -> f32 {
+        unsafe { self.0.param.modulated_normalized_value() }
+    }
+
+    pub fn default_normalized(&self) -> f32 {
+        unsafe { self.0.param.default_normalized_value() }
+    }
+
+    pub fn set_normalized(&self, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+        if self.0.value.set_if_changed(value) {
+            self.0.push_to_host(value);
+        }
+    }
+
+    pub fn set_plain(&self, value: f32) {
+        self.set_normalized(unsafe { self.0.param.preview_normalized(value) });
+    }
+
+    /// Call when the user starts dragging a control so the host records the change as a single gesture.
+    pub fn begin_gesture(&self) {
+        if !self.0.in_gesture.replace(true) {
+            unsafe { self.0.context.raw_begin_set_parameter(self.0.param) };
+        }
+    }
+
+    pub fn end_gesture(&self) {
+        if self.0.in_gesture.replace(false) {
+            unsafe { self.0.context.raw_end_set_parameter(self.0.param) };
+        }
+    }
+
+    pub fn to_display_string(&self) -> String {
+        unsafe { self.0.param.normalized_value_to_string(self.0.value.get_fast(), true) }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct ParamBindings(Vec<Weak<ParamState>>);
+
+impl ParamBindings {
+    pub(crate) fn sync(&mut self) {
+        self.0.retain(|state| match state.upgrade() {
+            Some(state) => {
+                state.sync();
+                true
+            }
+            None => false,
+        });
+    }
+}
+
+impl<A: App> Cx<A> {
+    /// Binds the parameter that `param` picks out of `params` to a new signal, e.g.
+    /// `cx.param_signal(&context, &params, |params| &params.gain)`. The signal keeps `params` alive.
+    pub fn param_signal<P: Params + 'static, T: Param>(
+        &mut self,
+        context: &Arc<dyn GuiContext>,
+        params: &Arc<P>,
+        param: impl FnOnce(&P) -> &T,
+    ) -> ParamSignal {
+        let param = param(params.as_ref());
+        let value = param.unmodulated_normalized_value();
+        let state = Rc::new(ParamState {
+            param: param.as_ptr(),
+            _params: params.clone(),
+            context: context.clone(),
+            value: self.signal(value),
+            synced_value: Cell::new(value),
+            in_gesture: Cell::new(false),
+        });
+        self.param_bindings.0.push(Rc::downgrade(&state));
+        ParamSignal(state)
+    }
+}
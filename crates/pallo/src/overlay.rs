@@ -0,0 +1,121 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{App, Component, ComponentId, Cx, Event, EventKinds, Overlay, tree::NodeId};
+
+/// The stacking order of overlays. Overlays on a higher layer are drawn and hit-tested above those on lower ones,
+/// and within a layer the most recently added one is on top.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub enum OverlayLayer {
+    Tooltip,
+    #[default]
+    Popup,
+    Modal,
+    Toast,
+}
+
+impl OverlayLayer {
+    const ALL: [Self; 4] = [Self::Tooltip, Self::Popup, Self::Modal, Self::Toast];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+struct OverlayEntry<A: App> {
+    layer: OverlayLayer,
+    id: NodeId,
+    component: Overlay<dyn Component<A>>,
+}
+
+pub(crate) struct Overlays<A: App> {
+    /// Open overlays from the bottom to the top.
+    entries: Vec<OverlayEntry<A>>,
+    blocks_input: [bool; OverlayLayer::ALL.len()],
+}
+
+impl<A: App> Default for Overlays<A> {
+    fn default() -> Self {
+        Self { entries: vec![], blocks_input: OverlayLayer::ALL.map(|layer| layer == OverlayLayer::Modal) }
+    }
+}
+
+impl<A: App> Cx<A> {
+    /// Adds a component above the rest of the UI on the `Popup` layer. It stays open until `close_overlay` is called.
+    pub fn add_overlay<C: Component<A> + 'static>(
+        &mut self,
+        add_component: impl FnOnce(&mut Cx<A>, ComponentId) -> C,
+    ) -> Overlay<C> {
+        self.add_overlay_to_layer(OverlayLayer::Popup, add_component)
+    }
+
+    pub fn add_overlay_to_layer<C: Component<A> + 'static>(
+        &mut self,
+        layer: OverlayLayer,
+        add_component: impl FnOnce(&mut Cx<A>, ComponentId) -> C,
+    ) -> Overlay<C> {
+        let id = ComponentId(Rc::new(self.tree.add(self.tree.get_root_id())));
+        let node_id = NodeId::from(&id);
+        self.tree.get_mut(node_id).overlay = true;
+        let component = Rc::new(RefCell::new((add_component)(self, id.clone())));
+        self.component_ids.push(id);
+        let entries = &mut self.overlays.entries;
+        let index = entries.partition_point(|entry| entry.layer <= layer);
+        entries.insert(index, OverlayEntry { layer, id: node_id, component: component.clone() });
+        self.damage.add_all();
+        component
+    }
+
+    /// Stops drawing `overlay` and sending it events. Its component is dropped once the returned handle is.
+    pub fn close_overlay<C: Component<A> + ?Sized>(&mut self, overlay: &Overlay<C>) {
+        let id = NodeId::from(overlay.borrow().id());
+        self.overlays.entries.retain(|entry| entry.id != id);
+        self.damage.add_all();
+    }
+
+    pub fn is_overlay_open<C: Component<A> + ?Sized>(&self, overlay: &Overlay<C>) -> bool {
+        let id = NodeId::from(overlay.borrow().id());
+        self.overlays.entries.iter().any(|entry| entry.id == id)
+    }
+
+    /// Whether a visible overlay on `layer` keeps pointer and keyboard input from reaching the UI and the overlays
+    /// below it. Only `Modal` blocks input by default.
+    pub fn set_overlay_layer_blocks_input(&mut self, layer: OverlayLayer, blocks_input: bool) {
+        self.overlays.blocks_input[layer.index()] = blocks_input;
+    }
+
+    /// The highest layer with a visible overlay that blocks input, if any.
+    pub(crate) fn input_blocking_overlay_layer(&self) -> Option<OverlayLayer> {
+        self.overlays
+            .entries
+            .iter()
+            .rev()
+            .find(|entry| self.overlays.blocks_input[entry.layer.index()] && self.is_visible(entry.id))
+            .map(|entry| entry.layer)
+    }
+
+    /// The open overlays that `event` may reach from the bottom to the top, along with whether it may reach the rest
+    /// of the UI.
+    pub(crate) fn overlays_receiving(&self, event: &Event<A>) -> (bool, Vec<Overlay<dyn Component<A>>>) {
+        // releases always get through so that presses started before a modal opened don't get stuck
+        let blockable = event.kind().intersects(EventKinds::POINTER | EventKinds::KEYBOARD)
+            && !matches!(event, Event::PointerUp(_) | Event::Keyup { .. } | Event::ModifiersChanged(_));
+        let lowest_layer = self.input_blocking_overlay_layer().filter(|_| blockable);
+        let overlays = self.overlays_from(lowest_layer).map(|entry| entry.component.clone()).collect();
+        (lowest_layer.is_none(), overlays)
+    }
+
+    /// The open overlays that can be hovered from the bottom to the top, along with whether the rest of the UI can.
+    pub(crate) fn hoverable_overlays(&self) -> (bool, Vec<NodeId>) {
+        let lowest_layer = self.input_blocking_overlay_layer();
+        (lowest_layer.is_none(), self.overlays_from(lowest_layer).map(|entry| entry.id).collect())
+    }
+
+    /// The open overlays from the bottom to the top.
+    pub(crate) fn open_overlays(&self) -> Vec<Overlay<dyn Component<A>>> {
+        self.overlays_from(None).map(|entry| entry.component.clone()).collect()
+    }
+
+    fn overlays_from(&self, lowest_layer: Option<OverlayLayer>) -> impl Iterator<Item = &OverlayEntry<A>> {
+        self.overlays.entries.iter().filter(move |entry| lowest_layer.is_none_or(|layer| entry.layer >= layer))
+    }
+}
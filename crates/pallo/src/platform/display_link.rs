@@ -0,0 +1,114 @@
+use std::{cell::RefCell, ffi::c_void, rc::Rc};
+
+use objc2::{DefinedClass, MainThreadMarker, MainThreadOnly, define_class, msg_send, rc::Retained, sel};
+#[cfg(target_os = "macos")]
+use objc2_app_kit::NSView;
+use objc2_foundation::{NSObject, NSObjectProtocol, NSRunLoop, NSRunLoopCommonModes};
+use objc2_quartz_core::{CADisplayLink, CAFrameRateRange};
+
+use crate::{App, Error, UI};
+
+struct Ivars {
+    on_frame: RefCell<Box<dyn FnMut(f32)>>,
+}
+
+define_class!(
+    #[unsafe(super = NSObject)]
+    #[thread_kind = MainThreadOnly]
+    #[name = "TahtiDisplayLinkTarget"]
+    #[ivars = Ivars]
+    struct TahtiDisplayLinkTarget;
+
+    impl TahtiDisplayLinkTarget {
+        #[unsafe(method(frame:))]
+        fn frame(&self, link: &CADisplayLink) {
+            let interval = unsafe { link.targetTimestamp() - link.timestamp() };
+            let refresh_rate = if interval > 0.0 { (1.0 / interval) as f32 } else { 60.0 };
+            // a callback that starts a nested run loop could get here again, in which case the frame is skipped
+            if let Ok(mut on_frame) = self.ivars().on_frame.try_borrow_mut() {
+                on_frame(refresh_rate);
+            }
+        }
+    }
+
+    unsafe impl NSObjectProtocol for TahtiDisplayLinkTarget {}
+);
+
+impl TahtiDisplayLinkTarget {
+    fn new(mtm: MainThreadMarker, on_frame: impl FnMut(f32) + 'static) -> Retained<Self> {
+        let this = Self::alloc(mtm).set_ivars(Ivars { on_frame: RefCell::new(Box::new(on_frame)) });
+        unsafe { msg_send![super(this), init] }
+    }
+}
+
+/// Calls back once per refresh of the display that a view is on, at whatever rate it refreshes, including the 120 Hz
+/// of ProMotion displays. On iOS those need `CADisableMinimumFrameDurationOnPhone` in the app's Info.plist to go above
+/// 60 Hz. Stops when dropped.
+pub struct DisplayLink {
+    link: Retained<CADisplayLink>,
+    _target: Retained<TahtiDisplayLinkTarget>,
+}
+
+impl DisplayLink {
+    /// Calls `on_frame` with the display's refresh rate in Hz before each refresh of the display that the `NSView` or
+    /// `UIView` at `view` is on. Has to be called on the main thread, where `on_frame` is called too. Fails before
+    /// macOS 14, where there's no fallback to `CVDisplayLink`, so hosts need to keep drawing on a timer of their own.
+    pub fn new(view: *mut c_void, on_frame: impl FnMut(f32) + 'static) -> Result<Self, Error> {
+        let mtm = MainThreadMarker::new()
+            .ok_or_else(|| Error::Window("display links can only be created on the main thread".into()))?;
+        let target = TahtiDisplayLinkTarget::new(mtm, on_frame);
+
+        #[cfg(target_os = "macos")]
+        let link = {
+            if !objc2::available!(macos = 14.0) {
+                return Err(Error::Window("display links need macOS 14".into()));
+            }
+            let view = unsafe { &*(view as *mut NSView) };
+            unsafe { view.displayLinkWithTarget_selector(&target, sel!(frame:)) }
+        };
+        #[cfg(target_os = "ios")]
+        let link = {
+            // the link follows the screen that it's added from
+            let _ = view;
+            unsafe { CADisplayLink::displayLinkWithTarget_selector(&target, sel!(frame:)) }
+        };
+
+        unsafe {
+            // as fast as the display goes, slowing down is left to the system, e.g. in low power mode
+            link.setPreferredFrameRateRange(CAFrameRateRange { minimum: 60.0, maximum: 120.0, preferred: 120.0 });
+            // common modes so that frames keep coming while e.g. a menu is tracking the mouse
+            link.addToRunLoop_forMode(&NSRunLoop::mainRunLoop(), NSRunLoopCommonModes);
+        }
+        Ok(Self { link, _target: target })
+    }
+
+    /// Draws `ui` once per refresh of the display that `view` is on, with `Cx::refresh_rate` and the target of
+    /// `Cx::frame_stats` kept up to date. Events sent to the UI in between are handled right away but only drawn at
+    /// the next refresh, so any number of redraws they cause are coalesced into one frame, and hosts don't need to draw
+    /// after handling them. With damage tracking, refreshes where nothing changed skip drawing altogether.
+    pub fn drive<A: App>(ui: Rc<RefCell<UI<A>>>, view: *mut c_void) -> Result<Self, Error> {
+        Self::new(view, move |refresh_rate| {
+            // the host is in the middle of using the UI, it'll get drawn at the next refresh
+            let Ok(mut ui) = ui.try_borrow_mut() else {
+                return;
+            };
+            if ui.ui_context.refresh_rate != refresh_rate {
+                ui.ui_context.refresh_rate = refresh_rate;
+                ui.ui_context.frame_stats.set_target_fps(refresh_rate);
+            }
+            ui.draw();
+        })
+    }
+
+    /// Stops the callbacks while paused, e.g. while the view is hidden.
+    pub fn set_paused(&self, paused: bool) {
+        self.link.setPaused(paused);
+    }
+}
+
+impl Drop for DisplayLink {
+    fn drop(&mut self) {
+        // the link holds on to its target until it's invalidated
+        self.link.invalidate();
+    }
+}
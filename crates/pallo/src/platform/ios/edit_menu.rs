@@ -0,0 +1,114 @@
+use objc2::{
+    DefinedClass, MainThreadMarker, MainThreadOnly, define_class, msg_send,
+    rc::Retained,
+    runtime::{AnyObject, Sel},
+    sel,
+};
+use objc2_core_foundation::CGRect;
+use objc2_foundation::{NSObject, NSObjectProtocol};
+use objc2_ui_kit::{UIMenuController, UIResponder, UIView};
+use std::cell::RefCell;
+
+use super::text_field::to_cg_rect;
+use crate::{EditAction, EditMenuOptions, Later};
+
+pub struct Ivars {
+    actions: RefCell<Vec<EditAction>>,
+    events: RefCell<Option<Later<EditAction>>>,
+}
+
+define_class!(
+    /// An invisible view that becomes the first responder while the menu is shown, since `UIMenuController` asks
+    /// the first responder which of the standard edit actions it can perform and sends them to it.
+    #[unsafe(super(UIView, UIResponder, NSObject))]
+    #[thread_kind = MainThreadOnly]
+    #[name = "TahtiEditMenuResponder"]
+    #[ivars = Ivars]
+    pub struct TahtiEditMenuResponder;
+
+    impl TahtiEditMenuResponder {
+        #[unsafe(method(canBecomeFirstResponder))]
+        fn can_become_first_responder(&self) -> bool {
+            true
+        }
+
+        #[unsafe(method(canPerformAction:withSender:))]
+        fn can_perform_action(&self, action: Sel, _sender: Option<&AnyObject>) -> bool {
+            to_edit_action(action).is_some_and(|action| self.ivars().actions.borrow().contains(&action))
+        }
+
+        #[unsafe(method(cut:))]
+        fn cut(&self, _sender: Option<&AnyObject>) {
+            self.send(EditAction::Cut);
+        }
+
+        #[unsafe(method(copy:))]
+        fn copy(&self, _sender: Option<&AnyObject>) {
+            self.send(EditAction::Copy);
+        }
+
+        #[unsafe(method(paste:))]
+        fn paste(&self, _sender: Option<&AnyObject>) {
+            self.send(EditAction::Paste);
+        }
+
+        #[unsafe(method(selectAll:))]
+        fn select_all(&self, _sender: Option<&AnyObject>) {
+            self.send(EditAction::SelectAll);
+        }
+    }
+
+    unsafe impl NSObjectProtocol for TahtiEditMenuResponder {}
+);
+
+impl TahtiEditMenuResponder {
+    fn new(mtm: MainThreadMarker) -> Retained<Self> {
+        let this = Self::alloc(mtm).set_ivars(Ivars { actions: RefCell::new(vec![]), events: RefCell::new(None) });
+        unsafe { msg_send![super(this), initWithFrame: CGRect::default()] }
+    }
+
+    fn send(&self, action: EditAction) {
+        if let Some(events) = &*self.ivars().events.borrow() {
+            events.set(action);
+        }
+    }
+}
+
+fn to_edit_action(action: Sel) -> Option<EditAction> {
+    [
+        (sel!(cut:), EditAction::Cut),
+        (sel!(copy:), EditAction::Copy),
+        (sel!(paste:), EditAction::Paste),
+        (sel!(selectAll:), EditAction::SelectAll),
+    ]
+    .into_iter()
+    .find(|(selector, _)| *selector == action)
+    .map(|(_, action)| action)
+}
+
+/// The system edit menu, shown with `UIMenuController` so that it looks and behaves like the one of native fields.
+#[derive(Default)]
+pub struct EditMenu {
+    responder: Option<Retained<TahtiEditMenuResponder>>,
+}
+
+impl EditMenu {
+    pub fn show(&mut self, view: &UIView, options: EditMenuOptions) -> bool {
+        let Some(mtm) = MainThreadMarker::new() else {
+            return false;
+        };
+        let responder = self.responder.get_or_insert_with(|| {
+            let responder = TahtiEditMenuResponder::new(mtm);
+            view.addSubview(&responder);
+            responder
+        });
+        *responder.ivars().actions.borrow_mut() = options.actions;
+        *responder.ivars().events.borrow_mut() = Some(options.events);
+        if !responder.becomeFirstResponder() {
+            return false;
+        }
+        #[allow(deprecated)]
+        UIMenuController::sharedMenuController(mtm).showMenuFromView_rect(view, to_cg_rect(options.bounds));
+        true
+    }
+}
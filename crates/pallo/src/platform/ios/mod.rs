@@ -1,16 +1,19 @@
 use crate::{
-    Canvas, File, FileOpenOptions, FileSaveOptions, PointerId, WindowEvent,
+    Canvas, EditMenuOptions, Error, File, FileOpenOptions, FileSaveOptions, Margin, MouseButton, PointerId, Rect,
+    TextFieldOptions, WindowEvent,
     platform::{InputType, platform::file_picker::open_file_opener},
     point,
 };
 use block2::RcBlock;
+use edit_menu::EditMenu;
 use file_picker::{TahtiDocumentPickerDelegate, open_file_saver};
 use objc2::{
     DefinedClass, MainThreadMarker, MainThreadOnly, define_class, ffi, msg_send, rc::Retained, runtime::ProtocolObject,
 };
 use objc2_core_foundation::{CGPoint, CGRect, CGSize};
 use objc2_foundation::{
-    NSData, NSDictionary, NSError, NSFileManager, NSObject, NSObjectProtocol, NSSearchPathDirectory, NSString, NSURL,
+    NSData, NSDictionary, NSError, NSFileManager, NSNotification, NSNotificationCenter, NSObject, NSObjectProtocol,
+    NSSearchPathDirectory, NSString, NSURL, NSValue,
 };
 use objc2_metal::{
     MTLCommandBuffer, MTLCommandQueue, MTLCreateSystemDefaultDevice, MTLDevice, MTLDrawable, MTLPixelFormat, MTLTexture,
@@ -19,7 +22,8 @@ use objc2_quartz_core::{CAMetalDrawable, CAMetalLayer};
 use objc2_ui_kit::{
     UIAlertAction, UIAlertActionStyle, UIAlertController, UIAlertControllerStyle, UIApplication, UIDragDropSession,
     UIDropInteraction, UIDropInteractionDelegate, UIDropOperation, UIDropProposal, UIDropSession, UIInteraction,
-    UIPasteboard, UIResponderStandardEditActions, UITextField, UIView,
+    UIKeyboardFrameEndUserInfoKey, UIKeyboardWillChangeFrameNotification, UIPasteboard, UIResponderStandardEditActions,
+    UITextField, UITextInputTraits, UITouch, UITouchPhase, UITouchType, UIView,
 };
 use objc2_uniform_type_identifiers::NSItemProviderUTType;
 use parking_lot::Mutex;
@@ -29,11 +33,14 @@ use skia_safe::{
     scalar,
 };
 use std::collections::VecDeque;
-use std::{ffi::c_void, path::PathBuf, ptr::NonNull, sync::Arc};
+use std::{cell::Cell, ffi::c_void, path::PathBuf, ptr::NonNull, rc::Rc, sync::Arc};
+use text_field::NativeTextField;
 
-use super::{Clipboard, Later, PlatformCommon};
+use super::{Clipboard, FileWatchers, Later, PlatformCommon, PlatformErrors, Watcher};
 
+mod edit_menu;
 mod file_picker;
+mod text_field;
 
 struct DragAndDropIvars {
     event_queue: Arc<Mutex<VecDeque<WindowEvent>>>,
@@ -139,10 +146,11 @@ impl TahtiDragAndDropDelegate {
 pub struct IOSClipboard;
 
 impl Clipboard for IOSClipboard {
-    fn write_string(&mut self, text: impl Into<String>) {
+    fn write_string(&mut self, text: impl Into<String>) -> Result<(), Error> {
         unsafe {
             UIPasteboard::generalPasteboard().setString(Some(&NSString::from_str(&text.into())));
         }
+        Ok(())
     }
     fn read_string(&self) -> Option<String> {
         unsafe { UIPasteboard::generalPasteboard().string().map(|s| s.to_string()) }
@@ -161,14 +169,23 @@ impl Clipboard for IOSClipboard {
         pasteboard.dataForPasteboardType(&NSString::from_str("public.audio")).map(|d| d.to_vec())
     }
 
-    fn write_data(&mut self, data: Vec<u8>) {
-        UIPasteboard::generalPasteboard()
-            .setData_forPasteboardType(&NSData::from_vec(data), &NSString::from_str("public.data"));
+    fn write_data(&mut self, data: Vec<u8>) -> Result<(), Error> {
+        self.write_custom("public.data", data)
     }
 
     fn read_data(&self) -> Option<Vec<u8>> {
+        self.read_custom("public.data")
+    }
+
+    fn write_custom(&mut self, type_id: &str, data: Vec<u8>) -> Result<(), Error> {
+        UIPasteboard::generalPasteboard()
+            .setData_forPasteboardType(&NSData::from_vec(data), &NSString::from_str(type_id));
+        Ok(())
+    }
+
+    fn read_custom(&self, type_id: &str) -> Option<Vec<u8>> {
         let pasteboard = UIPasteboard::generalPasteboard();
-        pasteboard.dataForPasteboardType(&NSString::from_str("public.data")).map(|d| d.to_vec())
+        pasteboard.dataForPasteboardType(&NSString::from_str(type_id)).map(|d| d.to_vec())
     }
 }
 
@@ -193,13 +210,53 @@ pub struct Platform {
     _drag_and_drop_delegate: Retained<TahtiDragAndDropDelegate>,
     event_queue: Arc<Mutex<VecDeque<WindowEvent>>>,
     clipboard: IOSClipboard,
+    file_watchers: FileWatchers,
+    errors: PlatformErrors,
+    /// How much of the bottom of the view the on-screen keyboard covers, in points.
+    keyboard_height: Rc<Cell<f64>>,
+    keyboard_observer: Retained<ProtocolObject<dyn NSObjectProtocol>>,
+    last_insets: Margin,
+    text_field: NativeTextField,
+    edit_menu: EditMenu,
+}
+
+impl Drop for Platform {
+    fn drop(&mut self) {
+        unsafe { NSNotificationCenter::defaultCenter().removeObserver(&self.keyboard_observer) };
+    }
 }
 
 impl PlatformCommon for Platform {
     type Frame = Frame;
 
     fn next_window_event(&mut self) -> Option<WindowEvent> {
-        self.event_queue.lock().pop_front()
+        self.event_queue
+            .lock()
+            .pop_front()
+            .or_else(|| self.errors.next_event())
+            .or_else(|| self.file_watchers.next_event())
+            .or_else(|| {
+                // UIKit doesn't notify the view's owner about safe area changes, e.g. on rotation, so they're polled
+                let insets = self.safe_area_insets();
+                (insets != self.last_insets).then(|| {
+                    self.last_insets = insets;
+                    WindowEvent::InsetsChanged(insets)
+                })
+            })
+    }
+
+    fn safe_area_insets(&self) -> Margin {
+        let insets = self.view.safeAreaInsets();
+        Margin::new(
+            insets.left as f32,
+            insets.top as f32,
+            insets.right as f32,
+            insets.bottom.max(self.keyboard_height.get()) as f32,
+        )
+    }
+
+    fn watch_path(&mut self, path: PathBuf) -> Watcher {
+        self.file_watchers.watch(path)
     }
 
     fn get_scale_factor(&self) -> f32 {
@@ -219,10 +276,17 @@ impl PlatformCommon for Platform {
             if !url.starts_with("shareddocuments://") {
                 url = "shareddocuments://".to_owned() + &url;
             }
-            let mtm = MainThreadMarker::new().expect("must be on the main thread");
+            let Some(mtm) = MainThreadMarker::new() else {
+                self.errors.report(Error::Window("the file explorer can only be opened on the main thread".into()));
+                return;
+            };
+            let Some(url) = NSURL::URLWithString(&NSString::from_str(&url)) else {
+                self.errors.report(Error::Io(format!("{url} isn't a valid URL")));
+                return;
+            };
             unsafe {
                 UIApplication::sharedApplication(mtm).openURL_options_completionHandler(
-                    &NSURL::URLWithString(&NSString::from_str(&url)).unwrap(),
+                    &url,
                     &NSDictionary::new(),
                     None,
                 );
@@ -280,10 +344,13 @@ impl PlatformCommon for Platform {
         title: String,
         enter_text: String,
         value: String,
-        _input_type: InputType,
+        input_type: InputType,
         result: &Later<String>,
     ) {
-        let mtm = MainThreadMarker::new().expect("Not on main thread.");
+        let Some(mtm) = MainThreadMarker::new() else {
+            self.errors.report(Error::Window("prompts can only be opened on the main thread".into()));
+            return;
+        };
         let alert = UIAlertController::alertControllerWithTitle_message_preferredStyle(
             Some(&NSString::from_str(&title)),
             None,
@@ -294,6 +361,9 @@ impl PlatformCommon for Platform {
         unsafe {
             alert.addTextFieldWithConfigurationHandler(Some(&RcBlock::new(move |view: NonNull<UITextField>| {
                 view.as_ref().setText(Some(&NSString::from_str(&value)));
+                if let InputType::Password = input_type {
+                    view.as_ref().setSecureTextEntry(true);
+                }
                 // TODO: fix
                 // view.as_ref().setKeyboardType(match input_type {
                 //     InputType::Text => UIKeyboardType::Default,
@@ -331,6 +401,22 @@ impl PlatformCommon for Platform {
         }
     }
 
+    fn open_text_field(&mut self, options: TextFieldOptions) -> bool {
+        self.text_field.open(&self.view, options)
+    }
+
+    fn move_text_field(&mut self, bounds: Rect) {
+        self.text_field.set_bounds(bounds);
+    }
+
+    fn close_text_field(&mut self) {
+        self.text_field.close();
+    }
+
+    fn show_edit_menu(&mut self, options: EditMenuOptions) -> bool {
+        self.edit_menu.show(&self.view, options)
+    }
+
     fn new_frame(&mut self) -> Option<Frame> {
         let autoreleasepool = unsafe { ffi::objc_autoreleasePoolPush() };
         if let Some(drawable) = self.metal_layer.nextDrawable() {
@@ -381,14 +467,16 @@ impl PlatformCommon for Platform {
 }
 
 impl Platform {
-    pub fn new_from_window_handle(handle: *mut c_void) -> Self {
+    pub fn new_from_window_handle(handle: *mut c_void) -> Result<Self, Error> {
         let view: Retained<UIView> = Retained::from(unsafe { &*(handle as *mut UIView) });
-        let device = MTLCreateSystemDefaultDevice().expect("Could not create Metal device.");
+        let device =
+            MTLCreateSystemDefaultDevice().ok_or_else(|| Error::Graphics("no Metal device available".into()))?;
+        let mtm = MainThreadMarker::new()
+            .ok_or_else(|| Error::Window("the platform must be created on the main thread".into()))?;
 
         let event_queue = Arc::new(Mutex::new(VecDeque::new()));
 
         let drag_and_drop_delegate = {
-            let mtm = MainThreadMarker::new().unwrap();
             let delegate = TahtiDragAndDropDelegate::new(mtm, event_queue.clone());
             let instance = UIDropInteraction::alloc(mtm);
             let interaction = UIDropInteraction::initWithDelegate(
@@ -409,7 +497,38 @@ impl Platform {
             layer
         };
 
-        let command_queue = device.newCommandQueue().expect("Could not create Metal command queue");
+        let keyboard_height = Rc::new(Cell::new(0.0));
+        let keyboard_observer = {
+            let keyboard_height = keyboard_height.clone();
+            let view = view.clone();
+            let block = RcBlock::new(move |notification: NonNull<NSNotification>| {
+                let notification = unsafe { notification.as_ref() };
+                let Some(frame) = notification
+                    .userInfo()
+                    .and_then(|info| info.objectForKey(unsafe { UIKeyboardFrameEndUserInfoKey }))
+                    .and_then(|value| value.downcast::<NSValue>().ok())
+                    .map(|value| value.CGRectValue())
+                else {
+                    return;
+                };
+                // the frame is in screen coordinates, which match the window's when it fills the screen, and is below
+                // the view when the keyboard hides
+                let frame = view.convertRect_fromView(frame, None);
+                let bounds = view.bounds();
+                keyboard_height.set((bounds.origin.y + bounds.size.height - frame.origin.y).max(0.0));
+            });
+            unsafe {
+                NSNotificationCenter::defaultCenter().addObserverForName_object_queue_usingBlock(
+                    Some(UIKeyboardWillChangeFrameNotification),
+                    None,
+                    None,
+                    &block,
+                )
+            }
+        };
+
+        let command_queue =
+            device.newCommandQueue().ok_or_else(|| Error::Graphics("couldn't create a Metal command queue".into()))?;
 
         let backend = unsafe {
             mtl::BackendContext::new(
@@ -417,18 +536,62 @@ impl Platform {
                 Retained::<ProtocolObject<dyn MTLCommandQueue>>::as_ptr(&command_queue) as mtl::Handle,
             )
         };
-        Self {
+        let direct_context = direct_contexts::make_metal(&backend, None)
+            .ok_or_else(|| Error::Graphics("couldn't create a Skia Metal context".into()))?;
+        Ok(Self {
             event_queue,
             metal_layer,
             _drag_and_drop_delegate: drag_and_drop_delegate,
-            direct_context: direct_contexts::make_metal(&backend, None)
-                .expect("Could not create metal direct context."),
+            direct_context,
             command_queue,
-            document_picker_delegate: TahtiDocumentPickerDelegate::new(
-                MainThreadMarker::new().expect("must be on the main thread"),
-            ),
+            document_picker_delegate: TahtiDocumentPickerDelegate::new(mtm),
             view,
             clipboard: IOSClipboard,
+            file_watchers: Default::default(),
+            errors: Default::default(),
+            keyboard_height,
+            keyboard_observer,
+            last_insets: Margin::default(),
+            text_field: NativeTextField::new(mtm),
+            edit_menu: EditMenu::default(),
+        })
+    }
+
+    /// Forwards an Apple Pencil touch from the host view's `touches*` overrides along with its force and tilt.
+    /// Returns false for finger touches, which the host should keep handling itself.
+    pub fn handle_pencil_touch(&self, touch: &UITouch) -> bool {
+        if touch.r#type() != UITouchType::Pencil {
+            return false;
+        }
+
+        let id = PointerId::Pen(0);
+        let location = touch.locationInView(Some(&self.view));
+        let position = point(location.x as f32, location.y as f32);
+        let max_force = touch.maximumPossibleForce();
+        let pressure = if max_force > 0.0 {
+            (touch.force() / max_force) as f32
+        } else {
+            1.0
+        };
+
+        // altitude is the angle from the screen and azimuth the direction the pencil points to
+        let altitude = touch.altitudeAngle();
+        let azimuth = touch.azimuthAngleInView(Some(&self.view));
+        let tilt = point(
+            (azimuth.cos() / altitude.tan()).atan().to_degrees() as f32,
+            (azimuth.sin() / altitude.tan()).atan().to_degrees() as f32,
+        );
+
+        let mut queue = self.event_queue.lock();
+        queue.push_back(WindowEvent::PenInput { id, pressure, tilt, barrel_button: false });
+        let phase = touch.phase();
+        if phase == UITouchPhase::Began {
+            queue.push_back(WindowEvent::PointerDown { position, button: MouseButton::Left, id });
+        } else if phase == UITouchPhase::Moved {
+            queue.push_back(WindowEvent::PointerMove { position, id });
+        } else if phase == UITouchPhase::Ended || phase == UITouchPhase::Cancelled {
+            queue.push_back(WindowEvent::PointerUp { id });
         }
+        true
     }
 }
@@ -0,0 +1,132 @@
+use objc2::{
+    DefinedClass, MainThreadMarker, MainThreadOnly, define_class, msg_send, rc::Retained, runtime::ProtocolObject, sel,
+};
+use objc2_core_foundation::{CGPoint, CGRect, CGSize};
+use objc2_foundation::{NSObject, NSObjectProtocol, NSString};
+use objc2_ui_kit::{UIColor, UIControlEvents, UIFont, UITextBorderStyle, UITextField, UITextFieldDelegate, UIView};
+use std::cell::RefCell;
+
+use crate::{InputType, Later, Rect, TextFieldEvent, TextFieldOptions};
+
+pub struct Ivars {
+    events: RefCell<Option<Later<TextFieldEvent>>>,
+}
+
+define_class!(
+    #[unsafe(super = NSObject)]
+    #[thread_kind = MainThreadOnly]
+    #[name = "TahtiTextFieldDelegate"]
+    #[ivars = Ivars]
+    pub struct TahtiTextFieldDelegate;
+
+    impl TahtiTextFieldDelegate {
+        #[unsafe(method(textChanged:))]
+        fn text_changed(&self, field: &UITextField) {
+            self.send(TextFieldEvent::Changed(get_text(field)));
+        }
+    }
+
+    unsafe impl NSObjectProtocol for TahtiTextFieldDelegate {}
+
+    unsafe impl UITextFieldDelegate for TahtiTextFieldDelegate {
+        #[unsafe(method(textFieldShouldReturn:))]
+        fn should_return(&self, field: &UITextField) -> bool {
+            // the input decides whether to close the field, since an invalid value keeps it open
+            self.send(TextFieldEvent::Submitted(get_text(field)));
+            false
+        }
+
+        #[unsafe(method(textFieldDidEndEditing:))]
+        fn did_end_editing(&self, field: &UITextField) {
+            self.send(TextFieldEvent::Closed(get_text(field)));
+        }
+    }
+);
+
+impl TahtiTextFieldDelegate {
+    pub fn new(mtm: MainThreadMarker) -> Retained<Self> {
+        let this = Self::alloc(mtm).set_ivars(Ivars { events: RefCell::new(None) });
+        unsafe { msg_send![super(this), init] }
+    }
+
+    fn send(&self, event: TextFieldEvent) {
+        if let Some(events) = &*self.ivars().events.borrow() {
+            events.set(event);
+        }
+    }
+}
+
+fn get_text(field: &UITextField) -> String {
+    field.text().map(|text| text.to_string()).unwrap_or_default()
+}
+
+pub(super) fn to_cg_rect(bounds: Rect) -> CGRect {
+    CGRect::new(
+        CGPoint::new(bounds.left() as f64, bounds.top() as f64),
+        CGSize::new(bounds.width() as f64, bounds.height() as f64),
+    )
+}
+
+/// A borderless field with a clear background that is shown over the input being edited.
+pub struct NativeTextField {
+    field: Option<Retained<UITextField>>,
+    delegate: Retained<TahtiTextFieldDelegate>,
+}
+
+impl NativeTextField {
+    pub fn new(mtm: MainThreadMarker) -> Self {
+        Self { field: None, delegate: TahtiTextFieldDelegate::new(mtm) }
+    }
+
+    pub fn open(&mut self, view: &UIView, options: TextFieldOptions) -> bool {
+        let Some(mtm) = MainThreadMarker::new() else {
+            return false;
+        };
+        let delegate = &self.delegate;
+        let field = self.field.get_or_insert_with(|| {
+            let field = UITextField::new(mtm);
+            field.setDelegate(Some(ProtocolObject::from_ref(&**delegate)));
+            unsafe {
+                field.addTarget_action_forControlEvents(
+                    Some(delegate.as_ref()),
+                    sel!(textChanged:),
+                    UIControlEvents::EditingChanged,
+                );
+            }
+            field.setBorderStyle(UITextBorderStyle::None);
+            field.setBackgroundColor(Some(&UIColor::clearColor()));
+            view.addSubview(&field);
+            field
+        });
+
+        *delegate.ivars().events.borrow_mut() = Some(options.events);
+        let color = options.color;
+        field.setFrame(to_cg_rect(options.bounds));
+        field.setText(Some(&NSString::from_str(&options.value)));
+        field.setFont(Some(&UIFont::systemFontOfSize(options.font_size as f64)));
+        field.setTextColor(Some(&UIColor::colorWithRed_green_blue_alpha(
+            color.red() as f64,
+            color.green() as f64,
+            color.blue() as f64,
+            color.alpha() as f64,
+        )));
+        field.setSecureTextEntry(matches!(options.input_type, InputType::Password));
+        field.setHidden(false);
+        field.becomeFirstResponder()
+    }
+
+    pub fn set_bounds(&self, bounds: Rect) {
+        if let Some(field) = &self.field {
+            field.setFrame(to_cg_rect(bounds));
+        }
+    }
+
+    pub fn close(&self) {
+        // the input closed the field, so it doesn't need to hear that editing ended
+        self.delegate.ivars().events.borrow_mut().take();
+        if let Some(field) = &self.field {
+            field.resignFirstResponder();
+            field.setHidden(true);
+        }
+    }
+}
@@ -1,17 +1,25 @@
 use std::{ffi::c_void, path::PathBuf, sync::Arc};
 
 use block2::RcBlock;
-use objc2::{AllocAnyThread, MainThreadMarker, Message, ffi, rc::Retained, runtime::ProtocolObject};
+use objc2::{
+    AllocAnyThread, MainThreadMarker, Message, ffi,
+    rc::Retained,
+    runtime::{AnyObject, ProtocolObject},
+};
 use objc2_app_kit::{
     NSAlert, NSApplication, NSDraggingItem, NSModalResponse, NSModalResponseCancel, NSModalResponseContinue,
-    NSModalResponseOK, NSOpenPanel, NSPasteboard, NSPasteboardWriting, NSSavePanel, NSTextField, NSView, NSWorkspace,
+    NSModalResponseOK, NSOpenPanel, NSPasteboard, NSPasteboardWriting, NSSavePanel, NSSecureTextField, NSTextField,
+    NSView, NSWorkspace,
+};
+use objc2_core_foundation::{CFData, CFRetained, CGPoint, CGRect, CGSize};
+use objc2_core_graphics::{
+    CGBitmapInfo, CGColorRenderingIntent, CGColorSpace, CGDataProvider, CGImage, CGImageAlphaInfo, CGImageByteOrderInfo,
 };
-use objc2_core_foundation::{CGPoint, CGRect, CGSize};
 use objc2_foundation::{NSArray, NSData, NSFileManager, NSPoint, NSRect, NSSearchPathDirectory, NSString, NSURL};
 use objc2_metal::{
     MTLCommandBuffer, MTLCommandQueue, MTLCreateSystemDefaultDevice, MTLDevice, MTLDrawable, MTLPixelFormat, MTLTexture,
 };
-use objc2_quartz_core::{CAMetalDrawable, CAMetalLayer};
+use objc2_quartz_core::{CALayer, CAMetalDrawable, CAMetalLayer};
 use objc2_uniform_type_identifiers::UTType;
 use pallo_util::File;
 use skia_safe::{
@@ -20,25 +28,107 @@ use skia_safe::{
     scalar,
 };
 
-use super::{Clipboard, PlatformCommon};
+use super::{Clipboard, FileWatchers, PlatformCommon, PlatformErrors, Watcher, msaa::MsaaTarget};
 use crate::{
-    Canvas, FileSaveOptions, Later, WindowEvent,
+    Canvas, Error, FileSaveOptions, Later, Rect, WindowEvent,
     platform::{FileOpenOptions, InputType},
 };
 
 pub struct Platform {
-    metal_layer: Retained<CAMetalLayer>,
-    command_queue: Retained<ProtocolObject<dyn MTLCommandQueue>>,
-    direct_context: DirectContext,
+    renderer: Renderer,
     ns_view: Retained<NSView>,
     clipboard: MacOsClipboard,
+    file_watchers: FileWatchers,
+    errors: PlatformErrors,
 }
 
 unsafe impl Send for Platform {}
 
+enum Renderer {
+    Metal {
+        layer: Retained<CAMetalLayer>,
+        command_queue: Retained<ProtocolObject<dyn MTLCommandQueue>>,
+        direct_context: DirectContext,
+        msaa: MsaaTarget,
+    },
+    /// Draws on the CPU and shows each frame as the contents of a plain layer, for when Metal can't be set up.
+    Software { layer: Retained<CALayer>, surface: Option<Surface> },
+}
+
+impl Renderer {
+    fn new_metal(view: &NSView) -> Result<Self, Error> {
+        let device =
+            MTLCreateSystemDefaultDevice().ok_or_else(|| Error::Graphics("no Metal device available".into()))?;
+
+        let command_queue =
+            device.newCommandQueue().ok_or_else(|| Error::Graphics("couldn't create a Metal command queue".into()))?;
+
+        let backend = unsafe {
+            mtl::BackendContext::new(
+                Retained::<ProtocolObject<dyn MTLDevice>>::as_ptr(&device) as mtl::Handle,
+                Retained::<ProtocolObject<dyn MTLCommandQueue>>::as_ptr(&command_queue) as mtl::Handle,
+            )
+        };
+        let direct_context = direct_contexts::make_metal(&backend, None)
+            .ok_or_else(|| Error::Graphics("couldn't create a Skia Metal context".into()))?;
+
+        let layer = CAMetalLayer::new();
+        layer.setDevice(Some(&device));
+        layer.setPixelFormat(MTLPixelFormat::BGRA8Unorm);
+        layer.setPresentsWithTransaction(false);
+        layer.setFramebufferOnly(false);
+        attach_layer(view, &layer);
+
+        Ok(Self::Metal { layer, command_queue, direct_context, msaa: MsaaTarget::new(1) })
+    }
+
+    fn new_software(view: &NSView) -> Self {
+        let layer = CALayer::new();
+        attach_layer(view, &layer);
+        Self::Software { layer, surface: None }
+    }
+}
+
+fn attach_layer(view: &NSView, layer: &CALayer) {
+    if let Some(view_layer) = view.layer() {
+        view_layer.addSublayer(layer);
+    } else {
+        view.setWantsLayer(true);
+        view.setLayer(Some(layer));
+    }
+}
+
+/// Copies the pixels of a raster surface into an image that a layer can show.
+fn image_from_surface(surface: &mut Surface) -> Option<CFRetained<CGImage>> {
+    let pixmap = surface.peek_pixels()?;
+    let data = CFData::from_bytes(pixmap.bytes()?);
+    unsafe {
+        let provider = CGDataProvider::with_cf_data(Some(&data))?;
+        let color_space = CGColorSpace::new_device_rgb()?;
+        CGImage::new(
+            pixmap.width() as usize,
+            pixmap.height() as usize,
+            8,
+            32,
+            pixmap.row_bytes(),
+            Some(&color_space),
+            // N32 surfaces are BGRA with premultiplied alpha
+            CGBitmapInfo(CGImageAlphaInfo::PremultipliedFirst.0 | CGImageByteOrderInfo::Order32Little.0),
+            Some(&provider),
+            std::ptr::null(),
+            false,
+            CGColorRenderingIntent::RenderingIntentDefault,
+        )
+    }
+}
+
 pub struct Frame {
-    drawable: Retained<ProtocolObject<dyn CAMetalDrawable>>,
+    /// `None` when drawing in software.
+    drawable: Option<Retained<ProtocolObject<dyn CAMetalDrawable>>>,
     surface: Surface,
+    /// The drawable's surface when `surface` is the multisampled one that gets resolved into it.
+    msaa_target: Option<Surface>,
+    dirty_rects: Vec<Rect>,
     autoreleasepool: *mut c_void,
 }
 
@@ -46,27 +136,48 @@ impl super::Frame for Frame {
     fn canvas(&mut self) -> Canvas<'_> {
         Canvas::new(self.surface.canvas())
     }
+
+    fn set_dirty_rects(&mut self, rects: Vec<Rect>) {
+        self.dirty_rects = rects;
+    }
 }
 
 pub struct MacOsClipboard;
 
 impl Clipboard for MacOsClipboard {
-    fn write_string(&mut self, text: impl Into<String>) {
+    fn write_string(&mut self, text: impl Into<String>) -> Result<(), Error> {
         let pasteboard = NSPasteboard::generalPasteboard();
         pasteboard.clearContents();
-        pasteboard.setString_forType(&NSString::from_str(&text.into()), &NSString::from_str("public.utf8-plain-text"));
+        let text = NSString::from_str(&text.into());
+        if pasteboard.setString_forType(&text, &NSString::from_str("public.utf8-plain-text")) {
+            Ok(())
+        } else {
+            Err(Error::Clipboard("the pasteboard rejected the text".into()))
+        }
+    }
+
+    fn write_data(&mut self, data: Vec<u8>) -> Result<(), Error> {
+        self.write_custom("public.data", data)
+    }
+
+    fn read_data(&self) -> Option<Vec<u8>> {
+        self.read_custom("public.data")
     }
 
-    fn write_data(&mut self, data: Vec<u8>) {
+    fn write_custom(&mut self, type_id: &str, data: Vec<u8>) -> Result<(), Error> {
         let pasteboard = NSPasteboard::generalPasteboard();
         pasteboard.clearContents();
-        pasteboard.setData_forType(Some(&NSData::from_vec(data)), &NSString::from_str("public.data"));
+        if pasteboard.setData_forType(Some(&NSData::from_vec(data)), &NSString::from_str(type_id)) {
+            Ok(())
+        } else {
+            Err(Error::Clipboard(format!("the pasteboard rejected data of type {type_id}")))
+        }
     }
 
-    fn read_data(&self) -> Option<Vec<u8>> {
+    fn read_custom(&self, type_id: &str) -> Option<Vec<u8>> {
         let pasteboard = NSPasteboard::generalPasteboard();
         if let Some(types) = pasteboard.types() {
-            let t = NSString::from_str("public.data");
+            let t = NSString::from_str(type_id);
             if types.containsObject(&t)
                 && let Some(data) = pasteboard.dataForType(&t)
             {
@@ -171,31 +282,41 @@ impl PlatformCommon for Platform {
     }
 
     fn file_save_dialog(&self, opts: FileSaveOptions) {
-        if let Some(mtm) = MainThreadMarker::new() {
-            let panel = NSSavePanel::new(mtm);
-            panel.setAllowedContentTypes(&NSArray::from_retained_slice(&[UTType::typeWithFilenameExtension(
-                &NSString::from_str(&opts.extension),
-            )
-            .expect("Can't convert extension to UTType.")]));
-            panel.setNameFieldStringValue(&NSString::from_str(&opts.filename));
-            let result_panel = panel.clone();
-            if let Some(window) = self.ns_view.window() {
-                panel.beginSheetModalForWindow_completionHandler(
-                    &window,
-                    &RcBlock::new(move |response| {
-                        if response == NSModalResponseOK
-                            && let Some(url) = result_panel.URL()
-                            && let Some(path) = url.path()
-                        {
-                            let path = PathBuf::from(path.to_string());
-                            let _ = std::fs::write(&path, &*opts.data);
-                            if let Some(result) = &opts.result {
-                                result.set(path);
+        let Some(mtm) = MainThreadMarker::new() else {
+            self.errors.report(Error::Window("the save dialog can only be opened on the main thread".into()));
+            return;
+        };
+        let Some(content_type) = UTType::typeWithFilenameExtension(&NSString::from_str(&opts.extension)) else {
+            self.errors.report(Error::Dialog(format!("no content type for the extension {}", opts.extension)));
+            return;
+        };
+        let panel = NSSavePanel::new(mtm);
+        panel.setAllowedContentTypes(&NSArray::from_retained_slice(&[content_type]));
+        panel.setNameFieldStringValue(&NSString::from_str(&opts.filename));
+        let result_panel = panel.clone();
+        let errors = self.errors.sender();
+        if let Some(window) = self.ns_view.window() {
+            panel.beginSheetModalForWindow_completionHandler(
+                &window,
+                &RcBlock::new(move |response| {
+                    if response == NSModalResponseOK
+                        && let Some(url) = result_panel.URL()
+                        && let Some(path) = url.path()
+                    {
+                        let path = PathBuf::from(path.to_string());
+                        match std::fs::write(&path, &*opts.data) {
+                            Ok(()) => {
+                                if let Some(result) = &opts.result {
+                                    result.set(path);
+                                }
+                            }
+                            Err(error) => {
+                                let _ = errors.send(error.into());
                             }
                         }
-                    }),
-                );
-            }
+                    }
+                }),
+            );
         }
     }
 
@@ -239,13 +360,34 @@ impl PlatformCommon for Platform {
 
     fn set_view_size(&mut self, size: (u32, u32)) {
         let scale_factor = self.get_scale_factor() as f64;
-        self.metal_layer.setDrawableSize(CGSize::new(scale_factor * size.0 as f64, scale_factor * size.1 as f64));
-        self.metal_layer.setBounds(CGRect::new(CGPoint::ZERO, CGSize::new(size.0 as f64, size.1 as f64)));
-        self.metal_layer.setPosition(CGPoint::new(size.0 as f64 * 0.5, size.1 as f64 * 0.5));
+        let pixel_size = CGSize::new(scale_factor * size.0 as f64, scale_factor * size.1 as f64);
+        let layer: &CALayer = match &mut self.renderer {
+            Renderer::Metal { layer, .. } => {
+                layer.setDrawableSize(pixel_size);
+                layer
+            }
+            Renderer::Software { layer, surface } => {
+                layer.setContentsScale(scale_factor);
+                *surface = skia_safe::surfaces::raster_n32_premul((pixel_size.width as i32, pixel_size.height as i32));
+                layer
+            }
+        };
+        layer.setBounds(CGRect::new(CGPoint::ZERO, CGSize::new(size.0 as f64, size.1 as f64)));
+        layer.setPosition(CGPoint::new(size.0 as f64 * 0.5, size.1 as f64 * 0.5));
+    }
+
+    fn set_sample_count(&mut self, count: usize) {
+        if let Renderer::Metal { msaa, .. } = &mut self.renderer {
+            msaa.set_sample_count(count);
+        }
     }
 
     fn next_window_event(&mut self) -> Option<WindowEvent> {
-        None
+        self.errors.next_event().or_else(|| self.file_watchers.next_event())
+    }
+
+    fn watch_path(&mut self, path: PathBuf) -> Watcher {
+        self.file_watchers.watch(path)
     }
 
     fn clipboard(&mut self) -> &mut impl Clipboard {
@@ -253,18 +395,33 @@ impl PlatformCommon for Platform {
     }
 
     fn open_path_in_file_explorer(&self, path: PathBuf) {
-        let _ = std::process::Command::new("open").arg("-R").arg(path.into_os_string()).spawn();
+        if let Err(error) = std::process::Command::new("open").arg("-R").arg(path.into_os_string()).spawn() {
+            self.errors.report(error.into());
+        }
     }
 
-    fn open_prompt(&self, title: String, enter_text: String, value: String, _: InputType, result: &Later<String>) {
-        let mtm = MainThreadMarker::new().expect("Not on main thread.");
+    fn open_prompt(
+        &self,
+        title: String,
+        enter_text: String,
+        value: String,
+        input_type: InputType,
+        result: &Later<String>,
+    ) {
+        let Some(mtm) = MainThreadMarker::new() else {
+            self.errors.report(Error::Window("prompts can only be opened on the main thread".into()));
+            return;
+        };
 
         let alert = NSAlert::new(mtm);
         alert.setMessageText(&NSString::from_str(&title));
         alert.addButtonWithTitle(&NSString::from_str(&enter_text));
         alert.addButtonWithTitle(&NSString::from_str("Cancel"));
 
-        let text_field = NSTextField::new(mtm);
+        let text_field = match input_type {
+            InputType::Password => Retained::into_super(NSSecureTextField::new(mtm)),
+            _ => NSTextField::new(mtm),
+        };
         text_field.setFrame(NSRect::new(CGPoint::new(0.0, 0.0), CGSize::new(200.0, 22.0)));
         text_field.setStringValue(&NSString::from_str(&value));
 
@@ -292,9 +449,21 @@ impl PlatformCommon for Platform {
 
     fn new_frame(&mut self) -> Option<Frame> {
         let autoreleasepool = unsafe { ffi::objc_autoreleasePoolPush() };
-        if let Some(drawable) = self.metal_layer.nextDrawable() {
+        let (layer, direct_context, msaa) = match &mut self.renderer {
+            Renderer::Metal { layer, direct_context, msaa, .. } => (layer, direct_context, msaa),
+            Renderer::Software { surface, .. } => {
+                return surface.take().map(|surface| Frame {
+                    autoreleasepool,
+                    surface,
+                    msaa_target: None,
+                    dirty_rects: Vec::new(),
+                    drawable: None,
+                });
+            }
+        };
+        if let Some(drawable) = layer.nextDrawable() {
             let drawable_size = {
-                let size = self.metal_layer.drawableSize();
+                let size = layer.drawableSize();
                 Size::new(size.width as scalar, size.height as scalar)
             };
 
@@ -310,27 +479,60 @@ impl PlatformCommon for Platform {
             );
 
             gpu::surfaces::wrap_backend_render_target(
-                &mut self.direct_context,
+                direct_context,
                 &backend_render_target,
                 SurfaceOrigin::TopLeft,
                 ColorType::BGRA8888,
                 None,
                 None,
             )
-            .map(|surface| Frame { autoreleasepool, surface, drawable })
+            .map(|surface| {
+                let (surface, msaa_target) = match msaa.surface(direct_context, &surface, ColorType::BGRA8888) {
+                    Some(msaa_surface) => (msaa_surface, Some(surface)),
+                    None => (surface, None),
+                };
+                Frame { autoreleasepool, surface, msaa_target, dirty_rects: Vec::new(), drawable: Some(drawable) }
+            })
         } else {
             None
         }
     }
 
-    fn end_frame(&mut self, frame: Frame) {
-        self.direct_context.flush_and_submit();
+    fn end_frame(&mut self, mut frame: Frame) {
+        match &mut self.renderer {
+            Renderer::Metal { layer, command_queue, direct_context, .. } => {
+                if let Some(mut target) = frame.msaa_target {
+                    MsaaTarget::resolve(&mut frame.surface, &mut target);
+                }
+                direct_context.flush_and_submit();
 
-        drop(frame.surface);
-        if let Some(command_buffer) = self.command_queue.commandBuffer() {
-            command_buffer.presentDrawable(&ProtocolObject::<dyn MTLDrawable>::from_retained(frame.drawable));
-            command_buffer.commit();
-            self.metal_layer.setNeedsDisplay();
+                drop(frame.surface);
+                if let Some(drawable) = frame.drawable
+                    && let Some(command_buffer) = command_queue.commandBuffer()
+                {
+                    command_buffer.presentDrawable(&ProtocolObject::<dyn MTLDrawable>::from_retained(drawable));
+                    command_buffer.commit();
+                    if frame.dirty_rects.is_empty() {
+                        layer.setNeedsDisplay();
+                    } else {
+                        // the layer is measured in points rather than pixels
+                        let scale = layer.contentsScale();
+                        for rect in &frame.dirty_rects {
+                            layer.setNeedsDisplayInRect(CGRect::new(
+                                CGPoint::new(rect.left() as f64 / scale, rect.top() as f64 / scale),
+                                CGSize::new(rect.width() as f64 / scale, rect.height() as f64 / scale),
+                            ));
+                        }
+                    }
+                }
+            }
+            Renderer::Software { layer, surface } => {
+                if let Some(image) = image_from_surface(&mut frame.surface) {
+                    unsafe { layer.setContents(Some(&*(CFRetained::as_ptr(&image).as_ptr() as *const AnyObject))) };
+                }
+                // the view may have been resized during the frame, in which case there's a new surface already
+                surface.get_or_insert(frame.surface);
+            }
         }
 
         unsafe {
@@ -341,42 +543,15 @@ impl PlatformCommon for Platform {
 
 #[allow(unused)]
 impl Platform {
-    pub fn new_from_window_handle(handle: *mut c_void) -> Self {
+    /// Creates a platform drawing into the `NSView` at `handle` with Metal. If Metal can't be set up, drawing falls
+    /// back to software and the error is sent as `WindowEvent::PlatformError`.
+    pub fn new_from_window_handle(handle: *mut c_void) -> Result<Self, Error> {
         let view: Retained<NSView> = Retained::from(unsafe { &*(handle as *mut NSView) });
-        let device = MTLCreateSystemDefaultDevice().expect("Unsable to create system Metal device.");
-
-        let metal_layer = {
-            let layer = CAMetalLayer::new();
-            layer.setDevice(Some(&device));
-            layer.setPixelFormat(MTLPixelFormat::BGRA8Unorm);
-            layer.setPresentsWithTransaction(false);
-            layer.setFramebufferOnly(false);
-
-            if let Some(view_layer) = view.layer() {
-                view_layer.addSublayer(&layer);
-            } else {
-                view.setWantsLayer(true);
-                view.setLayer(Some(&layer));
-            }
-
-            layer
-        };
-
-        let command_queue = device.newCommandQueue().expect("Unable to create Metal command queue.");
-
-        let backend = unsafe {
-            mtl::BackendContext::new(
-                Retained::<ProtocolObject<dyn MTLDevice>>::as_ptr(&device) as mtl::Handle,
-                Retained::<ProtocolObject<dyn MTLCommandQueue>>::as_ptr(&command_queue) as mtl::Handle,
-            )
-        };
-        Self {
-            metal_layer,
-            direct_context: direct_contexts::make_metal(&backend, None)
-                .expect("Unable to create Metal direct context."),
-            command_queue,
-            ns_view: view,
-            clipboard: MacOsClipboard,
-        }
+        let errors = PlatformErrors::default();
+        let renderer = Renderer::new_metal(&view).unwrap_or_else(|error| {
+            errors.report(error);
+            Renderer::new_software(&view)
+        });
+        Ok(Self { renderer, ns_view: view, clipboard: MacOsClipboard, file_watchers: Default::default(), errors })
     }
 }
@@ -1,25 +1,50 @@
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+mod display_link;
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+mod msaa;
 #[cfg_attr(target_os = "macos", path = "macos.rs")]
 #[cfg_attr(target_os = "ios", path = "ios/mod.rs")]
 #[cfg_attr(target_os = "windows", path = "windows.rs")]
 #[cfg_attr(target_family = "wasm", path = "web.rs")]
 pub mod platform;
+#[cfg(target_os = "windows")]
+mod swap_chain;
+#[cfg(all(target_os = "windows", feature = "vulkan"))]
+mod vulkan;
 
-use crate::{Canvas, Later, WindowEvent};
+use crate::{Canvas, Color, Error, Later, Margin, Rect, WindowEvent};
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub use display_link::*;
 use pallo_util::File;
 pub use platform::*;
 use std::{path::PathBuf, sync::Arc};
 
+#[cfg(not(target_family = "wasm"))]
+use std::sync::mpsc::{Receiver, Sender, channel};
+
 #[derive(Copy, Clone)]
 pub enum InputType {
     Text,
     Number,
+    /// Text that is hidden while it's entered.
+    Password,
 }
 
 pub trait Clipboard {
-    fn write_string(&mut self, text: impl Into<String>);
-    fn write_data(&mut self, data: Vec<u8>);
+    fn write_string(&mut self, text: impl Into<String>) -> Result<(), Error>;
+    fn write_data(&mut self, data: Vec<u8>) -> Result<(), Error>;
     fn read_data(&self) -> Option<Vec<u8>>;
+    /// Writes `data` under a platform type, i.e. a UTI on Apple platforms or a registered format name on Windows,
+    /// so that other applications using the same type can read it.
+    fn write_custom(&mut self, type_id: &str, data: Vec<u8>) -> Result<(), Error>;
+    fn read_custom(&self, type_id: &str) -> Option<Vec<u8>>;
     fn read_string(&self) -> Option<String>;
+    /// Reads the clipboard text into `result`, for platforms where reading can't be done synchronously.
+    fn read_string_async(&self, result: &Later<String>) {
+        if let Some(text) = self.read_string() {
+            result.set(text);
+        }
+    }
     fn read_paths(&self) -> Option<Vec<PathBuf>>;
     fn read_audio(&self) -> Option<Vec<u8>>;
 }
@@ -29,6 +54,9 @@ pub trait Frame {
     fn canvas(&mut self) -> Canvas;
     #[cfg(not(target_family = "wasm"))]
     fn canvas(&mut self) -> Canvas<'_>;
+    /// Limits presenting the frame to `rects`, in pixels, when nothing outside them changed since the last frame, so
+    /// that the compositor has less to do. Everything is presented if the platform can't present partially.
+    fn set_dirty_rects(&mut self, _rects: Vec<Rect>) {}
 }
 
 pub trait PlatformCommon {
@@ -40,7 +68,17 @@ pub trait PlatformCommon {
     fn start_drag(&self, path: PathBuf);
     fn get_scale_factor(&self) -> f32;
     fn set_view_size(&mut self, size: (u32, u32));
+    /// Draws frames with `count` samples per pixel to smooth the edges of paths more than antialiasing does by itself.
+    /// Lower counts are used if the GPU doesn't support it, and 1 turns multisampling off.
+    fn set_sample_count(&mut self, _count: usize) {}
+    /// How much of each edge of the view is covered by notches, rounded corners, system bars or the on-screen
+    /// keyboard, in the same units as `WindowEvent::Resized`. Changes are sent as `WindowEvent::InsetsChanged`.
+    fn safe_area_insets(&self) -> Margin {
+        Margin::default()
+    }
     fn next_window_event(&mut self) -> Option<WindowEvent>;
+    /// Sends `WindowEvent::FileChanged` when anything in `path` changes, until the returned watcher is dropped.
+    fn watch_path(&mut self, path: PathBuf) -> Watcher;
     fn clipboard(&mut self) -> &mut impl Clipboard;
     fn documents_folder_path() -> Option<PathBuf>;
     fn open_prompt(
@@ -51,10 +89,101 @@ pub trait PlatformCommon {
         input_type: InputType,
         result: &Later<String>,
     );
+    /// Shows a native text field over `options.bounds`, so that editing gets the platform's autocorrect, selection
+    /// handles and keyboards. Only one field is open at a time. Returns false if the platform has no such field.
+    fn open_text_field(&mut self, _options: TextFieldOptions) -> bool {
+        false
+    }
+    /// Moves the field opened with `open_text_field`, e.g. when the component it edits scrolls.
+    fn move_text_field(&mut self, _bounds: Rect) {}
+    fn close_text_field(&mut self) {}
+    /// Shows the platform's own edit menu for a text input, pointing at `options.bounds`. Returns false if the
+    /// platform has none, in which case the input shows a `PopupMenu` instead.
+    fn show_edit_menu(&mut self, _options: EditMenuOptions) -> bool {
+        false
+    }
     fn new_frame(&mut self) -> Option<Self::Frame>;
     fn end_frame(&mut self, frame: Self::Frame);
 }
 
+pub struct Watcher {
+    #[cfg(not(target_family = "wasm"))]
+    _watcher: Option<notify::RecommendedWatcher>,
+}
+
+/// Forwards changes from the background threads of file watchers to the window events of a platform.
+#[cfg(not(target_family = "wasm"))]
+pub(crate) struct FileWatchers {
+    sender: Sender<PathBuf>,
+    receiver: Receiver<PathBuf>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Default for FileWatchers {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        Self { sender, receiver }
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl FileWatchers {
+    pub(crate) fn watch(&self, path: PathBuf) -> Watcher {
+        use notify::Watcher as _;
+
+        let sender = self.sender.clone();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event
+                && !event.kind.is_access()
+            {
+                for path in event.paths {
+                    let _ = sender.send(path);
+                }
+            }
+        })
+        .and_then(|mut watcher| {
+            watcher.watch(&path, notify::RecursiveMode::Recursive)?;
+            Ok(watcher)
+        });
+        Watcher { _watcher: watcher.ok() }
+    }
+
+    pub(crate) fn next_event(&self) -> Option<WindowEvent> {
+        self.receiver.try_recv().ok().map(WindowEvent::FileChanged)
+    }
+}
+
+/// Collects errors from work that finishes outside the call that started it, like dialogs, and hands them out as
+/// window events.
+#[cfg(not(target_family = "wasm"))]
+pub(crate) struct PlatformErrors {
+    sender: Sender<Error>,
+    receiver: Receiver<Error>,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Default for PlatformErrors {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        Self { sender, receiver }
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl PlatformErrors {
+    pub(crate) fn sender(&self) -> Sender<Error> {
+        self.sender.clone()
+    }
+
+    pub(crate) fn report(&self, error: Error) {
+        let _ = self.sender.send(error);
+    }
+
+    pub(crate) fn next_event(&self) -> Option<WindowEvent> {
+        self.receiver.try_recv().ok().map(WindowEvent::PlatformError)
+    }
+}
+
 pub struct FileOpenOptions {
     pub filetype_desc: String,
     pub extensions: Vec<String>,
@@ -64,6 +193,57 @@ pub struct FileOpenOptions {
     pub result: Later<Vec<File>>,
 }
 
+pub struct TextFieldOptions {
+    /// Where the field goes, in the same units as `WindowEvent::Resized`.
+    pub bounds: Rect,
+    pub value: String,
+    pub input_type: InputType,
+    pub font_size: f32,
+    pub color: Color,
+    /// Holds the latest event. Each one carries the whole text, so it doesn't matter if the ones before it are missed.
+    pub events: Later<TextFieldEvent>,
+}
+
+#[derive(Clone, Debug)]
+pub enum TextFieldEvent {
+    Changed(String),
+    /// Return was pressed.
+    Submitted(String),
+    /// The field lost focus, e.g. because the keyboard was dismissed.
+    Closed(String),
+}
+
+/// The standard actions of a text input's edit menu.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EditAction {
+    Cut,
+    Copy,
+    Paste,
+    SelectAll,
+}
+
+impl EditAction {
+    pub const ALL: [Self; 4] = [Self::Cut, Self::Copy, Self::Paste, Self::SelectAll];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Cut => "Cut",
+            Self::Copy => "Copy",
+            Self::Paste => "Paste",
+            Self::SelectAll => "Select All",
+        }
+    }
+}
+
+pub struct EditMenuOptions {
+    /// What the menu points at, in the same units as `WindowEvent::Resized`.
+    pub bounds: Rect,
+    /// The actions that can be performed right now. The others are left out of the menu.
+    pub actions: Vec<EditAction>,
+    /// Holds the chosen action.
+    pub events: Later<EditAction>,
+}
+
 pub struct FileSaveOptions {
     pub filename: String,
     pub filetype_desc: String,
@@ -0,0 +1,73 @@
+use skia_safe::{
+    AlphaType, BlendMode, ColorType, ImageInfo, Paint, SamplingOptions, Surface,
+    gpu::{self, Budgeted, DirectContext, SurfaceOrigin},
+};
+
+/// An offscreen surface that frames are drawn into with multisampling when the surfaces that get presented can't be
+/// multisampled themselves, as is the case with swap chains. It's resolved into the presented surface at the end of
+/// each frame.
+pub(crate) struct MsaaTarget {
+    requested_sample_count: usize,
+    surface: Option<Surface>,
+}
+
+impl MsaaTarget {
+    pub(crate) fn new(sample_count: usize) -> Self {
+        Self { requested_sample_count: sample_count, surface: None }
+    }
+
+    pub(crate) fn set_sample_count(&mut self, sample_count: usize) {
+        if sample_count != self.requested_sample_count {
+            self.requested_sample_count = sample_count;
+            self.surface = None;
+        }
+    }
+
+    /// A surface of the same size and color type as `target` to draw into instead, or `None` if multisampling is off
+    /// or the GPU doesn't support it. Lower sample counts are tried when the requested one isn't supported.
+    pub(crate) fn surface(
+        &mut self,
+        context: &mut DirectContext,
+        target: &Surface,
+        color_type: ColorType,
+    ) -> Option<Surface> {
+        if self.requested_sample_count <= 1 {
+            return None;
+        }
+        if let Some(surface) = &self.surface
+            && surface.width() == target.width()
+            && surface.height() == target.height()
+        {
+            return Some(surface.clone());
+        }
+
+        let info = ImageInfo::new((target.width(), target.height()), color_type, AlphaType::Premul, None);
+        let mut sample_count =
+            self.requested_sample_count.min(context.max_surface_sample_count_for_color_type(color_type));
+        self.surface = None;
+        while sample_count > 1 {
+            self.surface = gpu::surfaces::render_target(
+                context,
+                Budgeted::Yes,
+                &info,
+                sample_count,
+                SurfaceOrigin::TopLeft,
+                None,
+                false,
+                None,
+            );
+            if self.surface.is_some() {
+                break;
+            }
+            sample_count /= 2;
+        }
+        self.surface.clone()
+    }
+
+    /// Draws the frame drawn into `surface` into `target`, which resolves the samples.
+    pub(crate) fn resolve(surface: &mut Surface, target: &mut Surface) {
+        let mut paint = Paint::default();
+        paint.set_blend_mode(BlendMode::Src);
+        surface.draw(target.canvas(), (0, 0), SamplingOptions::default(), Some(&paint));
+    }
+}
@@ -0,0 +1,22 @@
+use skia_safe::Surface;
+
+use crate::{Error, IntPoint, Rect};
+
+/// The surfaces that frames are drawn into and the waiting and presenting around them, which depends on the graphics
+/// API that the platform draws with.
+pub(crate) trait SwapChain {
+    /// Recreates the surfaces for a view of `size` pixels.
+    fn resize(&mut self, size: IntPoint) -> Result<(), Error>;
+
+    /// Waits until the next surface can be drawn into and returns it along with its index, or `None` if there's no
+    /// surface to draw into, e.g. while the window is minimized.
+    fn acquire(&mut self) -> Option<(Surface, usize)>;
+
+    /// Takes back the surface returned by `acquire` once the frame has been drawn into it and shows it. Only
+    /// `dirty_rects` need to be updated on screen unless they're empty.
+    fn present(&mut self, surface: Surface, index: usize, dirty_rects: &[Rect]);
+
+    /// Draws into a multisampled surface that's resolved into the presented one, see
+    /// `PlatformCommon::set_sample_count`.
+    fn set_sample_count(&mut self, _count: usize) {}
+}
@@ -0,0 +1,374 @@
+use std::{ffi::c_void, mem::ManuallyDrop, ptr};
+
+use ash::{
+    khr,
+    vk::{self, Handle},
+};
+use skia_safe::{
+    ColorType, Surface,
+    gpu::{
+        DirectContext, FlushInfo, SurfaceOrigin, SyncCpu, backend_render_targets, direct_contexts,
+        surfaces::wrap_backend_render_target, vk as skia_vk,
+    },
+    surface::BackendSurfaceAccess,
+};
+use windows::Win32::{Foundation::HWND, System::LibraryLoader::GetModuleHandleW};
+
+use super::{msaa::MsaaTarget, swap_chain::SwapChain};
+use crate::{Error, IntPoint, Rect};
+
+fn graphics_error(context: &'static str) -> impl FnOnce(vk::Result) -> Error {
+    move |error| Error::Graphics(format!("{context}: {error}"))
+}
+
+/// Draws with Vulkan into a swap chain of a window's surface.
+///
+/// Frames are kept simple rather than pipelined: acquiring an image waits on a fence until it can be drawn into, and
+/// presenting waits for Skia's work to finish, so no semaphores need to be handed between Skia and the swap chain.
+pub(crate) struct VulkanSwapChain {
+    /// Dropped by hand before the device it was created on.
+    gr_context: ManuallyDrop<DirectContext>,
+    _entry: ash::Entry,
+    instance: ash::Instance,
+    physical_device: vk::PhysicalDevice,
+    device: ash::Device,
+    queue: vk::Queue,
+    surface_loader: khr::surface::Instance,
+    surface: vk::SurfaceKHR,
+    swapchain_loader: khr::swapchain::Device,
+    swapchain: vk::SwapchainKHR,
+    format: vk::SurfaceFormatKHR,
+    surfaces: Vec<Option<Surface>>,
+    msaa: MsaaTarget,
+    /// Signalled once the image acquired for the next frame can be drawn into.
+    acquire_fence: vk::Fence,
+    size: IntPoint,
+    /// Set when the swap chain no longer matches the surface, e.g. after the window was resized.
+    out_of_date: bool,
+}
+
+impl VulkanSwapChain {
+    pub(crate) fn new(hwnd: HWND, size: IntPoint) -> Result<Self, Error> {
+        let entry = unsafe { ash::Entry::load() }
+            .map_err(|error| Error::Graphics(format!("Failed to load the Vulkan library: {error}")))?;
+
+        let app_info = vk::ApplicationInfo::default().api_version(vk::API_VERSION_1_1);
+        let extensions = [khr::surface::NAME.as_ptr(), khr::win32_surface::NAME.as_ptr()];
+        let instance_info =
+            vk::InstanceCreateInfo::default().application_info(&app_info).enabled_extension_names(&extensions);
+        let instance = unsafe { entry.create_instance(&instance_info, None) }
+            .map_err(graphics_error("Failed to create the Vulkan instance"))?;
+
+        let hinstance = unsafe { GetModuleHandleW(None) }
+            .map_err(|error| Error::Graphics(format!("Failed to get the module handle: {error}")))?;
+        let surface_info =
+            vk::Win32SurfaceCreateInfoKHR::default().hinstance(hinstance.0 as isize).hwnd(hwnd.0 as isize);
+        let surface =
+            unsafe { khr::win32_surface::Instance::new(&entry, &instance).create_win32_surface(&surface_info, None) }
+                .map_err(graphics_error("Failed to create the Vulkan surface"))?;
+        let surface_loader = khr::surface::Instance::new(&entry, &instance);
+
+        let (physical_device, queue_family) = find_physical_device(&instance, &surface_loader, surface)?;
+
+        let priorities = [1.0];
+        let queue_infos =
+            [vk::DeviceQueueCreateInfo::default().queue_family_index(queue_family).queue_priorities(&priorities)];
+        let device_extensions = [khr::swapchain::NAME.as_ptr()];
+        let device_info = vk::DeviceCreateInfo::default()
+            .queue_create_infos(&queue_infos)
+            .enabled_extension_names(&device_extensions);
+        let device = unsafe { instance.create_device(physical_device, &device_info, None) }
+            .map_err(graphics_error("Failed to create the Vulkan device"))?;
+        let queue = unsafe { device.get_device_queue(queue_family, 0) };
+
+        let gr_context = {
+            let get_proc = |of| unsafe {
+                match of {
+                    skia_vk::GetProcOf::Instance(raw_instance, name) => {
+                        entry.get_instance_proc_addr(vk::Instance::from_raw(raw_instance as _), name)
+                    }
+                    skia_vk::GetProcOf::Device(raw_device, name) => {
+                        (instance.fp_v1_0().get_device_proc_addr)(vk::Device::from_raw(raw_device as _), name)
+                    }
+                }
+                .map(|function| function as *const c_void)
+                .unwrap_or(ptr::null())
+            };
+            let backend_context = unsafe {
+                skia_vk::BackendContext::new(
+                    instance.handle().as_raw() as _,
+                    physical_device.as_raw() as _,
+                    device.handle().as_raw() as _,
+                    (queue.as_raw() as _, queue_family as usize),
+                    &get_proc,
+                )
+            };
+            direct_contexts::make_vulkan(&backend_context, None)
+                .ok_or_else(|| Error::Graphics("Failed to create Skia context".into()))?
+        };
+
+        let acquire_fence = unsafe { device.create_fence(&vk::FenceCreateInfo::default(), None) }
+            .map_err(graphics_error("Failed to create fence"))?;
+        let swapchain_loader = khr::swapchain::Device::new(&instance, &device);
+        let format = choose_format(&surface_loader, physical_device, surface)?;
+
+        let mut swap_chain = Self {
+            gr_context: ManuallyDrop::new(gr_context),
+            _entry: entry,
+            instance,
+            physical_device,
+            device,
+            queue,
+            surface_loader,
+            surface,
+            swapchain_loader,
+            swapchain: vk::SwapchainKHR::null(),
+            format,
+            surfaces: Vec::new(),
+            msaa: MsaaTarget::new(1),
+            acquire_fence,
+            size,
+            out_of_date: false,
+        };
+        swap_chain.setup_swapchain()?;
+        Ok(swap_chain)
+    }
+
+    /// (Re)creates the swap chain and the surfaces wrapping its images for the current size.
+    fn setup_swapchain(&mut self) -> Result<(), Error> {
+        let capabilities =
+            unsafe { self.surface_loader.get_physical_device_surface_capabilities(self.physical_device, self.surface) }
+                .map_err(graphics_error("Failed to get the surface capabilities"))?;
+
+        // the extent is decided by the window unless the surface leaves it up to the swap chain
+        let extent = if capabilities.current_extent.width != u32::MAX {
+            capabilities.current_extent
+        } else {
+            vk::Extent2D {
+                width: (self.size.x as u32)
+                    .clamp(capabilities.min_image_extent.width, capabilities.max_image_extent.width),
+                height: (self.size.y as u32)
+                    .clamp(capabilities.min_image_extent.height, capabilities.max_image_extent.height),
+            }
+        };
+        self.surfaces.clear();
+        if extent.width == 0 || extent.height == 0 {
+            // minimized, there's nothing to draw into until the window is shown again
+            return Ok(());
+        }
+
+        let mut image_count = capabilities.min_image_count + 1;
+        if capabilities.max_image_count > 0 {
+            image_count = image_count.min(capabilities.max_image_count);
+        }
+        let composite_alpha =
+            if capabilities.supported_composite_alpha.contains(vk::CompositeAlphaFlagsKHR::PRE_MULTIPLIED) {
+                vk::CompositeAlphaFlagsKHR::PRE_MULTIPLIED
+            } else {
+                vk::CompositeAlphaFlagsKHR::OPAQUE
+            };
+        let old_swapchain = self.swapchain;
+        let swapchain_info = vk::SwapchainCreateInfoKHR::default()
+            .surface(self.surface)
+            .min_image_count(image_count)
+            .image_format(self.format.format)
+            .image_color_space(self.format.color_space)
+            .image_extent(extent)
+            .image_array_layers(1)
+            .image_usage(
+                vk::ImageUsageFlags::COLOR_ATTACHMENT
+                    | vk::ImageUsageFlags::TRANSFER_SRC
+                    | vk::ImageUsageFlags::TRANSFER_DST,
+            )
+            .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .pre_transform(capabilities.current_transform)
+            .composite_alpha(composite_alpha)
+            .present_mode(vk::PresentModeKHR::FIFO)
+            .clipped(true)
+            .old_swapchain(old_swapchain);
+        self.swapchain = unsafe { self.swapchain_loader.create_swapchain(&swapchain_info, None) }
+            .map_err(graphics_error("Failed to create the Vulkan swap chain"))?;
+        if old_swapchain != vk::SwapchainKHR::null() {
+            unsafe { self.swapchain_loader.destroy_swapchain(old_swapchain, None) };
+        }
+
+        let images = unsafe { self.swapchain_loader.get_swapchain_images(self.swapchain) }
+            .map_err(graphics_error("Failed to get the swap chain images"))?;
+        let (skia_format, color_type) = self.skia_format();
+        for image in images {
+            let image_info = unsafe {
+                skia_vk::ImageInfo::new(
+                    image.as_raw() as _,
+                    skia_vk::Alloc::default(),
+                    skia_vk::ImageTiling::OPTIMAL,
+                    skia_vk::ImageLayout::UNDEFINED,
+                    skia_format,
+                    1,
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+            };
+            let render_target =
+                backend_render_targets::make_vk((extent.width as i32, extent.height as i32), &image_info);
+            let surface = wrap_backend_render_target(
+                &mut self.gr_context,
+                &render_target,
+                SurfaceOrigin::TopLeft,
+                color_type,
+                None,
+                None,
+            )
+            .ok_or_else(|| Error::Graphics("Could not create backend render target".into()))?;
+            self.surfaces.push(Some(surface));
+        }
+        self.out_of_date = false;
+        Ok(())
+    }
+
+    fn skia_format(&self) -> (skia_vk::Format, ColorType) {
+        match self.format.format {
+            vk::Format::R8G8B8A8_UNORM => (skia_vk::Format::R8G8B8A8_UNORM, ColorType::RGBA8888),
+            _ => (skia_vk::Format::B8G8R8A8_UNORM, ColorType::BGRA8888),
+        }
+    }
+
+    fn wait_for_gpu(&mut self) {
+        self.gr_context.flush_submit_and_sync_cpu();
+        let _ = unsafe { self.device.device_wait_idle() };
+    }
+}
+
+impl SwapChain for VulkanSwapChain {
+    fn resize(&mut self, size: IntPoint) -> Result<(), Error> {
+        self.size = size;
+        self.wait_for_gpu();
+        self.setup_swapchain()
+    }
+
+    fn acquire(&mut self) -> Option<(Surface, usize)> {
+        if self.out_of_date {
+            self.wait_for_gpu();
+            self.setup_swapchain().ok()?;
+        }
+        if self.surfaces.is_empty() {
+            return None;
+        }
+        let acquired = unsafe {
+            self.swapchain_loader.acquire_next_image(
+                self.swapchain,
+                u64::MAX,
+                vk::Semaphore::null(),
+                self.acquire_fence,
+            )
+        };
+        let index = match acquired {
+            Ok((index, suboptimal)) => {
+                self.out_of_date = suboptimal;
+                index as usize
+            }
+            Err(_) => {
+                self.out_of_date = true;
+                return None;
+            }
+        };
+        unsafe {
+            let _ = self.device.wait_for_fences(&[self.acquire_fence], true, u64::MAX);
+            let _ = self.device.reset_fences(&[self.acquire_fence]);
+        }
+        let surface = self.surfaces[index].take()?;
+        let (_, color_type) = self.skia_format();
+        match self.msaa.surface(&mut self.gr_context, &surface, color_type) {
+            Some(msaa_surface) => {
+                self.surfaces[index] = Some(surface);
+                Some((msaa_surface, index))
+            }
+            None => Some((surface, index)),
+        }
+    }
+
+    fn present(&mut self, mut surface: Surface, index: usize, _dirty_rects: &[Rect]) {
+        // the image was kept if the frame was drawn with multisampling
+        if let Some(mut target) = self.surfaces[index].take() {
+            MsaaTarget::resolve(&mut surface, &mut target);
+            surface = target;
+        }
+        // moves the image to the layout for presenting, and waits for it to be drawn since nothing else tells the
+        // presentation engine when that's done
+        self.gr_context.flush_surface_with_access(&mut surface, BackendSurfaceAccess::Present, &FlushInfo::default());
+        self.gr_context.submit(Some(SyncCpu::Yes));
+        self.surfaces[index] = Some(surface);
+
+        let swapchains = [self.swapchain];
+        let image_indices = [index as u32];
+        let present_info = vk::PresentInfoKHR::default().swapchains(&swapchains).image_indices(&image_indices);
+        match unsafe { self.swapchain_loader.queue_present(self.queue, &present_info) } {
+            Ok(suboptimal) => self.out_of_date |= suboptimal,
+            Err(_) => self.out_of_date = true,
+        }
+    }
+
+    fn set_sample_count(&mut self, count: usize) {
+        self.msaa.set_sample_count(count);
+    }
+}
+
+impl Drop for VulkanSwapChain {
+    fn drop(&mut self) {
+        self.wait_for_gpu();
+        self.surfaces.clear();
+        unsafe {
+            self.gr_context.release_resources_and_abandon();
+            ManuallyDrop::drop(&mut self.gr_context);
+            self.device.destroy_fence(self.acquire_fence, None);
+            self.swapchain_loader.destroy_swapchain(self.swapchain, None);
+            self.device.destroy_device(None);
+            self.surface_loader.destroy_surface(self.surface, None);
+            self.instance.destroy_instance(None);
+        }
+    }
+}
+
+/// Finds a GPU with a queue that can both draw and present to `surface`, preferring discrete GPUs.
+fn find_physical_device(
+    instance: &ash::Instance,
+    surface_loader: &khr::surface::Instance,
+    surface: vk::SurfaceKHR,
+) -> Result<(vk::PhysicalDevice, u32), Error> {
+    let physical_devices = unsafe { instance.enumerate_physical_devices() }
+        .map_err(graphics_error("Failed to enumerate the Vulkan devices"))?;
+    let mut candidates: Vec<_> = physical_devices
+        .into_iter()
+        .filter_map(|physical_device| {
+            let families = unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
+            let family = families.iter().enumerate().position(|(index, family)| {
+                family.queue_flags.contains(vk::QueueFlags::GRAPHICS)
+                    && unsafe {
+                        surface_loader.get_physical_device_surface_support(physical_device, index as u32, surface)
+                    }
+                    .unwrap_or(false)
+            })?;
+            Some((physical_device, family as u32))
+        })
+        .collect();
+    candidates.sort_by_key(|(physical_device, _)| {
+        let properties = unsafe { instance.get_physical_device_properties(*physical_device) };
+        properties.device_type != vk::PhysicalDeviceType::DISCRETE_GPU
+    });
+    candidates.into_iter().next().ok_or_else(|| Error::Graphics("Failed to find any suitable Vulkan devices".into()))
+}
+
+fn choose_format(
+    surface_loader: &khr::surface::Instance,
+    physical_device: vk::PhysicalDevice,
+    surface: vk::SurfaceKHR,
+) -> Result<vk::SurfaceFormatKHR, Error> {
+    let formats = unsafe { surface_loader.get_physical_device_surface_formats(physical_device, surface) }
+        .map_err(graphics_error("Failed to get the surface formats"))?;
+    formats
+        .iter()
+        .find(|format| matches!(format.format, vk::Format::B8G8R8A8_UNORM | vk::Format::R8G8B8A8_UNORM))
+        .copied()
+        .ok_or_else(|| Error::Graphics("The surface has no supported formats".into()))
+}
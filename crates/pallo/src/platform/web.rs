@@ -1,13 +1,22 @@
 use crate::{
-    App, Canvas, Component, ComponentId, Cx, EventStatus, File, IntPoint, JsCanvas, Later, Modifiers, MouseButton,
-    PointerId, UI, WindowEvent,
-    platform::{Clipboard, FileOpenOptions, FileSaveOptions, PlatformCommon},
+    App, Canvas, Component, ComponentId, Cx, Error, EventStatus, FetchOptions, FetchResponse, File, IntPoint, JsCanvas,
+    Later, Margin, Modifiers, MouseButton, PointerId, Rect, ScrollDelta, ScrollPhase, TextFieldEvent, TextFieldOptions,
+    UI, WindowEvent,
+    file_loader::LoadState,
+    live_channel::{Backoff, LiveEvent, LiveMessage, LiveState},
+    platform::{Clipboard, FileOpenOptions, FileSaveOptions, InputType, PlatformCommon, Watcher},
     point,
 };
 use js_sys::Uint8Array;
-use keyboard_types::Key;
+use keyboard_types::{Code, Key, Location};
 use std::str::FromStr;
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, VecDeque},
+    path::PathBuf,
+    rc::Rc,
+    sync::Arc,
+};
 use wasm_bindgen::{JsValue, prelude::*};
 use web_sys::window;
 
@@ -16,14 +25,205 @@ pub fn create_canvas<A: App, R: Component<A> + 'static>(
     create_root: impl Fn(&mut Cx<A>, ComponentId) -> R + 'static,
 ) -> JsValue {
     let size = A::get_initial_size(&init);
-    let ui = WebUI { ui: Box::new(UI::new(init, Platform::default(), &create_root)) };
+    let platform = Platform::with_view(JsView::new(A::sample_count() as u32));
+    let ui = WebUI { ui: Box::new(UI::new(init, platform, &create_root)) };
     create_canvas_internal(ui, size.x as u32, size.y as u32)
 }
 
+/// Creates a canvas on the main thread that is rendered by a UI running in `worker`, which has to call
+/// `run_in_worker`. Input events are posted to the worker, so they can't be captured from the page.
+pub fn create_worker_canvas(worker: &web_sys::Worker) -> JsValue {
+    create_worker_canvas_internal(worker)
+}
+
+/// Runs the UI in a web worker once the main thread has created its canvas with `create_worker_canvas`.
+/// File dialogs and prompts need the main thread and aren't available in this mode.
+pub fn run_in_worker<A: App, R: Component<A> + 'static>(
+    init: A::AppInit,
+    create_root: impl Fn(&mut Cx<A>, ComponentId) -> R + 'static,
+) {
+    run_in_worker_internal(Closure::once_into_js(move |canvas: JsValue, scale_factor: f32| {
+        let size = A::get_initial_size(&init);
+        let platform = Platform::with_view(JsView::new_offscreen(canvas, scale_factor, A::sample_count() as u32));
+        let mut ui = WebUI { ui: Box::new(UI::new(init, platform, &create_root)) };
+        ui.on_resize(size.x as usize, size.y as usize, scale_factor);
+        ui
+    }));
+}
+
+pub(crate) fn fetch_file(url: String, state: Arc<LoadState>, result: Later<Arc<Vec<u8>>>) {
+    let (on_progress, on_done) = load_callbacks(state, result);
+    fetch_with_progress(url, on_progress, on_done);
+}
+
+pub(crate) fn read_blob(blob: web_sys::Blob, state: Arc<LoadState>, result: Later<Arc<Vec<u8>>>) {
+    let (on_progress, on_done) = load_callbacks(state, result);
+    read_blob_with_progress(blob, on_progress, on_done);
+}
+
+pub(crate) fn fetch_request(
+    url: String,
+    options: FetchOptions,
+    state: Arc<LoadState>,
+    done: impl FnOnce(Result<FetchResponse, Error>) + 'static,
+) {
+    let on_progress = {
+        let state = state.clone();
+        // 1 is reserved for when the result has been set
+        Closure::<dyn FnMut(f32)>::new(move |progress: f32| state.set_progress(progress.min(0.99)))
+    };
+    let on_done = Closure::once_into_js(move |status: u16, data: Option<Uint8Array>, error: Option<String>| {
+        (done)(match data {
+            Some(data) => Ok(FetchResponse { status, body: Arc::new(data.to_vec()) }),
+            None => Err(Error::Network(error.unwrap_or_default())),
+        })
+    });
+    let headers: js_sys::Array =
+        options.headers.iter().map(|(name, value)| js_sys::Array::of2(&name.into(), &value.into())).collect();
+    fetch_with_options(url, options.method.as_str(), headers, options.body, on_progress.into_js_value(), on_done);
+}
+
+/// The socket of a `LiveChannel`, replaced with a new one on each reconnect.
+pub(crate) struct WebSocketConnection {
+    socket: Rc<RefCell<Option<web_sys::WebSocket>>>,
+}
+
+impl WebSocketConnection {
+    /// Sends the queued messages if the socket is open.
+    pub(crate) fn flush(&self, state: &LiveState) {
+        if let Some(socket) = self.socket.borrow().as_ref() {
+            send_queued(socket, state);
+        }
+    }
+
+    pub(crate) fn close(&self) {
+        if let Some(socket) = self.socket.borrow_mut().take() {
+            let _ = socket.close();
+        }
+    }
+}
+
+pub(crate) fn open_web_socket(url: String, state: Arc<LiveState>, backoff: Backoff) -> WebSocketConnection {
+    let socket = Rc::new(RefCell::new(None));
+    connect_web_socket(url, state, backoff, 0, socket.clone());
+    WebSocketConnection { socket }
+}
+
+fn connect_web_socket(
+    url: String,
+    state: Arc<LiveState>,
+    backoff: Backoff,
+    attempt: u32,
+    slot: Rc<RefCell<Option<web_sys::WebSocket>>>,
+) {
+    if state.is_closed() {
+        return;
+    }
+    let Ok(socket) = web_sys::WebSocket::new(&url) else {
+        reconnect_web_socket(url, state, backoff, attempt + 1, slot);
+        return;
+    };
+    socket.set_binary_type(web_sys::BinaryType::Arraybuffer);
+
+    let on_open = {
+        let (state, socket) = (state.clone(), socket.clone());
+        Closure::<dyn FnMut()>::new(move || {
+            state.receive(LiveEvent::Connected);
+            send_queued(&socket, &state);
+        })
+        .into_js_value()
+    };
+    let on_message = {
+        let state = state.clone();
+        Closure::<dyn FnMut(web_sys::MessageEvent)>::new(move |event: web_sys::MessageEvent| {
+            let data = event.data();
+            if let Some(text) = data.as_string() {
+                state.receive(LiveEvent::Message(LiveMessage::Text(text)));
+            } else if let Ok(buffer) = data.dyn_into::<js_sys::ArrayBuffer>() {
+                state.receive(LiveEvent::Message(LiveMessage::Binary(Uint8Array::new(&buffer).to_vec())));
+            }
+        })
+        .into_js_value()
+    };
+    let on_close = {
+        let (state, slot) = (state.clone(), slot.clone());
+        Closure::once_into_js(move || {
+            // a connection that was lost starts over from the first delay, one that couldn't be made backs off more
+            let attempt = if state.is_connected() { 0 } else { attempt + 1 };
+            reconnect_web_socket(url, state, backoff, attempt, slot);
+        })
+    };
+    socket.set_onopen(Some(on_open.unchecked_ref()));
+    socket.set_onmessage(Some(on_message.unchecked_ref()));
+    socket.set_onclose(Some(on_close.unchecked_ref()));
+    *slot.borrow_mut() = Some(socket);
+}
+
+fn reconnect_web_socket(
+    url: String,
+    state: Arc<LiveState>,
+    backoff: Backoff,
+    attempt: u32,
+    slot: Rc<RefCell<Option<web_sys::WebSocket>>>,
+) {
+    if state.is_closed() {
+        return;
+    }
+    slot.borrow_mut().take();
+    let reconnect_in = backoff.delay(attempt);
+    state.receive(LiveEvent::Disconnected { reconnect_in });
+    if let Some(delay) = reconnect_in {
+        let reconnect = Closure::once_into_js(move || connect_web_socket(url, state, backoff, attempt, slot));
+        set_timeout(&reconnect, delay.as_millis() as i32);
+    }
+}
+
+fn send_queued(socket: &web_sys::WebSocket, state: &LiveState) {
+    if socket.ready_state() != web_sys::WebSocket::OPEN {
+        return;
+    }
+    while let Some(message) = state.take_outgoing() {
+        let _ = match message {
+            LiveMessage::Text(text) => socket.send_with_str(&text),
+            LiveMessage::Binary(data) => socket.send_with_u8_array(&data),
+        };
+    }
+}
+
+fn load_callbacks(state: Arc<LoadState>, result: Later<Arc<Vec<u8>>>) -> (JsValue, JsValue) {
+    let on_progress = {
+        let state = state.clone();
+        // 1 is reserved for when the result has been set
+        Closure::<dyn FnMut(f32)>::new(move |progress: f32| state.set_progress(progress.min(0.99)))
+    };
+    let on_done = Closure::once_into_js(move |data: Option<Uint8Array>| match data {
+        Some(data) => {
+            result.set(Arc::new(data.to_vec()));
+            state.set_progress(1.0);
+        }
+        None => state.fail(),
+    });
+    (on_progress.into_js_value(), on_done)
+}
+
 fn convert_key(key: String) -> Key {
     Key::from_str(&key).unwrap_or(Key::Character(key))
 }
 
+fn convert_code(code: &str) -> Code {
+    Code::from_str(code).unwrap_or(Code::Unidentified)
+}
+
+// KeyboardEvent.location
+fn convert_location(location: u32) -> Location {
+    match location {
+        1 => Location::Left,
+        2 => Location::Right,
+        3 => Location::Numpad,
+        _ => Location::Standard,
+    }
+}
+
 impl<A: App> WebUIMethods for UI<A> {
     fn on_event_web(&mut self, event: WindowEvent) -> EventStatus {
         self.on_event(event)
@@ -44,6 +244,10 @@ impl<A: App> WebUIMethods for UI<A> {
     fn should_resize_to_web(&mut self) -> Option<IntPoint> {
         self.should_resize_to()
     }
+
+    fn paste_web(&mut self, text: String) {
+        self.ui_context.platform.clipboard.on_paste(text);
+    }
 }
 
 trait WebUIMethods {
@@ -52,6 +256,7 @@ trait WebUIMethods {
     fn get_view_web(&mut self) -> JsView;
     fn set_frame(&mut self, frame: Frame);
     fn should_resize_to_web(&mut self) -> Option<IntPoint>;
+    fn paste_web(&mut self, text: String);
 }
 
 #[wasm_bindgen]
@@ -116,20 +321,69 @@ impl WebUI {
         self.ui.on_event_web(WindowEvent::PointerUp { id: PointerId::Mouse });
     }
 
-    pub fn mouse_wheel(&mut self, x: f32, y: f32) {
-        self.ui.on_event_web(WindowEvent::MouseWheel(point(x, y)));
+    pub fn pen_input(&mut self, id: usize, pressure: f32, tilt_x: f32, tilt_y: f32, barrel_button: bool) {
+        self.ui.on_event_web(WindowEvent::PenInput {
+            id: PointerId::Pen(id),
+            pressure,
+            tilt: point(tilt_x, tilt_y),
+            barrel_button,
+        });
+    }
+
+    pub fn pen_down(&mut self, id: usize, x: f32, y: f32) {
+        self.ui.on_event_web(WindowEvent::PointerDown {
+            id: PointerId::Pen(id),
+            position: point(x, y),
+            button: MouseButton::Left,
+        });
+    }
+
+    pub fn pen_move(&mut self, id: usize, x: f32, y: f32) {
+        self.ui.on_event_web(WindowEvent::PointerMove { position: point(x, y), id: PointerId::Pen(id) });
+    }
+
+    pub fn pen_up(&mut self, id: usize) {
+        self.ui.on_event_web(WindowEvent::PointerUp { id: PointerId::Pen(id) });
+    }
+
+    pub fn pen_leave(&mut self, id: usize) {
+        self.ui.on_event_web(WindowEvent::PointerLeave { id: PointerId::Pen(id) });
+    }
+
+    pub fn mouse_wheel(&mut self, x: f32, y: f32, delta_mode: u32) {
+        // WheelEvent.deltaMode: 0 = pixels, 1 = lines, 2 = pages
+        let delta = match delta_mode {
+            0 => ScrollDelta::Pixels(point(x, y)),
+            _ => ScrollDelta::Lines(point(x, y)),
+        };
+        self.ui.on_event_web(WindowEvent::MouseWheel { delta, phase: ScrollPhase::Changed });
     }
 
     pub fn modifiers_changed(&mut self, meta: bool, shift: bool, alt: bool, ctrl: bool) {
         self.ui.on_event_web(WindowEvent::ModifiersChanged(Modifiers { ctrl, meta, shift, alt }));
     }
 
-    pub fn key_down(&mut self, key: String) -> bool {
-        matches!(self.ui.on_event_web(WindowEvent::Keydown(convert_key(key))), EventStatus::Captured)
+    pub fn key_down(&mut self, key: String, code: String, repeat: bool, location: u32) -> bool {
+        let event = WindowEvent::Keydown {
+            key: convert_key(key),
+            code: convert_code(&code),
+            repeat,
+            location: convert_location(location),
+        };
+        matches!(self.ui.on_event_web(event), EventStatus::Captured)
     }
 
-    pub fn key_up(&mut self, key: String) -> bool {
-        matches!(self.ui.on_event_web(WindowEvent::Keyup(convert_key(key))), EventStatus::Captured)
+    pub fn key_up(&mut self, key: String, code: String, location: u32) -> bool {
+        let event = WindowEvent::Keyup {
+            key: convert_key(key),
+            code: convert_code(&code),
+            location: convert_location(location),
+        };
+        matches!(self.ui.on_event_web(event), EventStatus::Captured)
+    }
+
+    pub fn paste(&mut self, text: String) {
+        self.ui.paste_web(text);
     }
 
     pub fn focus(&mut self, focused: bool) {
@@ -151,44 +405,152 @@ pub struct Platform {
     clipboard: WebClipboard,
     frame: Option<Frame>,
     js_view: JsView,
+    view_size: Rc<Cell<IntPoint>>,
+    // the last size reported by the resize observer, i.e. the size the canvas has been laid out at
+    observed_size: Rc<Cell<Option<IntPoint>>>,
+    insets: Rc<Cell<Margin>>,
+    events: Rc<RefCell<VecDeque<WindowEvent>>>,
 }
 
 impl Default for Platform {
     fn default() -> Self {
-        Self { clipboard: Default::default(), frame: Default::default(), js_view: JsView::new() }
+        Self::with_view(JsView::new(1))
+    }
+}
+
+impl Platform {
+    fn with_view(js_view: JsView) -> Self {
+        let view_size: Rc<Cell<IntPoint>> = Default::default();
+        let observed_size: Rc<Cell<Option<IntPoint>>> = Default::default();
+        let insets: Rc<Cell<Margin>> = Default::default();
+        let events: Rc<RefCell<VecDeque<WindowEvent>>> = Default::default();
+
+        let on_resize = {
+            let observed_size = observed_size.clone();
+            let events = events.clone();
+            Closure::<dyn FnMut(u32, u32)>::new(move |width: u32, height: u32| {
+                let size = (width, height).into();
+                observed_size.set(Some(size));
+                events.borrow_mut().push_back(WindowEvent::Resized(size));
+            })
+        };
+        let on_scale_factor_changed = {
+            let view_size = view_size.clone();
+            let events = events.clone();
+            Closure::<dyn FnMut(f32)>::new(move |scale_factor: f32| {
+                let mut events = events.borrow_mut();
+                events.push_back(WindowEvent::ScaleFactorChanged(scale_factor));
+                // resizing again recreates the surface at the new resolution
+                events.push_back(WindowEvent::Resized(view_size.get()));
+            })
+        };
+        let on_insets_changed = {
+            let insets = insets.clone();
+            let events = events.clone();
+            Closure::<dyn FnMut(f32, f32, f32, f32)>::new(move |left: f32, top: f32, right: f32, bottom: f32| {
+                insets.set(Margin::new(left, top, right, bottom));
+                events.borrow_mut().push_back(WindowEvent::InsetsChanged(insets.get()));
+            })
+        };
+        js_view.observe(
+            on_resize.into_js_value(),
+            on_scale_factor_changed.into_js_value(),
+            on_insets_changed.into_js_value(),
+        );
+
+        Self {
+            clipboard: Default::default(),
+            frame: Default::default(),
+            js_view,
+            view_size,
+            observed_size,
+            insets,
+            events,
+        }
     }
 }
 
 #[derive(Default)]
 pub struct WebClipboard {
-    data: Option<Vec<u8>>,
+    // custom types only live within the page for now
+    data: HashMap<String, Vec<u8>>,
+    pasted_text: Option<String>,
+    // a read that hasn't been resolved yet, by either the clipboard api or the next paste event
+    pending_read: Rc<RefCell<Option<Later<String>>>>,
+}
+
+impl WebClipboard {
+    fn on_paste(&mut self, text: String) {
+        if let Some(result) = self.pending_read.borrow_mut().take() {
+            result.set(text.clone());
+        }
+        self.pasted_text = Some(text);
+    }
 }
 
 impl Clipboard for WebClipboard {
-    fn write_string(&mut self, text: impl Into<String>) {
-        let window = window().expect("should have a window in this context");
+    fn write_string(&mut self, text: impl Into<String>) -> Result<(), Error> {
         let text: String = text.into();
-        let _ = window.navigator().clipboard().write_text(&text);
+        match window() {
+            Some(window) => {
+                let _ = window.navigator().clipboard().write_text(&text);
+            }
+            // workers can't access the clipboard
+            None => forward_to_main_thread("clipboard", text),
+        }
+        Ok(())
+    }
+
+    fn write_data(&mut self, data: Vec<u8>) -> Result<(), Error> {
+        self.write_custom("application/octet-stream", data)
     }
 
-    fn write_data(&mut self, data: Vec<u8>) {
+    fn read_data(&self) -> Option<Vec<u8>> {
+        self.read_custom("application/octet-stream")
+    }
+
+    fn write_custom(&mut self, type_id: &str, data: Vec<u8>) -> Result<(), Error> {
         // use js_sys::{Array, Uint8Array};
         // let window = window().expect("should have a window in this context");
         // let obj = js_sys::Object::new();
-        // let _ = js_sys::Reflect::set(&obj, &"web application/octet-stream".into(), Blob::new(&*data).as_ref());
+        // let _ = js_sys::Reflect::set(&obj, &format!("web {type_id}").into(), Blob::new(&*data).as_ref());
         // let _ = window
         //     .navigator()
         //     .clipboard()
         //     .write(&Array::of1(&web_sys::ClipboardItem::new_with_record_from_str_to_blob_promise(&obj).unwrap()));
-        self.data = Some(data);
+        self.data.insert(type_id.to_string(), data);
+        Ok(())
     }
 
-    fn read_data(&self) -> Option<Vec<u8>> {
-        self.data.clone()
+    fn read_custom(&self, type_id: &str) -> Option<Vec<u8>> {
+        self.data.get(type_id).cloned()
     }
 
     fn read_string(&self) -> Option<String> {
-        None
+        self.pasted_text.clone()
+    }
+
+    fn read_string_async(&self, result: &Later<String>) {
+        *self.pending_read.borrow_mut() = Some(result.clone());
+
+        // the clipboard api only exists on the main thread in secure contexts
+        let Some(navigator) = window().map(|window| window.navigator()) else {
+            return;
+        };
+        if !js_sys::Reflect::has(&navigator, &"clipboard".into()).unwrap_or(false) {
+            return;
+        }
+
+        let pending_read = self.pending_read.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            // rejected if the user denies the permission, in which case the paste event delivers the text instead
+            if let Ok(text) = wasm_bindgen_futures::JsFuture::from(navigator.clipboard().read_text()).await
+                && let Some(text) = text.as_string()
+                && let Some(result) = pending_read.borrow_mut().take()
+            {
+                result.set(text);
+            }
+        });
     }
 
     fn read_paths(&self) -> Option<Vec<PathBuf>> {
@@ -200,9 +562,29 @@ impl Clipboard for WebClipboard {
     }
 }
 
+#[wasm_bindgen]
+extern "C" {
+    // available on both windows and workers
+    #[wasm_bindgen(js_name = setTimeout)]
+    fn set_timeout(handler: &JsValue, timeout: i32) -> i32;
+}
+
 #[wasm_bindgen(module = "/src/platform/web_platform.js")]
 extern "C" {
     fn create_canvas_internal(ui: WebUI, width: u32, height: u32) -> JsValue;
+    fn create_worker_canvas_internal(worker: &web_sys::Worker) -> JsValue;
+    fn run_in_worker_internal(create: JsValue);
+    fn forward_to_main_thread(kind: &str, value: String);
+    fn fetch_with_progress(url: String, on_progress: JsValue, on_done: JsValue);
+    fn fetch_with_options(
+        url: String,
+        method: &str,
+        headers: js_sys::Array,
+        body: Option<Vec<u8>>,
+        on_progress: JsValue,
+        on_done: JsValue,
+    );
+    fn read_blob_with_progress(blob: web_sys::Blob, on_progress: JsValue, on_done: JsValue);
 
     fn save_file(filename: String, bytes: Vec<u8>, mime_type: String);
     fn get_file_input() -> web_sys::Element;
@@ -220,10 +602,43 @@ extern "C" {
     type JsView;
 
     #[wasm_bindgen(constructor)]
-    fn new() -> JsView;
+    fn new(sample_count: u32) -> JsView;
+
+    #[wasm_bindgen(static_method_of = JsView)]
+    fn new_offscreen(canvas: JsValue, scale_factor: f32, sample_count: u32) -> JsView;
+
+    #[wasm_bindgen(method)]
+    fn scale_factor(this: &JsView) -> f32;
+
+    #[wasm_bindgen(method)]
+    fn resize(this: &JsView, width: u32, height: u32);
+
+    #[wasm_bindgen(method)]
+    fn resize_surface(this: &JsView, width: u32, height: u32);
 
     #[wasm_bindgen(method)]
-    fn resize(this: &JsView, widt: u32, height: u32);
+    #[allow(clippy::too_many_arguments)]
+    fn open_text_field(
+        this: &JsView,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        value: String,
+        input_type: &str,
+        font_size: f32,
+        color: String,
+        on_event: JsValue,
+    ) -> bool;
+
+    #[wasm_bindgen(method)]
+    fn move_text_field(this: &JsView, x: f32, y: f32, width: f32, height: f32);
+
+    #[wasm_bindgen(method)]
+    fn close_text_field(this: &JsView);
+
+    #[wasm_bindgen(method)]
+    fn observe(this: &JsView, on_resize: JsValue, on_scale_factor_changed: JsValue, on_insets_changed: JsValue);
 }
 
 impl Platform {
@@ -237,8 +652,7 @@ impl PlatformCommon for Platform {
     type Frame = Frame;
 
     fn get_scale_factor(&self) -> f32 {
-        let window = web_sys::window().expect("should have a window in this context");
-        window.device_pixel_ratio() as f32
+        self.js_view.scale_factor()
     }
 
     fn clipboard(&mut self) -> &mut impl Clipboard {
@@ -253,7 +667,9 @@ impl PlatformCommon for Platform {
         input_type: super::InputType,
         result: &Later<String>,
     ) {
-        if let Ok(Some(value)) = window().unwrap().prompt_with_message_and_default(&title, &value) {
+        if let Some(window) = window()
+            && let Ok(Some(value)) = window.prompt_with_message_and_default(&title, &value)
+        {
             result.set(value);
         }
     }
@@ -265,18 +681,37 @@ impl PlatformCommon for Platform {
     fn open_path_in_file_explorer(&self, path: PathBuf) {}
 
     fn set_view_size(&mut self, size: (u32, u32)) {
-        self.js_view.resize(size.0, size.1)
+        let (width, height) = size;
+        self.view_size.set(size.into());
+        if self.observed_size.get() == Some(size.into()) {
+            // the canvas is already laid out at this size, so only the surface needs to follow it
+            self.js_view.resize_surface(width, height);
+        } else {
+            self.js_view.resize(width, height);
+        }
     }
 
     fn start_drag(&self, path: PathBuf) {}
 
+    fn safe_area_insets(&self) -> Margin {
+        self.insets.get()
+    }
+
     fn next_window_event(&mut self) -> Option<WindowEvent> {
-        None
+        self.events.borrow_mut().pop_front()
+    }
+
+    fn watch_path(&mut self, path: PathBuf) -> Watcher {
+        Watcher {}
     }
 
     fn open_url(&self, url: impl Into<String>) {
-        let window = web_sys::window().expect("should have a Window");
-        let _ = window.open_with_url(&url.into());
+        match web_sys::window() {
+            Some(window) => {
+                let _ = window.open_with_url(&url.into());
+            }
+            None => forward_to_main_thread("open_url", url.into()),
+        }
     }
 
     fn file_open_dialog(&self, opts: FileOpenOptions) {
@@ -316,6 +751,49 @@ impl PlatformCommon for Platform {
         save_file(options.filename, options.data.to_vec(), options.mime_type);
     }
 
+    fn open_text_field(&mut self, options: TextFieldOptions) -> bool {
+        let TextFieldOptions { bounds, value, input_type, font_size, color, events } = options;
+        let input_type = match input_type {
+            InputType::Text => "text",
+            InputType::Number => "number",
+            InputType::Password => "password",
+        };
+        let color = format!(
+            "rgba({}, {}, {}, {})",
+            color.red() * 255.0,
+            color.green() * 255.0,
+            color.blue() * 255.0,
+            color.alpha()
+        );
+        // 0 is an edit, 1 a press of enter and 2 the field losing focus
+        let on_event = Closure::<dyn FnMut(u32, String)>::new(move |kind: u32, text: String| {
+            events.set(match kind {
+                0 => TextFieldEvent::Changed(text),
+                1 => TextFieldEvent::Submitted(text),
+                _ => TextFieldEvent::Closed(text),
+            });
+        });
+        self.js_view.open_text_field(
+            bounds.left(),
+            bounds.top(),
+            bounds.width(),
+            bounds.height(),
+            value,
+            input_type,
+            font_size,
+            color,
+            on_event.into_js_value(),
+        )
+    }
+
+    fn move_text_field(&mut self, bounds: Rect) {
+        self.js_view.move_text_field(bounds.left(), bounds.top(), bounds.width(), bounds.height());
+    }
+
+    fn close_text_field(&mut self) {
+        self.js_view.close_text_field();
+    }
+
     fn new_frame(&mut self) -> Option<Self::Frame> {
         self.frame.take()
     }
@@ -7,26 +7,271 @@ export class JsFile {
   get_data() { return this.data; }
 }
 
+function setCanvasStyle(canvas, width, height) {
+  canvas.tabIndex = 0;
+
+  canvas.style.maxWidth = `${width}px`;
+  canvas.style.maxHeight = `${height}px`;
+  canvas.style.aspectRatio = `${width} / ${height}`;
+}
+
+// falls back to drawing on the cpu and copying the frames with putImageData when webgl isn't available
+function makeSurface(canvas, software, antialias) {
+  if (software) {
+    return CanvasKit.MakeSWCanvasSurface(canvas);
+  }
+  // webgl picks the sample count itself, and multisampling is dropped if the context can't be made with it
+  const surface =
+    (antialias && CanvasKit.MakeWebGLCanvasSurface(canvas, null, { antialias: 1 })) ||
+    CanvasKit.MakeWebGLCanvasSurface(canvas);
+  return surface ?? CanvasKit.MakeSWCanvasSurface(canvas);
+}
+
+function observeCanvas(canvas, getSize, onResize, onScaleFactorChanged, onInsetsChanged) {
+  new ResizeObserver(entries => {
+    const { inlineSize, blockSize } = entries[entries.length - 1].contentBoxSize[0];
+    const width = Math.round(inlineSize);
+    const height = Math.round(blockSize);
+    const size = getSize();
+    // a detached or hidden canvas has no size
+    if (width > 0 && height > 0 && (width !== size.width || height !== size.height)) {
+      onResize(width, height);
+    }
+  }).observe(canvas);
+
+  // a resolution query only matches the current ratio, so it has to be replaced after every change
+  const watchScaleFactor = () => {
+    matchMedia(`(resolution: ${window.devicePixelRatio}dppx)`).addEventListener('change', () => {
+      onScaleFactorChanged(window.devicePixelRatio);
+      watchScaleFactor();
+    }, { once: true });
+  };
+  watchScaleFactor();
+
+  observeInsets(canvas, getSize, onInsetsChanged);
+}
+
+// reports how much of each edge of the canvas is under a notch, a home indicator or the on-screen keyboard
+function observeInsets(canvas, getSize, onInsetsChanged) {
+  // the safe area is only exposed to css, so it's read back from a probe element
+  const probe = document.createElement('div');
+  probe.style = 'position: fixed; visibility: hidden; pointer-events: none; padding: env(safe-area-inset-top) '
+    + 'env(safe-area-inset-right) env(safe-area-inset-bottom) env(safe-area-inset-left);';
+  document.body.appendChild(probe);
+
+  let last = [0, 0, 0, 0];
+  const update = () => {
+    const rect = canvas.getBoundingClientRect();
+    if (rect.width === 0 || rect.height === 0) return;
+
+    const style = getComputedStyle(probe);
+    // the visual viewport shrinks when the on-screen keyboard opens
+    const viewport = window.visualViewport;
+    const visibleBottom = viewport ? viewport.offsetTop + viewport.height : window.innerHeight;
+    const safeBottom = Math.min(visibleBottom, window.innerHeight - parseFloat(style.paddingBottom));
+    const scale = getSize().width / rect.width;
+    const insets = [
+      parseFloat(style.paddingLeft) - rect.left,
+      parseFloat(style.paddingTop) - rect.top,
+      rect.right - (window.innerWidth - parseFloat(style.paddingRight)),
+      rect.bottom - safeBottom,
+    ].map(inset => Math.max(0, inset) * scale);
+
+    if (insets.some((inset, i) => inset !== last[i])) {
+      last = insets;
+      onInsetsChanged(...insets);
+    }
+  };
+  window.addEventListener('resize', update);
+  window.addEventListener('scroll', update, { passive: true });
+  window.visualViewport?.addEventListener('resize', update);
+  window.visualViewport?.addEventListener('scroll', update);
+  new ResizeObserver(update).observe(canvas);
+}
+
 export class JsView {
-  constructor() {
-    this.canvas = document.createElement('canvas');
-    this.surface = CanvasKit.MakeWebGLCanvasSurface(this.canvas);
+  constructor(sampleCount, offscreenCanvas, scaleFactor) {
+    if (offscreenCanvas) {
+      // rendering in a worker, the canvas element and its events live on the main thread
+      this.canvas = offscreenCanvas;
+      this.isOffscreen = true;
+      this.offscreenScaleFactor = scaleFactor;
+    } else {
+      this.canvas = document.createElement('canvas');
+    }
+    // the attributes of a webgl context can't be changed once it's made
+    this.antialias = sampleCount > 1;
+    this.surface = makeSurface(this.canvas, false, this.antialias);
+    // without a surface, e.g. for a canvas that has no size yet, webgl is tried again when the canvas is resized
+    this.isSoftware = this.surface ? !this.surface.reportBackendTypeIsGPU() : false;
+  }
+
+  static new_offscreen(canvas, scaleFactor, sampleCount) {
+    return new JsView(sampleCount, canvas, scaleFactor);
+  }
+
+  scale_factor() {
+    return this.isOffscreen ? this.offscreenScaleFactor : window.devicePixelRatio;
   }
 
   resize(width, height) {
-    this.canvas.width = width;
-    this.canvas.height = height;
-    this.canvas.tabIndex = 0;
+    if (this.isOffscreen) {
+      self.postMessage({ type: 'pallo:resize', width, height });
+    } else {
+      setCanvasStyle(this.canvas, width, height);
+    }
 
-    this.canvas.style.maxWidth = `${width}px`;
-    this.canvas.style.maxHeight = `${height}px`;
-    this.canvas.style.aspectRatio = `${width} / ${height}`;
-    
-    this.canvas.width *= window.devicePixelRatio;
-    this.canvas.height *= window.devicePixelRatio;
+    this.resize_surface(width, height);
+  }
+
+  // only changes the resolution, for when the css size of the canvas already matches
+  resize_surface(width, height) {
+    this.width = width;
+    this.height = height;
+    this.canvas.width = width * this.scale_factor();
+    this.canvas.height = height * this.scale_factor();
+
+    // once webgl has failed the canvas may have a 2d context, which can't be swapped for a webgl one
+    this.surface = makeSurface(this.canvas, this.isSoftware, this.antialias);
+  }
+
+  observe(onResize, onScaleFactorChanged, onInsetsChanged) {
+    if (this.isOffscreen) {
+      // called with the changes observed on the main thread
+      this.onResize = onResize;
+      this.onScaleFactorChanged = onScaleFactorChanged;
+      this.onInsetsChanged = onInsetsChanged;
+    } else {
+      observeCanvas(this.canvas, () => this, onResize, onScaleFactorChanged, onInsetsChanged);
+    }
+  }
+
+  observed_resize(width, height) {
+    if (width !== this.width || height !== this.height) {
+      this.onResize(width, height);
+    }
+  }
 
-    this.surface = CanvasKit.MakeWebGLCanvasSurface(this.canvas);
+  set_scale_factor(scaleFactor) {
+    this.offscreenScaleFactor = scaleFactor;
+    this.onScaleFactorChanged(scaleFactor);
   }
+
+  observed_insets(left, top, right, bottom) {
+    this.onInsetsChanged(left, top, right, bottom);
+  }
+
+  // an input element placed over the canvas for native text editing, positioned in the view's units
+  open_text_field(x, y, width, height, value, type, fontSize, color, onEvent) {
+    // workers can't reach the dom
+    if (this.isOffscreen) return false;
+
+    if (!this.textField) {
+      const input = document.createElement('input');
+      input.style = 'position: fixed; margin: 0; padding: 0; border: none; outline: none; background: transparent;';
+      input.addEventListener('input', () => this.onTextFieldEvent?.(0, input.value));
+      input.addEventListener('keydown', e => {
+        if (e.key === 'Enter') {
+          e.preventDefault();
+          this.onTextFieldEvent?.(1, input.value);
+        }
+      });
+      input.addEventListener('blur', () => this.onTextFieldEvent?.(2, input.value));
+      document.body.appendChild(input);
+      this.textField = input;
+    }
+
+    const input = this.textField;
+    this.onTextFieldEvent = onEvent;
+    this.textFieldFontSize = fontSize;
+    input.type = type === 'password' ? 'password' : 'text';
+    input.inputMode = type === 'number' ? 'decimal' : 'text';
+    input.value = value;
+    input.style.color = color;
+    input.style.display = 'block';
+    this.move_text_field(x, y, width, height);
+    input.focus();
+    return true;
+  }
+
+  move_text_field(x, y, width, height) {
+    const rect = this.canvas.getBoundingClientRect();
+    const scale = rect.width / this.width;
+    Object.assign(this.textField.style, {
+      left: `${rect.left + x * scale}px`,
+      top: `${rect.top + y * scale}px`,
+      width: `${width * scale}px`,
+      height: `${height * scale}px`,
+      font: `${this.textFieldFontSize * scale}px sans-serif`,
+    });
+  }
+
+  close_text_field() {
+    // closed by the ui, so it doesn't need to hear about the blur
+    this.onTextFieldEvent = undefined;
+    this.textField?.blur();
+    if (this.textField) {
+      this.textField.style.display = 'none';
+    }
+  }
+}
+
+export async function fetch_with_progress(url, onProgress, onDone) {
+  try {
+    const response = await fetch(url);
+    if (!response.ok) {
+      throw new Error(response.statusText);
+    }
+    onDone(await readStream(response.body, +response.headers.get('Content-Length'), onProgress));
+  } catch {
+    onDone(undefined);
+  }
+}
+
+export async function fetch_with_options(url, method, headers, body, onProgress, onDone) {
+  try {
+    const response = await fetch(url, { method, headers, body });
+    const total = +response.headers.get('Content-Length');
+    const data = response.body ? await readStream(response.body, total, onProgress) : new Uint8Array();
+    onDone(response.status, data, undefined);
+  } catch (error) {
+    onDone(0, undefined, String(error));
+  }
+}
+
+export async function read_blob_with_progress(blob, onProgress, onDone) {
+  try {
+    onDone(await readStream(blob.stream(), blob.size, onProgress));
+  } catch {
+    onDone(undefined);
+  }
+}
+
+async function readStream(stream, total, onProgress) {
+  const reader = stream.getReader();
+  const chunks = [];
+  let received = 0;
+  for (;;) {
+    const { done, value } = await reader.read();
+    if (done) break;
+    chunks.push(value);
+    received += value.length;
+    if (total) {
+      onProgress(received / total);
+    }
+  }
+
+  const data = new Uint8Array(received);
+  let offset = 0;
+  for (const chunk of chunks) {
+    data.set(chunk, offset);
+    offset += chunk.length;
+  }
+  return data;
+}
+
+export function forward_to_main_thread(kind, value) {
+  self.postMessage({ type: `pallo:${kind}`, value });
 }
 
 export function save_file(filename, bytes, mime_type) {
@@ -72,31 +317,115 @@ export function trigger_file_input(callback) {
 }
 
 export function create_canvas_internal(ui, width, height) {
-  const supportsTouch = 'ontouchstart' in window;
-
   const canvasNode = ui.get_view().canvas;
   ui.get_view().resize(width, height);
   ui.on_resize(width, height, window.devicePixelRatio);
 
+  addEventListeners(canvasNode, ui, () => ui.get_view());
+  startDrawing(ui);
+
+  return canvasNode;
+}
+
+export function create_worker_canvas_internal(worker) {
+  const canvasNode = document.createElement('canvas');
+  const offscreen = canvasNode.transferControlToOffscreen();
+  worker.postMessage({ type: 'pallo:init', canvas: offscreen, scaleFactor: window.devicePixelRatio }, [offscreen]);
+
+  const size = { width: 0, height: 0 };
+  worker.addEventListener('message', e => {
+    switch (e.data?.type) {
+      case 'pallo:resize':
+        size.width = e.data.width;
+        size.height = e.data.height;
+        setCanvasStyle(canvasNode, size.width, size.height);
+        break;
+      case 'pallo:clipboard':
+        navigator.clipboard?.writeText(e.data.value);
+        break;
+      case 'pallo:open_url':
+        window.open(e.data.value);
+        break;
+    }
+  });
+
+  const postToView = (method, ...args) => worker.postMessage({ type: 'pallo:view', method, args });
+  observeCanvas(
+    canvasNode,
+    () => size,
+    (width, height) => postToView('observed_resize', width, height),
+    scaleFactor => postToView('set_scale_factor', scaleFactor),
+    (...insets) => postToView('observed_insets', ...insets),
+  );
+
+  // forwards calls to the ui in the worker, which means key events can't be captured
+  const ui = new Proxy({}, {
+    get: (_, method) => (...args) => {
+      worker.postMessage({ type: 'pallo:ui', method, args });
+      return false;
+    },
+  });
+  addEventListeners(canvasNode, ui, () => size);
+
+  return canvasNode;
+}
+
+export function run_in_worker_internal(create) {
+  self.addEventListener('message', function init(e) {
+    if (e.data?.type !== 'pallo:init') return;
+    self.removeEventListener('message', init);
+
+    const ui = create(e.data.canvas, e.data.scaleFactor);
+    const view = ui.get_view();
+    self.addEventListener('message', e => {
+      const { type, method, args } = e.data ?? {};
+      if (type === 'pallo:ui') {
+        ui[method](...args);
+      } else if (type === 'pallo:view') {
+        view[method](...args);
+      }
+    });
+    startDrawing(ui);
+  });
+}
+
+function startDrawing(ui) {
+  function draw(canvas) {
+    ui.on_draw(canvas);
+    ui.get_view().surface.requestAnimationFrame(draw);
+  }
+  ui.get_view().surface.requestAnimationFrame(draw);
+}
+
+// `getSize` returns the logical size of the view that pointer positions are mapped to
+function addEventListeners(canvasNode, ui, getSize) {
+  const supportsTouch = 'ontouchstart' in window;
+
   function getPointerPosition(e) {
     const rect = canvasNode.getBoundingClientRect();
     const clientX = e.clientX || e.touches?.[0].clientX || 0;
     const clientY = e.clientY || e.touches?.[0].clientY || 0;
     const relativeX = (clientX - rect.left) / rect.width;
     const relativeY = (clientY - rect.top) / rect.height;
-    const x = relativeX * +canvasNode.width / window.devicePixelRatio;
-    const y = relativeY * +canvasNode.height / window.devicePixelRatio;
+    const x = relativeX * getSize().width;
+    const y = relativeY * getSize().height;
     return { x, y };
   }
 
+  // styluses are handled by the pointer event listeners below
+  function isStylus(e) {
+    return e.changedTouches?.[0]?.touchType === 'stylus';
+  }
+
   if (supportsTouch) {
     canvasNode.addEventListener('touchstart', e => {
+      if (isStylus(e)) return;
       const {x, y} = getPointerPosition(e);
       ui.mouse_down(x, y, false);
     });
   
     canvasNode.addEventListener('touchmove', e => {
-      if (e.touches.length === 1) {
+      if (e.touches.length === 1 && !isStylus(e)) {
         e.preventDefault();
         const {x, y} = getPointerPosition(e);
         ui.mouse_move(x, y);  
@@ -104,6 +433,7 @@ export function create_canvas_internal(ui, width, height) {
     }, { passive: false });
   
     canvasNode.addEventListener('touchend', e => {
+      if (isStylus(e)) return;
       ui.mouse_up();
     });
   } else {
@@ -130,10 +460,47 @@ export function create_canvas_internal(ui, width, height) {
     });
   }
 
+  function penInput(e) {
+    ui.pen_input(e.pointerId, e.pressure, e.tiltX, e.tiltY, (e.buttons & 2) !== 0);
+  }
+
+  canvasNode.addEventListener('pointerdown', e => {
+    if (e.pointerType === 'pen') {
+      // stops the browser from also sending compatibility mouse events
+      e.preventDefault();
+      canvasNode.setPointerCapture(e.pointerId);
+      const {x, y} = getPointerPosition(e);
+      penInput(e);
+      ui.pen_down(e.pointerId, x, y);
+    }
+  });
+
+  canvasNode.addEventListener('pointermove', e => {
+    if (e.pointerType === 'pen') {
+      const {x, y} = getPointerPosition(e);
+      penInput(e);
+      ui.pen_move(e.pointerId, x, y);
+    }
+  });
+
+  for (const type of ['pointerup', 'pointercancel']) {
+    canvasNode.addEventListener(type, e => {
+      if (e.pointerType === 'pen') {
+        ui.pen_up(e.pointerId);
+      }
+    });
+  }
+
+  canvasNode.addEventListener('pointerleave', e => {
+    if (e.pointerType === 'pen') {
+      ui.pen_leave(e.pointerId);
+    }
+  });
+
   canvasNode.addEventListener('wheel', e => {
     e.preventDefault();
     e.stopPropagation();
-    ui.mouse_wheel(e.deltaX, -e.deltaY);
+    ui.mouse_wheel(e.deltaX, -e.deltaY, e.deltaMode);
   });
 
   canvasNode.addEventListener('focus', e => {
@@ -144,10 +511,15 @@ export function create_canvas_internal(ui, width, height) {
     ui.focus(false);
   });
 
+  // the paste event is the fallback for when the async clipboard api isn't allowed, so it must not be prevented
+  function isPasteShortcut(e) {
+    return (e.metaKey || e.ctrlKey) && e.key === 'v';
+  }
+
   window.addEventListener('keydown', e => {
     if (document.activeElement === canvasNode) {
       ui.modifiers_changed(e.metaKey, e.shiftKey, e.altKey);
-      if (ui.key_down(e.key)) {
+      if (ui.key_down(e.key, e.code, e.repeat, e.location) && !isPasteShortcut(e)) {
         e.stopPropagation();
         e.preventDefault();
       }
@@ -157,13 +529,20 @@ export function create_canvas_internal(ui, width, height) {
   window.addEventListener('keyup', e => {
     if (document.activeElement === canvasNode) {
       ui.modifiers_changed(e.metaKey, e.shiftKey, e.altKey);
-      if (ui.key_up(e.key)) {
+      if (ui.key_up(e.key, e.code, e.location)) {
         e.stopPropagation();
         e.preventDefault();
       }
     }
   });
 
+  window.addEventListener('paste', e => {
+    if (document.activeElement === canvasNode) {
+      e.preventDefault();
+      ui.paste(e.clipboardData?.getData('text/plain') ?? '');
+    }
+  });
+
   document.addEventListener('dragover', e => {
     const {x, y} = getPointerPosition(e);
     ui.mouse_move(x, y);
@@ -209,12 +588,4 @@ export function create_canvas_internal(ui, width, height) {
       }
     }
   });
-
-  function draw(canvas) {
-    ui.on_draw(canvas);
-    ui.get_view().surface.requestAnimationFrame(draw);
-  }
-  ui.get_view().surface.requestAnimationFrame(draw);
-
-  return canvasNode;
 }
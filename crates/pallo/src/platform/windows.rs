@@ -2,7 +2,7 @@
 // (C) 2023 Neovide Contributors — licensed under the MIT license.
 // See README.md for full license text.
 
-use crate::{File, IntPoint, WindowEvent, int_point};
+use crate::{Error, File, IntPoint, MouseButton, PointerId, Rect, WindowEvent, int_point, point};
 use skia_safe::{
     ColorSpace, ColorType, Surface,
     gpu::{
@@ -13,13 +13,14 @@ use skia_safe::{
     surface::BackendSurfaceAccess,
 };
 use std::{
+    collections::VecDeque,
     ffi::{OsString, c_void},
     os::windows::ffi::OsStringExt,
     path::PathBuf,
 };
 use windows::{
     Win32::{
-        Foundation::{CloseHandle, HANDLE, HGLOBAL, HWND},
+        Foundation::{CloseHandle, HANDLE, HGLOBAL, HWND, POINT, RECT, WPARAM},
         Graphics::{
             Direct3D::D3D_FEATURE_LEVEL_11_0,
             Direct3D12::{
@@ -37,11 +38,15 @@ use windows::{
                     DXGI_SAMPLE_DESC,
                 },
                 CreateDXGIFactory1, DXGI_ADAPTER_FLAG, DXGI_ADAPTER_FLAG_SOFTWARE, DXGI_PRESENT,
-                DXGI_SCALING_STRETCH, DXGI_SWAP_CHAIN_DESC1, DXGI_SWAP_CHAIN_FLAG,
+                DXGI_PRESENT_PARAMETERS, DXGI_SCALING_STRETCH, DXGI_SWAP_CHAIN_DESC1, DXGI_SWAP_CHAIN_FLAG,
                 DXGI_SWAP_CHAIN_FLAG_FRAME_LATENCY_WAITABLE_OBJECT,
                 DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL, DXGI_USAGE_RENDER_TARGET_OUTPUT, IDXGIAdapter1,
                 IDXGIFactory2, IDXGISwapChain1, IDXGISwapChain3,
             },
+            Gdi::{
+                BI_RGB, BITMAPINFO, BITMAPINFOHEADER, DIB_RGB_COLORS, GetDC, ReleaseDC, SRCCOPY, ScreenToClient,
+                StretchDIBits,
+            },
         },
         System::{
             DataExchange::{
@@ -54,19 +59,28 @@ use windows::{
         },
         UI::{
             HiDpi::GetDpiForWindow,
+            Input::Pointer::{GetPointerPenInfo, GetPointerType, POINTER_PEN_INFO},
             Shell::{DragQueryFileW, HDROP},
+            WindowsAndMessaging::{
+                PEN_FLAG_BARREL, POINTER_INPUT_TYPE, PT_PEN, WM_POINTERDOWN, WM_POINTERLEAVE, WM_POINTERUP,
+                WM_POINTERUPDATE,
+            },
         },
     },
-    core::{Interface, PCWSTR, Result, w},
+    core::{HSTRING, Interface, PCWSTR},
 };
 
-use super::{Clipboard, PlatformCommon};
+#[cfg(feature = "vulkan")]
+use super::vulkan::VulkanSwapChain;
+use super::{
+    Clipboard, FileWatchers, PlatformCommon, PlatformErrors, Watcher, msaa::MsaaTarget, swap_chain::SwapChain,
+};
 
 pub struct WindowsClipboard {
     hwnd: HWND,
 }
 
-const CLIPBOARD_FORMAT: windows::core::PCWSTR = w!("com.httnn.tahti_clipboard.v1");
+const CLIPBOARD_FORMAT: &str = "com.httnn.tahti_clipboard.v1";
 
 struct ClipboardGuard;
 
@@ -86,49 +100,58 @@ impl Drop for ClipboardGuard {
 }
 
 impl Clipboard for WindowsClipboard {
-    fn write_string(&mut self, text: impl Into<String>) {
-        let mut clipboard = arboard::Clipboard::new().unwrap();
-        clipboard.set_text(text.into()).unwrap();
+    fn write_string(&mut self, text: impl Into<String>) -> Result<(), Error> {
+        let mut clipboard = arboard::Clipboard::new().map_err(|error| Error::Clipboard(error.to_string()))?;
+        clipboard.set_text(text.into()).map_err(|error| Error::Clipboard(error.to_string()))
     }
 
-    fn write_data(&mut self, data: Vec<u8>) {
+    fn write_data(&mut self, data: Vec<u8>) -> Result<(), Error> {
+        self.write_custom(CLIPBOARD_FORMAT, data)
+    }
+
+    fn read_data(&self) -> Option<Vec<u8>> {
+        self.read_custom(CLIPBOARD_FORMAT)
+    }
+
+    fn write_custom(&mut self, type_id: &str, data: Vec<u8>) -> Result<(), Error> {
+        let type_id = HSTRING::from(type_id);
         unsafe {
-            let clipboard_guard = ClipboardGuard::new(self.hwnd);
-            if clipboard_guard.is_none() {
-                return;
-            }
+            let _clipboard_guard =
+                ClipboardGuard::new(self.hwnd).ok_or_else(|| Error::Clipboard("couldn't open the clipboard".into()))?;
 
-            let format = RegisterClipboardFormatW(CLIPBOARD_FORMAT);
+            let format = RegisterClipboardFormatW(PCWSTR(type_id.as_ptr()));
             if format == 0 {
-                return;
+                return Err(Error::Clipboard(format!("couldn't register the clipboard format {type_id}")));
             }
 
             let _ = EmptyClipboard();
 
             let size = data.len();
-            if let Ok(hmem) = GlobalAlloc(GMEM_MOVEABLE, size) {
-                if hmem.is_invalid() {
-                    return;
-                }
+            let hmem = GlobalAlloc(GMEM_MOVEABLE, size).map_err(|error| Error::Clipboard(error.to_string()))?;
+            if hmem.is_invalid() {
+                return Err(Error::Clipboard("couldn't allocate clipboard memory".into()));
+            }
 
-                let ptr = GlobalLock(hmem);
-                if !ptr.is_null() {
-                    std::ptr::copy_nonoverlapping(data.as_ptr(), ptr as *mut u8, size);
-                    let _ = GlobalUnlock(hmem);
-                    let _ = SetClipboardData(format, Some(HANDLE(hmem.0)));
-                }
+            let ptr = GlobalLock(hmem);
+            if ptr.is_null() {
+                return Err(Error::Clipboard("couldn't lock clipboard memory".into()));
             }
+            std::ptr::copy_nonoverlapping(data.as_ptr(), ptr as *mut u8, size);
+            let _ = GlobalUnlock(hmem);
+            SetClipboardData(format, Some(HANDLE(hmem.0))).map_err(|error| Error::Clipboard(error.to_string()))?;
         }
+        Ok(())
     }
 
-    fn read_data(&self) -> Option<Vec<u8>> {
+    fn read_custom(&self, type_id: &str) -> Option<Vec<u8>> {
+        let type_id = HSTRING::from(type_id);
         unsafe {
             let clipboard_guard = ClipboardGuard::new(self.hwnd);
             if clipboard_guard.is_none() {
                 return None;
             }
 
-            let format = RegisterClipboardFormatW(CLIPBOARD_FORMAT);
+            let format = RegisterClipboardFormatW(PCWSTR(type_id.as_ptr()));
 
             if format != 0 && IsClipboardFormatAvailable(format).is_ok() {
                 let handle = GetClipboardData(format).ok()?;
@@ -155,7 +178,7 @@ impl Clipboard for WindowsClipboard {
     }
 
     fn read_string(&self) -> Option<String> {
-        let mut clipboard = arboard::Clipboard::new().unwrap();
+        let mut clipboard = arboard::Clipboard::new().ok()?;
         clipboard.get_text().ok()
     }
 
@@ -167,8 +190,9 @@ impl Clipboard for WindowsClipboard {
 
             let mut out = Vec::new();
             if IsClipboardFormatAvailable(CF_HDROP.0 as u32).is_ok() {
-                let hdrop = GetClipboardData(CF_HDROP.0 as u32).unwrap();
-                if !hdrop.is_invalid() {
+                if let Ok(hdrop) = GetClipboardData(CF_HDROP.0 as u32)
+                    && !hdrop.is_invalid()
+                {
                     // count files
                     let count = DragQueryFileW(HDROP(hdrop.0), u32::MAX, None);
                     // buffer for each path
@@ -195,7 +219,7 @@ impl Clipboard for WindowsClipboard {
             }
 
             let data = if IsClipboardFormatAvailable(CF_WAVE.0 as u32).is_ok() {
-                let hmem = HGLOBAL(GetClipboardData(CF_WAVE.0 as u32).unwrap().0);
+                let hmem = HGLOBAL(GetClipboardData(CF_WAVE.0 as u32).map_or(std::ptr::null_mut(), |handle| handle.0));
                 if !hmem.is_invalid() {
                     let ptr = GlobalLock(hmem);
                     if !ptr.is_null() {
@@ -220,7 +244,7 @@ impl Clipboard for WindowsClipboard {
     }
 }
 
-fn get_hardware_adapter(factory: &IDXGIFactory2) -> Result<IDXGIAdapter1> {
+fn get_hardware_adapter(factory: &IDXGIFactory2) -> windows::core::Result<IDXGIAdapter1> {
     for i in 0.. {
         let adapter = unsafe { factory.EnumAdapters1(i)? };
         let desc = unsafe { adapter.GetDesc1() }?;
@@ -247,42 +271,66 @@ fn get_hardware_adapter(factory: &IDXGIFactory2) -> Result<IDXGIAdapter1> {
     unreachable!()
 }
 
+fn graphics_error(context: &'static str) -> impl FnOnce(windows::core::Error) -> Error {
+    move |error| Error::Graphics(format!("{context}: {error}"))
+}
+
 pub struct Frame {
     surface: Surface,
     surface_index: usize,
+    dirty_rects: Vec<Rect>,
 }
 
 impl super::Frame for Frame {
     fn canvas(&mut self) -> crate::Canvas<'_> {
         crate::Canvas::new(self.surface.canvas())
     }
+
+    fn set_dirty_rects(&mut self, rects: Vec<Rect>) {
+        self.dirty_rects = rects;
+    }
 }
 
 const BUFFER_COUNT: u32 = 2;
 
+/// The graphics APIs that a `Platform` can draw with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GraphicsBackend {
+    #[default]
+    Direct3D12,
+    /// Needs the `vulkan` feature.
+    #[cfg(feature = "vulkan")]
+    Vulkan,
+    /// Draws on the CPU and copies the frames to the window with GDI. Slow, but works without a GPU, e.g. over
+    /// remote desktop or with broken drivers.
+    Software,
+}
+
+impl GraphicsBackend {
+    /// The backend named by the `PALLO_GRAPHICS_BACKEND` environment variable, `d3d12`, `vulkan` or `software`, so
+    /// that another one can be tried without rebuilding when a driver misbehaves. Falls back to the default one.
+    pub fn from_env() -> Self {
+        match std::env::var("PALLO_GRAPHICS_BACKEND").as_deref() {
+            #[cfg(feature = "vulkan")]
+            Ok("vulkan") => Self::Vulkan,
+            Ok("software") => Self::Software,
+            _ => Self::default(),
+        }
+    }
+}
+
 pub struct Platform {
     hwnd: HWND,
-    gr_context: DirectContext,
-    swap_chain: IDXGISwapChain3,
-    swap_chain_desc: DXGI_SWAP_CHAIN_DESC1,
-    swap_chain_waitable: HANDLE,
-    pub command_queue: ID3D12CommandQueue,
-    buffers: Vec<ID3D12Resource>,
-    surfaces: Vec<Option<Surface>>,
-    fence_values: Vec<u64>,
-    fence: ID3D12Fence,
-    fence_event: HANDLE,
-    frame_swapped: bool,
-    frame_index: usize,
+    swap_chain: Box<dyn SwapChain>,
+    /// The queue that frames are drawn with, when drawing with Direct3D 12.
+    pub command_queue: Option<ID3D12CommandQueue>,
+    #[cfg(feature = "gpu_profiling")]
+    pub device: Option<ID3D12Device>,
     size: IntPoint,
     clipboard: WindowsClipboard,
-    _backend_context: BackendContext,
-    #[cfg(feature = "gpu_profiling")]
-    pub device: ID3D12Device,
-    _adapter: IDXGIAdapter1,
-    _composition_device: IDCompositionDevice,
-    _target: IDCompositionTarget,
-    _visual: IDCompositionVisual,
+    event_queue: VecDeque<WindowEvent>,
+    file_watchers: FileWatchers,
+    errors: PlatformErrors,
 }
 
 unsafe impl Send for Platform {}
@@ -297,7 +345,11 @@ impl PlatformCommon for Platform {
     }
 
     fn next_window_event(&mut self) -> Option<WindowEvent> {
-        None
+        self.event_queue.pop_front().or_else(|| self.errors.next_event()).or_else(|| self.file_watchers.next_event())
+    }
+
+    fn watch_path(&mut self, path: PathBuf) -> Watcher {
+        self.file_watchers.watch(path)
     }
 
     fn start_drag(&self, _path: std::path::PathBuf) {}
@@ -317,26 +369,13 @@ impl PlatformCommon for Platform {
         let height = (size.1 as f32 * scale) as u32;
         self.size = (width, height).into();
 
-        // Clean up any outstanding resources in command lists
-        self.gr_context.flush_submit_and_sync_cpu();
-
-        self.wait_for_gpu();
-
-        self.surfaces.clear();
-        self.buffers.clear();
-
-        unsafe {
-            self.swap_chain
-                .ResizeBuffers(
-                    0,
-                    width,
-                    height,
-                    DXGI_FORMAT_UNKNOWN,
-                    DXGI_SWAP_CHAIN_FLAG(self.swap_chain_desc.Flags as i32),
-                )
-                .expect("Failed to resize buffers");
+        if let Err(error) = self.swap_chain.resize(self.size) {
+            self.errors.report(error);
         }
-        self.setup_surfaces();
+    }
+
+    fn set_sample_count(&mut self, count: usize) {
+        self.swap_chain.set_sample_count(count);
     }
 
     fn clipboard(&mut self) -> &mut impl Clipboard {
@@ -344,26 +383,34 @@ impl PlatformCommon for Platform {
     }
 
     fn open_path_in_file_explorer(&self, path: PathBuf) {
-        std::process::Command::new("explorer")
-            .arg("/select,")
-            .arg(path.to_path_buf().into_os_string())
-            .spawn()
-            .unwrap();
+        if let Err(error) =
+            std::process::Command::new("explorer").arg("/select,").arg(path.to_path_buf().into_os_string()).spawn()
+        {
+            self.errors.report(error.into());
+        }
     }
 
     fn file_open_dialog(&self, opts: super::FileOpenOptions) {
         std::thread::spawn(move || {
-            if let Some(path) = rfd::FileDialog::new()
-                .add_filter(opts.filetype_desc, &opts.extensions)
-                .set_directory("~")
-                .pick_file()
-            {
-                opts.result.set(vec![File::Path(path)]);
+            let mut dialog = rfd::FileDialog::new().set_directory("~");
+            if !opts.extensions.is_empty() {
+                dialog = dialog.add_filter(opts.filetype_desc, &opts.extensions);
+            }
+            // the dialog picks either files or folders, so folders win when both are allowed
+            let paths = match (opts.folder, opts.multi) {
+                (true, true) => dialog.pick_folders(),
+                (true, false) => dialog.pick_folder().map(|path| vec![path]),
+                (false, true) => dialog.pick_files(),
+                (false, false) => dialog.pick_file().map(|path| vec![path]),
+            };
+            if let Some(paths) = paths {
+                opts.result.set(paths.into_iter().map(File::from_path_buf).collect());
             }
         });
     }
 
     fn file_save_dialog(&self, options: super::FileSaveOptions) {
+        let errors = self.errors.sender();
         std::thread::spawn(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .set_file_name(options.filename)
@@ -371,7 +418,16 @@ impl PlatformCommon for Platform {
                 .set_directory("~")
                 .save_file()
             {
-                std::fs::write(path.clone(), (*options.data).clone()).unwrap();
+                match std::fs::write(&path, &*options.data) {
+                    Ok(()) => {
+                        if let Some(result) = &options.result {
+                            result.set(path);
+                        }
+                    }
+                    Err(error) => {
+                        let _ = errors.send(error.into());
+                    }
+                }
             }
         });
     }
@@ -389,61 +445,166 @@ impl PlatformCommon for Platform {
     type Frame = Frame;
 
     fn new_frame(&mut self) -> Option<Self::Frame> {
-        // Only block the cpu when whe actually need to draw to the canvas
-        if self.frame_swapped {
-            self.move_to_next_frame();
-        }
-        if let Some(mut surface) = self.surfaces[self.frame_index].take() {
-            surface.canvas().save();
-            Some(Frame {
-                surface,
-                surface_index: self.frame_index,
-            })
-        } else {
-            None
-        }
+        let (mut surface, surface_index) = self.swap_chain.acquire()?;
+        surface.canvas().save();
+        Some(Frame { surface, surface_index, dirty_rects: Vec::new() })
     }
 
     fn end_frame(&mut self, mut frame: Self::Frame) {
         frame.surface.canvas().restore();
-        self.surfaces[frame.surface_index] = Some(frame.surface);
-        // {
-        //     tracy_gpu_zone!("wait for vsync");
-        //     vsync.wait_for_vsync();
-        // }
-        self.swap_buffers();
+        self.swap_chain.present(frame.surface, frame.surface_index, &frame.dirty_rects);
     }
 }
 
 impl Platform {
-    pub fn new_from_window_handle(hwnd: *mut c_void) -> Self {
+    /// Creates a platform drawing into `hwnd` with the backend given by `GraphicsBackend::from_env`. If it can't be
+    /// set up, drawing falls back to `GraphicsBackend::Software` and the error is sent as
+    /// `WindowEvent::PlatformError`.
+    pub fn new_from_window_handle(hwnd: *mut c_void) -> Result<Self, Error> {
+        match Self::new_from_window_handle_with_backend(hwnd, GraphicsBackend::from_env()) {
+            Err(error @ Error::Graphics(_)) => {
+                let platform = Self::new_from_window_handle_with_backend(hwnd, GraphicsBackend::Software)?;
+                platform.errors.report(error);
+                Ok(platform)
+            }
+            result => result,
+        }
+    }
+
+    pub fn new_from_window_handle_with_backend(hwnd: *mut c_void, backend: GraphicsBackend) -> Result<Self, Error> {
         let hwnd = HWND(hwnd as *mut _);
+        let size = int_point(1000, 1000);
+        let mut command_queue = None;
+        #[cfg(feature = "gpu_profiling")]
+        let mut device = None;
+        let swap_chain: Box<dyn SwapChain> = match backend {
+            GraphicsBackend::Direct3D12 => {
+                let swap_chain = D3d12SwapChain::new(hwnd, size)?;
+                command_queue = Some(swap_chain.command_queue.clone());
+                #[cfg(feature = "gpu_profiling")]
+                {
+                    device = Some(swap_chain.device.clone());
+                }
+                Box::new(swap_chain)
+            }
+            #[cfg(feature = "vulkan")]
+            GraphicsBackend::Vulkan => Box::new(VulkanSwapChain::new(hwnd, size)?),
+            GraphicsBackend::Software => Box::new(SoftwareSwapChain::new(hwnd, size)?),
+        };
+
+        Ok(Self {
+            hwnd,
+            swap_chain,
+            command_queue,
+            #[cfg(feature = "gpu_profiling")]
+            device,
+            size,
+            clipboard: WindowsClipboard { hwnd: hwnd },
+            event_queue: VecDeque::new(),
+            file_watchers: Default::default(),
+            errors: Default::default(),
+        })
+    }
+
+    /// Handles `WM_POINTER*` messages coming from a pen. Hosts should call this from their window
+    /// procedure and skip their own handling of the message when it returns true.
+    pub fn handle_pointer_message(&mut self, msg: u32, wparam: WPARAM) -> bool {
+        if !matches!(msg, WM_POINTERDOWN | WM_POINTERUPDATE | WM_POINTERUP | WM_POINTERLEAVE) {
+            return false;
+        }
+
+        let pointer_id = (wparam.0 & 0xffff) as u32;
+        let mut pointer_type = POINTER_INPUT_TYPE::default();
+        if unsafe { GetPointerType(pointer_id, &mut pointer_type) }.is_err() || pointer_type != PT_PEN {
+            return false;
+        }
+
+        let id = PointerId::Pen(pointer_id as usize);
+        if msg == WM_POINTERLEAVE {
+            // only the state of a pen hovering out of range is dropped, a pressed one gets a WM_POINTERUP first
+            self.event_queue.push_back(WindowEvent::PointerLeave { id });
+            return true;
+        }
+
+        let mut info = POINTER_PEN_INFO::default();
+        if unsafe { GetPointerPenInfo(pointer_id, &mut info) }.is_err() {
+            return false;
+        }
+
+        let mut location: POINT = info.pointerInfo.ptPixelLocation;
+        let _ = unsafe { ScreenToClient(self.hwnd, &mut location) };
+        let scale = self.get_scale_factor();
+        let position = point(location.x as f32 / scale, location.y as f32 / scale);
+
+        self.event_queue.push_back(WindowEvent::PenInput {
+            id,
+            pressure: info.pressure as f32 / 1024.0,
+            tilt: point(info.tiltX as f32, info.tiltY as f32),
+            barrel_button: info.penFlags & PEN_FLAG_BARREL != 0,
+        });
+        self.event_queue.push_back(match msg {
+            WM_POINTERDOWN => WindowEvent::PointerDown { position, button: MouseButton::Left, id },
+            WM_POINTERUP => WindowEvent::PointerUp { id },
+            _ => WindowEvent::PointerMove { position, id },
+        });
+        true
+    }
+}
+
+/// Draws with Direct3D 12 into a swap chain shown with DirectComposition.
+struct D3d12SwapChain {
+    gr_context: DirectContext,
+    swap_chain: IDXGISwapChain3,
+    swap_chain_desc: DXGI_SWAP_CHAIN_DESC1,
+    swap_chain_waitable: HANDLE,
+    command_queue: ID3D12CommandQueue,
+    buffers: Vec<ID3D12Resource>,
+    surfaces: Vec<Option<Surface>>,
+    msaa: MsaaTarget,
+    fence_values: Vec<u64>,
+    fence: ID3D12Fence,
+    fence_event: HANDLE,
+    frame_swapped: bool,
+    frame_index: usize,
+    /// How many more frames to present in full before dirty rects can be used, since each buffer has to be shown in
+    /// full once after being created.
+    full_presents_left: u32,
+    size: IntPoint,
+    _backend_context: BackendContext,
+    #[cfg(feature = "gpu_profiling")]
+    device: ID3D12Device,
+    _adapter: IDXGIAdapter1,
+    _composition_device: IDCompositionDevice,
+    _target: IDCompositionTarget,
+    _visual: IDCompositionVisual,
+}
+
+impl D3d12SwapChain {
+    fn new(hwnd: HWND, size: IntPoint) -> Result<Self, Error> {
         #[cfg(feature = "d3d_debug")]
         let dxgi_factory: IDXGIFactory2 = unsafe {
             let mut debug_controller: Option<ID3D12Debug> = None;
             D3D12GetDebugInterface(&mut debug_controller)
-                .expect("Failed to create Direct3D debug controller");
+                .map_err(graphics_error("Failed to create Direct3D debug controller"))?;
 
-            debug_controller
-                .expect("Failed to enable debug layer")
-                .EnableDebugLayer();
+            debug_controller.ok_or_else(|| Error::Graphics("Failed to enable debug layer".into()))?.EnableDebugLayer();
 
-            CreateDXGIFactory2(DXGI_CREATE_FACTORY_DEBUG).expect("Failed to create DXGI factory")
+            CreateDXGIFactory2(DXGI_CREATE_FACTORY_DEBUG).map_err(graphics_error("Failed to create DXGI factory"))?
         };
 
         #[cfg(not(feature = "d3d_debug"))]
         let dxgi_factory: IDXGIFactory2 =
-            unsafe { CreateDXGIFactory1().expect("Failed to create DXGI factory") };
+            unsafe { CreateDXGIFactory1().map_err(graphics_error("Failed to create DXGI factory"))? };
 
         let adapter = get_hardware_adapter(&dxgi_factory)
-            .expect("Failed to find any suitable Direct3D 12 adapters");
+            .map_err(graphics_error("Failed to find any suitable Direct3D 12 adapters"))?;
 
         let mut device: Option<ID3D12Device> = None;
         unsafe {
             D3D12CreateDevice(&adapter, D3D_FEATURE_LEVEL_11_0, &mut device)
-                .expect("Failed to create a Direct3D 12 device");
+                .map_err(graphics_error("Failed to create a Direct3D 12 device"))?;
         }
-        let device = device.expect("Failed to create a Direct3D 12 device");
+        let device = device.ok_or_else(|| Error::Graphics("Failed to create a Direct3D 12 device".into()))?;
 
         // Describe and create the command queue.
         let queue_desc = D3D12_COMMAND_QUEUE_DESC {
@@ -454,11 +615,9 @@ impl Platform {
         let command_queue: ID3D12CommandQueue = unsafe {
             device
                 .CreateCommandQueue(&queue_desc)
-                .expect("Failed to create the Direct3D command queue")
+                .map_err(graphics_error("Failed to create the Direct3D command queue"))?
         };
 
-        let size = int_point(1000, 1000);
-
         // Describe and create the swap chain.
         let swap_chain_desc = DXGI_SWAP_CHAIN_DESC1 {
             Width: size.x as u32, // TODO: uhhh
@@ -480,46 +639,35 @@ impl Platform {
         let swap_chain = unsafe {
             dxgi_factory
                 .CreateSwapChainForComposition(&command_queue, &swap_chain_desc, None)
-                .expect("Failed to create the Direct3D swap chain")
+                .map_err(graphics_error("Failed to create the Direct3D swap chain"))?
         };
 
         let swap_chain: IDXGISwapChain3 =
-            IDXGISwapChain1::cast(&swap_chain).expect("Failed to cast");
+            IDXGISwapChain1::cast(&swap_chain).map_err(graphics_error("Failed to cast"))?;
 
         unsafe {
-            swap_chain
-                .SetMaximumFrameLatency(1)
-                .expect("Failed to set maximum frame latency");
+            swap_chain.SetMaximumFrameLatency(1).map_err(graphics_error("Failed to set maximum frame latency"))?;
         }
-        let composition_device: IDCompositionDevice = unsafe {
-            DCompositionCreateDevice2(None).expect("Could not create composition device")
-        };
+        let composition_device: IDCompositionDevice =
+            unsafe { DCompositionCreateDevice2(None).map_err(graphics_error("Could not create composition device"))? };
         let target = unsafe {
             composition_device
                 .CreateTargetForHwnd(hwnd, true)
-                .expect("Could not create composition target")
+                .map_err(graphics_error("Could not create composition target"))?
         };
         let visual = unsafe {
-            composition_device
-                .CreateVisual()
-                .expect("Could not create composition visual")
+            composition_device.CreateVisual().map_err(graphics_error("Could not create composition visual"))?
         };
 
         unsafe {
-            visual
-                .SetContent(&swap_chain)
-                .expect("Failed to set composition content");
-            target
-                .SetRoot(&visual)
-                .expect("Failed to set composition root");
-            composition_device
-                .Commit()
-                .expect("Failed to commit composition");
+            visual.SetContent(&swap_chain).map_err(graphics_error("Failed to set composition content"))?;
+            target.SetRoot(&visual).map_err(graphics_error("Failed to set composition root"))?;
+            composition_device.Commit().map_err(graphics_error("Failed to commit composition"))?;
         }
 
         let swap_chain_waitable = unsafe { swap_chain.GetFrameLatencyWaitableObject() };
         if swap_chain_waitable.is_invalid() {
-            panic!("Failed to get swapchain waitable object");
+            return Err(Error::Graphics("Failed to get swapchain waitable object".into()));
         }
 
         // use a high value to make it easier to track these in PIX
@@ -527,11 +675,11 @@ impl Platform {
         let fence: ID3D12Fence = unsafe {
             device
                 .CreateFence(fence_values[0], D3D12_FENCE_FLAG_NONE)
-                .expect("Failed to create fence")
+                .map_err(graphics_error("Failed to create fence"))?
         };
 
         let fence_event = unsafe {
-            CreateEventW(None, false, false, PCWSTR::null()).expect("Failed to create event")
+            CreateEventW(None, false, false, PCWSTR::null()).map_err(graphics_error("Failed to create event"))?
         };
         let frame_index = unsafe { swap_chain.GetCurrentBackBufferIndex() as usize };
 
@@ -543,11 +691,11 @@ impl Platform {
             protected_context: Protected::No,
         };
         let gr_context = unsafe {
-            DirectContext::new_d3d(&backend_context, None).expect("Failed to create Skia context")
+            DirectContext::new_d3d(&backend_context, None)
+                .ok_or_else(|| Error::Graphics("Failed to create Skia context".into()))?
         };
 
         let mut ret = Self {
-            hwnd,
             _adapter: adapter,
             #[cfg(feature = "gpu_profiling")]
             device,
@@ -559,36 +707,34 @@ impl Platform {
             _backend_context: backend_context,
             buffers: Vec::new(),
             surfaces: Vec::new(),
+            msaa: MsaaTarget::new(1),
             fence_values,
             fence,
             fence_event,
             frame_swapped: true,
             frame_index,
+            full_presents_left: BUFFER_COUNT,
             size,
-            clipboard: WindowsClipboard { hwnd: hwnd },
             _composition_device: composition_device,
             _target: target,
             _visual: visual,
         };
-        ret.setup_surfaces();
+        ret.setup_surfaces()?;
 
-        ret
+        Ok(ret)
     }
 
-    fn setup_surfaces(&mut self) {
+    fn setup_surfaces(&mut self) -> Result<(), Error> {
         let size = (
-            self.size.x.try_into().expect("Could not convert width"),
-            self.size.y.try_into().expect("Could not convert height"),
+            self.size.x.try_into().map_err(|_| Error::Graphics("Could not convert width".into()))?,
+            self.size.y.try_into().map_err(|_| Error::Graphics("Could not convert height".into()))?,
         );
 
         self.buffers.clear();
         self.surfaces.clear();
         for i in 0..self.swap_chain_desc.BufferCount {
-            let buffer: ID3D12Resource = unsafe {
-                self.swap_chain
-                    .GetBuffer(i)
-                    .expect("Could not get swapchain buffer")
-            };
+            let buffer: ID3D12Resource =
+                unsafe { self.swap_chain.GetBuffer(i).map_err(graphics_error("Could not get swapchain buffer"))? };
             self.buffers.push(buffer.clone());
 
             let info = TextureResourceInfo {
@@ -610,24 +756,25 @@ impl Platform {
                 ColorSpace::new_srgb(),
                 None,
             )
-            .expect("Could not create backend render target");
+            .ok_or_else(|| Error::Graphics("Could not create backend render target".into()))?;
             self.surfaces.push(Some(surface));
         }
         self.frame_index = unsafe { self.swap_chain.GetCurrentBackBufferIndex() as usize };
+        Ok(())
     }
 
-    fn wait_for_gpu(&mut self) {
+    fn wait_for_gpu(&mut self) -> Result<(), Error> {
         unsafe {
-            let current_fence_value = *self.fence_values.iter().max().unwrap();
+            let current_fence_value = self.fence_values.iter().copied().max().unwrap_or_default();
             // Schedule a Signal command in the queue.
             self.command_queue
                 .Signal(&self.fence, current_fence_value)
-                .unwrap();
+                .map_err(graphics_error("Failed to signal the fence"))?;
 
             // Wait until the fence has been processed.
             self.fence
                 .SetEventOnCompletion(current_fence_value, self.fence_event)
-                .unwrap();
+                .map_err(graphics_error("Failed to wait for the fence"))?;
             WaitForSingleObjectEx(self.fence_event, INFINITE, false);
 
             // Increment all fence values
@@ -635,9 +782,10 @@ impl Platform {
                 *v = current_fence_value + 1;
             }
         }
+        Ok(())
     }
 
-    fn move_to_next_frame(&mut self) {
+    fn move_to_next_frame(&mut self) -> Result<(), Error> {
         if self.frame_swapped {
             unsafe {
                 let current_fence_value = self.fence_values[self.frame_index];
@@ -645,7 +793,7 @@ impl Platform {
                 // Schedule a Signal command in the queue.
                 self.command_queue
                     .Signal(&self.fence, current_fence_value)
-                    .unwrap();
+                    .map_err(graphics_error("Failed to signal the fence"))?;
 
                 // Update the frame index.
                 self.frame_index = self.swap_chain.GetCurrentBackBufferIndex() as usize;
@@ -655,7 +803,7 @@ impl Platform {
                 if self.fence.GetCompletedValue() < old_fence_value {
                     self.fence
                         .SetEventOnCompletion(old_fence_value, self.fence_event)
-                        .unwrap();
+                        .map_err(graphics_error("Failed to wait for the fence"))?;
                     WaitForSingleObjectEx(self.fence_event, INFINITE, false);
                 }
 
@@ -664,9 +812,10 @@ impl Platform {
                 self.frame_swapped = false;
             }
         }
+        Ok(())
     }
 
-    fn swap_buffers(&mut self) {
+    fn swap_buffers(&mut self, dirty_rects: &[Rect]) {
         unsafe {
             // Switch the back buffer resource state to present For some reason the
             // DirectContext::flush_and_submit does not do that for us automatically.
@@ -679,7 +828,28 @@ impl Platform {
                 );
                 self.gr_context.submit(Some(SyncCpu::No));
 
-                if self.swap_chain.Present(1, DXGI_PRESENT(0)).is_ok() {
+                let presented = if dirty_rects.is_empty() || self.full_presents_left > 0 {
+                    self.full_presents_left = self.full_presents_left.saturating_sub(1);
+                    self.swap_chain.Present(1, DXGI_PRESENT(0))
+                } else {
+                    let mut rects: Vec<RECT> = dirty_rects
+                        .iter()
+                        .map(|rect| RECT {
+                            left: rect.left().floor().max(0.0) as i32,
+                            top: rect.top().floor().max(0.0) as i32,
+                            right: (rect.right().ceil() as i32).min(self.size.x),
+                            bottom: (rect.bottom().ceil() as i32).min(self.size.y),
+                        })
+                        .filter(|rect| rect.left < rect.right && rect.top < rect.bottom)
+                        .collect();
+                    let parameters = DXGI_PRESENT_PARAMETERS {
+                        DirtyRectsCount: rects.len() as u32,
+                        pDirtyRects: rects.as_mut_ptr(),
+                        ..Default::default()
+                    };
+                    self.swap_chain.Present1(1, DXGI_PRESENT(0), &parameters)
+                };
+                if presented.is_ok() {
                     self.frame_swapped = true;
                 }
             }
@@ -687,12 +857,147 @@ impl Platform {
     }
 }
 
-impl Drop for Platform {
+impl SwapChain for D3d12SwapChain {
+    fn resize(&mut self, size: IntPoint) -> Result<(), Error> {
+        self.size = size;
+
+        // Clean up any outstanding resources in command lists
+        self.gr_context.flush_submit_and_sync_cpu();
+
+        self.wait_for_gpu()?;
+
+        self.surfaces.clear();
+        self.buffers.clear();
+
+        let resized = unsafe {
+            self.swap_chain.ResizeBuffers(
+                0,
+                size.x as u32,
+                size.y as u32,
+                DXGI_FORMAT_UNKNOWN,
+                DXGI_SWAP_CHAIN_FLAG(self.swap_chain_desc.Flags as i32),
+            )
+        };
+        resized.map_err(graphics_error("Failed to resize buffers"))?;
+        self.full_presents_left = BUFFER_COUNT;
+        self.setup_surfaces()
+    }
+
+    fn acquire(&mut self) -> Option<(Surface, usize)> {
+        // Only block the cpu when whe actually need to draw to the canvas
+        if self.frame_swapped
+            && let Err(error) = self.move_to_next_frame()
+        {
+            crate::error!("{error}");
+            return None;
+        }
+        let surface = self.surfaces[self.frame_index].take()?;
+        match self.msaa.surface(&mut self.gr_context, &surface, ColorType::RGBA8888) {
+            Some(msaa_surface) => {
+                self.surfaces[self.frame_index] = Some(surface);
+                Some((msaa_surface, self.frame_index))
+            }
+            None => Some((surface, self.frame_index)),
+        }
+    }
+
+    fn present(&mut self, mut surface: Surface, index: usize, dirty_rects: &[Rect]) {
+        // the back buffer was kept if the frame was drawn with multisampling
+        match &mut self.surfaces[index] {
+            Some(target) => MsaaTarget::resolve(&mut surface, target),
+            None => self.surfaces[index] = Some(surface),
+        }
+        // {
+        //     tracy_gpu_zone!("wait for vsync");
+        //     vsync.wait_for_vsync();
+        // }
+        self.swap_buffers(dirty_rects);
+    }
+
+    fn set_sample_count(&mut self, count: usize) {
+        self.msaa.set_sample_count(count);
+    }
+}
+
+impl Drop for D3d12SwapChain {
     fn drop(&mut self) {
         unsafe {
             self.gr_context.release_resources_and_abandon();
-            self.wait_for_gpu();
-            CloseHandle(self.fence_event).unwrap();
+            let _ = self.wait_for_gpu();
+            let _ = CloseHandle(self.fence_event);
+        }
+    }
+}
+
+/// Draws into a raster surface and copies it to the window with GDI after each frame.
+struct SoftwareSwapChain {
+    hwnd: HWND,
+    surface: Option<Surface>,
+}
+
+impl SoftwareSwapChain {
+    fn new(hwnd: HWND, size: IntPoint) -> Result<Self, Error> {
+        let mut swap_chain = Self { hwnd, surface: None };
+        swap_chain.resize(size)?;
+        Ok(swap_chain)
+    }
+}
+
+impl SwapChain for SoftwareSwapChain {
+    fn resize(&mut self, size: IntPoint) -> Result<(), Error> {
+        if size.x <= 0 || size.y <= 0 {
+            // minimized, there's nothing to draw into until the window is shown again
+            self.surface = None;
+            return Ok(());
+        }
+        // N32 is BGRA on Windows, which is what a 32 bit DIB expects
+        self.surface = Some(
+            skia_safe::surfaces::raster_n32_premul((size.x, size.y))
+                .ok_or_else(|| Error::Graphics("Failed to create the raster surface".into()))?,
+        );
+        Ok(())
+    }
+
+    fn acquire(&mut self) -> Option<(Surface, usize)> {
+        Some((self.surface.take()?, 0))
+    }
+
+    fn present(&mut self, mut surface: Surface, _index: usize, _dirty_rects: &[Rect]) {
+        if let Some(pixmap) = surface.peek_pixels() {
+            let (width, height) = (pixmap.width(), pixmap.height());
+            let info = BITMAPINFO {
+                bmiHeader: BITMAPINFOHEADER {
+                    biSize: size_of::<BITMAPINFOHEADER>() as u32,
+                    biWidth: pixmap.row_bytes_as_pixels() as i32,
+                    // negative for rows going from top to bottom
+                    biHeight: -height,
+                    biPlanes: 1,
+                    biBitCount: 32,
+                    biCompression: BI_RGB.0,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            unsafe {
+                let hdc = GetDC(Some(self.hwnd));
+                StretchDIBits(
+                    hdc,
+                    0,
+                    0,
+                    width,
+                    height,
+                    0,
+                    0,
+                    width,
+                    height,
+                    Some(pixmap.addr()),
+                    &info,
+                    DIB_RGB_COLORS,
+                    SRCCOPY,
+                );
+                ReleaseDC(Some(self.hwnd), hdc);
+            }
         }
+        self.surface = Some(surface);
     }
 }
@@ -1,4 +1,7 @@
-use std::rc::Rc;
+use std::{
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
 
 use rustc_hash::FxHashMap;
 
@@ -58,7 +61,7 @@ impl Property {
     }
 }
 
-#[derive(Clone, Eq, Hash, Copy)]
+#[derive(Clone, Eq, Copy)]
 pub struct PropertyId {
     pub id: usize,
     #[cfg(debug_assertions)]
@@ -71,6 +74,17 @@ impl PartialEq for PropertyId {
     }
 }
 
+impl Hash for PropertyId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+#[cfg(debug_assertions)]
+thread_local! {
+    static REGISTERED_NAMES: std::cell::RefCell<FxHashMap<usize, &'static str>> = std::cell::RefCell::default();
+}
+
 impl PropertyId {
     pub const fn new(id: usize, #[allow(unused)] name: &'static str) -> PropertyId {
         Self {
@@ -79,6 +93,16 @@ impl PropertyId {
             name,
         }
     }
+
+    /// Panics if a property with a different name has already been registered with the same id, since `property_id!`
+    /// derives ids from a hash of the name.
+    #[cfg(debug_assertions)]
+    fn check_collision(&self) {
+        REGISTERED_NAMES.with_borrow_mut(|names| {
+            let name = *names.entry(self.id).or_insert(self.name);
+            assert!(name == self.name, "property ids of \"{name}\" and \"{}\" collide, rename one of them", self.name);
+        });
+    }
 }
 
 #[derive(Default)]
@@ -89,6 +113,8 @@ pub struct PropertyStore {
 
 impl PropertyStore {
     pub fn set(&mut self, key: PropertyId, value: Property) {
+        #[cfg(debug_assertions)]
+        key.check_collision();
         self.map.insert(key, value);
     }
 
@@ -122,3 +148,30 @@ impl PropertyStore {
         self.map.remove(&key)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pallo_macro::property_id;
+
+    use super::*;
+
+    #[test]
+    fn property_ids_come_from_the_name() {
+        assert!(property_id!("size") == property_id!("size"));
+        assert!(property_id!("size") != property_id!("color"));
+
+        let mut store = PropertyStore::default();
+        store.set(property_id!("size"), Property::Any(Rc::new(1)));
+        store.set(property_id!("size"), Property::Any(Rc::new(2)));
+        assert_eq!(*store.get(property_id!("size")).unwrap().as_any::<i32>(), 2);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "property ids of \"first\" and \"second\" collide")]
+    fn colliding_property_ids_panic() {
+        let mut store = PropertyStore::default();
+        store.set(PropertyId::new(42, "first"), Property::Any(Rc::new(())));
+        store.set(PropertyId::new(42, "second"), Property::Any(Rc::new(())));
+    }
+}
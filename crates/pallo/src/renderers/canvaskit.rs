@@ -1,9 +1,15 @@
-use crate::{BorderRadius, Color, Fill, IntPoint, Join, Point, RasterSurfaceType, Rect, rgba};
-use js_sys::{Array, Float32Array, Object, Reflect, Uint16Array};
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{BorderRadius, Color, Fill, IntPoint, Join, Matrix, Point, RasterSurfaceType, Rect, rgba};
+use js_sys::{Array, Float32Array, Object, Reflect, Uint8Array, Uint16Array};
 use rustc_hash::FxHashMap;
 use wasm_bindgen::prelude::*;
 
-use super::{Cap, FontVariable, ImageType};
+use super::{
+    Cap, DisplayListType, FontVariable, FrameDisposal, ImageFrame, ImageType, ShaderEffectType, SurfacePoolStats,
+    VectorFormat, Vertices,
+    surface_pool::{SurfacePool, bucket_size},
+};
 
 #[wasm_bindgen]
 extern "C" {
@@ -23,7 +29,10 @@ extern "C" {
     fn getCanvas(this: &JsSurface) -> JsCanvas;
 
     #[wasm_bindgen(method, js_class = JsSurface, js_namespace = CanvasKit)]
-    fn makeImageSnapshot(this: &JsSurface) -> JsImage;
+    fn makeImageSnapshot(this: &JsSurface, bounds: Vec<i32>) -> JsImage;
+
+    #[wasm_bindgen(method, js_class = JsSurface, js_namespace = CanvasKit)]
+    fn delete(this: &JsSurface);
 
     #[wasm_bindgen(js_name = Shader, js_namespace = CanvasKit)]
     type JsShader;
@@ -40,6 +49,30 @@ extern "C" {
         tile_mode: &JsValue,
     ) -> JsShader;
 
+    #[wasm_bindgen(js_name = RuntimeEffect, js_namespace = CanvasKit)]
+    type JsRuntimeEffect;
+
+    #[wasm_bindgen(js_name = Make, js_namespace = ["CanvasKit", "RuntimeEffect"])]
+    fn MakeRuntimeEffect(sksl: &str, on_error: &Closure<dyn FnMut(String)>) -> Option<JsRuntimeEffect>;
+
+    #[wasm_bindgen(method, js_class = RuntimeEffect, js_namespace = CanvasKit)]
+    fn makeShader(this: &JsRuntimeEffect, uniforms: Vec<f32>) -> Option<JsShader>;
+
+    #[wasm_bindgen(method, js_class = RuntimeEffect, js_namespace = CanvasKit)]
+    fn getUniformCount(this: &JsRuntimeEffect) -> usize;
+
+    #[wasm_bindgen(method, js_class = RuntimeEffect, js_namespace = CanvasKit)]
+    fn getUniformFloatCount(this: &JsRuntimeEffect) -> usize;
+
+    #[wasm_bindgen(method, js_class = RuntimeEffect, js_namespace = CanvasKit)]
+    fn getUniformName(this: &JsRuntimeEffect, index: usize) -> String;
+
+    #[wasm_bindgen(method, js_class = RuntimeEffect, js_namespace = CanvasKit)]
+    fn getUniform(this: &JsRuntimeEffect, index: usize) -> JsValue;
+
+    #[wasm_bindgen(method, js_class = RuntimeEffect, js_namespace = CanvasKit)]
+    fn delete(this: &JsRuntimeEffect);
+
     #[wasm_bindgen(js_name = Typeface, js_namespace = CanvasKit)]
     type JsTypeface;
 
@@ -49,6 +82,9 @@ extern "C" {
     #[wasm_bindgen(js_namespace = ["CanvasKit", "Typeface"])]
     fn MakeTypefaceFromData(text: &[u8]) -> JsTypeface;
 
+    #[wasm_bindgen(method, js_class = Typeface, js_namespace = CanvasKit)]
+    fn makeVariation(this: &JsTypeface, coordinates: &Array) -> Option<JsTypeface>;
+
     #[wasm_bindgen(js_name = Font, js_namespace = CanvasKit)]
     type JsFont;
 
@@ -82,6 +118,9 @@ extern "C" {
     #[wasm_bindgen(method, js_class = Font, js_namespace = CanvasKit)]
     fn getGlyphWidths(this: &JsFont, glyph_ids: &Uint16Array) -> Float32Array;
 
+    #[wasm_bindgen(method, js_class = Font, js_namespace = CanvasKit)]
+    fn getMetrics(this: &JsFont) -> JsValue;
+
     #[wasm_bindgen(method, js_class = Font, js_namespace = CanvasKit)]
     fn delete(this: &JsFont);
 
@@ -109,9 +148,33 @@ extern "C" {
     #[wasm_bindgen(js_namespace = CanvasKit)]
     fn MakeImageFromEncoded(data: &[u8]) -> JsImage;
 
+    #[wasm_bindgen(method, js_class = Image, js_namespace = CanvasKit)]
+    fn encodeToBytes(this: &JsImage) -> Option<Uint8Array>;
+
     #[wasm_bindgen(method, js_class = Image, js_namespace = CanvasKit)]
     fn delete(this: &JsImage);
 
+    #[wasm_bindgen(js_name = AnimatedImage, js_namespace = CanvasKit)]
+    type JsAnimatedImage;
+
+    #[wasm_bindgen(js_namespace = CanvasKit)]
+    fn MakeAnimatedImageFromEncoded(data: &[u8]) -> Option<JsAnimatedImage>;
+
+    #[wasm_bindgen(method, js_class = AnimatedImage, js_namespace = CanvasKit)]
+    fn getFrameCount(this: &JsAnimatedImage) -> usize;
+
+    #[wasm_bindgen(method, js_class = AnimatedImage, js_namespace = CanvasKit)]
+    fn currentFrameDuration(this: &JsAnimatedImage) -> f32;
+
+    #[wasm_bindgen(method, js_class = AnimatedImage, js_namespace = CanvasKit)]
+    fn decodeNextFrame(this: &JsAnimatedImage) -> f32;
+
+    #[wasm_bindgen(method, js_class = AnimatedImage, js_namespace = CanvasKit)]
+    fn makeImageAtCurrentFrame(this: &JsAnimatedImage) -> Option<JsImage>;
+
+    #[wasm_bindgen(method, js_class = AnimatedImage, js_namespace = CanvasKit)]
+    fn delete(this: &JsAnimatedImage);
+
     #[wasm_bindgen(js_name = Path, js_namespace = CanvasKit)]
     type JsPath;
 
@@ -267,9 +330,21 @@ extern "C" {
     #[wasm_bindgen(method, js_class = Canvas, js_namespace = CanvasKit)]
     fn save(this: &JsCanvas);
 
+    #[wasm_bindgen(method, js_class = Canvas, js_namespace = CanvasKit)]
+    fn getDeviceClipBounds(this: &JsCanvas) -> Vec<i32>;
+
+    #[wasm_bindgen(method, js_class = Canvas, js_namespace = CanvasKit)]
+    fn getTotalMatrix(this: &JsCanvas) -> Vec<f32>;
+
     #[wasm_bindgen(method, js_class = Canvas, js_namespace = CanvasKit)]
     fn restore(this: &JsCanvas);
 
+    #[wasm_bindgen(method, js_class = Canvas, js_namespace = CanvasKit)]
+    fn getSaveCount(this: &JsCanvas) -> i32;
+
+    #[wasm_bindgen(method, js_class = Canvas, js_namespace = CanvasKit)]
+    fn restoreToCount(this: &JsCanvas, count: i32);
+
     #[wasm_bindgen(method, js_class = Canvas, js_namespace = CanvasKit)]
     fn drawPath(this: &JsCanvas, path: &JsPath, paint: &JsPaint);
 
@@ -289,6 +364,9 @@ extern "C" {
     #[wasm_bindgen(method, js_class = Canvas, js_namespace = CanvasKit)]
     fn clipRect(this: &JsCanvas, rect: &JsRect, op: &JsValue, antialias: bool);
 
+    #[wasm_bindgen(method, js_class = Canvas, js_namespace = CanvasKit)]
+    fn clipRRect(this: &JsCanvas, rrect: Vec<f32>, op: &JsValue, antialias: bool);
+
     #[wasm_bindgen(method, js_class = Canvas, js_namespace = CanvasKit)]
     fn clear(this: &JsCanvas, color: &Array);
 
@@ -310,6 +388,51 @@ extern "C" {
     #[wasm_bindgen(method, js_class = Canvas, js_namespace = CanvasKit)]
     fn drawImage(this: &JsCanvas, image: &JsImage, position_x: f32, position_y: f32, paint: &JsPaint);
 
+    #[wasm_bindgen(method, js_class = Canvas, js_namespace = CanvasKit)]
+    fn drawPicture(this: &JsCanvas, picture: &JsPicture);
+
+    #[wasm_bindgen(method, js_class = Canvas, js_namespace = CanvasKit)]
+    fn readPixels(this: &JsCanvas, x: i32, y: i32, image_info: &JsValue) -> Option<Vec<u8>>;
+
+    #[wasm_bindgen(method, js_class = Canvas, js_namespace = CanvasKit)]
+    fn drawVertices(this: &JsCanvas, vertices: &JsVertices, blend_mode: &JsValue, paint: &JsPaint);
+
+    #[wasm_bindgen(js_name = Vertices, js_namespace = CanvasKit)]
+    type JsVertices;
+
+    #[wasm_bindgen(js_namespace = CanvasKit)]
+    fn MakeVertices(
+        mode: &JsValue,
+        positions: Vec<f32>,
+        tex_coords: Option<Vec<f32>>,
+        colors: Option<Vec<f32>>,
+        indices: Option<Vec<u16>>,
+    ) -> JsVertices;
+
+    #[wasm_bindgen(method, js_class = Vertices, js_namespace = CanvasKit)]
+    fn delete(this: &JsVertices);
+
+    #[wasm_bindgen(js_name = PictureRecorder, js_namespace = CanvasKit)]
+    type JsPictureRecorder;
+
+    #[wasm_bindgen(constructor, js_class = PictureRecorder, js_namespace = CanvasKit)]
+    fn new() -> JsPictureRecorder;
+
+    #[wasm_bindgen(method, js_class = PictureRecorder, js_namespace = CanvasKit)]
+    fn beginRecording(this: &JsPictureRecorder, bounds: &JsRect) -> JsCanvas;
+
+    #[wasm_bindgen(method, js_class = PictureRecorder, js_namespace = CanvasKit)]
+    fn finishRecordingAsPicture(this: &JsPictureRecorder) -> JsPicture;
+
+    #[wasm_bindgen(method, js_class = PictureRecorder, js_namespace = CanvasKit)]
+    fn delete(this: &JsPictureRecorder);
+
+    #[wasm_bindgen(js_name = SkPicture, js_namespace = CanvasKit)]
+    type JsPicture;
+
+    #[wasm_bindgen(method, js_class = SkPicture, js_namespace = CanvasKit)]
+    fn delete(this: &JsPicture);
+
     #[wasm_bindgen(method, js_class = Canvas, js_namespace = CanvasKit)]
     fn saveLayer(this: &JsCanvas, paint: &JsValue, rect: &JsRect, backdrop: &JsImageFilter, flags: i32);
 
@@ -332,6 +455,9 @@ extern "C" {
     #[wasm_bindgen(thread_local_v2, js_name = Intersect, js_namespace = ["CanvasKit", "ClipOp"])]
     static CLIP_OP_INTERSECT: JsValue;
 
+    #[wasm_bindgen(thread_local_v2, js_name = Difference, js_namespace = ["CanvasKit", "ClipOp"])]
+    static CLIP_OP_DIFFERENCE: JsValue;
+
     #[wasm_bindgen(thread_local_v2, js_name = Linear, js_namespace = ["CanvasKit", "FilterMode"])]
     static FILTER_MODE_LINEAR: JsValue;
 
@@ -386,6 +512,9 @@ extern "C" {
     #[wasm_bindgen(thread_local_v2, js_name = EvenOdd, js_namespace = ["CanvasKit", "FillType"])]
     static FILL_TYPE_EVEN_ODD: JsValue;
 
+    #[wasm_bindgen(thread_local_v2, js_name = Triangles, js_namespace = ["CanvasKit", "VertexMode"])]
+    static VERTEX_MODE_TRIANGLES: JsValue;
+
     #[wasm_bindgen(thread_local_v2, js_name = Clear, js_namespace = ["CanvasKit", "BlendMode"])]
     static BLEND_MODE_CLEAR: JsValue;
 
@@ -480,16 +609,44 @@ extern "C" {
     static SAVE_LAYER_INIT_WITH_PREVIOUS: i32;
 }
 
+/// A surface in a pool, which is deleted if the pool drops it rather than handing it out.
+struct PooledSurface(Option<JsSurface>);
+
+impl Drop for PooledSurface {
+    fn drop(&mut self) {
+        if let Some(surface) = &self.0 {
+            surface.delete();
+        }
+    }
+}
+
 pub struct Surface {
     surface: JsSurface,
     size: IntPoint,
+    // the part of the surface that is drawn, which is smaller than the surface itself when it came from a pool
     scaled_size: IntPoint,
+    pool: Option<(Rc<RefCell<SurfacePool<PooledSurface>>>, IntPoint)>,
+}
+
+impl Surface {
+    fn snapshot(&self) -> JsImage {
+        self.surface.makeImageSnapshot(vec![0, 0, self.scaled_size.x, self.scaled_size.y])
+    }
+}
+
+impl Drop for Surface {
+    fn drop(&mut self) {
+        match self.pool.take() {
+            Some((pool, bucket)) => pool.borrow_mut().give_back(bucket, PooledSurface(Some(self.surface.clone()))),
+            None => self.surface.delete(),
+        }
+    }
 }
 
 impl RasterSurfaceType<Renderer> for Surface {
     fn new(size: IntPoint, scale_factor: f32) -> Self {
         let scaled_size = size.with_scale(scale_factor);
-        Self { surface: MakeSurface(scaled_size.x as usize, scaled_size.y as usize), size, scaled_size }
+        Self { surface: MakeSurface(scaled_size.x as usize, scaled_size.y as usize), size, scaled_size, pool: None }
     }
 
     fn get_canvas<'a>(&'a self) -> Canvas {
@@ -503,6 +660,18 @@ impl RasterSurfaceType<Renderer> for Surface {
     fn get_size(&self) -> IntPoint {
         self.size
     }
+
+    fn encode_png(&self) -> Option<Vec<u8>> {
+        let image = self.snapshot();
+        let data = image.encodeToBytes().map(|data| data.to_vec());
+        image.delete();
+        data
+    }
+
+    fn read_pixels(&self, rect: Rect) -> Option<Vec<u8>> {
+        let (offset, size) = super::pixel_bounds(rect, self.scaled_size)?;
+        self.surface.getCanvas().readPixels(offset.x, offset.y, &rgba_image_info(size.x, size.y))
+    }
 }
 
 pub struct Font {
@@ -535,28 +704,57 @@ impl super::FontType for Font {
         let glyph_widths = self.font.getGlyphWidths(&glyph_ids);
         glyph_widths.to_vec()
     }
+
+    fn get_ascent(&self) -> f32 {
+        -self.get_metric("ascent")
+    }
+
+    fn get_descent(&self) -> f32 {
+        self.get_metric("descent")
+    }
+
+    fn get_line_height(&self) -> f32 {
+        self.get_descent() + self.get_ascent() + self.get_metric("leading")
+    }
 }
 
-pub struct TextBlob {
-    blob: JsTextBlob,
+impl Font {
+    fn get_metric(&self, name: &str) -> f32 {
+        Reflect::get(&self.font.getMetrics(), &name.into()).ok().and_then(|value| value.as_f64()).unwrap_or(0.0) as f32
+    }
 }
 
-impl Drop for TextBlob {
+/// Deletes the blob when the last `TextBlob` sharing it is dropped.
+struct TextBlobHandle(JsTextBlob);
+
+impl Drop for TextBlobHandle {
     fn drop(&mut self) {
-        if !self.blob.is_null() {
-            self.blob.delete();
+        if !self.0.is_null() {
+            self.0.delete();
         }
     }
 }
 
+#[derive(Clone)]
+pub struct TextBlob {
+    blob: Rc<TextBlobHandle>,
+}
+
 impl super::TextBlobType<Renderer> for TextBlob {
     fn new(text: String, font: &Font) -> Option<Self> {
-        Some(Self { blob: MakeFromText(text, &font.font) })
+        Some(Self { blob: Rc::new(TextBlobHandle(MakeFromText(text, &font.font))) })
     }
 }
 
+/// A typeface id and variation axes with the values' bits.
+type VariationKey = (usize, Vec<(String, u32)>);
+
 pub struct Renderer {
     typefaces: FxHashMap<usize, JsTypeface>,
+    /// Typefaces with variations applied, or `None` if the typeface has no variation axes.
+    variations: RefCell<FxHashMap<VariationKey, Option<JsTypeface>>>,
+    // shared with the surfaces that came from it, which give themselves back when dropped
+    surface_pool: Rc<RefCell<SurfacePool<PooledSurface>>>,
 }
 
 impl Drop for Renderer {
@@ -564,13 +762,28 @@ impl Drop for Renderer {
         for (_, typeface) in &self.typefaces {
             typeface.delete();
         }
+        for typeface in self.variations.get_mut().values().flatten() {
+            typeface.delete();
+        }
     }
 }
 
 impl Default for Renderer {
     fn default() -> Self {
-        Self { typefaces: Default::default() }
+        Self { typefaces: Default::default(), variations: Default::default(), surface_pool: Default::default() }
+    }
+}
+
+fn make_variation(typeface: &JsTypeface, variables: &[FontVariable]) -> Option<JsTypeface> {
+    let coordinates = Array::new();
+    for variable in variables {
+        let axis = variable.axis.bytes().fold(0u32, |tag, byte| (tag << 8) | byte as u32);
+        let coordinate = Object::new();
+        Reflect::set(&coordinate, &"axis".into(), &axis.into()).unwrap();
+        Reflect::set(&coordinate, &"value".into(), &variable.value.into()).unwrap();
+        coordinates.push(&coordinate);
     }
+    typeface.makeVariation(&coordinates)
 }
 
 impl super::RendererType for Renderer {
@@ -578,23 +791,83 @@ impl super::RendererType for Renderer {
     type TextBlob = TextBlob;
     type Image = Image;
     type Path = Path;
+    type PathBuilder = PathBuilder;
     type Canvas<'a> = Canvas;
     type Surface = Surface;
+    type DisplayList = DisplayList;
 
     fn add_typeface(&mut self, id: impl Into<usize>, data: &[u8]) {
-        self.typefaces.insert(id.into(), MakeTypefaceFromData(data));
+        let id = id.into();
+        self.variations.get_mut().retain(|(typeface_id, _), typeface| {
+            if *typeface_id == id
+                && let Some(typeface) = typeface
+            {
+                typeface.delete();
+            }
+            *typeface_id != id
+        });
+        if let Some(typeface) = self.typefaces.insert(id, MakeTypefaceFromData(data)) {
+            typeface.delete();
+        }
     }
 
-    fn create_font(&self, id: impl Into<usize>, font_size: f32, _variables: Vec<FontVariable>) -> Font {
-        // TODO: implement variable font
+    fn create_font(&self, id: impl Into<usize>, font_size: f32, variables: Vec<FontVariable>) -> Font {
+        let id = id.into();
+        let typeface = &self.typefaces[&id];
         let font = JsFont::new();
-        font.setTypeface(&self.typefaces[&id.into()]);
+        if variables.is_empty() {
+            font.setTypeface(typeface);
+        } else {
+            let key = (id, variables.iter().map(|variable| (variable.get_axis(), variable.value.to_bits())).collect());
+            let mut variations = self.variations.borrow_mut();
+            let variation = variations.entry(key).or_insert_with(|| make_variation(typeface, &variables));
+            font.setTypeface(variation.as_ref().unwrap_or(typeface));
+        }
         font.setSubpixel(true);
         font.setEdging(&FONT_EDGING_SUBPIXEL_AA.with(JsValue::clone));
         font.setHinting(&FONT_HINTING_FULL.with(JsValue::clone));
         font.setSize(font_size);
         Font { font }
     }
+
+    fn create_surface(&self, size: IntPoint, scale_factor: f32) -> Surface {
+        let scaled_size = size.with_scale(scale_factor);
+        let bucket = bucket_size(scaled_size);
+        let surface = self
+            .surface_pool
+            .borrow_mut()
+            .take(bucket, |bucket| PooledSurface(Some(MakeSurface(bucket.x as usize, bucket.y as usize))))
+            .0
+            .take()
+            .unwrap();
+        let canvas = surface.getCanvas();
+        // everything that was done to the canvas of a reused surface is above the save that was made when it was
+        // handed out before, including transforms made without saving
+        canvas.restoreToCount(1);
+        canvas.clear(&to_skia_color(rgba(0x00000000)));
+        canvas.save();
+        Surface { surface, size, scaled_size, pool: Some((self.surface_pool.clone(), bucket)) }
+    }
+
+    fn trim_surface_pool(&mut self, max_bytes: usize) {
+        self.surface_pool.borrow_mut().trim(max_bytes);
+    }
+
+    fn surface_pool_stats(&self) -> SurfacePoolStats {
+        self.surface_pool.borrow().stats()
+    }
+
+    // CanvasKit doesn't expose its glyph cache
+    fn glyph_cache_bytes(&self) -> usize {
+        0
+    }
+
+    fn purge_glyph_cache(&mut self) {}
+
+    fn export_vector(_format: VectorFormat, _size: Point, _list: &DisplayList) -> Result<Vec<u8>, crate::Error> {
+        // canvaskit is built without skia's pdf and svg backends
+        Err(crate::Error::Graphics("vector export isn't supported on the web".into()))
+    }
 }
 
 pub struct Image {
@@ -609,19 +882,35 @@ impl Drop for Image {
 
 impl super::ImageType for Image {
     fn from_data(data: &[u8], width: i32, height: i32) -> Option<Image> {
-        let image_info = Object::new();
-        Reflect::set(&image_info, &"width".into(), &width.into()).unwrap();
-        Reflect::set(&image_info, &"height".into(), &height.into()).unwrap();
-        Reflect::set(&image_info, &"alphaType".into(), &ALPHA_TYPE_UNPREMUL.with(JsValue::clone)).unwrap();
-        Reflect::set(&image_info, &"colorSpace".into(), &COLOR_SPACE_SRGB.with(JsValue::clone)).unwrap();
-        Reflect::set(&image_info, &"colorType".into(), &COLOR_TYPE_RGBA_8888.with(JsValue::clone)).unwrap();
-        Some(Self { image: MakeImage(&image_info.into(), data, (width * 4) as usize) })
+        Some(Self { image: MakeImage(&rgba_image_info(width, height), data, (width * 4) as usize) })
     }
 
     fn from_encoded(data: &[u8]) -> Option<Image> {
         Some(Self { image: MakeImageFromEncoded(data) })
     }
 
+    fn frames_from_encoded(data: &[u8]) -> Option<Vec<ImageFrame<Image>>> {
+        let animated_image = MakeAnimatedImageFromEncoded(data)?;
+        let frame_count = animated_image.getFrameCount();
+        let mut frames = Vec::with_capacity(frame_count);
+        if frame_count > 1 {
+            // canvaskit composites the frames itself and doesn't expose how they are disposed
+            for _ in 0..frame_count {
+                let Some(image) = animated_image.makeImageAtCurrentFrame() else {
+                    break;
+                };
+                frames.push(ImageFrame {
+                    image: Self { image },
+                    duration_ms: animated_image.currentFrameDuration(),
+                    disposal: FrameDisposal::Keep,
+                });
+                animated_image.decodeNextFrame();
+            }
+        }
+        animated_image.delete();
+        (frames.len() > 1).then_some(frames)
+    }
+
     fn get_bounds(&self) -> Rect {
         Rect::from_xywh(0.0, 0.0, self.image.width(), self.image.height())
     }
@@ -644,11 +933,119 @@ fn make_rounded_rect(rect: Rect, radius: BorderRadius) -> Vec<f32> {
     ]
 }
 
+/// Deletes the effect when the last `ShaderEffect` sharing it is dropped.
+struct RuntimeEffectHandle(JsRuntimeEffect);
+
+impl Drop for RuntimeEffectHandle {
+    fn drop(&mut self) {
+        self.0.delete();
+    }
+}
+
+#[derive(Clone)]
+pub struct ShaderEffect {
+    effect: Rc<RuntimeEffectHandle>,
+    uniforms: Vec<f32>,
+}
+
+impl ShaderEffect {
+    fn make_shader(&self) -> Option<JsShader> {
+        self.effect.0.makeShader(self.uniforms.clone())
+    }
+}
+
+impl ShaderEffectType for ShaderEffect {
+    fn new(source: &str) -> Result<Self, crate::Error> {
+        let error = Rc::new(RefCell::new(String::new()));
+        let on_error = Closure::<dyn FnMut(String)>::new({
+            let error = error.clone();
+            move |message: String| *error.borrow_mut() = message
+        });
+        let effect = MakeRuntimeEffect(source, &on_error).ok_or_else(|| crate::Error::Graphics(error.take()))?;
+        let uniforms = vec![0.0; effect.getUniformFloatCount()];
+        Ok(Self { effect: Rc::new(RuntimeEffectHandle(effect)), uniforms })
+    }
+
+    fn set_uniform(&mut self, name: &str, values: &[f32]) -> Result<(), crate::Error> {
+        let effect = &self.effect.0;
+        let index = (0..effect.getUniformCount())
+            .find(|&index| effect.getUniformName(index) == name)
+            .ok_or_else(|| crate::Error::Graphics(format!("the shader has no uniform called {name}")))?;
+        let uniform = effect.getUniform(index);
+        let get = |key: &str| {
+            Reflect::get(&uniform, &key.into()).ok().and_then(|value| value.as_f64()).unwrap_or(0.0) as usize
+        };
+        let (slot, size) = (get("slot"), get("columns") * get("rows"));
+        if size != values.len() {
+            return Err(crate::Error::Graphics(format!("{name} takes {size} floats")));
+        }
+        self.uniforms[slot..slot + size].copy_from_slice(values);
+        Ok(())
+    }
+}
+
+/// Deletes the picture when the last `DisplayList` sharing it is dropped.
+struct PictureHandle(JsPicture);
+
+impl Drop for PictureHandle {
+    fn drop(&mut self) {
+        self.0.delete();
+    }
+}
+
+#[derive(Clone)]
+pub struct DisplayList {
+    picture: Rc<PictureHandle>,
+    bounds: Rect,
+}
+
+impl DisplayListType<Renderer> for DisplayList {
+    fn record(bounds: Rect, draw: impl FnOnce(&mut Canvas)) -> Self {
+        let recorder = JsPictureRecorder::new();
+        draw(&mut Canvas::new(recorder.beginRecording(&to_skia_rect(bounds))));
+        let picture = recorder.finishRecordingAsPicture();
+        recorder.delete();
+        Self { picture: Rc::new(PictureHandle(picture)), bounds }
+    }
+
+    fn get_bounds(&self) -> Rect {
+        self.bounds
+    }
+}
+
+/// Deletes the path when the last `Path` sharing it is dropped.
+struct PathHandle(JsPath);
+
+impl Drop for PathHandle {
+    fn drop(&mut self) {
+        if !self.0.isDeleted() {
+            self.0.delete();
+        }
+    }
+}
+
+// js objects can't leave their thread, so there's no use for an `Arc`
+#[derive(Clone)]
 pub struct Path {
+    path: Rc<PathHandle>,
+}
+
+impl super::PathType for Path {
+    fn with_offset(&self, value: Point) -> Self {
+        Self { path: Rc::new(PathHandle(self.path.0.copy().offset(value.x, value.y))) }
+    }
+
+    fn with_scale(&self, value: Point) -> Self {
+        let matrix = scaled(value.x, value.y);
+        Self { path: Rc::new(PathHandle(self.path.0.copy().transform(&matrix))) }
+    }
+}
+
+pub struct PathBuilder {
     path: JsPath,
 }
 
-impl Drop for Path {
+impl Drop for PathBuilder {
     fn drop(&mut self) {
         if !self.path.isDeleted() {
             self.path.delete();
@@ -656,13 +1053,13 @@ impl Drop for Path {
     }
 }
 
-impl Default for Path {
+impl Default for PathBuilder {
     fn default() -> Self {
         Self { path: JsPath::new() }
     }
 }
 
-impl super::PathType for Path {
+impl super::PathBuilderType<Renderer> for PathBuilder {
     fn fill_type_even_odd(&mut self) {
         self.path.setFillType(&FILL_TYPE_EVEN_ODD.with(JsValue::clone));
     }
@@ -711,19 +1108,59 @@ impl super::PathType for Path {
         self
     }
 
-    fn with_offset(&self, value: Point) -> Self {
-        Self { path: self.path.copy().offset(value.x, value.y) }
+    fn reset(&mut self) {
+        self.path.reset();
     }
 
-    fn with_scale(&mut self, value: Point) -> Self {
-        let matrix = scaled(value.x, value.y);
-        let path = self.path.transform(&matrix);
-        Self { path }
+    fn build(&self) -> Path {
+        Path { path: Rc::new(PathHandle(self.path.copy())) }
     }
+}
 
-    fn reset(&mut self) {
-        self.path.reset();
-    }
+fn to_skia_blend_mode(blend_mode: super::BlendMode) -> JsValue {
+    let blend_mode = match blend_mode {
+        super::BlendMode::Clear => &BLEND_MODE_CLEAR,
+        super::BlendMode::Src => &BLEND_MODE_SRC,
+        super::BlendMode::Dst => &BLEND_MODE_DST,
+        super::BlendMode::SrcOver => &BLEND_MODE_SRCOVER,
+        super::BlendMode::DstOver => &BLEND_MODE_DSTOVER,
+        super::BlendMode::SrcIn => &BLEND_MODE_SRCIN,
+        super::BlendMode::DstIn => &BLEND_MODE_DSTIN,
+        super::BlendMode::SrcOut => &BLEND_MODE_SRCOUT,
+        super::BlendMode::DstOut => &BLEND_MODE_DSTOUT,
+        super::BlendMode::SrcATop => &BLEND_MODE_SRCATOP,
+        super::BlendMode::DstATop => &BLEND_MODE_DSTATOP,
+        super::BlendMode::Xor => &BLEND_MODE_XOR,
+        super::BlendMode::Plus => &BLEND_MODE_PLUS,
+        super::BlendMode::Modulate => &BLEND_MODE_MODULATE,
+        super::BlendMode::Screen => &BLEND_MODE_SCREEN,
+        super::BlendMode::Overlay => &BLEND_MODE_OVERLAY,
+        super::BlendMode::Darken => &BLEND_MODE_DARKEN,
+        super::BlendMode::Lighten => &BLEND_MODE_LIGHTEN,
+        super::BlendMode::ColorDodge => &BLEND_MODE_COLORDODGE,
+        super::BlendMode::ColorBurn => &BLEND_MODE_COLORBURN,
+        super::BlendMode::HardLight => &BLEND_MODE_HARDLIGHT,
+        super::BlendMode::SoftLight => &BLEND_MODE_SOFTLIGHT,
+        super::BlendMode::Difference => &BLEND_MODE_DIFFERENCE,
+        super::BlendMode::Exclusion => &BLEND_MODE_EXCLUSION,
+        super::BlendMode::Multiply => &BLEND_MODE_MULTIPLY,
+        super::BlendMode::Hue => &BLEND_MODE_HUE,
+        super::BlendMode::Saturation => &BLEND_MODE_SATURATION,
+        super::BlendMode::Color => &BLEND_MODE_COLOR,
+        super::BlendMode::Luminosity => &BLEND_MODE_LUMINOSITY,
+    };
+    blend_mode.with(JsValue::clone)
+}
+
+/// An `ImageInfo` for rows of RGBA bytes without premultiplied alpha.
+fn rgba_image_info(width: i32, height: i32) -> JsValue {
+    let image_info = Object::new();
+    Reflect::set(&image_info, &"width".into(), &width.into()).unwrap();
+    Reflect::set(&image_info, &"height".into(), &height.into()).unwrap();
+    Reflect::set(&image_info, &"alphaType".into(), &ALPHA_TYPE_UNPREMUL.with(JsValue::clone)).unwrap();
+    Reflect::set(&image_info, &"colorSpace".into(), &COLOR_SPACE_SRGB.with(JsValue::clone)).unwrap();
+    Reflect::set(&image_info, &"colorType".into(), &COLOR_TYPE_RGBA_8888.with(JsValue::clone)).unwrap();
+    image_info.into()
 }
 
 fn to_skia_rect(rect: Rect) -> JsRect {
@@ -755,6 +1192,9 @@ pub struct Canvas {
     alpha_mul: f32,
     prev_scale: f32,
     blend_mode: JsValue,
+    // mirrors the canvas' transform without the scale factor, along with the ones saved by `save`
+    matrix: Matrix,
+    saved_matrices: Vec<Matrix>,
 }
 
 impl Drop for Canvas {
@@ -775,6 +1215,8 @@ impl Canvas {
             alpha_mul: 1.0,
             prev_scale: 1.0,
             blend_mode: BLEND_MODE_SRCATOP.with(JsValue::clone),
+            matrix: Matrix::IDENTITY,
+            saved_matrices: vec![],
         }
     }
 }
@@ -785,12 +1227,15 @@ impl super::CanvasType<Renderer> for Canvas {
         self.canvas.save();
         self.scale_factor = scale_factor;
         self.scale(1.0);
+        self.matrix = Matrix::IDENTITY;
+        self.saved_matrices.clear();
     }
 
     fn scale(&mut self, mut factor: f32) -> &mut Self {
         self.canvas.scale(1.0 / self.prev_scale, 1.0 / self.prev_scale);
         factor *= self.scale_factor;
         self.canvas.scale(factor, factor);
+        self.matrix = self.matrix * Matrix::scale(factor / self.prev_scale);
         self.prev_scale = factor;
         self
     }
@@ -807,38 +1252,7 @@ impl super::CanvasType<Renderer> for Canvas {
 
     fn with_blend_mode(&mut self, blend_mode: super::BlendMode, cb: impl FnOnce(&mut Self)) -> &mut Self {
         let prev_blend = self.blend_mode.clone();
-        self.blend_mode = match blend_mode {
-            super::BlendMode::Clear => &BLEND_MODE_CLEAR,
-            super::BlendMode::Src => &BLEND_MODE_SRC,
-            super::BlendMode::Dst => &BLEND_MODE_DST,
-            super::BlendMode::SrcOver => &BLEND_MODE_SRCOVER,
-            super::BlendMode::DstOver => &BLEND_MODE_DSTOVER,
-            super::BlendMode::SrcIn => &BLEND_MODE_SRCIN,
-            super::BlendMode::DstIn => &BLEND_MODE_DSTIN,
-            super::BlendMode::SrcOut => &BLEND_MODE_SRCOUT,
-            super::BlendMode::DstOut => &BLEND_MODE_DSTOUT,
-            super::BlendMode::SrcATop => &BLEND_MODE_SRCATOP,
-            super::BlendMode::DstATop => &BLEND_MODE_DSTATOP,
-            super::BlendMode::Xor => &BLEND_MODE_XOR,
-            super::BlendMode::Plus => &BLEND_MODE_PLUS,
-            super::BlendMode::Modulate => &BLEND_MODE_MODULATE,
-            super::BlendMode::Screen => &BLEND_MODE_SCREEN,
-            super::BlendMode::Overlay => &BLEND_MODE_OVERLAY,
-            super::BlendMode::Darken => &BLEND_MODE_DARKEN,
-            super::BlendMode::Lighten => &BLEND_MODE_LIGHTEN,
-            super::BlendMode::ColorDodge => &BLEND_MODE_COLORDODGE,
-            super::BlendMode::ColorBurn => &BLEND_MODE_COLORBURN,
-            super::BlendMode::HardLight => &BLEND_MODE_HARDLIGHT,
-            super::BlendMode::SoftLight => &BLEND_MODE_SOFTLIGHT,
-            super::BlendMode::Difference => &BLEND_MODE_DIFFERENCE,
-            super::BlendMode::Exclusion => &BLEND_MODE_EXCLUSION,
-            super::BlendMode::Multiply => &BLEND_MODE_MULTIPLY,
-            super::BlendMode::Hue => &BLEND_MODE_HUE,
-            super::BlendMode::Saturation => &BLEND_MODE_SATURATION,
-            super::BlendMode::Color => &BLEND_MODE_COLOR,
-            super::BlendMode::Luminosity => &BLEND_MODE_LUMINOSITY,
-        }
-        .with(JsValue::clone);
+        self.blend_mode = to_skia_blend_mode(blend_mode);
         self.paint.setBlendMode(&self.blend_mode);
         (cb)(self);
         self.paint.setBlendMode(&prev_blend);
@@ -852,10 +1266,10 @@ impl super::CanvasType<Renderer> for Canvas {
     }
 
     fn draw_path_at(&mut self, path: &Path, bounds: Rect) -> &mut Self {
-        self.canvas.save();
-        self.canvas.translate(bounds.a.x, bounds.a.y);
-        self.canvas.drawPath(&path.path, &self.paint);
-        self.canvas.restore();
+        self.save();
+        self.translate(bounds.a);
+        self.canvas.drawPath(&path.path.0, &self.paint);
+        self.restore();
         self
     }
 
@@ -889,19 +1303,52 @@ impl super::CanvasType<Renderer> for Canvas {
         self
     }
 
+    fn with_group_alpha(&mut self, alpha: f32, cb: impl FnOnce(&mut Self)) -> &mut Self {
+        if alpha >= 1.0 {
+            (cb)(self);
+            return self;
+        }
+        let paint = JsPaint::new();
+        paint.setColor(&to_skia_color(rgba(0x000000ff).with_alpha(alpha.max(0.0))));
+        self.canvas.saveLayer(&paint, JsValue::NULL.unchecked_ref(), JsValue::NULL.unchecked_ref(), 0);
+        self.saved_matrices.push(self.matrix);
+        (cb)(self);
+        self.restore();
+        paint.delete();
+        self
+    }
+
     fn with_clip_path(&mut self, path: &Path, cb: impl FnOnce(&mut Self)) -> &mut Self {
-        self.canvas.save();
-        self.canvas.clipPath(&path.path, &CLIP_OP_INTERSECT.with(JsValue::clone), true);
+        self.save();
+        self.canvas.clipPath(&path.path.0, &CLIP_OP_INTERSECT.with(JsValue::clone), true);
         (cb)(self);
-        self.canvas.restore();
+        self.restore();
         self
     }
 
     fn with_clip_rect(&mut self, clip_rect: Rect, cb: impl FnOnce(&mut Self)) -> &mut Self {
-        self.canvas.save();
+        self.save();
         self.canvas.clipRect(&to_skia_rect(clip_rect), &CLIP_OP_INTERSECT.with(JsValue::clone), true);
         (cb)(self);
-        self.canvas.restore();
+        self.restore();
+        self
+    }
+
+    fn with_clip_rrect(
+        &mut self,
+        rect: Rect,
+        radius: impl Into<BorderRadius>,
+        op: super::ClipOp,
+        cb: impl FnOnce(&mut Self),
+    ) -> &mut Self {
+        let op = match op {
+            super::ClipOp::Intersect => CLIP_OP_INTERSECT.with(JsValue::clone),
+            super::ClipOp::Difference => CLIP_OP_DIFFERENCE.with(JsValue::clone),
+        };
+        self.save();
+        self.canvas.clipRRect(make_rounded_rect(rect, radius.into()), &op, true);
+        (cb)(self);
+        self.restore();
         self
     }
 
@@ -914,18 +1361,24 @@ impl super::CanvasType<Renderer> for Canvas {
             }
             Fill::Gradient(gradient) => {
                 self.color(rgba(0x000000ff));
-                let colors =
-                    gradient.colors.map(|c| to_skia_color_f32_array(c))[..gradient.num_positions as usize].to_vec();
+                let colors = gradient.colors.iter().map(|&color| to_skia_color_f32_array(color)).collect();
                 let shader = MakeLinearGradient(
                     to_skia_point(gradient.points.0),
                     to_skia_point(gradient.points.1),
                     colors,
-                    gradient.positions[..gradient.num_positions as usize].to_vec(),
+                    gradient.positions,
                     &TILEMODE_CLAMP.with(JsValue::clone),
                 );
                 self.paint.setShader(&shader);
                 shader.delete();
             }
+            Fill::Shader(shader) => {
+                self.color(rgba(0x000000ff));
+                if let Some(shader) = shader.make_shader() {
+                    self.paint.setShader(&shader);
+                    shader.delete();
+                }
+            }
         }
         self
     }
@@ -940,18 +1393,24 @@ impl super::CanvasType<Renderer> for Canvas {
             }
             Fill::Gradient(gradient) => {
                 self.color(rgba(0x000000ff));
-                let colors =
-                    gradient.colors.map(|c| to_skia_color_f32_array(c))[..gradient.num_positions as usize].to_vec();
+                let colors = gradient.colors.iter().map(|&color| to_skia_color_f32_array(color)).collect();
                 let shader = MakeLinearGradient(
                     to_skia_point(gradient.points.0),
                     to_skia_point(gradient.points.1),
                     colors,
-                    gradient.positions[..gradient.num_positions as usize].to_vec(),
+                    gradient.positions,
                     &TILEMODE_CLAMP.with(JsValue::clone),
                 );
                 self.paint.setShader(&shader);
                 shader.delete();
             }
+            Fill::Shader(shader) => {
+                self.color(rgba(0x000000ff));
+                if let Some(shader) = shader.make_shader() {
+                    self.paint.setShader(&shader);
+                    shader.delete();
+                }
+            }
         }
         self
     }
@@ -1010,20 +1469,55 @@ impl super::CanvasType<Renderer> for Canvas {
     }
 
     fn draw_text(&mut self, blob: &TextBlob, position: Point) -> &mut Self {
-        self.canvas.drawTextBlob(&blob.blob, position.x, position.y, &self.paint);
+        self.canvas.drawTextBlob(&blob.blob.0, position.x, position.y, &self.paint);
         self
     }
 
     fn draw_surface(&mut self, surface: &Surface, position: Point) -> &mut Self {
         self.canvas.save();
         self.canvas.scale(1.0 / self.scale_factor, 1.0 / self.scale_factor);
-        let image = surface.surface.makeImageSnapshot();
+        let image = surface.snapshot();
         self.canvas.drawImage(&image, position.x * self.scale_factor, position.y * self.scale_factor, &self.paint);
         image.delete();
         self.canvas.restore();
         self
     }
 
+    fn draw_vertices(&mut self, vertices: &Vertices, blend_mode: super::BlendMode) -> &mut Self {
+        let flatten = |points: &[Point]| points.iter().flat_map(|point| [point.x, point.y]).collect::<Vec<_>>();
+        let colors = vertices
+            .colors
+            .iter()
+            .map(|color| color.with_alpha_mul(self.alpha_mul))
+            .flat_map(|color| [color.red(), color.green(), color.blue(), color.alpha()])
+            .collect::<Vec<_>>();
+        let vertices = MakeVertices(
+            &VERTEX_MODE_TRIANGLES.with(JsValue::clone),
+            flatten(&vertices.positions),
+            (!vertices.tex_coords.is_empty()).then(|| flatten(&vertices.tex_coords)),
+            (!colors.is_empty()).then_some(colors),
+            (!vertices.indices.is_empty()).then(|| vertices.indices.clone()),
+        );
+        self.canvas.drawVertices(&vertices, &to_skia_blend_mode(blend_mode), &self.paint);
+        vertices.delete();
+        self
+    }
+
+    fn draw_display_list(&mut self, list: &DisplayList) -> &mut Self {
+        if self.alpha_mul >= 1.0 {
+            self.canvas.drawPicture(&list.picture.0);
+            return self;
+        }
+        // pictures are drawn without a paint, so the alpha is applied to a layer instead
+        let paint = JsPaint::new();
+        paint.setColor(&to_skia_color(rgba(0x000000ff).with_alpha(self.alpha_mul)));
+        self.canvas.saveLayer(&paint, &to_skia_rect(list.bounds), JsValue::NULL.unchecked_ref(), 0);
+        self.canvas.drawPicture(&list.picture.0);
+        self.canvas.restore();
+        paint.delete();
+        self
+    }
+
     fn write_pixels(&mut self, size: IntPoint, offset: IntPoint, pixels: &[u8]) -> &mut Self {
         self.canvas.writePixels(
             pixels,
@@ -1039,28 +1533,28 @@ impl super::CanvasType<Renderer> for Canvas {
     }
 
     fn with_rotation(&mut self, degrees: f32, point: impl Into<Point>, cb: impl FnOnce(&mut Self)) -> &mut Self {
-        self.canvas.save();
+        self.save();
         let p: Point = point.into();
         self.canvas.rotate(degrees, p.x, p.y);
+        self.matrix = self.matrix * Matrix::rotation(degrees, p);
         (cb)(self);
-        self.canvas.restore();
+        self.restore();
         self
     }
 
     fn with_scale(&mut self, scale: f32, cb: impl FnOnce(&mut Self)) -> &mut Self {
-        self.canvas.save();
+        self.save();
         self.scale(scale);
         (cb)(self);
-        self.canvas.restore();
+        self.restore();
         self
     }
 
     fn with_translation(&mut self, amount: impl Into<Point>, cb: impl FnOnce(&mut Self)) -> &mut Self {
-        let p: Point = amount.into();
-        self.canvas.save();
-        self.canvas.translate(p.x, p.y);
+        self.save();
+        self.translate(amount);
         cb(self);
-        self.canvas.restore();
+        self.restore();
         self
     }
 
@@ -1079,23 +1573,79 @@ impl super::CanvasType<Renderer> for Canvas {
 
     fn save(&mut self) -> &mut Self {
         self.canvas.save();
+        self.saved_matrices.push(self.matrix);
         self
     }
 
     fn restore(&mut self) -> &mut Self {
         self.canvas.restore();
+        if let Some(matrix) = self.saved_matrices.pop() {
+            self.matrix = matrix;
+        }
         self
     }
 
+    fn catch_unwind(&mut self, cb: impl FnOnce(&mut Self)) -> std::thread::Result<()> {
+        let save_count = self.canvas.getSaveCount();
+        let (matrix, saved_matrices, alpha_mul, prev_scale) =
+            (self.matrix, self.saved_matrices.len(), self.alpha_mul, self.prev_scale);
+        let blend_mode = self.blend_mode.clone();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| cb(self)));
+        if result.is_err() {
+            self.canvas.restoreToCount(save_count);
+            self.matrix = matrix;
+            self.saved_matrices.truncate(saved_matrices);
+            self.alpha_mul = alpha_mul;
+            self.prev_scale = prev_scale;
+            self.paint.setBlendMode(&blend_mode);
+            self.blend_mode = blend_mode;
+            self.paint.setMaskFilter(&JsValue::null());
+        }
+        result
+    }
+
     fn scale_rel(&mut self, point: impl Into<Point>) -> &mut Self {
         let p: Point = point.into();
         self.canvas.scale(p.x, p.y);
+        self.matrix = self.matrix * Matrix::scale(p);
         self
     }
 
     fn translate(&mut self, point: impl Into<Point>) -> &mut Self {
         let p: Point = point.into();
         self.canvas.translate(p.x, p.y);
+        self.matrix = self.matrix * Matrix::translation(p);
         self
     }
+
+    fn local_clip_bounds(&self) -> Option<Rect> {
+        let device = self.canvas.getDeviceClipBounds();
+        if device[2] <= device[0] || device[3] <= device[1] {
+            return None;
+        }
+        // map the device clip back through the inverse of the affine part of the 3x3 matrix
+        let m = self.canvas.getTotalMatrix();
+        let det = m[0] * m[4] - m[1] * m[3];
+        if det == 0.0 {
+            return None;
+        }
+        let to_local = |x: f32, y: f32| {
+            let (x, y) = (x - m[2], y - m[5]);
+            Point::new((m[4] * x - m[1] * y) / det, (m[0] * y - m[3] * x) / det)
+        };
+        let (left, top, right, bottom) = (device[0] as f32, device[1] as f32, device[2] as f32, device[3] as f32);
+        let corners = [
+            to_local(left, top),
+            to_local(right, top),
+            to_local(left, bottom),
+            to_local(right, bottom),
+        ];
+        let a = corners.iter().fold(corners[0], |a, corner| a.min(*corner));
+        let b = corners.iter().fold(corners[0], |b, corner| b.max(*corner));
+        Some(Rect::from_ab(a, b))
+    }
+
+    fn current_matrix(&self) -> Matrix {
+        self.matrix
+    }
 }
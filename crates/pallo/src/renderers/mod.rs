@@ -1,11 +1,14 @@
-use crate::{Color, Gradient, IntPoint, Point, Rect};
+use crate::{Color, Error, Gradient, IntPoint, Later, Matrix, Point, Rect, point, rgba};
 
 #[cfg_attr(any(target_os = "macos", target_os = "windows", target_os = "ios"), path = "skia.rs")]
 #[cfg_attr(target_family = "wasm", path = "canvaskit.rs")]
 pub mod renderer;
+mod surface_pool;
 
 pub use renderer::*;
+pub use surface_pool::SurfacePoolStats;
 
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct BorderRadius {
     pub left: f32,
     pub top: f32,
@@ -28,6 +31,7 @@ impl From<Point> for BorderRadius {
 pub enum Fill {
     Color(Color),
     Gradient(Gradient),
+    Shader(ShaderEffect),
 }
 
 #[allow(unused)]
@@ -47,8 +51,13 @@ impl FontVariable {
     pub fn get_axis(&self) -> String {
         self.axis.into()
     }
+
+    pub fn get_value(&self) -> f32 {
+        self.value
+    }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Cap {
     Butt,
     Round,
@@ -64,7 +73,14 @@ pub enum Join {
 pub trait FontType {
     fn get_cap_height(&self) -> f32;
     fn get_string_width(&self, str: &str) -> f32;
+    /// One advance width per `char` in `str`.
     fn get_glyph_widths(&self, str: &str) -> Vec<f32>;
+    /// The distance from the baseline to the top of the tallest glyphs, as a positive number.
+    fn get_ascent(&self) -> f32;
+    /// The distance from the baseline to the bottom of the lowest glyphs.
+    fn get_descent(&self) -> f32;
+    /// The recommended distance between the baselines of consecutive lines.
+    fn get_line_height(&self) -> f32;
 }
 
 pub trait TextBlobType<B: RendererType> {
@@ -73,17 +89,114 @@ pub trait TextBlobType<B: RendererType> {
         Self: Sized;
 }
 
+/// A shader written in SkSL, with a `half4 main(float2 coord)` function that returns the color at `coord` in the
+/// canvas' coordinates. Clones share the compiled shader but have their own uniforms.
+pub trait ShaderEffectType: Clone {
+    /// Compiles `source`, or returns an error that tells what's wrong with it.
+    fn new(source: &str) -> Result<Self, Error>
+    where
+        Self: Sized;
+    /// Sets the `float`, `vec` or `mat` uniform `name`, which starts out as zeros.
+    fn set_uniform(&mut self, name: &str, values: &[f32]) -> Result<(), Error>;
+
+    fn with_uniform(mut self, name: &str, values: &[f32]) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        self.set_uniform(name, values)?;
+        Ok(self)
+    }
+}
+
 pub trait ImageType {
     fn from_encoded(data: &[u8]) -> Option<Self>
     where
         Self: Sized;
     fn from_data(data: &[u8], width: i32, height: i32) -> Option<Self>
+    where
+        Self: Sized;
+    /// Decodes every frame of an animated GIF, APNG or WebP, or returns `None` if `data` isn't an animated image.
+    /// Each frame is composited onto the frames before it, so it can be drawn on its own.
+    fn frames_from_encoded(data: &[u8]) -> Option<Vec<ImageFrame<Self>>>
     where
         Self: Sized;
     fn get_bounds(&self) -> Rect;
 }
 
-pub trait PathType {
+/// What happens to the area of a frame of an animated image before the next frame is drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameDisposal {
+    /// The frame is left in place.
+    Keep,
+    /// The area is cleared to transparent.
+    RestoreBackground,
+    /// The area is restored to what it was before the frame was drawn.
+    RestorePrevious,
+}
+
+pub struct ImageFrame<I> {
+    pub image: I,
+    pub duration_ms: f32,
+    pub disposal: FrameDisposal,
+}
+
+/// The decoded frames of an animated image.
+pub struct AnimatedImage {
+    frames: Vec<ImageFrame<Image>>,
+    duration_ms: f32,
+}
+
+impl AnimatedImage {
+    pub fn from_encoded(data: &[u8]) -> Option<Self> {
+        let mut frames = Image::frames_from_encoded(data)?;
+        // like browsers, treat frames that are too short to be intentional as lasting 100 ms
+        for frame in &mut frames {
+            if frame.duration_ms <= 10.0 {
+                frame.duration_ms = 100.0;
+            }
+        }
+        let duration_ms = frames.iter().map(|frame| frame.duration_ms).sum();
+        Some(Self { frames, duration_ms })
+    }
+
+    pub fn frames(&self) -> &[ImageFrame<Image>] {
+        &self.frames
+    }
+
+    /// The duration of a single loop through all of the frames.
+    pub fn duration_ms(&self) -> f32 {
+        self.duration_ms
+    }
+
+    /// The index of the frame shown at `time_ms`, looping back to the first frame after `duration_ms`.
+    pub fn frame_index_at(&self, time_ms: f32) -> usize {
+        frame_index_at(self.frames.iter().map(|frame| frame.duration_ms), self.duration_ms, time_ms)
+    }
+
+    pub fn frame_at(&self, time_ms: f32) -> &Image {
+        &self.frames[self.frame_index_at(time_ms)].image
+    }
+}
+
+/// The index of the frame shown at `time_ms` given how long each frame lasts, looping every `duration_ms`.
+fn frame_index_at(frame_durations: impl ExactSizeIterator<Item = f32>, duration_ms: f32, time_ms: f32) -> usize {
+    let last = frame_durations.len().saturating_sub(1);
+    if duration_ms <= 0.0 {
+        return 0;
+    }
+    let mut time_ms = time_ms.rem_euclid(duration_ms);
+    for (index, frame_duration_ms) in frame_durations.enumerate() {
+        if time_ms < frame_duration_ms {
+            return index;
+        }
+        time_ms -= frame_duration_ms;
+    }
+    last
+}
+
+/// Builds a `Path` out of segments. Building doesn't use up the builder, so it can keep adding segments or be reset
+/// to build another path.
+pub trait PathBuilderType<B: RendererType>: Default {
     fn move_to(&mut self, point: Point) -> &mut Self;
     fn line_to(&mut self, point: Point) -> &mut Self;
     fn conic_to(&mut self, p1: Point, p2: Point, weight: f32) -> &mut Self;
@@ -93,10 +206,55 @@ pub trait PathType {
     fn add_rounded_rectangle(&mut self, rect: Rect, rounding: Point) -> &mut Self;
     fn close(&mut self);
     fn cubic_to(&mut self, cp1: Point, cp2: Point, point: Point) -> &mut Self;
-    fn with_offset(&self, value: Point) -> Self;
-    fn with_scale(&mut self, value: Point) -> Self;
     fn fill_type_even_odd(&mut self);
     fn reset(&mut self);
+    /// The path built so far.
+    fn build(&self) -> B::Path;
+}
+
+/// An immutable path. Clones share the same path, so they're cheap to keep around in caches and display lists.
+pub trait PathType: Clone {
+    fn with_offset(&self, value: Point) -> Self;
+    fn with_scale(&self, value: Point) -> Self;
+}
+
+/// A mesh of triangles for `CanvasType::draw_vertices`, e.g. for drawing a spectrogram as a single grid instead of
+/// a rect per cell. Can be kept around and refilled to avoid allocating on every frame.
+#[derive(Clone, Debug, Default)]
+pub struct Vertices {
+    pub positions: Vec<Point>,
+    /// Either empty or one per position. Interpolated across each triangle.
+    pub colors: Vec<Color>,
+    /// Either empty or one per position. Where the shader of a gradient or `ShaderEffect` fill is sampled at each
+    /// position, instead of at the position itself.
+    pub tex_coords: Vec<Point>,
+    /// The positions of each triangle, three at a time. If empty, every three positions make a triangle.
+    pub indices: Vec<u16>,
+}
+
+impl Vertices {
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.colors.clear();
+        self.tex_coords.clear();
+        self.indices.clear();
+    }
+
+    /// Adds `rect` as two triangles of `color`, e.g. a cell of a spectrogram. Indices are 16 bits, so a single mesh
+    /// holds up to 16384 rects. Returns false without adding the rect when the mesh is full, in which case the rest
+    /// should go into another one.
+    pub fn add_rect(&mut self, rect: Rect, color: Color) -> bool {
+        let Ok(first) = u16::try_from(self.positions.len()) else {
+            return false;
+        };
+        if first > u16::MAX - 3 {
+            return false;
+        }
+        self.positions.extend([rect.a, point(rect.b.x, rect.a.y), rect.b, point(rect.a.x, rect.b.y)]);
+        self.colors.extend([color; 4]);
+        self.indices.extend([0, 1, 2, 0, 2, 3].map(|index| first + index));
+        true
+    }
 }
 
 pub enum BlendMode {
@@ -131,6 +289,16 @@ pub enum BlendMode {
     Luminosity,
 }
 
+/// How a clip combines with the clip that's already in effect.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClipOp {
+    /// Only draws inside the shape.
+    #[default]
+    Intersect,
+    /// Only draws outside the shape, e.g. for cutting a hole in an overlay.
+    Difference,
+}
+
 pub trait CanvasType<B: RendererType> {
     fn set_scale_factor(&mut self, scale_factor: f32);
     fn scale(&mut self, factor: f32) -> &mut Self;
@@ -138,11 +306,29 @@ pub trait CanvasType<B: RendererType> {
     fn draw_path(&mut self, path: &B::Path) -> &mut Self;
     fn draw_path_at(&mut self, path: &B::Path, bounds: Rect) -> &mut Self;
     fn draw_image(&mut self, image: &B::Image, bounds: Rect) -> &mut Self;
+    /// Draws the triangles of `vertices` in one go, filled with the vertices' colors combined with the current fill
+    /// by `blend_mode`, or with just the current fill if they have no colors.
+    fn draw_vertices(&mut self, vertices: &Vertices, blend_mode: BlendMode) -> &mut Self;
     fn with_scale(&mut self, scale: f32, cb: impl FnOnce(&mut Self)) -> &mut Self;
     fn with_blur(&mut self, amount: f32, cb: impl FnOnce(&mut Self)) -> &mut Self;
+    /// Multiplies the alpha of everything drawn in `cb` by `alpha`, shape by shape, so overlapping shapes show
+    /// through each other. Use `with_group_alpha` to fade them as one.
     fn with_alpha(&mut self, alpha: f32, cb: impl FnOnce(&mut Self)) -> &mut Self;
+    /// Draws everything in `cb` into an offscreen layer that is then composited with `alpha`, so the group fades
+    /// uniformly without seams where its shapes overlap. The layer costs an extra allocation and pass, so prefer
+    /// `with_alpha` for single shapes and surfaces.
+    fn with_group_alpha(&mut self, alpha: f32, cb: impl FnOnce(&mut Self)) -> &mut Self;
     fn with_clip_path(&mut self, path: &B::Path, cb: impl FnOnce(&mut Self)) -> &mut Self;
     fn with_clip_rect(&mut self, clip_rect: Rect, cb: impl FnOnce(&mut Self)) -> &mut Self;
+    /// Clips what's drawn in `cb` to the rounded rect, or to everything but it with `ClipOp::Difference`. The clip is
+    /// antialiased, so children get smooth rounded corners.
+    fn with_clip_rrect(
+        &mut self,
+        rect: Rect,
+        radius: impl Into<BorderRadius>,
+        op: ClipOp,
+        cb: impl FnOnce(&mut Self),
+    ) -> &mut Self;
     fn with_rotation(&mut self, degrees: f32, point: impl Into<Point>, cb: impl FnOnce(&mut Self)) -> &mut Self;
     fn with_translation(&mut self, amount: impl Into<Point>, cb: impl FnOnce(&mut Self)) -> &mut Self;
     fn with_blend_mode(&mut self, blend_mode: BlendMode, cb: impl FnOnce(&mut Self)) -> &mut Self;
@@ -158,12 +344,22 @@ pub trait CanvasType<B: RendererType> {
     fn stroke_join(&mut self, join: Join) -> &mut Self;
     fn draw_text(&mut self, blob: &B::TextBlob, position: Point) -> &mut Self;
     fn draw_surface(&mut self, surface: &B::Surface, position: Point) -> &mut Self;
+    /// Replays what was recorded into `list` with the current transform, clip and alpha.
+    fn draw_display_list(&mut self, list: &B::DisplayList) -> &mut Self;
     fn write_pixels(&mut self, size: IntPoint, offset: IntPoint, pixels: &[u8]) -> &mut Self;
     fn backdrop_filter(&mut self, bounds: Rect, amount: f32) -> &mut Self;
     fn save(&mut self) -> &mut Self;
     fn restore(&mut self) -> &mut Self;
+    /// Calls `cb` and returns the panic if it panics, after undoing the saves, clips, transforms, alpha and blend mode
+    /// that it left in effect. Panics abort on wasm, so there this is the same as calling `cb`.
+    fn catch_unwind(&mut self, cb: impl FnOnce(&mut Self)) -> std::thread::Result<()>;
     fn translate(&mut self, point: impl Into<Point>) -> &mut Self;
     fn scale_rel(&mut self, point: impl Into<Point>) -> &mut Self;
+    /// The bounds of the current clip in the current coordinates, or `None` if everything is clipped out.
+    fn local_clip_bounds(&self) -> Option<Rect>;
+    /// The transform from the current coordinates to the window's logical coordinates, i.e. not including the
+    /// scale factor.
+    fn current_matrix(&self) -> Matrix;
 }
 
 pub trait RasterSurfaceType<B: RendererType> {
@@ -171,15 +367,170 @@ pub trait RasterSurfaceType<B: RendererType> {
     fn get_canvas(&self) -> B::Canvas<'_>;
     fn draw(&self, func: impl FnOnce(Canvas, Rect));
     fn get_size(&self) -> IntPoint;
+    /// Encodes the surface's contents as a PNG.
+    fn encode_png(&self) -> Option<Vec<u8>>;
+    /// Reads the pixels that `rect` covers, in the surface's pixels rather than its size, as rows of RGBA bytes
+    /// without premultiplied alpha. Returns `None` if `rect` isn't within the surface.
+    fn read_pixels(&self, rect: Rect) -> Option<Vec<u8>>;
+    /// Like `read_pixels`, but sets `result` instead of returning the pixels, or leaves it unset if `rect` isn't
+    /// within the surface. The renderers here all read synchronously, so `result` is set before this returns; the
+    /// method is there for renderers that have to wait for the GPU to override.
+    fn read_pixels_async(&self, rect: Rect, result: &Later<Vec<u8>>) {
+        if let Some(pixels) = self.read_pixels(rect) {
+            result.set(pixels);
+        }
+    }
+    /// The color of the pixel at `position`, e.g. for an eyedropper.
+    fn read_color(&self, position: IntPoint) -> Option<Color> {
+        let pixels = self.read_pixels(Rect::from_xywh(position.x as f32, position.y as f32, 1.0, 1.0))?;
+        Some(rgba(u32::from_be_bytes(pixels.try_into().ok()?)))
+    }
+}
+
+/// The offset and size of the pixels that `rect` covers, if they are all within `size`.
+fn pixel_bounds(rect: Rect, size: IntPoint) -> Option<(IntPoint, IntPoint)> {
+    let (left, top) = (rect.left().floor() as i32, rect.top().floor() as i32);
+    let (right, bottom) = (rect.right().ceil() as i32, rect.bottom().ceil() as i32);
+    let within = left >= 0 && top >= 0 && right <= size.x && bottom <= size.y;
+    (within && right > left && bottom > top)
+        .then(|| (IntPoint { x: left, y: top }, IntPoint { x: right - left, y: bottom - top }))
+}
+
+/// Draws that are recorded once and can then be replayed any number of times, e.g. on every frame or into a layer,
+/// or exported with `RendererType::export_vector`. Clones share the same recording.
+pub trait DisplayListType<B: RendererType>: Clone {
+    /// Records what `draw` draws. Anything drawn outside `bounds` may be left out.
+    fn record(bounds: Rect, draw: impl FnOnce(&mut B::Canvas<'_>)) -> Self;
+    fn get_bounds(&self) -> Rect;
 }
 
 pub trait RendererType: Sized {
     type Font: FontType + Clone;
-    type TextBlob: TextBlobType<Self>;
+    /// Clones share the same blob.
+    type TextBlob: TextBlobType<Self> + Clone;
     type Image: ImageType;
     type Path: PathType;
+    type PathBuilder: PathBuilderType<Self>;
     type Canvas<'a>: CanvasType<Self>;
     type Surface: RasterSurfaceType<Self>;
+    type DisplayList: DisplayListType<Self>;
     fn add_typeface(&mut self, id: impl Into<usize>, data: &[u8]);
     fn create_font(&self, id: impl Into<usize>, font_size: f32, variables: Vec<FontVariable>) -> Self::Font;
+    /// Like `RasterSurfaceType::new`, but reuses a surface that was dropped if one of about the same size is free.
+    /// The surface starts out transparent either way.
+    fn create_surface(&self, size: IntPoint, scale_factor: f32) -> Self::Surface;
+    /// Drops free surfaces until the ones that are kept for reuse take at most `max_bytes`.
+    fn trim_surface_pool(&mut self, max_bytes: usize);
+    fn surface_pool_stats(&self) -> SurfacePoolStats;
+    /// The memory taken by the glyphs that the renderer keeps rasterized, or 0 if it doesn't tell. May cover every
+    /// renderer in the process, as Skia's glyph cache is global.
+    fn glyph_cache_bytes(&self) -> usize;
+    /// Drops the rasterized glyphs, which are rasterized again the next time they're drawn. May drop those of every
+    /// renderer in the process, as Skia's glyph cache is global.
+    fn purge_glyph_cache(&mut self);
+    /// Writes what was recorded into `list` on a page of `size` into a vector document.
+    fn export_vector(format: VectorFormat, size: Point, list: &Self::DisplayList) -> Result<Vec<u8>, Error>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VectorFormat {
+    Pdf,
+    Svg,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn animation_frames_loop() {
+        let durations = [100.0, 50.0, 250.0];
+        let frame_at = |time_ms| frame_index_at(durations.into_iter(), 400.0, time_ms);
+        assert_eq!(frame_at(0.0), 0);
+        assert_eq!(frame_at(99.9), 0);
+        assert_eq!(frame_at(100.0), 1);
+        assert_eq!(frame_at(160.0), 2);
+        assert_eq!(frame_at(399.0), 2);
+        assert_eq!(frame_at(400.0), 0);
+        assert_eq!(frame_at(1320.0), 1);
+        assert_eq!(frame_at(-50.0), 2);
+        assert_eq!(frame_index_at(durations.into_iter(), 0.0, 120.0), 0);
+    }
+
+    struct Pixels {
+        size: IntPoint,
+        data: Vec<u8>,
+    }
+
+    impl RasterSurfaceType<Renderer> for Pixels {
+        fn new(size: IntPoint, _scale_factor: f32) -> Self {
+            Self { size, data: vec![0; (size.x * size.y * 4) as usize] }
+        }
+
+        fn get_canvas(&self) -> <Renderer as RendererType>::Canvas<'_> {
+            unimplemented!()
+        }
+
+        fn draw(&self, _func: impl FnOnce(Canvas, Rect)) {
+            unimplemented!()
+        }
+
+        fn get_size(&self) -> IntPoint {
+            self.size
+        }
+
+        fn encode_png(&self) -> Option<Vec<u8>> {
+            None
+        }
+
+        fn read_pixels(&self, rect: Rect) -> Option<Vec<u8>> {
+            let (offset, size) = pixel_bounds(rect, self.size)?;
+            let rows = (offset.y..offset.y + size.y).map(|y| {
+                let start = ((y * self.size.x + offset.x) * 4) as usize;
+                &self.data[start..start + size.x as usize * 4]
+            });
+            Some(rows.flatten().copied().collect())
+        }
+    }
+
+    #[test]
+    fn pixel_bounds_cover_partial_pixels() {
+        let size = IntPoint { x: 10, y: 5 };
+        let bounds = |x, y, w, h| pixel_bounds(Rect::from_xywh(x, y, w, h), size);
+        assert_eq!(bounds(0.0, 0.0, 10.0, 5.0), Some((IntPoint { x: 0, y: 0 }, IntPoint { x: 10, y: 5 })));
+        assert_eq!(bounds(1.5, 0.2, 2.0, 1.0), Some((IntPoint { x: 1, y: 0 }, IntPoint { x: 3, y: 2 })));
+        assert_eq!(bounds(-1.0, 0.0, 2.0, 2.0), None);
+        assert_eq!(bounds(9.5, 4.0, 1.0, 1.0), None);
+        assert_eq!(bounds(2.0, 2.0, 0.0, 1.0), None);
+    }
+
+    #[test]
+    fn reads_the_color_of_a_pixel() {
+        let mut surface = Pixels::new(IntPoint { x: 3, y: 2 }, 1.0);
+        surface.data[(3 + 1) * 4..(3 + 2) * 4].copy_from_slice(&[0x11, 0x22, 0x33, 0x44]);
+        assert_eq!(surface.read_color(IntPoint { x: 1, y: 1 }), Some(rgba(0x11223344)));
+        assert_eq!(surface.read_color(IntPoint { x: 0, y: 0 }), Some(rgba(0)));
+        assert_eq!(surface.read_color(IntPoint { x: 3, y: 0 }), None);
+
+        let result = Later::default();
+        surface.read_pixels_async(Rect::from_xywh(1.0, 1.0, 2.0, 1.0), &result);
+        assert_eq!(result.value(), Some(vec![0x11, 0x22, 0x33, 0x44, 0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn vertices_hold_as_many_rects_as_16_bit_indices_can_reach() {
+        let mut vertices = Vertices::default();
+        let rect = Rect::from_xywh(0.0, 0.0, 1.0, 1.0);
+        for _ in 0..16384 {
+            assert!(vertices.add_rect(rect, Color::default()));
+        }
+        assert_eq!(vertices.indices.last(), Some(&u16::MAX));
+        assert!(!vertices.add_rect(rect, Color::default()));
+        assert_eq!(vertices.positions.len(), 65536);
+        assert_eq!(vertices.indices.len(), 16384 * 6);
+
+        vertices.clear();
+        assert!(vertices.add_rect(rect, Color::default()));
+        assert_eq!(vertices.indices, [0, 1, 2, 0, 2, 3]);
+    }
 }
@@ -1,11 +1,15 @@
-use std::cell::UnsafeCell;
+use std::{
+    cell::{RefCell, UnsafeCell},
+    rc::Rc,
+    sync::Arc,
+};
 
 use rustc_hash::FxHashMap;
 use skia_safe::{
-    ClipOp, Data, FontArguments, FontMgr, FourByteTag, ISize, ImageInfo, MaskFilter, Paint, PathDirection, RRect,
-    SamplingOptions, Typeface,
+    ClipOp, Data, FontArguments, FontMgr, FourByteTag, IRect, ISize, ImageInfo, MaskFilter, Paint, PathDirection,
+    RRect, SamplingOptions, Typeface,
     canvas::SaveLayerRec,
-    color_filters,
+    codec, color_filters,
     font_arguments::{VariationPosition, variation_position::Coordinate},
     gradient_shader::{GradientShaderColors, linear},
     image_filters::{self, CropRect},
@@ -13,9 +17,13 @@ use skia_safe::{
     surfaces,
 };
 
-use crate::{Color, IntPoint, Point, Rect, point, renderers::ImageType, rgb};
+use crate::{Color, Error, IntPoint, Matrix, Point, Rect, point, renderers::ImageType, rgb};
 
-use super::{BorderRadius, CanvasType, Cap, Fill, FontVariable, Join, RasterSurfaceType};
+use super::{
+    BorderRadius, CanvasType, Cap, DisplayListType, Fill, FontVariable, FrameDisposal, ImageFrame, Join,
+    RasterSurfaceType, ShaderEffectType, SurfacePoolStats, VectorFormat, Vertices,
+    surface_pool::{SurfacePool, bucket_size},
+};
 
 #[derive(Clone)]
 pub struct Font {
@@ -38,8 +46,22 @@ impl super::FontType for Font {
         self.font.get_widths(&glyphs, &mut widths);
         widths
     }
+
+    fn get_ascent(&self) -> f32 {
+        -self.font.metrics().1.ascent
+    }
+
+    fn get_descent(&self) -> f32 {
+        self.font.metrics().1.descent
+    }
+
+    fn get_line_height(&self) -> f32 {
+        self.font.metrics().0
+    }
 }
 
+// skia's blobs are reference counted already
+#[derive(Clone)]
 pub struct TextBlob {
     blob: skia_safe::TextBlob,
 }
@@ -53,6 +75,8 @@ impl super::TextBlobType<Renderer> for TextBlob {
 #[derive(Default)]
 pub struct Renderer {
     typefaces: FxHashMap<usize, Typeface>,
+    // shared with the surfaces that came from it, which give themselves back when dropped
+    surface_pool: Rc<RefCell<SurfacePool<skia_safe::Surface>>>,
 }
 
 impl super::RendererType for Renderer {
@@ -60,8 +84,10 @@ impl super::RendererType for Renderer {
     type TextBlob = TextBlob;
     type Image = Image;
     type Path = Path;
+    type PathBuilder = PathBuilder;
     type Canvas<'a> = Canvas<'a>;
     type Surface = Surface;
+    type DisplayList = DisplayList;
 
     fn add_typeface(&mut self, id: impl Into<usize>, data: &[u8]) {
         let mgr = FontMgr::default();
@@ -91,6 +117,80 @@ impl super::RendererType for Renderer {
         font.set_hinting(skia_safe::FontHinting::Full);
         Font { font }
     }
+
+    fn create_surface(&self, size: IntPoint, scale_factor: f32) -> Surface {
+        let scaled_size = size.with_scale(scale_factor);
+        let mut surface = self.surface_pool.borrow_mut().take(bucket_size(scaled_size), raster_surface);
+        let canvas = surface.canvas();
+        // everything that was done to the canvas of a reused surface is above the save that was made when it was
+        // handed out before, including transforms made without saving
+        canvas.restore_to_count(1);
+        canvas.clear(skia_safe::Color::TRANSPARENT);
+        canvas.save();
+        Surface { surface: surface.into(), size, scaled_size, pool: Some(self.surface_pool.clone()) }
+    }
+
+    fn trim_surface_pool(&mut self, max_bytes: usize) {
+        self.surface_pool.borrow_mut().trim(max_bytes);
+    }
+
+    fn surface_pool_stats(&self) -> SurfacePoolStats {
+        self.surface_pool.borrow().stats()
+    }
+
+    fn glyph_cache_bytes(&self) -> usize {
+        skia_safe::graphics::font_cache_used()
+    }
+
+    fn purge_glyph_cache(&mut self) {
+        skia_safe::graphics::purge_font_cache();
+    }
+
+    fn export_vector(format: VectorFormat, size: Point, list: &DisplayList) -> Result<Vec<u8>, crate::Error> {
+        match format {
+            VectorFormat::Pdf => {
+                let mut data = vec![];
+                let mut page = skia_safe::pdf::new_document(&mut data, None).begin_page((size.x, size.y), None);
+                page.canvas().draw_picture(&list.picture, None, None);
+                page.end_page().close();
+                Ok(data)
+            }
+            VectorFormat::Svg => {
+                let canvas = skia_safe::svg::Canvas::new(skia_safe::Rect::from_wh(size.x, size.y), None);
+                canvas.draw_picture(&list.picture, None, None);
+                Ok(canvas.end().as_bytes().to_vec())
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ShaderEffect {
+    effect: skia_safe::RuntimeEffect,
+    uniforms: Vec<u8>,
+}
+
+impl ShaderEffectType for ShaderEffect {
+    fn new(source: &str) -> Result<Self, Error> {
+        let effect = skia_safe::RuntimeEffect::make_for_shader(source, None).map_err(Error::Graphics)?;
+        let uniforms = vec![0; effect.uniform_size()];
+        Ok(Self { effect, uniforms })
+    }
+
+    fn set_uniform(&mut self, name: &str, values: &[f32]) -> Result<(), Error> {
+        let uniform = self
+            .effect
+            .uniforms()
+            .iter()
+            .find(|uniform| uniform.name() == name)
+            .ok_or_else(|| Error::Graphics(format!("the shader has no uniform called {name}")))?;
+        if uniform.size_in_bytes() != size_of_val(values) {
+            return Err(Error::Graphics(format!("{name} takes {} floats", uniform.size_in_bytes() / 4)));
+        }
+        let bytes = values.iter().flat_map(|value| value.to_ne_bytes());
+        self.uniforms.splice(uniform.offset()..uniform.offset() + uniform.size_in_bytes(), bytes);
+        Ok(())
+    }
 }
 
 impl From<Cap> for skia_safe::PaintCap {
@@ -133,13 +233,68 @@ impl super::ImageType for Image {
     {
         skia_safe::images::deferred_from_encoded_data(Data::new_copy(data), None).map(|image| Image { image })
     }
+
+    fn frames_from_encoded(data: &[u8]) -> Option<Vec<ImageFrame<Image>>> {
+        let mut codec = skia_safe::Codec::from_data(Data::new_copy(data))?;
+        if codec.get_frame_count() < 2 {
+            return None;
+        }
+        let info = ImageInfo::new_n32_premul(codec.info().dimensions(), None);
+        let row_bytes = info.min_row_bytes();
+
+        let mut decoded: Vec<Data> = Vec::new();
+        let mut frames = Vec::new();
+        for (frame_index, frame_info) in codec.get_frame_info().into_iter().enumerate() {
+            // frames are decoded on top of the frame they depend on, which skia tells us after applying disposal
+            let prior_frame = usize::try_from(frame_info.required_frame).ok();
+            let mut pixels = match prior_frame {
+                Some(prior_frame) => decoded[prior_frame].as_bytes().to_vec(),
+                None => vec![0; info.compute_min_byte_size()],
+            };
+            let options =
+                codec::Options { zero_initialized: codec::ZeroInitialized::No, subset: None, frame_index, prior_frame };
+            if codec.get_pixels_with_options(&info, &mut pixels, row_bytes, Some(&options)) != codec::Result::Success {
+                break;
+            }
+            let pixels = Data::new_copy(&pixels);
+            let Some(image) = skia_safe::images::raster_from_data(&info, pixels.clone(), row_bytes) else {
+                break;
+            };
+            decoded.push(pixels);
+            frames.push(ImageFrame {
+                image: Image { image },
+                duration_ms: frame_info.duration as f32,
+                disposal: match frame_info.disposal_method {
+                    codec::DisposalMethod::Keep => FrameDisposal::Keep,
+                    codec::DisposalMethod::RestoreBGColor => FrameDisposal::RestoreBackground,
+                    codec::DisposalMethod::RestorePrevious => FrameDisposal::RestorePrevious,
+                },
+            });
+        }
+        (frames.len() > 1).then_some(frames)
+    }
 }
 
+#[derive(Clone)]
 pub struct Path {
+    path: Arc<skia_safe::Path>,
+}
+
+impl super::PathType for Path {
+    fn with_offset(&self, value: Point) -> Self {
+        Path { path: Arc::new(self.path.with_offset(value)) }
+    }
+
+    fn with_scale(&self, value: Point) -> Self {
+        Path { path: Arc::new(self.path.make_scale((value.x, value.y))) }
+    }
+}
+
+pub struct PathBuilder {
     path: skia_safe::Path,
 }
 
-impl Default for Path {
+impl Default for PathBuilder {
     fn default() -> Self {
         let mut path = skia_safe::Path::default();
         path.set_fill_type(skia_safe::PathFillType::Winding);
@@ -147,7 +302,7 @@ impl Default for Path {
     }
 }
 
-impl super::PathType for Path {
+impl super::PathBuilderType<Renderer> for PathBuilder {
     fn move_to(&mut self, point: Point) -> &mut Self {
         self.path.move_to(point);
         self
@@ -198,14 +353,6 @@ impl super::PathType for Path {
         self
     }
 
-    fn with_offset(&self, value: Point) -> Self {
-        Path { path: self.path.with_offset(value) }
-    }
-
-    fn with_scale(&mut self, value: Point) -> Self {
-        Path { path: self.path.make_scale((value.x, value.y)) }
-    }
-
     fn reset(&mut self) {
         self.path.reset();
     }
@@ -213,33 +360,69 @@ impl super::PathType for Path {
     fn fill_type_even_odd(&mut self) {
         self.path.set_fill_type(skia_safe::PathFillType::EvenOdd);
     }
+
+    fn build(&self) -> Path {
+        Path { path: Arc::new(self.path.clone()) }
+    }
+}
+
+// pictures are reference counted already
+#[derive(Clone)]
+pub struct DisplayList {
+    picture: skia_safe::Picture,
+}
+
+impl DisplayListType<Renderer> for DisplayList {
+    fn record(bounds: Rect, draw: impl FnOnce(&mut Canvas<'_>)) -> Self {
+        let mut recorder = skia_safe::PictureRecorder::new();
+        draw(&mut Canvas::new(recorder.begin_recording(rect_to_rect(bounds), false)));
+        // only fails if recording wasn't begun
+        let picture = recorder.finish_recording_as_picture(None).unwrap();
+        Self { picture }
+    }
+
+    fn get_bounds(&self) -> Rect {
+        let bounds = self.picture.cull_rect();
+        Rect::from_xywh(bounds.left, bounds.top, bounds.width(), bounds.height())
+    }
+}
+
+fn raster_surface(size: IntPoint) -> skia_safe::Surface {
+    surfaces::raster(
+        &ImageInfo::new(
+            ISize::new(size.x, size.y),
+            skia_safe::ColorType::RGBA8888,
+            skia_safe::AlphaType::Unpremul,
+            None,
+        ),
+        None,
+        None,
+    )
+    .unwrap()
 }
 
 pub struct Surface {
     surface: UnsafeCell<skia_safe::Surface>,
     size: IntPoint,
+    // the part of the surface that is drawn, which is smaller than the surface itself when it came from a pool
     scaled_size: IntPoint,
+    pool: Option<Rc<RefCell<SurfacePool<skia_safe::Surface>>>>,
+}
+
+impl Drop for Surface {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.take() {
+            let surface = self.surface.get_mut().clone();
+            let bucket = IntPoint { x: surface.width(), y: surface.height() };
+            pool.borrow_mut().give_back(bucket, surface);
+        }
+    }
 }
 
 impl RasterSurfaceType<Renderer> for Surface {
     fn new(size: IntPoint, scale_factor: f32) -> Self {
         let scaled_size = size.with_scale(scale_factor);
-        Self {
-            size,
-            scaled_size,
-            surface: surfaces::raster(
-                &ImageInfo::new(
-                    ISize::new(scaled_size.x, scaled_size.y),
-                    skia_safe::ColorType::RGBA8888,
-                    skia_safe::AlphaType::Unpremul,
-                    None,
-                ),
-                None,
-                None,
-            )
-            .unwrap()
-            .into(),
-        }
+        Self { surface: raster_surface(scaled_size).into(), size, scaled_size, pool: None }
     }
 
     fn get_canvas(&self) -> Canvas<'_> {
@@ -253,6 +436,26 @@ impl RasterSurfaceType<Renderer> for Surface {
     fn get_size(&self) -> IntPoint {
         self.size
     }
+
+    fn encode_png(&self) -> Option<Vec<u8>> {
+        let bounds = IRect::from_wh(self.scaled_size.x, self.scaled_size.y);
+        let image = unsafe { (*self.surface.get()).image_snapshot_with_bounds(bounds) }?;
+        image.encode(None, skia_safe::EncodedImageFormat::PNG, None).map(|data| data.as_bytes().to_vec())
+    }
+
+    fn read_pixels(&self, rect: Rect) -> Option<Vec<u8>> {
+        let (offset, size) = super::pixel_bounds(rect, self.scaled_size)?;
+        let info = ImageInfo::new(
+            ISize::new(size.x, size.y),
+            skia_safe::ColorType::RGBA8888,
+            skia_safe::AlphaType::Unpremul,
+            None,
+        );
+        let row_bytes = size.x as usize * 4;
+        let mut pixels = vec![0; row_bytes * size.y as usize];
+        let read = unsafe { (*self.surface.get()).read_pixels(&info, &mut pixels, row_bytes, (offset.x, offset.y)) };
+        read.then_some(pixels)
+    }
 }
 
 pub struct Canvas<'a> {
@@ -260,13 +463,16 @@ pub struct Canvas<'a> {
     paint: skia_safe::Paint,
     alpha_mult: f32,
     scale_factor: f32,
+    // mirrors the canvas' transform without the scale factor, along with the ones saved by `save`
+    matrix: Matrix,
+    saved_matrices: Vec<Matrix>,
 }
 
 impl<'a> Canvas<'a> {
     pub fn new(canvas: &'a skia_safe::Canvas) -> Self {
         let mut paint = Paint::default();
         paint.set_anti_alias(true);
-        Self { canvas, paint, alpha_mult: 1.0, scale_factor: 1.0 }
+        Self { canvas, paint, alpha_mult: 1.0, scale_factor: 1.0, matrix: Matrix::IDENTITY, saved_matrices: vec![] }
     }
 }
 
@@ -280,6 +486,7 @@ impl super::CanvasType<Renderer> for Canvas<'_> {
         factor *= self.scale_factor;
         self.canvas.reset_matrix();
         self.canvas.scale((factor, factor));
+        self.matrix = Matrix::scale(factor / self.scale_factor);
         self
     }
 
@@ -297,10 +504,10 @@ impl super::CanvasType<Renderer> for Canvas<'_> {
     }
 
     fn draw_path_at(&mut self, path: &Path, bounds: Rect) -> &mut Self {
-        self.canvas.save();
-        self.canvas.translate((bounds.a.x, bounds.a.y));
+        self.save();
+        self.translate(bounds.a);
         self.draw_path(path);
-        self.canvas.restore();
+        self.restore();
         self
     }
 
@@ -316,6 +523,22 @@ impl super::CanvasType<Renderer> for Canvas<'_> {
         self
     }
 
+    fn draw_vertices(&mut self, vertices: &Vertices, blend_mode: super::BlendMode) -> &mut Self {
+        let positions: Vec<skia_safe::Point> = vertices.positions.iter().map(|&position| position.into()).collect();
+        let tex_coords: Vec<skia_safe::Point> = vertices.tex_coords.iter().map(|&coord| coord.into()).collect();
+        let colors: Vec<skia_safe::Color> =
+            vertices.colors.iter().map(|color| color.with_alpha_mul(self.alpha_mult).into()).collect();
+        let vertices = skia_safe::Vertices::new_copy(
+            skia_safe::vertices::VertexMode::Triangles,
+            &positions,
+            &tex_coords,
+            &colors,
+            (!vertices.indices.is_empty()).then_some(&vertices.indices[..]),
+        );
+        self.canvas.draw_vertices(&vertices, to_skia_blend_mode(blend_mode), &self.paint);
+        self
+    }
+
     fn with_blur(&mut self, amount: f32, cb: impl FnOnce(&mut Self)) -> &mut Self {
         self.paint.set_mask_filter(MaskFilter::blur(skia_safe::BlurStyle::Normal, amount, None));
         (cb)(self);
@@ -331,19 +554,49 @@ impl super::CanvasType<Renderer> for Canvas<'_> {
         self
     }
 
+    fn with_group_alpha(&mut self, alpha: f32, cb: impl FnOnce(&mut Self)) -> &mut Self {
+        if alpha >= 1.0 {
+            (cb)(self);
+            return self;
+        }
+        let mut paint = Paint::default();
+        paint.set_alpha_f(alpha.max(0.0));
+        self.canvas.save_layer(&SaveLayerRec::default().paint(&paint));
+        self.saved_matrices.push(self.matrix);
+        (cb)(self);
+        self.restore();
+        self
+    }
+
     fn with_clip_path(&mut self, path: &Path, cb: impl FnOnce(&mut Self)) -> &mut Self {
-        self.canvas.save();
+        self.save();
         self.canvas.clip_path(&path.path, ClipOp::Intersect, true);
         (cb)(self);
-        self.canvas.restore();
+        self.restore();
         self
     }
 
     fn with_clip_rect(&mut self, clip_rect: Rect, cb: impl FnOnce(&mut Self)) -> &mut Self {
-        self.canvas.save();
+        self.save();
         self.canvas.clip_rect(rect_to_rect(clip_rect), ClipOp::Intersect, true);
         (cb)(self);
-        self.canvas.restore();
+        self.restore();
+        self
+    }
+
+    fn with_clip_rrect(
+        &mut self,
+        rect: Rect,
+        radius: impl Into<BorderRadius>,
+        op: super::ClipOp,
+        cb: impl FnOnce(&mut Self),
+    ) -> &mut Self {
+        let radius: BorderRadius = radius.into();
+        let rrect = RRect::new_nine_patch(rect_to_rect(rect), radius.left, radius.top, radius.right, radius.bottom);
+        self.save();
+        self.canvas.clip_rrect(rrect, to_skia_clip_op(op), true);
+        (cb)(self);
+        self.restore();
         self
     }
 
@@ -418,10 +671,17 @@ impl super::CanvasType<Renderer> for Canvas<'_> {
     fn draw_surface(&mut self, surface: &Surface, position: Point) -> &mut Self {
         self.canvas.save();
         self.canvas.scale((1.0 / self.scale_factor, 1.0 / self.scale_factor));
+        let position = position * self.scale_factor;
+        let size = surface.scaled_size.to_float();
+        self.canvas.clip_rect(
+            rect_to_rect(Rect::from_xywh(position.x, position.y, size.x, size.y)),
+            ClipOp::Intersect,
+            false,
+        );
         unsafe {
             (*surface.surface.get()).draw(
                 self.canvas,
-                position * self.scale_factor,
+                position,
                 SamplingOptions::new(skia_safe::FilterMode::Linear, skia_safe::MipmapMode::None),
                 None,
             );
@@ -430,6 +690,13 @@ impl super::CanvasType<Renderer> for Canvas<'_> {
         self
     }
 
+    fn draw_display_list(&mut self, list: &DisplayList) -> &mut Self {
+        let mut paint = Paint::default();
+        paint.set_alpha_f(self.alpha_mult);
+        self.canvas.draw_picture(&list.picture, None, (self.alpha_mult < 1.0).then_some(&paint));
+        self
+    }
+
     fn write_pixels(&mut self, size: IntPoint, offset: IntPoint, pixels: &[u8]) -> &mut Self {
         let _ = self.canvas.write_pixels(
             &ImageInfo::new(
@@ -446,65 +713,36 @@ impl super::CanvasType<Renderer> for Canvas<'_> {
     }
 
     fn with_rotation(&mut self, degrees: f32, point: impl Into<Point>, cb: impl FnOnce(&mut Self)) -> &mut Self {
-        self.canvas.save();
+        self.save();
         let p: Point = point.into();
         self.canvas.rotate(degrees, Some(p.into()));
+        self.matrix = self.matrix * Matrix::rotation(degrees, p);
         (cb)(self);
-        self.canvas.restore();
+        self.restore();
         self
     }
 
     fn with_scale(&mut self, scale: f32, cb: impl FnOnce(&mut Self)) -> &mut Self {
-        self.canvas.save();
+        self.save();
         self.scale(scale);
         (cb)(self);
-        self.canvas.restore();
+        self.restore();
         self
     }
 
     fn with_blend_mode(&mut self, blend_mode: super::BlendMode, cb: impl FnOnce(&mut Self)) -> &mut Self {
         let prev_blend = self.paint.blend_mode_or(skia_safe::BlendMode::Src);
-        self.paint.set_blend_mode(match blend_mode {
-            super::BlendMode::Clear => skia_safe::BlendMode::Clear,
-            super::BlendMode::Src => skia_safe::BlendMode::Src,
-            super::BlendMode::Dst => skia_safe::BlendMode::Dst,
-            super::BlendMode::SrcOver => skia_safe::BlendMode::SrcOver,
-            super::BlendMode::DstOver => skia_safe::BlendMode::DstOver,
-            super::BlendMode::SrcIn => skia_safe::BlendMode::SrcIn,
-            super::BlendMode::DstIn => skia_safe::BlendMode::DstIn,
-            super::BlendMode::SrcOut => skia_safe::BlendMode::SrcOut,
-            super::BlendMode::DstOut => skia_safe::BlendMode::DstOut,
-            super::BlendMode::SrcATop => skia_safe::BlendMode::SrcATop,
-            super::BlendMode::DstATop => skia_safe::BlendMode::DstATop,
-            super::BlendMode::Xor => skia_safe::BlendMode::Xor,
-            super::BlendMode::Plus => skia_safe::BlendMode::Plus,
-            super::BlendMode::Modulate => skia_safe::BlendMode::Modulate,
-            super::BlendMode::Screen => skia_safe::BlendMode::Screen,
-            super::BlendMode::Overlay => skia_safe::BlendMode::Overlay,
-            super::BlendMode::Darken => skia_safe::BlendMode::Darken,
-            super::BlendMode::Lighten => skia_safe::BlendMode::Lighten,
-            super::BlendMode::ColorDodge => skia_safe::BlendMode::ColorDodge,
-            super::BlendMode::ColorBurn => skia_safe::BlendMode::ColorBurn,
-            super::BlendMode::HardLight => skia_safe::BlendMode::HardLight,
-            super::BlendMode::SoftLight => skia_safe::BlendMode::SoftLight,
-            super::BlendMode::Difference => skia_safe::BlendMode::Difference,
-            super::BlendMode::Exclusion => skia_safe::BlendMode::Exclusion,
-            super::BlendMode::Multiply => skia_safe::BlendMode::Multiply,
-            super::BlendMode::Hue => skia_safe::BlendMode::Hue,
-            super::BlendMode::Saturation => skia_safe::BlendMode::Saturation,
-            super::BlendMode::Color => skia_safe::BlendMode::Color,
-            super::BlendMode::Luminosity => skia_safe::BlendMode::Luminosity,
-        });
+        self.paint.set_blend_mode(to_skia_blend_mode(blend_mode));
         (cb)(self);
         self.paint.set_blend_mode(prev_blend);
         self
     }
 
     fn with_translation(&mut self, amount: impl Into<Point>, cb: impl FnOnce(&mut Self)) -> &mut Self {
-        self.canvas.save();
-        self.canvas.translate(amount.into());
+        self.save();
+        self.translate(amount);
         cb(self);
-        self.canvas.restore();
+        self.restore();
         self
     }
 
@@ -527,25 +765,55 @@ impl super::CanvasType<Renderer> for Canvas<'_> {
 
     fn save(&mut self) -> &mut Self {
         self.canvas.save();
+        self.saved_matrices.push(self.matrix);
         self
     }
 
     fn restore(&mut self) -> &mut Self {
         self.canvas.restore();
+        if let Some(matrix) = self.saved_matrices.pop() {
+            self.matrix = matrix;
+        }
         self
     }
 
+    fn catch_unwind(&mut self, cb: impl FnOnce(&mut Self)) -> std::thread::Result<()> {
+        let save_count = self.canvas.save_count();
+        let (matrix, saved_matrices, alpha_mult) = (self.matrix, self.saved_matrices.len(), self.alpha_mult);
+        let blend_mode = self.paint.blend_mode_or(skia_safe::BlendMode::Src);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| cb(self)));
+        if result.is_err() {
+            self.canvas.restore_to_count(save_count);
+            self.matrix = matrix;
+            self.saved_matrices.truncate(saved_matrices);
+            self.alpha_mult = alpha_mult;
+            self.paint.set_blend_mode(blend_mode);
+            self.paint.set_mask_filter(None);
+        }
+        result
+    }
+
     fn translate(&mut self, point: impl Into<Point>) -> &mut Self {
         let p: Point = point.into();
         self.canvas.translate(p);
+        self.matrix = self.matrix * Matrix::translation(p);
         self
     }
 
     fn scale_rel(&mut self, point: impl Into<Point>) -> &mut Self {
         let p: Point = point.into();
         self.canvas.scale((p.x, p.y));
+        self.matrix = self.matrix * Matrix::scale(p);
         self
     }
+
+    fn local_clip_bounds(&self) -> Option<Rect> {
+        self.canvas.local_clip_bounds().map(|r| Rect::from_ab(point(r.left, r.top), point(r.right, r.bottom)))
+    }
+
+    fn current_matrix(&self) -> Matrix {
+        self.matrix
+    }
 }
 
 impl Canvas<'_> {
@@ -557,15 +825,20 @@ impl Canvas<'_> {
             }
             Fill::Gradient(gradient) => {
                 self.color(rgb(0));
+                let colors: Vec<skia_safe::Color> = gradient.colors.iter().map(|&color| color.into()).collect();
                 self.paint.set_shader(linear(
                     gradient.points,
-                    GradientShaderColors::Colors(&gradient.colors.map(|c| c.into())[..gradient.num_positions as usize]),
-                    Some(&gradient.positions.map(|p| p)[..gradient.num_positions as usize]),
+                    GradientShaderColors::Colors(&colors),
+                    Some(gradient.positions.as_slice()),
                     skia_safe::TileMode::Clamp,
                     None,
                     None,
                 ));
             }
+            Fill::Shader(shader) => {
+                self.color(rgb(0));
+                self.paint.set_shader(shader.effect.make_shader(Data::new_copy(&shader.uniforms), &[], None));
+            }
         }
     }
 }
@@ -594,6 +867,47 @@ impl From<Rect> for skia_safe::Rect {
     }
 }
 
+fn to_skia_clip_op(op: super::ClipOp) -> ClipOp {
+    match op {
+        super::ClipOp::Intersect => ClipOp::Intersect,
+        super::ClipOp::Difference => ClipOp::Difference,
+    }
+}
+
+fn to_skia_blend_mode(blend_mode: super::BlendMode) -> skia_safe::BlendMode {
+    match blend_mode {
+        super::BlendMode::Clear => skia_safe::BlendMode::Clear,
+        super::BlendMode::Src => skia_safe::BlendMode::Src,
+        super::BlendMode::Dst => skia_safe::BlendMode::Dst,
+        super::BlendMode::SrcOver => skia_safe::BlendMode::SrcOver,
+        super::BlendMode::DstOver => skia_safe::BlendMode::DstOver,
+        super::BlendMode::SrcIn => skia_safe::BlendMode::SrcIn,
+        super::BlendMode::DstIn => skia_safe::BlendMode::DstIn,
+        super::BlendMode::SrcOut => skia_safe::BlendMode::SrcOut,
+        super::BlendMode::DstOut => skia_safe::BlendMode::DstOut,
+        super::BlendMode::SrcATop => skia_safe::BlendMode::SrcATop,
+        super::BlendMode::DstATop => skia_safe::BlendMode::DstATop,
+        super::BlendMode::Xor => skia_safe::BlendMode::Xor,
+        super::BlendMode::Plus => skia_safe::BlendMode::Plus,
+        super::BlendMode::Modulate => skia_safe::BlendMode::Modulate,
+        super::BlendMode::Screen => skia_safe::BlendMode::Screen,
+        super::BlendMode::Overlay => skia_safe::BlendMode::Overlay,
+        super::BlendMode::Darken => skia_safe::BlendMode::Darken,
+        super::BlendMode::Lighten => skia_safe::BlendMode::Lighten,
+        super::BlendMode::ColorDodge => skia_safe::BlendMode::ColorDodge,
+        super::BlendMode::ColorBurn => skia_safe::BlendMode::ColorBurn,
+        super::BlendMode::HardLight => skia_safe::BlendMode::HardLight,
+        super::BlendMode::SoftLight => skia_safe::BlendMode::SoftLight,
+        super::BlendMode::Difference => skia_safe::BlendMode::Difference,
+        super::BlendMode::Exclusion => skia_safe::BlendMode::Exclusion,
+        super::BlendMode::Multiply => skia_safe::BlendMode::Multiply,
+        super::BlendMode::Hue => skia_safe::BlendMode::Hue,
+        super::BlendMode::Saturation => skia_safe::BlendMode::Saturation,
+        super::BlendMode::Color => skia_safe::BlendMode::Color,
+        super::BlendMode::Luminosity => skia_safe::BlendMode::Luminosity,
+    }
+}
+
 pub fn rect_to_rect(rect: Rect) -> skia_safe::Rect {
     skia_safe::Rect::new(rect.left(), rect.top(), rect.right(), rect.bottom())
 }
@@ -0,0 +1,141 @@
+use crate::IntPoint;
+
+/// How many bytes of unused surfaces a pool keeps by default before it starts dropping the oldest ones.
+pub(crate) const DEFAULT_SURFACE_POOL_BYTES: usize = 64 * 1024 * 1024;
+
+/// Counters of a renderer's surface pool, e.g. for seeing whether cached components reuse their surfaces.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SurfacePoolStats {
+    /// Surfaces that had to be allocated because none of the right size was free.
+    pub allocations: u64,
+    /// Surfaces that were handed out again instead of being allocated.
+    pub reuses: u64,
+    /// Surfaces that aren't in use and are waiting to be reused.
+    pub free_surfaces: usize,
+    /// The memory taken by the free surfaces, assuming 4 bytes per pixel.
+    pub free_bytes: usize,
+    /// Surfaces that were handed out and haven't been given back yet, e.g. the caches of components.
+    pub used_surfaces: usize,
+    /// The memory taken by the used surfaces, assuming 4 bytes per pixel.
+    pub used_bytes: usize,
+}
+
+/// The size that surfaces of `size` pixels are allocated at, so that surfaces whose size changes by a few pixels
+/// keep landing in the same bucket.
+pub(crate) fn bucket_size(size: IntPoint) -> IntPoint {
+    let round = |length: i32| {
+        let step = if length <= 512 { 32 } else { 128 };
+        (length.max(1) + step - 1) / step * step
+    };
+    IntPoint { x: round(size.x), y: round(size.y) }
+}
+
+fn byte_size(size: IntPoint) -> usize {
+    size.x as usize * size.y as usize * 4
+}
+
+/// Surfaces that were dropped, kept by their bucket size until a surface of the same bucket is needed again.
+pub(crate) struct SurfacePool<S> {
+    // oldest first
+    free: Vec<(IntPoint, S)>,
+    max_bytes: usize,
+    stats: SurfacePoolStats,
+}
+
+impl<S> Default for SurfacePool<S> {
+    fn default() -> Self {
+        Self { free: Vec::new(), max_bytes: DEFAULT_SURFACE_POOL_BYTES, stats: SurfacePoolStats::default() }
+    }
+}
+
+impl<S> SurfacePool<S> {
+    /// A free surface of `bucket`, or a new one from `allocate` if there's none.
+    pub(crate) fn take(&mut self, bucket: IntPoint, allocate: impl FnOnce(IntPoint) -> S) -> S {
+        self.stats.used_surfaces += 1;
+        self.stats.used_bytes += byte_size(bucket);
+        // the most recently freed one is the likeliest to still be in a cache
+        if let Some(index) = self.free.iter().rposition(|(size, _)| *size == bucket) {
+            let (size, surface) = self.free.remove(index);
+            self.stats.reuses += 1;
+            self.stats.free_surfaces -= 1;
+            self.stats.free_bytes -= byte_size(size);
+            return surface;
+        }
+        self.stats.allocations += 1;
+        allocate(bucket)
+    }
+
+    pub(crate) fn give_back(&mut self, bucket: IntPoint, surface: S) {
+        self.stats.used_surfaces = self.stats.used_surfaces.saturating_sub(1);
+        self.stats.used_bytes = self.stats.used_bytes.saturating_sub(byte_size(bucket));
+        self.free.push((bucket, surface));
+        self.stats.free_surfaces += 1;
+        self.stats.free_bytes += byte_size(bucket);
+        self.trim(self.max_bytes);
+    }
+
+    /// Drops the oldest free surfaces until they take at most `max_bytes`.
+    pub(crate) fn trim(&mut self, max_bytes: usize) {
+        let mut excess = self.stats.free_bytes.saturating_sub(max_bytes);
+        let mut count = 0;
+        for (size, _) in &self.free {
+            if excess == 0 {
+                break;
+            }
+            excess = excess.saturating_sub(byte_size(*size));
+            self.stats.free_bytes -= byte_size(*size);
+            count += 1;
+        }
+        self.free.drain(..count);
+        self.stats.free_surfaces = self.free.len();
+    }
+
+    pub(crate) fn stats(&self) -> SurfacePoolStats {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SurfacePool, bucket_size};
+    use crate::IntPoint;
+
+    #[test]
+    fn sizes_are_rounded_up_to_buckets() {
+        assert_eq!(bucket_size(IntPoint::from((100, 1))), IntPoint::from((128, 32)));
+        assert_eq!(bucket_size(IntPoint::from((101, 128))), IntPoint::from((128, 128)));
+        assert_eq!(bucket_size(IntPoint::from((600, 0))), IntPoint::from((640, 32)));
+    }
+
+    #[test]
+    fn freed_surfaces_are_reused_within_their_bucket() {
+        let mut pool = SurfacePool::default();
+        let bucket = bucket_size(IntPoint::from((100, 100)));
+        let surface = pool.take(bucket, |_| 1);
+        pool.give_back(bucket, surface);
+        assert_eq!(pool.take(bucket_size(IntPoint::from((101, 99))), |_| 2), 1);
+        assert_eq!(pool.take(bucket_size(IntPoint::from((300, 300))), |_| 3), 3);
+
+        let stats = pool.stats();
+        assert_eq!((stats.allocations, stats.reuses, stats.free_surfaces), (2, 1, 0));
+        assert_eq!(stats.used_surfaces, 2);
+        assert_eq!(stats.used_bytes, (128 * 128 + 320 * 320) * 4);
+    }
+
+    #[test]
+    fn trimming_drops_the_oldest_surfaces_first() {
+        let mut pool = SurfacePool::default();
+        let bucket = IntPoint::from((32, 32));
+        for surface in 0..3 {
+            pool.give_back(bucket, surface);
+        }
+        pool.trim(32 * 32 * 4);
+        assert_eq!(pool.stats().free_surfaces, 1);
+        assert_eq!(pool.stats().free_bytes, 32 * 32 * 4);
+        assert_eq!(pool.take(bucket, |_| 3), 2);
+
+        pool.give_back(bucket, 4);
+        pool.trim(0);
+        assert_eq!(pool.stats().free_bytes, 0);
+    }
+}
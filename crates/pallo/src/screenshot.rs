@@ -0,0 +1,60 @@
+use crate::{App, CanvasType, Component, Cx, IntPoint, Later, RasterSurfaceType, Surface, tree::NodeId};
+
+/// A PNG encoded image, e.g. from `Cx::render_component_to_image`.
+pub struct EncodedImage {
+    /// The size in pixels.
+    pub size: IntPoint,
+    pub data: Vec<u8>,
+}
+
+struct ScreenshotRequest {
+    id: NodeId,
+    scale: f32,
+    result: Later<Option<EncodedImage>>,
+}
+
+#[derive(Default)]
+pub(crate) struct Screenshots {
+    requests: Vec<ScreenshotRequest>,
+}
+
+impl<A: App> Cx<A> {
+    /// Renders the component `id` and its descendants into an offscreen image the next time it's drawn, with `scale`
+    /// pixels per unit of its bounds. The result is `None` if the component wasn't drawn in the next frame or the
+    /// image couldn't be encoded.
+    pub fn render_component_to_image(&mut self, id: impl Into<NodeId>, scale: f32) -> Later<Option<EncodedImage>> {
+        let result = Later::default();
+        self.screenshots.requests.push(ScreenshotRequest { id: id.into(), scale, result: result.clone() });
+        result
+    }
+
+    #[inline]
+    pub(crate) fn has_requested_screenshots(&self) -> bool {
+        !self.screenshots.requests.is_empty()
+    }
+
+    /// Called by `Component::draw_instrumented` before drawing `component` on screen.
+    pub(crate) fn take_requested_screenshots<C: Component<A> + ?Sized>(&mut self, component: &C) {
+        let id = component.id().into();
+        while let Some(index) = self.screenshots.requests.iter().position(|request| request.id == id) {
+            let request = self.screenshots.requests.swap_remove(index);
+            let bounds = self.get_bounds(id);
+            let surface = Surface::new(bounds.size().to_int(), request.scale);
+            {
+                let mut canvas = surface.get_canvas();
+                canvas.set_scale_factor(request.scale);
+                canvas.translate((-bounds.left(), -bounds.top()));
+                component.draw(self, &mut canvas);
+            }
+            let size = surface.get_size().with_scale(request.scale);
+            request.result.set(surface.encode_png().map(|data| EncodedImage { size, data }));
+        }
+    }
+
+    /// Gives up on the screenshots of components that weren't drawn.
+    pub(crate) fn fail_requested_screenshots(&mut self) {
+        for request in self.screenshots.requests.drain(..) {
+            request.result.set(None);
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    cell::{Ref, RefCell, RefMut},
+    cell::{Cell, Ref, RefCell, RefMut},
     ops::Deref,
     rc::Rc,
 };
@@ -54,6 +54,8 @@ pub struct Runtime {
     computeds: RefCell<Vec<Option<ComputedData>>>,
     signals: RefCell<Vec<Option<SignalData>>>,
     current_computed_id: RefCell<Option<ComputedId>>,
+    /// Counts the times any signal was set, so that damage tracking can tell when something may look different.
+    changes: Cell<u64>,
 }
 
 pub struct SignalCx {
@@ -86,6 +88,11 @@ impl SignalCx {
     pub fn computed_static<T: Clone + 'static>(&self, value: T) -> Computed<T> {
         Computed::new_static(value)
     }
+
+    /// How many times signals of this context have been set. Only meaningful compared to an earlier count.
+    pub(crate) fn changes(&self) -> u64 {
+        self.rt.changes.get()
+    }
 }
 
 pub enum Computed<T> {
@@ -314,6 +321,7 @@ impl<T: 'static> Signal<T> {
     }
 
     fn mark_depending_computeds_dirty(&self) {
+        self.rt.changes.set(self.rt.changes.get() + 1);
         let mut computeds = self.rt.computeds.borrow_mut();
         let signals = self.rt.signals.borrow();
         for computed_id in &signals[self.id.0].as_ref().unwrap().dependents {
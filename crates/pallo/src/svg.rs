@@ -1,59 +1,99 @@
-use std::{rc::Rc, slice::Iter};
+use std::{fmt, rc::Rc, slice::Iter};
 
 use crate::{
     Align, App, Canvas, Cx, Point, Rect, point,
-    renderers::{CanvasType, PathType, renderer::Path},
+    renderers::{
+        CanvasType, PathBuilderType,
+        renderer::{Path, PathBuilder},
+    },
 };
 
+/// Why an SVG couldn't be parsed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SvgError {
+    /// The byte offset into the SVG source where parsing went wrong.
+    pub position: usize,
+    pub message: String,
+}
+
+impl SvgError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self { position, message: message.into() }
+    }
+}
+
+impl fmt::Display for SvgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "byte {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for SvgError {}
+
 #[derive(Debug)]
 enum Token {
     Command(char),
     Number(f32),
 }
 
-impl Token {
-    fn is_relative(&self) -> bool {
-        match self {
-            Token::Command(c) => c.is_lowercase(),
-            Token::Number(_) => false,
+/// Where the number starting at `start` ends. Numbers may follow each other without a separator, as in `1-2` or
+/// `0.5.5`, and may have an exponent, as in `1e-3`.
+fn number_end(d: &str, start: usize) -> usize {
+    let bytes = d.as_bytes();
+    let skip_digits = |i: &mut usize| {
+        while bytes.get(*i).is_some_and(u8::is_ascii_digit) {
+            *i += 1;
+        }
+    };
+    let mut i = start;
+    if matches!(bytes.get(i), Some(b'-' | b'+')) {
+        i += 1;
+    }
+    skip_digits(&mut i);
+    if bytes.get(i) == Some(&b'.') {
+        i += 1;
+        skip_digits(&mut i);
+    }
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        let mut exponent = i + 1;
+        if matches!(bytes.get(exponent), Some(b'-' | b'+')) {
+            exponent += 1;
+        }
+        // otherwise the `e` is left for a command
+        if bytes.get(exponent).is_some_and(u8::is_ascii_digit) {
+            i = exponent;
+            skip_digits(&mut i);
         }
     }
+    i
 }
 
-fn tokenize_svg_path(mut d: String) -> Vec<Token> {
-    let mut tokens: Vec<Token> = vec![];
-    let mut number = String::new();
-    d.push(' '); // make sure last token is added
-    for c in d.chars() {
+/// Splits the path data `d` into tokens along with their positions, `offset` being the position of `d` in the SVG.
+fn tokenize_svg_path(d: &str, offset: usize) -> Result<Vec<(usize, Token)>, SvgError> {
+    let mut tokens = vec![];
+    let mut i = 0;
+    while let Some(c) = d[i..].chars().next() {
         match c {
-            'A'..='Z' | 'a'..='z' | '-' | ',' | ' ' => {
-                if !number.is_empty()
-                    && let Ok(n) = std::mem::take(&mut number).parse::<f32>()
-                {
-                    tokens.push(Token::Number(n));
-                }
-            }
-            '.' => {
-                if number.contains('.')
-                    && let Ok(n) = std::mem::take(&mut number).parse::<f32>()
-                {
-                    tokens.push(Token::Number(n));
-                }
+            '0'..='9' | '.' | '-' | '+' => {
+                let end = number_end(d, i);
+                let number = d[i..end]
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|number| number.is_finite())
+                    .ok_or_else(|| SvgError::new(offset + i, format!("invalid number {:?}", &d[i..end])))?;
+                tokens.push((offset + i, Token::Number(number)));
+                i = end;
             }
-            _ => {}
-        }
-        match c {
             'A'..='Z' | 'a'..='z' => {
-                tokens.push(Token::Command(c));
-            }
-            '0'..='9' | '.' | '-' => {
-                number.push(c);
+                tokens.push((offset + i, Token::Command(c)));
+                i += 1;
             }
-            _ => {}
+            ',' => i += 1,
+            c if c.is_whitespace() => i += c.len_utf8(),
+            c => return Err(SvgError::new(offset + i, format!("unexpected {c:?} in path data"))),
         }
     }
-
-    tokens
+    Ok(tokens)
 }
 
 #[derive(Debug)]
@@ -65,11 +105,11 @@ enum SvgPathCommand {
     ClosePath,
 }
 
-fn get_n_numbers<const N: usize>(iter: &mut Iter<'_, Token>) -> Option<[f32; N]> {
+fn get_n_numbers<const N: usize>(iter: &mut Iter<'_, (usize, Token)>) -> Option<[f32; N]> {
     let mut out = [0.0; N];
     let backup = iter.clone();
     for j in 0..N {
-        if let Some(Token::Number(n)) = iter.next() {
+        if let Some((_, Token::Number(n))) = iter.next() {
             out[j] = *n;
         } else {
             *iter = backup;
@@ -79,17 +119,24 @@ fn get_n_numbers<const N: usize>(iter: &mut Iter<'_, Token>) -> Option<[f32; N]>
     Some(out)
 }
 
-fn parse_svg_path(d: impl Into<String>) -> Result<Vec<SvgPathCommand>, String> {
+/// Parses the path data `d`, found at `offset` in the SVG. Commands that aren't supported are skipped along with
+/// their numbers.
+fn parse_svg_path(d: &str, offset: usize) -> Result<Vec<SvgPathCommand>, SvgError> {
     let mut commands = Vec::new();
-    let tokens = tokenize_svg_path(d.into());
+    let tokens = tokenize_svg_path(d, offset)?;
     let mut iter = tokens.iter();
     let mut p = point(0.0, 0.0);
-    while let Some(token) = iter.next() {
-        match token {
-            Token::Command('M') | Token::Command('m') => {
+    while let Some((position, token)) = iter.next() {
+        let Token::Command(command) = token else {
+            return Err(SvgError::new(*position, "expected a command, found a number"));
+        };
+        let relative = command.is_ascii_lowercase();
+        let num_commands = commands.len();
+        let num_numbers = match command {
+            'M' | 'm' => {
                 let mut first = true;
                 while let Some([x, y]) = get_n_numbers(&mut iter) {
-                    if token.is_relative() {
+                    if relative {
                         p += point(x, y);
                     } else {
                         p = point(x, y);
@@ -101,43 +148,47 @@ fn parse_svg_path(d: impl Into<String>) -> Result<Vec<SvgPathCommand>, String> {
                     });
                     first = false;
                 }
+                2
             }
-            Token::Command('L') | Token::Command('l') => {
+            'L' | 'l' => {
                 while let Some([x, y]) = get_n_numbers(&mut iter) {
-                    if token.is_relative() {
+                    if relative {
                         p += point(x, y);
                     } else {
                         p = point(x, y);
                     }
                     commands.push(SvgPathCommand::LineTo(p));
                 }
+                2
             }
-            Token::Command('H') | Token::Command('h') => {
+            'H' | 'h' => {
                 while let Some([value]) = get_n_numbers(&mut iter) {
-                    if token.is_relative() {
+                    if relative {
                         p.x += value;
                     } else {
                         p.x = value;
                     }
                     commands.push(SvgPathCommand::LineTo(p));
                 }
+                1
             }
-            Token::Command('V') | Token::Command('v') => {
+            'V' | 'v' => {
                 while let Some([value]) = get_n_numbers(&mut iter) {
-                    if token.is_relative() {
+                    if relative {
                         p.y += value;
                     } else {
                         p.y = value;
                     }
                     commands.push(SvgPathCommand::LineTo(p));
                 }
+                1
             }
-            Token::Command('C') | Token::Command('c') => {
+            'C' | 'c' => {
                 while let Some([cp1_x, cp1_y, cp2_x, cp2_y, pt_x, pt_y]) = get_n_numbers(&mut iter) {
                     let mut cp1 = point(cp1_x, cp1_y);
                     let mut cp2 = point(cp2_x, cp2_y);
                     let pt = point(pt_x, pt_y);
-                    if token.is_relative() {
+                    if relative {
                         cp1 += p;
                         cp2 += p;
                         p += pt;
@@ -146,12 +197,13 @@ fn parse_svg_path(d: impl Into<String>) -> Result<Vec<SvgPathCommand>, String> {
                     }
                     commands.push(SvgPathCommand::Bezier { cp1, cp2, point: p });
                 }
+                6
             }
-            Token::Command('S') | Token::Command('s') => {
+            'S' | 's' => {
                 while let Some([cp_x, cp_y, pt_x, pt_y]) = get_n_numbers(&mut iter) {
                     let mut cp = point(cp_x, cp_y);
                     let pt = point(pt_x, pt_y);
-                    if token.is_relative() {
+                    if relative {
                         cp += p;
                         p += pt;
                     } else {
@@ -167,10 +219,11 @@ fn parse_svg_path(d: impl Into<String>) -> Result<Vec<SvgPathCommand>, String> {
                         point: p,
                     });
                 }
+                4
             }
-            Token::Command('A') | Token::Command('a') => {
+            'A' | 'a' => {
                 while let Some([rx, ry, angle, large_arc, sweep_arc, x, y]) = get_n_numbers(&mut iter) {
-                    if token.is_relative() {
+                    if relative {
                         p += point(x, y);
                     } else {
                         p = point(x, y);
@@ -183,16 +236,29 @@ fn parse_svg_path(d: impl Into<String>) -> Result<Vec<SvgPathCommand>, String> {
                         point: p,
                     });
                 }
+                7
             }
-            Token::Command('Z') => {
+            'Z' | 'z' => {
                 commands.push(SvgPathCommand::ClosePath);
+                0
             }
-            Token::Command(c) => {
-                panic!("Unknown command {c}");
-            }
-            _ => {
-                panic!("Unhandled number!");
+            c => {
+                crate::warn!("skipping the unsupported SVG path command {c:?} at byte {position}");
+                while let Some((_, Token::Number(_))) = iter.as_slice().first() {
+                    iter.next();
+                }
+                continue;
             }
+        };
+        if num_numbers == 0 {
+            continue;
+        }
+        if commands.len() == num_commands {
+            return Err(SvgError::new(*position, format!("expected {num_numbers} numbers after {command}")));
+        }
+        if let Some((position, Token::Number(_))) = iter.as_slice().first() {
+            let message = format!("expected the numbers after {command} in groups of {num_numbers}");
+            return Err(SvgError::new(*position, message));
         }
     }
 
@@ -204,33 +270,49 @@ struct SvgShape {
     pub(crate) viewbox: Rect,
 }
 
-fn get_shape(svg: &'static str) -> Result<SvgShape, String> {
-    let viewbox = {
-        let s = svg.find("viewBox=\"").ok_or("No viewbox found.")? + 9;
-        let e = s + svg[s..].find('"').ok_or("Incorrect viewbox tag.")?;
-        let mut tokens = svg[s..e].split(' ');
-        let x = tokens.next().and_then(|v| v.parse::<f32>().ok()).ok_or("Invalid viewbox")?;
-        let y = tokens.next().and_then(|v| v.parse::<f32>().ok()).ok_or("Invalid viewbox")?;
-        let width = tokens.next().and_then(|v| v.parse::<f32>().ok()).ok_or("Invalid viewbox")?;
-        let height = tokens.next().and_then(|v| v.parse::<f32>().ok()).ok_or("Invalid viewbox")?;
-        Rect::from_xywh(x, y, width, height)
-    };
+fn parse_viewbox(svg: &str) -> Result<Rect, SvgError> {
+    let start = svg.find("viewBox=\"").ok_or_else(|| SvgError::new(0, "no viewBox found"))? + 9;
+    let end = start + svg[start..].find('"').ok_or_else(|| SvgError::new(start, "the viewBox isn't closed"))?;
+    let values = svg[start..end]
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|value| !value.is_empty())
+        .map(|value| value.parse::<f32>())
+        .collect::<Result<Vec<_>, _>>();
+    match values.as_deref() {
+        Ok(&[x, y, width, height]) if width > 0.0 && height > 0.0 => Ok(Rect::from_xywh(x, y, width, height)),
+        Ok(&[_, _, _, _]) => Err(SvgError::new(start, "the viewBox has no area")),
+        _ => Err(SvgError::new(start, "expected four numbers in the viewBox")),
+    }
+}
 
+/// The path data of each `d` attribute in `svg`, along with its position.
+fn path_data(svg: &str) -> Result<Vec<(usize, &str)>, SvgError> {
     let mut paths = vec![];
+    let mut position = 0;
+    while let Some(found) = svg[position..].find("d=\"") {
+        let start = position + found + 3;
+        // e.g. `id="` isn't a path
+        let is_attribute = svg[..position + found].ends_with(char::is_whitespace);
+        let end = start + svg[start..].find('"').ok_or_else(|| SvgError::new(start, "the path data isn't closed"))?;
+        if is_attribute {
+            paths.push((start, &svg[start..end]));
+        }
+        position = end;
+    }
+    Ok(paths)
+}
 
+fn get_shape(svg: &str) -> Result<SvgShape, SvgError> {
+    let viewbox = parse_viewbox(svg)?;
     let even_odd = svg.contains("fill-rule=\"evenodd\"");
 
-    let mut position = 0;
-    while let Some(mut d_start) = svg[position..].find("d=\"").map(|v| v + 3) {
-        d_start += position;
-        let d_end = d_start + svg[d_start..].find('"').ok_or("Invalid path argument.")?;
-        let d = svg[d_start..d_end].to_owned();
-
-        let mut path = Path::default();
+    let mut paths = vec![];
+    for (offset, d) in path_data(svg)? {
+        let mut path = PathBuilder::default();
         if even_odd {
             path.fill_type_even_odd();
         }
-        for cmd in parse_svg_path(d)? {
+        for cmd in parse_svg_path(d, offset)? {
             match cmd {
                 SvgPathCommand::MoveTo(point) => {
                     path.move_to(point);
@@ -249,8 +331,7 @@ fn get_shape(svg: &'static str) -> Result<SvgShape, String> {
                 }
             }
         }
-        paths.push(path);
-        position = d_end;
+        paths.push(path.build());
     }
     Ok(SvgShape { viewbox, paths })
 }
@@ -268,8 +349,9 @@ impl Clone for Svg {
 }
 
 impl Svg {
-    pub fn new(svg: &'static str) -> Self {
-        Self { scale: 1.0, translation: Default::default(), shape: get_shape(svg).unwrap().into() }
+    /// Parses the paths of `svg`. Only the `viewBox`, the `d` attributes and `fill-rule="evenodd"` are read.
+    pub fn new(svg: &str) -> Result<Self, SvgError> {
+        Ok(Self { scale: 1.0, translation: Default::default(), shape: get_shape(svg)?.into() })
     }
 
     pub fn set_bounds<A: App>(&mut self, _cx: &mut Cx<A>, target_rect: Rect) {
@@ -294,11 +376,113 @@ impl Svg {
 
 #[cfg(test)]
 mod test {
-    use crate::svg::parse_svg_path;
+    use crate::svg::{SvgPathCommand, parse_svg_path, parse_viewbox, path_data};
 
     #[test]
     fn test_tokenizer() {
         let path = "M17 5H7a2 2 0 0 0-2 2v10a2 2 0 0 0 2 2h10a2 2 0 0 0 2-2V7a2 2 0 0 0-2-2ZM7 2a5 5 0 0 0-5 5v10a5 5 0 0 0 5 5h10a5 5 0 0 0 5-5V7a5 5 0 0 0-5-5H7Z";
-        let _ = parse_svg_path(path);
+        assert_eq!(parse_svg_path(path, 0).unwrap().len(), 20);
+    }
+
+    #[test]
+    fn numbers_may_be_packed_and_have_exponents() {
+        let commands = parse_svg_path("M1e1-2.5.5.5L+3,4e-1z", 0).unwrap();
+        assert!(matches!(commands[0], SvgPathCommand::MoveTo(p) if p.x == 10.0 && p.y == -2.5));
+        assert!(matches!(commands[1], SvgPathCommand::LineTo(p) if p.x == 0.5 && p.y == 0.5));
+        assert!(matches!(commands[2], SvgPathCommand::LineTo(p) if p.x == 3.0 && p.y == 0.4));
+        assert!(matches!(commands[3], SvgPathCommand::ClosePath));
+    }
+
+    #[test]
+    fn unsupported_commands_are_skipped() {
+        let commands = parse_svg_path("M0 0 Q1 1 2 2 L3 3", 0).unwrap();
+        assert_eq!(commands.len(), 2);
+        assert!(matches!(commands[1], SvgPathCommand::LineTo(p) if p.x == 3.0));
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        let position = |d: &str| parse_svg_path(d, 10).unwrap_err().position;
+        assert_eq!(position("1 2"), 10);
+        assert_eq!(position("M0 0 L1"), 15);
+        assert_eq!(position("M0 0 1"), 15);
+        assert_eq!(position("M0 0 L1 2 3"), 20);
+        assert_eq!(position("M0 0 L- 2"), 16);
+        assert_eq!(position("M0 0 L1 2 #"), 20);
+        assert_eq!(position("M0 0 Z 1"), 17);
+
+        assert_eq!(parse_viewbox(r#"<svg viewBox="0 0 x 24">"#).unwrap_err().position, 14);
+        assert!(parse_viewbox(r#"<svg viewBox="0 0 0 24">"#).is_err());
+        assert!(parse_viewbox(r#"<svg viewBox="0,0, 24 24">"#).is_ok());
+        assert_eq!(path_data(r#"<path id="a" d="M0 0"/>"#).unwrap(), [(16, "M0 0")]);
+    }
+
+    #[test]
+    fn malformed_input_never_panics() {
+        let corpus = [
+            "",
+            " ",
+            ",",
+            "M",
+            "Z",
+            "z1",
+            "M1",
+            "M1,",
+            "M1 2 3",
+            "-",
+            "+",
+            ".",
+            "-.",
+            "1e",
+            "1e+",
+            "1e-",
+            "e5",
+            "M1e",
+            "M1..2",
+            "M--1 2",
+            "M1 2 A",
+            "M0 0 A1 1 0 1 1",
+            "M0 0 A1 1 0 1 1 2 2 3",
+            "M\u{0}0",
+            "M0 0 L🦀 1",
+            "é",
+            "M 1e39 0",
+            "M NaN 0",
+            "M inf 0",
+            "M0 0 C1 2 3 4 5",
+            "M0 0 S1 2 3",
+            "M0 0 H",
+            "M0 0 V v h",
+        ];
+        for d in corpus {
+            let _ = parse_svg_path(d, 0);
+        }
+        for svg in [
+            "",
+            "viewBox=\"",
+            "<svg viewBox=\"0 0 24\">",
+            "<svg viewBox=\"0 0 24 24 24\">",
+            "<svg viewBox=\"0 0 24 24\"><path d=\"M0",
+            "d=\"M0 0\" viewBox=\"0 0 1 1\"",
+        ] {
+            let _ = parse_viewbox(svg);
+            let _ = path_data(svg);
+        }
+
+        // random strings from the characters that make up path data
+        const ALPHABET: &[u8] = b"MmLlHhVvCcSsAaZzQqTtEe0123456789.-+, ";
+        let mut state = 0x2545_f491_u32;
+        for _ in 0..10_000 {
+            let len = state as usize % 24;
+            let d: String = (0..len)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    ALPHABET[state as usize % ALPHABET.len()] as char
+                })
+                .collect();
+            let _ = parse_svg_path(&d, 0);
+        }
     }
 }
@@ -0,0 +1,157 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::Modifiers;
+
+/// Whether the modifiers held with an arrow key or backspace should act on whole words: Alt on Apple platforms and
+/// Ctrl elsewhere.
+pub fn is_word_modifier(mods: &Modifiers) -> bool {
+    if cfg!(any(target_os = "macos", target_os = "ios")) {
+        mods.alt
+    } else {
+        mods.ctrl || mods.meta
+    }
+}
+
+/// Whether the modifiers held with an arrow key or backspace should act on the whole line. Only Apple platforms have
+/// this, elsewhere Home and End do the same.
+pub fn is_line_modifier(mods: &Modifiers) -> bool {
+    cfg!(any(target_os = "macos", target_os = "ios")) && mods.meta
+}
+
+/// The start of the word before `index`, skipping any whitespace and punctuation in between.
+pub fn previous_word_boundary(text: &str, index: usize) -> usize {
+    text.split_word_bound_indices()
+        .rev()
+        .find(|(start, segment)| *start < index && is_word(segment))
+        .map_or(0, |(start, _)| start)
+}
+
+/// The end of the word after `index`, skipping any whitespace and punctuation in between.
+pub fn next_word_boundary(text: &str, index: usize) -> usize {
+    text.split_word_bound_indices()
+        .map(|(start, segment)| (start + segment.len(), segment))
+        .find(|(end, segment)| *end > index && is_word(segment))
+        .map_or(text.len(), |(end, _)| end)
+}
+
+/// The word that `index` is in as a byte range, or the run of whitespace or punctuation if it's not in one, which is
+/// what a double click selects.
+pub fn word_range_at(text: &str, index: usize) -> (usize, usize) {
+    let mut last = (text.len(), text.len());
+    for (start, segment) in text.split_word_bound_indices() {
+        let end = start + segment.len();
+        if end > index {
+            return (start, end);
+        }
+        last = (start, end);
+    }
+    last
+}
+
+/// The grapheme boundary before `index`, so that e.g. an emoji with a skin tone modifier or a letter with a combining
+/// accent is stepped over as a whole.
+pub fn previous_grapheme_boundary(text: &str, index: usize) -> usize {
+    text.grapheme_indices(true).map(|(start, _)| start).take_while(|start| *start < index).last().unwrap_or(0)
+}
+
+/// The grapheme boundary after `index`.
+pub fn next_grapheme_boundary(text: &str, index: usize) -> usize {
+    text.grapheme_indices(true)
+        .map(|(start, grapheme)| start + grapheme.len())
+        .find(|end| *end > index)
+        .unwrap_or(text.len())
+}
+
+/// The last grapheme boundary at or before `index`.
+pub fn floor_grapheme_boundary(text: &str, index: usize) -> usize {
+    if index >= text.len() {
+        text.len()
+    } else {
+        previous_grapheme_boundary(text, index + 1)
+    }
+}
+
+/// The byte offset and x position of every grapheme boundary in `text`, including both ends. `glyph_widths` has one
+/// width per `char`, as returned by `FontType::get_glyph_widths`.
+pub fn grapheme_positions(text: &str, glyph_widths: &[f32]) -> Vec<(usize, f32)> {
+    let mut widths = glyph_widths.iter();
+    let mut x = 0.0;
+    let mut positions = vec![(0, 0.0)];
+    for (start, grapheme) in text.grapheme_indices(true) {
+        x += widths.by_ref().take(grapheme.chars().count()).sum::<f32>();
+        positions.push((start + grapheme.len(), x));
+    }
+    positions
+}
+
+/// Shown in place of each grapheme of a masked value.
+pub const MASK_CHAR: char = '\u{2022}';
+
+/// `text` with every grapheme replaced by `MASK_CHAR`.
+pub fn mask_text(text: &str) -> String {
+    text.graphemes(true).map(|_| MASK_CHAR).collect()
+}
+
+/// Glyph widths for `text` as drawn by `mask_text`, one per `char` like `FontType::get_glyph_widths`. Each grapheme's
+/// first char gets the width of the mask and the rest get nothing.
+pub fn masked_glyph_widths(text: &str, mask_width: f32) -> Vec<f32> {
+    text.graphemes(true)
+        .flat_map(|grapheme| grapheme.chars().enumerate().map(move |(i, _)| if i == 0 { mask_width } else { 0.0 }))
+        .collect()
+}
+
+fn is_word(segment: &str) -> bool {
+    segment.chars().any(char::is_alphanumeric)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_boundaries_skip_whitespace_and_punctuation() {
+        let text = "gain: -6.0 dB, pan";
+        assert_eq!(previous_word_boundary(text, text.len()), 15);
+        assert_eq!(previous_word_boundary(text, 15), 11);
+        // from the middle of a word to its start
+        assert_eq!(previous_word_boundary(text, 2), 0);
+        assert_eq!(previous_word_boundary(text, 0), 0);
+
+        assert_eq!(next_word_boundary(text, 0), 4);
+        assert_eq!(next_word_boundary(text, 4), 10);
+        assert_eq!(next_word_boundary(text, 15), text.len());
+        assert_eq!(next_word_boundary(text, text.len()), text.len());
+    }
+
+    #[test]
+    fn word_ranges_cover_the_word_or_the_gap() {
+        let text = "gain: -6.0 dB";
+        assert_eq!(word_range_at(text, 0), (0, 4));
+        assert_eq!(word_range_at(text, 2), (0, 4));
+        assert_eq!(word_range_at(text, 4), (4, 5));
+        assert_eq!(word_range_at(text, 12), (11, 13));
+        // past the end selects the last word
+        assert_eq!(word_range_at(text, text.len()), (11, 13));
+        assert_eq!(word_range_at("", 0), (0, 0));
+    }
+
+    #[test]
+    fn graphemes_are_stepped_over_whole() {
+        // "e" with a combining acute accent, then a thumbs up with a skin tone modifier
+        let text = "ae\u{301}\u{1f44d}\u{1f3fd}b";
+        assert_eq!(next_grapheme_boundary(text, 0), 1);
+        assert_eq!(next_grapheme_boundary(text, 1), 4);
+        assert_eq!(next_grapheme_boundary(text, 4), 12);
+        assert_eq!(previous_grapheme_boundary(text, 12), 4);
+        assert_eq!(previous_grapheme_boundary(text, 4), 1);
+        assert_eq!(floor_grapheme_boundary(text, 6), 4);
+        assert_eq!(floor_grapheme_boundary(text, 12), 12);
+
+        let positions = grapheme_positions(text, &[1.0; 6]);
+        assert_eq!(positions, [(0, 0.0), (1, 1.0), (4, 3.0), (12, 5.0), (13, 6.0)]);
+
+        assert_eq!(mask_text(text).chars().count(), 4);
+        let positions = grapheme_positions(text, &masked_glyph_widths(text, 1.0));
+        assert_eq!(positions, [(0, 0.0), (1, 1.0), (4, 2.0), (12, 3.0), (13, 4.0)]);
+    }
+}
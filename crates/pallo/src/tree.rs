@@ -1,14 +1,18 @@
 use std::collections::VecDeque;
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+/// An index into the tree's arena along with the generation of the slot, so that an id of a removed node doesn't
+/// refer to whatever reuses its slot later.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub struct NodeId {
-    pub id: usize,
+    pub index: usize,
+    pub generation: u32,
 }
 
 pub struct Node<T> {
     data: T,
     parent: Option<NodeId>,
     children: Vec<NodeId>,
+    generation: u32,
 }
 
 pub struct Tree<T> {
@@ -19,33 +23,57 @@ pub struct Tree<T> {
 
 impl<T: Default> Default for Tree<T> {
     fn default() -> Self {
-        let root = Node { data: T::default(), parent: None, children: vec![] };
-        Self { nodes: vec![root], root_id: NodeId { id: 0 }, empty_cells: vec![] }
+        let root = Node { data: T::default(), parent: None, children: vec![], generation: 0 };
+        Self { nodes: vec![root], root_id: NodeId { index: 0, generation: 0 }, empty_cells: vec![] }
     }
 }
 
 impl<T: Default> Tree<T> {
     pub fn add(&mut self, parent_id: NodeId) -> NodeId {
-        let node = Node { data: T::default(), parent: Some(parent_id), children: vec![] };
-        let id = if let Some(idx) = self.empty_cells.pop() {
-            self.nodes[idx] = node;
-            NodeId { id: idx }
+        self.debug_assert_alive(parent_id);
+        let id = if let Some(index) = self.empty_cells.pop() {
+            // the generation was bumped when the slot was freed
+            let generation = self.nodes[index].generation;
+            self.nodes[index] = Node { data: T::default(), parent: Some(parent_id), children: vec![], generation };
+            NodeId { index, generation }
         } else {
-            self.nodes.push(node);
-            NodeId { id: self.nodes.len() - 1 }
+            self.nodes.push(Node { data: T::default(), parent: Some(parent_id), children: vec![], generation: 0 });
+            NodeId { index: self.nodes.len() - 1, generation: 0 }
         };
-        self.nodes.get_mut(parent_id.id).unwrap().children.push(id);
+        self.nodes[parent_id.index].children.push(id);
         id
     }
 
+    /// Adds a node at `index` among the children of `parent_id`, or last if `index` is past the end.
+    pub fn insert(&mut self, parent_id: NodeId, index: usize) -> NodeId {
+        let id = self.add(parent_id);
+        let children = &mut self.nodes[parent_id.index].children;
+        children.pop();
+        children.insert(index.min(children.len()), id);
+        id
+    }
+
+    /// Moves `id` along with its subtree to `index` among the children of `new_parent`. The caller makes sure that
+    /// `new_parent` isn't inside the subtree.
+    pub fn move_node(&mut self, id: NodeId, new_parent: NodeId, index: usize) {
+        self.debug_assert_alive(id);
+        self.debug_assert_alive(new_parent);
+        if let Some(old_parent) = self.nodes[id.index].parent {
+            self.nodes[old_parent.index].children.retain(|child| *child != id);
+        }
+        self.nodes[id.index].parent = Some(new_parent);
+        let children = &mut self.nodes[new_parent.index].children;
+        children.insert(index.min(children.len()), id);
+    }
+
     pub fn traverse_depth(&self, root: NodeId, mut callback: impl FnMut(NodeId, &T) -> bool) {
         let mut stack = Vec::new();
         stack.push(root);
 
         while let Some(current) = stack.pop() {
-            debug_assert!(current.id < self.nodes.len());
-            let node = unsafe { self.nodes.get_unchecked(current.id) };
-            if callback(current, &node.data) || current.id == root.id {
+            self.debug_assert_alive(current);
+            let node = unsafe { self.nodes.get_unchecked(current.index) };
+            if callback(current, &node.data) || current == root {
                 for child in node.children.iter().rev() {
                     stack.push(*child);
                 }
@@ -58,9 +86,9 @@ impl<T: Default> Tree<T> {
         stack.push(root);
 
         while let Some(current) = stack.pop() {
-            debug_assert!(current.id < self.nodes.len());
-            let node = unsafe { self.nodes.get_unchecked_mut(current.id) };
-            if callback(current, &mut node.data) || current.id == root.id {
+            self.debug_assert_alive(current);
+            let node = unsafe { self.nodes.get_unchecked_mut(current.index) };
+            if callback(current, &mut node.data) || current == root {
                 for child in node.children.iter().rev() {
                     stack.push(*child);
                 }
@@ -75,7 +103,7 @@ impl<T: Default> Tree<T> {
 
         while let Some(current) = queue.pop_front() {
             if (callback(current, self.get(current)))
-                && let Some(node) = self.nodes.get(current.id)
+                && let Some(node) = self.nodes.get(current.index)
             {
                 for child in &node.children {
                     queue.push_back(*child);
@@ -86,16 +114,39 @@ impl<T: Default> Tree<T> {
 
     // should remove the node specified by the id along with its entire subtree
     pub fn remove(&mut self, id: NodeId) {
-        if let Some(parent_id) = self.nodes[id.id].parent {
-            let children = &mut self.nodes.get_mut(parent_id.id).unwrap().children;
-            let index = children.iter().position(|x| *x == id).unwrap();
-            children.remove(index);
+        if !self.contains(id) {
+            return;
         }
-        self.empty_cells.push(id.id);
+        if let Some(parent_id) = self.nodes[id.index].parent
+            && self.contains(parent_id)
+        {
+            let children = &mut self.nodes[parent_id.index].children;
+            if let Some(index) = children.iter().position(|x| *x == id) {
+                children.remove(index);
+            }
+        }
+        self.nodes[id.index].generation = self.nodes[id.index].generation.wrapping_add(1);
+        self.empty_cells.push(id.index);
+    }
+
+    /// How many nodes haven't been removed, including the root.
+    pub fn len(&self) -> usize {
+        self.nodes.len() - self.empty_cells.len()
+    }
+
+    /// Whether `id` refers to a node that hasn't been removed.
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.nodes.get(id.index).is_some_and(|node| node.generation == id.generation)
+    }
+
+    pub fn get_children(&self, id: NodeId) -> &[NodeId] {
+        self.debug_assert_alive(id);
+        &self.nodes[id.index].children
     }
 
     pub fn get_children_mut(&mut self, id: NodeId) -> &mut Vec<NodeId> {
-        &mut self.nodes[id.id].children
+        self.debug_assert_alive(id);
+        &mut self.nodes[id.index].children
     }
 
     pub fn get_root_id(&self) -> NodeId {
@@ -103,16 +154,68 @@ impl<T: Default> Tree<T> {
     }
 
     pub fn get_parent(&self, id: NodeId) -> Option<NodeId> {
-        self.nodes[id.id].parent
+        self.debug_assert_alive(id);
+        self.nodes[id.index].parent
     }
 
     pub fn get_mut(&mut self, id: NodeId) -> &mut T {
-        debug_assert!(id.id < self.nodes.len());
-        unsafe { &mut self.nodes.get_unchecked_mut(id.id).data }
+        self.debug_assert_alive(id);
+        unsafe { &mut self.nodes.get_unchecked_mut(id.index).data }
     }
 
     pub fn get(&self, id: NodeId) -> &T {
-        debug_assert!(id.id < self.nodes.len());
-        unsafe { &self.nodes.get_unchecked(id.id).data }
+        self.debug_assert_alive(id);
+        unsafe { &self.nodes.get_unchecked(id.index).data }
+    }
+
+    #[inline]
+    fn debug_assert_alive(&self, id: NodeId) {
+        debug_assert!(id.index < self.nodes.len(), "{id:?} is out of bounds");
+        debug_assert!(self.contains(id), "{id:?} refers to a removed node");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reused_slots_get_a_new_generation() {
+        let mut tree = Tree::<u32>::default();
+        let root = tree.get_root_id();
+        let a = tree.add(root);
+        *tree.get_mut(a) = 1;
+        tree.remove(a);
+        assert!(!tree.contains(a));
+        assert_eq!(tree.len(), 1);
+
+        let b = tree.add(root);
+        assert_eq!(b.index, a.index);
+        assert_ne!(b, a);
+        assert!(tree.contains(b));
+        assert_eq!(*tree.get(b), 0);
+        assert_eq!(tree.get_children(root), [b]);
+        assert_eq!(tree.len(), 2);
+
+        // removing a stale id leaves the new node alone
+        tree.remove(a);
+        assert!(tree.contains(b));
+    }
+
+    #[test]
+    fn nodes_move_with_their_subtree() {
+        let mut tree = Tree::<u32>::default();
+        let root = tree.get_root_id();
+        let a = tree.add(root);
+        let b = tree.add(root);
+        let child = tree.add(a);
+        let first = tree.insert(root, 0);
+        assert_eq!(tree.get_children(root), [first, a, b]);
+
+        tree.move_node(a, b, 0);
+        assert_eq!(tree.get_children(root), [first, b]);
+        assert_eq!(tree.get_children(b), [a]);
+        assert_eq!(tree.get_parent(a), Some(b));
+        assert_eq!(tree.get_parent(child), Some(a));
     }
 }
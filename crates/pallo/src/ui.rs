@@ -1,22 +1,33 @@
-use keyboard_types::Key;
+use keyboard_types::{Code, Key, Location};
 use pallo_util::File;
-use std::rc::Rc;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 use web_time::Instant;
 
 use crate::{
-    Canvas, ComponentState, IntPoint, Overlay, PointerId, PointerState, SignalCx,
+    Canvas, ComponentState, ControlId, ControllerInput, Error, IntPoint, PointerId, PointerState, SignalCx,
     component::{Component, ComponentId, WeakComponentId},
     context::Cx,
-    event::{Event, EventStatus, MouseButton},
-    geometry::{Point, Rect},
+    event::{Event, EventStatus, MouseButton, ScrollDelta, ScrollGesture, ScrollPhase},
+    frame_stats::{FrameSample, FrameStatsHud},
+    geometry::{Margin, Point, Rect},
+    inspector::Inspector,
     platform::{Frame, Platform, PlatformCommon},
     point,
-    renderers::CanvasType,
+    renderers::{
+        CanvasType, DisplayListType, RendererType, VectorFormat,
+        renderer::{DisplayList, Renderer},
+    },
     rgb,
-    tree::Tree,
+    tree::{NodeId, Tree},
+    utils::span,
 };
 
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct Modifiers {
     pub meta: bool,
     pub shift: bool,
@@ -54,11 +65,37 @@ pub trait App: Sized + 'static {
     fn default_font_weight() -> f32 {
         550.0
     }
+    /// How many times per second `fixed_update` is called. `None` disables fixed updates.
+    fn fixed_update_rate() -> Option<f32> {
+        None
+    }
+    /// Called from `UI::draw` at `fixed_update_rate`, independently of the frame rate. `dt` is in seconds.
+    fn fixed_update(&mut self, _dt: f32) {}
+    /// How many samples per pixel the GPU draws with, for scenes with many paths that alias even with antialiasing.
+    /// Falls back to fewer where it's not supported. See `PlatformCommon::set_sample_count`.
+    fn sample_count() -> usize {
+        1
+    }
     fn draw_scrollbar(_cx: &mut Cx<Self>, canvas: &mut Canvas, bounds: Rect, active: bool) {
         canvas
             .fill(rgb(0xffffff).with_alpha(if active { 0.8 } else { 0.5 }))
             .draw_round_rect(bounds, bounds.width() * 0.5);
     }
+    /// Drawn by `ScrollList` in place of items that haven't been loaded yet.
+    fn draw_list_placeholder(_cx: &mut Cx<Self>, canvas: &mut Canvas, bounds: Rect) {
+        canvas.fill(rgb(0xffffff).with_alpha(0.08)).draw_round_rect(bounds.with_margin(Margin::xy(4.0, 2.0)), 4.0);
+    }
+    /// Drawn by `ScrollList` in the space above the items while it's pulled to refresh. `progress` goes from 0 to 1
+    /// as it's pulled further and is `None` once refreshing.
+    fn draw_refresh_indicator(cx: &mut Cx<Self>, canvas: &mut Canvas, bounds: Rect, progress: Option<f32>) {
+        let size = bounds.height().min(20.0);
+        let indicator = Rect::from_size(size, size).centered_within(bounds);
+        let (start, sweep) = match progress {
+            Some(progress) => (-90.0, 300.0 * progress),
+            None => ((cx.num_frames % 60) as f32 * 6.0, 270.0),
+        };
+        canvas.stroke(rgb(0xffffff).with_alpha(0.6), 2.0).draw_arc(indicator, start, sweep);
+    }
 }
 
 pub struct UI<A: App> {
@@ -66,19 +103,35 @@ pub struct UI<A: App> {
     pub(crate) ui_context: Cx<A>,
     last_frame_start: Instant,
     last_window_size: IntPoint,
+    last_insets: Margin,
     is_broadcasting: bool,
-    overlays: Vec<Overlay<dyn Component<A>>>,
+    fixed_update_accumulator: f32,
+    scroll_gesture: ScrollGesture,
+    /// Applied to the pen's state once the pointer event following it creates one.
+    pending_pen_input: Option<PenInput>,
+    recorder: Option<Recorder>,
+    playback: Option<Playback>,
+    inspector: Inspector,
+    frame_stats_hud: FrameStatsHud,
 }
 
+// limits how far fixed updates try to catch up after a stall
+const MAX_FIXED_UPDATES_PER_FRAME: usize = 8;
+// how soon and how close to the previous press a press has to be to count towards a double click
+const MULTI_CLICK_MS: u128 = 300;
+const CLICK_SLOP: f32 = 10.0;
+
 unsafe impl<A: App> Send for UI<A> {}
 unsafe impl<A: App> Sync for UI<A> {}
 
 impl<A: App> UI<A> {
     pub fn new<R: Component<A> + 'static>(
         init: A::AppInit,
-        platform: Platform,
+        mut platform: Platform,
         create_root: impl Fn(&mut Cx<A>, ComponentId) -> R,
     ) -> Self {
+        platform.set_sample_count(A::sample_count());
+        let last_insets = platform.safe_area_insets();
         let mut ui_context = Cx::new(init, platform);
         let root_id = ComponentId(Rc::new(ui_context.tree.add(ui_context.tree.get_root_id())));
         ui_context.component_ids.push(root_id.clone());
@@ -87,41 +140,241 @@ impl<A: App> UI<A> {
             ui_context,
             last_frame_start: Instant::now(),
             last_window_size: IntPoint::default(),
+            last_insets,
             is_broadcasting: false,
-            overlays: vec![],
+            fixed_update_accumulator: 0.0,
+            scroll_gesture: ScrollGesture::default(),
+            pending_pen_input: None,
+            recorder: None,
+            playback: None,
+            inspector: Inspector::default(),
+            frame_stats_hud: FrameStatsHud::default(),
         }
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub enum WindowEvent {
     Resized(IntPoint),
-    PointerMove { position: Point, id: PointerId },
-    PointerDown { position: Point, button: MouseButton, id: PointerId },
-    PointerUp { id: PointerId },
-    Keydown(Key),
-    Keyup(Key),
+    PointerMove {
+        position: Point,
+        id: PointerId,
+    },
+    PointerDown {
+        position: Point,
+        button: MouseButton,
+        id: PointerId,
+    },
+    PointerUp {
+        id: PointerId,
+    },
+    /// A pointer that isn't pressed went out of range, e.g. a hovering pen moved away from the tablet.
+    PointerLeave {
+        id: PointerId,
+    },
+    /// Pen data for the pointer event that follows it.
+    PenInput {
+        id: PointerId,
+        pressure: f32,
+        tilt: Point,
+        barrel_button: bool,
+    },
+    /// `key` is what the key means in the current layout and `code` is where it is on the keyboard, which is what
+    /// shortcuts that depend on the position, like WASD, should match. `repeat` is set for auto-repeats while the key
+    /// is held.
+    Keydown {
+        key: Key,
+        code: Code,
+        repeat: bool,
+        location: Location,
+    },
+    Keyup {
+        key: Key,
+        code: Code,
+        location: Location,
+    },
     ScaleFactorChanged(f32),
+    /// The safe area insets of the view changed, see `PlatformCommon::safe_area_insets`.
+    InsetsChanged(Margin),
     ModifiersChanged(Modifiers),
     FileHovered(Vec<String>),
     FileDropped(Vec<File>),
     FileDropCancelled,
-    MouseWheel(Point),
+    FileChanged(PathBuf),
+    MouseWheel {
+        delta: ScrollDelta,
+        phase: ScrollPhase,
+    },
     FocusChanged(bool),
+    /// A MIDI or gamepad control moved. Hosts that read these devices feed them in here, with `value` normalized
+    /// to `0.0..=1.0`.
+    Controller {
+        device: String,
+        control: ControlId,
+        value: f32,
+    },
+    PlatformError(Error),
+}
+
+impl WindowEvent {
+    /// Whether the event comes from the user, as opposed to the window or the file system.
+    fn is_input(&self) -> bool {
+        !matches!(
+            self,
+            WindowEvent::Resized(_)
+                | WindowEvent::ScaleFactorChanged(_)
+                | WindowEvent::InsetsChanged(_)
+                | WindowEvent::FocusChanged(_)
+                | WindowEvent::FileChanged(_)
+                | WindowEvent::PlatformError(_)
+        )
+    }
+}
+
+/// The data of a `WindowEvent::PenInput` for a pen that doesn't have a pointer state yet.
+#[derive(Clone, Copy)]
+struct PenInput {
+    id: PointerId,
+    pressure: f32,
+    tilt: Point,
+    barrel_button: bool,
+}
+
+impl PenInput {
+    fn apply(&self, state: &mut PointerState<impl App>) {
+        state.pressure = self.pressure;
+        state.tilt = self.tilt;
+        state.barrel_button = self.barrel_button;
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    /// Frames drawn since the recording started. Playback delivers the event on the same frame.
+    pub frame: u64,
+    /// Milliseconds since the recording started.
+    pub time_ms: f64,
+    pub event: WindowEvent,
+}
+
+/// Window events captured with `UI::start_recording`, which can be saved as JSON and replayed with
+/// `UI::play_recording` to reproduce an interaction.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct EventRecording {
+    /// The window size when the recording started, which is restored before playback.
+    pub window_size: IntPoint,
+    pub events: Vec<RecordedEvent>,
+}
+
+impl EventRecording {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+struct Recorder {
+    start: Instant,
+    start_frame: u64,
+    recording: EventRecording,
+}
+
+struct Playback {
+    start_frame: u64,
+    events: VecDeque<RecordedEvent>,
+}
+
+impl<A: App> UI<A> {
+    /// Starts capturing every window event passed to `on_event`. Any previous recording is discarded.
+    pub fn start_recording(&mut self) {
+        self.recorder = Some(Recorder {
+            start: Instant::now(),
+            start_frame: self.ui_context.num_frames,
+            recording: EventRecording { window_size: self.last_window_size, events: vec![] },
+        });
+    }
+
+    pub fn stop_recording(&mut self) -> Option<EventRecording> {
+        self.recorder.take().map(|recorder| recorder.recording)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Replays `recording` frame by frame from the next call to `draw`. Input from the platform is ignored until
+    /// playback finishes or `stop_playback` is called.
+    pub fn play_recording(&mut self, recording: EventRecording) {
+        self.recorder = None;
+        if recording.window_size != IntPoint::default() {
+            self.handle_event(WindowEvent::Resized(recording.window_size));
+        }
+        self.playback = Some(Playback { start_frame: self.ui_context.num_frames, events: recording.events.into() });
+    }
+
+    pub fn stop_playback(&mut self) {
+        self.playback = None;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    fn next_playback_event(&mut self) -> Option<WindowEvent> {
+        let playback = self.playback.as_mut()?;
+        let frame = self.ui_context.num_frames - playback.start_frame;
+        match playback.events.front() {
+            Some(next) if next.frame <= frame => playback.events.pop_front().map(|recorded| recorded.event),
+            Some(_) => None,
+            None => {
+                self.playback = None;
+                None
+            }
+        }
+    }
 }
 
 impl<A: App> UI<A> {
     pub fn broadcast_event(&mut self, event: &mut Event<A>) {
         if !self.is_broadcasting {
+            span!("broadcast_event");
             self.is_broadcasting = true;
-            self.root.event(&mut self.ui_context, event);
-            for overlay in self.overlays.iter() {
-                overlay.borrow_mut().event(&mut self.ui_context, event);
+            let (reaches_root, overlays) = self.ui_context.overlays_receiving(event);
+            if reaches_root {
+                self.root.dispatch_event(&mut self.ui_context, event);
+            }
+            for overlay in overlays {
+                overlay.borrow_mut().dispatch_event(&mut self.ui_context, event);
             }
             self.is_broadcasting = false;
         }
     }
 
+    /// Handles the window events that the platform has queued and sends out the events that components emitted, without
+    /// drawing. Otherwise that only happens in `draw`, so hosts can call this as input arrives between frames to have
+    /// e.g. knob drags follow the pointer at the rate of the input rather than the frame rate.
+    pub fn pump_events(&mut self) {
+        self.handle_window_events();
+        self.dispatch_input();
+    }
+
+    fn handle_window_events(&mut self) {
+        while let Some(event) = self.ui_context.platform.next_window_event() {
+            self.on_event(event);
+        }
+    }
+
+    fn dispatch_input(&mut self) {
+        while let Some(mut event) = self.ui_context.input.pop_front() {
+            self.broadcast_event(&mut event);
+        }
+    }
+
     pub fn draw(&mut self) {
+        span!("frame");
         let start = Instant::now();
 
         let scale_factor = self.ui_context.platform.get_scale_factor();
@@ -129,16 +382,31 @@ impl<A: App> UI<A> {
             self.on_event(WindowEvent::Resized(self.last_window_size));
         }
 
-        self.ui_context.frame_delta_ms = (start - self.last_frame_start).as_millis() as f32;
+        let frame_delta = start - self.last_frame_start;
+        self.ui_context.frame_delta_ms = frame_delta.as_millis() as f32;
         self.ui_context.num_frames += 1;
         self.last_frame_start = start;
 
-        for overlay in self.ui_context.overlays.drain(..) {
-            self.overlays.push(overlay);
+        // apply host automation to bound parameters
+        #[cfg(feature = "nih")]
+        self.ui_context.param_bindings.sync();
+
+        self.ui_context.file_loader.sync();
+        self.ui_context.fetcher.sync();
+        self.ui_context.poll_catalog_loads();
+        self.ui_context.poll_later_callbacks();
+        self.ui_context.poll_live_channels();
+
+        let events_start = Instant::now();
+        self.handle_window_events();
+        if self.scroll_gesture.has_ended(start) {
+            // goes to the container the gesture was scrolling, which is still the wheel target
+            let delta = ScrollDelta::Pixels(point(0.0, 0.0));
+            self.broadcast_event(&mut Event::MouseWheel { delta, phase: ScrollPhase::MomentumEnded });
         }
 
-        while let Some(event) = self.ui_context.platform.next_window_event() {
-            self.on_event(event);
+        while let Some(event) = self.next_playback_event() {
+            self.handle_event(event);
         }
 
         // check for long presses
@@ -160,8 +428,22 @@ impl<A: App> UI<A> {
             }
         }
 
+        let mut events_micros = (Instant::now() - events_start).as_micros();
+
+        // run fixed rate updates
+        if let Some(rate) = A::fixed_update_rate() {
+            let dt = 1.0 / rate;
+            self.fixed_update_accumulator = (self.fixed_update_accumulator + frame_delta.as_secs_f32())
+                .min(dt * MAX_FIXED_UPDATES_PER_FRAME as f32);
+            while self.fixed_update_accumulator >= dt {
+                self.ui_context.app.fixed_update(dt);
+                self.fixed_update_accumulator -= dt;
+            }
+        }
+
         // send update event
         {
+            span!("update");
             let start = Instant::now();
             self.broadcast_event(&mut Event::Update);
             let duration = (Instant::now() - start).as_micros();
@@ -178,9 +460,9 @@ impl<A: App> UI<A> {
         }
 
         // handle and broadcast input events
-        while let Some(mut e) = self.ui_context.input.pop_front() {
-            self.broadcast_event(&mut e);
-        }
+        let input_start = Instant::now();
+        self.dispatch_input();
+        events_micros += (Instant::now() - input_start).as_micros();
 
         // garbage collect removed components
         self.ui_context.component_ids.retain(|id| {
@@ -202,38 +484,172 @@ impl<A: App> UI<A> {
         });
 
         // advance all animations
-        self.ui_context.animations.tick(self.ui_context.frame_delta_ms);
+        let animating = self.ui_context.animations.tick(self.ui_context.frame_delta_ms);
+        self.ui_context.tick_bounds_animations();
+        self.ui_context.tick_unmounts(self.ui_context.frame_delta_ms);
+        self.ui_context.invalidate_changes(animating);
 
         // draw
-        if let Some(mut frame) = self.ui_context.platform.new_frame() {
+        let mut submit_micros = 0;
+        if self.inspector.enabled || self.ui_context.frame_stats.is_hud_visible() {
+            self.ui_context.invalidate_all();
+        }
+        let needs_frame = self.ui_context.has_damage() || self.ui_context.has_requested_screenshots();
+        if needs_frame && let Some(mut frame) = self.ui_context.platform.new_frame() {
             let mut canvas = frame.canvas();
             canvas.set_scale_factor(self.ui_context.scale_factor.get_fast());
             canvas.scale(self.ui_context.ui_scale);
             {
+                span!("draw");
                 let start = Instant::now();
-                self.root.draw(&mut self.ui_context, &mut canvas);
-                for overlay in self.overlays.iter().rev() {
-                    overlay.borrow().draw(&mut self.ui_context, &mut canvas);
-                }
+                self.root.draw_instrumented(&mut self.ui_context, &mut canvas);
+                self.draw_portals(&mut canvas);
+                self.draw_overlays(&mut canvas);
+                self.ui_context.fail_requested_screenshots();
                 self.ui_context.draw_time_micros = (Instant::now() - start).as_micros();
+                if self.inspector.enabled {
+                    self.inspector.draw(&mut self.ui_context, &mut canvas);
+                }
+                if self.ui_context.frame_stats.is_hud_visible() {
+                    self.frame_stats_hud.draw(&mut self.ui_context, &mut canvas);
+                }
             }
+            frame.set_dirty_rects(self.ui_context.take_dirty_rects());
+            span!("submit_frame");
+            let submit_start = Instant::now();
             self.ui_context.platform.end_frame(frame);
+            submit_micros = (Instant::now() - submit_start).as_micros();
         }
 
         // calculate cpu time
-        self.ui_context.frame_time_micros = (Instant::now() - start).as_micros();
+        let cx = &mut self.ui_context;
+        cx.frame_time_micros = (Instant::now() - start).as_micros();
+        cx.frame_stats.add_sample(FrameSample {
+            interval_micros: frame_delta.as_micros(),
+            frame_micros: cx.frame_time_micros,
+            events_micros,
+            update_micros: cx.update_time_micros,
+            draw_micros: cx.draw_time_micros,
+            submit_micros,
+        });
+    }
+
+    fn layout_root(&mut self) {
+        let cx = &mut self.ui_context;
+        let size = self.last_window_size;
+        let bounds = Rect::from_size(size.x as f32, size.y as f32);
+
+        let scale = cx.app.get_ui_scale(size);
+        cx.ui_bounds = bounds.with_scale(1.0 / scale);
+        cx.ui_scale = scale;
+        cx.safe_area.set_if_changed(self.last_insets.with_scale(1.0 / scale));
+
+        let scale_factor_changed = cx.scale_factor.set_if_changed(cx.platform.get_scale_factor());
+        let root_bounds = cx.root_bounds();
+        if scale_factor_changed || self.root.get_bounds(cx) != root_bounds {
+            span!("layout");
+            self.root.layout(cx, root_bounds);
+        }
     }
 
-    fn update_hovered_component(tree: &mut Tree<ComponentState<A>>, pointer: &mut PointerState<A>) {
-        let mut hovered_component = None;
-        tree.traverse_depth(tree.get_root_id(), |id, state| {
-            let contains_point = state.bounds.contains(&pointer.position);
-            if state.visible && state.hoverable && !Cx::is_disabled(tree, id) && contains_point {
-                hovered_component = Some(id);
+    fn update_hovered_component(cx: &mut Cx<A>, pointer_id: PointerId) {
+        let Some(position) = cx.pointer_state.get(&pointer_id).map(|pointer| pointer.position) else {
+            return;
+        };
+        let hovered_component = Self::component_at(cx, position, |state| state.hoverable);
+        if let Some(pointer) = cx.pointer_state.get_mut(&pointer_id) {
+            pointer.hovered_component = hovered_component;
+        }
+    }
+
+    /// The topmost visible and enabled component at `position` that `accepts`.
+    fn component_at(cx: &Cx<A>, position: Point, accepts: impl Fn(&ComponentState<A>) -> bool) -> Option<NodeId> {
+        let (root_hoverable, overlays) = cx.hoverable_overlays();
+        let portals: Vec<_> = cx.portals.iter().map(|(id, _)| *id).collect();
+        Self::component_in(&cx.tree, root_hoverable, &portals, &overlays, position, accepts)
+    }
+
+    /// Finds the topmost component under `position` that `accepts`, searching the main UI if `root_hoverable`, then
+    /// `portals`, then `overlays`.
+    fn component_in(
+        tree: &Tree<ComponentState<A>>,
+        root_hoverable: bool,
+        portals: &[NodeId],
+        overlays: &[NodeId],
+        position: Point,
+        accepts: impl Fn(&ComponentState<A>) -> bool,
+    ) -> Option<NodeId> {
+        let mut found = None;
+        let mut hit_test = |id: NodeId, state: &ComponentState<A>| {
+            let contains_point = state.bounds.contains(&state.to_local(position));
+            // unmounting components can't be hit, nor can their children
+            let is_unmounting = state.unmount.is_some();
+            if state.visible && accepts(state) && !is_unmounting && !Cx::is_disabled(tree, id) && contains_point {
+                found = Some(id);
+            }
+            state.visible && !is_unmounting && (!state.clips_children || contains_point)
+        };
+        // portals are tested after the rest of the UI since they're drawn on top, and without their ancestors'
+        // clipping, and overlays are tested last in the order of their layers
+        if root_hoverable {
+            tree.traverse_depth(tree.get_root_id(), |id, state| !state.portal && !state.overlay && hit_test(id, state));
+            for id in portals {
+                if tree.contains(*id) && Cx::is_visible_in(tree, *id) {
+                    tree.traverse_depth(*id, &mut hit_test);
+                }
+            }
+        }
+        for id in overlays {
+            if Cx::is_visible_in(tree, *id) {
+                tree.traverse_depth(*id, &mut hit_test);
             }
-            state.visible && (!state.clips_children || contains_point)
+        }
+        found
+    }
+
+    fn draw_portals(&mut self, canvas: &mut Canvas) {
+        let cx = &mut self.ui_context;
+        cx.portals.retain(|(id, component)| cx.tree.contains(*id) && component.strong_count() > 0);
+        let portals: Vec<_> = cx.portals.iter().filter_map(|(_, component)| component.upgrade()).collect();
+        for portal in portals {
+            portal.borrow().draw_instrumented(cx, canvas);
+        }
+    }
+
+    fn draw_overlays(&mut self, canvas: &mut Canvas) {
+        for overlay in self.ui_context.open_overlays() {
+            overlay.borrow().draw_instrumented(&mut self.ui_context, canvas);
+        }
+    }
+
+    /// Writes the UI as it's currently laid out to a single page PDF at `path`, with shapes and text kept as vectors.
+    pub fn export_pdf(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        self.export_vector(VectorFormat::Pdf, path.as_ref())
+    }
+
+    /// Like `export_pdf`, but writes an SVG.
+    pub fn export_svg(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        self.export_vector(VectorFormat::Svg, path.as_ref())
+    }
+
+    fn export_vector(&mut self, format: VectorFormat, path: &Path) -> Result<(), Error> {
+        let ui_scale = self.ui_context.ui_scale;
+        let size = self.ui_context.ui_bounds.size() * ui_scale;
+        let list = DisplayList::record(Rect::from_xywh(0.0, 0.0, size.x, size.y), |canvas| {
+            canvas.set_scale_factor(1.0);
+            canvas.scale(ui_scale);
+            self.root.draw_instrumented(&mut self.ui_context, canvas);
+            self.draw_portals(canvas);
+            self.draw_overlays(canvas);
         });
-        pointer.hovered_component = hovered_component;
+        let data = Renderer::export_vector(format, size, &list)?;
+        std::fs::write(path, data)?;
+        Ok(())
+    }
+
+    /// Shows or hides the debug inspector, which debug builds also toggle with Ctrl+Shift+I (Cmd+Shift+I on macOS).
+    pub fn toggle_inspector(&mut self) {
+        self.inspector.enabled = !self.inspector.enabled;
     }
 
     pub fn should_resize_to(&mut self) -> Option<IntPoint> {
@@ -241,51 +657,73 @@ impl<A: App> UI<A> {
     }
 
     pub fn on_event(&mut self, event: WindowEvent) -> EventStatus {
+        if self.playback.is_some() && event.is_input() {
+            return EventStatus::Ignored;
+        }
+        if let Some(recorder) = &mut self.recorder {
+            recorder.recording.events.push(RecordedEvent {
+                frame: self.ui_context.num_frames - recorder.start_frame,
+                time_ms: recorder.start.elapsed().as_secs_f64() * 1000.0,
+                event: event.clone(),
+            });
+        }
+        self.handle_event(event)
+    }
+
+    fn handle_event(&mut self, event: WindowEvent) -> EventStatus {
+        if event.is_input() {
+            // e.g. hovering changes how components look without them invalidating
+            self.ui_context.invalidate_all();
+        }
         match event {
             WindowEvent::Resized(size) => {
                 self.last_window_size = size;
-                let cx = &mut self.ui_context;
-                cx.platform.set_view_size(size.into());
-                let bounds = Rect::from_size(size.x as f32, size.y as f32);
-
-                let scale = cx.app.get_ui_scale(size);
-                cx.ui_bounds = bounds.with_scale(1.0 / scale);
-                cx.ui_scale = scale;
-
-                let scale_factor_changed = cx.scale_factor.set_if_changed(cx.platform.get_scale_factor());
-                if scale_factor_changed || self.root.get_bounds(cx) != cx.ui_bounds {
-                    self.root.layout(cx, cx.ui_bounds);
-                }
+                self.ui_context.invalidate_all();
+                self.ui_context.platform.set_view_size(size.into());
+                self.layout_root();
+            }
+            WindowEvent::InsetsChanged(insets) => {
+                self.last_insets = insets;
+                self.layout_root();
             }
             WindowEvent::PointerMove { mut position, id } => {
+                let pen_input = self.pending_pen_input.take();
                 let cx = &mut self.ui_context;
                 position = position / cx.ui_scale;
 
-                let state = cx.pointer_state.entry(id).or_default();
+                let state = cx.pointer_state.entry(id).or_insert_with(|| PointerState { id, ..Default::default() });
+                if let Some(pen_input) = pen_input.filter(|pen_input| pen_input.id == id) {
+                    pen_input.apply(state);
+                }
                 state.velocity = position - state.position;
                 state.position = position;
                 state.delta = state.position - state.down_position;
                 state.delta_sum += state.delta;
 
-                Self::update_hovered_component(&mut cx.tree, state);
+                Self::update_hovered_component(cx, id);
 
                 let state = self.ui_context.pointer_state[&id].clone();
                 self.broadcast_event(&mut Event::PointerMove(state));
             }
             WindowEvent::PointerDown { mut position, button, id } => {
+                let pen_input = self.pending_pen_input.take();
                 let cx = &mut self.ui_context;
                 position = position / cx.ui_scale;
 
                 // update pointer state and hovered component
-                let state = cx.pointer_state.entry(id).or_default();
+                let state = cx.pointer_state.entry(id).or_insert_with(|| PointerState { id, ..Default::default() });
+                if let Some(pen_input) = pen_input.filter(|pen_input| pen_input.id == id) {
+                    pen_input.apply(state);
+                }
                 state.delta = point(0.0, 0.0);
                 state.delta_sum = point(0.0, 0.0);
                 state.button = button;
                 state.position = position;
                 state.down_position = state.position;
                 state.down_time = Some(Instant::now());
-                Self::update_hovered_component(&mut cx.tree, state);
+                Self::update_hovered_component(cx, id);
 
+                let state = cx.pointer_state.get_mut(&id).unwrap();
                 if let Some(hovered) = state.hovered_component {
                     state.pressed_component = Some(hovered);
                 }
@@ -297,8 +735,8 @@ impl<A: App> UI<A> {
                 cx.previous_pointer_down_time = now;
                 let movement_since_last_down = (cx.previous_pointer_down_position - position).len();
                 cx.previous_pointer_down_position = position;
-                if mouse_down_delta_ms > 300
-                    || movement_since_last_down > 10.0
+                if mouse_down_delta_ms > MULTI_CLICK_MS
+                    || movement_since_last_down > CLICK_SLOP
                     || state.hovered_component != cx.num_clicks_component
                 {
                     cx.num_clicks = 1;
@@ -326,7 +764,19 @@ impl<A: App> UI<A> {
             }
             WindowEvent::PointerUp { id } => {
                 if let Some(state) = self.ui_context.pointer_state.get(&id).cloned() {
-                    self.broadcast_event(&mut Event::PointerUp(state));
+                    self.broadcast_event(&mut Event::PointerUp(state.clone()));
+
+                    let is_click = state.pressed_component.is_some()
+                        && state.pressed_component == state.hovered_component
+                        && !state.is_long_press
+                        && (state.position - state.down_position).len() <= CLICK_SLOP;
+                    if is_click {
+                        let count = self.ui_context.num_clicks;
+                        self.broadcast_event(&mut Event::Click { pointer: state.clone(), count });
+                        if count == 2 {
+                            self.broadcast_event(&mut Event::DoubleClick(state));
+                        }
+                    }
                 }
 
                 let cx = &mut self.ui_context;
@@ -334,13 +784,28 @@ impl<A: App> UI<A> {
                     state.pressed_component = None;
                     state.is_long_press = false;
                     state.down_time = None;
-                    Self::update_hovered_component(&mut cx.tree, state);
+                    Self::update_hovered_component(cx, id);
                 }
 
-                if let PointerId::Touch(_) = id {
+                // touches and pens get a new state the next time they come into range
+                if let PointerId::Touch(_) | PointerId::Pen(_) = id {
                     self.ui_context.pointer_state.remove(&id);
                 }
             }
+            WindowEvent::PointerLeave { id } => {
+                let cx = &mut self.ui_context;
+                if cx.pointer_state.get(&id).is_some_and(|state| state.pressed_component.is_none()) {
+                    cx.pointer_state.remove(&id);
+                }
+            }
+            WindowEvent::PenInput { id, pressure, tilt, barrel_button } => {
+                let pen_input = PenInput { id, pressure, tilt, barrel_button };
+                // a pen coming into range gets its state from the event that follows
+                match self.ui_context.pointer_state.get_mut(&id) {
+                    Some(state) => pen_input.apply(state),
+                    None => self.pending_pen_input = Some(pen_input),
+                }
+            }
             WindowEvent::ScaleFactorChanged(scale_factor) => {
                 self.ui_context.scale_factor.set(scale_factor);
             }
@@ -358,8 +823,12 @@ impl<A: App> UI<A> {
                 cx.mods.ctrl = mods.ctrl;
                 self.broadcast_event(&mut event);
             }
-            WindowEvent::MouseWheel(delta) => {
-                self.broadcast_event(&mut Event::MouseWheel(delta));
+            WindowEvent::MouseWheel { delta, phase } => {
+                let phase = self.scroll_gesture.on_wheel(phase, Instant::now());
+                let cx = &mut self.ui_context;
+                let innermost = Self::component_at(cx, cx.main_pointer().position, |state| state.scroll.is_some());
+                cx.route_wheel(innermost, delta.to_pixels());
+                self.broadcast_event(&mut Event::MouseWheel { delta, phase });
             }
             WindowEvent::FileHovered(path) => {
                 self.broadcast_event(&mut Event::FileHovered(path));
@@ -370,14 +839,30 @@ impl<A: App> UI<A> {
                 return EventStatus::Captured;
             }
             WindowEvent::FileDropCancelled => self.broadcast_event(&mut Event::FileDropCancelled),
-            WindowEvent::Keydown(key) => {
-                let mut event = Event::Keydown { key, captured: false };
+            WindowEvent::FileChanged(path) => self.broadcast_event(&mut Event::FileChanged(path)),
+            WindowEvent::PlatformError(error) => {
+                crate::error!("{error}");
+                self.broadcast_event(&mut Event::PlatformError(error));
+            }
+            WindowEvent::Keydown { key, code, repeat, location } => {
+                if cfg!(debug_assertions) && !repeat && Inspector::is_toggle_chord(&self.ui_context, &key) {
+                    self.toggle_inspector();
+                    return EventStatus::Captured;
+                }
+                let mut event = Event::Keydown { key, code, repeat, location, captured: false };
                 self.broadcast_event(&mut event);
                 if let Event::Keydown { captured: true, .. } = event {
                     return EventStatus::Captured;
                 }
             }
-            WindowEvent::Keyup(key) => self.broadcast_event(&mut Event::Keyup(key)),
+            WindowEvent::Keyup { key, code, location } => {
+                self.broadcast_event(&mut Event::Keyup { key, code, location })
+            }
+            WindowEvent::Controller { device, control, value } => {
+                let input = ControllerInput { device, control, value };
+                self.ui_context.controllers.handle(&input);
+                self.broadcast_event(&mut Event::Controller(input));
+            }
             WindowEvent::FocusChanged(is_focused) => {
                 self.broadcast_event(&mut Event::WindowFocusChanged(is_focused));
                 if !is_focused && self.ui_context.focused_component.is_some() {
@@ -394,3 +879,89 @@ impl<A: App> UI<A> {
         EventStatus::Ignored
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// An app for tests that need an `App` type but never run it.
+    pub(crate) struct TestApp;
+
+    impl App for TestApp {
+        type Input = ();
+        type FontId = usize;
+        type AppInit = ();
+        type ComponentState = ();
+
+        fn new(_rt: &SignalCx, _init: Self::AppInit) -> Self {
+            Self
+        }
+
+        fn get_ui_scale(&self, _size: IntPoint) -> f32 {
+            1.0
+        }
+
+        fn get_initial_size(_init: &Self::AppInit) -> IntPoint {
+            IntPoint { x: 100, y: 100 }
+        }
+    }
+
+    #[test]
+    fn recording_survives_json() {
+        let recording = EventRecording {
+            window_size: IntPoint { x: 800, y: 600 },
+            events: vec![
+                RecordedEvent {
+                    frame: 3,
+                    time_ms: 50.0,
+                    event: WindowEvent::PointerDown {
+                        position: point(10.0, 20.0),
+                        button: MouseButton::Left,
+                        id: PointerId::Mouse,
+                    },
+                },
+                RecordedEvent {
+                    frame: 4,
+                    time_ms: 66.0,
+                    event: WindowEvent::Keydown {
+                        key: Key::Enter,
+                        code: Code::Enter,
+                        repeat: false,
+                        location: Location::Standard,
+                    },
+                },
+            ],
+        };
+        let restored = EventRecording::from_json(&recording.to_json().unwrap()).unwrap();
+        assert_eq!(restored.window_size, recording.window_size);
+        assert_eq!(restored.events.len(), 2);
+        assert_eq!(restored.events[0].frame, 3);
+        assert!(matches!(
+            restored.events[0].event,
+            WindowEvent::PointerDown { position, button: MouseButton::Left, id: PointerId::Mouse }
+                if position == point(10.0, 20.0)
+        ));
+        assert!(matches!(restored.events[1].event, WindowEvent::Keydown { key: Key::Enter, code: Code::Enter, .. }));
+    }
+
+    #[test]
+    fn portals_can_be_hit_outside_their_ancestors_clip() {
+        let mut tree = Tree::<ComponentState<TestApp>>::default();
+        let root = tree.get_root_id();
+        tree.get_mut(root).bounds = Rect::from_xywh(0.0, 0.0, 100.0, 100.0);
+        let clip = tree.add(root);
+        tree.get_mut(clip).bounds = Rect::from_xywh(0.0, 0.0, 10.0, 10.0);
+        let portal = tree.add(clip);
+        let content = tree.add(portal);
+        tree.get_mut(portal).portal = true;
+        tree.get_mut(portal).bounds = Rect::from_xywh(50.0, 50.0, 20.0, 20.0);
+        tree.get_mut(content).bounds = Rect::from_xywh(50.0, 50.0, 20.0, 20.0);
+        tree.get_mut(content).hoverable = true;
+        let hoverable = |state: &ComponentState<TestApp>| state.hoverable;
+        let position = point(60.0, 60.0);
+        // the clipping parent hides the portal from the main pass
+        assert_eq!(UI::<TestApp>::component_in(&tree, true, &[], &[], position, hoverable), None);
+        assert_eq!(UI::<TestApp>::component_in(&tree, true, &[portal], &[], position, hoverable), Some(content));
+        assert_eq!(UI::<TestApp>::component_in(&tree, false, &[portal], &[], position, hoverable), None);
+    }
+}
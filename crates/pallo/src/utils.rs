@@ -1,17 +1,31 @@
 use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
 
 use crate::{App, Canvas, CanvasType, Cx, RasterSurfaceType, Rect, Surface};
 use std::{
     any::Any,
     collections::VecDeque,
-    sync::{Arc, atomic::AtomicBool},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+    },
 };
 
-#[derive(Eq, Hash, PartialEq, Clone, Copy)]
+/// Enters a `tracing` span that lasts until the end of the scope. Compiles to nothing without the `tracing` feature.
+macro_rules! span {
+    ($($args:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!($($args)*).entered();
+    };
+}
+pub(crate) use span;
+
+#[derive(Eq, Hash, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum PointerId {
     Mouse,
     DragAndDrop,
     Touch(usize),
+    Pen(usize),
 }
 
 pub struct Output<T> {
@@ -71,7 +85,7 @@ impl CachedCanvas {
     }
 
     pub fn layout<A: App>(&mut self, cx: &mut Cx<A>, bounds: Rect) {
-        self.surface = Surface::new(bounds.size().to_int(), cx.scale_factor.get_fast());
+        self.surface = cx.create_surface(bounds.size().to_int(), cx.scale_factor.get_fast());
     }
 
     pub fn mark_dirty(&mut self) {
@@ -89,37 +103,338 @@ impl CachedCanvas {
     }
 }
 
+struct LaterState<T> {
+    value: Option<T>,
+    context: Option<Box<dyn Any + Send>>,
+    /// Takes the value instead of it being stored, set when the `Later` is mapped or chained.
+    on_set: Option<Box<dyn FnOnce(T) + Send>>,
+    cancelled: bool,
+}
+
+/// A value that's set later, e.g. by a platform dialog or a background thread, and polled with `value` or handled with
+/// `on_complete`.
 pub struct Later<T> {
-    value: Arc<Mutex<Option<T>>>,
-    context: Arc<Mutex<Option<Box<dyn Any + Send>>>>,
+    state: Arc<Mutex<LaterState<T>>>,
 }
 
 impl<T> Clone for Later<T> {
     fn clone(&self) -> Self {
-        Self { value: self.value.clone(), context: self.context.clone() }
+        Self { state: self.state.clone() }
     }
 }
 
 impl<T> Default for Later<T> {
     fn default() -> Self {
-        Self { value: Arc::new(Mutex::new(None)), context: Arc::new(Mutex::new(None)) }
+        Self { state: Arc::new(Mutex::new(LaterState { value: None, context: None, on_set: None, cancelled: false })) }
     }
 }
 
 impl<T> Later<T> {
+    /// Sets the value, unless the `Later` has been cancelled.
     pub fn set(&self, value: T) {
-        *self.value.lock() = Some(value);
+        let mut state = self.state.lock();
+        if state.cancelled {
+            return;
+        }
+        match state.on_set.take() {
+            Some(on_set) => {
+                drop(state);
+                (on_set)(value);
+            }
+            None => state.value = Some(value),
+        }
     }
 
     pub fn value(&self) -> Option<T> {
-        self.value.lock().take()
+        self.state.lock().value.take()
     }
 
     pub fn set_context<M: Send + 'static>(&self, meta: M) {
-        *self.context.lock() = Some(Box::new(meta));
+        self.state.lock().context = Some(Box::new(meta));
     }
 
     pub fn take_context<M: 'static>(&self) -> Option<Box<M>> {
-        self.context.lock().take().and_then(|v| v.downcast().ok())
+        self.state.lock().context.take().and_then(|v| v.downcast().ok())
+    }
+
+    /// Drops the value and any callbacks, and ignores values set from now on. Producers can check `is_cancelled` to
+    /// stop working on a value that's no longer wanted.
+    pub fn cancel(&self) {
+        let mut state = self.state.lock();
+        state.cancelled = true;
+        state.value = None;
+        state.on_set = None;
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.lock().cancelled
+    }
+
+    /// Hands the value to `on_set` as soon as it's set, on the thread that sets it.
+    fn take_on_set(&self, on_set: impl FnOnce(T) + Send + 'static) {
+        let mut state = self.state.lock();
+        if state.cancelled {
+            return;
+        }
+        match state.value.take() {
+            Some(value) => {
+                drop(state);
+                (on_set)(value);
+            }
+            None => state.on_set = Some(Box::new(on_set)),
+        }
+    }
+}
+
+impl<T: 'static> Later<T> {
+    /// A `Later` that's set to `f` of this one's value. The value is passed on instead of being kept, so this one's
+    /// `value` doesn't return it anymore.
+    pub fn map<U: Send + 'static>(&self, f: impl FnOnce(T) -> U + Send + 'static) -> Later<U> {
+        let mapped = Later::default();
+        let output = mapped.clone();
+        self.take_on_set(move |value| output.set((f)(value)));
+        mapped
+    }
+
+    /// A `Later` that's set to the value of the `Later` returned by `f`, e.g. for loading a file once a dialog has
+    /// picked it.
+    pub fn and_then<U: Send + 'static>(&self, f: impl FnOnce(T) -> Later<U> + Send + 'static) -> Later<U> {
+        let chained = Later::default();
+        let output = chained.clone();
+        self.take_on_set(move |value| (f)(value).take_on_set(move |value| output.set(value)));
+        chained
+    }
+
+    /// Calls `f` on the UI thread at the start of the frame after the value is set. Nothing is called if the `Later`
+    /// is cancelled before that.
+    pub fn on_complete<A: App>(&self, cx: &mut Cx<A>, f: impl FnOnce(&mut Cx<A>, T) + 'static) {
+        let later = self.clone();
+        let mut f = Some(f);
+        cx.later_callbacks.push(Box::new(move |cx| {
+            if later.is_cancelled() {
+                return false;
+            }
+            match later.value() {
+                Some(value) => {
+                    if let Some(f) = f.take() {
+                        (f)(cx, value);
+                    }
+                    false
+                }
+                None => true,
+            }
+        }));
+    }
+}
+
+/// A `Later::on_complete` callback, which returns whether it's still waiting for its value.
+pub(crate) type LaterCallback<A> = Box<dyn FnMut(&mut Cx<A>) -> bool>;
+
+impl<A: App> Cx<A> {
+    /// Runs the `Later::on_complete` callbacks whose values have arrived.
+    pub(crate) fn poll_later_callbacks(&mut self) {
+        let mut callbacks = std::mem::take(&mut self.later_callbacks);
+        callbacks.retain_mut(|callback| (callback)(self));
+        // keep the callbacks that were added by the ones that just ran
+        callbacks.append(&mut self.later_callbacks);
+        self.later_callbacks = callbacks;
+    }
+}
+
+struct MessageChannel<T> {
+    queue: Mutex<VecDeque<T>>,
+    closed: AtomicBool,
+}
+
+/// Sends messages of one type to the `ChannelReceiver` created with it by `Cx::channel`. It can be cloned, and sent to
+/// other threads if `T` can.
+pub struct ChannelSender<T>(Arc<MessageChannel<T>>);
+
+impl<T> Clone for ChannelSender<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> ChannelSender<T> {
+    /// Queues `message` for the receiver, or drops it if the receiver has been dropped.
+    pub fn send(&self, message: T) {
+        if !self.is_closed() {
+            self.0.queue.lock().push_back(message);
+        }
+    }
+
+    /// Whether the receiver has been dropped, so that there's no point in sending anything anymore.
+    pub fn is_closed(&self) -> bool {
+        self.0.closed.load(Ordering::Acquire)
+    }
+}
+
+/// Receives the messages of a `ChannelSender`, usually polled by the component holding it on `Event::Update`.
+pub struct ChannelReceiver<T>(Arc<MessageChannel<T>>);
+
+impl<T> ChannelReceiver<T> {
+    /// The oldest message that hasn't been received yet.
+    pub fn next(&self) -> Option<T> {
+        self.0.queue.lock().pop_front()
+    }
+
+    /// Takes all of the pending messages, oldest first.
+    pub fn drain(&self) -> Vec<T> {
+        self.0.queue.lock().drain(..).collect()
+    }
+}
+
+impl<T> Drop for ChannelReceiver<T> {
+    fn drop(&mut self) {
+        self.0.closed.store(true, Ordering::Release);
+        self.0.queue.lock().clear();
+    }
+}
+
+pub(crate) fn channel<T>() -> (ChannelSender<T>, ChannelReceiver<T>) {
+    let channel = Arc::new(MessageChannel { queue: Mutex::new(VecDeque::new()), closed: AtomicBool::new(false) });
+    (ChannelSender(channel.clone()), ChannelReceiver(channel))
+}
+
+/// The message that a panic was started with, if it was a string as with `panic!` and `unwrap`.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+struct SampleChannel {
+    buffer: Box<[AtomicU32]>,
+    read: AtomicUsize,
+    write: AtomicUsize,
+}
+
+/// Creates a lock-free single producer, single consumer queue for streaming samples out of an audio thread.
+pub fn sample_channel(capacity: usize) -> (SampleSender, SampleReceiver) {
+    let channel = Arc::new(SampleChannel {
+        buffer: (0..capacity + 1).map(|_| AtomicU32::new(0)).collect(),
+        read: AtomicUsize::new(0),
+        write: AtomicUsize::new(0),
+    });
+    (SampleSender(channel.clone()), SampleReceiver(channel))
+}
+
+pub struct SampleSender(Arc<SampleChannel>);
+
+impl SampleSender {
+    /// Pushes as many samples as fit without blocking and returns how many were written.
+    pub fn push(&mut self, samples: &[f32]) -> usize {
+        let channel = &self.0;
+        let len = channel.buffer.len();
+        let read = channel.read.load(Ordering::Acquire);
+        let mut write = channel.write.load(Ordering::Relaxed);
+        let count = samples.len().min((read + len - write - 1) % len);
+        for sample in &samples[..count] {
+            channel.buffer[write].store(sample.to_bits(), Ordering::Relaxed);
+            write = (write + 1) % len;
+        }
+        channel.write.store(write, Ordering::Release);
+        count
+    }
+}
+
+pub struct SampleReceiver(Arc<SampleChannel>);
+
+impl SampleReceiver {
+    /// Calls `f` for each sample pushed since the previous call, oldest first.
+    pub fn drain(&mut self, mut f: impl FnMut(f32)) {
+        let channel = &self.0;
+        let len = channel.buffer.len();
+        let write = channel.write.load(Ordering::Acquire);
+        let mut read = channel.read.load(Ordering::Relaxed);
+        while read != write {
+            f(f32::from_bits(channel.buffer[read].load(Ordering::Relaxed)));
+            read = (read + 1) % len;
+        }
+        channel.read.store(read, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panic_messages_are_read_from_strings() {
+        let payload = std::panic::catch_unwind(|| panic!("index {} out of range", 3)).unwrap_err();
+        assert_eq!(panic_message(&*payload), "index 3 out of range");
+        let payload = std::panic::catch_unwind(|| panic!("static")).unwrap_err();
+        assert_eq!(panic_message(&*payload), "static");
+        let payload = std::panic::catch_unwind(|| std::panic::panic_any(3)).unwrap_err();
+        assert_eq!(panic_message(&*payload), "unknown panic");
+    }
+
+    #[test]
+    fn mapped_and_chained_laters_pass_values_on() {
+        let later = Later::default();
+        let mapped = later.map(|value: i32| value * 2);
+        let inner = Later::default();
+        let chained = {
+            let inner = inner.clone();
+            mapped.and_then(move |value| {
+                inner.set_context(value);
+                inner.clone()
+            })
+        };
+        later.set(21);
+        assert_eq!(later.value(), None);
+        assert_eq!(chained.value(), None);
+        assert_eq!(inner.take_context::<i32>().map(|value| *value), Some(42));
+        inner.set("done");
+        assert_eq!(chained.value(), Some("done"));
+
+        // values that are already set are passed on right away
+        let ready = Later::default();
+        ready.set(1);
+        assert_eq!(ready.map(|value: i32| value + 1).value(), Some(2));
+    }
+
+    #[test]
+    fn cancelled_laters_ignore_values() {
+        let later = Later::default();
+        let mapped = later.map(|value: i32| value);
+        later.cancel();
+        later.set(1);
+        assert!(later.is_cancelled());
+        assert_eq!(later.value(), None);
+        assert_eq!(mapped.value(), None);
+    }
+
+    #[test]
+    fn channels_deliver_messages_in_order() {
+        let (sender, receiver) = channel();
+        let other_sender = sender.clone();
+        sender.send(1);
+        other_sender.send(2);
+        sender.send(3);
+        assert_eq!(receiver.next(), Some(1));
+        assert_eq!(receiver.drain(), [2, 3]);
+        assert_eq!(receiver.next(), None);
+        assert!(!sender.is_closed());
+        drop(receiver);
+        assert!(sender.is_closed());
+    }
+
+    #[test]
+    fn sample_channel_drops_overflow_and_wraps() {
+        let (mut sender, mut receiver) = sample_channel(4);
+        assert_eq!(sender.push(&[1.0, 2.0, 3.0, 4.0, 5.0]), 4);
+
+        let mut out = vec![];
+        receiver.drain(|s| out.push(s));
+        assert_eq!(out, [1.0, 2.0, 3.0, 4.0]);
+
+        assert_eq!(sender.push(&[6.0, 7.0, 8.0]), 3);
+        out.clear();
+        receiver.drain(|s| out.push(s));
+        assert_eq!(out, [6.0, 7.0, 8.0]);
     }
 }
@@ -0,0 +1,93 @@
+use crate::{App, Cx, Point, tree::NodeId};
+
+/// The directions a scroll container can still scroll in. Containers report it with `Cx::set_can_scroll` whenever
+/// their scroll position or content changes.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct CanScroll {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+}
+
+impl CanScroll {
+    /// For content scrolled vertically to `top`, which is kept between `min` and `max`.
+    pub fn vertical(top: f32, min: f32, max: f32) -> Self {
+        Self { up: top > min, down: top < max, ..Default::default() }
+    }
+
+    /// For content scrolled horizontally to `left`, which is kept between `min` and `max`.
+    pub fn horizontal(left: f32, min: f32, max: f32) -> Self {
+        Self { left: left > min, right: left < max, ..Default::default() }
+    }
+
+    /// Whether a wheel event of `delta` pixels would move the content. A positive delta scrolls up or left.
+    pub fn takes(&self, delta: Point) -> bool {
+        (delta.y > 0.0 && self.up)
+            || (delta.y < 0.0 && self.down)
+            || (delta.x > 0.0 && self.left)
+            || (delta.x < 0.0 && self.right)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct ScrollContainer {
+    can_scroll: CanScroll,
+    chains: bool,
+}
+
+impl Default for ScrollContainer {
+    fn default() -> Self {
+        Self { can_scroll: CanScroll::default(), chains: true }
+    }
+}
+
+impl<A: App> Cx<A> {
+    /// Makes `id` a scroll container that wheel events can be routed to. A wheel event goes to the innermost
+    /// container under the pointer, or to the one around it if the inner one can't scroll any further that way.
+    pub fn set_can_scroll(&mut self, id: impl Into<NodeId>, can_scroll: CanScroll) {
+        self.tree.get_mut(id.into()).scroll.get_or_insert_default().can_scroll = can_scroll;
+    }
+
+    /// Whether the wheel events that `id` can't take go on to the scroll container around it. On by default, turn it
+    /// off to keep e.g. a list that was scrolled to its end from scrolling the page it's on.
+    pub fn set_scroll_chaining(&mut self, id: impl Into<NodeId>, chaining: bool) {
+        self.tree.get_mut(id.into()).scroll.get_or_insert_default().chains = chaining;
+    }
+
+    /// Whether the `Event::MouseWheel` being sent is meant for the scroll container `id`. Containers should ignore
+    /// the wheel events of others, so that nested ones don't scroll together.
+    pub fn is_wheel_target(&self, id: impl Into<NodeId>) -> bool {
+        self.wheel_target == Some(id.into())
+    }
+
+    /// Picks the target of a wheel event of `delta` pixels, starting from `innermost`, the scroll container under the
+    /// pointer.
+    pub(crate) fn route_wheel(&mut self, innermost: Option<NodeId>, delta: Point) {
+        self.wheel_target = None;
+        let mut id = innermost;
+        while let Some(current) = id {
+            if let Some(scroll) = self.tree.get(current).scroll
+                && (scroll.can_scroll.takes(delta) || !scroll.chains)
+            {
+                self.wheel_target = Some(current);
+                return;
+            }
+            id = self.tree.get_parent(current);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CanScroll;
+    use crate::point;
+
+    #[test]
+    fn a_list_at_its_end_only_takes_wheel_events_back_up() {
+        let can_scroll = CanScroll::vertical(200.0, 0.0, 200.0);
+        assert!(can_scroll.takes(point(0.0, 40.0)));
+        assert!(!can_scroll.takes(point(0.0, -40.0)));
+        assert!(!can_scroll.takes(point(-40.0, 0.0)));
+    }
+}
@@ -1,70 +1,259 @@
 extern crate proc_macro;
 
 use proc_macro::TokenStream;
-use quote::quote;
+use proc_macro2::Span;
+use quote::{quote, quote_spanned};
 use syn::{
-    Error, Ident, ImplItem, ImplItemFn, ItemImpl, PathArguments, Token, Type,
+    Data, DeriveInput, Error, Expr, Fields, Ident, ImplItem, ImplItemFn, ItemImpl, Member, PathArguments, Token, Type,
+    TypeParamBound,
     parse::{Parse, ParseStream},
+    parse_quote,
     punctuated::Punctuated,
     spanned::Spanned,
 };
 use syn::{LitStr, parse_macro_input};
 
-use std::sync::atomic::{AtomicUsize, Ordering};
-
-static COUNTER: AtomicUsize = AtomicUsize::new(0);
+/// 64-bit FNV-1a, so that ids stay the same across compilations and crates.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
 
+/// A `PropertyId` derived from a hash of the name, so the same name always gives the same id.
 #[proc_macro]
 pub fn property_id(input: TokenStream) -> TokenStream {
     let input_literal = parse_macro_input!(input as LitStr);
 
-    let id = COUNTER.fetch_add(1, Ordering::SeqCst);
+    let hash = fnv1a(input_literal.value().as_bytes());
 
     let expanded = quote! {
-        PropertyId::new(#id, #input_literal)
+        PropertyId::new(#hash as usize, #input_literal)
     };
 
     TokenStream::from(expanded)
 }
 
-enum ChildSpec {
-    Plain(Ident),
-    Opt(Ident),
-    Iter(Ident),
+enum ChildKind {
+    Plain,
+    Opt,
+    Iter,
+    Map,
+}
+
+struct ChildSpec {
+    /// Whether the field holds a `Box<dyn Component<UI>>` (or similar) that has to be dereferenced explicitly.
+    is_dyn: bool,
+    path: Punctuated<Member, Token![.]>,
+    kind: ChildKind,
 }
 
 impl ChildSpec {
-    fn ident(&self) -> &Ident {
-        match self {
-            ChildSpec::Plain(i) | ChildSpec::Opt(i) | ChildSpec::Iter(i) => i,
+    /// Binds each child of the field to `child` and runs `body` for it.
+    fn for_each(&self, ui_ty: &Type, mutable: bool, body: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        let span = self.path.span();
+        let path = &self.path;
+        let body = match (self.is_dyn, mutable) {
+            (false, _) => body,
+            (true, false) => quote_spanned! { span => let child: &dyn Component<#ui_ty> = &**child; #body },
+            (true, true) => quote_spanned! { span => let child: &mut dyn Component<#ui_ty> = &mut **child; #body },
+        };
+        match (&self.kind, mutable) {
+            (ChildKind::Plain, false) => quote_spanned! { span => { let child = &self.#path; #body } },
+            (ChildKind::Plain, true) => quote_spanned! { span => { let child = &mut self.#path; #body } },
+            (ChildKind::Opt, false) => quote_spanned! { span => if let Some(child) = self.#path.as_ref() { #body } },
+            (ChildKind::Opt, true) => quote_spanned! { span => if let Some(child) = self.#path.as_mut() { #body } },
+            (ChildKind::Iter, false) => quote_spanned! { span => for child in (&self.#path).into_iter() { #body } },
+            (ChildKind::Iter, true) => quote_spanned! { span => for child in (&mut self.#path).into_iter() { #body } },
+            (ChildKind::Map, false) => quote_spanned! { span => for child in self.#path.values() { #body } },
+            (ChildKind::Map, true) => quote_spanned! { span => for child in self.#path.values_mut() { #body } },
         }
     }
 }
 
+enum ChildrenArg {
+    Child(ChildSpec),
+    Layout(Ident),
+    Gap(Expr),
+}
+
 struct ChildrenArgs {
     children: Vec<ChildSpec>,
+    /// The `ChildLayout` variant given with `layout = "..."`.
+    layout: Option<Ident>,
+    gap: Option<Expr>,
 }
 
 impl Parse for ChildrenArgs {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let list: Punctuated<ChildSpec, Token![,]> = input.parse_terminated(ChildSpec::parse, Token![,])?;
-        Ok(Self { children: list.into_iter().collect() })
+        let list: Punctuated<ChildrenArg, Token![,]> = input.parse_terminated(ChildrenArg::parse, Token![,])?;
+        let mut args = Self { children: vec![], layout: None, gap: None };
+        for arg in list {
+            match arg {
+                ChildrenArg::Child(child) => args.children.push(child),
+                ChildrenArg::Layout(layout) => args.layout = Some(layout),
+                ChildrenArg::Gap(gap) => args.gap = Some(gap),
+            }
+        }
+        if let (None, Some(gap)) = (&args.layout, &args.gap) {
+            return Err(Error::new(gap.span(), "`gap` needs a `layout`"));
+        }
+        Ok(args)
+    }
+}
+
+impl Parse for ChildrenArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if !(input.peek(Ident) && input.peek2(Token![=])) {
+            return Ok(ChildrenArg::Child(input.parse()?));
+        }
+        let name: Ident = input.parse()?;
+        let _eq: Token![=] = input.parse()?;
+        if name == "layout" {
+            Ok(ChildrenArg::Layout(child_layout_variant(&input.parse()?)?))
+        } else if name == "gap" {
+            Ok(ChildrenArg::Gap(input.parse()?))
+        } else {
+            Err(Error::new(name.span(), "expected `layout` or `gap`"))
+        }
     }
 }
 
 impl Parse for ChildSpec {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let ident: Ident = input.parse()?;
+        let is_dyn = if input.peek(Token![dyn]) {
+            let _dyn: Token![dyn] = input.parse()?;
+            true
+        } else {
+            false
+        };
 
-        if input.peek(Token![?]) {
-            let _q: Token![?] = input.parse()?;
-            return Ok(ChildSpec::Opt(ident));
+        let mut path = Punctuated::new();
+        loop {
+            if !(input.peek(Ident) || input.peek(syn::LitInt)) {
+                return Err(input.error("expected a field, e.g. `button`, `header.button` or `0`"));
+            }
+            path.push_value(input.parse::<Member>()?);
+            if !input.peek(Token![.]) {
+                break;
+            }
+            path.push_punct(input.parse::<Token![.]>()?);
         }
-        if input.peek(Token![*]) {
+
+        let kind = if input.peek(Token![?]) {
+            let _q: Token![?] = input.parse()?;
+            ChildKind::Opt
+        } else if input.peek(Token![*]) {
             let _s: Token![*] = input.parse()?;
-            return Ok(ChildSpec::Iter(ident));
-        }
-        Ok(ChildSpec::Plain(ident))
+            ChildKind::Iter
+        } else if input.peek(Ident) {
+            let ident: Ident = input.parse()?;
+            if ident != "map" || !input.peek(Token![*]) {
+                return Err(Error::new(ident.span(), "expected `?`, `*` or `map*` after the field"));
+            }
+            let _s: Token![*] = input.parse()?;
+            ChildKind::Map
+        } else {
+            ChildKind::Plain
+        };
+
+        Ok(Self { is_dyn, path, kind })
+    }
+}
+
+/// Maps `"row"`, `"column"` or `"stack"` to the `ChildLayout` variant.
+fn child_layout_variant(value: &LitStr) -> syn::Result<Ident> {
+    let variant = match value.value().as_str() {
+        "row" => "Row",
+        "column" => "Column",
+        "stack" => "Stack",
+        _ => return Err(Error::new(value.span(), "expected \"row\", \"column\" or \"stack\"")),
+    };
+    Ok(Ident::new(variant, value.span()))
+}
+
+/// Generates the `Component` methods that go through a component's children.
+struct ChildrenCodegen<'a> {
+    ui_ty: &'a Type,
+    children: &'a [ChildSpec],
+    layout: Option<&'a Ident>,
+    gap: Option<&'a Expr>,
+}
+
+impl ChildrenCodegen<'_> {
+    /// The generated methods by name, leaving out the layout ones if there's no `layout`.
+    fn methods(&self) -> Vec<(&'static str, proc_macro2::TokenStream)> {
+        let ui_ty = self.ui_ty;
+        let draw_stmts = self.children.iter().map(|c| {
+            c.for_each(ui_ty, false, quote_spanned! { c.path.span() => child.draw_instrumented(cx, canvas); })
+        });
+        let event_stmts = self
+            .children
+            .iter()
+            .map(|c| c.for_each(ui_ty, true, quote_spanned! { c.path.span() => child.dispatch_event(cx, event); }));
+        let mut methods = vec![
+            (
+                "draw_children",
+                quote! {
+                    fn draw_children(&self, cx: &mut Cx<#ui_ty>, canvas: &mut Canvas) {
+                        #(#draw_stmts)*
+                    }
+                },
+            ),
+            (
+                "event_children",
+                quote! {
+                    fn event_children(&mut self, cx: &mut Cx<#ui_ty>, event: &mut Event<#ui_ty>) {
+                        #(#event_stmts)*
+                    }
+                },
+            ),
+        ];
+
+        let Some(variant) = self.layout else {
+            return methods;
+        };
+        // the children as a `Vec<&mut dyn Component<UI>>` for `ChildLayout`
+        let collect_children = {
+            let push_stmts = self.children.iter().map(|c| c.for_each(ui_ty, true, quote! { children.push(child); }));
+            quote! {
+                let mut children: Vec<&mut dyn Component<#ui_ty>> = Vec::new();
+                #(#push_stmts)*
+            }
+        };
+        let gap = self.gap.map_or_else(|| quote! { 0.0 }, |gap| quote! { (#gap) as f32 });
+        methods.extend([
+            (
+                "layout_children",
+                quote! {
+                    fn layout_children(&mut self, cx: &mut Cx<#ui_ty>, bounds: Rect) {
+                        #collect_children
+                        ChildLayout::#variant.layout(cx, bounds, #gap, children);
+                    }
+                },
+            ),
+            (
+                "layout",
+                quote! {
+                    fn layout(&mut self, cx: &mut Cx<#ui_ty>, bounds: Rect) {
+                        self.set_bounds(cx, bounds);
+                        self.layout_children(cx, bounds);
+                    }
+                },
+            ),
+            (
+                "get_preferred_size",
+                quote! {
+                    fn get_preferred_size(
+                        &mut self,
+                        cx: &mut Cx<#ui_ty>,
+                        parent_bounds: Rect,
+                    ) -> (Option<f32>, Option<f32>) {
+                        #collect_children
+                        ChildLayout::#variant.preferred_size(cx, parent_bounds, #gap, children)
+                    }
+                },
+            ),
+        ]);
+        methods
     }
 }
 
@@ -108,6 +297,78 @@ fn compile_error(msg: &str) -> TokenStream {
     quote::quote!( compile_error!(#lit); ).into()
 }
 
+/// Implements `draw_children` and `event_children` for the listed fields, unless the impl already has them. Each
+/// entry is a field or a dotted path to one (`header.button`), optionally followed by `?` for an `Option`, `*` for
+/// anything iterable or `map*` for the values of a map. Prefix it with `dyn` for boxed trait objects, e.g.
+/// `#[children(header.button, rows*, dyn panels map*)]`.
+///
+/// With `layout = "row"`, `"column"` or `"stack"` (and optionally `gap = <f32>`), it also implements
+/// `layout_children`, `layout` and `get_preferred_size` with the corresponding `ChildLayout`.
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// use pallo::*;
+/// # struct Leaf {
+/// #     id: ComponentId,
+/// # }
+/// # impl<A: App> Component<A> for Leaf {
+/// #     fn id(&self) -> &ComponentId {
+/// #         &self.id
+/// #     }
+/// #     fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+/// #         self.set_bounds(cx, bounds);
+/// #     }
+/// # }
+///
+/// struct Header {
+///     button: Leaf,
+/// }
+///
+/// struct Panel<A: App> {
+///     id: ComponentId,
+///     header: Header,
+///     footer: Option<Leaf>,
+///     rows: Vec<Leaf>,
+///     tabs: BTreeMap<u32, Box<dyn Component<A>>>,
+/// }
+///
+/// #[children(header.button, footer?, rows*, dyn tabs map*, layout = "column", gap = 4.0)]
+/// impl<A: App> Component<A> for Panel<A> {
+///     fn id(&self) -> &ComponentId {
+///         &self.id
+///     }
+/// }
+/// ```
+///
+/// A `gap` needs a `layout`, which has to be one of the three:
+///
+/// ```compile_fail
+/// # use pallo::*;
+/// # struct Panel {
+/// #     id: ComponentId,
+/// # }
+/// #[children(gap = 4.0)]
+/// impl<A: App> Component<A> for Panel {
+///     fn id(&self) -> &ComponentId {
+///         &self.id
+///     }
+///     fn layout(&mut self, _cx: &mut Cx<A>, _bounds: Rect) {}
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use pallo::*;
+/// # struct Panel {
+/// #     id: ComponentId,
+/// # }
+/// #[children(layout = "grid")]
+/// impl<A: App> Component<A> for Panel {
+///     fn id(&self) -> &ComponentId {
+///         &self.id
+///     }
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn children(attr: TokenStream, item: TokenStream) -> TokenStream {
     let result = std::panic::catch_unwind(|| children_impl(attr, item));
@@ -135,74 +396,218 @@ fn children_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
         Err(e) => return e.to_compile_error().into(),
     };
 
-    let needs_draw = !has_method(&item_impl.items, "draw_children");
-    let needs_event = !has_method(&item_impl.items, "event_children");
-
-    let draw_stmts = args.children.iter().map(|c| {
-        let field = c.ident();
-        match c {
-            ChildSpec::Plain(_) => quote! { self.#field.draw(cx, canvas); },
-            ChildSpec::Opt(_) => quote! {
-                if let Some(child) = self.#field.as_ref() {
-                    child.draw(cx, canvas);
-                }
-            },
-            ChildSpec::Iter(_) => quote! {
-                for child in (&self.#field).into_iter() {
-                    child.draw(cx, canvas);
-                }
-            },
+    let codegen = ChildrenCodegen {
+        ui_ty: &ui_ty,
+        children: &args.children,
+        layout: args.layout.as_ref(),
+        gap: args.gap.as_ref(),
+    };
+    let mut injected: Vec<ImplItem> = Vec::new();
+    for (name, method) in codegen.methods() {
+        if has_method(&item_impl.items, name) {
+            continue;
         }
-    });
-
-    let event_stmts = args.children.iter().map(|c| {
-        let field = c.ident();
-        match c {
-            ChildSpec::Plain(_) => quote! { self.#field.event(cx, event); },
-            ChildSpec::Opt(_) => quote! {
-                if let Some(child) = self.#field.as_mut() {
-                    child.event(cx, event);
-                }
-            },
-            ChildSpec::Iter(_) => quote! {
-                for child in (&mut self.#field).into_iter() {
-                    child.event(cx, event);
-                }
-            },
+        match parse2_or_compile_error::<ImplItemFn>(method) {
+            Ok(f) => injected.push(ImplItem::Fn(f)),
+            Err(ts) => return ts,
         }
-    });
+    }
 
-    let mut injected: Vec<ImplItem> = Vec::new();
+    if !injected.is_empty() {
+        let mut new_items = injected;
+        new_items.extend(item_impl.items.into_iter());
+        item_impl.items = new_items;
+    }
 
-    if needs_draw {
-        let f: ImplItemFn = match parse2_or_compile_error(quote! {
-            fn draw_children(&self, cx: &mut Cx<#ui_ty>, canvas: &mut Canvas) {
-                #(#draw_stmts)*
-            }
-        }) {
-            Ok(f) => f,
-            Err(ts) => return ts,
-        };
-        injected.push(ImplItem::Fn(f));
+    TokenStream::from(quote! { #item_impl })
+}
+
+/// Implements `Component` for a struct with an `id: ComponentId` field, drawing, sending events to and laying out
+/// the fields marked with `#[child]`. `Option`s, `Vec`s, maps and `Box<dyn Component<UI>>`s of children are handled
+/// based on the field's type.
+///
+/// The children are stacked on top of each other unless `#[component(layout = "row")]` or `"column"` says
+/// otherwise, optionally with a `gap = <f32>`. The UI type is taken from `#[component(app = MyApp)]`, a type
+/// parameter bound by `App` or otherwise left generic.
+///
+/// ```
+/// use pallo::*;
+/// # struct Leaf {
+/// #     id: ComponentId,
+/// # }
+/// # impl<A: App> Component<A> for Leaf {
+/// #     fn id(&self) -> &ComponentId {
+/// #         &self.id
+/// #     }
+/// #     fn layout(&mut self, cx: &mut Cx<A>, bounds: Rect) {
+/// #         self.set_bounds(cx, bounds);
+/// #     }
+/// # }
+///
+/// #[derive(Component)]
+/// #[component(layout = "row", gap = 8.0)]
+/// struct Toolbar<A: App> {
+///     id: ComponentId,
+///     #[child]
+///     play: Leaf,
+///     #[child]
+///     record: Option<Leaf>,
+///     #[child]
+///     tools: Vec<Box<dyn Component<A>>>,
+///     label: String,
+/// }
+///
+/// #[derive(Component)]
+/// struct Overlay {
+///     id: ComponentId,
+///     #[child]
+///     content: Leaf,
+/// }
+/// ```
+///
+/// Only structs with an `id` field can derive it, and `#[component]` only takes `layout`, `gap` and `app`:
+///
+/// ```compile_fail
+/// # use pallo::*;
+/// #[derive(Component)]
+/// struct Toolbar {
+///     #[child]
+///     play: Label,
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use pallo::*;
+/// #[derive(Component)]
+/// #[component(gap = 8.0, size = 4)]
+/// struct Toolbar {
+///     id: ComponentId,
+/// }
+/// ```
+#[proc_macro_derive(Component, attributes(child, component))]
+pub fn derive_component(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    derive_component_impl(input).unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+fn derive_component_impl(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new(input.ident.span(), "#[derive(Component)] only supports structs"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new(input.ident.span(), "#[derive(Component)] needs a struct with named fields"));
+    };
+    if !fields.named.iter().any(|f| f.ident.as_ref().is_some_and(|i| i == "id")) {
+        return Err(Error::new(input.ident.span(), "#[derive(Component)] needs an `id: ComponentId` field"));
     }
 
-    if needs_event {
-        let f: ImplItemFn = match parse2_or_compile_error(quote! {
-            fn event_children(&mut self, cx: &mut Cx<#ui_ty>, event: &mut Event<#ui_ty>) {
-                #(#event_stmts)*
+    let mut layout = None;
+    let mut gap = None;
+    let mut app = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("component")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("layout") {
+                layout = Some(child_layout_variant(&meta.value()?.parse()?)?);
+            } else if meta.path.is_ident("gap") {
+                gap = Some(meta.value()?.parse::<Expr>()?);
+            } else if meta.path.is_ident("app") {
+                app = Some(meta.value()?.parse::<Type>()?);
+            } else {
+                return Err(meta.error("expected `layout`, `gap` or `app`"));
             }
-        }) {
-            Ok(f) => f,
-            Err(ts) => return ts,
+            Ok(())
+        })?;
+    }
+
+    let mut children = vec![];
+    for field in &fields.named {
+        let Some(attr) = field.attrs.iter().find(|a| a.path().is_ident("child")) else {
+            continue;
         };
-        injected.push(ImplItem::Fn(f));
+        attr.meta.require_path_only()?;
+        let ident = field.ident.clone().expect("named field");
+        let (kind, is_dyn) = child_kind_of(&field.ty);
+        let mut path = Punctuated::new();
+        path.push(Member::Named(ident));
+        children.push(ChildSpec { is_dyn, path, kind });
     }
 
-    if !injected.is_empty() {
-        let mut new_items = injected;
-        new_items.extend(item_impl.items.into_iter());
-        item_impl.items = new_items;
+    // the UI type, adding a generic one to the impl if the struct doesn't name it
+    let mut impl_generics = input.generics.clone();
+    let ui_ty: Type = if let Some(app) = app {
+        app
+    } else if let Some(param) = input.generics.type_params().find(|p| {
+        p.bounds
+            .iter()
+            .any(|b| matches!(b, TypeParamBound::Trait(t) if t.path.segments.last().is_some_and(|s| s.ident == "App")))
+    }) {
+        let ident = &param.ident;
+        parse_quote!(#ident)
+    } else {
+        impl_generics.params.push(parse_quote!(__A: App));
+        parse_quote!(__A)
+    };
+
+    let stack = Ident::new("Stack", Span::call_site());
+    let codegen = ChildrenCodegen {
+        ui_ty: &ui_ty,
+        children: &children,
+        layout: Some(layout.as_ref().unwrap_or(&stack)),
+        gap: gap.as_ref(),
+    };
+    let methods = codegen.methods().into_iter().map(|(_, method)| method);
+
+    let name = &input.ident;
+    let (impl_generics, _, _) = impl_generics.split_for_impl();
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics Component<#ui_ty> for #name #ty_generics #where_clause {
+            fn id(&self) -> &ComponentId {
+                &self.id
+            }
+
+            #(#methods)*
+        }
+    })
+}
+
+/// How a `#[child]` field holds its children, and whether they're `Box<dyn Component<UI>>`s.
+fn child_kind_of(ty: &Type) -> (ChildKind, bool) {
+    fn last_segment(ty: &Type) -> Option<&syn::PathSegment> {
+        match ty {
+            Type::Path(p) => p.path.segments.last(),
+            _ => None,
+        }
+    }
+    fn type_args(segment: &syn::PathSegment) -> Vec<&Type> {
+        match &segment.arguments {
+            PathArguments::AngleBracketed(ab) => ab
+                .args
+                .iter()
+                .filter_map(|a| match a {
+                    syn::GenericArgument::Type(t) => Some(t),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
+    fn is_boxed_dyn(ty: &Type) -> bool {
+        last_segment(ty)
+            .filter(|s| s.ident == "Box")
+            .is_some_and(|s| type_args(s).first().is_some_and(|t| matches!(t, Type::TraitObject(_))))
     }
 
-    TokenStream::from(quote! { #item_impl })
+    let Some(segment) = last_segment(ty) else {
+        return (ChildKind::Plain, false);
+    };
+    let args = type_args(segment);
+    match segment.ident.to_string().as_str() {
+        "Option" => (ChildKind::Opt, args.first().is_some_and(|t| is_boxed_dyn(t))),
+        "Vec" | "VecDeque" => (ChildKind::Iter, args.first().is_some_and(|t| is_boxed_dyn(t))),
+        "HashMap" | "BTreeMap" | "FxHashMap" | "IndexMap" => {
+            (ChildKind::Map, args.last().is_some_and(|t| is_boxed_dyn(t)))
+        }
+        _ => (ChildKind::Plain, is_boxed_dyn(ty)),
+    }
 }
@@ -1,36 +1,38 @@
 use std::{
     fmt::Debug,
     hash::{DefaultHasher, Hash, Hasher},
+    io::{Read, Seek, SeekFrom},
     path::PathBuf,
     sync::Arc,
 };
 
-#[cfg(target_family = "wasm")]
-use wasm_bindgen::prelude::wasm_bindgen;
-
 use serde::{Deserialize, Serialize};
 
-#[cfg(not(target_family = "wasm"))]
-pub fn log(input: impl Debug) {
-    dbg!(input);
-}
-
-#[cfg(target_family = "wasm")]
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = console, js_name = log)]
-    fn console_log(s: &str);
-}
+mod logger;
+mod recent_files;
+pub use logger::*;
+pub use recent_files::*;
 
-#[cfg(target_family = "wasm")]
+/// Logs the debug representation of `input` at the `Debug` level under this crate's module. Use `debug!` to have the
+/// message filtered by the calling module instead.
 pub fn log(input: impl Debug) {
-    console_log(&format!("{:?}", input));
+    log_args(LogLevel::Debug, module_path!(), format_args!("{input:?}"));
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum File {
     Path(std::path::PathBuf),
-    Data { name: String, data: Arc<Vec<u8>> },
+    Data {
+        name: String,
+        data: Arc<Vec<u8>>,
+    },
+    /// A browser file that is only read when needed, see `read_range_async`.
+    #[cfg(target_family = "wasm")]
+    #[serde(skip)]
+    Blob {
+        name: String,
+        blob: web_sys::Blob,
+    },
 }
 
 impl File {
@@ -42,17 +44,22 @@ impl File {
         Self::Data { name: name.into(), data: data.into() }
     }
 
+    #[cfg(target_family = "wasm")]
+    pub fn from_blob(name: impl Into<String>, blob: web_sys::Blob) -> Self {
+        Self::Blob { name: name.into(), blob }
+    }
+
     pub fn path(&self) -> Option<PathBuf> {
         match self {
             File::Path(path_buf) => Some(path_buf.clone()),
-            File::Data { .. } => None,
+            _ => None,
         }
     }
 
     pub fn extension(&self) -> Option<String> {
         match self {
             File::Path(path) => path.extension().and_then(|e| e.to_str().map(|s| s.to_owned())),
-            File::Data { name, .. } => name.split(".").last().map(|s| s.to_owned()),
+            _ => self.name()?.split(".").last().map(|s| s.to_owned()),
         }
     }
 
@@ -60,6 +67,8 @@ impl File {
         match self {
             File::Path(path) => path.file_name().and_then(|e| e.to_str().map(|s| s.to_owned())),
             File::Data { name, .. } => Some(name.clone()),
+            #[cfg(target_family = "wasm")]
+            File::Blob { name, .. } => Some(name.clone()),
         }
     }
 
@@ -72,6 +81,8 @@ impl File {
             File::Data { data, .. } => {
                 data.hash(&mut hasher);
             }
+            #[cfg(target_family = "wasm")]
+            File::Blob { .. } => return None,
         };
         Some(hasher.finish())
     }
@@ -80,23 +91,90 @@ impl File {
         match self {
             File::Path(path_buf) => std::fs::File::open(path_buf).ok()?.metadata().ok()?.len().into(),
             File::Data { data, .. } => Some(data.len() as u64),
+            #[cfg(target_family = "wasm")]
+            File::Blob { blob, .. } => Some(blob.size() as u64),
         }
     }
 
     pub fn data(&self) -> Option<Arc<Vec<u8>>> {
         match self {
-            // #[cfg(not(target_os = "ios"))]
+            // sandboxed files from an earlier session have to be accessed through `RecentFiles::resolve` first
             File::Path(path_buf) => std::fs::read(path_buf).ok().map(Arc::new),
-            // #[cfg(target_os = "ios")]
-            // File::Path(path) => {
-            //     let url = objc2_foundation::NSURL::from_file_path(path)?;
-            //     dbg!(unsafe { url.startAccessingSecurityScopedResource() });
-            //     dbg!(&path);
-            //     let data = std::fs::read(path).map(Arc::new).unwrap();
-            //     unsafe { url.stopAccessingSecurityScopedResource() };
-            //     data.into()
-            // }
             File::Data { data, .. } => Some(data.clone()),
+            #[cfg(target_family = "wasm")]
+            File::Blob { .. } => None,
+        }
+    }
+
+    /// Reads up to `len` bytes starting at `offset` without loading the rest of the file.
+    /// Returns fewer bytes if the range goes past the end of the file.
+    pub fn read_range(&self, offset: u64, len: usize) -> Option<Vec<u8>> {
+        match self {
+            File::Path(path) => {
+                let mut file = std::fs::File::open(path).ok()?;
+                let available = file.metadata().ok()?.len().saturating_sub(offset);
+                file.seek(SeekFrom::Start(offset)).ok()?;
+                // a huge `len` shouldn't allocate more than the file could ever give
+                let mut data = Vec::with_capacity(len.min(available.try_into().unwrap_or(usize::MAX)));
+                file.take(len as u64).read_to_end(&mut data).ok()?;
+                Some(data)
+            }
+            File::Data { data, .. } => {
+                let start = (offset as usize).min(data.len());
+                let end = start.saturating_add(len).min(data.len());
+                Some(data[start..end].to_vec())
+            }
+            #[cfg(target_family = "wasm")]
+            File::Blob { .. } => None,
+        }
+    }
+
+    /// Like `read_range` but also works for blobs, which can only be read asynchronously.
+    pub fn read_range_async(&self, offset: u64, len: usize, callback: impl FnOnce(Option<Vec<u8>>) + 'static) {
+        match self {
+            #[cfg(target_family = "wasm")]
+            File::Blob { blob, .. } => {
+                let end = offset.saturating_add(len as u64).min(blob.size() as u64);
+                let Ok(slice) = blob.slice_with_f64_and_f64(offset as f64, end as f64) else {
+                    return callback(None);
+                };
+                wasm_bindgen_futures::spawn_local(async move {
+                    let buffer = wasm_bindgen_futures::JsFuture::from(slice.array_buffer()).await;
+                    callback(buffer.ok().map(|buffer| js_sys::Uint8Array::new(&buffer).to_vec()));
+                });
+            }
+            _ => callback(self.read_range(offset, len)),
+        }
+    }
+
+    /// Memory-maps the file so it can be paged through without reading it all. In-memory files are returned as is.
+    #[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+    pub fn map(&self) -> Option<FileMap> {
+        match self {
+            File::Path(path) => {
+                let file = std::fs::File::open(path).ok()?;
+                // safety: the mapping is only valid as long as nothing else truncates the file
+                unsafe { memmap2::Mmap::map(&file) }.ok().map(FileMap::Mapped)
+            }
+            File::Data { data, .. } => Some(FileMap::Data(data.clone())),
+        }
+    }
+}
+
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+pub enum FileMap {
+    Mapped(memmap2::Mmap),
+    Data(Arc<Vec<u8>>),
+}
+
+#[cfg(all(feature = "memmap", not(target_family = "wasm")))]
+impl std::ops::Deref for FileMap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FileMap::Mapped(map) => map,
+            FileMap::Data(data) => data,
         }
     }
 }
@@ -106,3 +184,25 @@ impl From<std::path::PathBuf> for File {
         File::from_path_buf(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_range_clamps_to_end() {
+        let file = File::from_data("test", (0..10).collect());
+        assert_eq!(file.read_range(2, 3), Some(vec![2, 3, 4]));
+        assert_eq!(file.read_range(8, 5), Some(vec![8, 9]));
+        assert_eq!(file.read_range(20, 5), Some(vec![]));
+
+        let path = std::env::temp_dir().join("pallo_util_read_range_test");
+        std::fs::write(&path, (0..10).collect::<Vec<u8>>()).unwrap();
+        let file = File::from_path_buf(path.clone());
+        assert_eq!(file.read_range(2, 3), Some(vec![2, 3, 4]));
+        assert_eq!(file.read_range(8, 5), Some(vec![8, 9]));
+        assert_eq!(file.read_range(8, usize::MAX), Some(vec![8, 9]));
+        assert_eq!(file.read_range(20, usize::MAX), Some(vec![]));
+        std::fs::remove_file(path).unwrap();
+    }
+}
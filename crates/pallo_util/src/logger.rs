@@ -0,0 +1,217 @@
+use std::{
+    collections::VecDeque,
+    fmt::{self, Display},
+    sync::Mutex,
+};
+
+/// How many records `recent_logs` can return.
+const HISTORY_LEN: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LogLevel::Trace => "TRACE",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LogRecord {
+    pub level: LogLevel,
+    pub module: &'static str,
+    pub message: String,
+}
+
+struct Logger {
+    level: LogLevel,
+    module_levels: Vec<(String, LogLevel)>,
+    history: VecDeque<LogRecord>,
+}
+
+impl Logger {
+    const fn new() -> Self {
+        Self {
+            level: if cfg!(debug_assertions) {
+                LogLevel::Debug
+            } else {
+                LogLevel::Info
+            },
+            module_levels: Vec::new(),
+            history: VecDeque::new(),
+        }
+    }
+
+    /// The level of the most specific filter matching `module`.
+    fn level_for(&self, module: &str) -> LogLevel {
+        self.module_levels
+            .iter()
+            .filter(|(prefix, _)| {
+                module == prefix || module.strip_prefix(prefix.as_str()).is_some_and(|rest| rest.starts_with("::"))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.level, |(_, level)| *level)
+    }
+}
+
+static LOGGER: Mutex<Logger> = Mutex::new(Logger::new());
+
+fn with_logger<T>(f: impl FnOnce(&mut Logger) -> T) -> T {
+    f(&mut LOGGER.lock().unwrap_or_else(|poisoned| poisoned.into_inner()))
+}
+
+/// Sets the minimum level for modules that don't have their own filter. Defaults to `Debug` in debug builds and
+/// `Info` otherwise.
+pub fn set_log_level(level: LogLevel) {
+    with_logger(|logger| logger.level = level);
+}
+
+/// Sets the minimum level for `module` and its submodules, e.g. `"pallo::platform"`.
+pub fn set_module_log_level(module: &str, level: LogLevel) {
+    with_logger(|logger| {
+        logger.module_levels.retain(|(prefix, _)| prefix != module);
+        logger.module_levels.push((module.to_string(), level));
+    });
+}
+
+pub fn log_enabled(level: LogLevel, module: &str) -> bool {
+    with_logger(|logger| level >= logger.level_for(module))
+}
+
+/// The last `count` records that passed the filters, oldest first.
+pub fn recent_logs(count: usize) -> Vec<LogRecord> {
+    with_logger(|logger| logger.history.iter().skip(logger.history.len().saturating_sub(count)).cloned().collect())
+}
+
+/// Used by the logging macros.
+#[doc(hidden)]
+pub fn log_args(level: LogLevel, module: &'static str, args: fmt::Arguments) {
+    with_logger(|logger| {
+        if level < logger.level_for(module) {
+            return;
+        }
+        let record = LogRecord { level, module, message: args.to_string() };
+        write_record(&record);
+        if logger.history.len() == HISTORY_LEN {
+            logger.history.pop_front();
+        }
+        logger.history.push_back(record);
+    });
+}
+
+#[cfg(not(any(target_family = "wasm", target_os = "macos", target_os = "ios")))]
+fn write_record(record: &LogRecord) {
+    eprintln!("[{} {}] {}", record.level, record.module, record.message);
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+fn write_record(record: &LogRecord) {
+    use oslog::{Level, OsLog};
+
+    let level = match record.level {
+        LogLevel::Trace => Level::Debug,
+        LogLevel::Debug => Level::Info,
+        LogLevel::Info => Level::Default,
+        LogLevel::Warn => Level::Error,
+        LogLevel::Error => Level::Fault,
+    };
+    OsLog::global().with_level(level, &format!("[{}] {}", record.module, record.message));
+    // os_log doesn't show up when running from a terminal
+    #[cfg(target_os = "macos")]
+    eprintln!("[{} {}] {}", record.level, record.module, record.message);
+}
+
+#[cfg(target_family = "wasm")]
+fn write_record(record: &LogRecord) {
+    let message = format!("[{}] {}", record.module, record.message);
+    match record.level {
+        LogLevel::Trace | LogLevel::Debug => console::debug(&message),
+        LogLevel::Info => console::info(&message),
+        LogLevel::Warn => console::warn(&message),
+        LogLevel::Error => console::error(&message),
+    }
+}
+
+#[cfg(target_family = "wasm")]
+mod console {
+    use wasm_bindgen::prelude::wasm_bindgen;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = console)]
+        pub fn debug(s: &str);
+        #[wasm_bindgen(js_namespace = console)]
+        pub fn info(s: &str);
+        #[wasm_bindgen(js_namespace = console)]
+        pub fn warn(s: &str);
+        #[wasm_bindgen(js_namespace = console)]
+        pub fn error(s: &str);
+    }
+}
+
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)+) => {
+        $crate::log_args($crate::LogLevel::Trace, module_path!(), format_args!($($arg)+))
+    };
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        $crate::log_args($crate::LogLevel::Debug, module_path!(), format_args!($($arg)+))
+    };
+}
+
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => {
+        $crate::log_args($crate::LogLevel::Info, module_path!(), format_args!($($arg)+))
+    };
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)+) => {
+        $crate::log_args($crate::LogLevel::Warn, module_path!(), format_args!($($arg)+))
+    };
+}
+
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)+) => {
+        $crate::log_args($crate::LogLevel::Error, module_path!(), format_args!($($arg)+))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_specific_module_filter_wins() {
+        let mut logger = Logger::new();
+        logger.level = LogLevel::Warn;
+        logger.module_levels.push(("app".into(), LogLevel::Info));
+        logger.module_levels.push(("app::audio".into(), LogLevel::Trace));
+
+        assert_eq!(logger.level_for("other"), LogLevel::Warn);
+        assert_eq!(logger.level_for("app"), LogLevel::Info);
+        assert_eq!(logger.level_for("app::ui"), LogLevel::Info);
+        assert_eq!(logger.level_for("app::audio::engine"), LogLevel::Trace);
+        // a shared prefix isn't a parent module
+        assert_eq!(logger.level_for("application"), LogLevel::Warn);
+    }
+}
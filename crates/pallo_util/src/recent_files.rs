@@ -0,0 +1,164 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::File;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Entry {
+    path: PathBuf,
+    /// A security-scoped bookmark on Apple platforms, which keeps working in the sandbox after a restart.
+    bookmark: Option<Vec<u8>>,
+}
+
+/// A list of recently opened files, newest first. Serialize it with the rest of the app's settings and call
+/// `resolve` after loading to regain access to the files.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecentFiles {
+    entries: Vec<Entry>,
+    max_len: usize,
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    #[serde(skip)]
+    accessed: Vec<objc2::rc::Retained<objc2_foundation::NSURL>>,
+}
+
+impl Default for RecentFiles {
+    fn default() -> Self {
+        Self::new(10)
+    }
+}
+
+impl RecentFiles {
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            entries: vec![],
+            max_len,
+            #[cfg(any(target_os = "macos", target_os = "ios"))]
+            accessed: vec![],
+        }
+    }
+
+    /// Moves `file` to the top of the list. Files without a path are ignored.
+    pub fn add(&mut self, file: &File) {
+        let Some(path) = file.path() else {
+            return;
+        };
+        self.remove(&path);
+        let bookmark = create_bookmark(&path);
+        self.entries.insert(0, Entry { path, bookmark });
+        self.entries.truncate(self.max_len);
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        self.entries.retain(|entry| entry.path != path);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.entries.iter().map(|entry| entry.path.as_path())
+    }
+
+    /// Resolves the bookmarks and starts accessing the files, which lasts until this is dropped.
+    /// Files that have moved get their new path and the ones that can't be found anymore are removed.
+    pub fn resolve(&mut self) -> Vec<File> {
+        let mut entries = std::mem::take(&mut self.entries);
+        entries.retain_mut(|entry| self.resolve_entry(entry));
+        self.entries = entries;
+        self.entries.iter().map(|entry| File::from_path_buf(entry.path.clone())).collect()
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    fn resolve_entry(&mut self, entry: &mut Entry) -> bool {
+        let Some(bookmark) = &entry.bookmark else {
+            return entry.path.exists();
+        };
+        let Some((url, is_stale)) = apple::resolve_bookmark(bookmark) else {
+            return false;
+        };
+        let Some(path) = url.to_file_path() else {
+            return false;
+        };
+        if unsafe { url.startAccessingSecurityScopedResource() } {
+            self.accessed.push(url);
+        }
+        if is_stale {
+            entry.bookmark = create_bookmark(&path).or(entry.bookmark.take());
+        }
+        entry.path = path;
+        true
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "ios")))]
+    fn resolve_entry(&mut self, entry: &mut Entry) -> bool {
+        entry.path.exists()
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+impl Drop for RecentFiles {
+    fn drop(&mut self) {
+        for url in &self.accessed {
+            unsafe { url.stopAccessingSecurityScopedResource() };
+        }
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+fn create_bookmark(path: &Path) -> Option<Vec<u8>> {
+    apple::create_bookmark(path)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "ios")))]
+fn create_bookmark(_path: &Path) -> Option<Vec<u8>> {
+    None
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+mod apple {
+    use std::path::Path;
+
+    use objc2::{rc::Retained, runtime::Bool};
+    use objc2_foundation::{NSData, NSURL, NSURLBookmarkCreationOptions, NSURLBookmarkResolutionOptions};
+
+    // security scopes are implicit on iOS
+    #[cfg(target_os = "macos")]
+    const CREATION_OPTIONS: NSURLBookmarkCreationOptions = NSURLBookmarkCreationOptions::WithSecurityScope;
+    #[cfg(target_os = "ios")]
+    const CREATION_OPTIONS: NSURLBookmarkCreationOptions = NSURLBookmarkCreationOptions::empty();
+    #[cfg(target_os = "macos")]
+    const RESOLUTION_OPTIONS: NSURLBookmarkResolutionOptions = NSURLBookmarkResolutionOptions::WithSecurityScope;
+    #[cfg(target_os = "ios")]
+    const RESOLUTION_OPTIONS: NSURLBookmarkResolutionOptions = NSURLBookmarkResolutionOptions::empty();
+
+    pub(super) fn create_bookmark(path: &Path) -> Option<Vec<u8>> {
+        let url = NSURL::from_file_path(path)?;
+        // files from a document picker can only be bookmarked while they're being accessed
+        let is_accessing = unsafe { url.startAccessingSecurityScopedResource() };
+        let bookmark = url
+            .bookmarkDataWithOptions_includingResourceValuesForKeys_relativeToURL_error(CREATION_OPTIONS, None, None)
+            .ok()
+            .map(|data| data.to_vec());
+        if is_accessing {
+            unsafe { url.stopAccessingSecurityScopedResource() };
+        }
+        bookmark
+    }
+
+    /// Returns the resolved url and whether the bookmark should be recreated.
+    pub(super) fn resolve_bookmark(bookmark: &[u8]) -> Option<(Retained<NSURL>, bool)> {
+        let mut is_stale = Bool::NO;
+        let url = unsafe {
+            NSURL::URLByResolvingBookmarkData_options_relativeToURL_bookmarkDataIsStale_error(
+                &NSData::with_bytes(bookmark),
+                RESOLUTION_OPTIONS,
+                None,
+                &mut is_stale,
+            )
+        }
+        .ok()?;
+        Some((url, is_stale.as_bool()))
+    }
+}